@@ -0,0 +1,674 @@
+// Agent Loop
+// ==========
+//
+// `run_agent` runs a tool-using conversation to its end in the backend, so
+// the frontend renders the trace instead of driving every round trip over
+// IPC. Each step streams a chat completion through `chat_proxy`; when the
+// model asks for tools, the calls go to the running MCP servers that offer
+// them (see `mcp::client`), their results are appended, and the next step
+// starts. The run ends when the model answers without tool calls, after
+// `max_steps`, on an error, or when it is cancelled.
+//
+// Every call is checked against the tool policy (see `mcp::policy`): it
+// runs, waits for `approve_agent_tool_call`, or is refused, and a refusal
+// is reported to the model as the tool's result. The trace is announced as
+// it happens:
+//
+// - `agent-step`: a step started; its text streams as the usual `llm-*`
+//   events under the step's `requestId`
+// - `agent-tool-call`: a tool call changed state (awaiting approval,
+//   running, succeeded, failed or denied)
+// - `agent-done`: the run ended, with the final answer
+//
+// Both OpenAI-compatible and Anthropic APIs are supported; the messages
+// carrying tool calls and results are written in the API's own format.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+use crate::chat_proxy::{ChatResponse, ProviderApi, ProviderConfig, ToolCallDelta};
+use crate::mcp::client;
+use crate::mcp::policy::{self, ToolPermission};
+
+const DEFAULT_MAX_STEPS: u32 = 10;
+const MAX_STEPS: u32 = 50;
+const MAX_RUN_ID_LENGTH: usize = 80;
+const LIST_TOOLS_TIMEOUT: Duration = Duration::from_secs(15);
+const TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(120);
+/// Longest tool result passed back to the model
+const MAX_RESULT_CHARS: usize = 100_000;
+
+/// Runs in progress, managed as app state.
+#[derive(Default)]
+pub struct AgentRuns {
+    runs: Mutex<HashMap<String, Arc<AgentRun>>>,
+}
+
+#[derive(Default)]
+struct AgentRun {
+    cancelled: AtomicBool,
+    /// Request id of the step streaming right now
+    streaming: Mutex<Option<String>>,
+    /// Calls waiting for approval, by call id
+    approvals: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+/// Takes a run out of the table when it ends.
+struct Registration<'a> {
+    runs: &'a AgentRuns,
+    run_id: String,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut runs) = self.runs.runs.lock() {
+            runs.remove(&self.run_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentRunStatus {
+    Completed,
+    /// The model still wanted tools after `max_steps`
+    MaxSteps,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCallStatus {
+    AwaitingApproval,
+    Running,
+    Succeeded,
+    /// The tool ran and reported an error, or couldn't be reached
+    Failed,
+    /// Refused by the policy or the user
+    Denied,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentStepEvent {
+    pub run_id: String,
+    pub step: u32,
+    /// Id of the step's `llm-*` events
+    pub request_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentToolCallEvent {
+    pub run_id: String,
+    pub step: u32,
+    pub call_id: String,
+    pub name: String,
+    /// `None` if no running server offers the tool
+    pub server_id: Option<String>,
+    pub arguments: Value,
+    pub destructive: bool,
+    pub status: ToolCallStatus,
+    /// The result text, once there is one
+    pub result: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentDoneEvent {
+    pub run_id: String,
+    pub status: AgentRunStatus,
+    pub steps: u32,
+    /// Text of the last step
+    pub text: String,
+    pub error: Option<String>,
+    /// The conversation with every step appended, to continue it later
+    pub messages: Value,
+}
+
+/// A tool offered to the model and the server it runs on.
+struct Tool {
+    server_id: String,
+    definition: Value,
+}
+
+/// A tool call assembled from its streamed deltas.
+struct ToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+fn emit<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) {
+    if let Err(e) = app.emit(event, payload) {
+        log::warn!("Failed to emit {event} event: {e}");
+    }
+}
+
+/// Tools of `server_ids`, or of every running server, by name. When two
+/// servers offer the same name, the first in order keeps it.
+async fn collect_tools(
+    app: &AppHandle,
+    server_ids: Option<Vec<String>>,
+) -> Result<HashMap<String, Tool>, String> {
+    let server_ids = server_ids.unwrap_or_else(|| crate::mcp::running_server_ids(app));
+    let mut tools: HashMap<String, Tool> = HashMap::new();
+    for server_id in server_ids {
+        let listed = client::list_tools(app, &server_id, LIST_TOOLS_TIMEOUT).await?;
+        for definition in listed {
+            let Some(name) = definition["name"].as_str().map(String::from) else {
+                continue;
+            };
+            if let Some(existing) = tools.get(&name) {
+                log::warn!(
+                    "Tool {name} of MCP server {server_id} is hidden by the one of {}",
+                    existing.server_id
+                );
+                continue;
+            }
+            tools.insert(
+                name,
+                Tool {
+                    server_id: server_id.clone(),
+                    definition,
+                },
+            );
+        }
+    }
+    Ok(tools)
+}
+
+/// The tools in the API's request format, sorted by name.
+fn tool_definitions(api: ProviderApi, tools: &HashMap<String, Tool>) -> Vec<Value> {
+    let mut names: Vec<&String> = tools.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let definition = &tools[name].definition;
+            let description = definition["description"].as_str().unwrap_or_default();
+            let schema = match &definition["inputSchema"] {
+                Value::Object(schema) => Value::Object(schema.clone()),
+                _ => json!({ "type": "object", "properties": {} }),
+            };
+            match api {
+                ProviderApi::Openai => json!({
+                    "type": "function",
+                    "function": { "name": name, "description": description, "parameters": schema }
+                }),
+                ProviderApi::Anthropic => json!({
+                    "name": name,
+                    "description": description,
+                    "input_schema": schema
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Tool calls made of the streamed deltas, in order.
+fn assemble_tool_calls(deltas: &[ToolCallDelta]) -> Vec<ToolCall> {
+    let mut calls: Vec<(u64, ToolCall)> = Vec::new();
+    for delta in deltas {
+        let position = match calls.iter().position(|(index, _)| *index == delta.index) {
+            Some(position) => position,
+            None => {
+                calls.push((
+                    delta.index,
+                    ToolCall {
+                        id: String::new(),
+                        name: String::new(),
+                        arguments: String::new(),
+                    },
+                ));
+                calls.len() - 1
+            }
+        };
+        let call = &mut calls[position].1;
+        if let Some(id) = delta.id.as_ref().filter(|_| call.id.is_empty()) {
+            call.id = id.clone();
+        }
+        if let Some(name) = delta.name.as_ref().filter(|_| call.name.is_empty()) {
+            call.name = name.clone();
+        }
+        if let Some(arguments) = &delta.arguments {
+            call.arguments.push_str(arguments);
+        }
+    }
+    calls.into_iter().map(|(_, call)| call).collect()
+}
+
+/// The assistant message asking for `calls`, in the API's format.
+fn assistant_message(api: ProviderApi, text: &str, calls: &[(ToolCall, Value)]) -> Value {
+    match api {
+        ProviderApi::Openai => json!({
+            "role": "assistant",
+            "content": if text.is_empty() { Value::Null } else { json!(text) },
+            "tool_calls": calls.iter().map(|(call, _)| json!({
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments }
+            })).collect::<Vec<_>>()
+        }),
+        ProviderApi::Anthropic => {
+            let mut content = Vec::new();
+            if !text.is_empty() {
+                content.push(json!({ "type": "text", "text": text }));
+            }
+            content.extend(calls.iter().map(|(call, arguments)| {
+                json!({ "type": "tool_use", "id": call.id, "name": call.name, "input": arguments })
+            }));
+            json!({ "role": "assistant", "content": content })
+        }
+    }
+}
+
+/// The messages carrying the results, in the API's format: one per result
+/// for OpenAI, a single user message for Anthropic.
+fn result_messages(api: ProviderApi, results: &[(String, String, bool)]) -> Vec<Value> {
+    match api {
+        ProviderApi::Openai => results
+            .iter()
+            .map(|(call_id, text, _)| json!({ "role": "tool", "tool_call_id": call_id, "content": text }))
+            .collect(),
+        ProviderApi::Anthropic => vec![json!({
+            "role": "user",
+            "content": results.iter().map(|(call_id, text, is_error)| json!({
+                "type": "tool_result",
+                "tool_use_id": call_id,
+                "content": text,
+                "is_error": is_error
+            })).collect::<Vec<_>>()
+        })],
+    }
+}
+
+/// The text of a `tools/call` result: text content as is, anything else
+/// as JSON.
+fn result_text(result: &Value) -> String {
+    let text = result["content"]
+        .as_array()
+        .map(|content| {
+            content
+                .iter()
+                .map(|item| match item["text"].as_str() {
+                    Some(text) => text.to_string(),
+                    None => item.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    if text.chars().count() > MAX_RESULT_CHARS {
+        let cut: String = text.chars().take(MAX_RESULT_CHARS).collect();
+        format!("{cut}\n[truncated]")
+    } else {
+        text
+    }
+}
+
+impl AgentRun {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Waits for the user's answer; false when the run is cancelled first.
+    async fn approval(&self, call_id: &str) -> bool {
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut approvals) = self.approvals.lock() {
+            approvals.insert(call_id.to_string(), sender);
+        }
+        if self.is_cancelled() {
+            return false;
+        }
+        receiver.await.unwrap_or(false)
+    }
+}
+
+/// Runs one tool call through the policy and the server. Returns the
+/// result text and whether it is an error.
+async fn dispatch(
+    app: &AppHandle,
+    run: &AgentRun,
+    event: &mut AgentToolCallEvent,
+    tool: Option<&Tool>,
+    tool_policy: &policy::ToolPolicy,
+) -> (String, bool) {
+    let Some(tool) = tool else {
+        event.status = ToolCallStatus::Failed;
+        return (
+            format!(
+                "Tool {} is not offered by any running MCP server",
+                event.name
+            ),
+            true,
+        );
+    };
+    let allowed = match tool_policy.permission(&tool.server_id, &tool.definition) {
+        ToolPermission::Allow => true,
+        ToolPermission::Deny => false,
+        ToolPermission::Ask => {
+            event.status = ToolCallStatus::AwaitingApproval;
+            emit(app, "agent-tool-call", event.clone());
+            run.approval(&event.call_id).await
+        }
+    };
+    if !allowed {
+        event.status = ToolCallStatus::Denied;
+        return (
+            format!("Permission to call {} was denied", event.name),
+            true,
+        );
+    }
+
+    event.status = ToolCallStatus::Running;
+    emit(app, "agent-tool-call", event.clone());
+    let started = Instant::now();
+    let result = client::call_tool(
+        app,
+        &tool.server_id,
+        &event.name,
+        event.arguments.clone(),
+        TOOL_CALL_TIMEOUT,
+    )
+    .await;
+    event.duration_ms = Some(started.elapsed().as_millis() as u64);
+    match result {
+        Ok(result) => {
+            let is_error = result["isError"].as_bool() == Some(true);
+            event.status = if is_error {
+                ToolCallStatus::Failed
+            } else {
+                ToolCallStatus::Succeeded
+            };
+            (result_text(&result), is_error)
+        }
+        Err(e) => {
+            event.status = ToolCallStatus::Failed;
+            (e, true)
+        }
+    }
+}
+
+/// The loop itself; returns how it ended, the steps taken and the last
+/// text.
+#[allow(clippy::too_many_arguments)]
+async fn run_steps(
+    app: &AppHandle,
+    run: &AgentRun,
+    run_id: &str,
+    provider_config: Option<ProviderConfig>,
+    provider_id: Option<String>,
+    api: ProviderApi,
+    mut request: Map<String, Value>,
+    tools: &HashMap<String, Tool>,
+    max_steps: u32,
+) -> (AgentRunStatus, u32, String, Result<(), String>, Value) {
+    let tool_policy = match policy::load(app) {
+        Ok(tool_policy) => tool_policy,
+        Err(e) => {
+            let messages = request.remove("messages").unwrap_or_default();
+            return (AgentRunStatus::Failed, 0, String::new(), Err(e), messages);
+        }
+    };
+    let mut text = String::new();
+    for step in 1..=max_steps {
+        if run.is_cancelled() {
+            let messages = request.remove("messages").unwrap_or_default();
+            return (AgentRunStatus::Cancelled, step - 1, text, Ok(()), messages);
+        }
+        let request_id = format!("{run_id}-{step}");
+        if let Ok(mut streaming) = run.streaming.lock() {
+            *streaming = Some(request_id.clone());
+        }
+        emit(
+            app,
+            "agent-step",
+            AgentStepEvent {
+                run_id: run_id.to_string(),
+                step,
+                request_id: request_id.clone(),
+            },
+        );
+        let response = crate::chat_proxy::complete(
+            app,
+            request_id,
+            provider_config.clone(),
+            provider_id.clone(),
+            Value::Object(request.clone()),
+        )
+        .await;
+        let ChatResponse {
+            text: step_text,
+            tool_calls,
+            ..
+        } = match response {
+            Ok(Some(response)) => response,
+            Ok(None) => {
+                let messages = request.remove("messages").unwrap_or_default();
+                return (AgentRunStatus::Cancelled, step, text, Ok(()), messages);
+            }
+            Err(e) => {
+                let messages = request.remove("messages").unwrap_or_default();
+                return (AgentRunStatus::Failed, step, text, Err(e), messages);
+            }
+        };
+        text = step_text;
+        let calls = assemble_tool_calls(&tool_calls);
+        if calls.is_empty() {
+            let mut messages = request.remove("messages").unwrap_or_default();
+            if let Some(messages) = messages.as_array_mut() {
+                messages.push(json!({ "role": "assistant", "content": text }));
+            }
+            return (AgentRunStatus::Completed, step, text, Ok(()), messages);
+        }
+
+        let calls: Vec<(ToolCall, Value)> = calls
+            .into_iter()
+            .map(|call| {
+                let arguments = if call.arguments.trim().is_empty() {
+                    Ok(json!({}))
+                } else {
+                    serde_json::from_str::<Value>(&call.arguments)
+                };
+                (call, arguments.unwrap_or(Value::Null))
+            })
+            .collect();
+        let mut results = Vec::with_capacity(calls.len());
+        for (call, arguments) in &calls {
+            let tool = tools.get(&call.name);
+            let mut event = AgentToolCallEvent {
+                run_id: run_id.to_string(),
+                step,
+                call_id: call.id.clone(),
+                name: call.name.clone(),
+                server_id: tool.map(|tool| tool.server_id.clone()),
+                arguments: arguments.clone(),
+                destructive: tool.is_some_and(|tool| policy::is_destructive(&tool.definition)),
+                status: ToolCallStatus::Running,
+                result: None,
+                duration_ms: None,
+            };
+            let (result, is_error) = if arguments.is_null() {
+                event.status = ToolCallStatus::Failed;
+                (
+                    format!("The arguments for {} are not valid JSON", call.name),
+                    true,
+                )
+            } else if run.is_cancelled() {
+                event.status = ToolCallStatus::Denied;
+                ("The run was cancelled".to_string(), true)
+            } else {
+                dispatch(app, run, &mut event, tool, &tool_policy).await
+            };
+            log::info!(
+                "Agent run {run_id} step {step}: {} {:?}",
+                call.name,
+                event.status
+            );
+            event.result = Some(result.clone());
+            emit(app, "agent-tool-call", event);
+            results.push((call.id.clone(), result, is_error));
+        }
+
+        let messages = request
+            .entry("messages")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Some(messages) = messages.as_array_mut() {
+            messages.push(assistant_message(api, &text, &calls));
+            messages.extend(result_messages(api, &results));
+        }
+    }
+    let messages = request.remove("messages").unwrap_or_default();
+    (AgentRunStatus::MaxSteps, max_steps, text, Ok(()), messages)
+}
+
+/// Runs a chat with the tools of `server_ids`, or of every running MCP
+/// server, until the model answers without calling one. `request` is the
+/// provider's chat completions or messages body; tools it lists already
+/// are kept, but only MCP tools can be called. The API is given in full by
+/// `provider_config` or is the registered provider `provider_id`. Resolves
+/// with the same summary `agent-done` carries.
+#[tauri::command]
+pub async fn run_agent(
+    app: AppHandle,
+    run_id: String,
+    provider_config: Option<ProviderConfig>,
+    provider_id: Option<String>,
+    request: Value,
+    server_ids: Option<Vec<String>>,
+    max_steps: Option<u32>,
+) -> Result<AgentDoneEvent, String> {
+    crate::validate_string_input(&run_id, MAX_RUN_ID_LENGTH, "Run id")?;
+    let max_steps = max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+    if max_steps == 0 || max_steps > MAX_STEPS {
+        return Err(format!("Steps must be between 1 and {MAX_STEPS}"));
+    }
+    let Value::Object(mut request) = request else {
+        return Err("The request must be a JSON object".to_string());
+    };
+    let api = match (&provider_config, &provider_id) {
+        (Some(config), None) => config.api,
+        (None, Some(id)) => crate::providers::config(&app, id)?.api,
+        _ => return Err("Pass either a provider configuration or a provider id".to_string()),
+    };
+
+    let runs = app.state::<AgentRuns>();
+    let run = Arc::new(AgentRun::default());
+    {
+        let mut table = runs.runs.lock().map_err(|e| e.to_string())?;
+        if table.contains_key(&run_id) {
+            return Err(format!("Agent run {run_id} is already running"));
+        }
+        table.insert(run_id.clone(), run.clone());
+    }
+    let _registration = Registration {
+        runs: runs.inner(),
+        run_id: run_id.clone(),
+    };
+
+    let tools = collect_tools(&app, server_ids).await?;
+    let mut definitions = request
+        .get("tools")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    definitions.extend(tool_definitions(api, &tools));
+    if !definitions.is_empty() {
+        request.insert("tools".to_string(), Value::Array(definitions));
+    }
+    log::info!(
+        "Starting agent run {run_id} with {} tools, up to {max_steps} steps",
+        tools.len()
+    );
+
+    let (status, steps, text, result, messages) = run_steps(
+        &app,
+        &run,
+        &run_id,
+        provider_config,
+        provider_id,
+        api,
+        request,
+        &tools,
+        max_steps,
+    )
+    .await;
+    log::info!("Agent run {run_id} ended after {steps} steps: {status:?}");
+    let done = AgentDoneEvent {
+        run_id,
+        status,
+        steps,
+        text,
+        error: result.err(),
+        messages,
+    };
+    emit(&app, "agent-done", done.clone());
+    Ok(done)
+}
+
+/// Answers a tool call awaiting approval. Returns false if no such call is
+/// waiting.
+#[tauri::command]
+pub async fn approve_agent_tool_call(
+    app: AppHandle,
+    run_id: String,
+    call_id: String,
+    approved: bool,
+) -> Result<bool, String> {
+    let run = app
+        .state::<AgentRuns>()
+        .runs
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&run_id)
+        .cloned();
+    let sender = run.and_then(|run| run.approvals.lock().ok()?.remove(&call_id));
+    match sender {
+        Some(sender) => {
+            log::info!(
+                "Tool call {call_id} of agent run {run_id} {}",
+                if approved { "approved" } else { "denied" }
+            );
+            Ok(sender.send(approved).is_ok())
+        }
+        None => Ok(false),
+    }
+}
+
+/// Stops a run: the streaming step is cancelled, calls awaiting approval
+/// are denied, and the run ends as cancelled. Returns false if no such run
+/// is in progress.
+#[tauri::command]
+pub async fn cancel_agent_run(app: AppHandle, run_id: String) -> Result<bool, String> {
+    let run = app
+        .state::<AgentRuns>()
+        .runs
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&run_id)
+        .cloned();
+    let Some(run) = run else {
+        return Ok(false);
+    };
+    log::info!("Cancelling agent run {run_id}");
+    run.cancelled.store(true, Ordering::Relaxed);
+    if let Ok(mut approvals) = run.approvals.lock() {
+        for (_, sender) in approvals.drain() {
+            let _ = sender.send(false);
+        }
+    }
+    let streaming = run.streaming.lock().ok().and_then(|s| s.clone());
+    if let Some(request_id) = streaming {
+        crate::chat_proxy::cancel_llm_request(app, request_id).await?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,521 @@
+// Image Annotations
+// =================
+//
+// Marks the user puts on an image attachment before sending it to a vision
+// model: a crop, arrows, ellipses and boxes around what matters, and blurred
+// regions for anything that should not leave the machine. They are stored
+// per attachment in `attachment_annotations`, so they can be edited again
+// later, and `render_annotated_image` bakes them into a new PNG attachment
+// for the message to refer to. The original attachment is left as it was.
+//
+// Positions and sizes are fractions of the original image's width and
+// height, so they don't depend on the size the image is shown at. Blurs are
+// applied first and the crop last, whatever their order in the list; the
+// other marks are drawn in order.
+
+use base64::Engine;
+use image::{imageops, ImageError, ImageFormat, ImageReader, Limits, Pixel, Rgba, RgbaImage};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use tauri::AppHandle;
+
+use crate::attachments::StoredAttachment;
+
+const MAX_ANNOTATIONS: usize = 200;
+/// Images that take more memory decoded are refused
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+const DEFAULT_COLOR: Rgba<u8> = Rgba([255, 59, 48, 255]);
+/// Stroke width as a fraction of the image's shorter side
+const DEFAULT_STROKE: f64 = 0.006;
+const MAX_STROKE: f64 = 0.1;
+/// Blur strength as a fraction of the blurred region's shorter side
+const BLUR_STRENGTH: f64 = 0.15;
+const MIN_BLUR_SIGMA: f32 = 4.0;
+/// Arrowhead length in stroke widths
+const ARROWHEAD_LENGTH: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    /// Keeps only this part of the image
+    Crop { rect: Rect },
+    /// A line with an arrowhead at `to`
+    Arrow {
+        from: Point,
+        to: Point,
+        /// `#rrggbb` or `#rrggbbaa`; red by default
+        #[serde(default)]
+        color: Option<String>,
+        /// Fraction of the image's shorter side
+        #[serde(default)]
+        stroke: Option<f64>,
+    },
+    /// The outline of the ellipse inside `rect`
+    Ellipse {
+        rect: Rect,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        stroke: Option<f64>,
+    },
+    /// The outline of `rect`
+    Rectangle {
+        rect: Rect,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        stroke: Option<f64>,
+    },
+    /// Blurs the region beyond recognition
+    Blur { rect: Rect },
+}
+
+fn db_error(action: &str) -> impl Fn(rusqlite::Error) -> String + '_ {
+    move |e| {
+        log::error!("Failed to {action}: {e}");
+        format!("Failed to {action}: {e}")
+    }
+}
+
+fn parse_color(color: Option<&str>) -> Result<Rgba<u8>, String> {
+    let Some(color) = color else {
+        return Ok(DEFAULT_COLOR);
+    };
+    let hex = color
+        .strip_prefix('#')
+        .filter(|hex| matches!(hex.len(), 6 | 8) && hex.is_ascii())
+        .ok_or_else(|| format!("Invalid color: {color}"))?;
+    let mut channels = [255u8; 4];
+    for (i, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid color: {color}"))?;
+    }
+    Ok(Rgba(channels))
+}
+
+fn validate_point(point: &Point) -> Result<(), String> {
+    if (0.0..=1.0).contains(&point.x) && (0.0..=1.0).contains(&point.y) {
+        Ok(())
+    } else {
+        Err("Annotation points must lie within the image".to_string())
+    }
+}
+
+fn validate_rect(rect: &Rect) -> Result<(), String> {
+    let within = rect.x >= 0.0
+        && rect.y >= 0.0
+        && rect.width > 0.0
+        && rect.height > 0.0
+        && rect.x + rect.width <= 1.0 + f64::EPSILON
+        && rect.y + rect.height <= 1.0 + f64::EPSILON;
+    if within {
+        Ok(())
+    } else {
+        Err("Annotation regions must lie within the image".to_string())
+    }
+}
+
+fn validate_style(color: &Option<String>, stroke: &Option<f64>) -> Result<(), String> {
+    parse_color(color.as_deref())?;
+    if stroke.is_some_and(|stroke| !(stroke > 0.0 && stroke <= MAX_STROKE)) {
+        return Err(format!("Stroke width must be between 0 and {MAX_STROKE}"));
+    }
+    Ok(())
+}
+
+fn validate(annotations: &[Annotation]) -> Result<(), String> {
+    if annotations.len() > MAX_ANNOTATIONS {
+        return Err(format!("Too many annotations (max {MAX_ANNOTATIONS})"));
+    }
+    let mut crops = 0;
+    for annotation in annotations {
+        match annotation {
+            Annotation::Crop { rect } => {
+                crops += 1;
+                validate_rect(rect)?;
+            }
+            Annotation::Arrow {
+                from,
+                to,
+                color,
+                stroke,
+            } => {
+                validate_point(from)?;
+                validate_point(to)?;
+                validate_style(color, stroke)?;
+            }
+            Annotation::Ellipse {
+                rect,
+                color,
+                stroke,
+            }
+            | Annotation::Rectangle {
+                rect,
+                color,
+                stroke,
+            } => {
+                validate_rect(rect)?;
+                validate_style(color, stroke)?;
+            }
+            Annotation::Blur { rect } => validate_rect(rect)?,
+        }
+    }
+    if crops > 1 {
+        return Err("An image can only be cropped once".to_string());
+    }
+    Ok(())
+}
+
+/// Pixel bounds of `rect` in an image of `width` by `height`, clamped to
+/// the image: left, top, width and height.
+fn pixel_rect(rect: &Rect, width: u32, height: u32) -> (u32, u32, u32, u32) {
+    let scale = |v: f64, size: u32| (v * size as f64).round().clamp(0.0, size as f64) as u32;
+    let left = scale(rect.x, width);
+    let top = scale(rect.y, height);
+    let right = scale(rect.x + rect.width, width).max(left);
+    let bottom = scale(rect.y + rect.height, height).max(top);
+    (left, top, right - left, bottom - top)
+}
+
+/// Blends `color` over every pixel in the `bounds` (left, top, right,
+/// bottom, in pixels) whose center `covered` says belongs to the mark, once
+/// per pixel so translucent colors stay even.
+fn paint(
+    image: &mut RgbaImage,
+    bounds: (f64, f64, f64, f64),
+    color: Rgba<u8>,
+    covered: impl Fn(f64, f64) -> bool,
+) {
+    let (width, height) = image.dimensions();
+    let x0 = bounds.0.floor().clamp(0.0, width as f64) as u32;
+    let y0 = bounds.1.floor().clamp(0.0, height as f64) as u32;
+    let x1 = bounds.2.ceil().clamp(0.0, width as f64) as u32;
+    let y1 = bounds.3.ceil().clamp(0.0, height as f64) as u32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if covered(x as f64 + 0.5, y as f64 + 0.5) {
+                image.get_pixel_mut(x, y).blend(&color);
+            }
+        }
+    }
+}
+
+/// Distance from `(px, py)` to the segment from `a` to `b`.
+fn segment_distance(px: f64, py: f64, a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((px - a.0) * dx + (py - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+}
+
+fn draw_arrow(image: &mut RgbaImage, from: &Point, to: &Point, color: Rgba<u8>, stroke: f64) {
+    let (width, height) = image.dimensions();
+    let a = (from.x * width as f64, from.y * height as f64);
+    let b = (to.x * width as f64, to.y * height as f64);
+    let radius = stroke / 2.0;
+
+    let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+    let head = (stroke * ARROWHEAD_LENGTH).min(length);
+    let angle = (a.1 - b.1).atan2(a.0 - b.0);
+    let barb = |offset: f64| {
+        (
+            b.0 + head * (angle + offset).cos(),
+            b.1 + head * (angle + offset).sin(),
+        )
+    };
+    let segments = [
+        (a, b),
+        (b, barb(std::f64::consts::FRAC_PI_6)),
+        (b, barb(-std::f64::consts::FRAC_PI_6)),
+    ];
+
+    let margin = radius + head;
+    let bounds = (
+        a.0.min(b.0) - margin,
+        a.1.min(b.1) - margin,
+        a.0.max(b.0) + margin,
+        a.1.max(b.1) + margin,
+    );
+    paint(image, bounds, color, |x, y| {
+        segments
+            .iter()
+            .any(|(p, q)| segment_distance(x, y, *p, *q) <= radius)
+    });
+}
+
+fn draw_ellipse(image: &mut RgbaImage, rect: &Rect, color: Rgba<u8>, stroke: f64) {
+    let (width, height) = image.dimensions();
+    let rx = rect.width * width as f64 / 2.0;
+    let ry = rect.height * height as f64 / 2.0;
+    let cx = rect.x * width as f64 + rx;
+    let cy = rect.y * height as f64 + ry;
+    let radius = stroke / 2.0;
+    let bounds = (
+        cx - rx - radius,
+        cy - ry - radius,
+        cx + rx + radius,
+        cy + ry + radius,
+    );
+    // Distance to the outline, approximated by scaling the normalized
+    // distance by the shorter radius
+    let scale = rx.min(ry).max(1.0);
+    paint(image, bounds, color, |x, y| {
+        let normalized =
+            (((x - cx) / rx.max(1.0)).powi(2) + ((y - cy) / ry.max(1.0)).powi(2)).sqrt();
+        ((normalized - 1.0) * scale).abs() <= radius
+    });
+}
+
+fn draw_rectangle(image: &mut RgbaImage, rect: &Rect, color: Rgba<u8>, stroke: f64) {
+    let (width, height) = image.dimensions();
+    let left = rect.x * width as f64;
+    let top = rect.y * height as f64;
+    let right = left + rect.width * width as f64;
+    let bottom = top + rect.height * height as f64;
+    let radius = stroke / 2.0;
+    let bounds = (left - radius, top - radius, right + radius, bottom + radius);
+    paint(image, bounds, color, |x, y| {
+        let inside =
+            x > left + radius && x < right - radius && y > top + radius && y < bottom - radius;
+        !inside
+    });
+}
+
+fn blur(image: &mut RgbaImage, rect: &Rect) {
+    let (width, height) = image.dimensions();
+    let (left, top, region_width, region_height) = pixel_rect(rect, width, height);
+    if region_width == 0 || region_height == 0 {
+        return;
+    }
+    let region = imageops::crop_imm(image, left, top, region_width, region_height).to_image();
+    let sigma = (region_width.min(region_height) as f64 * BLUR_STRENGTH) as f32;
+    let blurred = imageops::blur(&region, sigma.max(MIN_BLUR_SIGMA));
+    imageops::replace(image, &blurred, left as i64, top as i64);
+}
+
+/// Applies annotations to an image: blurs, then marks in order, then the
+/// crop.
+fn render(mut image: RgbaImage, annotations: &[Annotation]) -> Result<RgbaImage, String> {
+    let (width, height) = image.dimensions();
+    let shorter_side = width.min(height) as f64;
+    let stroke_px =
+        |stroke: &Option<f64>| (stroke.unwrap_or(DEFAULT_STROKE) * shorter_side).max(1.0);
+
+    for annotation in annotations {
+        if let Annotation::Blur { rect } = annotation {
+            blur(&mut image, rect);
+        }
+    }
+    for annotation in annotations {
+        match annotation {
+            Annotation::Arrow {
+                from,
+                to,
+                color,
+                stroke,
+            } => draw_arrow(
+                &mut image,
+                from,
+                to,
+                parse_color(color.as_deref())?,
+                stroke_px(stroke),
+            ),
+            Annotation::Ellipse {
+                rect,
+                color,
+                stroke,
+            } => draw_ellipse(
+                &mut image,
+                rect,
+                parse_color(color.as_deref())?,
+                stroke_px(stroke),
+            ),
+            Annotation::Rectangle {
+                rect,
+                color,
+                stroke,
+            } => draw_rectangle(
+                &mut image,
+                rect,
+                parse_color(color.as_deref())?,
+                stroke_px(stroke),
+            ),
+            Annotation::Crop { .. } | Annotation::Blur { .. } => {}
+        }
+    }
+
+    let crop = annotations.iter().find_map(|annotation| match annotation {
+        Annotation::Crop { rect } => Some(pixel_rect(rect, width, height)),
+        _ => None,
+    });
+    match crop {
+        Some((_, _, 0, _)) | Some((_, _, _, 0)) => Err("The crop is empty".to_string()),
+        Some((left, top, crop_width, crop_height)) => {
+            Ok(imageops::crop_imm(&image, left, top, crop_width, crop_height).to_image())
+        }
+        None => Ok(image),
+    }
+}
+
+fn load(app: &AppHandle, attachment: &str) -> Result<Vec<Annotation>, String> {
+    let conn = crate::db::open(app)?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT annotations FROM attachment_annotations WHERE attachment = ?1",
+            params![attachment],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error("read annotations"))?;
+    match json {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse annotations of {attachment}: {e}")),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Deletes the annotations of attachments that no longer exist. Used by
+/// attachment GC; failures are logged and skipped.
+pub fn forget(app: &AppHandle, attachments: &[String]) {
+    let result = crate::db::open(app).and_then(|conn| {
+        for attachment in attachments {
+            conn.execute(
+                "DELETE FROM attachment_annotations WHERE attachment = ?1",
+                params![attachment],
+            )
+            .map_err(db_error("delete annotations"))?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to delete annotations of removed attachments: {e}");
+    }
+}
+
+/// The annotations saved for an attachment, in the order they were drawn.
+#[tauri::command]
+pub async fn get_image_annotations(
+    app: AppHandle,
+    attachment: String,
+) -> Result<Vec<Annotation>, String> {
+    crate::validate_filename(&attachment)?;
+    load(&app, &attachment)
+}
+
+/// Replaces the annotations saved for an attachment; an empty list removes
+/// them.
+#[tauri::command]
+pub async fn save_image_annotations(
+    app: AppHandle,
+    attachment: String,
+    annotations: Vec<Annotation>,
+) -> Result<(), String> {
+    crate::validate_filename(&attachment)?;
+    validate(&annotations)?;
+
+    let conn = crate::db::open(&app)?;
+    if annotations.is_empty() {
+        conn.execute(
+            "DELETE FROM attachment_annotations WHERE attachment = ?1",
+            params![attachment],
+        )
+        .map_err(db_error("delete annotations"))?;
+        return Ok(());
+    }
+    let json = serde_json::to_string(&annotations)
+        .map_err(|e| format!("Failed to serialize annotations: {e}"))?;
+    conn.execute(
+        "INSERT INTO attachment_annotations (attachment, annotations, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT (attachment) DO UPDATE
+             SET annotations = excluded.annotations, updated_at = excluded.updated_at",
+        params![attachment, json, crate::time::now_ms() as i64],
+    )
+    .map_err(db_error("save annotations"))?;
+    log::debug!(
+        "Saved {} annotations for attachment {attachment}",
+        annotations.len()
+    );
+    Ok(())
+}
+
+/// Renders an image attachment with annotations baked in and stores the
+/// result as a new PNG attachment. Without `annotations`, the saved ones
+/// are used.
+#[tauri::command]
+pub async fn render_annotated_image(
+    app: AppHandle,
+    attachment: String,
+    annotations: Option<Vec<Annotation>>,
+) -> Result<StoredAttachment, String> {
+    crate::validate_filename(&attachment)?;
+    let annotations = match annotations {
+        Some(annotations) => annotations,
+        None => load(&app, &attachment)?,
+    };
+    validate(&annotations)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let stored = crate::attachments::read(&app, attachment.clone())?;
+        let bytes = engine
+            .decode(stored.data.trim())
+            .map_err(|e| format!("Attachment {attachment} is not valid base64: {e}"))?;
+
+        let mut reader = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| format!("Failed to read image: {e}"))?;
+        if reader.format().is_none() {
+            return Err(format!(
+                "Attachment {attachment} is not a PNG or JPEG image"
+            ));
+        }
+        let mut limits = Limits::default();
+        limits.max_alloc = Some(MAX_DECODED_BYTES);
+        reader.limits(limits);
+        let image = reader
+            .decode()
+            .map_err(|e| match e {
+                ImageError::Limits(_) => "Image is too large to annotate".to_string(),
+                e => format!("Failed to decode image: {e}"),
+            })?
+            .to_rgba8();
+
+        let rendered = render(image, &annotations)?;
+        let mut png = Vec::new();
+        rendered
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode image: {e}"))?;
+
+        let result =
+            crate::attachments::store(&app, engine.encode(png), Some("image/png".to_string()))?;
+        log::info!(
+            "Rendered {} annotations on attachment {attachment} as {}",
+            annotations.len(),
+            result.hash
+        );
+        Ok(result)
+    })
+    .await
+    .map_err(|e| format!("Annotation task failed: {e}"))?
+}
@@ -0,0 +1,84 @@
+// API Keys
+// ========
+//
+// Keys for the built-in model providers (see `llm::ProviderId`), kept in
+// the keychain under `api_key:<provider>`. The frontend sets, checks and
+// removes them, but never reads one back: backend features that call a
+// model (benchmarks, task extraction, watch folders, model listing) look
+// the key up here themselves, so it does not pass through the webview.
+
+use crate::keychain;
+use crate::llm::ProviderId;
+
+const MAX_KEY_LENGTH: usize = 1000;
+/// Providers that take a key
+const KEYED_PROVIDERS: &[ProviderId] = &[
+    ProviderId::Google,
+    ProviderId::OpenAI,
+    ProviderId::Groq,
+    ProviderId::Mistral,
+];
+
+fn account(provider: ProviderId) -> String {
+    format!("api_key:{}", provider.as_str())
+}
+
+/// The stored key for `provider`, or `None` if there is none.
+pub fn get(provider: ProviderId) -> Result<Option<String>, String> {
+    if !KEYED_PROVIDERS.contains(&provider) {
+        return Ok(None);
+    }
+    keychain::get(&account(provider))
+}
+
+/// The key for a request to `provider`: the stored one, or an empty key for
+/// providers that take none.
+pub fn require(provider: ProviderId) -> Result<String, String> {
+    if !KEYED_PROVIDERS.contains(&provider) {
+        return Ok(String::new());
+    }
+    get(provider)?.ok_or_else(|| format!("No API key for {}", provider.as_str()))
+}
+
+/// Deletes every stored key. Used when wiping app data; failures are
+/// logged and skipped.
+pub fn delete_all_credentials() {
+    for provider in KEYED_PROVIDERS {
+        if let Err(e) = keychain::delete(&account(*provider)) {
+            log::warn!("Failed to delete API key for {}: {e}", provider.as_str());
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_api_key(provider: ProviderId, key: String) -> Result<(), String> {
+    if !KEYED_PROVIDERS.contains(&provider) {
+        return Err(format!("{} does not use an API key", provider.as_str()));
+    }
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("API key is required".to_string());
+    }
+    if key.len() > MAX_KEY_LENGTH || key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("Invalid API key".to_string());
+    }
+    keychain::set(&account(provider), key)?;
+    log::info!("Stored API key for {}", provider.as_str());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn has_api_key(provider: ProviderId) -> Result<bool, String> {
+    Ok(get(provider)?.is_some())
+}
+
+/// Removes the stored key; removing one that doesn't exist is not an error.
+#[tauri::command]
+pub async fn delete_api_key(provider: ProviderId) -> Result<(), String> {
+    if !KEYED_PROVIDERS.contains(&provider) {
+        return Ok(());
+    }
+    keychain::delete(&account(provider))?;
+    log::info!("Deleted API key for {}", provider.as_str());
+    Ok(())
+}
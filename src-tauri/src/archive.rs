@@ -0,0 +1,597 @@
+// Conversation Archive
+// ====================
+//
+// Archiving takes a conversation out of the hot tables (messages, search
+// index, attachments) and keeps it as one zstd-compressed JSON document in
+// `conversation_archive`, so the tables every listing and search touches
+// stay small as history grows. The conversation's title, tags and the start
+// of its first user message stay in plain columns and can be searched with
+// `list_archived_conversations`; its messages are not in the full-text
+// index until it is unarchived.
+//
+// The document holds everything needed to put the conversation back as it
+// was: messages with their attachment data inline, earlier revisions of
+// edited messages, tags, folder and pin. Messages deleted but not yet
+// purged are dropped. Unarchiving restores the conversation in the hot
+// tables and removes the archived copy in the same transaction.
+//
+// Archived conversations are read-only, and so are conversations locked
+// with `set_conversations_locked`. The backend enforces it in either
+// storage backend: appending, editing, deleting or restoring their
+// messages, or deleting the conversation, fails with an error starting
+// with `CONVERSATION_READ_ONLY`, whatever the UI allowed, and purging
+// leaves their deleted messages alone. With the `unarchive_on_reply`
+// preference, appending to an archived conversation unarchives it first
+// instead.
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::conversations::Conversation;
+use crate::time::now_ms;
+
+const COMPRESSION_LEVEL: i32 = 9;
+const PREVIEW_CHARS: usize = 200;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+const MAX_QUERY_CHARS: usize = 500;
+const MAX_BULK_IDS: usize = 10_000;
+/// Version of the archived document
+const FORMAT: u32 = 1;
+
+/// Prefix of the error for changes to an archived or locked conversation.
+pub const CONVERSATION_READ_ONLY: &str = "Conversation is read-only";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchivedRevision {
+    message_id: String,
+    revision: u32,
+    content: Value,
+    replaced_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchivedDocument {
+    format: u32,
+    conversation: Conversation,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    folder_id: Option<String>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    revisions: Vec<ArchivedRevision>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub model_id: String,
+    pub message_count: u64,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    /// Milliseconds since the UNIX epoch
+    pub updated_at: u64,
+    /// Milliseconds since the UNIX epoch
+    pub archived_at: u64,
+    pub tags: Vec<String>,
+    /// The start of the first user message
+    pub preview: String,
+    /// Bytes of the uncompressed document
+    pub size: u64,
+    /// Bytes stored
+    pub compressed_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedConversationPage {
+    /// Most recently updated first
+    pub conversations: Vec<ArchivedConversationSummary>,
+    /// Zero-based page number
+    pub page: u32,
+    pub page_size: u32,
+    /// Matching conversations across all pages
+    pub total: u64,
+}
+
+fn db_error(action: &str) -> impl Fn(rusqlite::Error) -> String + '_ {
+    move |e| {
+        log::error!("Failed to {action}: {e}");
+        format!("Failed to {action}: {e}")
+    }
+}
+
+/// Reads everything about a conversation the archive keeps, or `None` if it
+/// does not exist.
+fn read_document(conn: &Connection, id: &str) -> rusqlite::Result<Option<ArchivedDocument>> {
+    let Some(conversation) = crate::storage::read_conversation(conn, id)? else {
+        return Ok(None);
+    };
+
+    let tags = conn
+        .prepare("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")?
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    let folder_id = conn
+        .query_row(
+            "SELECT folder_id FROM conversation_folders WHERE conversation_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let pinned = conn
+        .query_row(
+            "SELECT 1 FROM conversation_pins WHERE conversation_id = ?1",
+            params![id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    let locked = conn
+        .query_row(
+            "SELECT 1 FROM conversation_locks WHERE conversation_id = ?1",
+            params![id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    // Revisions of deleted messages go with them
+    let mut select = conn.prepare(
+        "SELECT r.message_id, r.revision, r.content, r.replaced_at FROM message_revisions r
+         WHERE r.conversation_id = ?1 AND NOT EXISTS
+             (SELECT 1 FROM message_deletions d
+              WHERE d.conversation_id = r.conversation_id AND d.message_id = r.message_id)
+         ORDER BY r.message_id, r.revision",
+    )?;
+    let mut rows = select.query(params![id])?;
+    let mut revisions = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut content: Value = serde_json::from_str(&row.get::<_, String>(2)?).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, e.into())
+        })?;
+        crate::storage::rehydrate(conn, &mut content)?;
+        revisions.push(ArchivedRevision {
+            message_id: row.get(0)?,
+            revision: row.get(1)?,
+            content,
+            replaced_at: row.get::<_, i64>(3)? as u64,
+        });
+    }
+
+    Ok(Some(ArchivedDocument {
+        format: FORMAT,
+        conversation,
+        tags,
+        folder_id,
+        pinned,
+        locked,
+        revisions,
+    }))
+}
+
+fn preview(conversation: &Conversation) -> String {
+    conversation
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| m.text().chars().take(PREVIEW_CHARS).collect())
+        .unwrap_or_default()
+}
+
+/// Puts an archived conversation back into the hot tables inside `tx`.
+fn restore_document(tx: &Transaction, document: &ArchivedDocument) -> rusqlite::Result<()> {
+    let id = &document.conversation.id;
+    crate::storage::insert(tx, &document.conversation)?;
+
+    for revision in &document.revisions {
+        let mut content = revision.content.clone();
+        let hashes = crate::storage::store_attachments(tx, &mut content)?;
+        tx.execute(
+            "INSERT INTO message_revisions
+                 (conversation_id, message_id, revision, content, replaced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                id,
+                revision.message_id,
+                revision.revision,
+                content.to_string(),
+                revision.replaced_at as i64
+            ],
+        )?;
+        crate::storage::link_attachments(tx, id, &revision.message_id, hashes)?;
+    }
+
+    let mut insert_tag =
+        tx.prepare("INSERT INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)")?;
+    for tag in &document.tags {
+        insert_tag.execute(params![id, tag])?;
+    }
+    // The folder may have been deleted in the meantime
+    if let Some(folder_id) = &document.folder_id {
+        tx.execute(
+            "INSERT INTO conversation_folders (conversation_id, folder_id)
+             SELECT ?1, id FROM folders WHERE id = ?2",
+            params![id, folder_id],
+        )?;
+    }
+    if document.pinned {
+        tx.execute(
+            "INSERT INTO conversation_pins (conversation_id, pinned_at) VALUES (?1, ?2)",
+            params![id, now_ms() as i64],
+        )?;
+    }
+    if document.locked {
+        tx.execute(
+            "INSERT INTO conversation_locks (conversation_id, locked_at) VALUES (?1, ?2)",
+            params![id, now_ms() as i64],
+        )?;
+    }
+    Ok(())
+}
+
+/// `Err` starting with `CONVERSATION_READ_ONLY` if the conversation is
+/// archived or locked.
+pub fn check_writable(conn: &Connection, id: &str) -> Result<(), String> {
+    let state: Option<String> = conn
+        .query_row(
+            "SELECT 'archived' FROM conversation_archive WHERE id = ?1
+             UNION ALL
+             SELECT 'locked' FROM conversation_locks WHERE conversation_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error("check conversation"))?;
+    match state {
+        Some(state) => {
+            log::warn!("Refused to change {state} conversation {id}");
+            Err(format!(
+                "{CONVERSATION_READ_ONLY}: conversation {id} is {state}"
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn is_archived(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM conversation_archive WHERE id = ?1",
+        params![id],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|archived| archived.is_some())
+    .map_err(db_error("check conversation"))
+}
+
+fn validate_ids(ids: &[String]) -> Result<(), String> {
+    if ids.len() > MAX_BULK_IDS {
+        return Err(format!("Too many conversations (max {MAX_BULK_IDS})"));
+    }
+    Ok(())
+}
+
+/// Moves one conversation into the archive. Returns `false` if it does not
+/// exist.
+fn archive(conn: &mut Connection, app: &AppHandle, id: &str) -> Result<bool, String> {
+    let Some(mut document) = read_document(conn, id).map_err(db_error("read conversation"))? else {
+        return Ok(false);
+    };
+    // Blob references would not keep the blobs alive once archived
+    crate::attachments::rehydrate(app, &mut document.conversation);
+
+    let json = serde_json::to_vec(&document)
+        .map_err(|e| format!("Failed to serialize conversation: {e}"))?;
+    let data = zstd::encode_all(json.as_slice(), COMPRESSION_LEVEL)
+        .map_err(|e| format!("Failed to compress conversation: {e}"))?;
+    let conversation = &document.conversation;
+
+    let tx = conn
+        .transaction()
+        .map_err(db_error("archive conversation"))?;
+    tx.execute(
+        "INSERT INTO conversation_archive
+             (id, title, model_id, created_at, updated_at, archived_at, message_count,
+              tags, preview, size, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            conversation.id,
+            conversation.title,
+            conversation.model_id,
+            conversation.created_at as i64,
+            conversation.updated_at as i64,
+            now_ms() as i64,
+            conversation.messages.len() as i64,
+            serde_json::to_string(&document.tags).unwrap_or_else(|_| "[]".to_string()),
+            preview(conversation),
+            json.len() as i64,
+            data
+        ],
+    )
+    .and_then(|_| tx.execute("DELETE FROM conversations WHERE id = ?1", params![id]))
+    .and_then(|_| crate::storage::delete_orphaned_attachments(&tx))
+    .and_then(|_| tx.commit())
+    .map_err(db_error("archive conversation"))?;
+
+    log::info!(
+        "Archived conversation {id} ({} bytes, {} compressed)",
+        json.len(),
+        data.len()
+    );
+    Ok(true)
+}
+
+/// Moves a conversation out of the hot tables into a compressed archive.
+/// Returns whether it existed.
+#[tauri::command]
+pub async fn archive_conversation(app: AppHandle, id: String) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        let archived = archive(&mut conn, &app, &id)?;
+        if archived {
+            crate::quick_actions::refresh(&app);
+        }
+        Ok(archived)
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {e}"))?
+}
+
+/// Archives every unpinned conversation last updated before `before`
+/// (milliseconds since the UNIX epoch). Returns how many were archived.
+#[tauri::command]
+pub async fn archive_conversations_before(app: AppHandle, before: u64) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        let ids = conn
+            .prepare(
+                "SELECT id FROM conversations c WHERE updated_at < ?1 AND NOT EXISTS
+                     (SELECT 1 FROM conversation_pins p WHERE p.conversation_id = c.id)
+                 ORDER BY updated_at",
+            )
+            .and_then(|mut select| {
+                select
+                    .query_map(params![before as i64], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            })
+            .map_err(db_error("list conversations to archive"))?;
+
+        let mut archived = 0;
+        for id in ids {
+            if archive(&mut conn, &app, &id)? {
+                archived += 1;
+            }
+        }
+        log::info!("Archived {archived} conversations not updated since {before}");
+        Ok(archived)
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {e}"))?
+}
+
+/// Moves one archived conversation back into the hot tables. Returns
+/// `false` if it is not archived.
+pub fn unarchive(conn: &mut Connection, app: &AppHandle, id: &str) -> Result<bool, String> {
+    let Some(data) = conn
+        .query_row(
+            "SELECT data FROM conversation_archive WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, Vec<u8>>(0),
+        )
+        .optional()
+        .map_err(db_error("read archived conversation"))?
+    else {
+        return Ok(false);
+    };
+
+    let json = zstd::decode_all(data.as_slice())
+        .map_err(|e| format!("Failed to decompress archived conversation: {e}"))?;
+    let document: ArchivedDocument = serde_json::from_slice(&json)
+        .map_err(|e| format!("Failed to parse archived conversation: {e}"))?;
+    if document.format > FORMAT {
+        return Err(format!(
+            "Conversation {id} was archived by a newer version of the app"
+        ));
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(db_error("unarchive conversation"))?;
+    restore_document(&tx, &document)
+        .and_then(|_| {
+            tx.execute(
+                "DELETE FROM conversation_archive WHERE id = ?1",
+                params![id],
+            )
+        })
+        .and_then(|_| tx.commit())
+        .map_err(db_error("unarchive conversation"))?;
+
+    log::info!("Unarchived conversation {id}");
+    if document.pinned {
+        crate::quick_actions::refresh(app);
+    }
+    Ok(true)
+}
+
+/// Moves an archived conversation back into the hot tables, with its
+/// messages searchable again. Returns whether it was archived.
+#[tauri::command]
+pub async fn unarchive_conversation(app: AppHandle, id: String) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        unarchive(&mut conn, &app, &id)
+    })
+    .await
+    .map_err(|e| format!("Unarchive task failed: {e}"))?
+}
+
+/// Archives the given conversations, pinned ones included. Returns how many
+/// were archived; ids that do not exist are skipped.
+#[tauri::command]
+pub async fn archive_conversations(app: AppHandle, ids: Vec<String>) -> Result<u64, String> {
+    validate_ids(&ids)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        let mut archived = 0;
+        for id in &ids {
+            if archive(&mut conn, &app, id)? {
+                archived += 1;
+            }
+        }
+        log::info!("Archived {archived} of {} conversations", ids.len());
+        if archived > 0 {
+            crate::quick_actions::refresh(&app);
+        }
+        Ok(archived)
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {e}"))?
+}
+
+/// Unarchives the given conversations. Returns how many were archived.
+#[tauri::command]
+pub async fn unarchive_conversations(app: AppHandle, ids: Vec<String>) -> Result<u64, String> {
+    validate_ids(&ids)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        let mut unarchived = 0;
+        for id in &ids {
+            if unarchive(&mut conn, &app, id)? {
+                unarchived += 1;
+            }
+        }
+        log::info!("Unarchived {unarchived} of {} conversations", ids.len());
+        Ok(unarchived)
+    })
+    .await
+    .map_err(|e| format!("Unarchive task failed: {e}"))?
+}
+
+/// Makes conversations read-only, or writable again. Returns how many
+/// changed; ids of conversations that do not exist or are archived are
+/// skipped.
+#[tauri::command]
+pub async fn set_conversations_locked(
+    app: AppHandle,
+    ids: Vec<String>,
+    locked: bool,
+) -> Result<u64, String> {
+    validate_ids(&ids)?;
+    let mut conn = crate::db::open(&app)?;
+    let tx = conn.transaction().map_err(db_error("lock conversations"))?;
+    let now = now_ms() as i64;
+    let mut changed = 0;
+    for id in &ids {
+        changed += if locked {
+            tx.execute(
+                "INSERT INTO conversation_locks (conversation_id, locked_at)
+                 SELECT id, ?2 FROM conversations WHERE id = ?1
+                 ON CONFLICT (conversation_id) DO NOTHING",
+                params![id, now],
+            )
+        } else {
+            tx.execute(
+                "DELETE FROM conversation_locks WHERE conversation_id = ?1",
+                params![id],
+            )
+        }
+        .map_err(db_error("lock conversations"))? as u64;
+    }
+    tx.commit().map_err(db_error("lock conversations"))?;
+
+    log::info!(
+        "{} {changed} conversations",
+        if locked { "Locked" } else { "Unlocked" }
+    );
+    Ok(changed)
+}
+
+/// One page of archived conversations, most recently updated first. With a
+/// `query`, only those whose title, tags or first message contain it,
+/// ignoring case.
+#[tauri::command]
+pub async fn list_archived_conversations(
+    app: AppHandle,
+    query: Option<String>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> Result<ArchivedConversationPage, String> {
+    let query = query
+        .map(|q| q.trim().to_string())
+        .filter(|q| !q.is_empty());
+    if let Some(query) = &query {
+        crate::validate_string_input(query, MAX_QUERY_CHARS, "Search query")?;
+    }
+    let page = page.unwrap_or(0);
+    let page_size = page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    // LIKE wildcards in the query match literally
+    let pattern = query.map(|q| {
+        let escaped = q
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("%{escaped}%")
+    });
+
+    let conn = crate::db::open(&app)?;
+    let filter = "?1 IS NULL OR title LIKE ?1 ESCAPE '\\' OR tags LIKE ?1 ESCAPE '\\'
+                  OR preview LIKE ?1 ESCAPE '\\'";
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM conversation_archive WHERE {filter}"),
+            params![pattern],
+            |row| row.get(0),
+        )
+        .map_err(db_error("list archived conversations"))?;
+
+    let mut select = conn
+        .prepare(&format!(
+            "SELECT id, title, model_id, created_at, updated_at, archived_at, message_count,
+                    tags, preview, size, length(data)
+             FROM conversation_archive WHERE {filter}
+             ORDER BY updated_at DESC, id LIMIT ?2 OFFSET ?3"
+        ))
+        .map_err(db_error("list archived conversations"))?;
+    let conversations = select
+        .query_map(
+            params![pattern, page_size, page as i64 * page_size as i64],
+            |row| {
+                Ok(ArchivedConversationSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    model_id: row.get(2)?,
+                    created_at: row.get::<_, i64>(3)? as u64,
+                    updated_at: row.get::<_, i64>(4)? as u64,
+                    archived_at: row.get::<_, i64>(5)? as u64,
+                    message_count: row.get::<_, i64>(6)? as u64,
+                    tags: serde_json::from_str(&row.get::<_, String>(7)?).unwrap_or_default(),
+                    preview: row.get(8)?,
+                    size: row.get::<_, i64>(9)? as u64,
+                    compressed_size: row.get::<_, i64>(10)? as u64,
+                })
+            },
+        )
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(db_error("list archived conversations"))?;
+
+    Ok(ArchivedConversationPage {
+        conversations,
+        page,
+        page_size,
+        total: total as u64,
+    })
+}
@@ -0,0 +1,615 @@
+// Attachment Store
+// ================
+//
+// Inline attachment payloads (images, audio, files) are moved out of the
+// mirrored conversation files into content-addressed blobs, so identical
+// attachments are stored once. Every blob keeps the set of messages that
+// reference it; when the last reference goes away (a message or
+// conversation is deleted) the blob is marked unreferenced and a periodic
+// GC pass deletes it once a grace period has passed, in case the deletion
+// is undone by a restore or a late sync.
+//
+// Files the user attaches are stored the same way with `store_attachment`,
+// before any message refers to them, and messages then carry only the
+// `blobRef`. The blob holds the base64 payload a content part would have
+// inline, so both paths share one hash. References come from the mirrored
+// conversation files and from messages in the database, which GC reads
+// before each pass; a stored file no message picks up is deleted after the
+// grace period like any other unreferenced blob.
+
+use base64::Engine;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::conversations::Conversation;
+use crate::hash::sha256_hex;
+use crate::time::now_ms;
+
+/// Key that replaces `data` in stored content parts
+pub const BLOB_REF_KEY: &str = "blobRef";
+/// How long an unreferenced blob is kept before GC deletes it
+const GC_GRACE_PERIOD_SECS: u64 = 7 * 24 * 60 * 60;
+/// Largest file `store_attachment` accepts, before base64 encoding
+const MAX_ATTACHMENT_BYTES: u64 = 25 * 1024 * 1024;
+/// Prefix of references held by messages in the database
+const DATABASE_REF_PREFIX: &str = "db:";
+
+/// Serializes updates to the reference index.
+#[derive(Default)]
+pub struct AttachmentStore {
+    index_lock: Mutex<()>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AttachmentIndex {
+    blobs: HashMap<String, BlobEntry>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BlobEntry {
+    size: u64,
+    /// `<conversation id>/<message id>` of every referencing message
+    refs: BTreeSet<String>,
+    /// UNIX timestamp (milliseconds) the last reference was dropped
+    #[serde(default, deserialize_with = "crate::time::legacy_ms_opt")]
+    unreferenced_since: Option<u64>,
+    /// Known for blobs stored with `store_attachment`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+}
+
+/// Where `store_attachment` takes the file from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum AttachmentSource {
+    /// Base64 encoded contents, e.g. pasted or dropped in the webview
+    Bytes {
+        data: String,
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+    /// A file on disk, e.g. picked in a file dialog
+    Path {
+        path: String,
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredAttachment {
+    /// Put in a content part as `blobRef` to refer to the file
+    pub hash: String,
+    pub mime_type: Option<String>,
+    /// Bytes of the stored base64 payload
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentData {
+    pub hash: String,
+    pub mime_type: Option<String>,
+    /// Base64 encoded contents
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentUsage {
+    pub blob_count: usize,
+    pub total_bytes: u64,
+    pub referenced_bytes: u64,
+    /// Bytes no message refers to any more
+    pub unreferenced_bytes: u64,
+    /// Unreferenced bytes past the grace period, freed by the next GC pass
+    pub reclaimable_bytes: u64,
+    pub grace_period_secs: u64,
+}
+
+fn get_attachments_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let attachments_dir = app_data_dir.join("attachments");
+
+    // Ensure the attachments and blob directories exist
+    std::fs::create_dir_all(attachments_dir.join("blobs"))
+        .map_err(|e| format!("Failed to create attachments directory: {e}"))?;
+
+    Ok(attachments_dir)
+}
+
+fn blob_path(app: &AppHandle, hash: &str) -> Result<PathBuf, String> {
+    crate::validate_filename(hash)?;
+    Ok(get_attachments_dir(app)?.join("blobs").join(hash))
+}
+
+fn blob_size(app: &AppHandle, hash: &str) -> Result<u64, String> {
+    Ok(std::fs::metadata(blob_path(app, hash)?)
+        .map(|m| m.len())
+        .unwrap_or(0))
+}
+
+fn read_index(app: &AppHandle) -> Result<AttachmentIndex, String> {
+    let path = get_attachments_dir(app)?.join("refs.json");
+    if !path.exists() {
+        return Ok(AttachmentIndex::default());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read attachment index: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse attachment index: {e}");
+        format!("Failed to parse attachment index: {e}")
+    })
+}
+
+fn write_index(app: &AppHandle, index: &AttachmentIndex) -> Result<(), String> {
+    let json = serde_json::to_vec(index)
+        .map_err(|e| format!("Failed to serialize attachment index: {e}"))?;
+    crate::export::write_atomic(&get_attachments_dir(app)?.join("refs.json"), &json)
+}
+
+/// Applies `update` to the index under the store lock and writes it back.
+fn update_index<T>(
+    app: &AppHandle,
+    update: impl FnOnce(&mut AttachmentIndex) -> T,
+) -> Result<T, String> {
+    try_update_index(app, |index| Ok(update(index)))
+}
+
+/// Like `update_index`, leaving the index as it was if `update` fails.
+/// GC deletes blobs under the same lock, so a blob written and referenced
+/// in `update` can't be collected in between.
+fn try_update_index<T>(
+    app: &AppHandle,
+    update: impl FnOnce(&mut AttachmentIndex) -> Result<T, String>,
+) -> Result<T, String> {
+    let store = app.state::<AttachmentStore>();
+    let _guard = store.index_lock.lock().map_err(|e| e.to_string())?;
+
+    let mut index = read_index(app)?;
+    let result = update(&mut index)?;
+    write_index(app, &index)?;
+    Ok(result)
+}
+
+/// Writes a blob unless it is already stored. Call with the index lock held.
+fn write_blob(app: &AppHandle, hash: &str, data: &str) -> Result<(), String> {
+    let path = blob_path(app, hash)?;
+    if !path.exists() {
+        crate::export::write_atomic(&path, data.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Replaces every reference starting with `prefix` with `refs`, given as
+/// (hash, reference without the prefix, size).
+fn replace_refs(index: &mut AttachmentIndex, prefix: &str, refs: Vec<(String, String, u64)>) {
+    let now = now_ms();
+    for entry in index.blobs.values_mut() {
+        entry.refs.retain(|r| !r.starts_with(prefix));
+    }
+    for (hash, reference, size) in refs {
+        let entry = index.blobs.entry(hash).or_default();
+        entry.size = size;
+        entry.refs.insert(format!("{prefix}{reference}"));
+    }
+    for entry in index.blobs.values_mut() {
+        if entry.refs.is_empty() {
+            entry.unreferenced_since.get_or_insert(now);
+        } else {
+            entry.unreferenced_since = None;
+        }
+    }
+}
+
+/// `replace_refs` on the stored index.
+fn set_refs(app: &AppHandle, prefix: &str, refs: Vec<(String, String, u64)>) -> Result<(), String> {
+    update_index(app, |index| replace_refs(index, prefix, refs))
+}
+
+/// Replaces the references held by one conversation.
+fn set_conversation_refs(
+    app: &AppHandle,
+    conversation_id: &str,
+    refs: Vec<(String, String, u64)>,
+) -> Result<(), String> {
+    set_refs(app, &format!("{conversation_id}/"), refs)
+}
+
+/// Drops every reference held by a deleted conversation.
+pub fn release_conversation(app: &AppHandle, conversation_id: &str) -> Result<(), String> {
+    set_conversation_refs(app, conversation_id, Vec::new())
+}
+
+/// Moves inline attachment data into blobs and records the references.
+/// Returns the conversation as it should be written to disk.
+pub fn externalize(app: &AppHandle, conversation: &Conversation) -> Result<Conversation, String> {
+    let mut stored = conversation.clone();
+    // (hash, message id) of blobs already stored
+    let mut existing = Vec::new();
+    // (hash, message id, data) of blobs to write
+    let mut new = Vec::new();
+
+    for message in &mut stored.messages {
+        let Value::Array(parts) = &mut message.content else {
+            continue;
+        };
+        for part in parts.iter_mut() {
+            if part.get("type").and_then(Value::as_str) == Some("text") {
+                continue;
+            }
+            // Already stored, e.g. with `store_attachment`
+            if let Some(hash) = part.get(BLOB_REF_KEY).and_then(Value::as_str) {
+                if crate::validate_filename(hash).is_err() {
+                    log::warn!("Ignoring invalid attachment reference {hash}");
+                    continue;
+                }
+                existing.push((hash.to_string(), message.id.clone()));
+                continue;
+            }
+            let Some(object) = part.as_object_mut() else {
+                continue;
+            };
+            if !object.get("data").is_some_and(Value::is_string) {
+                continue;
+            }
+            let Some(Value::String(data)) = object.remove("data") else {
+                continue;
+            };
+
+            let hash = sha256_hex(data.as_bytes());
+            object.insert(BLOB_REF_KEY.to_string(), Value::String(hash.clone()));
+            new.push((hash, message.id.clone(), data));
+        }
+    }
+
+    // Writing the blobs and referencing them is one step under the index
+    // lock, so GC can't delete a blob this found already stored
+    try_update_index(app, |index| {
+        let mut refs = Vec::with_capacity(existing.len() + new.len());
+        for (hash, message_id) in existing {
+            let size = blob_size(app, &hash)?;
+            refs.push((hash, message_id, size));
+        }
+        for (hash, message_id, data) in new {
+            write_blob(app, &hash, &data)?;
+            refs.push((hash, message_id, data.len() as u64));
+        }
+        replace_refs(index, &format!("{}/", conversation.id), refs);
+        Ok(())
+    })?;
+    Ok(stored)
+}
+
+/// Restores inline attachment data from blobs after loading.
+pub fn rehydrate(app: &AppHandle, conversation: &mut Conversation) {
+    for message in &mut conversation.messages {
+        let Value::Array(parts) = &mut message.content else {
+            continue;
+        };
+        for part in parts.iter_mut() {
+            let Some(object) = part.as_object_mut() else {
+                continue;
+            };
+            let Some(hash) = object.get(BLOB_REF_KEY).and_then(Value::as_str) else {
+                continue;
+            };
+
+            match blob_path(app, hash).and_then(|path| {
+                std::fs::read_to_string(path).map_err(|e| format!("Failed to read blob: {e}"))
+            }) {
+                Ok(data) => {
+                    object.remove(BLOB_REF_KEY);
+                    object.insert("data".to_string(), Value::String(data));
+                }
+                Err(e) => log::warn!(
+                    "Missing attachment {hash} in conversation {}: {e}",
+                    conversation.id
+                ),
+            }
+        }
+    }
+}
+
+/// Blobs referenced by messages (and their earlier revisions) in the
+/// database, as (hash, `<conversation id>/<message id>`).
+fn database_refs(app: &AppHandle) -> Result<Vec<(String, String)>, String> {
+    let conn = crate::db::open(app)?;
+    let mut select = conn
+        .prepare(
+            "SELECT f.value, m.conversation_id || '/' || m.message_id
+             FROM (SELECT conversation_id, id AS message_id, content FROM messages
+                   UNION ALL
+                   SELECT conversation_id, message_id, content FROM message_revisions) m,
+                  json_each(CASE WHEN json_type(m.content) = 'array'
+                                 THEN m.content ELSE '[]' END) p,
+                  json_each(CASE WHEN p.type = 'object' THEN p.value ELSE '{}' END) f
+             WHERE instr(m.content, ?1) > 0 AND f.key = ?1 AND f.type = 'text'",
+        )
+        .map_err(|e| format!("Failed to read attachment references: {e}"))?;
+    let refs = select
+        .query_map(params![BLOB_REF_KEY], |row| Ok((row.get(0)?, row.get(1)?)))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read attachment references: {e}"))?;
+    Ok(refs)
+}
+
+/// Deletes blobs that have been unreferenced for longer than the grace
+/// period. Blobs on disk that the index does not know about start their
+/// grace period now.
+pub fn collect_garbage(app: &AppHandle) -> Result<(), String> {
+    let mut refs = Vec::new();
+    for (hash, reference) in database_refs(app)? {
+        if blob_path(app, &hash).is_err() {
+            continue;
+        }
+        let size = blob_size(app, &hash)?;
+        refs.push((hash, reference, size));
+    }
+    set_refs(app, DATABASE_REF_PREFIX, refs)?;
+
+    let blobs_dir = get_attachments_dir(app)?.join("blobs");
+    let on_disk: Vec<(String, u64)> = std::fs::read_dir(&blobs_dir)
+        .map_err(|e| format!("Failed to read attachment blobs: {e}"))?
+        .flatten()
+        .filter(|e| e.path().extension().is_none())
+        .map(|e| {
+            let size = e.metadata().map(|m| m.len()).unwrap_or(0);
+            (e.file_name().to_string_lossy().to_string(), size)
+        })
+        .collect();
+
+    let (removed, freed) = update_index(app, |index| {
+        let now = now_ms();
+        for (hash, size) in &on_disk {
+            index
+                .blobs
+                .entry(hash.clone())
+                .or_insert_with(|| BlobEntry {
+                    size: *size,
+                    refs: BTreeSet::new(),
+                    unreferenced_since: Some(now),
+                    mime_type: None,
+                });
+        }
+
+        let expired: Vec<String> = index
+            .blobs
+            .iter()
+            .filter(|(_, e)| {
+                e.refs.is_empty()
+                    && e.unreferenced_since
+                        .is_some_and(|t| now.saturating_sub(t) >= GC_GRACE_PERIOD_SECS * 1000)
+            })
+            .map(|(hash, _)| hash.clone())
+            .collect();
+
+        let mut freed = 0;
+        let mut removed = Vec::new();
+        for hash in expired {
+            let path = blobs_dir.join(&hash);
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    log::warn!("Failed to delete attachment blob {hash}: {e}");
+                    continue;
+                }
+            }
+            if let Some(entry) = index.blobs.remove(&hash) {
+                freed += entry.size;
+                removed.push(hash);
+            }
+        }
+        (removed, freed)
+    })?;
+
+    if !removed.is_empty() {
+        crate::annotations::forget(app, &removed);
+        log::info!(
+            "Attachment GC removed {} blobs ({freed} bytes)",
+            removed.len()
+        );
+    }
+    Ok(())
+}
+
+/// Reports how much space attachments use and how much GC can reclaim.
+#[tauri::command]
+pub async fn get_attachment_usage(app: AppHandle) -> Result<AttachmentUsage, String> {
+    let index = {
+        let store = app.state::<AttachmentStore>();
+        let _guard = store.index_lock.lock().map_err(|e| e.to_string())?;
+        read_index(&app)?
+    };
+
+    let now = now_ms();
+    let mut usage = AttachmentUsage {
+        blob_count: index.blobs.len(),
+        total_bytes: 0,
+        referenced_bytes: 0,
+        unreferenced_bytes: 0,
+        reclaimable_bytes: 0,
+        grace_period_secs: GC_GRACE_PERIOD_SECS,
+    };
+    for entry in index.blobs.values() {
+        usage.total_bytes += entry.size;
+        if !entry.refs.is_empty() {
+            usage.referenced_bytes += entry.size;
+            continue;
+        }
+        usage.unreferenced_bytes += entry.size;
+        if entry
+            .unreferenced_since
+            .is_some_and(|t| now.saturating_sub(t) >= GC_GRACE_PERIOD_SECS * 1000)
+        {
+            usage.reclaimable_bytes += entry.size;
+        }
+    }
+
+    Ok(usage)
+}
+
+/// The MIME type of the file types attachments commonly are, from the
+/// extension.
+pub fn guess_mime_type(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "txt" | "log" | "rs" | "ts" | "tsx" | "js" | "jsx" | "py" | "go" | "java" | "c" | "h"
+        | "cpp" | "hpp" | "rb" | "sh" | "toml" | "yaml" | "yml" | "sql" | "css" => "text/plain",
+        _ => return None,
+    };
+    Some(mime_type.to_string())
+}
+
+/// Stores a user-provided file as a blob and returns its hash. Storing the
+/// same contents twice returns the same hash. Until a message refers to it
+/// the blob counts as unreferenced, so an attachment that is never sent is
+/// collected after the grace period.
+#[tauri::command]
+pub async fn store_attachment(
+    app: AppHandle,
+    source: AttachmentSource,
+) -> Result<StoredAttachment, String> {
+    match source {
+        AttachmentSource::Bytes { data, mime_type } => {
+            if data.len() as u64 > MAX_ATTACHMENT_BYTES / 3 * 4 + 4 {
+                return Err(format!(
+                    "Attachment too large (max {} MB)",
+                    MAX_ATTACHMENT_BYTES / 1024 / 1024
+                ));
+            }
+            // Rejects anything that would not decode for the model
+            base64::engine::general_purpose::STANDARD
+                .decode(&data)
+                .map_err(|e| format!("Invalid attachment data: {e}"))?;
+            store(&app, data, mime_type)
+        }
+        AttachmentSource::Path { path, mime_type } => {
+            let path = PathBuf::from(path);
+            let bytes = read_file(&path)?;
+            store_bytes(&app, &bytes, mime_type.or_else(|| guess_mime_type(&path)))
+        }
+    }
+}
+
+/// Reads a file to attach, refusing directories and anything over the
+/// attachment size limit.
+pub fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    if metadata.len() > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachment too large (max {} MB)",
+            MAX_ATTACHMENT_BYTES / 1024 / 1024
+        ));
+    }
+    std::fs::read(path).map_err(|e| {
+        log::error!("Failed to read attachment {}: {e}", path.display());
+        format!("Failed to read {}: {e}", path.display())
+    })
+}
+
+/// Stores a base64 payload as a blob, unreferenced until a message refers
+/// to it.
+pub fn store(
+    app: &AppHandle,
+    data: String,
+    mime_type: Option<String>,
+) -> Result<StoredAttachment, String> {
+    if let Some(mime_type) = &mime_type {
+        crate::validate_string_input(mime_type, 100, "MIME type")?;
+    }
+
+    let hash = sha256_hex(data.as_bytes());
+    let size = data.len() as u64;
+    let mime_type = try_update_index(app, |index| {
+        write_blob(app, &hash, &data)?;
+        let entry = index.blobs.entry(hash.clone()).or_default();
+        entry.size = size;
+        if entry.refs.is_empty() {
+            entry.unreferenced_since.get_or_insert(now_ms());
+        }
+        if entry.mime_type.is_none() {
+            entry.mime_type = mime_type;
+        }
+        Ok(entry.mime_type.clone())
+    })?;
+
+    log::info!("Stored attachment {hash} ({size} bytes)");
+    Ok(StoredAttachment {
+        hash,
+        mime_type,
+        size,
+    })
+}
+
+/// Stores raw file contents as a blob, encoding them as the base64 payload
+/// blobs hold.
+pub fn store_bytes(
+    app: &AppHandle,
+    bytes: &[u8],
+    mime_type: Option<String>,
+) -> Result<StoredAttachment, String> {
+    store(
+        app,
+        base64::engine::general_purpose::STANDARD.encode(bytes),
+        mime_type,
+    )
+}
+
+/// Reads a stored attachment by hash.
+#[tauri::command]
+pub async fn get_attachment(app: AppHandle, hash: String) -> Result<AttachmentData, String> {
+    read(&app, hash)
+}
+
+/// A blob with the MIME type it was stored with.
+pub fn read(app: &AppHandle, hash: String) -> Result<AttachmentData, String> {
+    let path = blob_path(app, &hash)?;
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("Attachment {hash} not found"));
+        }
+        Err(e) => return Err(format!("Failed to read attachment {hash}: {e}")),
+    };
+    let mime_type = {
+        let store = app.state::<AttachmentStore>();
+        let _guard = store.index_lock.lock().map_err(|e| e.to_string())?;
+        read_index(app)?
+            .blobs
+            .get(&hash)
+            .and_then(|entry| entry.mime_type.clone())
+    };
+
+    Ok(AttachmentData {
+        hash,
+        mime_type,
+        data,
+    })
+}
@@ -0,0 +1,776 @@
+// Audio Recording and Transcription
+// =================================
+//
+// Voice input for the playground. `start_audio_recording` captures the
+// default microphone with cpal on a thread of its own, since audio streams
+// can't move between threads on every platform. The samples are mixed down
+// to mono 16-bit PCM and streamed into a WAV file in the temp directory, so
+// a long recording doesn't sit in memory; `stop_audio_recording` stores
+// the file as an attachment and deletes it. A recording is capped at
+// `MAX_RECORDING_SECS`: samples past it are dropped and an
+// `audio-recording-limit` event tells the webview to stop it.
+//
+// `transcribe_audio` turns an audio attachment into text with one of two
+// engines:
+//
+// - `openai`: the `audio/transcriptions` endpoint of an OpenAI-compatible
+//   API. Models other than `whisper-1` stream their transcript.
+// - `whisper_cpp`: whisper.cpp's `whisper-cli` on this machine, looked up
+//   on the login shell's PATH or taken from `WHISPER_CLI`, with a model
+//   file the user downloaded. Only builds with the `local-whisper` feature
+//   accept it.
+//
+// Either way `transcription-partial` events carry the transcript as it
+// grows, and the command resolves with the whole of it.
+
+use base64::Engine;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, StreamConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::attachments::StoredAttachment;
+use crate::chat_proxy::ProviderConfig;
+
+const MAX_RECORDING_SECS: u64 = 10 * 60;
+const MAX_RECORDINGS: usize = 4;
+const WAV_HEADER_LEN: usize = 44;
+const MAX_ID_LENGTH: usize = 100;
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini-transcribe";
+/// The one OpenAI transcription model that can't stream
+const NON_STREAMING_MODEL: &str = "whisper-1";
+/// Whether this build accepts the `whisper_cpp` engine
+const WHISPER_CPP_ENABLED: bool = cfg!(feature = "local-whisper");
+
+/// Messages to a recording's thread.
+enum Message {
+    Samples(Vec<i16>),
+    Failed(String),
+    Stop,
+}
+
+struct Recording {
+    /// Also held by the audio callback
+    sender: mpsc::Sender<Message>,
+    thread: JoinHandle<Result<Captured, String>>,
+}
+
+/// A finished recording, as a WAV file.
+struct Captured {
+    path: PathBuf,
+    sample_rate: u32,
+    frames: u64,
+}
+
+impl Captured {
+    fn duration_ms(&self) -> u64 {
+        self.frames * 1000 / self.sample_rate as u64
+    }
+}
+
+/// The header of a WAV file of 16-bit PCM.
+fn wav_header(sample_rate: u32, channels: u16, data_len: u32) -> Vec<u8> {
+    let block_align = channels * 2;
+    let mut header = Vec::with_capacity(WAV_HEADER_LEN);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(36 + data_len).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    // PCM
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+/// Mixes interleaved frames of `channels` samples down to mono.
+fn downmix<T>(data: &[T], channels: usize) -> Vec<i16>
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    data.chunks_exact(channels.max(1))
+        .map(|frame| {
+            let sum: f32 = frame.iter().map(|&s| f32::from_sample(s)).sum();
+            i16::from_sample(sum / frame.len() as f32)
+        })
+        .collect()
+}
+
+/// Builds an input stream for `device` that sends its samples, in mono,
+/// to `sender`.
+fn input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    sender: mpsc::Sender<Message>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let errors = sender.clone();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let _ = sender.send(Message::Samples(downmix(data, channels)));
+            },
+            move |e| {
+                let _ = errors.send(Message::Failed(e.to_string()));
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to open the microphone: {e}"))
+}
+
+/// Opens the default microphone and starts capturing into `sender`.
+fn open_microphone(sender: mpsc::Sender<Message>) -> Result<(cpal::Stream, u32), String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("No microphone was found")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to read the microphone's settings: {e}"))?;
+    let config: StreamConfig = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => input_stream::<f32>(&device, &config, sender),
+        SampleFormat::I16 => input_stream::<i16>(&device, &config, sender),
+        SampleFormat::U16 => input_stream::<u16>(&device, &config, sender),
+        SampleFormat::I32 => input_stream::<i32>(&device, &config, sender),
+        SampleFormat::U8 => input_stream::<u8>(&device, &config, sender),
+        format => Err(format!(
+            "The microphone's sample format {format} is not supported"
+        )),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start the microphone: {e}"))?;
+    Ok((stream, config.sample_rate.0))
+}
+
+/// Writes what the microphone sends to `path` until the recording stops.
+fn write_recording(
+    app: &AppHandle,
+    recording_id: &str,
+    path: &Path,
+    sample_rate: u32,
+    receiver: mpsc::Receiver<Message>,
+) -> Result<Captured, String> {
+    let write_error = |e: std::io::Error| format!("Failed to write the recording: {e}");
+    let mut file = BufWriter::new(std::fs::File::create(path).map_err(write_error)?);
+    // The sizes are filled in once they are known
+    file.write_all(&wav_header(sample_rate, 1, 0))
+        .map_err(write_error)?;
+    let max_frames = sample_rate as u64 * MAX_RECORDING_SECS;
+    let mut frames = 0u64;
+    for message in receiver {
+        match message {
+            Message::Samples(samples) => {
+                let room = max_frames - frames;
+                if room == 0 {
+                    continue;
+                }
+                let take = samples.len().min(room as usize);
+                for sample in &samples[..take] {
+                    file.write_all(&sample.to_le_bytes()).map_err(write_error)?;
+                }
+                frames += take as u64;
+                if frames == max_frames {
+                    log::info!("Audio recording {recording_id} reached its length limit");
+                    let _ = app.emit(
+                        "audio-recording-limit",
+                        RecordingLimit {
+                            recording_id: recording_id.to_string(),
+                        },
+                    );
+                }
+            }
+            Message::Failed(e) => return Err(format!("The microphone stopped: {e}")),
+            Message::Stop => break,
+        }
+    }
+    let mut file = file.into_inner().map_err(|e| write_error(e.into_error()))?;
+    file.seek(SeekFrom::Start(0)).map_err(write_error)?;
+    file.write_all(&wav_header(sample_rate, 1, (frames * 2) as u32))
+        .map_err(write_error)?;
+    Ok(Captured {
+        path: path.to_path_buf(),
+        sample_rate,
+        frames,
+    })
+}
+
+/// Recordings in progress by id; managed as app state.
+#[derive(Default)]
+pub struct AudioRecordings(Mutex<HashMap<String, Recording>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedAudio {
+    pub attachment: StoredAttachment,
+    pub duration_ms: u64,
+}
+
+/// Payload of the `audio-recording-limit` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingLimit {
+    pub recording_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum TranscriptionEngine {
+    Openai {
+        /// The API in full, or `provider_id` of a registered provider
+        #[serde(default)]
+        provider_config: Option<ProviderConfig>,
+        #[serde(default)]
+        provider_id: Option<String>,
+        /// `gpt-4o-mini-transcribe` by default
+        #[serde(default)]
+        model: Option<String>,
+        /// ISO 639-1 code; detected when left out
+        #[serde(default)]
+        language: Option<String>,
+    },
+    WhisperCpp {
+        /// A ggml model file, e.g. `ggml-base.en.bin`
+        model_path: String,
+        #[serde(default)]
+        language: Option<String>,
+    },
+}
+
+/// Payload of the `transcription-partial` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionPartial {
+    pub transcription_id: String,
+    /// What was added since the last event
+    pub delta: String,
+    /// The transcript so far
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcription {
+    pub transcription_id: String,
+    pub text: String,
+    pub duration_ms: u64,
+}
+
+/// Starts recording from the default microphone. Fails if there is none
+/// or it can't be opened, e.g. because microphone access was denied.
+#[tauri::command]
+pub async fn start_audio_recording(app: AppHandle, recording_id: String) -> Result<(), String> {
+    crate::validate_string_input(&recording_id, MAX_ID_LENGTH, "Recording id")?;
+    if recording_id.is_empty() {
+        return Err("Recording id is required".to_string());
+    }
+    let state = app.state::<AudioRecordings>();
+    let mut recordings = state.0.lock().map_err(|e| e.to_string())?;
+    if recordings.contains_key(&recording_id) {
+        return Err(format!("Recording {recording_id} is already running"));
+    }
+    if recordings.len() >= MAX_RECORDINGS {
+        return Err("Too many recordings are running".to_string());
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "nexus-recording-{}-{}.wav",
+        std::process::id(),
+        crate::time::now_ms()
+    ));
+    let (sender, receiver) = mpsc::channel();
+    let (started, started_rx) = mpsc::sync_channel(1);
+    let thread_app = app.clone();
+    let thread_id = recording_id.clone();
+    let stream_sender = sender.clone();
+    let thread = std::thread::Builder::new()
+        .name(format!("audio-recording-{recording_id}"))
+        .spawn(move || {
+            let (stream, sample_rate) = match open_microphone(stream_sender) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    let _ = started.send(Err(e.clone()));
+                    return Err(e);
+                }
+            };
+            let _ = started.send(Ok(sample_rate));
+            let result = write_recording(&thread_app, &thread_id, &path, sample_rate, receiver);
+            drop(stream);
+            if result.is_err() {
+                let _ = std::fs::remove_file(&path);
+            }
+            result
+        })
+        .map_err(|e| format!("Failed to start recording: {e}"))?;
+    let sample_rate = started_rx
+        .recv()
+        .map_err(|_| "The recording thread stopped".to_string())??;
+    recordings.insert(recording_id.clone(), Recording { sender, thread });
+    log::info!("Started audio recording {recording_id} ({sample_rate} Hz)");
+    Ok(())
+}
+
+/// Stops a recording's thread and waits for its file.
+async fn finish(app: &AppHandle, recording_id: &str) -> Result<Option<Captured>, String> {
+    let recording = app
+        .state::<AudioRecordings>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(recording_id);
+    let Some(recording) = recording else {
+        return Ok(None);
+    };
+    let _ = recording.sender.send(Message::Stop);
+    tauri::async_runtime::spawn_blocking(move || recording.thread.join())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "The recording thread panicked".to_string())?
+        .map(Some)
+}
+
+/// Ends a recording and stores it as a WAV attachment.
+#[tauri::command]
+pub async fn stop_audio_recording(
+    app: AppHandle,
+    recording_id: String,
+) -> Result<RecordedAudio, String> {
+    let captured = finish(&app, &recording_id)
+        .await?
+        .ok_or_else(|| format!("Recording {recording_id} is not running"))?;
+    let wav = std::fs::read(&captured.path);
+    let _ = std::fs::remove_file(&captured.path);
+    let wav = wav.map_err(|e| format!("Failed to read the recording: {e}"))?;
+    if captured.frames == 0 {
+        return Err("Nothing was recorded".to_string());
+    }
+    let duration_ms = captured.duration_ms();
+    let attachment = crate::attachments::store_bytes(&app, &wav, Some("audio/wav".to_string()))?;
+    log::info!(
+        "Stored audio recording {recording_id} ({duration_ms}ms) as attachment {}",
+        attachment.hash
+    );
+    Ok(RecordedAudio {
+        attachment,
+        duration_ms,
+    })
+}
+
+/// Discards a recording.
+#[tauri::command]
+pub async fn cancel_audio_recording(app: AppHandle, recording_id: String) -> Result<(), String> {
+    match finish(&app, &recording_id).await {
+        Ok(Some(captured)) => {
+            let _ = std::fs::remove_file(&captured.path);
+            log::info!("Cancelled audio recording {recording_id}");
+        }
+        Ok(None) => {}
+        // Its file is already gone
+        Err(e) => log::warn!("Cancelled audio recording {recording_id}, which failed: {e}"),
+    }
+    Ok(())
+}
+
+fn extension(mime_type: Option<&str>) -> &'static str {
+    match mime_type {
+        Some("audio/mpeg") | Some("audio/mp3") => "mp3",
+        Some("audio/mp4") | Some("audio/m4a") | Some("audio/x-m4a") => "m4a",
+        Some("audio/ogg") => "ogg",
+        Some("audio/webm") => "webm",
+        Some("audio/flac") => "flac",
+        _ => "wav",
+    }
+}
+
+fn validate_language(language: &Option<String>) -> Result<(), String> {
+    match language {
+        Some(language)
+            if language.len() > 8 || !language.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            Err(format!("Invalid language code: {language}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    transcription_id: &'a str,
+    text: String,
+}
+
+impl Progress<'_> {
+    fn push(&mut self, delta: &str) {
+        if delta.is_empty() {
+            return;
+        }
+        self.text.push_str(delta);
+        let event = TranscriptionPartial {
+            transcription_id: self.transcription_id.to_string(),
+            delta: delta.to_string(),
+            text: self.text.clone(),
+        };
+        if let Err(e) = self.app.emit("transcription-partial", event) {
+            log::warn!("Failed to emit transcription-partial event: {e}");
+        }
+    }
+}
+
+/// A multipart form body with the audio file and text fields.
+fn multipart(
+    boundary: &str,
+    audio: &[u8],
+    file_name: &str,
+    mime_type: &str,
+    fields: &[(&str, &str)],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(audio.len() + 1024);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: {mime_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(audio);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    body
+}
+
+async fn transcribe_openai(
+    progress: &mut Progress<'_>,
+    config: ProviderConfig,
+    model: String,
+    language: Option<String>,
+    audio: Vec<u8>,
+    mime_type: &str,
+) -> Result<(), String> {
+    let url = crate::chat_proxy::api_url(&config.base_url, "audio/transcriptions")?;
+    let mut headers = crate::chat_proxy::build_headers(&config)?;
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate boundary: {e}"))?;
+    let boundary: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    let boundary = format!("nexus-{boundary}");
+    let content_type = format!("multipart/form-data; boundary={boundary}");
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        content_type
+            .parse()
+            .map_err(|_| "Invalid content type".to_string())?,
+    );
+
+    let stream = model != NON_STREAMING_MODEL;
+    let mut fields = vec![("model", model.as_str()), ("response_format", "json")];
+    if stream {
+        fields.push(("stream", "true"));
+    }
+    if let Some(language) = language.as_deref() {
+        fields.push(("language", language));
+    }
+    let file_name = format!("audio.{}", extension(Some(mime_type)));
+    let body = multipart(&boundary, &audio, &file_name, mime_type, &fields);
+
+    let host = url.host_str().unwrap_or_default().to_string();
+    log::info!(
+        "Transcribing {} with {model} at {host}",
+        progress.transcription_id
+    );
+    let mut response = crate::llm::http_client()
+        .post(url)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "{host} returned {status}: {}",
+            crate::llm::error_message(&body)
+        ));
+    }
+
+    if stream {
+        let mut done = None;
+        crate::llm::read_sse(&mut response, |data| {
+            let Ok(event) = serde_json::from_str::<Value>(data) else {
+                return;
+            };
+            match event["type"].as_str() {
+                Some("transcript.text.delta") => {
+                    progress.push(event["delta"].as_str().unwrap_or_default())
+                }
+                Some("transcript.text.done") => {
+                    done = event["text"].as_str().map(String::from);
+                }
+                _ => {}
+            }
+        })
+        .await
+        .map_err(|e| format!("Transcription stream failed: {e}"))?;
+        // The final text is authoritative where the deltas fell short
+        if let Some(text) = done {
+            progress.text = text;
+        }
+    } else {
+        let value: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid transcription response: {e}"))?;
+        progress.push(value["text"].as_str().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// `WHISPER_CLI`, or `whisper-cli` on the login shell's PATH.
+fn find_whisper_cli() -> Result<PathBuf, String> {
+    if let Ok(path) = std::env::var("WHISPER_CLI") {
+        let path = PathBuf::from(path);
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!(
+                "WHISPER_CLI points to {}, which does not exist",
+                path.display()
+            ))
+        };
+    }
+    let binary = if cfg!(windows) {
+        "whisper-cli.exe"
+    } else {
+        "whisper-cli"
+    };
+    let path = crate::mcp::login_shell_path(&crate::mcp::login_shell());
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            "whisper-cli was not found; install whisper.cpp or set WHISPER_CLI to its path"
+                .to_string()
+        })
+}
+
+/// Runs `whisper-cli` over the audio, reporting every segment as it is
+/// printed.
+async fn transcribe_whisper_cpp(
+    progress: &mut Progress<'_>,
+    model_path: String,
+    language: Option<String>,
+    audio: Vec<u8>,
+    mime_type: Option<&str>,
+) -> Result<(), String> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    if !WHISPER_CPP_ENABLED {
+        return Err("Local transcription needs a build with the local-whisper feature".to_string());
+    }
+    crate::validate_string_input(&model_path, 4096, "Model path")?;
+    if !std::path::Path::new(&model_path).is_file() {
+        return Err(format!("Model file {model_path} does not exist"));
+    }
+    let binary = find_whisper_cli()?;
+    let input = std::env::temp_dir().join(format!(
+        "nexus-transcription-{}-{}.{}",
+        std::process::id(),
+        crate::time::now_ms(),
+        extension(mime_type)
+    ));
+    std::fs::write(&input, audio).map_err(|e| format!("Failed to write audio: {e}"))?;
+
+    log::info!(
+        "Transcribing {} with whisper.cpp",
+        progress.transcription_id
+    );
+    let (segments, mut segment_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let run = {
+        let input = input.clone();
+        tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
+            let mut command = Command::new(binary);
+            command
+                .arg("-m")
+                .arg(&model_path)
+                .arg("-f")
+                .arg(&input)
+                .arg("-l")
+                .arg(language.as_deref().unwrap_or("auto"))
+                .arg("--no-prints")
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            let mut child = command
+                .spawn()
+                .map_err(|e| format!("Failed to start whisper-cli: {e}"))?;
+            if let Some(stdout) = child.stdout.take() {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    // `[00:00:00.000 --> 00:00:02.000]  text`
+                    let text = match line.split_once(']') {
+                        Some((timestamps, text)) if timestamps.starts_with('[') => text,
+                        _ => line.as_str(),
+                    };
+                    let text = text.trim();
+                    if !text.is_empty() && segments.send(text.to_string()).is_err() {
+                        break;
+                    }
+                }
+            }
+            let output = child
+                .wait_with_output()
+                .map_err(|e| format!("whisper-cli failed: {e}"))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let reason = stderr.lines().last().unwrap_or_default().trim();
+                return Err(format!(
+                    "whisper-cli failed with {}: {reason}",
+                    output.status
+                ));
+            }
+            Ok(())
+        })
+    };
+
+    // Segments arrive until the process closes its output
+    while let Some(segment) = segment_rx.recv().await {
+        let delta = if progress.text.is_empty() {
+            segment
+        } else {
+            format!(" {segment}")
+        };
+        progress.push(&delta);
+    }
+    let result = run
+        .await
+        .map_err(|e| format!("Transcription task failed: {e}"))
+        .and_then(|result| result);
+    let _ = std::fs::remove_file(&input);
+    result
+}
+
+/// Transcribes an audio attachment, emitting `transcription-partial` as
+/// the transcript grows.
+#[tauri::command]
+pub async fn transcribe_audio(
+    app: AppHandle,
+    transcription_id: String,
+    attachment: String,
+    engine: TranscriptionEngine,
+) -> Result<Transcription, String> {
+    crate::validate_string_input(&transcription_id, MAX_ID_LENGTH, "Transcription id")?;
+    let stored = crate::attachments::read(&app, attachment.clone())?;
+    let mime_type = stored.mime_type.as_deref();
+    if mime_type.is_some_and(|mime_type| !mime_type.starts_with("audio/")) {
+        return Err(format!("Attachment {attachment} is not audio"));
+    }
+    let audio = base64::engine::general_purpose::STANDARD
+        .decode(stored.data.trim())
+        .map_err(|e| format!("Attachment {attachment} is not valid base64: {e}"))?;
+
+    let started = Instant::now();
+    let mut progress = Progress {
+        app: &app,
+        transcription_id: &transcription_id,
+        text: String::new(),
+    };
+    let result = match engine {
+        TranscriptionEngine::Openai {
+            provider_config,
+            provider_id,
+            model,
+            language,
+        } => {
+            validate_language(&language)?;
+            let config = match (provider_config, provider_id) {
+                (Some(config), None) => config,
+                (None, Some(id)) => crate::providers::config(&app, &id)?,
+                _ => {
+                    return Err("Pass either a provider configuration or a provider id".to_string())
+                }
+            };
+            let model = model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
+            crate::validate_string_input(&model, 200, "Model")?;
+            transcribe_openai(
+                &mut progress,
+                config,
+                model,
+                language,
+                audio,
+                mime_type.unwrap_or("audio/wav"),
+            )
+            .await
+        }
+        TranscriptionEngine::WhisperCpp {
+            model_path,
+            language,
+        } => {
+            validate_language(&language)?;
+            transcribe_whisper_cpp(&mut progress, model_path, language, audio, mime_type).await
+        }
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if let Err(e) = result {
+        log::error!("Transcription {transcription_id} failed: {e}");
+        return Err(e);
+    }
+    log::info!("Transcribed {transcription_id} in {duration_ms}ms");
+    let text = progress.text.trim().to_string();
+    Ok(Transcription {
+        transcription_id,
+        text,
+        duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downmixes_to_mono() {
+        assert_eq!(downmix(&[0.5f32, 0.5, -1.0, 1.0], 2), vec![16384, 0]);
+        assert_eq!(downmix(&[100i16, -100, 300], 1), vec![100, -100, 300]);
+        // A partial frame at the end is dropped
+        assert_eq!(downmix(&[i16::MIN, i16::MIN, 7], 2), vec![i16::MIN]);
+    }
+
+    #[test]
+    fn writes_wav_header() {
+        let header = wav_header(16_000, 1, 32_000);
+        assert_eq!(header.len(), WAV_HEADER_LEN);
+        assert_eq!(&header[..4], b"RIFF");
+        assert_eq!(header[4..8], (36 + 32_000u32).to_le_bytes());
+        assert_eq!(header[24..28], 16_000u32.to_le_bytes());
+        // Bytes per second
+        assert_eq!(header[28..32], 32_000u32.to_le_bytes());
+        assert_eq!(header[40..44], 32_000u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,502 @@
+// MCP Tool Audit Log
+// ==================
+//
+// Every `tools/call` request written to an MCP server is tracked until its
+// response arrives on stdout, then appended as a single JSON line to
+// `audit/mcp-tool-calls.jsonl` in the app data directory. The file is only
+// ever appended to, so users can review which tools touched their system
+// and when.
+//
+// Remote (HTTP/SSE) servers are reached from the webview, which reports
+// each exchange with `record_mcp_network_activity`: the destination host,
+// bytes sent and received, and the status. These go to
+// `audit/mcp-network.jsonl` the same way, without paths or query strings,
+// which can carry credentials. `get_network_activity` sums them per host,
+// so users can check which hosts a server's traffic actually went to.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, State};
+
+use crate::hash::canonical_json_hash;
+use crate::time::now_ms;
+
+const AUDIT_PAGE_SIZE: usize = 50;
+/// Largest byte count one exchange may report, to catch nonsense values
+const MAX_EXCHANGE_BYTES: u64 = 1 << 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpAuditOutcome {
+    /// The server returned a result
+    Success,
+    /// The server returned a result flagged with `isError`
+    ToolError,
+    /// The server answered with a JSON-RPC error
+    RpcError,
+    /// The server was stopped before it answered
+    ServerStopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpAuditEntry {
+    /// UNIX timestamp (milliseconds) at which the call was sent
+    #[serde(deserialize_with = "crate::time::legacy_ms")]
+    pub timestamp: u64,
+    pub server_id: String,
+    pub tool: String,
+    /// SHA-256 of the canonicalised call arguments
+    pub arguments_hash: String,
+    pub duration_ms: u64,
+    pub outcome: McpAuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpAuditFilter {
+    pub server_id: Option<String>,
+    pub tool: Option<String>,
+    pub outcome: Option<McpAuditOutcome>,
+    /// Inclusive lower bound on `timestamp`, in milliseconds
+    pub since: Option<u64>,
+    /// Exclusive upper bound on `timestamp`, in milliseconds
+    pub until: Option<u64>,
+}
+
+impl McpAuditFilter {
+    fn matches(&self, entry: &McpAuditEntry) -> bool {
+        self.server_id
+            .as_ref()
+            .is_none_or(|id| *id == entry.server_id)
+            && self.tool.as_ref().is_none_or(|tool| *tool == entry.tool)
+            && self.outcome.is_none_or(|outcome| outcome == entry.outcome)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkExchangeKind {
+    /// A JSON-RPC message POSTed to the server, with its response
+    Request,
+    /// Opening the server's SSE stream
+    SseConnect,
+    /// One event read from the SSE stream
+    SseEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkActivityEntry {
+    /// UNIX timestamp in milliseconds
+    pub timestamp_ms: u64,
+    pub server_id: String,
+    /// Host and, if not the default, port
+    pub host: String,
+    pub kind: NetworkExchangeKind,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// HTTP status, `None` if the exchange failed before one arrived
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostActivity {
+    pub host: String,
+    /// Requests and SSE connections; SSE events are not counted
+    pub requests: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkActivity {
+    pub server_id: String,
+    /// Most recently contacted first
+    pub hosts: Vec<HostActivity>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpAuditPage {
+    /// Matching entries, newest first
+    pub entries: Vec<McpAuditEntry>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: usize,
+}
+
+struct PendingToolCall {
+    tool: String,
+    arguments_hash: String,
+    timestamp: u64,
+    started: Instant,
+}
+
+/// The `tools/call` requests in data written to a server's stdin, by their
+/// serialized JSON-RPC id.
+fn tool_calls(data: &str) -> impl Iterator<Item = (String, Value)> + '_ {
+    data.lines().filter_map(|line| {
+        let message = serde_json::from_str::<Value>(line).ok()?;
+        if message.get("method").and_then(Value::as_str) != Some("tools/call") {
+            return None;
+        }
+        let id = message.get("id")?.to_string();
+        Some((id, message))
+    })
+}
+
+#[derive(Default)]
+pub struct McpAuditLog {
+    /// In-flight calls keyed by (server id, serialized JSON-RPC id)
+    pending: Mutex<HashMap<(String, String), PendingToolCall>>,
+    /// Serializes appends so concurrent servers never interleave lines
+    write_lock: Mutex<()>,
+}
+
+impl McpAuditLog {
+    /// Inspects data about to be written to a server's stdin and starts
+    /// tracking any `tools/call` requests it contains. Tracking starts before
+    /// the write so a fast response can't arrive ahead of it.
+    pub fn observe_request(&self, server_id: &str, data: &str) {
+        for (id, message) in tool_calls(data) {
+            let params = message.get("params");
+            let tool = params
+                .and_then(|p| p.get("name"))
+                .and_then(Value::as_str)
+                .unwrap_or("<unknown>")
+                .to_string();
+            let arguments = params
+                .and_then(|p| p.get("arguments"))
+                .cloned()
+                .unwrap_or(Value::Null);
+
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.insert(
+                    (server_id.to_string(), id),
+                    PendingToolCall {
+                        tool,
+                        arguments_hash: canonical_json_hash(&arguments),
+                        timestamp: now_ms(),
+                        started: Instant::now(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Stops tracking the requests in `data` after writing it failed.
+    pub fn forget_request(&self, server_id: &str, data: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for (id, _) in tool_calls(data) {
+                pending.remove(&(server_id.to_string(), id));
+            }
+        }
+    }
+
+    /// Inspects a line read from a server's stdout and records the outcome if
+    /// it answers a tracked tool call.
+    pub fn observe_response(&self, app: &AppHandle, server_id: &str, line: &str) {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            return;
+        };
+        let Some(id) = message.get("id") else {
+            return;
+        };
+
+        let call = match self.pending.lock() {
+            Ok(mut pending) => pending.remove(&(server_id.to_string(), id.to_string())),
+            Err(_) => None,
+        };
+        let Some(call) = call else {
+            return;
+        };
+
+        let (outcome, error) = if let Some(error) = message.get("error") {
+            let text = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("Unknown error")
+                .to_string();
+            (McpAuditOutcome::RpcError, Some(text))
+        } else if message
+            .get("result")
+            .and_then(|r| r.get("isError"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+        {
+            (McpAuditOutcome::ToolError, None)
+        } else {
+            (McpAuditOutcome::Success, None)
+        };
+
+        self.record(app, server_id, call, outcome, error);
+    }
+
+    /// Closes out every in-flight call for a server that is going away.
+    pub fn abandon_server(&self, app: &AppHandle, server_id: &str) {
+        let calls: Vec<PendingToolCall> = match self.pending.lock() {
+            Ok(mut pending) => {
+                let keys: Vec<_> = pending
+                    .keys()
+                    .filter(|(id, _)| id == server_id)
+                    .cloned()
+                    .collect();
+                keys.into_iter()
+                    .filter_map(|key| pending.remove(&key))
+                    .collect()
+            }
+            Err(_) => Vec::new(),
+        };
+
+        for call in calls {
+            self.record(app, server_id, call, McpAuditOutcome::ServerStopped, None);
+        }
+    }
+
+    /// Records a call made by a built-in tool that runs a process itself
+    /// rather than through an MCP server. `source` stands in for the server
+    /// id, e.g. `builtin:kubectl`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_builtin(
+        &self,
+        app: &AppHandle,
+        source: &str,
+        tool: &str,
+        arguments: &Value,
+        started: Instant,
+        outcome: McpAuditOutcome,
+        error: Option<String>,
+    ) {
+        let call = PendingToolCall {
+            tool: tool.to_string(),
+            arguments_hash: canonical_json_hash(arguments),
+            timestamp: now_ms().saturating_sub(started.elapsed().as_millis() as u64),
+            started,
+        };
+        self.record(app, source, call, outcome, error);
+    }
+
+    fn record(
+        &self,
+        app: &AppHandle,
+        server_id: &str,
+        call: PendingToolCall,
+        outcome: McpAuditOutcome,
+        error: Option<String>,
+    ) {
+        let entry = McpAuditEntry {
+            timestamp: call.timestamp,
+            server_id: server_id.to_string(),
+            tool: call.tool,
+            arguments_hash: call.arguments_hash,
+            duration_ms: call.started.elapsed().as_millis() as u64,
+            outcome,
+            error,
+        };
+
+        if let Err(e) = self.append(app, &entry) {
+            log::error!("Failed to write MCP audit entry: {e}");
+        }
+    }
+
+    fn append(&self, app: &AppHandle, entry: &McpAuditEntry) -> Result<(), String> {
+        self.append_line(&get_audit_log_path(app)?, entry)?;
+        log::debug!(
+            "Audited MCP tool call {} on {} ({:?})",
+            entry.tool,
+            entry.server_id,
+            entry.outcome
+        );
+        Ok(())
+    }
+
+    fn append_line(&self, path: &Path, entry: &impl Serialize) -> Result<(), String> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {e}"))?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open audit log: {e}"))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to append to audit log: {e}"))
+    }
+
+    /// Reads a log under the write lock, so a half-written line is never
+    /// seen. A missing log reads as empty.
+    fn read(&self, path: &Path) -> Result<String, String> {
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        std::fs::read_to_string(path).map_err(|e| {
+            log::error!("Failed to read audit log: {e}");
+            format!("Failed to read audit log: {e}")
+        })
+    }
+}
+
+fn audit_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let audit_dir = app_data_dir.join("audit");
+
+    // Ensure the audit directory exists
+    std::fs::create_dir_all(&audit_dir)
+        .map_err(|e| format!("Failed to create audit directory: {e}"))?;
+
+    Ok(audit_dir)
+}
+
+fn get_audit_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(audit_dir(app)?.join("mcp-tool-calls.jsonl"))
+}
+
+fn get_network_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(audit_dir(app)?.join("mcp-network.jsonl"))
+}
+
+/// Host and non-default port of `url`; nothing else of it is logged.
+fn destination(url: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {e}"))?;
+    let host = url.host_str().ok_or("URL has no host")?;
+    Ok(match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn query_mcp_audit_log(
+    app: AppHandle,
+    state: State<'_, McpAuditLog>,
+    filter: Option<McpAuditFilter>,
+    page: Option<usize>,
+) -> Result<McpAuditPage, String> {
+    let filter = filter.unwrap_or_default();
+    let page = page.unwrap_or(0);
+    let path = get_audit_log_path(&app)?;
+
+    let contents = state.read(&path)?;
+
+    let mut matching: Vec<McpAuditEntry> = contents
+        .lines()
+        .filter_map(|line| match serde_json::from_str::<McpAuditEntry>(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Skipping malformed audit entry: {e}");
+                None
+            }
+        })
+        .filter(|entry| filter.matches(entry))
+        .collect();
+
+    let total = matching.len();
+    matching.reverse();
+    let entries = matching
+        .into_iter()
+        .skip(page * AUDIT_PAGE_SIZE)
+        .take(AUDIT_PAGE_SIZE)
+        .collect();
+
+    Ok(McpAuditPage {
+        entries,
+        page,
+        page_size: AUDIT_PAGE_SIZE,
+        total,
+    })
+}
+
+/// Records one exchange with a remote MCP server, reported by the webview
+/// that made it.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn record_mcp_network_activity(
+    app: AppHandle,
+    state: State<'_, McpAuditLog>,
+    server_id: String,
+    url: String,
+    kind: NetworkExchangeKind,
+    bytes_sent: u64,
+    bytes_received: u64,
+    status: Option<u16>,
+) -> Result<(), String> {
+    crate::validate_filename(&server_id)?;
+    if bytes_sent > MAX_EXCHANGE_BYTES || bytes_received > MAX_EXCHANGE_BYTES {
+        return Err("Invalid byte count".to_string());
+    }
+    let entry = NetworkActivityEntry {
+        timestamp_ms: now_ms(),
+        server_id,
+        host: destination(&url)?,
+        kind,
+        bytes_sent,
+        bytes_received,
+        status,
+    };
+    state.append_line(&get_network_log_path(&app)?, &entry)
+}
+
+/// Per-host totals of a remote server's recorded traffic.
+#[tauri::command]
+pub async fn get_network_activity(
+    app: AppHandle,
+    state: State<'_, McpAuditLog>,
+    server_id: String,
+) -> Result<NetworkActivity, String> {
+    let contents = state.read(&get_network_log_path(&app)?)?;
+    let mut hosts: HashMap<String, HostActivity> = HashMap::new();
+    let entries = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<NetworkActivityEntry>(line).ok())
+        .filter(|entry| entry.server_id == server_id);
+    for entry in entries {
+        let host = hosts
+            .entry(entry.host.clone())
+            .or_insert_with(|| HostActivity {
+                host: entry.host,
+                requests: 0,
+                bytes_sent: 0,
+                bytes_received: 0,
+                first_seen_ms: entry.timestamp_ms,
+                last_seen_ms: entry.timestamp_ms,
+            });
+        if entry.kind != NetworkExchangeKind::SseEvent {
+            host.requests += 1;
+        }
+        host.bytes_sent += entry.bytes_sent;
+        host.bytes_received += entry.bytes_received;
+        host.first_seen_ms = host.first_seen_ms.min(entry.timestamp_ms);
+        host.last_seen_ms = host.last_seen_ms.max(entry.timestamp_ms);
+    }
+
+    let mut hosts: Vec<HostActivity> = hosts.into_values().collect();
+    hosts.sort_by_key(|h| std::cmp::Reverse(h.last_seen_ms));
+    Ok(NetworkActivity {
+        server_id,
+        bytes_sent: hosts.iter().map(|h| h.bytes_sent).sum(),
+        bytes_received: hosts.iter().map(|h| h.bytes_received).sum(),
+        hosts,
+    })
+}
@@ -0,0 +1,332 @@
+// Backup Restore
+// ==============
+//
+// Restores the JSON backups written by "Export Data" in the Advanced
+// preferences pane without wiping existing data. Conversations are merged by
+// id: identical copies are skipped, unknown ones are added, and conflicting
+// ones are imported next to the local copy under a bumped version id so
+// nothing is ever overwritten. A conversation an earlier merge already
+// imported under a bumped id counts as identical, so merging the same
+// backup twice adds nothing the second time.
+//
+// Attachments whose content is already stored, locally or earlier in the
+// backup, are imported as a reference to the stored blob instead of
+// another inline copy.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tauri::{AppHandle, Emitter};
+
+use crate::attachments::BLOB_REF_KEY;
+use crate::conversations::{self, Conversation};
+use crate::hash::{canonical_json_hash, sha256_hex};
+
+/// Largest backup file we are willing to parse
+const MAX_BACKUP_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BackupFile {
+    Export { conversations: Vec<Conversation> },
+    Bare(Vec<Conversation>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionBump {
+    pub original_id: String,
+    pub new_id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupMergeReport {
+    pub dry_run: bool,
+    /// Conversations that did not exist locally
+    pub added: Vec<String>,
+    /// Conversations identical to the local copy
+    pub skipped: Vec<String>,
+    /// Conversations that differ from the local copy and were imported alongside it
+    pub version_bumped: Vec<VersionBump>,
+    /// Attachments in the imported conversations
+    pub attachments_total: usize,
+    /// Attachments whose content already exists locally or earlier in the
+    /// backup, imported as a reference to it
+    pub attachments_deduplicated: usize,
+}
+
+fn conversation_hash(conversation: &Conversation) -> String {
+    serde_json::to_value(conversation)
+        .map(|v| canonical_json_hash(&v))
+        .unwrap_or_default()
+}
+
+/// Hashes of every inline attachment payload in a conversation.
+fn attachment_hashes(conversation: &Conversation) -> Vec<String> {
+    conversation
+        .messages
+        .iter()
+        .flat_map(|m| m.attachments())
+        .filter_map(|part| part.get("data").and_then(Value::as_str))
+        .map(|data| sha256_hex(data.as_bytes()))
+        .collect()
+}
+
+/// Replaces the inline payload of every attachment already in `known` with
+/// a reference to its blob, and adds the others to `known`. Returns how
+/// many attachments there are and how many were replaced.
+fn dedupe_attachments(
+    conversation: &mut Conversation,
+    known: &mut HashSet<String>,
+) -> (usize, usize) {
+    let (mut total, mut deduplicated) = (0, 0);
+    for message in &mut conversation.messages {
+        let Value::Array(parts) = &mut message.content else {
+            continue;
+        };
+        for part in parts {
+            if part.get("type").and_then(Value::as_str) == Some("text") {
+                continue;
+            }
+            let Some(object) = part.as_object_mut() else {
+                continue;
+            };
+            let Some(hash) = object
+                .get("data")
+                .and_then(Value::as_str)
+                .map(|data| sha256_hex(data.as_bytes()))
+            else {
+                continue;
+            };
+            total += 1;
+            if !known.insert(hash.clone()) {
+                deduplicated += 1;
+                object.remove("data");
+                object.insert(BLOB_REF_KEY.to_string(), Value::String(hash));
+            }
+        }
+    }
+    (total, deduplicated)
+}
+
+/// `conversation` as a merge imports it next to a different local copy.
+fn restored_copy(conversation: &Conversation, id: String) -> Conversation {
+    Conversation {
+        id,
+        title: format!("{} (restored)", conversation.title),
+        ..conversation.clone()
+    }
+}
+
+/// Whether `id` is one of the `<original>_v<n>` copies of `original`.
+fn is_version_of(id: &str, original: &str) -> bool {
+    id.strip_prefix(original)
+        .and_then(|rest| rest.strip_prefix("_v"))
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Whether `conversation` is stored already, under its own id or as a copy
+/// an earlier merge imported next to a different local conversation.
+/// `local_hashes` maps local conversation ids to `conversation_hash`.
+fn already_merged(conversation: &Conversation, local_hashes: &HashMap<String, String>) -> bool {
+    if local_hashes.get(&conversation.id) == Some(&conversation_hash(conversation)) {
+        return true;
+    }
+    local_hashes
+        .iter()
+        .filter(|(id, _)| is_version_of(id, &conversation.id))
+        .any(|(id, hash)| *hash == conversation_hash(&restored_copy(conversation, id.clone())))
+}
+
+/// Picks the first `<id>_v<n>` that is not taken yet.
+pub fn next_version_id(id: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{id}_v{n}"))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_else(|| format!("{id}_copy"))
+}
+
+fn read_backup(path: &str) -> Result<Vec<Conversation>, String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("Failed to read backup file: {e}"))?;
+    if metadata.len() > MAX_BACKUP_BYTES {
+        return Err("Backup file too large (max 512MB)".to_string());
+    }
+
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read backup file: {e}"))?;
+
+    match serde_json::from_str::<BackupFile>(&contents).map_err(|e| {
+        log::error!("Failed to parse backup: {e}");
+        format!("Not a Nexus backup file: {e}")
+    })? {
+        BackupFile::Export { conversations } | BackupFile::Bare(conversations) => Ok(conversations),
+    }
+}
+
+/// Merges a backup into the existing conversations. With `dry_run` set the
+/// report is computed without writing anything.
+#[tauri::command]
+pub async fn merge_backup(
+    app: AppHandle,
+    path: String,
+    dry_run: bool,
+) -> Result<BackupMergeReport, String> {
+    log::info!("Merging backup {path} (dry run: {dry_run})");
+
+    let incoming = read_backup(&path)?;
+    let existing = conversations::load_all(&app)?;
+    let local_hashes: HashMap<String, String> = existing
+        .iter()
+        .map(|c| (c.id.clone(), conversation_hash(c)))
+        .collect();
+
+    let mut taken: HashSet<String> = local_hashes.keys().cloned().collect();
+    let mut known_attachments: HashSet<String> =
+        existing.iter().flat_map(attachment_hashes).collect();
+    drop(existing);
+
+    let mut report = BackupMergeReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut imported: Vec<Conversation> = Vec::new();
+
+    for mut conversation in incoming {
+        if let Err(e) = crate::validate_filename(&conversation.id) {
+            log::warn!("Skipping backup conversation with invalid id: {e}");
+            continue;
+        }
+
+        if already_merged(&conversation, &local_hashes) {
+            report.skipped.push(conversation.id.clone());
+            continue;
+        }
+        match local_hashes.get(&conversation.id) {
+            Some(_) => {
+                let new_id = next_version_id(&conversation.id, &taken);
+                report.version_bumped.push(VersionBump {
+                    original_id: conversation.id.clone(),
+                    new_id: new_id.clone(),
+                });
+                conversation = restored_copy(&conversation, new_id);
+            }
+            // The same id may appear twice in a hand-edited backup
+            None if taken.contains(&conversation.id) => {
+                report.skipped.push(conversation.id.clone());
+                continue;
+            }
+            None => report.added.push(conversation.id.clone()),
+        }
+
+        let (attachments, deduplicated) =
+            dedupe_attachments(&mut conversation, &mut known_attachments);
+        report.attachments_total += attachments;
+        report.attachments_deduplicated += deduplicated;
+
+        taken.insert(conversation.id.clone());
+        imported.push(conversation);
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    for conversation in &imported {
+        conversations::save(&app, conversation)?;
+    }
+
+    // Let the frontend add the restored conversations to its store
+    if let Err(e) = app.emit("conversations-restored", &imported) {
+        log::error!("Failed to emit conversations-restored event: {e}");
+    }
+
+    log::info!(
+        "Backup merged: {} added, {} skipped, {} version-bumped",
+        report.added.len(),
+        report.skipped.len(),
+        report.version_bumped.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversations::ChatMessage;
+    use serde_json::json;
+
+    fn conversation(id: &str, content: Value) -> Conversation {
+        Conversation {
+            id: id.to_string(),
+            title: "Trip".to_string(),
+            messages: vec![ChatMessage {
+                id: "m1".to_string(),
+                role: "user".to_string(),
+                content,
+                timestamp: 1,
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
+                usage: None,
+            }],
+            model_id: "model".to_string(),
+            system_prompt: String::new(),
+            created_at: 1,
+            updated_at: 1,
+        }
+    }
+
+    fn hashes(conversations: &[Conversation]) -> HashMap<String, String> {
+        conversations
+            .iter()
+            .map(|c| (c.id.clone(), conversation_hash(c)))
+            .collect()
+    }
+
+    #[test]
+    fn picks_the_first_free_version_id() {
+        let taken: HashSet<String> = ["a_v2".to_string()].into();
+        assert_eq!(next_version_id("a", &taken), "a_v3");
+        assert_eq!(next_version_id("b", &taken), "b_v2");
+    }
+
+    #[test]
+    fn a_second_merge_finds_the_bumped_copy() {
+        let local = conversation("a", json!("Local"));
+        let incoming = conversation("a", json!("From the backup"));
+        assert!(!already_merged(
+            &incoming,
+            &hashes(std::slice::from_ref(&local))
+        ));
+
+        let bumped = restored_copy(&incoming, "a_v2".to_string());
+        assert!(already_merged(&incoming, &hashes(&[local, bumped])));
+    }
+
+    #[test]
+    fn only_exact_version_ids_count_as_copies() {
+        assert!(is_version_of("a_v2", "a"));
+        assert!(is_version_of("a_v12", "a"));
+        assert!(!is_version_of("a_v", "a"));
+        assert!(!is_version_of("a_vx", "a"));
+        assert!(!is_version_of("ab_v2", "a"));
+    }
+
+    #[test]
+    fn known_attachments_become_references() {
+        let image = json!({"type": "image", "data": "aGVsbG8="});
+        let mut first = conversation("a", json!([image.clone()]));
+        let mut second = conversation("b", json!([{"type": "text", "text": "Hi"}, image]));
+        let mut known = HashSet::new();
+
+        assert_eq!(dedupe_attachments(&mut first, &mut known), (1, 0));
+        assert_eq!(first.messages[0].content[0]["data"], "aGVsbG8=");
+
+        assert_eq!(dedupe_attachments(&mut second, &mut known), (1, 1));
+        let part = &second.messages[0].content[1];
+        assert!(part.get("data").is_none());
+        assert_eq!(part[BLOB_REF_KEY], sha256_hex(b"aGVsbG8="));
+    }
+}
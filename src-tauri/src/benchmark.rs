@@ -0,0 +1,283 @@
+// Provider Benchmarks
+// ===================
+//
+// Measures time-to-first-token, throughput and error rate for a set of
+// models by sending the same prompt to each. Models run concurrently while
+// the iterations for one model run back to back, so a model never competes
+// with itself for bandwidth. Every run is appended to a history file so
+// results can be compared across networks and times of day.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::llm::{self, LlmMessage, LlmRequest, ProviderId};
+use crate::middleware;
+use crate::operations::{self, OperationKind, ProgressUnit};
+
+const MAX_ITERATIONS: u32 = 20;
+const MAX_MODELS: usize = 16;
+/// Keeps benchmark responses short and comparable
+const BENCHMARK_MAX_TOKENS: u32 = 512;
+/// Rough characters per token, used when a provider does not report usage
+const CHARS_PER_TOKEN: f64 = 4.0;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchmarkModel {
+    pub provider: ProviderId,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkRun {
+    pub ttft_ms: Option<u64>,
+    pub total_ms: u64,
+    pub output_tokens: u64,
+    /// Token count was estimated from the response length
+    pub tokens_estimated: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub provider: ProviderId,
+    pub model: String,
+    pub runs: Vec<BenchmarkRun>,
+    /// Median time to first token over successful runs
+    pub median_ttft_ms: Option<u64>,
+    /// Mean output tokens per second over successful runs, measured after the first token
+    pub tokens_per_sec: Option<f64>,
+    /// Fraction of runs that failed, 0.0 to 1.0
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// UNIX timestamp (milliseconds)
+    #[serde(deserialize_with = "crate::time::legacy_ms")]
+    pub timestamp: u64,
+    pub prompt: String,
+    pub iterations: u32,
+    pub results: Vec<BenchmarkResult>,
+}
+
+fn get_benchmarks_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("provider-benchmarks.jsonl"))
+}
+
+fn append_history(app: &AppHandle, report: &BenchmarkReport) -> Result<(), String> {
+    let line =
+        serde_json::to_string(report).map_err(|e| format!("Failed to serialize report: {e}"))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_benchmarks_path(app)?)
+        .map_err(|e| format!("Failed to open benchmark history: {e}"))?;
+    writeln!(file, "{line}").map_err(|e| format!("Failed to write benchmark history: {e}"))
+}
+
+async fn run_once(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    model: &BenchmarkModel,
+    api_key: &str,
+    prompt: &str,
+) -> BenchmarkRun {
+    let request = LlmRequest {
+        provider: model.provider,
+        model: model.model.clone(),
+        api_key: api_key.to_string(),
+        system_prompt: None,
+        messages: vec![LlmMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        }],
+        max_tokens: Some(BENCHMARK_MAX_TOKENS),
+    };
+
+    // Cached answers would make every run after the first look instant
+    match middleware::stream_chat(app, client, &request, false, |_| {}).await {
+        Ok(outcome) => {
+            let (output_tokens, tokens_estimated) = match outcome.output_tokens {
+                Some(tokens) => (tokens, false),
+                None => (
+                    (outcome.text.chars().count() as f64 / CHARS_PER_TOKEN).ceil() as u64,
+                    true,
+                ),
+            };
+            BenchmarkRun {
+                ttft_ms: outcome.first_token.map(|d| d.as_millis() as u64),
+                total_ms: outcome.total.as_millis() as u64,
+                output_tokens,
+                tokens_estimated,
+                error: None,
+            }
+        }
+        Err(e) => BenchmarkRun {
+            ttft_ms: None,
+            total_ms: 0,
+            output_tokens: 0,
+            tokens_estimated: false,
+            error: Some(e),
+        },
+    }
+}
+
+fn summarize(model: &BenchmarkModel, runs: Vec<BenchmarkRun>) -> BenchmarkResult {
+    let ok: Vec<&BenchmarkRun> = runs.iter().filter(|r| r.error.is_none()).collect();
+
+    let mut ttfts: Vec<u64> = ok.iter().filter_map(|r| r.ttft_ms).collect();
+    ttfts.sort_unstable();
+    let median_ttft_ms = ttfts.get(ttfts.len() / 2).copied();
+
+    let rates: Vec<f64> = ok
+        .iter()
+        .filter_map(|r| {
+            let generating_ms = r.total_ms.saturating_sub(r.ttft_ms?);
+            (generating_ms > 0 && r.output_tokens > 0)
+                .then(|| r.output_tokens as f64 * 1000.0 / generating_ms as f64)
+        })
+        .collect();
+    let tokens_per_sec =
+        (!rates.is_empty()).then(|| rates.iter().sum::<f64>() / rates.len() as f64);
+
+    let error_rate = if runs.is_empty() {
+        0.0
+    } else {
+        (runs.len() - ok.len()) as f64 / runs.len() as f64
+    };
+
+    BenchmarkResult {
+        provider: model.provider,
+        model: model.model.clone(),
+        runs,
+        median_ttft_ms,
+        tokens_per_sec,
+        error_rate,
+    }
+}
+
+/// Benchmarks each model with the same prompt and records the results.
+/// Models whose provider has no API key stored are skipped.
+#[tauri::command]
+pub async fn benchmark_providers(
+    app: AppHandle,
+    models: Vec<BenchmarkModel>,
+    prompt: String,
+    iterations: u32,
+) -> Result<BenchmarkReport, String> {
+    crate::validate_string_input(&prompt, 10_000, "Prompt")?;
+    if models.is_empty() {
+        return Err("Select at least one model to benchmark".to_string());
+    }
+    if models.len() > MAX_MODELS {
+        return Err(format!("Too many models (max {MAX_MODELS})"));
+    }
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err(format!("Iterations must be between 1 and {MAX_ITERATIONS}"));
+    }
+
+    let mut keyed = Vec::new();
+    for model in models {
+        match crate::api_keys::require(model.provider) {
+            Ok(api_key) => keyed.push((model, api_key)),
+            Err(_) => log::info!("Skipping {}: no API key", model.model),
+        }
+    }
+    if keyed.is_empty() {
+        return Err("None of the selected models has an API key".to_string());
+    }
+    let models = keyed;
+
+    log::info!(
+        "Benchmarking {} models, {iterations} iterations each",
+        models.len()
+    );
+
+    let total = models.len() as u64;
+    let mut operation = operations::start(
+        &app,
+        OperationKind::Benchmark,
+        format!("{total} models, {iterations} iterations"),
+        ProgressUnit::Items,
+    );
+    let cancel = operation.cancel_flag();
+    let client = llm::http_client();
+    let handles: Vec<_> = models
+        .into_iter()
+        .map(|(model, api_key)| {
+            let app = app.clone();
+            let client = client.clone();
+            let prompt = prompt.clone();
+            let cancel = cancel.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut runs = Vec::new();
+                for _ in 0..iterations {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    runs.push(run_once(&app, &client, &model, &api_key, &prompt).await);
+                }
+                summarize(&model, runs)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => return operation.finish(Err(format!("Benchmark task failed: {e}"))),
+        }
+        operation.progress(results.len() as u64, Some(total), None);
+    }
+    // Partial results are not worth keeping in the history
+    if operation.is_cancelled() {
+        return operation.finish(Err(operations::CANCELLED.to_string()));
+    }
+
+    let report = BenchmarkReport {
+        timestamp: crate::time::now_ms(),
+        prompt,
+        iterations,
+        results,
+    };
+
+    if let Err(e) = append_history(&app, &report) {
+        log::error!("Failed to save benchmark results: {e}");
+    }
+
+    operation.finish(Ok(report))
+}
+
+/// Past benchmark reports, newest first.
+#[tauri::command]
+pub async fn get_benchmark_history(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<BenchmarkReport>, String> {
+    let path = get_benchmarks_path(&app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file =
+        std::fs::File::open(&path).map_err(|e| format!("Failed to open benchmark history: {e}"))?;
+    let mut reports: Vec<BenchmarkReport> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+
+    reports.reverse();
+    reports.truncate(limit.unwrap_or(50));
+    Ok(reports)
+}
@@ -0,0 +1,284 @@
+// Budgets
+// =======
+//
+// Monthly spending limits per provider. Every model request the backend
+// sends, for its own features or proxied for the chat, is costed when it
+// ends from its token usage and the price table in `usage`, and the cost is
+// added to the provider's spend for the calendar month (UTC). Crossing 80%
+// and then 100% of a limit emits `llm-budget-warning`, once each per month.
+// With `hard_stop` set, a provider over its limit refuses new requests until
+// the month rolls over or the limit is raised.
+//
+// Providers are named by the built-in ids (`openai`, `groq`, ...), by the
+// ids of registered providers, or for ad-hoc proxy configurations by the
+// API host. Local servers and models without a known price cost nothing.
+// Limits and the month's spend are kept in `budgets.json` in the app data
+// directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAX_PROVIDER_CHARS: usize = 200;
+/// Percentages of a limit at which `llm-budget-warning` is emitted
+const WARNING_PERCENTS: &[u8] = &[80, 100];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetLimit {
+    /// US dollars per calendar month
+    pub usd_limit: f64,
+    /// Refuse requests once the limit is reached, rather than only warn
+    #[serde(default)]
+    pub hard_stop: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProviderSpend {
+    /// `YYYY-MM`, UTC
+    month: String,
+    cost: f64,
+    requests: u64,
+    /// Highest of `WARNING_PERCENTS` already warned about this month
+    warned_percent: u8,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BudgetFile {
+    limits: HashMap<String, BudgetLimit>,
+    spend: HashMap<String, ProviderSpend>,
+}
+
+/// Serializes updates to `budgets.json`, managed as app state.
+#[derive(Default)]
+pub struct Budgets {
+    lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub provider: String,
+    /// `None` without a limit
+    pub usd_limit: Option<f64>,
+    pub hard_stop: bool,
+    /// `YYYY-MM`, UTC
+    pub month: String,
+    /// Estimated, in US dollars
+    pub spent_usd: f64,
+    pub requests: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmBudgetWarningEvent {
+    pub provider: String,
+    pub month: String,
+    pub spent_usd: f64,
+    pub usd_limit: f64,
+    /// The share of the limit crossed: 80 or 100
+    pub percent: u8,
+    /// Requests to the provider are refused from now on
+    pub hard_stop: bool,
+}
+
+fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+fn get_budgets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::profiles::data_dir(app)?.join("budgets.json"))
+}
+
+fn load(app: &AppHandle) -> Result<BudgetFile, String> {
+    let path = get_budgets_path(app)?;
+    if !path.exists() {
+        return Ok(BudgetFile::default());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read budgets: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse budgets: {e}");
+        format!("Failed to parse budgets: {e}")
+    })
+}
+
+fn store(app: &AppHandle, file: &BudgetFile) -> Result<(), String> {
+    let json =
+        serde_json::to_vec_pretty(file).map_err(|e| format!("Failed to serialize budgets: {e}"))?;
+    crate::export::write_atomic(&get_budgets_path(app)?, &json)
+}
+
+/// The provider's spend this month; last month's is dropped.
+fn spend_this_month<'a>(file: &'a mut BudgetFile, provider: &str) -> &'a mut ProviderSpend {
+    let month = current_month();
+    let spend = file.spend.entry(provider.to_string()).or_default();
+    if spend.month != month {
+        *spend = ProviderSpend {
+            month,
+            ..ProviderSpend::default()
+        };
+    }
+    spend
+}
+
+fn status(file: &mut BudgetFile, provider: &str) -> BudgetStatus {
+    let limit = file.limits.get(provider).cloned();
+    let spend = spend_this_month(file, provider);
+    BudgetStatus {
+        provider: provider.to_string(),
+        usd_limit: limit.as_ref().map(|l| l.usd_limit),
+        hard_stop: limit.is_some_and(|l| l.hard_stop),
+        month: spend.month.clone(),
+        spent_usd: spend.cost,
+        requests: spend.requests,
+    }
+}
+
+/// Fails if `provider` has used up a budget with a hard stop.
+pub fn check(app: &AppHandle, provider: &str) -> Result<(), String> {
+    let state = app.state::<Budgets>();
+    let _guard = state.lock.lock().map_err(|e| e.to_string())?;
+    let mut file = load(app)?;
+    let status = status(&mut file, provider);
+    match status.usd_limit {
+        Some(limit) if status.hard_stop && status.spent_usd >= limit => Err(format!(
+            "The monthly budget of ${limit:.2} for {provider} is used up (${:.2} spent)",
+            status.spent_usd
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Adds the cost of a finished request to the provider's spend, warning
+/// when it crosses a share of the budget. Returns the cost, `None` if the
+/// model has no known price.
+pub fn record(
+    app: &AppHandle,
+    provider: &str,
+    model: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+) -> Option<f64> {
+    let cost = crate::usage::request_cost(model, input_tokens, output_tokens)?;
+    if let Err(e) = add(app, provider, cost) {
+        log::warn!("Failed to record spend for {provider}: {e}");
+    }
+    Some(cost)
+}
+
+fn add(app: &AppHandle, provider: &str, cost: f64) -> Result<(), String> {
+    let state = app.state::<Budgets>();
+    let _guard = state.lock.lock().map_err(|e| e.to_string())?;
+    let mut file = load(app)?;
+    let limit = file.limits.get(provider).cloned();
+    let spend = spend_this_month(&mut file, provider);
+    spend.cost += cost;
+    spend.requests += 1;
+
+    let warning = limit.and_then(|limit| {
+        let used = spend.cost / limit.usd_limit * 100.0;
+        let percent = WARNING_PERCENTS
+            .iter()
+            .copied()
+            .filter(|p| used >= f64::from(*p) && *p > spend.warned_percent)
+            .max()?;
+        spend.warned_percent = percent;
+        Some(LlmBudgetWarningEvent {
+            provider: provider.to_string(),
+            month: spend.month.clone(),
+            spent_usd: spend.cost,
+            usd_limit: limit.usd_limit,
+            percent,
+            hard_stop: limit.hard_stop && percent >= 100,
+        })
+    });
+    store(app, &file)?;
+
+    if let Some(warning) = warning {
+        log::warn!(
+            "{provider} has used {}% of its ${:.2} monthly budget",
+            warning.percent,
+            warning.usd_limit
+        );
+        if let Err(e) = app.emit("llm-budget-warning", &warning) {
+            log::warn!("Failed to emit llm-budget-warning: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Sets the monthly budget for `provider`, in US dollars, or removes it
+/// with `usd_limit` null. With `hard_stop`, requests are refused once it
+/// is used up; otherwise the budget only warns.
+#[tauri::command]
+pub async fn set_budget(
+    app: AppHandle,
+    provider: String,
+    usd_limit: Option<f64>,
+    hard_stop: Option<bool>,
+) -> Result<BudgetStatus, String> {
+    crate::validate_string_input(&provider, MAX_PROVIDER_CHARS, "provider")?;
+    if provider.trim().is_empty() {
+        return Err("Provider must not be empty".to_string());
+    }
+    if let Some(limit) = usd_limit {
+        if !limit.is_finite() || limit <= 0.0 {
+            return Err("Budget must be a positive amount".to_string());
+        }
+    }
+
+    let state = app.state::<Budgets>();
+    let _guard = state.lock.lock().map_err(|e| e.to_string())?;
+    let mut file = load(&app)?;
+    match usd_limit {
+        Some(usd_limit) => {
+            file.limits.insert(
+                provider.clone(),
+                BudgetLimit {
+                    usd_limit,
+                    hard_stop: hard_stop.unwrap_or(false),
+                },
+            );
+        }
+        None => {
+            file.limits.remove(&provider);
+        }
+    }
+    // Warn again against the new limit
+    spend_this_month(&mut file, &provider).warned_percent = 0;
+    store(&app, &file)?;
+    log::info!("Set monthly budget for {provider} to {usd_limit:?}");
+    Ok(status(&mut file, &provider))
+}
+
+/// Budgets and this month's spend of every provider with a budget or with
+/// spend this month, sorted by provider.
+#[tauri::command]
+pub async fn get_budgets(app: AppHandle) -> Result<Vec<BudgetStatus>, String> {
+    let state = app.state::<Budgets>();
+    let _guard = state.lock.lock().map_err(|e| e.to_string())?;
+    let mut file = load(&app)?;
+    let month = current_month();
+    let mut providers: Vec<String> = file
+        .limits
+        .keys()
+        .chain(
+            file.spend
+                .iter()
+                .filter(|(_, spend)| spend.month == month)
+                .map(|(provider, _)| provider),
+        )
+        .cloned()
+        .collect();
+    providers.sort();
+    providers.dedup();
+    Ok(providers
+        .iter()
+        .map(|provider| status(&mut file, provider))
+        .collect())
+}
@@ -0,0 +1,642 @@
+// Conversation Bundles
+// ====================
+//
+// A `.nexus` file carries conversations from one Nexus to another. It is a
+// zip archive with `manifest.json` listing what it holds, and one
+// `conversations/<id>.json` per conversation with its attachments inline:
+//
+//     {
+//       "format": "nexus-bundle",
+//       "version": 1,
+//       "exportedAt": 1767225600000,
+//       "appVersion": "0.1.0",
+//       "conversations": [
+//         { "id": "...", "title": "...", "messageCount": 12,
+//           "file": "conversations/....json" }
+//       ]
+//     }
+//
+// `export_bundle` writes one; `import_bundle` merges one into the store
+// like a backup (see `backup`): identical conversations are skipped, and
+// one that differs from a local conversation with its id is imported next
+// to it under a new id.
+//
+// The installers associate `.nexus` files with the app, so opening one
+// launches or focuses it and offers to import the bundle. On Windows and
+// Linux the file arrives as an argument, which a launch hands to a running
+// daemon (see `daemon`); macOS delivers it as an `Opened` event. Nothing
+// is imported until the user confirms: the bundle's manifest is emitted as
+// `bundle-opened`, or kept for the window being opened until it asks for
+// it with `take_pending_bundle`, and the window calls `import_bundle`.
+//
+// A bundle may come from anywhere, so reading one is bounded: by its size
+// on disk, by how far each entry and all of them together may inflate,
+// and by the number of conversations. The manifest may only point at
+// `conversations/<id>.json` entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::conversations::Conversation;
+use crate::hash::canonical_json_hash;
+use crate::time::now_ms;
+
+pub const EXTENSION: &str = "nexus";
+const FORMAT: &str = "nexus-bundle";
+const VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const MAX_CONVERSATIONS: usize = 10_000;
+
+/// How much of a bundle is read before giving up on it.
+struct Limits {
+    /// Of the file on disk
+    bundle: u64,
+    manifest: u64,
+    /// Of one conversation's JSON, attachments included
+    conversation: u64,
+    /// Of every conversation's JSON together, all of which is held in memory
+    total: u64,
+    conversations: usize,
+}
+
+const LIMITS: Limits = Limits {
+    bundle: 1024 * 1024 * 1024,
+    manifest: 16 * 1024 * 1024,
+    conversation: 256 * 1024 * 1024,
+    total: 1024 * 1024 * 1024,
+    conversations: MAX_CONVERSATIONS,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleEntry {
+    pub id: String,
+    pub title: String,
+    pub message_count: usize,
+    /// Path of the conversation's JSON in the archive
+    pub file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    /// Milliseconds since the UNIX epoch
+    pub exported_at: u64,
+    pub app_version: String,
+    pub conversations: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedConversation {
+    /// The id in the bundle, taken by a different local conversation
+    pub original_id: String,
+    pub new_id: String,
+}
+
+/// Payload of the `bundle-imported` event.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportReport {
+    /// Name of the bundle file
+    pub bundle: String,
+    /// Ids of the conversations added, renamed ones under their new id
+    pub imported: Vec<String>,
+    /// Identical to a local conversation
+    pub skipped: Vec<String>,
+    pub renamed: Vec<RenamedConversation>,
+}
+
+/// Payload of the `bundle-opened` event: a bundle waiting for the user to
+/// confirm its import.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedBundle {
+    /// To pass to `import_bundle`
+    pub path: String,
+    /// Name of the bundle file
+    pub bundle: String,
+    pub manifest: BundleManifest,
+}
+
+/// A bundle opened before the window could take it; managed as app state.
+#[derive(Default)]
+pub struct PendingBundle(Mutex<Option<OpenedBundle>>);
+
+fn conversation_hash(conversation: &Conversation) -> String {
+    serde_json::to_value(conversation)
+        .map(|value| canonical_json_hash(&value))
+        .unwrap_or_default()
+}
+
+fn conversation_file(id: &str) -> String {
+    format!("conversations/{id}.json")
+}
+
+/// Reads an archive entry, refusing to inflate it beyond `limit` bytes.
+fn read_entry<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    limit: u64,
+) -> Result<Vec<u8>, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|e| format!("The bundle has no {name}: {e}"))?;
+    let mut data = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read {name} from the bundle: {e}"))?;
+    if data.len() as u64 > limit {
+        return Err(format!("{name} in the bundle is too large"));
+    }
+    Ok(data)
+}
+
+/// Writes the conversations with the given ids into a bundle at `path`.
+fn write_bundle(app: &AppHandle, ids: &[String], path: &Path) -> Result<BundleManifest, String> {
+    let storage = crate::persistence::current(app);
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let mut archive = zip::ZipWriter::new(&mut buffer);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut manifest = BundleManifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: now_ms(),
+        app_version: app.package_info().version.to_string(),
+        conversations: Vec::new(),
+    };
+
+    for id in ids {
+        let Some(mut conversation) = storage.load(app, id)? else {
+            return Err(format!("Conversation {id} not found"));
+        };
+        // Blob references mean nothing on another machine
+        crate::attachments::rehydrate(app, &mut conversation);
+        let json = serde_json::to_vec(&conversation)
+            .map_err(|e| format!("Failed to serialize conversation: {e}"))?;
+        let file = conversation_file(id);
+        archive
+            .start_file(file.as_str(), options)
+            .and_then(|_| std::io::Write::write_all(&mut archive, &json).map_err(Into::into))
+            .map_err(|e| format!("Failed to write bundle: {e}"))?;
+        manifest.conversations.push(BundleEntry {
+            id: conversation.id,
+            title: conversation.title,
+            message_count: conversation.messages.len(),
+            file,
+        });
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize bundle manifest: {e}"))?;
+    archive
+        .start_file(MANIFEST_FILE, options)
+        .and_then(|_| std::io::Write::write_all(&mut archive, &json).map_err(Into::into))
+        .and_then(|_| archive.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to write bundle: {e}"))?;
+    crate::export::write_atomic(path, buffer.get_ref())?;
+    Ok(manifest)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+type BundleArchive = zip::ZipArchive<std::io::BufReader<std::fs::File>>;
+
+/// Opens a bundle and reads its manifest.
+fn read_manifest(path: &Path, limits: &Limits) -> Result<(BundleArchive, BundleManifest), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open bundle: {e}"))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read bundle: {e}"))?
+        .len();
+    if size > limits.bundle {
+        return Err(format!(
+            "Bundle too large (max {}MB)",
+            limits.bundle / 1024 / 1024
+        ));
+    }
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Not a Nexus bundle: {e}"))?;
+
+    let manifest: BundleManifest =
+        serde_json::from_slice(&read_entry(&mut archive, MANIFEST_FILE, limits.manifest)?)
+            .map_err(|e| format!("Not a Nexus bundle: {e}"))?;
+    if manifest.format != FORMAT {
+        return Err("Not a Nexus bundle".to_string());
+    }
+    if manifest.version > VERSION {
+        return Err("The bundle was made by a newer version of Nexus".to_string());
+    }
+    if manifest.conversations.len() > limits.conversations {
+        return Err(format!(
+            "Too many conversations in the bundle (max {})",
+            limits.conversations
+        ));
+    }
+    let mut files = HashSet::new();
+    for entry in &manifest.conversations {
+        // Only ever `conversations/<id>.json`, whatever the manifest claims
+        let valid = entry
+            .file
+            .strip_prefix("conversations/")
+            .and_then(|name| name.strip_suffix(".json"))
+            .is_some_and(|name| crate::validate_filename(name).is_ok());
+        if !valid {
+            return Err(format!("The bundle lists an invalid file: {}", entry.file));
+        }
+        if !files.insert(entry.file.as_str()) {
+            return Err(format!("The bundle lists {} more than once", entry.file));
+        }
+    }
+    Ok((archive, manifest))
+}
+
+/// Reads a bundle's manifest and conversations.
+fn read_bundle(
+    path: &Path,
+    limits: &Limits,
+) -> Result<(BundleManifest, Vec<Conversation>), String> {
+    let (mut archive, manifest) = read_manifest(path, limits)?;
+    let mut remaining = limits.total;
+    let mut conversations = Vec::with_capacity(manifest.conversations.len());
+    for entry in &manifest.conversations {
+        let json = read_entry(&mut archive, &entry.file, limits.conversation)?;
+        remaining = remaining.checked_sub(json.len() as u64).ok_or_else(|| {
+            format!(
+                "The bundle's conversations are too large together (max {}MB)",
+                limits.total / 1024 / 1024
+            )
+        })?;
+        let conversation: Conversation = serde_json::from_slice(&json)
+            .map_err(|e| format!("Failed to parse {} in the bundle: {e}", entry.file))?;
+        crate::validate_filename(&conversation.id)
+            .map_err(|e| format!("Invalid conversation id in the bundle: {e}"))?;
+        conversations.push(conversation);
+    }
+    Ok((manifest, conversations))
+}
+
+/// Merges a bundle into the store.
+fn import(app: &AppHandle, path: &Path) -> Result<BundleImportReport, String> {
+    let (manifest, conversations) = read_bundle(path, &LIMITS)?;
+    log::info!(
+        "Importing bundle {} with {} conversations, exported by Nexus {}",
+        path.display(),
+        conversations.len(),
+        manifest.app_version
+    );
+
+    let storage = crate::persistence::current(app);
+    let mut taken: HashSet<String> = storage.ids(app)?.into_iter().collect();
+    let mut report = BundleImportReport {
+        bundle: file_name(path),
+        ..Default::default()
+    };
+    for mut conversation in conversations {
+        if taken.contains(&conversation.id) {
+            let local = storage.load(app, &conversation.id)?.map(|mut local| {
+                crate::attachments::rehydrate(app, &mut local);
+                local
+            });
+            if local
+                .is_some_and(|local| conversation_hash(&local) == conversation_hash(&conversation))
+            {
+                report.skipped.push(conversation.id);
+                continue;
+            }
+            let new_id = crate::backup::next_version_id(&conversation.id, &taken);
+            report.renamed.push(RenamedConversation {
+                original_id: conversation.id.clone(),
+                new_id: new_id.clone(),
+            });
+            conversation.id = new_id;
+        }
+        storage.create(app, &conversation)?;
+        taken.insert(conversation.id.clone());
+        report.imported.push(conversation.id);
+    }
+
+    log::info!(
+        "Bundle imported: {} added, {} skipped, {} renamed",
+        report.imported.len(),
+        report.skipped.len(),
+        report.renamed.len()
+    );
+    Ok(report)
+}
+
+/// The bundle the app was launched with, if any, as an absolute path a
+/// daemon can open from its own working directory.
+pub fn from_args() -> Option<PathBuf> {
+    std::env::args_os()
+        .skip(1)
+        .map(PathBuf::from)
+        .find(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == EXTENSION)
+                && path.is_file()
+        })
+        .and_then(|path| std::fs::canonicalize(path).ok())
+}
+
+/// Reads the manifest of a bundle that was opened, for the user to confirm
+/// its import.
+fn opened(path: &Path) -> Result<OpenedBundle, String> {
+    let (_, manifest) = read_manifest(path, &LIMITS)?;
+    Ok(OpenedBundle {
+        path: path.to_string_lossy().into_owned(),
+        bundle: file_name(path),
+        manifest,
+    })
+}
+
+/// Shows the window and offers it a bundle that was opened, or keeps the
+/// bundle for the window being opened.
+pub fn open(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let opened = opened(path).inspect_err(|e| log::error!("Failed to open bundle: {e}"))?;
+    let window_open = app.get_webview_window("main").is_some();
+    if !window_open {
+        // The new window picks it up once it has loaded
+        let pending = app.state::<PendingBundle>();
+        *pending.0.lock().map_err(|e| e.to_string())? = Some(opened.clone());
+    }
+    crate::daemon::show_main_window(app)?;
+    if window_open {
+        app.emit("bundle-opened", &opened)
+            .map_err(|e| format!("Failed to emit bundle-opened event: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Called at startup: keeps the bundle this launch was opened with for the
+/// window.
+pub fn start(app: &AppHandle) {
+    let Some(path) = from_args() else {
+        return;
+    };
+    match opened(&path) {
+        Ok(opened) => {
+            if let Ok(mut pending) = app.state::<PendingBundle>().0.lock() {
+                *pending = Some(opened);
+            }
+        }
+        Err(e) => log::error!("Failed to open bundle: {e}"),
+    }
+}
+
+/// Writes the given conversations to a `.nexus` bundle at `path`.
+#[tauri::command]
+pub async fn export_bundle(
+    app: AppHandle,
+    conversation_ids: Vec<String>,
+    path: String,
+) -> Result<BundleManifest, String> {
+    if conversation_ids.is_empty() {
+        return Err("Select at least one conversation to export".to_string());
+    }
+    if conversation_ids.len() > MAX_CONVERSATIONS {
+        return Err(format!(
+            "Too many conversations to export (max {MAX_CONVERSATIONS})"
+        ));
+    }
+    for id in &conversation_ids {
+        crate::validate_filename(id)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = write_bundle(&app, &conversation_ids, Path::new(&path))?;
+        log::info!(
+            "Exported {} conversations to bundle {path}",
+            manifest.conversations.len()
+        );
+        Ok(manifest)
+    })
+    .await
+    .map_err(|e| format!("Bundle export task failed: {e}"))?
+}
+
+/// Imports the conversations of a `.nexus` bundle and emits
+/// `bundle-imported`.
+#[tauri::command]
+pub async fn import_bundle(app: AppHandle, path: String) -> Result<BundleImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let report = import(&app, Path::new(&path))?;
+        if let Err(e) = app.emit("bundle-imported", &report) {
+            log::error!("Failed to emit bundle-imported event: {e}");
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Bundle import task failed: {e}"))?
+}
+
+/// Returns the bundle the app was opened with, once.
+#[tauri::command]
+pub async fn take_pending_bundle(app: AppHandle) -> Result<Option<OpenedBundle>, String> {
+    let pending = app.state::<PendingBundle>();
+    let opened = pending.0.lock().map_err(|e| e.to_string())?.take();
+    Ok(opened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_LIMITS: Limits = Limits {
+        bundle: 64 * 1024,
+        manifest: 16 * 1024,
+        conversation: 1024,
+        total: 2048,
+        conversations: 3,
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nexus-bundle-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(format!("test.{EXTENSION}"))
+    }
+
+    fn conversation(id: &str, padding: usize) -> Vec<u8> {
+        serde_json::to_vec(&Conversation {
+            id: id.to_string(),
+            title: "x".repeat(padding),
+            messages: Vec::new(),
+            model_id: "model".to_string(),
+            system_prompt: String::new(),
+            created_at: 0,
+            updated_at: 0,
+        })
+        .unwrap()
+    }
+
+    fn manifest(files: &[&str]) -> Vec<u8> {
+        serde_json::to_vec(&BundleManifest {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported_at: 0,
+            app_version: "0.1.0".to_string(),
+            conversations: files
+                .iter()
+                .map(|file| BundleEntry {
+                    id: "c".to_string(),
+                    title: "c".to_string(),
+                    message_count: 0,
+                    file: file.to_string(),
+                })
+                .collect(),
+        })
+        .unwrap()
+    }
+
+    /// Writes a bundle holding `entries` as they are, consistent or not.
+    fn write(name: &str, entries: &[(&str, Vec<u8>)]) -> PathBuf {
+        let path = temp_path(name);
+        let mut archive = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        for (file, data) in entries {
+            archive
+                .start_file(*file, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut archive, data).unwrap();
+        }
+        archive.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_the_conversations_a_manifest_lists() {
+        let path = write(
+            "valid",
+            &[
+                (
+                    MANIFEST_FILE,
+                    manifest(&["conversations/a.json", "conversations/b.json"]),
+                ),
+                ("conversations/a.json", conversation("a", 0)),
+                ("conversations/b.json", conversation("b", 0)),
+            ],
+        );
+        let (manifest, conversations) = read_bundle(&path, &TEST_LIMITS).unwrap();
+        assert_eq!(manifest.conversations.len(), 2);
+        let ids: Vec<_> = conversations.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[test]
+    fn refuses_a_conversation_over_its_limit() {
+        let path = write(
+            "entry",
+            &[
+                (MANIFEST_FILE, manifest(&["conversations/a.json"])),
+                ("conversations/a.json", conversation("a", 2000)),
+            ],
+        );
+        let err = read_bundle(&path, &TEST_LIMITS).unwrap_err();
+        assert_eq!(err, "conversations/a.json in the bundle is too large");
+    }
+
+    #[test]
+    fn refuses_conversations_over_the_total_limit() {
+        // Each fits on its own, three of them do not
+        let path = write(
+            "total",
+            &[
+                (
+                    MANIFEST_FILE,
+                    manifest(&[
+                        "conversations/a.json",
+                        "conversations/b.json",
+                        "conversations/c.json",
+                    ]),
+                ),
+                ("conversations/a.json", conversation("a", 800)),
+                ("conversations/b.json", conversation("b", 800)),
+                ("conversations/c.json", conversation("c", 800)),
+            ],
+        );
+        let err = read_bundle(&path, &TEST_LIMITS).unwrap_err();
+        assert!(err.contains("too large together"), "{err}");
+    }
+
+    #[test]
+    fn refuses_a_bundle_over_its_limit_before_opening_it() {
+        let path = temp_path("size");
+        let file = std::fs::File::create(&path).unwrap();
+        file.set_len(TEST_LIMITS.bundle + 1).unwrap();
+        let err = read_manifest(&path, &TEST_LIMITS).err().unwrap();
+        assert!(err.starts_with("Bundle too large"), "{err}");
+    }
+
+    #[test]
+    fn refuses_too_many_conversations() {
+        let files = [
+            "conversations/a.json",
+            "conversations/b.json",
+            "conversations/c.json",
+            "conversations/d.json",
+        ];
+        let path = write("count", &[(MANIFEST_FILE, manifest(&files))]);
+        let err = read_manifest(&path, &TEST_LIMITS).err().unwrap();
+        assert_eq!(err, "Too many conversations in the bundle (max 3)");
+    }
+
+    #[test]
+    fn refuses_a_manifest_entry_without_its_file() {
+        let path = write(
+            "missing",
+            &[
+                (
+                    MANIFEST_FILE,
+                    manifest(&["conversations/a.json", "conversations/b.json"]),
+                ),
+                ("conversations/a.json", conversation("a", 0)),
+            ],
+        );
+        let err = read_bundle(&path, &TEST_LIMITS).unwrap_err();
+        assert!(
+            err.starts_with("The bundle has no conversations/b.json"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn refuses_files_outside_the_conversations_directory() {
+        for file in [
+            "../a.json",
+            "conversations/../../a.json",
+            "conversations/../manifest.json",
+            "/etc/a.json",
+            "conversations/nested/a.json",
+            "conversations/a.txt",
+            "a.json",
+        ] {
+            let path = write("outside", &[(MANIFEST_FILE, manifest(&[file]))]);
+            let err = read_manifest(&path, &TEST_LIMITS).err().unwrap();
+            assert_eq!(err, format!("The bundle lists an invalid file: {file}"));
+        }
+    }
+
+    #[test]
+    fn refuses_a_file_listed_twice() {
+        let path = write(
+            "twice",
+            &[(
+                MANIFEST_FILE,
+                manifest(&["conversations/a.json", "conversations/a.json"]),
+            )],
+        );
+        let err = read_manifest(&path, &TEST_LIMITS).err().unwrap();
+        assert_eq!(err, "The bundle lists conversations/a.json more than once");
+    }
+}
@@ -0,0 +1,345 @@
+// Calendar Events
+// ===============
+//
+// Turns event details produced by a model ("schedule a meeting about
+// this") into an iCalendar (RFC 5545) file and hands it to the default
+// calendar app. Details are validated here rather than trusted, since they
+// come from model output. Files are kept under `calendar/` in the app data
+// directory.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_TEXT_CHARS: usize = 5000;
+const MAX_ATTENDEES: usize = 100;
+/// Events without an end or duration last this long
+const DEFAULT_DURATION_MINUTES: i64 = 60;
+/// Longest event accepted, to catch swapped or garbled dates
+const MAX_EVENT_DAYS: i64 = 366;
+/// Content lines are folded at this many octets (RFC 5545 §3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDetails {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    /// ISO 8601 date or date-time; times without an offset are local
+    pub start: String,
+    /// ISO 8601 date or date-time; defaults to `start` plus `duration_minutes`
+    #[serde(default)]
+    pub end: Option<String>,
+    #[serde(default)]
+    pub duration_minutes: Option<i64>,
+    /// Whole-day event; times in `start` and `end` are ignored
+    #[serde(default)]
+    pub all_day: bool,
+    /// Email addresses
+    #[serde(default)]
+    pub attendees: Vec<String>,
+}
+
+/// A point in time, or a whole day for all-day events.
+enum EventTime {
+    At(DateTime<Utc>),
+    Day(NaiveDate),
+}
+
+fn parse_time(value: &str, field: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| format!("Invalid {field} time: {value}"))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid {field} time: {value}"))
+}
+
+fn parse_day(value: &str, field: &str) -> Result<NaiveDate, String> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid {field} date: {value}"))
+}
+
+/// Start and exclusive end of the event.
+fn event_times(details: &EventDetails) -> Result<(EventTime, EventTime), String> {
+    if details.all_day {
+        let start = parse_day(&details.start, "start")?;
+        // All-day end dates are exclusive in iCalendar, but people mean the last day
+        let end = match &details.end {
+            Some(end) => parse_day(end, "end")? + Duration::days(1),
+            None => start + Duration::days(1),
+        };
+        if end <= start {
+            return Err("Event ends before it starts".to_string());
+        }
+        if (end - start).num_days() > MAX_EVENT_DAYS {
+            return Err(format!("Event is longer than {MAX_EVENT_DAYS} days"));
+        }
+        return Ok((EventTime::Day(start), EventTime::Day(end)));
+    }
+
+    let start = parse_time(&details.start, "start")?;
+    let end = match (&details.end, details.duration_minutes) {
+        (Some(end), _) => parse_time(end, "end")?,
+        (None, Some(minutes)) if minutes > 0 => start + Duration::minutes(minutes),
+        (None, Some(_)) => return Err("Duration must be positive".to_string()),
+        (None, None) => start + Duration::minutes(DEFAULT_DURATION_MINUTES),
+    };
+    if end <= start {
+        return Err("Event ends before it starts".to_string());
+    }
+    if (end - start).num_days() > MAX_EVENT_DAYS {
+        return Err(format!("Event is longer than {MAX_EVENT_DAYS} days"));
+    }
+    Ok((EventTime::At(start), EventTime::At(end)))
+}
+
+fn validate_email(email: &str) -> Result<(), String> {
+    let valid = email.len() <= 254
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid attendee email: {email}"))
+    }
+}
+
+fn validate(details: &EventDetails) -> Result<(), String> {
+    if details.title.trim().is_empty() {
+        return Err("Event title is required".to_string());
+    }
+    crate::validate_string_input(&details.title, MAX_TITLE_CHARS, "Event title")?;
+    if let Some(description) = &details.description {
+        crate::validate_string_input(description, MAX_TEXT_CHARS, "Event description")?;
+    }
+    if let Some(location) = &details.location {
+        crate::validate_string_input(location, MAX_TITLE_CHARS, "Event location")?;
+    }
+    if details.attendees.len() > MAX_ATTENDEES {
+        return Err(format!("Too many attendees (max {MAX_ATTENDEES})"));
+    }
+    details
+        .attendees
+        .iter()
+        .try_for_each(|email| validate_email(email.trim()))
+}
+
+/// Escapes a TEXT value (RFC 5545 §3.3.11).
+fn escape_text(text: &str) -> String {
+    text.trim()
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+/// Appends a content line, folded at `MAX_LINE_OCTETS` without splitting
+/// UTF-8 characters.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space, which counts toward the limit
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn format_time(name: &str, time: &EventTime) -> String {
+    match time {
+        EventTime::At(at) => format!("{name}:{}", at.format("%Y%m%dT%H%M%SZ")),
+        EventTime::Day(day) => format!("{name};VALUE=DATE:{}", day.format("%Y%m%d")),
+    }
+}
+
+fn build_ics(details: &EventDetails, uid: &str) -> Result<String, String> {
+    let (start, end) = event_times(details)?;
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//Nexus//Nexus AI//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, "BEGIN:VEVENT");
+    push_line(&mut ics, &format!("UID:{uid}"));
+    push_line(
+        &mut ics,
+        &format!("DTSTAMP:{}", Utc::now().format("%Y%m%dT%H%M%SZ")),
+    );
+    push_line(&mut ics, &format_time("DTSTART", &start));
+    push_line(&mut ics, &format_time("DTEND", &end));
+    push_line(
+        &mut ics,
+        &format!("SUMMARY:{}", escape_text(&details.title)),
+    );
+    if let Some(description) = details
+        .description
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+    {
+        push_line(
+            &mut ics,
+            &format!("DESCRIPTION:{}", escape_text(description)),
+        );
+    }
+    if let Some(location) = details.location.as_deref().filter(|l| !l.trim().is_empty()) {
+        push_line(&mut ics, &format!("LOCATION:{}", escape_text(location)));
+    }
+    for attendee in &details.attendees {
+        push_line(
+            &mut ics,
+            &format!(
+                "ATTENDEE;ROLE=REQ-PARTICIPANT;RSVP=TRUE:mailto:{}",
+                attendee.trim()
+            ),
+        );
+    }
+    push_line(&mut ics, "END:VEVENT");
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok(ics)
+}
+
+fn get_calendar_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let calendar_dir = app_data_dir.join("calendar");
+    std::fs::create_dir_all(&calendar_dir)
+        .map_err(|e| format!("Failed to create calendar directory: {e}"))?;
+
+    Ok(calendar_dir)
+}
+
+/// Validates event details, writes them as an .ics file and, unless `open`
+/// is false, opens it with the default calendar app. Returns the file path.
+#[tauri::command]
+pub async fn create_ics(
+    app: AppHandle,
+    event_details: EventDetails,
+    open: Option<bool>,
+) -> Result<String, String> {
+    validate(&event_details)?;
+
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate event id: {e}"))?;
+    let id: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    let ics = build_ics(&event_details, &format!("{id}@nexus"))?;
+
+    let path = get_calendar_dir(&app)?.join(format!("{id}.ics"));
+    crate::export::write_atomic(&path, ics.as_bytes())?;
+    log::info!("Created calendar event {path:?}");
+
+    if open.unwrap_or(true) {
+        use tauri_plugin_opener::OpenerExt;
+
+        app.opener()
+            .open_path(path.to_string_lossy(), None::<&str>)
+            .map_err(|e| {
+                log::error!("Failed to open calendar event: {e}");
+                format!("Failed to open calendar event: {e}")
+            })?;
+    }
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(start: &str) -> EventDetails {
+        EventDetails {
+            title: "Planning".to_string(),
+            description: None,
+            location: None,
+            start: start.to_string(),
+            end: None,
+            duration_minutes: None,
+            all_day: false,
+            attendees: Vec::new(),
+        }
+    }
+
+    fn lines(ics: &str) -> Vec<&str> {
+        ics.split("\r\n").collect()
+    }
+
+    #[test]
+    fn builds_timed_event_in_utc() {
+        let ics = build_ics(&details("2026-03-10T09:30:00+02:00"), "uid-1").unwrap();
+        let lines = lines(&ics);
+        assert_eq!(lines[0], "BEGIN:VCALENDAR");
+        assert!(lines.contains(&"UID:uid-1"));
+        assert!(lines.contains(&"DTSTART:20260310T073000Z"));
+        // Default duration
+        assert!(lines.contains(&"DTEND:20260310T083000Z"));
+        assert!(lines.contains(&"SUMMARY:Planning"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn all_day_end_is_exclusive() {
+        let mut event = details("2026-03-10");
+        event.all_day = true;
+        event.end = Some("2026-03-12".to_string());
+        let ics = build_ics(&event, "uid-2").unwrap();
+        assert!(lines(&ics).contains(&"DTSTART;VALUE=DATE:20260310"));
+        assert!(lines(&ics).contains(&"DTEND;VALUE=DATE:20260313"));
+    }
+
+    #[test]
+    fn rejects_events_ending_before_they_start() {
+        let mut event = details("2026-03-10T10:00:00Z");
+        event.end = Some("2026-03-10T09:00:00Z".to_string());
+        assert!(build_ics(&event, "uid").is_err());
+        event.end = None;
+        event.duration_minutes = Some(0);
+        assert!(build_ics(&event, "uid").is_err());
+    }
+
+    #[test]
+    fn escapes_and_folds_text() {
+        assert_eq!(escape_text(" a;b,c\\d\r\ne "), r"a\;b\,c\\d\ne");
+
+        let mut event = details("2026-03-10T10:00:00Z");
+        event.description = Some("é".repeat(100));
+        let ics = build_ics(&event, "uid").unwrap();
+        assert!(lines(&ics).iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("DESCRIPTION:{}", "é".repeat(100))));
+    }
+
+    #[test]
+    fn validates_attendee_emails() {
+        assert!(validate_email("ada@example.com").is_ok());
+        for email in ["ada", "@example.com", "ada@localhost", "a da@example.com"] {
+            assert!(validate_email(email).is_err(), "{email}");
+        }
+    }
+}
@@ -0,0 +1,759 @@
+// Chat Completion Proxy
+// =====================
+//
+// Lets the frontend stream chat completions from any OpenAI-compatible API
+// (OpenAI, Groq, OpenRouter, vLLM, LM Studio, ...) or from Anthropic's
+// Messages API through the backend. The HTTPS request is made here, so
+// browser CORS rules don't apply and the API key never ends up in a
+// webview request.
+//
+// The request body is the provider's own chat completions or messages
+// body, passed through with streaming switched on. Whichever API it goes
+// to, the stream comes back as the same events, tagged with the caller's
+// request id:
+//
+// - `llm-chunk`: a text delta and/or a tool call delta
+// - `llm-done`: the finish reason and token usage, once the stream ends,
+//   and whether the response came from the cache (see `response_cache`)
+// - `llm-error`: the request or the stream failed; nothing follows
+// - `llm-cancelled`: `cancel_llm_request` stopped it, with the tokens used
+//   so far; nothing follows
+// - `llm-retrying`: the provider could not be reached or answered with a
+//   transient error before streaming, and the request is sent again after
+//   the given delay (see `retry`)
+// - `llm-budget-warning`: the provider's spend this month crossed 80% or
+//   100% of its budget (see `budget`)
+//
+// Anthropic's output is normalized to the OpenAI shape: `tool_use` content
+// blocks become tool call deltas numbered from 0, their `input_json_delta`s
+// the argument pieces, and stop reasons the matching finish reasons
+// (`end_turn` is `stop`, `max_tokens` is `length`, `tool_use` is
+// `tool_calls`). Thinking blocks are not forwarded.
+//
+// The command itself resolves once the stream has ended. Each stream runs
+// in its own task, so cancelling aborts it wherever it is, closing the
+// connection and with it the provider's generation (and billing). Providers
+// report usage only at the end of a stream, so the usage of a cancelled one
+// is mostly estimated from the text that had arrived.
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::task::AbortHandle;
+
+use crate::response_cache;
+
+const MAX_HEADERS: usize = 20;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Rough characters per token, for the usage of cancelled streams
+const CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Streams in flight by request id, managed as app state.
+#[derive(Default)]
+pub struct ActiveStreams(Mutex<HashMap<String, AbortHandle>>);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderApi {
+    /// `POST {base_url}/chat/completions`
+    #[default]
+    Openai,
+    /// `POST {base_url}/messages`
+    Anthropic,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConfig {
+    #[serde(default)]
+    pub api: ProviderApi,
+    /// Base URL of the API, e.g. `https://api.openai.com/v1` or
+    /// `https://api.anthropic.com/v1`
+    pub base_url: String,
+    /// Sent as a bearer token, or as `x-api-key` to Anthropic
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Extra headers, e.g. `HTTP-Referer` for OpenRouter
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Part of a tool call, as the provider streams it: the id and name come
+/// first, the arguments in pieces after.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallDelta {
+    /// Which of the message's tool calls this belongs to
+    pub index: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A piece of the JSON arguments, to be concatenated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmChunkEvent {
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCallDelta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmDoneEvent {
+    pub request_id: String,
+    /// `stop`, `length`, `tool_calls`, ... as the provider reported it
+    pub finish_reason: Option<String>,
+    pub usage: Option<TokenUsage>,
+    /// Answered from the response cache (see `response_cache`)
+    pub cached: bool,
+}
+
+/// A finished response, assembled from everything that was streamed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatResponse {
+    pub text: String,
+    /// The deltas as streamed; those with the same `index` make up one call
+    pub tool_calls: Vec<ToolCallDelta>,
+    pub finish_reason: Option<String>,
+    pub usage: Option<TokenUsage>,
+    /// Milliseconds from sending the request to the first text or tool
+    /// call; `None` when nothing was streamed or it came from the cache
+    #[serde(skip)]
+    pub first_chunk_ms: Option<u64>,
+    /// Answered from the response cache
+    #[serde(skip)]
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmErrorEvent {
+    pub request_id: String,
+    pub message: String,
+    /// HTTP status, when the provider answered with an error
+    pub status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmCancelledEvent {
+    pub request_id: String,
+    /// Tokens used up to the cancellation
+    pub usage: TokenUsage,
+    /// Some of `usage` was estimated rather than reported by the provider
+    pub usage_estimated: bool,
+}
+
+/// Loopback and private network hosts, where self-hosted servers (Ollama,
+/// LM Studio, vLLM) usually run without TLS.
+pub fn is_local(url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_matches(['[', ']']).parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Err(_) => host == "localhost" || host.ends_with(".local"),
+    }
+}
+
+/// Parses an API base URL: https, or http on a local host.
+pub fn parse_base_url(base_url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(base_url.trim()).map_err(|e| format!("Invalid base URL: {e}"))?;
+    match parsed.scheme() {
+        "https" => Ok(parsed),
+        "http" if is_local(&parsed) => Ok(parsed),
+        _ => Err("Base URL must use https, or http on a local network".to_string()),
+    }
+}
+
+/// `path` under the base URL.
+pub fn api_url(base_url: &str, path: &str) -> Result<Url, String> {
+    let parsed = parse_base_url(base_url)?;
+    let url = format!("{}/{path}", parsed.as_str().trim_end_matches('/'));
+    Url::parse(&url).map_err(|e| format!("Invalid base URL: {e}"))
+}
+
+fn endpoint_url(config: &ProviderConfig) -> Result<Url, String> {
+    let path = match config.api {
+        ProviderApi::Openai => "chat/completions",
+        ProviderApi::Anthropic => "messages",
+    };
+    api_url(&config.base_url, path)
+}
+
+pub fn build_headers(config: &ProviderConfig) -> Result<HeaderMap, String> {
+    if config.headers.len() > MAX_HEADERS {
+        return Err(format!("At most {MAX_HEADERS} extra headers are allowed"));
+    }
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name {name}: {e}"))?;
+        let value =
+            HeaderValue::from_str(value).map_err(|e| format!("Invalid value for {name}: {e}"))?;
+        headers.insert(name, value);
+    }
+    if let Some(api_key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        let (name, value) = match config.api {
+            ProviderApi::Openai => (reqwest::header::AUTHORIZATION, format!("Bearer {api_key}")),
+            ProviderApi::Anthropic => (HeaderName::from_static("x-api-key"), api_key.to_string()),
+        };
+        let mut value = HeaderValue::from_str(&value).map_err(|_| "Invalid API key".to_string())?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    if config.api == ProviderApi::Anthropic {
+        headers
+            .entry("anthropic-version")
+            .or_insert(HeaderValue::from_static(ANTHROPIC_VERSION));
+    }
+    Ok(headers)
+}
+
+/// Turns the provider's stream events into chunk events, collecting the
+/// finish reason and token usage on the way.
+struct StreamParser {
+    api: ProviderApi,
+    request_id: String,
+    finish_reason: Option<String>,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    /// Anthropic content block index of each tool call, in order
+    tool_blocks: Vec<u64>,
+    /// An error event in the stream
+    error: Option<String>,
+    /// Text and tool call arguments received so far
+    streamed_chars: u64,
+    /// Everything streamed, for the finished response
+    transcript: (String, Vec<ToolCallDelta>),
+    started: Instant,
+    first_chunk_ms: Option<u64>,
+}
+
+impl StreamParser {
+    fn new(api: ProviderApi, request_id: &str) -> Self {
+        Self {
+            api,
+            request_id: request_id.to_string(),
+            finish_reason: None,
+            input_tokens: None,
+            output_tokens: None,
+            tool_blocks: Vec::new(),
+            error: None,
+            streamed_chars: 0,
+            transcript: Default::default(),
+            started: Instant::now(),
+            first_chunk_ms: None,
+        }
+    }
+
+    fn chunk(
+        &mut self,
+        delta: Option<String>,
+        tool_calls: Vec<ToolCallDelta>,
+    ) -> Option<LlmChunkEvent> {
+        let chars = delta
+            .iter()
+            .chain(tool_calls.iter().filter_map(|c| c.arguments.as_ref()));
+        self.streamed_chars += chars.map(|text| text.chars().count() as u64).sum::<u64>();
+        let (text, calls) = &mut self.transcript;
+        text.push_str(delta.as_deref().unwrap_or_default());
+        calls.extend(tool_calls.iter().cloned());
+        let has_content = delta.is_some() || !tool_calls.is_empty();
+        if has_content && self.first_chunk_ms.is_none() {
+            self.first_chunk_ms = Some(self.started.elapsed().as_millis() as u64);
+        }
+        has_content.then(|| LlmChunkEvent {
+            request_id: self.request_id.clone(),
+            delta,
+            tool_calls,
+        })
+    }
+
+    /// The chunk event for one stream event, if it has any content.
+    fn feed(&mut self, data: &Value) -> Option<LlmChunkEvent> {
+        match self.api {
+            ProviderApi::Openai => self.feed_openai(data),
+            ProviderApi::Anthropic => self.feed_anthropic(data),
+        }
+    }
+
+    fn feed_openai(&mut self, data: &Value) -> Option<LlmChunkEvent> {
+        let choice = &data["choices"][0];
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.finish_reason = Some(reason.to_string());
+        }
+        // Groq reports usage under `x_groq`
+        for usage in [&data["usage"], &data["x_groq"]["usage"]] {
+            if let (Some(input), Some(output)) = (
+                usage["prompt_tokens"].as_u64(),
+                usage["completion_tokens"].as_u64(),
+            ) {
+                self.input_tokens = Some(input);
+                self.output_tokens = Some(output);
+            }
+        }
+
+        let delta = choice["delta"]["content"]
+            .as_str()
+            .filter(|text| !text.is_empty())
+            .map(String::from);
+        let tool_calls: Vec<ToolCallDelta> = choice["delta"]["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .map(|call| ToolCallDelta {
+                        index: call["index"].as_u64().unwrap_or_default(),
+                        id: call["id"].as_str().map(String::from),
+                        name: call["function"]["name"].as_str().map(String::from),
+                        arguments: call["function"]["arguments"].as_str().map(String::from),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.chunk(delta, tool_calls)
+    }
+
+    fn feed_anthropic(&mut self, data: &Value) -> Option<LlmChunkEvent> {
+        let block = data["index"].as_u64().unwrap_or_default();
+        match data["type"].as_str()? {
+            "message_start" => {
+                let usage = &data["message"]["usage"];
+                self.input_tokens = usage["input_tokens"].as_u64();
+                self.output_tokens = usage["output_tokens"].as_u64();
+                None
+            }
+            "content_block_start" => {
+                let content = &data["content_block"];
+                match content["type"].as_str()? {
+                    "text" => self.chunk(
+                        content["text"]
+                            .as_str()
+                            .filter(|t| !t.is_empty())
+                            .map(String::from),
+                        Vec::new(),
+                    ),
+                    "tool_use" => {
+                        self.tool_blocks.push(block);
+                        let call = ToolCallDelta {
+                            index: self.tool_blocks.len() as u64 - 1,
+                            id: content["id"].as_str().map(String::from),
+                            name: content["name"].as_str().map(String::from),
+                            arguments: None,
+                        };
+                        self.chunk(None, vec![call])
+                    }
+                    _ => None,
+                }
+            }
+            "content_block_delta" => {
+                let delta = &data["delta"];
+                match delta["type"].as_str()? {
+                    "text_delta" => {
+                        self.chunk(delta["text"].as_str().map(String::from), Vec::new())
+                    }
+                    "input_json_delta" => {
+                        let index = self.tool_blocks.iter().position(|&b| b == block)?;
+                        let call = ToolCallDelta {
+                            index: index as u64,
+                            id: None,
+                            name: None,
+                            arguments: delta["partial_json"].as_str().map(String::from),
+                        };
+                        self.chunk(None, vec![call])
+                    }
+                    _ => None,
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = data["delta"]["stop_reason"].as_str() {
+                    let reason = match reason {
+                        "end_turn" | "stop_sequence" => "stop",
+                        "max_tokens" => "length",
+                        "tool_use" => "tool_calls",
+                        other => other,
+                    };
+                    self.finish_reason = Some(reason.to_string());
+                }
+                if let Some(output) = data["usage"]["output_tokens"].as_u64() {
+                    self.output_tokens = Some(output);
+                }
+                None
+            }
+            "error" => {
+                self.error = Some(
+                    data["error"]["message"]
+                        .as_str()
+                        .unwrap_or("Unknown error")
+                        .to_string(),
+                );
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Usage so far of a stream cut short, estimating what the provider has
+    /// not reported from `input_chars` of prompt and the text received.
+    fn partial_usage(&self, input_chars: u64) -> (TokenUsage, bool) {
+        let estimate = |chars: u64| (chars as f64 / CHARS_PER_TOKEN).ceil() as u64;
+        let streamed = estimate(self.streamed_chars);
+        let output_tokens = self.output_tokens.unwrap_or_default().max(streamed);
+        let usage = TokenUsage {
+            input_tokens: self.input_tokens.unwrap_or_else(|| estimate(input_chars)),
+            output_tokens,
+        };
+        let estimated = self.input_tokens.is_none() || self.output_tokens != Some(output_tokens);
+        (usage, estimated)
+    }
+
+    fn finish(&self) -> LlmDoneEvent {
+        LlmDoneEvent {
+            request_id: self.request_id.clone(),
+            finish_reason: self.finish_reason.clone(),
+            usage: self.input_tokens.zip(self.output_tokens).map(
+                |(input_tokens, output_tokens)| TokenUsage {
+                    input_tokens,
+                    output_tokens,
+                },
+            ),
+            cached: false,
+        }
+    }
+
+    /// The finished response, taking what was streamed.
+    fn response(&mut self, done: &LlmDoneEvent) -> ChatResponse {
+        let (text, tool_calls) = std::mem::take(&mut self.transcript);
+        ChatResponse {
+            text,
+            tool_calls,
+            finish_reason: done.finish_reason.clone(),
+            usage: done.usage.clone(),
+            first_chunk_ms: self.first_chunk_ms,
+            cached: false,
+        }
+    }
+}
+
+/// Characters of prompt text in a request body, leaving out inline data
+/// such as images.
+fn prompt_chars(value: &Value) -> u64 {
+    match value {
+        Value::String(text) if !text.starts_with("data:") => text.chars().count() as u64,
+        Value::Array(items) => items.iter().map(prompt_chars).sum(),
+        Value::Object(fields) => fields
+            .iter()
+            .filter(|(key, _)| key.as_str() != "data")
+            .map(|(_, value)| prompt_chars(value))
+            .sum(),
+        _ => 0,
+    }
+}
+
+async fn stream(
+    app: &AppHandle,
+    request_id: &str,
+    config: &ProviderConfig,
+    mut request: Value,
+    parser: &Mutex<StreamParser>,
+) -> Result<LlmDoneEvent, (String, Option<u16>)> {
+    let url = endpoint_url(config).map_err(|e| (e, None))?;
+    let headers = build_headers(config).map_err(|e| (e, None))?;
+    let Some(body) = request.as_object_mut() else {
+        return Err(("The request must be a JSON object".to_string(), None));
+    };
+    body.insert("stream".to_string(), json!(true));
+    if config.api == ProviderApi::Openai {
+        body.entry("stream_options")
+            .or_insert_with(|| json!({ "include_usage": true }));
+    }
+
+    let host = url.host_str().unwrap_or_default().to_string();
+    log::info!("Proxying chat completion {request_id} to {host}");
+    let client = crate::llm::http_client();
+    let mut response = crate::retry::send(app, Some(request_id), &host, || {
+        client
+            .post(url.clone())
+            .headers(headers.clone())
+            .json(&request)
+    })
+    .await
+    .map_err(|e| (e, None))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err((
+            format!(
+                "{host} returned {status}: {}",
+                crate::llm::error_message(&body)
+            ),
+            Some(status.as_u16()),
+        ));
+    }
+
+    crate::llm::read_sse(&mut response, |data| {
+        let Ok(value) = serde_json::from_str::<Value>(data) else {
+            log::debug!("Skipping unparseable chunk in {request_id}");
+            return;
+        };
+        let chunk = parser
+            .lock()
+            .ok()
+            .and_then(|mut parser| parser.feed(&value));
+        if let Some(chunk) = chunk {
+            if let Err(e) = app.emit("llm-chunk", chunk) {
+                log::warn!("Failed to emit llm-chunk event: {e}");
+            }
+        }
+    })
+    .await
+    .map_err(|e| (format!("Stream from {host} failed: {e}"), None))?;
+
+    let mut parser = parser
+        .lock()
+        .map_err(|e| (format!("Failed to read stream: {e}"), None))?;
+    if let Some(error) = parser.error.take() {
+        return Err((format!("Stream from {host} failed: {error}"), None));
+    }
+    Ok(parser.finish())
+}
+
+/// Emits a cached response as the events of a stream.
+fn replay(app: &AppHandle, request_id: String, cached: ChatResponse) {
+    if !cached.text.is_empty() || !cached.tool_calls.is_empty() {
+        let chunk = LlmChunkEvent {
+            request_id: request_id.clone(),
+            delta: (!cached.text.is_empty()).then_some(cached.text),
+            tool_calls: cached.tool_calls,
+        };
+        if let Err(e) = app.emit("llm-chunk", chunk) {
+            log::warn!("Failed to emit llm-chunk event: {e}");
+        }
+    }
+    let done = LlmDoneEvent {
+        request_id,
+        finish_reason: cached.finish_reason,
+        usage: cached.usage,
+        cached: true,
+    };
+    if let Err(e) = app.emit("llm-done", done) {
+        log::warn!("Failed to emit llm-done event: {e}");
+    }
+}
+
+/// Streams a chat completion like `stream_chat_completion` and returns the
+/// finished response, or `None` if it was cancelled.
+pub async fn complete(
+    app: &AppHandle,
+    request_id: String,
+    provider_config: Option<ProviderConfig>,
+    provider_id: Option<String>,
+    request: Value,
+) -> Result<Option<ChatResponse>, String> {
+    let app = app.clone();
+    crate::validate_string_input(&request_id, 100, "Request id")?;
+    let (provider_config, provider) = match (provider_config, provider_id) {
+        (Some(config), None) => {
+            let host = endpoint_url(&config)?
+                .host_str()
+                .unwrap_or_default()
+                .to_string();
+            (config, host)
+        }
+        (None, Some(id)) => (crate::providers::config(&app, &id)?, id),
+        _ => return Err("Pass either a provider configuration or a provider id".to_string()),
+    };
+    let cache = response_cache::settings(&app);
+    let cache_key = cache
+        .as_ref()
+        .map(|_| response_cache::key(&provider, &request));
+    if let Some((settings, key)) = cache.as_ref().zip(cache_key.as_deref()) {
+        if let Some(cached) = response_cache::get(&app, settings, key) {
+            log::info!("Answering chat completion {request_id} from the cache");
+            replay(&app, request_id, cached.clone());
+            return Ok(Some(ChatResponse {
+                cached: true,
+                ..cached
+            }));
+        }
+    }
+
+    // Self-hosted servers cost nothing, so they have no budget
+    let billed = !is_local(&endpoint_url(&provider_config)?);
+    if billed {
+        crate::budget::check(&app, &provider)?;
+    }
+    let model = request["model"].as_str().unwrap_or_default().to_string();
+    let record_spend = |usage: &TokenUsage| {
+        if billed {
+            crate::budget::record(
+                &app,
+                &provider,
+                &model,
+                usage.input_tokens,
+                usage.output_tokens,
+            );
+        }
+    };
+
+    let input_chars = prompt_chars(&request["messages"]) + prompt_chars(&request["system"]);
+    let parser = Arc::new(Mutex::new(StreamParser::new(
+        provider_config.api,
+        &request_id,
+    )));
+    let task = tauri::async_runtime::spawn({
+        let app = app.clone();
+        let request_id = request_id.clone();
+        let parser = parser.clone();
+        async move { stream(&app, &request_id, &provider_config, request, &parser).await }
+    });
+    {
+        let streams = app.state::<ActiveStreams>();
+        let mut streams = streams
+            .0
+            .lock()
+            .map_err(|e| format!("Failed to register stream: {e}"))?;
+        if streams.contains_key(&request_id) {
+            task.abort();
+            return Err(format!("Request {request_id} is already streaming"));
+        }
+        streams.insert(request_id.clone(), task.inner().abort_handle());
+    }
+    let result = task.await;
+    // `cancel_llm_request` takes the entry out when it aborts the task
+    let cancelled = app
+        .state::<ActiveStreams>()
+        .0
+        .lock()
+        .map(|mut streams| streams.remove(&request_id).is_none())
+        .unwrap_or(false);
+
+    let result = match result {
+        _ if cancelled => {
+            let (usage, usage_estimated) = parser
+                .lock()
+                .map(|parser| parser.partial_usage(input_chars))
+                .map_err(|e| format!("Failed to read stream: {e}"))?;
+            log::info!(
+                "Chat completion {request_id} cancelled after {} output tokens",
+                usage.output_tokens
+            );
+            record_spend(&usage);
+            let event = LlmCancelledEvent {
+                request_id,
+                usage,
+                usage_estimated,
+            };
+            if let Err(e) = app.emit("llm-cancelled", event) {
+                log::warn!("Failed to emit llm-cancelled event: {e}");
+            }
+            return Ok(None);
+        }
+        Ok(result) => result,
+        Err(e) => Err((format!("Stream task failed: {e}"), None)),
+    };
+    match result {
+        Ok(done) => {
+            log::info!(
+                "Chat completion {request_id} finished: {:?}",
+                done.finish_reason
+            );
+            let response = parser
+                .lock()
+                .map(|mut parser| parser.response(&done))
+                .map_err(|e| format!("Failed to read stream: {e}"))?;
+            if let Some((settings, key)) = cache.as_ref().zip(cache_key.as_deref()) {
+                if response.finish_reason.is_some() {
+                    response_cache::put(&app, settings, key, &provider, &model, &response);
+                }
+            }
+            match &done.usage {
+                Some(usage) => record_spend(usage),
+                None => {
+                    // Not every server reports usage; estimate it instead
+                    if let Ok(parser) = parser.lock() {
+                        record_spend(&parser.partial_usage(input_chars).0);
+                    }
+                }
+            }
+            if let Err(e) = app.emit("llm-done", done) {
+                log::warn!("Failed to emit llm-done event: {e}");
+            }
+            Ok(Some(response))
+        }
+        Err((message, status)) => {
+            log::error!("Chat completion {request_id} failed: {message}");
+            let event = LlmErrorEvent {
+                request_id,
+                message: message.clone(),
+                status,
+            };
+            if let Err(e) = app.emit("llm-error", event) {
+                log::warn!("Failed to emit llm-error event: {e}");
+            }
+            Err(message)
+        }
+    }
+}
+
+/// Streams a chat completion from an OpenAI-compatible or Anthropic API,
+/// emitting `llm-chunk` events and then `llm-done`, `llm-error` or
+/// `llm-cancelled`, all carrying `request_id`. Resolves when the stream has
+/// ended. The API is either given in full by `provider_config` or is the
+/// registered provider `provider_id`.
+#[tauri::command]
+pub async fn stream_chat_completion(
+    app: AppHandle,
+    request_id: String,
+    provider_config: Option<ProviderConfig>,
+    provider_id: Option<String>,
+    request: Value,
+) -> Result<(), String> {
+    complete(&app, request_id, provider_config, provider_id, request)
+        .await
+        .map(|_| ())
+}
+
+/// Stops a streaming chat completion: the connection is closed right away
+/// and the stream ends with `llm-cancelled`. Returns `false` if no stream
+/// with this id is in flight.
+#[tauri::command]
+pub async fn cancel_llm_request(app: AppHandle, request_id: String) -> Result<bool, String> {
+    let handle = app
+        .state::<ActiveStreams>()
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to cancel request: {e}"))?
+        .remove(&request_id);
+    match handle {
+        Some(handle) => {
+            handle.abort();
+            log::info!("Cancelling chat completion {request_id}");
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,481 @@
+// Clipboard History
+// =================
+//
+// With `clipboard_history` switched on in the preferences, the clipboard
+// is polled and every new text or image copied is kept in a ring buffer of
+// `max_entries`, newest first, so it can be pasted into a chat later. The
+// history lives in memory only: it is gone when the app quits or the
+// history is switched off. Copies made while an app from `excluded_apps`
+// is in front (matched by name, ignoring case) are skipped; password
+// managers belong there. Which app is in front is known on Windows and
+// macOS only, so on other platforms the list has no effect. Whatever was
+// on the clipboard before the history was switched on is not captured.
+// On Windows and macOS each poll first checks the system's clipboard change
+// counter, and the clipboard is only read and hashed once that moves.
+//
+// `paste_clipboard_as_attachment` stores the image on the clipboard now as
+// a PNG attachment, ready to send to a vision model; it works with the
+// history switched off.
+
+use base64::Engine;
+use image::{imageops, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::attachments::StoredAttachment;
+use crate::hash::sha256_hex;
+use crate::time::now_ms;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_ENTRIES_LIMIT: usize = 500;
+const MAX_EXCLUDED_APPS: usize = 100;
+const MAX_APP_NAME_CHARS: usize = 200;
+/// Text beyond this many characters is cut off
+const MAX_TEXT_CHARS: usize = 20_000;
+/// Larger images are left out of the history
+const MAX_IMAGE_PIXELS: u64 = 40_000_000;
+/// Longest side of a thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ClipboardHistorySettings {
+    pub enabled: bool,
+    pub max_entries: usize,
+    /// Names of apps whose copies are never captured, e.g. `1Password`
+    pub excluded_apps: Vec<String>,
+}
+
+impl Default for ClipboardHistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 50,
+            excluded_apps: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClipboardContent {
+    Text {
+        text: String,
+        truncated: bool,
+    },
+    Image {
+        width: u32,
+        height: u32,
+        /// Base64 PNG, at most `THUMBNAIL_SIZE` pixels a side
+        thumbnail: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub id: u64,
+    /// UNIX timestamp (milliseconds)
+    pub captured_at: u64,
+    /// The app in front when it was copied, where known
+    pub source_app: Option<String>,
+    #[serde(flatten)]
+    pub content: ClipboardContent,
+}
+
+struct HistoryItem {
+    entry: ClipboardEntry,
+    /// The full image of image entries
+    png: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct History {
+    settings: ClipboardHistorySettings,
+    items: VecDeque<HistoryItem>,
+    /// Hash of the clipboard contents last seen
+    last_seen: Option<String>,
+    /// The platform's clipboard change counter when last polled, see
+    /// `change_count`
+    last_change: Option<u64>,
+    /// Whether the clipboard was polled since the history was switched on
+    primed: bool,
+    next_id: u64,
+}
+
+/// The captured entries; managed as app state.
+#[derive(Default)]
+pub struct ClipboardHistory(Mutex<History>);
+
+pub fn validate(settings: &ClipboardHistorySettings) -> Result<(), String> {
+    if settings.max_entries == 0 || settings.max_entries > MAX_ENTRIES_LIMIT {
+        return Err(format!(
+            "Clipboard history size must be between 1 and {MAX_ENTRIES_LIMIT} entries"
+        ));
+    }
+    if settings.excluded_apps.len() > MAX_EXCLUDED_APPS {
+        return Err(format!(
+            "At most {MAX_EXCLUDED_APPS} apps can be excluded from the clipboard history"
+        ));
+    }
+    for app in &settings.excluded_apps {
+        crate::validate_string_input(app, MAX_APP_NAME_CHARS, "App name")?;
+        if app.trim().is_empty() {
+            return Err("Excluded app names can't be empty".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Makes `settings` the ones the watcher uses. Switching the history off
+/// forgets every entry.
+pub fn apply_settings(app: &AppHandle, settings: &ClipboardHistorySettings) {
+    let state = app.state::<ClipboardHistory>();
+    let Ok(mut history) = state.0.lock() else {
+        return;
+    };
+    if !settings.enabled {
+        history.items.clear();
+        history.last_seen = None;
+        history.last_change = None;
+        history.primed = false;
+    }
+    history.items.truncate(settings.max_entries);
+    history.settings = settings.clone();
+}
+
+/// Applies the saved settings and starts polling the clipboard.
+pub fn start(app: &AppHandle) {
+    match crate::read_preferences(app) {
+        Ok(preferences) => apply_settings(app, &preferences.clipboard_history),
+        Err(e) => log::warn!("Clipboard history stays off: {e}"),
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let enabled = app
+            .state::<ClipboardHistory>()
+            .0
+            .lock()
+            .is_ok_and(|history| history.settings.enabled);
+        if enabled {
+            poll(&app);
+        }
+    });
+}
+
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {e}"))?;
+    Ok(png)
+}
+
+/// The image on the clipboard, if it holds one.
+pub fn read_image(app: &AppHandle) -> Option<RgbaImage> {
+    let image = app.clipboard().read_image().ok()?;
+    if image.width() as u64 * image.height() as u64 > MAX_IMAGE_PIXELS {
+        return None;
+    }
+    RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+}
+
+fn is_excluded(settings: &ClipboardHistorySettings, source_app: Option<&str>) -> bool {
+    let Some(source_app) = source_app else {
+        return false;
+    };
+    let name = source_app.trim_end_matches(".exe");
+    settings
+        .excluded_apps
+        .iter()
+        .any(|excluded| excluded.trim().eq_ignore_ascii_case(name))
+}
+
+fn poll(app: &AppHandle) {
+    // Reading and hashing the clipboard is only worth it when the
+    // platform's counter says it changed
+    let change = change_count();
+    let state = app.state::<ClipboardHistory>();
+    let unchanged = state
+        .0
+        .lock()
+        .is_ok_and(|history| history.primed && change.is_some() && history.last_change == change);
+    if unchanged {
+        return;
+    }
+
+    // An image is checked first: copying one often puts a file name or
+    // an HTML snippet on the clipboard as text too
+    let image = read_image(app);
+    let text = match image {
+        Some(_) => None,
+        None => app
+            .clipboard()
+            .read_text()
+            .ok()
+            .filter(|text| !text.trim().is_empty()),
+    };
+    let fingerprint = match (&image, &text) {
+        (Some(image), _) => Some(sha256_hex(image.as_raw())),
+        (None, Some(text)) => Some(sha256_hex(text.as_bytes())),
+        (None, None) => None,
+    };
+
+    let settings = {
+        let Ok(mut history) = state.0.lock() else {
+            return;
+        };
+        history.last_change = change;
+        if !history.primed {
+            history.primed = true;
+            history.last_seen = fingerprint;
+            return;
+        }
+        if fingerprint.is_none() || history.last_seen == fingerprint {
+            return;
+        }
+        history.last_seen = fingerprint;
+        history.settings.clone()
+    };
+    let source_app = frontmost_app();
+    if is_excluded(&settings, source_app.as_deref()) {
+        log::debug!("Skipped a copy from an excluded app");
+        return;
+    }
+
+    // Encoded without the lock held, so reading the history never waits on it
+    let (content, png) = match (image, text) {
+        (Some(image), _) => {
+            let scale = (THUMBNAIL_SIZE as f64 / image.width().max(image.height()) as f64).min(1.0);
+            let thumbnail = imageops::thumbnail(
+                &image,
+                ((image.width() as f64 * scale).round() as u32).max(1),
+                ((image.height() as f64 * scale).round() as u32).max(1),
+            );
+            let (thumbnail, png) = match (encode_png(&thumbnail), encode_png(&image)) {
+                (Ok(thumbnail), Ok(png)) => (thumbnail, png),
+                (Err(e), _) | (_, Err(e)) => {
+                    log::warn!("Skipped a copied image: {e}");
+                    return;
+                }
+            };
+            let content = ClipboardContent::Image {
+                width: image.width(),
+                height: image.height(),
+                thumbnail: base64::engine::general_purpose::STANDARD.encode(thumbnail),
+            };
+            (content, Some(png))
+        }
+        (None, Some(text)) => {
+            let content = match text.char_indices().nth(MAX_TEXT_CHARS) {
+                Some((end, _)) => ClipboardContent::Text {
+                    text: text[..end].to_string(),
+                    truncated: true,
+                },
+                None => ClipboardContent::Text {
+                    text,
+                    truncated: false,
+                },
+            };
+            (content, None)
+        }
+        (None, None) => return,
+    };
+
+    let Ok(mut history) = state.0.lock() else {
+        return;
+    };
+    // Switched off while encoding
+    if !history.settings.enabled {
+        return;
+    }
+    history.next_id += 1;
+    let entry = ClipboardEntry {
+        id: history.next_id,
+        captured_at: now_ms(),
+        source_app,
+        content,
+    };
+    history.items.push_front(HistoryItem {
+        entry: entry.clone(),
+        png,
+    });
+    let max_entries = history.settings.max_entries;
+    history.items.truncate(max_entries);
+    drop(history);
+
+    if let Err(e) = app.emit("clipboard-captured", entry) {
+        log::warn!("Failed to emit clipboard-captured event: {e}");
+    }
+}
+
+/// A counter the system bumps whenever the clipboard changes, where there
+/// is one. Without it every poll reads and hashes the clipboard.
+#[cfg(windows)]
+fn change_count() -> Option<u64> {
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    // Zero means the sequence number isn't available to this session
+    match unsafe { GetClipboardSequenceNumber() } {
+        0 => None,
+        sequence => Some(sequence as u64),
+    }
+}
+
+/// A counter the system bumps whenever the clipboard changes, where there
+/// is one. Without it every poll reads and hashes the clipboard.
+#[cfg(target_os = "macos")]
+fn change_count() -> Option<u64> {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+
+    unsafe {
+        let pasteboard: *mut AnyObject = msg_send![class!(NSPasteboard), generalPasteboard];
+        if pasteboard.is_null() {
+            return None;
+        }
+        let count: isize = msg_send![pasteboard, changeCount];
+        Some(count as u64)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn change_count() -> Option<u64> {
+    None
+}
+
+/// Name of the app whose window is in front.
+#[cfg(windows)]
+fn frontmost_app() -> Option<String> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0u32;
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_invalid() {
+            return None;
+        }
+        GetWindowThreadProcessId(window, Some(&mut pid));
+    }
+    if pid == 0 {
+        return None;
+    }
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    system
+        .process(pid)
+        .map(|process| process.name().to_string_lossy().into_owned())
+}
+
+/// Name of the app whose window is in front.
+#[cfg(target_os = "macos")]
+fn frontmost_app() -> Option<String> {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use std::ffi::{c_char, CStr};
+
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let application: *mut AnyObject = msg_send![workspace, frontmostApplication];
+        if application.is_null() {
+            return None;
+        }
+        let name: *mut AnyObject = msg_send![application, localizedName];
+        if name.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![name, UTF8String];
+        if utf8.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn frontmost_app() -> Option<String> {
+    None
+}
+
+/// The captured entries, newest first.
+#[tauri::command]
+pub async fn get_clipboard_history(app: AppHandle) -> Result<Vec<ClipboardEntry>, String> {
+    let state = app.state::<ClipboardHistory>();
+    let history = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(history
+        .items
+        .iter()
+        .map(|item| item.entry.clone())
+        .collect())
+}
+
+#[tauri::command]
+pub async fn clear_clipboard_history(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<ClipboardHistory>();
+    state.0.lock().map_err(|e| e.to_string())?.items.clear();
+    log::info!("Cleared clipboard history");
+    Ok(())
+}
+
+/// Stores an image entry of the history as a PNG attachment.
+#[tauri::command]
+pub async fn attach_clipboard_entry(app: AppHandle, id: u64) -> Result<StoredAttachment, String> {
+    let png = {
+        let state = app.state::<ClipboardHistory>();
+        let history = state.0.lock().map_err(|e| e.to_string())?;
+        let item = history
+            .items
+            .iter()
+            .find(|item| item.entry.id == id)
+            .ok_or_else(|| format!("Clipboard entry {id} not found"))?;
+        item.png
+            .clone()
+            .ok_or_else(|| format!("Clipboard entry {id} is not an image"))?
+    };
+    crate::attachments::store(
+        &app,
+        base64::engine::general_purpose::STANDARD.encode(png),
+        Some("image/png".to_string()),
+    )
+}
+
+/// Stores the image on the clipboard as a PNG attachment.
+#[tauri::command]
+pub async fn paste_clipboard_as_attachment(app: AppHandle) -> Result<StoredAttachment, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = app
+            .clipboard()
+            .read_image()
+            .map_err(|_| "The clipboard holds no image".to_string())?;
+        let image = RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+            .ok_or("The clipboard image is malformed")?;
+        let png = encode_png(&image)?;
+        let stored = crate::attachments::store(
+            &app,
+            base64::engine::general_purpose::STANDARD.encode(png),
+            Some("image/png".to_string()),
+        )?;
+        log::info!(
+            "Pasted a {}x{} clipboard image as attachment {}",
+            image.width(),
+            image.height(),
+            stored.hash
+        );
+        Ok(stored)
+    })
+    .await
+    .map_err(|e| format!("Clipboard task failed: {e}"))?
+}
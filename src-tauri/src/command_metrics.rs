@@ -0,0 +1,235 @@
+// Command Metrics
+// ===============
+//
+// How long every IPC call takes, as the UI experiences it. The frontend
+// wraps the invoke layer, times each call from request to response, and
+// sends the samples here in batches with `record_command_metrics`; they
+// are kept in `command_samples` for a week. `get_command_metrics` sums them
+// up per command (calls, error rate, average, 95th percentile and slowest
+// duration, payload sizes), slowest in total first, which is where to look
+// when the UI feels sluggish.
+//
+// Calls slower than their command's latency budget are logged as warnings
+// when they are recorded. Commands that are long-running by design have no
+// budget.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+/// Budget for commands without their own entry in `LATENCY_BUDGETS`
+const DEFAULT_BUDGET_MS: f64 = 250.0;
+/// Per-command budgets in milliseconds; `None` for no budget
+const LATENCY_BUDGETS: &[(&str, Option<f64>)] = &[
+    ("import_conversations", None),
+    ("export_all_conversations", None),
+    ("export_training_data", None),
+    ("migrate_storage", None),
+    ("ollama_pull_model", None),
+    ("benchmark_providers", None),
+    ("stream_chat_completion", None),
+    ("extract_tasks", None),
+    ("merge_backup", None),
+    ("list_provider_models", Some(3000.0)),
+    ("ollama_list_models", Some(1000.0)),
+    ("search_messages", Some(500.0)),
+    ("get_usage_stats", Some(500.0)),
+];
+const RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+const MAX_SAMPLES: i64 = 50_000;
+const MAX_BATCH: usize = 1000;
+const MAX_COMMAND_NAME_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSample {
+    /// Command name, with the plugin prefix for plugin commands
+    pub command: String,
+    pub duration_ms: f64,
+    /// Serialized arguments
+    pub request_bytes: u64,
+    /// Serialized response or error
+    pub response_bytes: u64,
+    pub error: bool,
+    /// Milliseconds since the UNIX epoch
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandMetrics {
+    pub command: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// 0.0 to 1.0
+    pub error_rate: f64,
+    pub avg_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+    /// Calls over the latency budget
+    pub slow_calls: u64,
+    pub budget_ms: Option<f64>,
+    pub avg_request_bytes: u64,
+    pub avg_response_bytes: u64,
+}
+
+fn budget(command: &str) -> Option<f64> {
+    LATENCY_BUDGETS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or(Some(DEFAULT_BUDGET_MS), |(_, budget)| *budget)
+}
+
+fn is_slow(command: &str, duration_ms: f64) -> bool {
+    budget(command).is_some_and(|budget| duration_ms > budget)
+}
+
+/// Stores a batch of samples from the frontend and logs the slow ones.
+#[tauri::command]
+pub async fn record_command_metrics(
+    app: AppHandle,
+    samples: Vec<CommandSample>,
+) -> Result<(), String> {
+    if samples.len() > MAX_BATCH {
+        return Err(format!("Too many samples (max {MAX_BATCH})"));
+    }
+    for sample in &samples {
+        crate::validate_string_input(&sample.command, MAX_COMMAND_NAME_LENGTH, "Command")?;
+        if is_slow(&sample.command, sample.duration_ms) {
+            log::warn!(
+                "Slow command {}: {:.0}ms (budget {:.0}ms, {} bytes in, {} bytes out)",
+                sample.command,
+                sample.duration_ms,
+                budget(&sample.command).unwrap_or_default(),
+                sample.request_bytes,
+                sample.response_bytes
+            );
+        }
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut conn = crate::db::open(&app)?;
+        let now = crate::time::now_ms() as i64;
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            {
+                let mut insert = tx.prepare(
+                    "INSERT INTO command_samples
+                         (command, duration_ms, request_bytes, response_bytes, error, recorded_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for sample in &samples {
+                    insert.execute(params![
+                        sample.command,
+                        sample.duration_ms.max(0.0),
+                        sample.request_bytes as i64,
+                        sample.response_bytes as i64,
+                        sample.error,
+                        sample.at as i64
+                    ])?;
+                }
+            }
+            tx.execute(
+                "DELETE FROM command_samples WHERE recorded_at < ?1",
+                params![now - RETENTION_MS],
+            )?;
+            tx.execute(
+                "DELETE FROM command_samples
+                 WHERE id <= (SELECT MAX(id) FROM command_samples) - ?1",
+                params![MAX_SAMPLES],
+            )?;
+            tx.commit()
+        })();
+        result.map_err(|e| {
+            log::error!("Failed to record command metrics: {e}");
+            format!("Failed to record command metrics: {e}")
+        })
+    })
+    .await
+    .map_err(|e| format!("Command metrics task failed: {e}"))?
+}
+
+/// Per-command metrics over the last `since` milliseconds (the whole
+/// week kept by default), slowest in total first.
+#[tauri::command]
+pub async fn get_command_metrics(
+    app: AppHandle,
+    since: Option<u64>,
+) -> Result<Vec<CommandMetrics>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = crate::db::open(&app)?;
+        let from = since.map_or(0, |since| crate::time::now_ms() as i64 - since as i64);
+
+        // Durations per command, sorted, for the percentile
+        let mut samples: BTreeMap<String, (Vec<f64>, u64, u64, u64)> = BTreeMap::new();
+        conn.prepare(
+            "SELECT command, duration_ms, error, request_bytes, response_bytes
+             FROM command_samples WHERE recorded_at >= ?1
+             ORDER BY command, duration_ms",
+        )
+        .and_then(|mut stmt| {
+            let rows = stmt.query_map(params![from], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, i64>(3)? as u64,
+                    row.get::<_, i64>(4)? as u64,
+                ))
+            })?;
+            for row in rows {
+                let (command, duration, error, request, response) = row?;
+                let entry = samples.entry(command).or_default();
+                entry.0.push(duration);
+                entry.1 += u64::from(error);
+                entry.2 += request;
+                entry.3 += response;
+            }
+            Ok(())
+        })
+        .map_err(|e| {
+            log::error!("Failed to read command metrics: {e}");
+            format!("Failed to read command metrics: {e}")
+        })?;
+
+        let mut metrics: Vec<CommandMetrics> = samples
+            .into_iter()
+            .map(|(command, (durations, errors, request, response))| {
+                let calls = durations.len() as u64;
+                let total_ms: f64 = durations.iter().sum();
+                let p95_index =
+                    ((durations.len() as f64 * 0.95).ceil() as usize).clamp(1, durations.len()) - 1;
+                CommandMetrics {
+                    calls,
+                    errors,
+                    error_rate: errors as f64 / calls as f64,
+                    avg_ms: total_ms / calls as f64,
+                    p95_ms: durations[p95_index],
+                    max_ms: durations.last().copied().unwrap_or_default(),
+                    total_ms,
+                    slow_calls: durations.iter().filter(|&&d| is_slow(&command, d)).count() as u64,
+                    budget_ms: budget(&command),
+                    avg_request_bytes: request / calls,
+                    avg_response_bytes: response / calls,
+                    command,
+                }
+            })
+            .collect();
+        metrics.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        Ok(metrics)
+    })
+    .await
+    .map_err(|e| format!("Command metrics task failed: {e}"))?
+}
+
+/// Deletes every recorded sample.
+#[tauri::command]
+pub async fn clear_command_metrics(app: AppHandle) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    conn.execute("DELETE FROM command_samples", [])
+        .map_err(|e| format!("Failed to clear command metrics: {e}"))?;
+    log::info!("Cleared command metrics");
+    Ok(())
+}
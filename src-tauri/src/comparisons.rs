@@ -0,0 +1,404 @@
+// Linked Comparison Sessions
+// ==========================
+//
+// The comparison view sends one prompt to several models, each answering in
+// its own conversation. A comparison records that fan-out: the linked
+// conversations (threads), and every prompt sent to all of them (turns) with
+// the id of the matching user message in each thread, so split panes can
+// scroll in step and follow-ups can go to every linked model. Comparisons
+// live in the database; the conversations themselves stay in the mirror.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+
+use crate::conversations::Conversation;
+use crate::time::now_ms;
+
+const MIN_THREADS: usize = 2;
+const MAX_THREADS: usize = 8;
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_PROMPT_CHARS: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub id: String,
+    pub title: String,
+    /// Threads in pane order
+    pub threads: Vec<ComparisonThread>,
+    /// Prompts sent to every thread, oldest first
+    pub turns: Vec<ComparisonTurn>,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: i64,
+    /// Milliseconds since the UNIX epoch
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonThread {
+    pub conversation_id: String,
+    pub model_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonTurn {
+    /// Zero-based position of the turn in the comparison
+    pub turn: i64,
+    pub prompt: String,
+    /// Milliseconds since the UNIX epoch
+    pub sent_at: i64,
+    /// Conversation id to the id of the user message carrying this prompt
+    pub anchors: HashMap<String, String>,
+}
+
+fn validate_prompt(prompt: &str) -> Result<(), String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt is required".to_string());
+    }
+    crate::validate_string_input(prompt, MAX_PROMPT_CHARS, "Prompt")
+}
+
+/// Anchors must point into the comparison's own threads.
+fn validate_anchors(
+    anchors: &HashMap<String, String>,
+    threads: &[ComparisonThread],
+) -> Result<(), String> {
+    for (conversation_id, message_id) in anchors {
+        if !threads
+            .iter()
+            .any(|t| &t.conversation_id == conversation_id)
+        {
+            return Err(format!(
+                "Conversation {conversation_id} is not part of this comparison"
+            ));
+        }
+        crate::validate_string_input(message_id, 256, "Message id")?;
+    }
+    Ok(())
+}
+
+/// Reads every comparison, or only the one with `id`.
+fn read(conn: &Connection, id: Option<&str>) -> rusqlite::Result<Vec<Comparison>> {
+    let mut comparisons: Vec<Comparison> = conn
+        .prepare(
+            "SELECT id, title, created_at, updated_at FROM comparisons
+             WHERE ?1 IS NULL OR id = ?1 ORDER BY updated_at DESC",
+        )?
+        .query_map(params![id], |row| {
+            Ok(Comparison {
+                id: row.get("id")?,
+                title: row.get("title")?,
+                threads: Vec::new(),
+                turns: Vec::new(),
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let index: HashMap<String, usize> = comparisons
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.id.clone(), i))
+        .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT comparison_id, conversation_id, model_id FROM comparison_threads
+         WHERE ?1 IS NULL OR comparison_id = ?1 ORDER BY comparison_id, position",
+    )?;
+    let mut rows = stmt.query(params![id])?;
+    while let Some(row) = rows.next()? {
+        if let Some(&i) = index.get(&row.get::<_, String>(0)?) {
+            comparisons[i].threads.push(ComparisonThread {
+                conversation_id: row.get(1)?,
+                model_id: row.get(2)?,
+            });
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT comparison_id, turn, prompt, sent_at FROM comparison_turns
+         WHERE ?1 IS NULL OR comparison_id = ?1 ORDER BY comparison_id, turn",
+    )?;
+    let mut rows = stmt.query(params![id])?;
+    while let Some(row) = rows.next()? {
+        if let Some(&i) = index.get(&row.get::<_, String>(0)?) {
+            comparisons[i].turns.push(ComparisonTurn {
+                turn: row.get(1)?,
+                prompt: row.get(2)?,
+                sent_at: row.get(3)?,
+                anchors: HashMap::new(),
+            });
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT comparison_id, turn, conversation_id, message_id FROM comparison_anchors
+         WHERE ?1 IS NULL OR comparison_id = ?1",
+    )?;
+    let mut rows = stmt.query(params![id])?;
+    while let Some(row) = rows.next()? {
+        let Some(&i) = index.get(&row.get::<_, String>(0)?) else {
+            continue;
+        };
+        let turn: i64 = row.get(1)?;
+        if let Some(turn) = comparisons[i].turns.iter_mut().find(|t| t.turn == turn) {
+            turn.anchors.insert(row.get(2)?, row.get(3)?);
+        }
+    }
+
+    Ok(comparisons)
+}
+
+fn read_one(conn: &Connection, id: &str) -> Result<Comparison, String> {
+    read(conn, Some(id))
+        .map_err(|e| format!("Failed to read comparison: {e}"))?
+        .pop()
+        .ok_or_else(|| format!("Comparison {id} not found"))
+}
+
+/// Every comparison, most recently active first.
+pub fn load_all(app: &AppHandle) -> Result<Vec<Comparison>, String> {
+    let conn = crate::db::open(app)?;
+    read(&conn, None).map_err(|e| format!("Failed to list comparisons: {e}"))
+}
+
+/// Appends a turn inside `tx` and returns it.
+fn insert_turn(
+    tx: &rusqlite::Transaction,
+    comparison_id: &str,
+    prompt: &str,
+    anchors: HashMap<String, String>,
+) -> rusqlite::Result<ComparisonTurn> {
+    let turn: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(turn) + 1, 0) FROM comparison_turns WHERE comparison_id = ?1",
+        params![comparison_id],
+        |row| row.get(0),
+    )?;
+    let sent_at = now_ms() as i64;
+    tx.execute(
+        "INSERT INTO comparison_turns (comparison_id, turn, prompt, sent_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![comparison_id, turn, prompt, sent_at],
+    )?;
+    let mut insert = tx.prepare(
+        "INSERT INTO comparison_anchors (comparison_id, turn, conversation_id, message_id)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (conversation_id, message_id) in &anchors {
+        insert.execute(params![comparison_id, turn, conversation_id, message_id])?;
+    }
+    tx.execute(
+        "UPDATE comparisons SET updated_at = ?2 WHERE id = ?1",
+        params![comparison_id, sent_at],
+    )?;
+
+    Ok(ComparisonTurn {
+        turn,
+        prompt: prompt.to_string(),
+        sent_at,
+        anchors,
+    })
+}
+
+/// Drops a deleted conversation from its comparison. A comparison left with
+/// a single thread is no longer a comparison and is removed.
+pub fn unlink_conversation(app: &AppHandle, conversation_id: &str) -> Result<(), String> {
+    let conn = crate::db::open(app)?;
+    let comparison_id: Option<String> = conn
+        .query_row(
+            "DELETE FROM comparison_threads WHERE conversation_id = ?1 RETURNING comparison_id",
+            params![conversation_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to unlink conversation: {e}"))?;
+    let Some(comparison_id) = comparison_id else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "DELETE FROM comparison_anchors WHERE comparison_id = ?1 AND conversation_id = ?2",
+        params![comparison_id, conversation_id],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "DELETE FROM comparisons WHERE id = ?1
+             AND (SELECT COUNT(*) FROM comparison_threads WHERE comparison_id = ?1) < ?2",
+            params![comparison_id, MIN_THREADS as i64],
+        )
+    })
+    .map_err(|e| format!("Failed to unlink conversation: {e}"))?;
+    log::info!("Unlinked conversation {conversation_id} from comparison {comparison_id}");
+    Ok(())
+}
+
+/// Records a prompt fanned out to several models. The frontend creates one
+/// conversation per model first; `threads` links them in pane order and
+/// `anchors` maps each conversation to the user message holding the prompt.
+#[tauri::command]
+pub async fn create_comparison(
+    app: AppHandle,
+    title: String,
+    prompt: String,
+    threads: Vec<ComparisonThread>,
+    anchors: Option<HashMap<String, String>>,
+) -> Result<Comparison, String> {
+    crate::validate_string_input(&title, MAX_TITLE_CHARS, "Comparison title")?;
+    validate_prompt(&prompt)?;
+    if !(MIN_THREADS..=MAX_THREADS).contains(&threads.len()) {
+        return Err(format!(
+            "A comparison needs {MIN_THREADS} to {MAX_THREADS} models"
+        ));
+    }
+    let mut seen = HashSet::new();
+    for thread in &threads {
+        crate::validate_filename(&thread.conversation_id)?;
+        crate::validate_string_input(&thread.model_id, 256, "Model id")?;
+        if !seen.insert(&thread.conversation_id) {
+            return Err(format!(
+                "Conversation {} is linked twice",
+                thread.conversation_id
+            ));
+        }
+    }
+    let anchors = anchors.unwrap_or_default();
+    validate_anchors(&anchors, &threads)?;
+
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Failed to generate comparison id: {e}"))?;
+    let id: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    let mut conn = crate::db::open(&app)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to create comparison: {e}"))?;
+    for thread in &threads {
+        let linked: Option<String> = tx
+            .query_row(
+                "SELECT comparison_id FROM comparison_threads WHERE conversation_id = ?1",
+                params![thread.conversation_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to create comparison: {e}"))?;
+        if linked.is_some() {
+            return Err(format!(
+                "Conversation {} is already part of a comparison",
+                thread.conversation_id
+            ));
+        }
+    }
+
+    let created_at = now_ms() as i64;
+    tx.execute(
+        "INSERT INTO comparisons (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+        params![id, title.trim(), created_at],
+    )
+    .and_then(|_| {
+        let mut insert = tx.prepare(
+            "INSERT INTO comparison_threads (comparison_id, conversation_id, model_id, position)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (position, thread) in threads.iter().enumerate() {
+            insert.execute(params![
+                id,
+                thread.conversation_id,
+                thread.model_id,
+                position as i64
+            ])?;
+        }
+        Ok(())
+    })
+    .and_then(|_| insert_turn(&tx, &id, &prompt, anchors))
+    .and_then(|_| tx.commit())
+    .map_err(|e| {
+        log::error!("Failed to create comparison: {e}");
+        format!("Failed to create comparison: {e}")
+    })?;
+
+    log::info!("Created comparison {id} across {} models", threads.len());
+    read_one(&conn, &id)
+}
+
+/// Records a follow-up prompt sent to every thread of a comparison.
+#[tauri::command]
+pub async fn record_comparison_turn(
+    app: AppHandle,
+    comparison_id: String,
+    prompt: String,
+    anchors: Option<HashMap<String, String>>,
+) -> Result<ComparisonTurn, String> {
+    validate_prompt(&prompt)?;
+    let mut conn = crate::db::open(&app)?;
+    let comparison = read_one(&conn, &comparison_id)?;
+    let anchors = anchors.unwrap_or_default();
+    validate_anchors(&anchors, &comparison.threads)?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to record comparison turn: {e}"))?;
+    let turn = insert_turn(&tx, &comparison_id, &prompt, anchors)
+        .and_then(|turn| tx.commit().map(|_| turn))
+        .map_err(|e| {
+            log::error!("Failed to record comparison turn: {e}");
+            format!("Failed to record comparison turn: {e}")
+        })?;
+    Ok(turn)
+}
+
+#[tauri::command]
+pub async fn list_comparisons(app: AppHandle) -> Result<Vec<Comparison>, String> {
+    load_all(&app)
+}
+
+/// The comparison a conversation is linked into, if any, so a follow-up
+/// typed into one pane can be sent to every linked model.
+#[tauri::command]
+pub async fn get_linked_comparison(
+    app: AppHandle,
+    conversation_id: String,
+) -> Result<Option<Comparison>, String> {
+    let conn = crate::db::open(&app)?;
+    let comparison_id: Option<String> = conn
+        .query_row(
+            "SELECT comparison_id FROM comparison_threads WHERE conversation_id = ?1",
+            params![conversation_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read comparison: {e}"))?;
+    comparison_id.map(|id| read_one(&conn, &id)).transpose()
+}
+
+/// Unlinks a comparison. Its conversations are kept as ordinary chats.
+#[tauri::command]
+pub async fn delete_comparison(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    conn.execute("DELETE FROM comparisons WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete comparison: {e}"))?;
+    log::info!("Deleted comparison {id}");
+    Ok(())
+}
+
+/// Renders a comparison as Markdown, grouping each prompt with every
+/// model's answer to it.
+#[tauri::command]
+pub async fn export_comparison(app: AppHandle, id: String) -> Result<String, String> {
+    let comparison = {
+        let conn = crate::db::open(&app)?;
+        read_one(&conn, &id)?
+    };
+    let conversations: Vec<Conversation> = crate::conversations::load_all(&app)?
+        .into_iter()
+        .filter(|c| comparison.threads.iter().any(|t| t.conversation_id == c.id))
+        .collect();
+    Ok(crate::export::render_comparison_markdown(
+        &comparison,
+        &conversations,
+    ))
+}
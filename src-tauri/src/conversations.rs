@@ -0,0 +1,250 @@
+// Conversation Mirror
+// ===================
+//
+// The frontend owns conversation state, but background features (scheduled
+// exports, backups) need to read it while the webview is idle. The frontend
+// mirrors each conversation here whenever it changes, and we keep one JSON
+// file per conversation under `conversations/` in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+// Matches the `Conversation` / `ChatMessage` types in src/store/chat-store.ts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub messages: Vec<ChatMessage>,
+    pub model_id: String,
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    /// Milliseconds since the UNIX epoch
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatMessage {
+    pub id: String,
+    pub role: String,
+    /// Either a plain string or an array of content parts
+    pub content: Value,
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallData>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    /// Tokens the response took, on assistant messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MessageUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageUsage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// The model that answered, which may differ from the conversation's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// In US dollars, when the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallData {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+}
+
+/// Concatenated text of message content, ignoring non-text parts.
+pub fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|p| p.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+impl ChatMessage {
+    /// Concatenated text of the message, ignoring non-text parts.
+    pub fn text(&self) -> String {
+        content_text(&self.content)
+    }
+
+    /// Non-text content parts (images, audio, files).
+    pub fn attachments(&self) -> Vec<&Value> {
+        match &self.content {
+            Value::Array(parts) => parts
+                .iter()
+                .filter(|p| p.get("type").and_then(Value::as_str) != Some("text"))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn get_conversations_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let conversations_dir = app_data_dir.join("conversations");
+
+    // Ensure the conversations directory exists
+    std::fs::create_dir_all(&conversations_dir)
+        .map_err(|e| format!("Failed to create conversations directory: {e}"))?;
+
+    Ok(conversations_dir)
+}
+
+/// Loads every mirrored conversation, skipping files that fail to parse.
+pub fn load_all(app: &AppHandle) -> Result<Vec<Conversation>, String> {
+    let dir = get_conversations_dir(app)?;
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        log::error!("Failed to read conversations directory: {e}");
+        format!("Failed to read directory: {e}")
+    })?;
+
+    let mut conversations = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| serde_json::from_str::<Conversation>(&c).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(mut conversation) => {
+                crate::attachments::rehydrate(app, &mut conversation);
+                conversations.push(conversation);
+            }
+            Err(e) => log::warn!("Skipping unreadable conversation {path:?}: {e}"),
+        }
+    }
+
+    Ok(conversations)
+}
+
+/// Reads one mirrored conversation, or `None` if it has no file.
+pub fn load(app: &AppHandle, id: &str) -> Result<Option<Conversation>, String> {
+    crate::validate_filename(id)?;
+    let file_path = get_conversations_dir(app)?.join(format!("{id}.json"));
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&file_path).map_err(|e| {
+        log::error!("Failed to read conversation file: {e}");
+        format!("Failed to read conversation file: {e}")
+    })?;
+    let mut conversation: Conversation = serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse conversation {id}: {e}");
+        format!("Failed to parse conversation: {e}")
+    })?;
+    crate::attachments::rehydrate(app, &mut conversation);
+    Ok(Some(conversation))
+}
+
+/// Ids of every mirrored conversation.
+pub fn ids(app: &AppHandle) -> Result<Vec<String>, String> {
+    let entries = std::fs::read_dir(get_conversations_dir(app)?)
+        .map_err(|e| format!("Failed to read directory: {e}"))?;
+    let mut ids: Vec<String> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Writes a conversation to its mirror file, with attachment payloads moved
+/// to the attachment store.
+pub fn save(app: &AppHandle, conversation: &Conversation) -> Result<(), String> {
+    crate::validate_filename(&conversation.id)?;
+
+    let file_path = get_conversations_dir(app)?.join(format!("{}.json", conversation.id));
+
+    let stored = crate::attachments::externalize(app, conversation)?;
+    let json_content = serde_json::to_string(&stored).map_err(|e| {
+        log::error!("Failed to serialize conversation: {e}");
+        format!("Failed to serialize conversation: {e}")
+    })?;
+
+    // Write to a temporary file first, then rename (atomic operation)
+    let temp_path = file_path.with_extension("tmp");
+
+    std::fs::write(&temp_path, json_content).map_err(|e| {
+        log::error!("Failed to write conversation file: {e}");
+        format!("Failed to write conversation file: {e}")
+    })?;
+
+    std::fs::rename(&temp_path, &file_path).map_err(|e| {
+        log::error!("Failed to finalize conversation file: {e}");
+        format!("Failed to finalize conversation file: {e}")
+    })
+}
+
+#[tauri::command]
+pub async fn sync_conversation(app: AppHandle, conversation: Conversation) -> Result<(), String> {
+    log::debug!("Syncing conversation {}", conversation.id);
+    save(&app, &conversation)?;
+
+    if let Err(e) =
+        crate::prompt_history::record(&app, &conversation.id, &conversation.system_prompt)
+    {
+        log::warn!("Failed to record system prompt history: {e}");
+    }
+    Ok(())
+}
+
+/// Deletes a conversation's mirror file and releases its attachments.
+/// Returns whether the file existed.
+pub fn remove(app: &AppHandle, id: &str) -> Result<bool, String> {
+    crate::validate_filename(id)?;
+
+    let file_path = get_conversations_dir(app)?.join(format!("{id}.json"));
+    let existed = file_path.exists();
+    if existed {
+        std::fs::remove_file(&file_path).map_err(|e| {
+            log::error!("Failed to remove conversation file: {e}");
+            format!("Failed to remove conversation file: {e}")
+        })?;
+    }
+
+    crate::attachments::release_conversation(app, id)?;
+    Ok(existed)
+}
+
+#[tauri::command]
+pub async fn remove_synced_conversation(app: AppHandle, id: String) -> Result<(), String> {
+    crate::validate_filename(&id)?;
+    log::info!("Removing synced conversation {id}");
+
+    if let Err(e) = crate::comparisons::unlink_conversation(&app, &id) {
+        log::warn!("Failed to unlink conversation {id} from its comparison: {e}");
+    }
+
+    remove(&app, &id).map(|_| ())
+}
@@ -0,0 +1,456 @@
+// Daemon Mode
+// ===========
+//
+// `nexus --daemon` runs the backend without opening a window, so MCP
+// servers, the scheduler and watch folders keep running for automations,
+// and closing a window later does not quit the app. The daemon accepts
+// one-line JSON requests on a loopback TCP port, authenticated with a
+// random token; both are written to `daemon.json` in the app data
+// directory, which only the user can read.
+//
+// Launching the app normally while a daemon runs does not start a second
+// backend. The new process asks the daemon to open its window and exits,
+// so the UI attaches to the daemon's running state: the daemon emits
+// `daemon-ui-attached` and the frontend resyncs with `get_daemon_status`.
+// `nexus --daemon-stop` asks a running daemon to quit.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const DAEMON_FLAG: &str = "--daemon";
+const STOP_FLAG: &str = "--daemon-stop";
+const MAIN_WINDOW: &str = "main";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Room for a deep link, which can carry a prompt
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// Where a running daemon can be reached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DaemonEndpoint {
+    port: u16,
+    token: String,
+    pid: u32,
+    /// Milliseconds since the UNIX epoch
+    started_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonCommand {
+    /// Open (or focus) the daemon's window
+    Show,
+    Status,
+    Quit,
+    /// A dock menu or jump list action from another launch
+    Run(crate::quick_actions::QuickAction),
+    /// A `nexus://` link another launch was opened with
+    OpenLink(crate::deep_link::RawLink),
+    /// A `.nexus` bundle another launch was opened with
+    OpenBundle(PathBuf),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonRequest {
+    token: String,
+    command: DaemonCommand,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonResponse {
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<DaemonStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonStatus {
+    /// The backend runs as a daemon and outlives its window
+    pub daemon: bool,
+    pub pid: u32,
+    /// Milliseconds since the UNIX epoch
+    pub started_at: u64,
+    /// MCP servers the backend is running, which the UI should reconnect to
+    pub running_mcp_servers: Vec<String>,
+}
+
+/// How this process was started; managed as app state.
+pub struct DaemonState {
+    daemon: bool,
+    started_at: u64,
+}
+
+impl Default for DaemonState {
+    fn default() -> Self {
+        Self {
+            daemon: requested(),
+            started_at: crate::time::now_ms(),
+        }
+    }
+}
+
+impl DaemonState {
+    pub fn is_daemon(&self) -> bool {
+        self.daemon
+    }
+
+    /// Milliseconds since the UNIX epoch
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+}
+
+/// Whether the app was started with `--daemon`.
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == DAEMON_FLAG)
+}
+
+/// Whether the app was started with `--daemon-stop`.
+pub fn stop_requested() -> bool {
+    std::env::args().any(|arg| arg == STOP_FLAG)
+}
+
+fn get_endpoint_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::base_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("daemon.json"))
+}
+
+fn status(app: &AppHandle) -> DaemonStatus {
+    let state = app.state::<DaemonState>();
+    DaemonStatus {
+        daemon: state.daemon,
+        pid: std::process::id(),
+        started_at: state.started_at,
+        running_mcp_servers: crate::mcp::running_server_ids(app),
+    }
+}
+
+/// Shows the main window, creating it from the app config if it does not
+/// exist yet, with the geometry it was left with (see `window_state`).
+pub fn show_main_window(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        return window
+            .show()
+            .and_then(|_| window.unminimize())
+            .and_then(|_| window.set_focus())
+            .map_err(|e| format!("Failed to show window: {e}"));
+    }
+
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == MAIN_WINDOW)
+        .ok_or("The main window is not configured")?;
+    // Hidden until it is back where it was left
+    let window = tauri::WebviewWindowBuilder::from_config(app, config)
+        .and_then(|builder| builder.visible(false).build())
+        .map_err(|e| {
+            log::error!("Failed to create main window: {e}");
+            format!("Failed to create main window: {e}")
+        })?;
+    crate::window_state::restore(app, &window)
+}
+
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn handle_connection(app: &AppHandle, token: &str, stream: TcpStream) -> Result<(), String> {
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_BYTES))
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read daemon request: {e}"))?;
+
+    let mut quit = false;
+    let response = match serde_json::from_str::<DaemonRequest>(&line) {
+        Ok(request) if token_matches(token, &request.token) => {
+            log::info!("Daemon request: {:?}", request.command);
+            match request.command {
+                DaemonCommand::Show => match show_main_window(app) {
+                    Ok(()) => {
+                        let _ = app.emit("daemon-ui-attached", status(app));
+                        DaemonResponse {
+                            ok: true,
+                            error: None,
+                            status: None,
+                        }
+                    }
+                    Err(e) => DaemonResponse {
+                        ok: false,
+                        error: Some(e),
+                        status: None,
+                    },
+                },
+                DaemonCommand::Status => DaemonResponse {
+                    ok: true,
+                    error: None,
+                    status: Some(status(app)),
+                },
+                DaemonCommand::Run(action) => match crate::quick_actions::perform(app, action) {
+                    Ok(()) => DaemonResponse {
+                        ok: true,
+                        error: None,
+                        status: None,
+                    },
+                    Err(e) => DaemonResponse {
+                        ok: false,
+                        error: Some(e),
+                        status: None,
+                    },
+                },
+                DaemonCommand::OpenLink(link) => match crate::deep_link::open(app, &link.0) {
+                    Ok(()) => DaemonResponse {
+                        ok: true,
+                        error: None,
+                        status: None,
+                    },
+                    Err(e) => DaemonResponse {
+                        ok: false,
+                        error: Some(e),
+                        status: None,
+                    },
+                },
+                // Reading a large bundle may take longer than the launch waits
+                DaemonCommand::OpenBundle(path) => {
+                    let app = app.clone();
+                    std::thread::spawn(move || {
+                        let _ = crate::bundle::open(&app, &path);
+                    });
+                    DaemonResponse {
+                        ok: true,
+                        error: None,
+                        status: None,
+                    }
+                }
+                DaemonCommand::Quit => {
+                    quit = true;
+                    DaemonResponse {
+                        ok: true,
+                        error: None,
+                        status: None,
+                    }
+                }
+            }
+        }
+        Ok(_) => {
+            log::warn!("Rejected daemon request with a wrong token");
+            DaemonResponse {
+                ok: false,
+                error: Some("Invalid token".to_string()),
+                status: None,
+            }
+        }
+        Err(e) => DaemonResponse {
+            ok: false,
+            error: Some(format!("Invalid request: {e}")),
+            status: None,
+        },
+    };
+
+    let mut reply = serde_json::to_string(&response).map_err(|e| e.to_string())?;
+    reply.push('\n');
+    (&stream)
+        .write_all(reply.as_bytes())
+        .map_err(|e| format!("Failed to answer daemon request: {e}"))?;
+
+    if quit {
+        log::info!("Daemon quitting on request");
+        app.exit(0);
+    }
+    Ok(())
+}
+
+/// Writes the endpoint file through a temporary file that is created
+/// readable by the user only, so the token is never visible to others, not
+/// even briefly.
+fn write_endpoint(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let temp_path = path.with_extension("json.tmp");
+    // A leftover file would keep its mode; `create_new` only applies ours to a new one
+    match std::fs::remove_file(&temp_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to write daemon endpoint: {e}")),
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&temp_path)
+        .map_err(|e| format!("Failed to write daemon endpoint: {e}"))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write daemon endpoint: {e}"))?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path).map_err(|e| format!("Failed to write daemon endpoint: {e}"))
+}
+
+/// Starts listening for requests and publishes the endpoint. Called once
+/// at startup in daemon mode.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .map_err(|e| format!("Failed to start daemon listener: {e}"))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate token: {e}"))?;
+    let endpoint = DaemonEndpoint {
+        port,
+        token: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        pid: std::process::id(),
+        started_at: app.state::<DaemonState>().started_at,
+    };
+    let json = serde_json::to_vec_pretty(&endpoint)
+        .map_err(|e| format!("Failed to serialize daemon endpoint: {e}"))?;
+    write_endpoint(&get_endpoint_path(app)?, &json)?;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream
+                .map_err(|e| e.to_string())
+                .and_then(|stream| handle_connection(&app, &endpoint.token, stream));
+            if let Err(e) = result {
+                log::warn!("Daemon connection failed: {e}");
+            }
+        }
+    });
+
+    log::info!("Running as a daemon, listening on 127.0.0.1:{port}");
+    Ok(())
+}
+
+/// Removes the endpoint file if this process published it. Called on exit.
+pub fn shutdown(app: &AppHandle) {
+    let Ok(path) = get_endpoint_path(app) else {
+        return;
+    };
+    let owned = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<DaemonEndpoint>(&contents).ok())
+        .is_some_and(|endpoint| endpoint.pid == std::process::id());
+    if owned {
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+/// Sends a command to a running daemon. Returns `None` when no daemon is
+/// reachable.
+pub fn send(app: &AppHandle, command: DaemonCommand) -> Result<Option<DaemonStatus>, String> {
+    let Ok(contents) = std::fs::read_to_string(get_endpoint_path(app)?) else {
+        return Ok(None);
+    };
+    let endpoint: DaemonEndpoint = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse daemon endpoint: {e}"))?;
+    if endpoint.pid == std::process::id() {
+        return Ok(None);
+    }
+
+    // A leftover file from a daemon that crashed points at a closed port
+    let Ok(stream) = TcpStream::connect_timeout(
+        &SocketAddr::from(([127, 0, 0, 1], endpoint.port)),
+        CONNECT_TIMEOUT,
+    ) else {
+        return Ok(None);
+    };
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+
+    let mut request = serde_json::to_string(&DaemonRequest {
+        token: endpoint.token,
+        command,
+    })
+    .map_err(|e| e.to_string())?;
+    request.push('\n');
+    (&stream)
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to contact daemon: {e}"))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read daemon response: {e}"))?;
+    let response: DaemonResponse =
+        serde_json::from_str(&line).map_err(|e| format!("Invalid daemon response: {e}"))?;
+    if !response.ok {
+        return Err(response
+            .error
+            .unwrap_or_else(|| "Daemon request failed".to_string()));
+    }
+
+    Ok(Some(response.status.unwrap_or(DaemonStatus {
+        daemon: true,
+        pid: endpoint.pid,
+        started_at: endpoint.started_at,
+        running_mcp_servers: Vec::new(),
+    })))
+}
+
+/// How the backend is running, for a window that attaches to a daemon.
+#[tauri::command]
+pub async fn get_daemon_status(app: AppHandle) -> Result<DaemonStatus, String> {
+    Ok(status(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_matches_only_the_exact_token() {
+        assert!(token_matches("secret-token", "secret-token"));
+        assert!(!token_matches("secret-token", "secret-tokem"));
+        assert!(!token_matches("secret-token", "secret"));
+        assert!(!token_matches("secret-token", "secret-token-and-more"));
+        assert!(!token_matches("secret-token", ""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn endpoint_file_is_private_even_over_a_leftover_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("nexus-daemon-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("daemon.json");
+        let temp_path = dir.join("daemon.json.tmp");
+        std::fs::write(&temp_path, "stale").unwrap();
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_endpoint(&path, b"{}").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+        assert!(!temp_path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
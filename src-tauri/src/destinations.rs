@@ -0,0 +1,381 @@
+// Share Destinations
+// ==================
+//
+// A small registry of named places exports and shares can be sent to:
+// local folders, webhook endpoints, email recipients (through the default
+// mail client via mailto) and WebDAV shares. The registry lives in
+// `destinations.json` in the app data directory. Secrets (webhook bearer
+// tokens, WebDAV passwords) never touch that file; they are kept in the
+// keychain under `destination:<id>`.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::keychain;
+
+const MAX_DESTINATIONS: usize = 100;
+const MAX_NAME_CHARS: usize = 100;
+/// Largest payload sent to a destination
+const MAX_CONTENT_BYTES: usize = 50 * 1024 * 1024;
+/// Mail clients truncate or reject long mailto URLs
+const MAX_MAILTO_BODY_CHARS: usize = 1800;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Destination {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: DestinationKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum DestinationKind {
+    /// Files are written into an existing local folder
+    Folder { path: String },
+    /// Content is POSTed as JSON; the credential is sent as a bearer token
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Opens a new message in the default mail client
+    Email {
+        to: Vec<String>,
+        #[serde(default)]
+        subject_prefix: Option<String>,
+    },
+    /// Files are PUT into a collection; the credential is the password
+    WebDav { url: String, username: String },
+}
+
+/// A destination as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestinationInfo {
+    #[serde(flatten)]
+    pub destination: Destination,
+    /// A secret is stored in the keychain
+    pub has_credential: bool,
+}
+
+fn credential_account(id: &str) -> String {
+    format!("destination:{id}")
+}
+
+fn get_destinations_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("destinations.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<Destination>, String> {
+    let path = get_destinations_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read destinations: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse destinations: {e}");
+        format!("Failed to parse destinations: {e}")
+    })
+}
+
+fn store(app: &AppHandle, destinations: &[Destination]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(destinations)
+        .map_err(|e| format!("Failed to serialize destinations: {e}"))?;
+    crate::export::write_atomic(&get_destinations_path(app)?, &json)
+}
+
+fn parse_http_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Only http and https URLs are supported".to_string());
+    }
+    Ok(parsed)
+}
+
+fn validate_email(email: &str) -> Result<(), String> {
+    let valid = email.len() <= 254
+        && !email
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, ',' | '?' | '&'))
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid email address: {email}"))
+    }
+}
+
+fn validate(destination: &Destination) -> Result<(), String> {
+    crate::validate_filename(&destination.id)?;
+    if destination.name.trim().is_empty() {
+        return Err("Destination name is required".to_string());
+    }
+    crate::validate_string_input(&destination.name, MAX_NAME_CHARS, "Destination name")?;
+
+    match &destination.kind {
+        DestinationKind::Folder { path } => {
+            if !Path::new(path).is_absolute() {
+                return Err("Destination folder must be an absolute path".to_string());
+            }
+        }
+        DestinationKind::Webhook { url, headers } => {
+            parse_http_url(url)?;
+            for name in headers.keys() {
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| format!("Invalid header name: {name}"))?;
+            }
+        }
+        DestinationKind::Email { to, .. } => {
+            if to.is_empty() {
+                return Err("Add at least one recipient".to_string());
+            }
+            to.iter().try_for_each(|email| validate_email(email))?;
+        }
+        DestinationKind::WebDav { url, username } => {
+            parse_http_url(url)?;
+            crate::validate_string_input(username, 256, "WebDAV username")?;
+        }
+    }
+    Ok(())
+}
+
+/// Percent-encodes a mailto component (RFC 6068).
+fn mailto_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn content_type(file_name: &str) -> &'static str {
+    match Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("html") => "text/html; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    crate::network::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+/// Sends `content` to a destination as a file named `file_name`.
+pub async fn deliver(
+    app: &AppHandle,
+    destination: &Destination,
+    file_name: &str,
+    content: &str,
+) -> Result<(), String> {
+    match &destination.kind {
+        DestinationKind::Folder { path } => {
+            let folder = Path::new(path);
+            if !folder.is_dir() {
+                return Err(format!("Destination folder {path} does not exist"));
+            }
+            crate::export::write_atomic(&folder.join(file_name), content.as_bytes())
+        }
+        DestinationKind::Webhook { url, headers } => {
+            let mut request = http_client()?
+                .post(parse_http_url(url)?)
+                .json(&serde_json::json!({
+                    "fileName": file_name,
+                    "content": content,
+                    "sentAt": chrono::Utc::now().to_rfc3339(),
+                }));
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            if let Some(token) = keychain::get(&credential_account(&destination.id))? {
+                request = request.bearer_auth(token);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send to {}: {e}", destination.name))?;
+            if !response.status().is_success() {
+                return Err(format!(
+                    "{} returned {}",
+                    destination.name,
+                    response.status()
+                ));
+            }
+            Ok(())
+        }
+        DestinationKind::Email { to, subject_prefix } => {
+            let subject = match subject_prefix.as_deref().filter(|p| !p.trim().is_empty()) {
+                Some(prefix) => format!("{} {file_name}", prefix.trim()),
+                None => file_name.to_string(),
+            };
+            let mut body: String = content.chars().take(MAX_MAILTO_BODY_CHARS).collect();
+            if body.len() < content.len() {
+                body.push_str("\n\n[truncated]");
+            }
+            let url = format!(
+                "mailto:{}?subject={}&body={}",
+                to.join(","),
+                mailto_encode(&subject),
+                mailto_encode(&body)
+            );
+
+            use tauri_plugin_opener::OpenerExt;
+            app.opener()
+                .open_url(url, None::<&str>)
+                .map_err(|e| format!("Failed to open mail client: {e}"))
+        }
+        DestinationKind::WebDav { url, username } => {
+            let mut collection = parse_http_url(url)?;
+            if !collection.path().ends_with('/') {
+                collection.set_path(&format!("{}/", collection.path()));
+            }
+            let target = collection
+                .join(file_name)
+                .map_err(|e| format!("Invalid file name: {e}"))?;
+            let password = keychain::get(&credential_account(&destination.id))?;
+
+            let response = http_client()?
+                .put(target)
+                .basic_auth(username, password)
+                .header(reqwest::header::CONTENT_TYPE, content_type(file_name))
+                .body(content.to_string())
+                .send()
+                .await
+                .map_err(|e| format!("Failed to upload to {}: {e}", destination.name))?;
+            if !response.status().is_success() {
+                return Err(format!(
+                    "{} returned {}",
+                    destination.name,
+                    response.status()
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Deletes the keychain entries of every destination. Used when wiping app
+/// data; failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for destination in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&credential_account(&destination.id)) {
+            log::warn!("Failed to delete credential of {}: {e}", destination.id);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_destinations(app: AppHandle) -> Result<Vec<DestinationInfo>, String> {
+    load(&app)?
+        .into_iter()
+        .map(|destination| {
+            let has_credential = keychain::get(&credential_account(&destination.id))?.is_some();
+            Ok(DestinationInfo {
+                destination,
+                has_credential,
+            })
+        })
+        .collect()
+}
+
+/// Adds or replaces a destination. A non-empty `credential` is stored in
+/// the keychain, an empty one removes the stored secret, and leaving it out
+/// keeps it.
+#[tauri::command]
+pub async fn save_destination(
+    app: AppHandle,
+    destination: Destination,
+    credential: Option<String>,
+) -> Result<(), String> {
+    validate(&destination)?;
+
+    let mut destinations = load(&app)?;
+    match destinations.iter().position(|d| d.id == destination.id) {
+        Some(index) => destinations[index] = destination.clone(),
+        None if destinations.len() >= MAX_DESTINATIONS => {
+            return Err(format!("Too many destinations (max {MAX_DESTINATIONS})"));
+        }
+        None => destinations.push(destination.clone()),
+    }
+
+    let account = credential_account(&destination.id);
+    match credential.as_deref() {
+        Some("") => keychain::delete(&account)?,
+        Some(secret) => keychain::set(&account, secret)?,
+        None => {}
+    }
+
+    store(&app, &destinations)?;
+    log::info!("Saved destination {}", destination.id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_destination(app: AppHandle, id: String) -> Result<(), String> {
+    let mut destinations = load(&app)?;
+    destinations.retain(|d| d.id != id);
+    store(&app, &destinations)?;
+    keychain::delete(&credential_account(&id))?;
+    log::info!("Removed destination {id}");
+    Ok(())
+}
+
+/// Sends text content, such as an exported conversation, to a destination
+/// as a file named `file_name`.
+#[tauri::command]
+pub async fn send_to_destination(
+    app: AppHandle,
+    destination_id: String,
+    file_name: String,
+    content: String,
+) -> Result<(), String> {
+    crate::validate_filename(&file_name)?;
+    if content.len() > MAX_CONTENT_BYTES {
+        return Err("Content is too large to send".to_string());
+    }
+
+    let destination = load(&app)?
+        .into_iter()
+        .find(|d| d.id == destination_id)
+        .ok_or_else(|| format!("Destination {destination_id} not found"))?;
+
+    log::info!("Sending {file_name} to destination {destination_id}");
+    deliver(&app, &destination, &file_name, &content)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to send to destination {destination_id}: {e}");
+            e
+        })
+}
@@ -0,0 +1,335 @@
+// Text Diffing
+// ============
+//
+// Compares two model responses or two versions of an artifact and returns
+// hunks the UI renders as-is. Long outputs made the JavaScript diff freeze
+// the renderer, so it runs here on a blocking thread. Texts are split into
+// words, sentences or lines; tokens are compared with whitespace normalized,
+// and short unchanged runs between two edits are folded into one
+// replacement, so a reworded sentence reads as one change instead of a
+// dozen one-word edits.
+
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices_deadline, get_diff_ratio, Algorithm, DiffTag};
+use std::time::{Duration, Instant};
+
+/// Largest text accepted on either side
+const MAX_DIFF_CHARS: usize = 2_000_000;
+/// Past this the diff falls back to a coarser, faster result
+const DIFF_DEADLINE: Duration = Duration::from_secs(2);
+/// Unchanged runs of at most this many words between two edits are folded
+/// into the surrounding change
+const MAX_FOLDED_WORDS: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Word,
+    Sentence,
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkKind {
+    Equal,
+    Insert,
+    Delete,
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub kind: HunkKind,
+    /// Text from `a`; empty for insertions
+    pub old_text: String,
+    /// Text from `b`; empty for deletions
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextDiff {
+    pub hunks: Vec<DiffHunk>,
+    /// Share of tokens unchanged, from 0 (nothing in common) to 1 (identical)
+    pub similarity: f32,
+    /// Tokens added in `b`
+    pub insertions: usize,
+    /// Tokens of `a` removed or replaced
+    pub deletions: usize,
+}
+
+/// Splits into word, whitespace and single punctuation tokens.
+fn words(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' || c == '\'' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut current: Option<Class> = None;
+    for (i, c) in text.char_indices() {
+        let next = class(c);
+        let split = match &current {
+            None => false,
+            Some(Class::Other) => true,
+            Some(prev) => *prev != next,
+        };
+        if split {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        current = Some(next);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Splits after sentence-ending punctuation followed by whitespace, and at
+/// line breaks. Trailing whitespace stays with its sentence.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let ends = match c {
+            '\n' => true,
+            '.' | '!' | '?' => chars.peek().is_some_and(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if !ends {
+            continue;
+        }
+        // Keep the whitespace after the sentence, up to and including a newline
+        let mut end = i + c.len_utf8();
+        if c != '\n' {
+            while let Some(&(j, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                chars.next();
+                end = j + next.len_utf8();
+                if next == '\n' {
+                    break;
+                }
+            }
+        }
+        tokens.push(&text[start..end]);
+        start = end;
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    match granularity {
+        Granularity::Word => words(text),
+        Granularity::Sentence => sentences(text),
+        Granularity::Line => text.split_inclusive('\n').collect(),
+    }
+}
+
+/// Comparison key of a token: whitespace runs collapse to one space and
+/// are trimmed from the ends of longer tokens, so reflowed text matches.
+fn key(token: &str) -> String {
+    let collapsed = token.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() && !token.is_empty() {
+        " ".to_string()
+    } else {
+        collapsed
+    }
+}
+
+fn is_word(token: &str) -> bool {
+    token.chars().any(char::is_alphanumeric)
+}
+
+/// Folds short unchanged runs sandwiched between edits into one replacement.
+fn fold(hunks: Vec<DiffHunk>, granularity: Granularity) -> Vec<DiffHunk> {
+    let foldable = |hunk: &DiffHunk| {
+        hunk.kind == HunkKind::Equal
+            && match granularity {
+                Granularity::Word => {
+                    words(&hunk.old_text)
+                        .into_iter()
+                        .filter(|t| is_word(t))
+                        .count()
+                        <= MAX_FOLDED_WORDS
+                }
+                // A blank or punctuation-only line or sentence
+                Granularity::Sentence | Granularity::Line => !is_word(&hunk.old_text),
+            }
+    };
+
+    let mut folded: Vec<DiffHunk> = Vec::with_capacity(hunks.len());
+    let mut iter = hunks.into_iter().peekable();
+    while let Some(hunk) = iter.next() {
+        let between_edits = folded.last().is_some_and(|h| h.kind != HunkKind::Equal)
+            && iter.peek().is_some_and(|h| h.kind != HunkKind::Equal);
+        let merge = if hunk.kind == HunkKind::Equal {
+            between_edits && foldable(&hunk)
+        } else {
+            folded.last().is_some_and(|h| h.kind != HunkKind::Equal)
+        };
+
+        match folded.last_mut() {
+            Some(last) if merge => {
+                last.old_text.push_str(&hunk.old_text);
+                last.new_text.push_str(&hunk.new_text);
+                last.kind = match (last.old_text.is_empty(), last.new_text.is_empty()) {
+                    (true, _) => HunkKind::Insert,
+                    (_, true) => HunkKind::Delete,
+                    _ => HunkKind::Replace,
+                };
+            }
+            _ => folded.push(hunk),
+        }
+    }
+    folded
+}
+
+/// Diffs `a` against `b` at the given granularity.
+fn diff(a: &str, b: &str, granularity: Granularity) -> TextDiff {
+    let old_tokens = tokenize(a, granularity);
+    let new_tokens = tokenize(b, granularity);
+    let old_keys: Vec<String> = old_tokens.iter().map(|t| key(t)).collect();
+    let new_keys: Vec<String> = new_tokens.iter().map(|t| key(t)).collect();
+
+    let ops = capture_diff_slices_deadline(
+        Algorithm::Myers,
+        &old_keys,
+        &new_keys,
+        Some(Instant::now() + DIFF_DEADLINE),
+    );
+    let similarity = get_diff_ratio(&ops, old_keys.len(), new_keys.len());
+
+    let (mut insertions, mut deletions) = (0, 0);
+    let hunks = ops
+        .iter()
+        .map(|op| {
+            let (tag, old, new) = op.as_tag_tuple();
+            let kind = match tag {
+                DiffTag::Equal => HunkKind::Equal,
+                DiffTag::Insert => HunkKind::Insert,
+                DiffTag::Delete => HunkKind::Delete,
+                DiffTag::Replace => HunkKind::Replace,
+            };
+            if kind != HunkKind::Equal {
+                insertions += new.len();
+                deletions += old.len();
+            }
+            DiffHunk {
+                kind,
+                old_text: old_tokens[old].concat(),
+                // Equal tokens may differ in whitespace; show the new side
+                new_text: new_tokens[new].concat(),
+            }
+        })
+        .collect();
+
+    TextDiff {
+        hunks: fold(hunks, granularity),
+        similarity,
+        insertions,
+        deletions,
+    }
+}
+
+/// Diffs two texts, e.g. two model responses or two artifact versions.
+/// `granularity` defaults to words.
+#[tauri::command]
+pub async fn diff_texts(
+    a: String,
+    b: String,
+    granularity: Option<Granularity>,
+) -> Result<TextDiff, String> {
+    if a.chars().count() > MAX_DIFF_CHARS || b.chars().count() > MAX_DIFF_CHARS {
+        return Err(format!(
+            "Texts longer than {MAX_DIFF_CHARS} characters can't be compared"
+        ));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || diff(&a, &b, granularity.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Diff failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(diff: &TextDiff) -> Vec<HunkKind> {
+        diff.hunks.iter().map(|h| h.kind).collect()
+    }
+
+    #[test]
+    fn identical_texts_are_one_equal_hunk() {
+        let result = diff("Same text here.", "Same text here.", Granularity::Word);
+        assert_eq!(kinds(&result), [HunkKind::Equal]);
+        assert_eq!(result.similarity, 1.0);
+        assert_eq!((result.insertions, result.deletions), (0, 0));
+    }
+
+    #[test]
+    fn whitespace_changes_are_not_edits() {
+        let result = diff("one  two\nthree", "one two three", Granularity::Word);
+        assert_eq!(kinds(&result), [HunkKind::Equal]);
+        assert_eq!(result.hunks[0].new_text, "one two three");
+    }
+
+    #[test]
+    fn reworded_run_folds_into_one_replacement() {
+        let result = diff(
+            "I like red apples",
+            "I love green apples",
+            Granularity::Word,
+        );
+        assert_eq!(
+            kinds(&result),
+            [HunkKind::Equal, HunkKind::Replace, HunkKind::Equal]
+        );
+        assert_eq!(result.hunks[1].old_text, "like red");
+        assert_eq!(result.hunks[1].new_text, "love green");
+    }
+
+    #[test]
+    fn insertions_and_deletions_are_counted() {
+        let result = diff("a\nb\n", "a\nb\nc\n", Granularity::Line);
+        assert_eq!(kinds(&result), [HunkKind::Equal, HunkKind::Insert]);
+        assert_eq!(result.hunks[1].new_text, "c\n");
+        assert_eq!((result.insertions, result.deletions), (1, 0));
+    }
+
+    #[test]
+    fn sentences_keep_trailing_whitespace() {
+        assert_eq!(
+            sentences("One. Two!\nThree 3.5 four"),
+            ["One. ", "Two!\n", "Three 3.5 four"]
+        );
+    }
+
+    #[test]
+    fn words_split_punctuation_one_at_a_time() {
+        assert_eq!(
+            words("don't stop...now"),
+            ["don't", " ", "stop", ".", ".", ".", "now"]
+        );
+    }
+}
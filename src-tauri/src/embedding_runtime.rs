@@ -0,0 +1,558 @@
+// Embedded Embedding Runtime
+// ==========================
+//
+// Runs ONNX embedding models inside the app with ONNX Runtime, so
+// knowledge bases can be indexed without the Ollama daemon. A model is a
+// directory under `models/embeddings` holding `model.onnx` and the
+// `tokenizer.json` that goes with it; `download_embedding_model` fetches
+// both from a Hugging Face repository of sentence-transformers exports,
+// e.g. `sentence-transformers/all-MiniLM-L6-v2`. Texts are padded to the
+// longest in their batch, the model's token states are averaged over the
+// attention mask and the vectors are normalised, as sentence-transformers
+// does.
+//
+// ONNX Runtime is loaded the first time it is needed rather than linked:
+// from `ORT_DYLIB_PATH`, from `onnxruntime/` in the app's resources, for
+// builds that bundle it, or from the system's library path. `probe`
+// tells whether it loaded and which execution providers it offers, the
+// GPU ones first: CoreML on macOS, DirectML on Windows, and the CPU
+// everywhere. A model runs on the first of them that accepts it.
+//
+// Only builds with the `local-embeddings` feature carry the runtime; in
+// others it is reported unavailable and `embeddings` uses Ollama.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+#[cfg(feature = "local-embeddings")]
+use std::path::Path;
+#[cfg(feature = "local-embeddings")]
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "local-embeddings")]
+use std::time::Instant;
+#[cfg(feature = "local-embeddings")]
+use tauri::Manager;
+
+/// Files that make up a model, as (path in the repository, local name)
+const MODEL_FILES: &[(&str, &str)] = &[
+    ("onnx/model.onnx", "model.onnx"),
+    ("tokenizer.json", "tokenizer.json"),
+];
+/// Texts per run of the model
+#[cfg(feature = "local-embeddings")]
+const BATCH_SIZE: usize = 32;
+/// Used for models whose tokenizer doesn't say; BERT's limit
+#[cfg(feature = "local-embeddings")]
+const DEFAULT_MAX_TOKENS: usize = 512;
+#[cfg(not(feature = "local-embeddings"))]
+const UNAVAILABLE: &str =
+    "This build has no embedded embedding runtime; it needs the local-embeddings feature";
+
+#[cfg(all(feature = "local-embeddings", windows))]
+const LIBRARY: &str = "onnxruntime.dll";
+#[cfg(all(feature = "local-embeddings", target_os = "macos"))]
+const LIBRARY: &str = "libonnxruntime.dylib";
+#[cfg(all(feature = "local-embeddings", not(any(windows, target_os = "macos"))))]
+const LIBRARY: &str = "libonnxruntime.so";
+
+/// Where ONNX Runtime was loaded from, or why it couldn't be
+#[cfg(feature = "local-embeddings")]
+static RUNTIME: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionProvider {
+    #[serde(rename = "coreml")]
+    CoreMl,
+    #[serde(rename = "directml")]
+    DirectMl,
+    #[serde(rename = "cpu")]
+    Cpu,
+}
+
+#[cfg(feature = "local-embeddings")]
+impl ExecutionProvider {
+    fn label(self) -> &'static str {
+        match self {
+            ExecutionProvider::CoreMl => "CoreML",
+            ExecutionProvider::DirectMl => "DirectML",
+            ExecutionProvider::Cpu => "CPU",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeProbe {
+    /// The ONNX Runtime library in use; `None` if it couldn't be loaded
+    pub library: Option<String>,
+    /// Why the runtime is unavailable
+    pub error: Option<String>,
+    /// Execution providers models can run on, fastest first
+    pub providers: Vec<ExecutionProvider>,
+    /// Installed models, by name
+    pub models: Vec<String>,
+}
+
+/// Vectors for texts, with what it took to compute them.
+pub struct Embedded {
+    pub embeddings: Vec<Vec<f32>>,
+    /// Input tokens, without padding
+    pub tokens: u64,
+    pub provider: ExecutionProvider,
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "local-embeddings")]
+struct Loaded {
+    model: String,
+    provider: ExecutionProvider,
+    session: ort::session::Session,
+    tokenizer: tokenizers::Tokenizer,
+}
+
+/// The loaded model, managed as app state. One model is kept loaded, so
+/// indexing a knowledge base doesn't load it again for every request.
+#[derive(Default)]
+pub struct EmbeddingRuntime {
+    #[cfg(feature = "local-embeddings")]
+    loaded: Mutex<Option<Loaded>>,
+}
+
+/// Directory embedding models are kept in, created if needed.
+fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::local_models::models_dir(app)?.join("embeddings");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create embedding models directory: {e}"))?;
+    Ok(dir)
+}
+
+fn validate_model_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid embedding model name {name}"))
+    }
+}
+
+/// Models with all their files downloaded, sorted by name.
+pub fn installed_models(app: &AppHandle) -> Vec<String> {
+    let Ok(entries) = models_dir(app).and_then(|dir| {
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read embedding models: {e}"))
+    }) else {
+        return Vec::new();
+    };
+    let mut models: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            MODEL_FILES
+                .iter()
+                .all(|(_, name)| entry.path().join(name).is_file())
+        })
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    models.sort();
+    models
+}
+
+pub fn is_installed(app: &AppHandle, model: &str) -> bool {
+    validate_model_name(model).is_ok() && installed_models(app).iter().any(|m| m == model)
+}
+
+/// Loads ONNX Runtime once, from the first place that has it.
+#[cfg(feature = "local-embeddings")]
+fn load_runtime(app: &AppHandle) -> Result<&'static Path, String> {
+    RUNTIME
+        .get_or_init(|| {
+            let mut candidates = Vec::new();
+            if let Some(path) = std::env::var_os("ORT_DYLIB_PATH") {
+                candidates.push(PathBuf::from(path));
+            }
+            if let Ok(dir) = app.path().resource_dir() {
+                candidates.push(dir.join("onnxruntime").join(LIBRARY));
+            }
+            // Left to the system's library search
+            candidates.push(PathBuf::from(LIBRARY));
+            let mut errors = Vec::new();
+            for path in candidates {
+                match ort::init_from(&path) {
+                    Ok(environment) => {
+                        environment.with_name("nexus").commit();
+                        log::info!("Loaded ONNX Runtime from {}", path.display());
+                        return Ok(path);
+                    }
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+            Err(format!("ONNX Runtime was not found: {}", errors.join("; ")))
+        })
+        .as_deref()
+        .map_err(Clone::clone)
+}
+
+/// Execution providers this system has, the GPU ones first.
+#[cfg(feature = "local-embeddings")]
+fn providers() -> Vec<ExecutionProvider> {
+    #[cfg(any(windows, target_os = "macos"))]
+    use ort::ep::ExecutionProvider as _;
+
+    let mut providers = Vec::new();
+    #[cfg(target_os = "macos")]
+    if ort::ep::CoreML::default().is_available().unwrap_or(false) {
+        providers.push(ExecutionProvider::CoreMl);
+    }
+    #[cfg(windows)]
+    if ort::ep::DirectML::default().is_available().unwrap_or(false) {
+        providers.push(ExecutionProvider::DirectMl);
+    }
+    providers.push(ExecutionProvider::Cpu);
+    providers
+}
+
+/// Whether the runtime can be used, and on what.
+#[cfg(feature = "local-embeddings")]
+pub fn probe(app: &AppHandle) -> RuntimeProbe {
+    let models = installed_models(app);
+    match load_runtime(app) {
+        Ok(library) => RuntimeProbe {
+            library: Some(library.to_string_lossy().into_owned()),
+            error: None,
+            providers: providers(),
+            models,
+        },
+        Err(e) => RuntimeProbe {
+            library: None,
+            error: Some(e),
+            providers: Vec::new(),
+            models,
+        },
+    }
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+pub fn probe(app: &AppHandle) -> RuntimeProbe {
+    RuntimeProbe {
+        library: None,
+        error: Some(UNAVAILABLE.to_string()),
+        providers: Vec::new(),
+        models: installed_models(app),
+    }
+}
+
+/// Creates a session for the model at `path` on `provider`, failing
+/// rather than quietly running on the CPU if the provider refuses it.
+#[cfg(feature = "local-embeddings")]
+fn session(path: &Path, provider: ExecutionProvider) -> Result<ort::session::Session, String> {
+    use ort::session::Session;
+
+    let mut builder = Session::builder().map_err(|e| e.to_string())?;
+    match provider {
+        #[cfg(target_os = "macos")]
+        ExecutionProvider::CoreMl => {
+            builder = builder
+                .with_execution_providers([ort::ep::CoreML::default().build().error_on_failure()])
+                .map_err(|e| e.to_string())?;
+        }
+        // DirectML can't run with memory patterns or parallel execution
+        #[cfg(windows)]
+        ExecutionProvider::DirectMl => {
+            builder = builder
+                .with_memory_pattern(false)
+                .map_err(|e| e.to_string())?
+                .with_parallel_execution(false)
+                .map_err(|e| e.to_string())?
+                .with_execution_providers([ort::ep::DirectML::default().build().error_on_failure()])
+                .map_err(|e| e.to_string())?;
+        }
+        ExecutionProvider::Cpu => {}
+        other => return Err(format!("{} is not available here", other.label())),
+    }
+    builder.commit_from_file(path).map_err(|e| e.to_string())
+}
+
+/// Loads `model` on `provider`, or on the first provider that takes it.
+#[cfg(feature = "local-embeddings")]
+fn load(
+    app: &AppHandle,
+    model: &str,
+    provider: Option<ExecutionProvider>,
+) -> Result<Loaded, String> {
+    use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+    let dir = models_dir(app)?.join(model);
+    let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+        .map_err(|e| format!("Failed to read the tokenizer of {model}: {e}"))?;
+    let max_length = tokenizer
+        .get_truncation()
+        .map_or(DEFAULT_MAX_TOKENS, |t| t.max_length);
+    tokenizer.with_padding(Some(PaddingParams::default()));
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length,
+            ..Default::default()
+        }))
+        .map_err(|e| format!("Failed to set up the tokenizer of {model}: {e}"))?;
+
+    let available = providers();
+    let candidates = match provider {
+        Some(provider) if !available.contains(&provider) => {
+            return Err(format!("{} is not available here", provider.label()))
+        }
+        Some(provider) => vec![provider],
+        None => available,
+    };
+    let mut errors = Vec::new();
+    for provider in candidates {
+        let started = Instant::now();
+        match session(&dir.join("model.onnx"), provider) {
+            Ok(session) => {
+                log::info!(
+                    "Loaded embedding model {model} on {} in {}ms",
+                    provider.label(),
+                    started.elapsed().as_millis()
+                );
+                return Ok(Loaded {
+                    model: model.to_string(),
+                    provider,
+                    session,
+                    tokenizer,
+                });
+            }
+            Err(e) => {
+                log::warn!("{} can't run {model}: {e}", provider.label());
+                errors.push(format!("{}: {e}", provider.label()));
+            }
+        }
+    }
+    Err(format!("Failed to load {model}: {}", errors.join("; ")))
+}
+
+/// Averages the token states `hidden`, shaped `[texts, tokens, dimensions]`,
+/// over the tokens `mask` marks as real, and normalises the results.
+#[cfg(feature = "local-embeddings")]
+fn mean_pool(hidden: &[f32], shape: &[usize], mask: &[i64]) -> Result<Vec<Vec<f32>>, String> {
+    let &[texts, tokens, dimensions] = shape else {
+        return Err(format!("Unexpected model output of shape {shape:?}"));
+    };
+    if hidden.len() != texts * tokens * dimensions || mask.len() != texts * tokens {
+        return Err("Model output doesn't match its input".to_string());
+    }
+    Ok((0..texts)
+        .map(|text| {
+            let mut vector = vec![0.0; dimensions];
+            let mut count = 0.0;
+            for token in 0..tokens {
+                if mask[text * tokens + token] == 0 {
+                    continue;
+                }
+                let start = (text * tokens + token) * dimensions;
+                for (sum, value) in vector.iter_mut().zip(&hidden[start..start + dimensions]) {
+                    *sum += value;
+                }
+                count += 1.0;
+            }
+            if count > 0.0 {
+                vector.iter_mut().for_each(|v| *v /= count);
+            }
+            normalize(vector)
+        })
+        .collect())
+}
+
+/// Scales `vector` to unit length, so dot products are cosine similarity.
+#[cfg(feature = "local-embeddings")]
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// Embeds one batch, returning its vectors and input token count.
+#[cfg(feature = "local-embeddings")]
+fn run(loaded: &mut Loaded, texts: &[String]) -> Result<(Vec<Vec<f32>>, u64), String> {
+    use ort::session::SessionInputValue;
+    use ort::value::Tensor;
+    use std::borrow::Cow;
+    use tokenizers::Encoding;
+
+    let encodings = loaded
+        .tokenizer
+        .encode_batch(texts.iter().map(String::as_str).collect::<Vec<_>>(), true)
+        .map_err(|e| format!("Failed to tokenize: {e}"))?;
+    let shape = [encodings.len(), encodings.first().map_or(0, Encoding::len)];
+    let column = |values: fn(&Encoding) -> &[u32]| -> Vec<i64> {
+        encodings
+            .iter()
+            .flat_map(|e| values(e).iter().map(|&v| i64::from(v)))
+            .collect()
+    };
+    let mask = column(Encoding::get_attention_mask);
+
+    let mut inputs: Vec<(Cow<str>, SessionInputValue)> = Vec::new();
+    for input in loaded.session.inputs() {
+        let values = match input.name() {
+            "input_ids" => column(Encoding::get_ids),
+            "attention_mask" => mask.clone(),
+            "token_type_ids" => column(Encoding::get_type_ids),
+            other => return Err(format!("{} has an unknown input {other}", loaded.model)),
+        };
+        let tensor = Tensor::from_array((shape, values)).map_err(|e| e.to_string())?;
+        inputs.push((Cow::Owned(input.name().to_string()), tensor.into()));
+    }
+    let outputs = loaded
+        .session
+        .run(inputs)
+        .map_err(|e| format!("Failed to run {}: {e}", loaded.model))?;
+    // Some exports include the pooling; the rest give token states
+    let output = outputs.get("sentence_embedding").unwrap_or(&outputs[0]);
+    let (output_shape, values) = output
+        .try_extract_tensor::<f32>()
+        .map_err(|e| format!("Unexpected output from {}: {e}", loaded.model))?;
+    let output_shape: Vec<usize> = output_shape.iter().map(|&d| d as usize).collect();
+    let embeddings = match output_shape[..] {
+        [texts, dimensions] if values.len() == texts * dimensions => values
+            .chunks(dimensions.max(1))
+            .map(|vector| normalize(vector.to_vec()))
+            .collect(),
+        _ => mean_pool(values, &output_shape, &mask)?,
+    };
+    let tokens = mask.iter().sum::<i64>() as u64;
+    Ok((embeddings, tokens))
+}
+
+#[cfg(feature = "local-embeddings")]
+fn embed_blocking(
+    app: &AppHandle,
+    model: &str,
+    provider: Option<ExecutionProvider>,
+    texts: &[String],
+) -> Result<Embedded, String> {
+    load_runtime(app)?;
+    let runtime = app.state::<EmbeddingRuntime>();
+    let mut slot = runtime.loaded.lock().map_err(|e| e.to_string())?;
+    // A different model is dropped before the next one loads
+    let current = slot
+        .take()
+        .filter(|l| l.model == model && provider.is_none_or(|p| p == l.provider));
+    let loaded = match current {
+        Some(loaded) => slot.insert(loaded),
+        None => slot.insert(load(app, model, provider)?),
+    };
+
+    let started = Instant::now();
+    let mut embeddings = Vec::with_capacity(texts.len());
+    let mut tokens = 0;
+    for batch in texts.chunks(BATCH_SIZE) {
+        let (vectors, batch_tokens) = run(loaded, batch)?;
+        embeddings.extend(vectors);
+        tokens += batch_tokens;
+    }
+    Ok(Embedded {
+        embeddings,
+        tokens,
+        provider: loaded.provider,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Embeds `texts` with the installed model `model`, on `provider` or on
+/// the fastest provider that takes the model. Loading a model the first
+/// time is not counted in `elapsed`.
+#[cfg(feature = "local-embeddings")]
+pub async fn embed(
+    app: &AppHandle,
+    model: &str,
+    provider: Option<ExecutionProvider>,
+    texts: &[String],
+) -> Result<Embedded, String> {
+    if !is_installed(app, model) {
+        return Err(format!(
+            "Embedding model {model} is not installed; download it first"
+        ));
+    }
+    let app = app.clone();
+    let model = model.to_string();
+    let texts = texts.to_vec();
+    tauri::async_runtime::spawn_blocking(move || embed_blocking(&app, &model, provider, &texts))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+pub async fn embed(
+    _app: &AppHandle,
+    _model: &str,
+    _provider: Option<ExecutionProvider>,
+    _texts: &[String],
+) -> Result<Embedded, String> {
+    Err(UNAVAILABLE.to_string())
+}
+
+/// Downloads the ONNX export and tokenizer of a sentence-transformers
+/// model from the Hugging Face repository `hf_repo`, e.g.
+/// `sentence-transformers/all-MiniLM-L6-v2`. The model is named after the
+/// repository, without the owner. Returns its name.
+#[tauri::command]
+pub async fn download_embedding_model(app: AppHandle, hf_repo: String) -> Result<String, String> {
+    crate::local_models::validate_hf_repo(&hf_repo)?;
+    let name = hf_repo.rsplit('/').next().unwrap_or(&hf_repo).to_string();
+    validate_model_name(&name)?;
+    if is_installed(&app, &name) {
+        return Err(format!("{name} is already downloaded"));
+    }
+    let dir = models_dir(&app)?.join(&name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    for (path, file_name) in MODEL_FILES {
+        let target = dir.join(file_name);
+        if target.is_file() {
+            continue;
+        }
+        let (url, sha256, size) = crate::local_models::resolve_hf(&hf_repo, path).await?;
+        crate::local_models::download_file(&app, &url, &target, sha256, size).await?;
+    }
+    Ok(name)
+}
+
+#[cfg(all(test, feature = "local-embeddings"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_pools_real_tokens_only() {
+        // Two texts of two tokens in two dimensions; the second is padded
+        let hidden = [1.0, 0.0, 3.0, 0.0, 0.0, 2.0, 9.0, 9.0];
+        let mask = [1, 1, 1, 0];
+        let pooled = mean_pool(&hidden, &[2, 2, 2], &mask).unwrap();
+        assert_eq!(pooled, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[test]
+    fn rejects_mismatched_output() {
+        assert!(mean_pool(&[0.0; 6], &[2, 2, 2], &[1, 1, 1, 1]).is_err());
+        assert!(mean_pool(&[0.0; 4], &[2, 2], &[1, 1]).is_err());
+    }
+
+    #[test]
+    fn normalizes_to_unit_length() {
+        assert_eq!(normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn validates_model_names() {
+        assert!(validate_model_name("all-MiniLM-L6-v2").is_ok());
+        assert!(validate_model_name("bge-small-en-v1.5").is_ok());
+        assert!(validate_model_name("..").is_err());
+        assert!(validate_model_name("a/b").is_err());
+        assert!(validate_model_name("").is_err());
+    }
+}
@@ -0,0 +1,465 @@
+// Local Embeddings
+// ================
+//
+// `embed_texts` turns texts into embedding vectors on this machine, for
+// indexing knowledge bases without sending documents to a provider. There
+// are two backends:
+//
+// - `onnx`: ONNX models run in the app itself (see `embedding_runtime`),
+//   on CoreML or DirectML where the system has them, else on the CPU.
+// - `ollama`: models run in the local Ollama daemon (see `ollama`), which
+//   picks Metal, CUDA or ROCm by itself. After each request the daemon's
+//   list of loaded models tells how much of the model sits in GPU memory,
+//   and that is reported as the device it ran on.
+//
+// Unless a backend is asked for, `embed_texts` probes the system: the
+// model runs in the app when ONNX Runtime loads and the model is an
+// installed ONNX model, and in Ollama otherwise. `probe_embedding_backends`
+// shows what the probe found.
+//
+// `benchmark_embeddings` embeds the same sample texts with each of several
+// models, after a warm-up request that loads the model, and reports their
+// throughput, so the fastest model that is good enough can be picked for
+// indexing. ONNX models are run on every execution provider the system
+// has, so the providers can be compared too.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::embedding_runtime::{self, ExecutionProvider};
+use crate::ollama;
+
+/// Used with Ollama when no model is given; small, fast and widely pulled
+pub const DEFAULT_MODEL: &str = "nomic-embed-text";
+/// Used in the app when no model is given and it is installed
+pub const DEFAULT_ONNX_MODEL: &str = "all-MiniLM-L6-v2";
+const MAX_TEXTS: usize = 10_000;
+const MAX_TEXT_LENGTH: usize = 100_000;
+/// Texts per request to the daemon
+const BATCH_SIZE: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_BENCHMARK_MODELS: usize = 10;
+const BENCHMARK_TEXTS: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingBackend {
+    /// ONNX Runtime inside the app
+    Onnx,
+    /// The local Ollama daemon
+    Ollama,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingDevice {
+    /// The whole model is in GPU memory
+    Gpu,
+    /// Some layers are in GPU memory, the rest run on the CPU
+    Mixed,
+    Cpu,
+    /// The model was unloaded before it could be checked
+    Unknown,
+}
+
+impl EmbeddingDevice {
+    /// CoreML may also use the Neural Engine; it counts as the GPU
+    fn of(provider: ExecutionProvider) -> Self {
+        match provider {
+            ExecutionProvider::CoreMl | ExecutionProvider::DirectMl => EmbeddingDevice::Gpu,
+            ExecutionProvider::Cpu => EmbeddingDevice::Cpu,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Embeddings {
+    pub model: String,
+    pub backend: EmbeddingBackend,
+    /// What the model ran on, for the `onnx` backend
+    pub provider: Option<ExecutionProvider>,
+    /// One vector per text, in the order of the texts
+    pub embeddings: Vec<Vec<f32>>,
+    pub dimensions: usize,
+    pub device: EmbeddingDevice,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingBenchmark {
+    pub model: String,
+    pub backend: EmbeddingBackend,
+    /// What the model ran on, for the `onnx` backend
+    pub provider: Option<ExecutionProvider>,
+    /// `None` if the model failed; see `error`
+    pub dimensions: Option<usize>,
+    pub device: EmbeddingDevice,
+    pub texts: usize,
+    pub duration_ms: u64,
+    pub texts_per_second: f64,
+    /// Input tokens per second, as counted by the tokenizer or the daemon
+    pub tokens_per_second: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingProbe {
+    pub onnx: embedding_runtime::RuntimeProbe,
+    /// Whether the Ollama daemon answers
+    pub ollama: bool,
+    /// What `embed_texts` uses when no backend or model is given
+    pub default_backend: EmbeddingBackend,
+    pub default_model: String,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct LoadedModel {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    size_vram: u64,
+}
+
+#[derive(Deserialize)]
+struct PsResponse {
+    #[serde(default)]
+    models: Vec<LoadedModel>,
+}
+
+/// Embeds one batch, returning its vectors and input token count.
+async fn embed_batch(
+    client: &reqwest::Client,
+    model: &str,
+    texts: &[String],
+) -> Result<EmbedResponse, String> {
+    let response = client
+        .post(format!("{}/api/embed", ollama::base_url()))
+        .timeout(REQUEST_TIMEOUT)
+        .json(&json!({ "model": model, "input": texts, "truncate": true }))
+        .send()
+        .await
+        .map_err(ollama::unreachable)?;
+    let response = ollama::check(response, "embed texts")
+        .await?
+        .json::<EmbedResponse>()
+        .await
+        .map_err(|e| format!("Unexpected embeddings from Ollama: {e}"))?;
+    if response.embeddings.len() != texts.len() {
+        return Err(format!(
+            "Ollama returned {} embeddings for {} texts",
+            response.embeddings.len(),
+            texts.len()
+        ));
+    }
+    Ok(response)
+}
+
+/// Where the daemon keeps `model` while it is loaded.
+async fn device(client: &reqwest::Client, model: &str) -> EmbeddingDevice {
+    let loaded = match client
+        .get(format!("{}/api/ps", ollama::base_url()))
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+    {
+        Ok(response) => response.json::<PsResponse>().await.ok(),
+        Err(_) => None,
+    };
+    // `nomic-embed-text` is listed as `nomic-embed-text:latest`
+    let tagged = if model.contains(':') {
+        model.to_string()
+    } else {
+        format!("{model}:latest")
+    };
+    match loaded
+        .into_iter()
+        .flat_map(|ps| ps.models)
+        .find(|m| m.name == model || m.name == tagged)
+    {
+        Some(m) if m.size_vram == 0 => EmbeddingDevice::Cpu,
+        Some(m) if m.size_vram >= m.size => EmbeddingDevice::Gpu,
+        Some(_) => EmbeddingDevice::Mixed,
+        None => EmbeddingDevice::Unknown,
+    }
+}
+
+/// Embeds `texts` in batches, returning the vectors, the input tokens if
+/// the daemon counted them, and how long it took.
+async fn embed(
+    client: &reqwest::Client,
+    model: &str,
+    texts: &[String],
+) -> Result<(Vec<Vec<f32>>, Option<u64>, Duration), String> {
+    let started = Instant::now();
+    let mut embeddings = Vec::with_capacity(texts.len());
+    let mut tokens = Some(0);
+    for batch in texts.chunks(BATCH_SIZE) {
+        let response = embed_batch(client, model, batch).await?;
+        tokens = tokens.zip(response.prompt_eval_count).map(|(a, b)| a + b);
+        embeddings.extend(response.embeddings);
+    }
+    Ok((embeddings, tokens, started.elapsed()))
+}
+
+/// The backend and model to use for `model` when `backend` isn't given:
+/// the app's runtime for installed ONNX models once it loads, else Ollama.
+fn select(
+    app: &AppHandle,
+    model: Option<String>,
+    backend: Option<EmbeddingBackend>,
+) -> Result<(EmbeddingBackend, String), String> {
+    let onnx_usable = || embedding_runtime::probe(app).error.is_none();
+    let selected = match (backend, model) {
+        (Some(EmbeddingBackend::Onnx), model) => (
+            EmbeddingBackend::Onnx,
+            model.unwrap_or_else(|| DEFAULT_ONNX_MODEL.to_string()),
+        ),
+        (Some(EmbeddingBackend::Ollama), model) => (
+            EmbeddingBackend::Ollama,
+            model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+        ),
+        (None, Some(model)) if embedding_runtime::is_installed(app, &model) && onnx_usable() => {
+            (EmbeddingBackend::Onnx, model)
+        }
+        (None, Some(model)) => (EmbeddingBackend::Ollama, model),
+        (None, None)
+            if embedding_runtime::is_installed(app, DEFAULT_ONNX_MODEL) && onnx_usable() =>
+        {
+            (EmbeddingBackend::Onnx, DEFAULT_ONNX_MODEL.to_string())
+        }
+        (None, None) => (EmbeddingBackend::Ollama, DEFAULT_MODEL.to_string()),
+    };
+    if selected.0 == EmbeddingBackend::Ollama {
+        ollama::validate_model_name(&selected.1)?;
+    }
+    Ok(selected)
+}
+
+fn validate_texts(texts: &[String]) -> Result<(), String> {
+    if texts.is_empty() || texts.len() > MAX_TEXTS {
+        return Err(format!("Give between 1 and {MAX_TEXTS} texts to embed"));
+    }
+    for text in texts {
+        crate::validate_string_input(text, MAX_TEXT_LENGTH, "text")?;
+    }
+    Ok(())
+}
+
+/// Embeds `texts` with the local embedding model `model` on `backend`,
+/// or on the backend the system probe picks (see `select`). Without a
+/// model, the ONNX model `all-MiniLM-L6-v2` is used if installed, else
+/// Ollama's `nomic-embed-text`. The model has to be downloaded or pulled
+/// first; texts longer than its context are truncated.
+#[tauri::command]
+pub async fn embed_texts(
+    app: AppHandle,
+    model: Option<String>,
+    backend: Option<EmbeddingBackend>,
+    texts: Vec<String>,
+) -> Result<Embeddings, String> {
+    validate_texts(&texts)?;
+    let (backend, model) = select(&app, model, backend)?;
+    let (embeddings, provider, device, elapsed) = match backend {
+        EmbeddingBackend::Onnx => {
+            let embedded = embedding_runtime::embed(&app, &model, None, &texts).await?;
+            let device = EmbeddingDevice::of(embedded.provider);
+            (
+                embedded.embeddings,
+                Some(embedded.provider),
+                device,
+                embedded.elapsed,
+            )
+        }
+        EmbeddingBackend::Ollama => {
+            let client = crate::llm::http_client();
+            let (embeddings, _, elapsed) = embed(&client, &model, &texts).await?;
+            let device = device(&client, &model).await;
+            (embeddings, None, device, elapsed)
+        }
+    };
+    log::info!(
+        "Embedded {} texts with {model} ({backend:?}) on {device:?} in {}ms",
+        texts.len(),
+        elapsed.as_millis()
+    );
+    Ok(Embeddings {
+        model,
+        backend,
+        provider,
+        dimensions: embeddings.first().map_or(0, Vec::len),
+        embeddings,
+        device,
+        duration_ms: elapsed.as_millis() as u64,
+    })
+}
+
+/// What each backend can do on this system, and what `embed_texts` picks
+/// by default.
+#[tauri::command]
+pub async fn probe_embedding_backends(app: AppHandle) -> Result<EmbeddingProbe, String> {
+    let probe_app = app.clone();
+    let onnx = tauri::async_runtime::spawn_blocking(move || embedding_runtime::probe(&probe_app))
+        .await
+        .map_err(|e| e.to_string())?;
+    let ollama = ollama::ollama_status().await.is_ok_and(|s| s.running);
+    let (default_backend, default_model) = select(&app, None, None)?;
+    Ok(EmbeddingProbe {
+        onnx,
+        ollama,
+        default_backend,
+        default_model,
+    })
+}
+
+/// Sample texts of typical knowledge-base chunk length.
+fn sample_texts() -> Vec<String> {
+    const SENTENCES: &[&str] = &[
+        "The quarterly report shows revenue growth across all regions.",
+        "Install the package and restart the server before running migrations.",
+        "Photosynthesis converts light energy into chemical energy in plants.",
+        "The meeting was moved to Thursday afternoon to fit the new schedule.",
+        "Each request is retried with exponential backoff when the API is busy.",
+        "The river flooded the lower fields after three days of heavy rain.",
+    ];
+    (0..BENCHMARK_TEXTS)
+        .map(|i| {
+            (0..8)
+                .map(|j| SENTENCES[(i + j) % SENTENCES.len()])
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// A benchmark result from a run, or from the error that stopped it.
+fn benchmark_result(
+    model: &str,
+    backend: EmbeddingBackend,
+    provider: Option<ExecutionProvider>,
+    texts: usize,
+    run: Result<(Option<usize>, EmbeddingDevice, Option<u64>, Duration), String>,
+) -> EmbeddingBenchmark {
+    match run {
+        Ok((dimensions, device, tokens, elapsed)) => {
+            let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+            EmbeddingBenchmark {
+                model: model.to_string(),
+                backend,
+                provider,
+                dimensions,
+                device,
+                texts,
+                duration_ms: elapsed.as_millis() as u64,
+                texts_per_second: texts as f64 / seconds,
+                tokens_per_second: tokens.map(|t| t as f64 / seconds),
+                error: None,
+            }
+        }
+        Err(e) => {
+            log::warn!("Embedding benchmark of {model} ({backend:?}) failed: {e}");
+            EmbeddingBenchmark {
+                model: model.to_string(),
+                backend,
+                provider,
+                dimensions: None,
+                device: EmbeddingDevice::Unknown,
+                texts,
+                duration_ms: 0,
+                texts_per_second: 0.0,
+                tokens_per_second: None,
+                error: Some(e),
+            }
+        }
+    }
+}
+
+/// Compares the throughput of local embedding models on `texts`, or on
+/// 128 built-in sample texts. Installed ONNX models are run in the app on
+/// each execution provider the system has, other models in Ollama. Runs
+/// go one after another; one that fails is reported with its error rather
+/// than failing the whole benchmark.
+#[tauri::command]
+pub async fn benchmark_embeddings(
+    app: AppHandle,
+    models: Vec<String>,
+    texts: Option<Vec<String>>,
+) -> Result<Vec<EmbeddingBenchmark>, String> {
+    if models.is_empty() || models.len() > MAX_BENCHMARK_MODELS {
+        return Err(format!(
+            "Give between 1 and {MAX_BENCHMARK_MODELS} models to benchmark"
+        ));
+    }
+    let texts = texts.unwrap_or_else(sample_texts);
+    validate_texts(&texts)?;
+    let mut runs = Vec::with_capacity(models.len());
+    for model in models {
+        runs.push(select(&app, Some(model), None)?);
+    }
+    let probe_app = app.clone();
+    let providers = tauri::async_runtime::spawn_blocking(move || {
+        embedding_runtime::probe(&probe_app).providers
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let client = crate::llm::http_client();
+    let mut results = Vec::with_capacity(runs.len());
+    for (backend, model) in runs {
+        if backend == EmbeddingBackend::Onnx {
+            for &provider in &providers {
+                let provider = Some(provider);
+                // Loads the model, so the timed run measures embedding only
+                let run = match embedding_runtime::embed(&app, &model, provider, &texts[..1]).await
+                {
+                    Ok(_) => embedding_runtime::embed(&app, &model, provider, &texts)
+                        .await
+                        .map(|embedded| {
+                            (
+                                embedded.embeddings.first().map(Vec::len),
+                                EmbeddingDevice::of(embedded.provider),
+                                Some(embedded.tokens),
+                                embedded.elapsed,
+                            )
+                        }),
+                    Err(e) => Err(e),
+                };
+                results.push(benchmark_result(
+                    &model,
+                    backend,
+                    provider,
+                    texts.len(),
+                    run,
+                ));
+            }
+            continue;
+        }
+        let warm_up = embed_batch(&client, &model, &texts[..1]).await;
+        let run = match warm_up {
+            Ok(_) => match embed(&client, &model, &texts).await {
+                Ok((embeddings, tokens, elapsed)) => Ok((
+                    embeddings.first().map(Vec::len),
+                    device(&client, &model).await,
+                    tokens,
+                    elapsed,
+                )),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        results.push(benchmark_result(&model, backend, None, texts.len(), run));
+    }
+    Ok(results)
+}
@@ -0,0 +1,859 @@
+// Conversation Export
+// ===================
+//
+// Renders conversations to Markdown, using the same layout as the frontend
+// exporter in src/lib/chat-export.ts, to standalone HTML and to the
+// canonical JSON schema; exports single conversations or zip archives of
+// all of them, and runs the scheduled export that keeps a user-chosen
+// folder (an Obsidian vault, a Time Machine covered directory, ...) up to
+// date with chat history.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::comparisons::{self, Comparison, ComparisonTurn};
+use crate::conversations::{self, Conversation};
+
+/// Minimum time between two scheduled exports
+const EXPORT_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn render(self, conversation: &Conversation) -> String {
+        match self {
+            ExportFormat::Markdown => render_markdown(conversation),
+            ExportFormat::Html => render_html(conversation),
+            ExportFormat::Json => render_json(conversation),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduledExportSettings {
+    pub enabled: bool,
+    /// Absolute path of the folder exports are written to
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportReport {
+    pub folder: String,
+    pub exported: u32,
+    pub unchanged: u32,
+    pub failed: u32,
+}
+
+/// Tracks what has already been written so each run only touches
+/// conversations that changed since the previous one.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ExportState {
+    /// UNIX timestamp (milliseconds) of the last completed run
+    #[serde(deserialize_with = "crate::time::legacy_ms")]
+    last_run: u64,
+    /// Folder the state refers to; switching folders re-exports everything
+    folder: String,
+    files: HashMap<String, ExportedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedFile {
+    filename: String,
+    updated_at: u64,
+}
+
+pub fn validate_scheduled_export(settings: &ScheduledExportSettings) -> Result<(), String> {
+    match settings.folder.as_deref() {
+        Some(folder) if !Path::new(folder).is_absolute() => {
+            Err("Export folder must be an absolute path".to_string())
+        }
+        None if settings.enabled => {
+            Err("Choose an export folder before enabling scheduled exports".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Renders a conversation as Markdown.
+pub fn render_markdown(conversation: &Conversation) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    lines.push(format!("# {}", conversation.title));
+    lines.push(String::new());
+
+    lines.push("## Metadata".to_string());
+    lines.push(String::new());
+    lines.push(format!("- **Model:** {}", conversation.model_id));
+    lines.push(format!(
+        "- **Created:** {}",
+        crate::time::format_datetime(conversation.created_at)
+    ));
+    lines.push(format!(
+        "- **Updated:** {}",
+        crate::time::format_datetime(conversation.updated_at)
+    ));
+    lines.push(String::new());
+
+    if !conversation.system_prompt.is_empty() {
+        lines.push("## System Prompt".to_string());
+        lines.push(String::new());
+        lines.push("```".to_string());
+        lines.push(conversation.system_prompt.clone());
+        lines.push("```".to_string());
+        lines.push(String::new());
+    }
+
+    lines.push("## Conversation".to_string());
+    lines.push(String::new());
+
+    for message in &conversation.messages {
+        // Tool results are shown alongside the tool calls that produced them
+        if message.role == "tool" {
+            continue;
+        }
+
+        let role_label = if message.role == "user" {
+            "**You**"
+        } else {
+            "**Assistant**"
+        };
+        lines.push(format!(
+            "### {role_label} ({})",
+            crate::time::format_datetime(message.timestamp)
+        ));
+        lines.push(String::new());
+
+        let attachments = message.attachments();
+        if !attachments.is_empty() {
+            let labels: Vec<String> = attachments
+                .iter()
+                .map(|part| {
+                    let kind = part.get("type").and_then(Value::as_str).unwrap_or("file");
+                    match part.get("filename").and_then(Value::as_str) {
+                        Some(name) => format!("[{kind}: {name}]"),
+                        None => format!("[{kind}]"),
+                    }
+                })
+                .collect();
+            lines.push(format!("*Attachments: {}*", labels.join(", ")));
+            lines.push(String::new());
+        }
+
+        lines.push(message.text());
+        lines.push(String::new());
+
+        if let Some(tool_calls) = message.tool_calls.as_ref().filter(|t| !t.is_empty()) {
+            lines.push("**Tools Used:**".to_string());
+            lines.push(String::new());
+            for call in tool_calls {
+                lines.push(format!("- `{}`", call.name));
+                lines.push("  - Arguments:".to_string());
+                lines.push("  ```json".to_string());
+                let arguments = serde_json::to_string_pretty(&call.arguments).unwrap_or_default();
+                lines.push(format!("  {}", arguments.replace('\n', "\n  ")));
+                lines.push("  ```".to_string());
+                if let Some(result) = &call.result {
+                    lines.push(format!("  - Status: {}", call.status));
+                    let preview: String = result.chars().take(200).collect();
+                    let ellipsis = if preview.len() < result.len() {
+                        "..."
+                    } else {
+                        ""
+                    };
+                    lines.push(format!(
+                        "  - Result: `{}{ellipsis}`",
+                        preview.replace('\n', " ")
+                    ));
+                }
+            }
+            lines.push(String::new());
+        }
+
+        lines.push("---".to_string());
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+/// Renders a conversation in the canonical JSON schema, the same one the
+/// frontend exporter in src/lib/chat-export.ts writes.
+pub fn render_json(conversation: &Conversation) -> String {
+    let messages: Vec<Value> = conversation
+        .messages
+        .iter()
+        // Tool results are included with the tool calls that produced them
+        .filter(|m| m.role != "tool")
+        .map(|message| {
+            let mut exported = serde_json::json!({
+                "role": message.role,
+                "content": message.text(),
+                "timestamp": crate::time::iso(message.timestamp),
+            });
+            let attachments: Vec<Value> = message
+                .attachments()
+                .iter()
+                .map(|part| {
+                    serde_json::json!({
+                        "type": part.get("type").and_then(Value::as_str).unwrap_or("file"),
+                        "mimeType": part.get("mimeType"),
+                        "filename": part.get("filename"),
+                    })
+                })
+                .collect();
+            if !attachments.is_empty() {
+                exported["attachments"] = Value::Array(attachments);
+            }
+            if let Some(tool_calls) = message.tool_calls.as_ref().filter(|t| !t.is_empty()) {
+                exported["toolCalls"] = tool_calls
+                    .iter()
+                    .map(|call| {
+                        serde_json::json!({
+                            "name": call.name,
+                            "arguments": call.arguments,
+                            "status": call.status,
+                            "result": call.result,
+                        })
+                    })
+                    .collect();
+            }
+            exported
+        })
+        .collect();
+
+    let mut exported = serde_json::json!({
+        "version": "1.0",
+        "exportedAt": crate::time::iso(crate::time::now_ms()),
+        "conversation": {
+            "id": conversation.id,
+            "title": conversation.title,
+            "model": conversation.model_id,
+            "createdAt": crate::time::iso(conversation.created_at),
+            "updatedAt": crate::time::iso(conversation.updated_at),
+            "messages": messages,
+        },
+    });
+    if !conversation.system_prompt.is_empty() {
+        exported["conversation"]["systemPrompt"] =
+            Value::String(conversation.system_prompt.clone());
+    }
+    serde_json::to_string_pretty(&exported).unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escaped text with `inline code` spans.
+fn inline_html(text: &str) -> String {
+    escape_html(text)
+        .split('`')
+        .enumerate()
+        .map(|(i, segment)| {
+            // Odd segments are between backticks; an unmatched one is kept
+            if i % 2 == 1 {
+                format!("<code>{segment}</code>")
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Message text as HTML: fenced code blocks become `<pre>` blocks, the
+/// rest paragraphs with line breaks.
+fn text_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    let flush = |html: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|l| inline_html(l)).collect();
+            html.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((language, lines)), Some(_)) => {
+                let class = if language.is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"language-{}\"", escape_html(language))
+                };
+                html.push_str(&format!(
+                    "<pre><code{class}>{}</code></pre>\n",
+                    escape_html(&lines.join("\n"))
+                ));
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(language)) => {
+                flush(&mut html, &mut paragraph);
+                code = Some((language.trim().to_string(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush(&mut html, &mut paragraph),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    // An unclosed fence still renders as code
+    if let Some((_, lines)) = code {
+        html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            escape_html(&lines.join("\n"))
+        ));
+    }
+    flush(&mut html, &mut paragraph);
+    html
+}
+
+/// An attachment embedded in the page, so the file stands on its own.
+fn attachment_html(part: &Value) -> String {
+    let kind = part.get("type").and_then(Value::as_str).unwrap_or("file");
+    let mime_type = part
+        .get("mimeType")
+        .and_then(Value::as_str)
+        .unwrap_or("application/octet-stream");
+    let filename = part.get("filename").and_then(Value::as_str);
+    let source = match part.get("data").and_then(Value::as_str) {
+        Some(data) if data.starts_with("data:") => Some(data.to_string()),
+        Some(data) => Some(format!("data:{mime_type};base64,{data}")),
+        None => part
+            .get("url")
+            .and_then(Value::as_str)
+            .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+            .map(str::to_string),
+    };
+    let label = escape_html(filename.unwrap_or(kind));
+
+    match (kind, source) {
+        ("image", Some(source)) => {
+            let alt = part
+                .get("alt")
+                .and_then(Value::as_str)
+                .or(filename)
+                .unwrap_or("image");
+            format!(
+                "<figure><img src=\"{}\" alt=\"{}\"></figure>\n",
+                escape_html(&source),
+                escape_html(alt)
+            )
+        }
+        ("audio", Some(source)) => {
+            format!(
+                "<audio controls src=\"{}\"></audio>\n",
+                escape_html(&source)
+            )
+        }
+        (_, Some(source)) => format!(
+            "<p class=\"attachment\"><a download=\"{label}\" href=\"{}\">{label}</a></p>\n",
+            escape_html(&source)
+        ),
+        (_, None) => format!(
+            "<p class=\"attachment\">[{}: {label}]</p>\n",
+            escape_html(kind)
+        ),
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,-apple-system,sans-serif;max-width:50rem;\
+margin:2rem auto;padding:0 1rem;line-height:1.55;color:#1f2328}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:1.5rem}\
+.meta{color:#59636e;font-size:.9rem}\
+.message{margin:1.25rem 0;padding:.75rem 1rem;border-radius:.5rem;background:#f6f8fa}\
+.message.user{background:#ddf4ff}\
+.role{font-weight:600}.time{color:#59636e;font-size:.85rem;margin-left:.5rem}\
+pre{background:#0d1117;color:#e6edf3;padding:.75rem;border-radius:.375rem;overflow-x:auto}\
+code{font-family:ui-monospace,SFMono-Regular,Menlo,monospace;font-size:.9em}\
+img{max-width:100%;border-radius:.375rem}\
+details{margin:.5rem 0}summary{cursor:pointer}\
+@media (prefers-color-scheme:dark){body{background:#0d1117;color:#e6edf3}\
+.message{background:#161b22}.message.user{background:#0c2d48}.meta,.time{color:#9198a1}}";
+
+/// Renders a conversation as a standalone HTML page, with attachments
+/// embedded as data URLs and tool calls in collapsible sections.
+pub fn render_html(conversation: &Conversation) -> String {
+    let title = escape_html(&conversation.title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n\
+         <header>\n<h1>{title}</h1>\n<p class=\"meta\">Model: {} · Created: {} · Updated: {}</p>\n</header>\n",
+        escape_html(&conversation.model_id),
+        crate::time::format_datetime(conversation.created_at),
+        crate::time::format_datetime(conversation.updated_at)
+    );
+
+    if !conversation.system_prompt.is_empty() {
+        html.push_str(&format!(
+            "<details>\n<summary>System prompt</summary>\n<pre><code>{}</code></pre>\n</details>\n",
+            escape_html(&conversation.system_prompt)
+        ));
+    }
+
+    for message in &conversation.messages {
+        // Tool results are shown with the tool calls that produced them
+        if message.role == "tool" {
+            continue;
+        }
+        let (class, label) = if message.role == "user" {
+            ("user", "You")
+        } else {
+            ("assistant", "Assistant")
+        };
+        html.push_str(&format!(
+            "<section class=\"message {class}\">\n<p><span class=\"role\">{label}</span>\
+             <span class=\"time\">{}</span></p>\n",
+            crate::time::format_datetime(message.timestamp)
+        ));
+        for part in message.attachments() {
+            html.push_str(&attachment_html(part));
+        }
+        html.push_str(&text_html(&message.text()));
+
+        for call in message.tool_calls.iter().flatten() {
+            let arguments = serde_json::to_string_pretty(&call.arguments).unwrap_or_default();
+            html.push_str(&format!(
+                "<details>\n<summary>Tool: <code>{}</code> ({})</summary>\n\
+                 <pre><code class=\"language-json\">{}</code></pre>\n",
+                escape_html(&call.name),
+                escape_html(&call.status),
+                escape_html(&arguments)
+            ));
+            if let Some(result) = &call.result {
+                html.push_str(&format!(
+                    "<pre><code>{}</code></pre>\n",
+                    escape_html(result)
+                ));
+            }
+            html.push_str("</details>\n");
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// The assistant's answer to a comparison turn in one thread: the messages
+/// after the turn's user message, up to the next user message. Threads
+/// without an anchor for the turn fall back to the turn-th user message.
+fn turn_answer(conversation: &Conversation, turn: &ComparisonTurn) -> Option<String> {
+    let messages = &conversation.messages;
+    let start = match turn.anchors.get(&conversation.id) {
+        Some(message_id) => messages.iter().position(|m| &m.id == message_id)?,
+        None => {
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| m.role == "user")
+                .nth(usize::try_from(turn.turn).ok()?)?
+                .0
+        }
+    };
+    let answer: Vec<String> = messages[start + 1..]
+        .iter()
+        .take_while(|m| m.role != "user")
+        .filter(|m| m.role == "assistant")
+        .map(|m| m.text())
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    (!answer.is_empty()).then(|| answer.join("\n\n"))
+}
+
+/// Renders a comparison as Markdown: each prompt once, followed by every
+/// model's answer to it in pane order.
+pub fn render_comparison_markdown(
+    comparison: &Comparison,
+    conversations: &[Conversation],
+) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    lines.push(format!("# {}", comparison.title));
+    lines.push(String::new());
+
+    lines.push("## Metadata".to_string());
+    lines.push(String::new());
+    let models: Vec<&str> = comparison
+        .threads
+        .iter()
+        .map(|t| t.model_id.as_str())
+        .collect();
+    lines.push(format!("- **Models:** {}", models.join(", ")));
+    lines.push(format!(
+        "- **Created:** {}",
+        crate::time::format_datetime(comparison.created_at as u64)
+    ));
+    lines.push(format!(
+        "- **Updated:** {}",
+        crate::time::format_datetime(comparison.updated_at as u64)
+    ));
+    lines.push(String::new());
+
+    for turn in &comparison.turns {
+        lines.push(format!(
+            "## Prompt {} ({})",
+            turn.turn + 1,
+            crate::time::format_datetime(turn.sent_at as u64)
+        ));
+        lines.push(String::new());
+        lines.push(turn.prompt.clone());
+        lines.push(String::new());
+
+        for thread in &comparison.threads {
+            lines.push(format!("### {}", thread.model_id));
+            lines.push(String::new());
+            let answer = conversations
+                .iter()
+                .find(|c| c.id == thread.conversation_id)
+                .and_then(|c| turn_answer(c, turn));
+            lines.push(answer.unwrap_or_else(|| "*No response*".to_string()));
+            lines.push(String::new());
+        }
+
+        lines.push("---".to_string());
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+fn sanitize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || c.is_whitespace() || *c == '-')
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .take(50)
+        .collect()
+}
+
+/// Builds a stable, filesystem-safe filename for a conversation.
+pub fn export_filename(conversation: &Conversation, extension: &str) -> String {
+    let sanitized = sanitize_title(&conversation.title);
+
+    if sanitized.is_empty() {
+        format!("{}.{extension}", conversation.id)
+    } else {
+        format!("{sanitized}_{}.{extension}", conversation.id)
+    }
+}
+
+/// Filename for a comparison, prefixed so it sorts apart from the
+/// conversations it groups.
+fn comparison_filename(comparison: &Comparison) -> String {
+    let sanitized = sanitize_title(&comparison.title);
+
+    if sanitized.is_empty() {
+        format!("Comparison_{}.md", comparison.id)
+    } else {
+        format!("Comparison_{sanitized}_{}.md", comparison.id)
+    }
+}
+
+/// Writes a file via a temporary sibling and a rename, so readers (and backup
+/// tools) never see a half-written export.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    std::fs::write(&temp_path, contents).map_err(|e| {
+        log::error!("Failed to write export file: {e}");
+        format!("Failed to write export file: {e}")
+    })?;
+
+    std::fs::rename(&temp_path, path).map_err(|e| {
+        log::error!("Failed to finalize export file: {e}");
+        format!("Failed to finalize export file: {e}")
+    })
+}
+
+fn get_export_state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("scheduled-export.json"))
+}
+
+fn load_export_state(app: &AppHandle) -> ExportState {
+    get_export_state_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_export_state(app: &AppHandle, state: &ExportState) -> Result<(), String> {
+    let path = get_export_state_path(app)?;
+    let json_content = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize export state: {e}"))?;
+    write_atomic(&path, json_content.as_bytes())
+}
+
+/// Writes one export file unless the copy from a previous run is current,
+/// recording the outcome in `state` and `report`.
+fn export_one(
+    folder: &Path,
+    state: &mut ExportState,
+    report: &mut ExportReport,
+    key: &str,
+    filename: String,
+    updated_at: u64,
+    render: impl FnOnce() -> String,
+) {
+    let previous = state.files.get(key);
+
+    if previous.is_some_and(|f| f.updated_at == updated_at && folder.join(&f.filename).exists()) {
+        report.unchanged += 1;
+        return;
+    }
+
+    if let Err(e) = write_atomic(&folder.join(&filename), render().as_bytes()) {
+        log::warn!("Failed to export {key}: {e}");
+        report.failed += 1;
+        return;
+    }
+
+    // Remove the old file when a rename changed the filename
+    if let Some(old) = previous.filter(|f| f.filename != filename) {
+        let _ = std::fs::remove_file(folder.join(&old.filename));
+    }
+
+    state.files.insert(
+        key.to_string(),
+        ExportedFile {
+            filename,
+            updated_at,
+        },
+    );
+    report.exported += 1;
+}
+
+/// Exports every conversation that changed since the last run into `folder`.
+fn export_changed(app: &AppHandle, folder: &Path) -> Result<ExportReport, String> {
+    std::fs::create_dir_all(folder).map_err(|e| format!("Failed to create export folder: {e}"))?;
+
+    let folder_key = folder.to_string_lossy().to_string();
+    let mut state = load_export_state(app);
+    if state.folder != folder_key {
+        state = ExportState {
+            folder: folder_key.clone(),
+            ..Default::default()
+        };
+    }
+
+    let mut report = ExportReport {
+        folder: folder_key,
+        exported: 0,
+        unchanged: 0,
+        failed: 0,
+    };
+
+    let conversations = conversations::load_all(app)?;
+    for conversation in &conversations {
+        export_one(
+            folder,
+            &mut state,
+            &mut report,
+            &conversation.id,
+            export_filename(conversation, "md"),
+            conversation.updated_at,
+            || render_markdown(conversation),
+        );
+    }
+
+    // Each comparison also gets one file grouping its models' answers,
+    // refreshed whenever the comparison or any of its threads changes
+    let comparisons = comparisons::load_all(app).unwrap_or_else(|e| {
+        log::warn!("Skipping comparison exports: {e}");
+        Vec::new()
+    });
+    for comparison in &comparisons {
+        let threads: Vec<Conversation> = conversations
+            .iter()
+            .filter(|c| comparison.threads.iter().any(|t| t.conversation_id == c.id))
+            .cloned()
+            .collect();
+        let updated_at = threads
+            .iter()
+            .map(|c| c.updated_at)
+            .fold(comparison.updated_at as u64, u64::max);
+        export_one(
+            folder,
+            &mut state,
+            &mut report,
+            &format!("comparison:{}", comparison.id),
+            comparison_filename(comparison),
+            updated_at,
+            || render_comparison_markdown(comparison, &threads),
+        );
+    }
+
+    state.last_run = crate::time::now_ms();
+    save_export_state(app, &state)?;
+
+    log::info!(
+        "Scheduled export to {} complete: {} exported, {} unchanged, {} failed",
+        report.folder,
+        report.exported,
+        report.unchanged,
+        report.failed
+    );
+    Ok(report)
+}
+
+/// Scheduler job: runs the export once a day when it is enabled.
+pub fn run_scheduled_export(app: &AppHandle) -> Result<(), String> {
+    let settings = crate::read_preferences(app)?.scheduled_export;
+    let Some(folder) = settings.folder.filter(|_| settings.enabled) else {
+        return Ok(());
+    };
+
+    let state = load_export_state(app);
+    if crate::time::now_ms().saturating_sub(state.last_run) < EXPORT_INTERVAL_MS {
+        return Ok(());
+    }
+
+    export_changed(app, Path::new(&folder)).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn run_scheduled_export_now(app: AppHandle) -> Result<ExportReport, String> {
+    let settings = crate::read_preferences(&app)?.scheduled_export;
+    let folder = settings
+        .folder
+        .ok_or("No export folder has been configured")?;
+
+    log::info!("Running scheduled export now");
+    export_changed(&app, Path::new(&folder))
+}
+
+/// A conversation from the mirror, or else from the conversation store.
+fn find_conversation(app: &AppHandle, id: &str) -> Result<Conversation, String> {
+    if let Some(conversation) = conversations::load_all(app)?
+        .into_iter()
+        .find(|c| c.id == id)
+    {
+        return Ok(conversation);
+    }
+    crate::persistence::current(app)
+        .load(app, id)?
+        .ok_or_else(|| format!("Conversation {id} not found"))
+}
+
+/// Writes one conversation to `path` as Markdown, standalone HTML or
+/// canonical JSON.
+#[tauri::command]
+pub async fn export_conversation(
+    app: AppHandle,
+    id: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<(), String> {
+    let conversation = find_conversation(&app, &id)?;
+    write_atomic(Path::new(&path), format.render(&conversation).as_bytes())?;
+    log::info!("Exported conversation {id} to {path}");
+    Ok(())
+}
+
+/// Writes every conversation, from the mirror and the conversation store,
+/// into a zip archive at `path`, one file per conversation. Returns the
+/// number of conversations written.
+#[tauri::command]
+pub async fn export_all_conversations(
+    app: AppHandle,
+    format: ExportFormat,
+    path: String,
+) -> Result<usize, String> {
+    let mut operation = crate::operations::start(
+        &app,
+        crate::operations::OperationKind::Export,
+        format!("All conversations ({})", format.extension()),
+        crate::operations::ProgressUnit::Items,
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = export_archive(&app, format, &path, &mut operation);
+        operation.finish(result)
+    })
+    .await
+    .map_err(|e| format!("Export failed: {e}"))?
+}
+
+fn export_archive(
+    app: &AppHandle,
+    format: ExportFormat,
+    path: &str,
+    operation: &mut crate::operations::Operation,
+) -> Result<usize, String> {
+    let mut all = conversations::load_all(app)?;
+    let mirrored: std::collections::HashSet<String> = all.iter().map(|c| c.id.clone()).collect();
+    let storage = crate::persistence::current(app);
+    for id in storage.ids(app)? {
+        if !mirrored.contains(&id) {
+            all.extend(storage.load(app, &id)?);
+        }
+    }
+    all.sort_by_key(|c| c.created_at);
+
+    let total = all.len() as u64;
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let mut archive = zip::ZipWriter::new(&mut buffer);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (index, conversation) in all.iter().enumerate() {
+        operation.check_cancelled()?;
+        archive
+            .start_file(export_filename(conversation, format.extension()), options)
+            .and_then(|_| {
+                std::io::Write::write_all(&mut archive, format.render(conversation).as_bytes())
+                    .map_err(Into::into)
+            })
+            .map_err(|e| format!("Failed to write export archive: {e}"))?;
+        operation.progress(index as u64 + 1, Some(total), None);
+    }
+    archive
+        .finish()
+        .map_err(|e| format!("Failed to write export archive: {e}"))?;
+
+    write_atomic(Path::new(path), buffer.get_ref())?;
+    log::info!("Exported {} conversations to {path}", all.len());
+    Ok(all.len())
+}
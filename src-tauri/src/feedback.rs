@@ -0,0 +1,262 @@
+// Response Feedback
+// =================
+//
+// Thumbs up/down ratings and free-text notes on assistant messages, kept
+// locally in the `feedback` table so teams can mine their own ratings
+// instead of sending them to a provider. Exports join each rating with the
+// conversation up to the rated message, one JSON object per line, in the
+// chat `messages` shape fine-tuning and eval tools expect.
+
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::conversations::{self, Conversation};
+use crate::time::now_ms;
+
+const MAX_NOTE_CHARS: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feedback {
+    pub conversation_id: String,
+    pub message_id: String,
+    pub model_id: String,
+    /// 1 for thumbs up, -1 for thumbs down, `None` for a note alone
+    pub rating: Option<i64>,
+    pub note: Option<String>,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: i64,
+    /// Milliseconds since the UNIX epoch
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFeedbackStats {
+    pub model_id: String,
+    pub up: u32,
+    pub down: u32,
+    /// Messages with a note
+    pub annotated: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedbackStats {
+    pub up: u32,
+    pub down: u32,
+    pub annotated: u32,
+    /// Per model, most rated first
+    pub models: Vec<ModelFeedbackStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportRecord<'a> {
+    #[serde(flatten)]
+    feedback: &'a Feedback,
+    /// The conversation up to and including the rated message; empty if the
+    /// conversation has since been deleted
+    messages: Vec<ExportMessage>,
+}
+
+fn row_to_feedback(row: &rusqlite::Row) -> rusqlite::Result<Feedback> {
+    Ok(Feedback {
+        conversation_id: row.get("conversation_id")?,
+        message_id: row.get("message_id")?,
+        model_id: row.get("model_id")?,
+        rating: row.get("rating")?,
+        note: row.get("note")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+/// The conversation up to `message_id` as role/content pairs, with the
+/// system prompt first. Tool traffic is left out.
+fn context(conversation: &Conversation, message_id: &str) -> Vec<ExportMessage> {
+    let Some(end) = conversation
+        .messages
+        .iter()
+        .position(|m| m.id == message_id)
+    else {
+        return Vec::new();
+    };
+
+    let mut messages = Vec::new();
+    if !conversation.system_prompt.trim().is_empty() {
+        messages.push(ExportMessage {
+            role: "system".to_string(),
+            content: conversation.system_prompt.clone(),
+        });
+    }
+    messages.extend(
+        conversation.messages[..=end]
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .map(|m| ExportMessage {
+                role: m.role.clone(),
+                content: m.text(),
+            })
+            .filter(|m| !m.content.trim().is_empty()),
+    );
+    messages
+}
+
+/// Rates or annotates a message. Clearing both the rating and the note
+/// removes the feedback. Returns the stored feedback, if any is left.
+#[tauri::command]
+pub async fn set_feedback(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+    model_id: String,
+    rating: Option<i64>,
+    note: Option<String>,
+) -> Result<Option<Feedback>, String> {
+    crate::validate_string_input(&conversation_id, 256, "Conversation id")?;
+    crate::validate_string_input(&message_id, 256, "Message id")?;
+    crate::validate_string_input(&model_id, 256, "Model id")?;
+    if rating.is_some_and(|r| r != 1 && r != -1) {
+        return Err("Rating must be 1 or -1".to_string());
+    }
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if let Some(note) = &note {
+        crate::validate_string_input(note, MAX_NOTE_CHARS, "Note")?;
+    }
+
+    let conn = crate::db::open(&app)?;
+    if rating.is_none() && note.is_none() {
+        conn.execute(
+            "DELETE FROM feedback WHERE conversation_id = ?1 AND message_id = ?2",
+            params![conversation_id, message_id],
+        )
+        .map_err(|e| format!("Failed to clear feedback: {e}"))?;
+        return Ok(None);
+    }
+
+    conn.query_row(
+        "INSERT INTO feedback
+             (conversation_id, message_id, model_id, rating, note, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+         ON CONFLICT (conversation_id, message_id) DO UPDATE SET
+             model_id = excluded.model_id,
+             rating = excluded.rating,
+             note = excluded.note,
+             updated_at = excluded.updated_at
+         RETURNING *",
+        params![
+            conversation_id,
+            message_id,
+            model_id,
+            rating,
+            note,
+            now_ms() as i64
+        ],
+        row_to_feedback,
+    )
+    .map(Some)
+    .map_err(|e| {
+        log::error!("Failed to store feedback: {e}");
+        format!("Failed to store feedback: {e}")
+    })
+}
+
+/// Feedback on the messages of one conversation.
+#[tauri::command]
+pub async fn get_feedback(
+    app: AppHandle,
+    conversation_id: String,
+) -> Result<Vec<Feedback>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.prepare("SELECT * FROM feedback WHERE conversation_id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![conversation_id], row_to_feedback)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to read feedback: {e}"))
+}
+
+/// Rating counts overall and per model.
+#[tauri::command]
+pub async fn get_feedback_stats(app: AppHandle) -> Result<FeedbackStats, String> {
+    let conn = crate::db::open(&app)?;
+    let models = conn
+        .prepare(
+            "SELECT model_id,
+                    SUM(rating IS 1) AS up,
+                    SUM(rating IS -1) AS down,
+                    SUM(note IS NOT NULL) AS annotated
+             FROM feedback GROUP BY model_id ORDER BY COUNT(*) DESC, model_id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(ModelFeedbackStats {
+                    model_id: row.get("model_id")?,
+                    up: row.get("up")?,
+                    down: row.get("down")?,
+                    annotated: row.get("annotated")?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to read feedback stats: {e}"))?;
+
+    Ok(FeedbackStats {
+        up: models.iter().map(|m| m.up).sum(),
+        down: models.iter().map(|m| m.down).sum(),
+        annotated: models.iter().map(|m| m.annotated).sum(),
+        models,
+    })
+}
+
+/// Writes feedback to `path` as JSON Lines, each record carrying the
+/// conversation up to the rated message. `rating` limits the export to
+/// thumbs up (1) or thumbs down (-1). Returns the number of records.
+#[tauri::command]
+pub async fn export_feedback(
+    app: AppHandle,
+    path: String,
+    rating: Option<i64>,
+) -> Result<usize, String> {
+    let feedback: Vec<Feedback> = {
+        let conn = crate::db::open(&app)?;
+        conn.prepare("SELECT * FROM feedback WHERE ?1 IS NULL OR rating = ?1 ORDER BY created_at")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![rating], row_to_feedback)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(|e| format!("Failed to read feedback: {e}"))?
+    };
+
+    let conversations: HashMap<String, Conversation> = conversations::load_all(&app)?
+        .into_iter()
+        .map(|c| (c.id.clone(), c))
+        .collect();
+
+    let mut contents = String::new();
+    for entry in &feedback {
+        let messages = conversations
+            .get(&entry.conversation_id)
+            .map(|c| context(c, &entry.message_id))
+            .unwrap_or_default();
+        let line = serde_json::to_string(&ExportRecord {
+            feedback: entry,
+            messages,
+        })
+        .map_err(|e| format!("Failed to serialize feedback: {e}"))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+
+    crate::export::write_atomic(Path::new(&path), contents.as_bytes())?;
+    log::info!("Exported {} feedback records to {path}", feedback.len());
+    Ok(feedback.len())
+}
@@ -0,0 +1,187 @@
+// File Drop
+// =========
+//
+// Files dropped on a window are taken in here rather than by the webview,
+// which gets only their paths and may not be allowed to read them. Each
+// file is checked (a type attachments support, within the attachment size
+// limit), copied into the attachment store, and described: text files come
+// with their text, PNG and JPEG images with a small PNG thumbnail. One
+// `files-ingested` event then goes to the window the files were dropped on,
+// listing what was stored and what was refused and why.
+//
+// Paths only come from the operating system's drop, so a drop is the user
+// choosing those files; nothing here takes paths from the webview.
+
+use base64::Engine;
+use image::{ImageError, ImageFormat, ImageReader, Limits};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+/// Files beyond this many in one drop are refused
+const MAX_DROPPED_FILES: usize = 20;
+/// Text beyond this many characters is cut off
+const MAX_TEXT_CHARS: usize = 100_000;
+/// Longest side of a thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+/// Images that take more memory decoded get no thumbnail
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestedFile {
+    /// File name without the directory
+    pub name: String,
+    /// Put in a content part as `blobRef` to refer to the file
+    pub hash: String,
+    pub mime_type: String,
+    /// Bytes of the file itself
+    pub size: u64,
+    /// Contents of text files, up to `MAX_TEXT_CHARS` characters
+    pub text: Option<String>,
+    pub text_truncated: bool,
+    /// Base64 PNG of images, at most `THUMBNAIL_SIZE` pixels a side
+    pub thumbnail: Option<String>,
+    /// Pixels of the full image
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedFile {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesIngested {
+    /// Label of the window the files were dropped on
+    pub window: String,
+    pub files: Vec<IngestedFile>,
+    pub rejected: Vec<RejectedFile>,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/") || mime_type == "application/json"
+}
+
+/// The text of a UTF-8 file, cut off after `MAX_TEXT_CHARS` characters.
+fn extract_text(bytes: &[u8]) -> Option<(String, bool)> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => Some((text[..end].to_string(), true)),
+        None => Some((text.to_string(), false)),
+    }
+}
+
+/// A base64 PNG thumbnail of a PNG or JPEG image, with the image's size.
+fn thumbnail(bytes: &[u8]) -> Result<(String, u32, u32), String> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to read image: {e}"))?;
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    reader.limits(limits);
+    let image = reader.decode().map_err(|e| match e {
+        ImageError::Limits(_) => "Image is too large for a thumbnail".to_string(),
+        e => format!("Failed to decode image: {e}"),
+    })?;
+    let mut png = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode thumbnail: {e}"))?;
+    Ok((
+        base64::engine::general_purpose::STANDARD.encode(png),
+        image.width(),
+        image.height(),
+    ))
+}
+
+fn ingest_file(app: &AppHandle, path: &Path) -> Result<IngestedFile, String> {
+    let mime_type = crate::attachments::guess_mime_type(path)
+        .ok_or_else(|| "This file type can't be attached".to_string())?;
+    let bytes = crate::attachments::read_file(path)?;
+    let stored = crate::attachments::store(
+        app,
+        base64::engine::general_purpose::STANDARD.encode(&bytes),
+        Some(mime_type.clone()),
+    )?;
+
+    let mut file = IngestedFile {
+        name: file_name(path),
+        hash: stored.hash,
+        mime_type,
+        size: bytes.len() as u64,
+        text: None,
+        text_truncated: false,
+        thumbnail: None,
+        width: None,
+        height: None,
+    };
+    if is_text(&file.mime_type) {
+        if let Some((text, truncated)) = extract_text(&bytes) {
+            file.text = Some(text);
+            file.text_truncated = truncated;
+        }
+    } else if matches!(file.mime_type.as_str(), "image/png" | "image/jpeg") {
+        // The file is stored either way; only the preview is missing
+        match thumbnail(&bytes) {
+            Ok((thumbnail, width, height)) => {
+                file.thumbnail = Some(thumbnail);
+                file.width = Some(width);
+                file.height = Some(height);
+            }
+            Err(e) => log::warn!("No thumbnail for dropped {}: {e}", file.hash),
+        }
+    }
+    Ok(file)
+}
+
+/// Takes in the files dropped on window `label` off the event loop, then
+/// emits `files-ingested` to it.
+pub fn ingest(app: &AppHandle, label: String, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut ingested = FilesIngested {
+            window: label,
+            files: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for (i, path) in paths.iter().enumerate() {
+            if i >= MAX_DROPPED_FILES {
+                ingested.rejected.push(RejectedFile {
+                    name: file_name(path),
+                    reason: format!("At most {MAX_DROPPED_FILES} files can be dropped at once"),
+                });
+                continue;
+            }
+            match ingest_file(&app, path) {
+                Ok(file) => ingested.files.push(file),
+                Err(reason) => ingested.rejected.push(RejectedFile {
+                    name: file_name(path),
+                    reason,
+                }),
+            }
+        }
+        log::info!(
+            "Ingested {} dropped files on {}, refused {}",
+            ingested.files.len(),
+            ingested.window,
+            ingested.rejected.len()
+        );
+        if let Err(e) = app.emit_to(ingested.window.as_str(), "files-ingested", &ingested) {
+            log::warn!("Failed to emit files-ingested event: {e}");
+        }
+    });
+}
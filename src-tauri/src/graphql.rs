@@ -0,0 +1,768 @@
+// GraphQL Tools
+// =============
+//
+// The GraphQL counterpart of OpenAPI tools. Registering an endpoint runs an
+// introspection query against it and turns the selected root fields into
+// tools: the field's arguments become the tool's JSON Schema and are passed
+// as variables, and the selection set is generated from the schema once, at
+// registration. Generation is bounded by a maximum depth and a maximum
+// number of selected fields, so no tool can ask for an unbounded amount of
+// data; fields that need arguments are left out of selections. Queries are
+// exposed by default, mutations only when selected by name.
+//
+// Registered endpoints live in `graphql_tools.json` in the app data
+// directory with their generated documents. Credentials are kept in the
+// keychain under `graphql:<id>` and sent as `openapi::OpenApiAuth`
+// describes.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::keychain;
+use crate::openapi::{self, HttpToolResult, OpenApiAuth};
+
+const MAX_ENDPOINTS: usize = 50;
+const MAX_OPERATIONS: usize = 200;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_SCHEMA_BYTES: usize = 20 * 1024 * 1024;
+const DEFAULT_RESPONSE_BYTES: usize = 256 * 1024;
+const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+const DEFAULT_DEPTH: usize = 2;
+const MAX_DEPTH: usize = 5;
+const DEFAULT_FIELDS: usize = 50;
+const MAX_FIELDS: usize = 500;
+/// Nesting of input objects described in a tool's schema
+const MAX_INPUT_DEPTH: usize = 5;
+const MAX_DESCRIPTION_CHARS: usize = 1024;
+const MAX_PREFIX_CHARS: usize = 20;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+const INTROSPECTION_QUERY: &str = "
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    types {
+      kind
+      name
+      description
+      fields(includeDeprecated: false) {
+        name
+        description
+        args { name description defaultValue type { ...TypeRef } }
+        type { ...TypeRef }
+      }
+      inputFields { name description defaultValue type { ...TypeRef } }
+      enumValues(includeDeprecated: false) { name }
+    }
+  }
+}
+fragment TypeRef on __Type {
+  kind name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name
+    ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Query,
+    Mutation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlOperation {
+    /// Name of the tool, unique across all registered APIs
+    pub tool_name: String,
+    pub kind: OperationKind,
+    /// Root field the operation selects
+    pub field: String,
+    pub description: String,
+    /// Generated document; the tool's arguments are its variables
+    pub document: String,
+    /// Deepest selection set in the document
+    pub depth: usize,
+    /// Fields selected by the document, a measure of its cost
+    pub field_count: usize,
+    /// JSON Schema of the tool's arguments
+    pub input_schema: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlIntegration {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    pub name: String,
+    pub endpoint: String,
+    pub auth: OpenApiAuth,
+    pub max_depth: usize,
+    pub max_fields: usize,
+    /// Response bytes returned to the model; the rest is cut off
+    pub max_response_bytes: usize,
+    pub operations: Vec<GraphQlOperation>,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: i64,
+}
+
+/// A registered endpoint as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlIntegrationInfo {
+    #[serde(flatten)]
+    pub integration: GraphQlIntegration,
+    /// A secret is stored in the keychain
+    pub has_credential: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlOptions {
+    /// Defaults to the endpoint's host
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Root fields to expose; every query field when left out. Mutations
+    /// are only exposed when listed here.
+    #[serde(default)]
+    pub operations: Option<Vec<String>>,
+    /// Nesting of generated selection sets, 1 to 5
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Fields selected by one generated document
+    #[serde(default)]
+    pub max_fields: Option<usize>,
+    #[serde(default)]
+    pub max_response_bytes: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntrospectionSchema {
+    query_type: Option<NamedType>,
+    mutation_type: Option<NamedType>,
+    types: Vec<SchemaType>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedType {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaType {
+    kind: String,
+    name: String,
+    #[serde(default)]
+    fields: Option<Vec<SchemaField>>,
+    #[serde(default)]
+    input_fields: Option<Vec<InputValue>>,
+    #[serde(default)]
+    enum_values: Option<Vec<NamedType>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaField {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    ty: TypeRef,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InputValue {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    default_value: Option<String>,
+    #[serde(rename = "type")]
+    ty: TypeRef,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypeRef {
+    kind: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    of_type: Option<Box<TypeRef>>,
+}
+
+impl TypeRef {
+    /// The type as written in a variable definition, e.g. `[ID!]!`.
+    fn signature(&self) -> String {
+        let inner = || self.of_type.as_deref().map(TypeRef::signature);
+        match self.kind.as_str() {
+            "NON_NULL" => format!("{}!", inner().unwrap_or_default()),
+            "LIST" => format!("[{}]", inner().unwrap_or_default()),
+            _ => self.name.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Name of the type inside any list and non-null wrappers.
+    fn base_name(&self) -> &str {
+        match (&self.name, &self.of_type) {
+            (Some(name), _) => name,
+            (None, Some(inner)) => inner.base_name(),
+            (None, None) => "",
+        }
+    }
+}
+
+impl InputValue {
+    fn is_required(&self) -> bool {
+        self.ty.kind == "NON_NULL" && self.default_value.is_none()
+    }
+}
+
+type Types<'a> = HashMap<&'a str, &'a SchemaType>;
+
+fn credential_account(id: &str) -> String {
+    format!("graphql:{id}")
+}
+
+fn get_registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("graphql_tools.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<GraphQlIntegration>, String> {
+    let path = get_registry_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read GraphQL tools: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse GraphQL tools: {e}");
+        format!("Failed to parse GraphQL tools: {e}")
+    })
+}
+
+fn store(app: &AppHandle, integrations: &[GraphQlIntegration]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(integrations)
+        .map_err(|e| format!("Failed to serialize GraphQL tools: {e}"))?;
+    crate::export::write_atomic(&get_registry_path(app)?, &json)
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    crate::network::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+/// POSTs a GraphQL request with the endpoint's credential.
+async fn post(
+    endpoint: &str,
+    auth: &OpenApiAuth,
+    credential: Option<String>,
+    query: &str,
+    variables: Value,
+) -> Result<reqwest::Response, String> {
+    let mut url = openapi::parse_http_url(endpoint)?;
+    openapi::authorize_url(&mut url, auth, credential.as_deref());
+    let request = http_client()?
+        .post(url)
+        .json(&json!({ "query": query, "variables": variables }));
+    openapi::authorize_request(request, auth, credential)
+        .send()
+        .await
+        .map_err(|e| format!("Request to {endpoint} failed: {e}"))
+}
+
+/// Runs the introspection query and returns the schema.
+async fn introspect(
+    endpoint: &str,
+    auth: &OpenApiAuth,
+    credential: Option<String>,
+) -> Result<IntrospectionSchema, String> {
+    let response = post(endpoint, auth, credential, INTROSPECTION_QUERY, json!({})).await?;
+    let status = response.status();
+    let (body, truncated) = openapi::read_limited(response, MAX_SCHEMA_BYTES).await?;
+    if truncated {
+        return Err("Schema is too large".to_string());
+    }
+    if !status.is_success() {
+        return Err(format!("Introspection returned {status}"));
+    }
+
+    let mut body: Value = serde_json::from_slice(&body)
+        .map_err(|e| format!("Invalid introspection response: {e}"))?;
+    if let Some(message) = body.pointer("/errors/0/message").and_then(Value::as_str) {
+        return Err(format!("Introspection failed: {message}"));
+    }
+    let schema = body
+        .pointer_mut("/data/__schema")
+        .map(Value::take)
+        .ok_or_else(|| "Introspection response has no schema".to_string())?;
+    serde_json::from_value(schema).map_err(|e| format!("Invalid introspection response: {e}"))
+}
+
+/// JSON Schema for a value of GraphQL input type `ty`.
+fn input_schema(types: &Types, ty: &TypeRef, depth: usize) -> Value {
+    match ty.kind.as_str() {
+        "NON_NULL" => match &ty.of_type {
+            Some(inner) => input_schema(types, inner, depth),
+            None => json!({}),
+        },
+        "LIST" => match &ty.of_type {
+            Some(inner) => json!({ "type": "array", "items": input_schema(types, inner, depth) }),
+            None => json!({ "type": "array" }),
+        },
+        _ => {
+            let name = ty.name.as_deref().unwrap_or_default();
+            match (name, types.get(name)) {
+                ("Int", _) => json!({ "type": "integer" }),
+                ("Float", _) => json!({ "type": "number" }),
+                ("Boolean", _) => json!({ "type": "boolean" }),
+                ("String" | "ID", _) => json!({ "type": "string" }),
+                (_, Some(schema_type)) if schema_type.kind == "ENUM" => {
+                    let values: Vec<&str> = schema_type
+                        .enum_values
+                        .iter()
+                        .flatten()
+                        .map(|v| v.name.as_str())
+                        .collect();
+                    json!({ "type": "string", "enum": values })
+                }
+                (_, Some(schema_type))
+                    if schema_type.kind == "INPUT_OBJECT" && depth < MAX_INPUT_DEPTH =>
+                {
+                    object_schema(types, schema_type.input_fields.iter().flatten(), depth + 1)
+                }
+                // Custom scalars and input objects nested too deeply
+                _ => json!({}),
+            }
+        }
+    }
+}
+
+/// JSON Schema of an object with one property per input value.
+fn object_schema<'a>(
+    types: &Types,
+    values: impl Iterator<Item = &'a InputValue>,
+    depth: usize,
+) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for value in values {
+        let mut schema = input_schema(types, &value.ty, depth);
+        if let (Some(description), Some(object)) =
+            (value.description.as_deref(), schema.as_object_mut())
+        {
+            object.insert("description".to_string(), json!(description));
+        }
+        if value.is_required() {
+            required.push(json!(value.name));
+        }
+        properties.insert(value.name.clone(), schema);
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Selection set for a value of type `type_name`, at most `depth` levels
+/// deep and with at most `*budget` fields, which is reduced by the fields
+/// used. Returns the set and its depth, or `None` for leaf types or when
+/// nothing could be selected.
+fn selection(
+    types: &Types,
+    type_name: &str,
+    depth: usize,
+    budget: &mut usize,
+) -> Option<(String, usize)> {
+    let schema_type = types.get(type_name)?;
+    match schema_type.kind.as_str() {
+        "OBJECT" | "INTERFACE" => {}
+        "UNION" if *budget > 0 && depth > 0 => {
+            *budget -= 1;
+            return Some(("{ __typename }".to_string(), 1));
+        }
+        _ => return None,
+    }
+    if depth == 0 {
+        return None;
+    }
+
+    let mut selected = Vec::new();
+    let mut deepest = 1;
+    for field in schema_type.fields.iter().flatten() {
+        if *budget == 0 {
+            break;
+        }
+        if field.args.iter().any(InputValue::is_required) {
+            continue;
+        }
+        let field_type = field.ty.base_name();
+        let is_leaf = types
+            .get(field_type)
+            .is_none_or(|t| matches!(t.kind.as_str(), "SCALAR" | "ENUM"));
+        if is_leaf {
+            *budget -= 1;
+            selected.push(field.name.clone());
+        } else if depth > 1 {
+            let mut inner_budget = budget.saturating_sub(1);
+            if let Some((inner, inner_depth)) =
+                selection(types, field_type, depth - 1, &mut inner_budget)
+            {
+                *budget = inner_budget;
+                deepest = deepest.max(inner_depth + 1);
+                selected.push(format!("{} {inner}", field.name));
+            }
+        }
+    }
+    (!selected.is_empty()).then(|| (format!("{{ {} }}", selected.join(" ")), deepest))
+}
+
+/// Generates tools for the root fields of `kind`, restricted to `selected`
+/// if given. Tool names are prefixed with `prefix` and kept out of `taken`.
+#[allow(clippy::too_many_arguments)]
+fn generate_operations(
+    types: &Types,
+    root: Option<&str>,
+    kind: OperationKind,
+    prefix: &str,
+    selected: Option<&[String]>,
+    max_depth: usize,
+    max_fields: usize,
+    taken: &mut HashSet<String>,
+) -> Vec<GraphQlOperation> {
+    let Some(root) = root.and_then(|name| types.get(name)) else {
+        return Vec::new();
+    };
+    let keyword = match kind {
+        OperationKind::Query => "query",
+        OperationKind::Mutation => "mutation",
+    };
+
+    let mut operations = Vec::new();
+    for field in root.fields.iter().flatten() {
+        match selected {
+            Some(selected) if !selected.contains(&field.name) => continue,
+            None if kind == OperationKind::Mutation => continue,
+            _ => {}
+        }
+
+        let mut budget = max_fields.saturating_sub(1);
+        let (selection_set, depth) =
+            match selection(types, field.ty.base_name(), max_depth, &mut budget) {
+                Some((set, depth)) => (format!(" {set}"), depth),
+                None if types
+                    .get(field.ty.base_name())
+                    .is_some_and(|t| matches!(t.kind.as_str(), "OBJECT" | "INTERFACE")) =>
+                {
+                    (" { __typename }".to_string(), 1)
+                }
+                None => (String::new(), 0),
+            };
+        let field_count = max_fields - budget;
+
+        let variables = field
+            .args
+            .iter()
+            .map(|arg| format!("${}: {}", arg.name, arg.ty.signature()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let arguments = field
+            .args
+            .iter()
+            .map(|arg| format!("{}: ${}", arg.name, arg.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let operation_name = openapi::sanitize_tool_name(&field.name).replace('-', "_");
+        let document = if field.args.is_empty() {
+            format!(
+                "{keyword} {operation_name} {{ {}{selection_set} }}",
+                field.name
+            )
+        } else {
+            format!(
+                "{keyword} {operation_name}({variables}) {{ {}({arguments}){selection_set} }}",
+                field.name
+            )
+        };
+
+        let description = field
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(|d| d.chars().take(MAX_DESCRIPTION_CHARS).collect())
+            .unwrap_or_else(|| format!("GraphQL {keyword} {}", field.name));
+
+        operations.push(GraphQlOperation {
+            tool_name: openapi::unique_tool_name(prefix, &field.name, taken),
+            kind,
+            field: field.name.clone(),
+            description,
+            document,
+            depth,
+            field_count,
+            input_schema: object_schema(types, field.args.iter(), 0),
+        });
+    }
+    operations
+}
+
+/// Names of the tools generated from GraphQL endpoints.
+pub fn tool_names(app: &AppHandle) -> Vec<String> {
+    load(app)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|i| i.operations.into_iter().map(|o| o.tool_name))
+        .collect()
+}
+
+/// Deletes the keychain entries of every registered endpoint. Used when
+/// wiping app data; failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for integration in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&credential_account(&integration.id)) {
+            log::warn!("Failed to delete credential of {}: {e}", integration.id);
+        }
+    }
+}
+
+/// Introspects a GraphQL endpoint and registers its selected root fields as
+/// tools. A non-empty `credential` is stored in the keychain and sent as
+/// `auth` describes, including for the introspection query.
+#[tauri::command]
+pub async fn register_graphql_tool(
+    app: AppHandle,
+    endpoint: String,
+    auth: Option<OpenApiAuth>,
+    credential: Option<String>,
+    options: Option<GraphQlOptions>,
+) -> Result<GraphQlIntegrationInfo, String> {
+    let endpoint = endpoint.trim().to_string();
+    crate::validate_string_input(&endpoint, 4096, "Endpoint")?;
+    let url = openapi::parse_http_url(&endpoint)?;
+    let auth = auth.unwrap_or_default();
+    openapi::validate_auth(&auth)?;
+    let options = options.unwrap_or_default();
+    let max_depth = options
+        .max_depth
+        .unwrap_or(DEFAULT_DEPTH)
+        .clamp(1, MAX_DEPTH);
+    let max_fields = options
+        .max_fields
+        .unwrap_or(DEFAULT_FIELDS)
+        .clamp(1, MAX_FIELDS);
+    let max_response_bytes = options
+        .max_response_bytes
+        .unwrap_or(DEFAULT_RESPONSE_BYTES)
+        .clamp(1024, MAX_RESPONSE_BYTES);
+    let credential = credential.filter(|secret| !secret.is_empty());
+    if !matches!(auth, OpenApiAuth::None) && credential.is_none() {
+        return Err("A credential is required for this authentication".to_string());
+    }
+
+    let mut integrations = load(&app)?;
+    if integrations.len() >= MAX_ENDPOINTS {
+        return Err(format!(
+            "Too many GraphQL integrations (max {MAX_ENDPOINTS})"
+        ));
+    }
+
+    let schema = introspect(&endpoint, &auth, credential.clone())
+        .await
+        .map_err(|e| {
+            log::error!("Failed to introspect {endpoint}: {e}");
+            e
+        })?;
+    let types: Types = schema.types.iter().map(|t| (t.name.as_str(), t)).collect();
+
+    let name = options
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .or(url.host_str())
+        .unwrap_or("graphql")
+        .to_string();
+    crate::validate_string_input(&name, MAX_NAME_CHARS, "Name")?;
+    let prefix: String = openapi::sanitize_tool_name(&name.to_ascii_lowercase())
+        .chars()
+        .take(MAX_PREFIX_CHARS)
+        .collect();
+
+    let mut taken: HashSet<String> = tool_names(&app)
+        .into_iter()
+        .chain(openapi::tool_names(&app))
+        .collect();
+    let selected = options.operations.as_deref();
+    let mut operations = generate_operations(
+        &types,
+        schema.query_type.as_ref().map(|t| t.name.as_str()),
+        OperationKind::Query,
+        &prefix,
+        selected,
+        max_depth,
+        max_fields,
+        &mut taken,
+    );
+    operations.extend(generate_operations(
+        &types,
+        schema.mutation_type.as_ref().map(|t| t.name.as_str()),
+        OperationKind::Mutation,
+        &prefix,
+        selected,
+        max_depth,
+        max_fields,
+        &mut taken,
+    ));
+
+    if let Some(selected) = selected {
+        let missing: Vec<&str> = selected
+            .iter()
+            .map(String::as_str)
+            .filter(|s| !operations.iter().any(|o| o.field == *s))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Fields not found in schema: {}",
+                missing.join(", ")
+            ));
+        }
+    }
+    if operations.is_empty() {
+        return Err("Schema has no fields that can be used as tools".to_string());
+    }
+    if operations.len() > MAX_OPERATIONS {
+        return Err(format!(
+            "Schema has {} root fields; select at most {MAX_OPERATIONS}",
+            operations.len()
+        ));
+    }
+
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate id: {e}"))?;
+    let integration = GraphQlIntegration {
+        id: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        name,
+        endpoint,
+        auth,
+        max_depth,
+        max_fields,
+        max_response_bytes,
+        operations,
+        created_at: crate::time::now_ms() as i64,
+    };
+
+    let has_credential = credential.is_some();
+    if let Some(secret) = credential {
+        keychain::set(&credential_account(&integration.id), &secret)?;
+    }
+    integrations.push(integration.clone());
+    store(&app, &integrations)?;
+
+    log::info!(
+        "Registered GraphQL integration {} with {} tools",
+        integration.id,
+        integration.operations.len()
+    );
+    Ok(GraphQlIntegrationInfo {
+        integration,
+        has_credential,
+    })
+}
+
+#[tauri::command]
+pub async fn list_graphql_tools(app: AppHandle) -> Result<Vec<GraphQlIntegrationInfo>, String> {
+    load(&app)?
+        .into_iter()
+        .map(|integration| {
+            let has_credential = keychain::get(&credential_account(&integration.id))?.is_some();
+            Ok(GraphQlIntegrationInfo {
+                integration,
+                has_credential,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn remove_graphql_tool(app: AppHandle, id: String) -> Result<(), String> {
+    let mut integrations = load(&app)?;
+    integrations.retain(|i| i.id != id);
+    store(&app, &integrations)?;
+    keychain::delete(&credential_account(&id))?;
+    log::info!("Removed GraphQL integration {id}");
+    Ok(())
+}
+
+/// Runs the document behind a generated tool with the model's arguments as
+/// variables. GraphQL errors are returned as results so the model can see
+/// them; only failures to make the request at all are errors.
+#[tauri::command]
+pub async fn call_graphql_tool(
+    app: AppHandle,
+    tool_name: String,
+    arguments: Option<Value>,
+) -> Result<HttpToolResult, String> {
+    let integrations = load(&app)?;
+    let (integration, operation) = integrations
+        .iter()
+        .find_map(|integration| {
+            integration
+                .operations
+                .iter()
+                .find(|o| o.tool_name == tool_name)
+                .map(|operation| (integration, operation))
+        })
+        .ok_or_else(|| format!("GraphQL tool {tool_name} not found"))?;
+    let variables = match arguments {
+        Some(Value::Object(arguments)) => Value::Object(arguments),
+        None | Some(Value::Null) => json!({}),
+        Some(_) => return Err("Tool arguments must be an object".to_string()),
+    };
+
+    let credential = keychain::get(&credential_account(&integration.id))?;
+    if !matches!(integration.auth, OpenApiAuth::None) && credential.is_none() {
+        return Err(format!("No credential stored for {}", integration.name));
+    }
+
+    log::info!("Calling GraphQL tool {tool_name} ({})", operation.field);
+    let response = post(
+        &integration.endpoint,
+        &integration.auth,
+        credential,
+        &operation.document,
+        variables,
+    )
+    .await
+    .map_err(|e| {
+        log::error!("GraphQL tool {tool_name} failed: {e}");
+        e
+    })?;
+    let mut result = openapi::tool_result(response, integration.max_response_bytes).await?;
+    // GraphQL reports most failures with a 200 and an `errors` list
+    if !result.truncated {
+        let has_errors = serde_json::from_str::<Value>(&result.content)
+            .ok()
+            .and_then(|body| body.get("errors").and_then(Value::as_array).map(Vec::len))
+            .is_some_and(|count| count > 0);
+        result.is_error |= has_errors;
+    }
+    Ok(result)
+}
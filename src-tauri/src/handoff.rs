@@ -0,0 +1,229 @@
+// Live Session Handoff
+// ====================
+//
+// Moves a single conversation to another machine without a full sync. The
+// handoff file holds the conversation (including its pinned system prompt
+// and model) plus the MCP servers it relies on, and is signed with
+// HMAC-SHA256 keyed by a passphrase the user enters on both machines, so a
+// tampered or mistyped file is rejected on import. Secret values (env vars,
+// HTTP headers) are blanked before writing; only their names travel.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use crate::backup::next_version_id;
+use crate::conversations::{self, Conversation};
+use crate::hash::{canonical_json, hmac_sha256_hex, hmac_sha256_verify};
+use crate::mcp::McpDockerConfig;
+
+const HANDOFF_FORMAT: &str = "nexus-live-session";
+/// 2: `exported_at` in milliseconds rather than seconds
+const HANDOFF_VERSION: u32 = 2;
+
+/// Largest handoff file we are willing to parse
+const MAX_HANDOFF_BYTES: u64 = 64 * 1024 * 1024;
+
+/// An MCP server the session needs. Mirrors `MCPServerConfig` in
+/// src/types/mcp.ts; values of `env` and `headers` are empty in the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionServer {
+    pub id: String,
+    pub name: String,
+    pub transport: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker: Option<McpDockerConfig>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub isolate_env: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_timeout_ms: Option<u64>,
+}
+
+impl SessionServer {
+    /// Drops secret values, keeping the variable and header names so the
+    /// importing machine knows what to ask for.
+    fn redacted(mut self) -> Self {
+        self.env.values_mut().for_each(String::clear);
+        self.headers.values_mut().for_each(String::clear);
+        if let Some(docker) = self.docker.as_mut() {
+            docker.env.values_mut().for_each(String::clear);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveSession {
+    /// UNIX timestamp (milliseconds) of the export; seconds in version 1
+    /// files, which are signed as they are and so read unchanged
+    exported_at: u64,
+    conversation: Conversation,
+    servers: Vec<SessionServer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HandoffFile {
+    format: String,
+    version: u32,
+    session: LiveSession,
+    /// Hex HMAC-SHA256 of the canonical JSON of `session`
+    signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSessionImport {
+    pub conversation_id: String,
+    pub title: String,
+    /// The id was already in use locally with different content
+    pub version_bumped: bool,
+    /// Servers the session needs; the frontend offers to install missing ones
+    pub servers: Vec<SessionServer>,
+}
+
+fn sign(session: &LiveSession, passphrase: &str) -> Result<String, String> {
+    let value =
+        serde_json::to_value(session).map_err(|e| format!("Failed to serialize session: {e}"))?;
+    Ok(hmac_sha256_hex(
+        passphrase.as_bytes(),
+        canonical_json(&value).as_bytes(),
+    ))
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.trim().is_empty() {
+        return Err("A passphrase is required to sign the session file".to_string());
+    }
+    Ok(())
+}
+
+/// Writes a signed handoff file for one conversation.
+#[tauri::command]
+pub async fn export_live_session(
+    app: AppHandle,
+    id: String,
+    servers: Vec<SessionServer>,
+    passphrase: String,
+    path: String,
+) -> Result<String, String> {
+    crate::validate_filename(&id)?;
+    validate_passphrase(&passphrase)?;
+    log::info!("Exporting live session {id} to {path}");
+
+    let conversation = conversations::load_all(&app)?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| format!("Conversation not found: {id}"))?;
+
+    let session = LiveSession {
+        exported_at: crate::time::now_ms(),
+        conversation,
+        servers: servers.into_iter().map(SessionServer::redacted).collect(),
+    };
+
+    let file = HandoffFile {
+        format: HANDOFF_FORMAT.to_string(),
+        version: HANDOFF_VERSION,
+        signature: sign(&session, &passphrase)?,
+        session,
+    };
+
+    let json = serde_json::to_vec_pretty(&file).map_err(|e| {
+        log::error!("Failed to serialize session file: {e}");
+        format!("Failed to serialize session file: {e}")
+    })?;
+    crate::export::write_atomic(Path::new(&path), &json)?;
+
+    Ok(path)
+}
+
+/// Verifies a handoff file and adds its conversation locally. Missing MCP
+/// servers are left to the frontend, which owns server configuration.
+#[tauri::command]
+pub async fn import_live_session(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<LiveSessionImport, String> {
+    validate_passphrase(&passphrase)?;
+    log::info!("Importing live session from {path}");
+
+    let metadata =
+        std::fs::metadata(&path).map_err(|e| format!("Failed to read session file: {e}"))?;
+    if metadata.len() > MAX_HANDOFF_BYTES {
+        return Err("Session file too large (max 64MB)".to_string());
+    }
+
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read session file: {e}"))?;
+    let file: HandoffFile = serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse session file: {e}");
+        format!("Not a Nexus session file: {e}")
+    })?;
+
+    if file.format != HANDOFF_FORMAT {
+        return Err("Not a Nexus session file".to_string());
+    }
+    if file.version > HANDOFF_VERSION {
+        return Err(format!(
+            "Session file version {} is newer than this app supports",
+            file.version
+        ));
+    }
+
+    let value = serde_json::to_value(&file.session)
+        .map_err(|e| format!("Failed to serialize session: {e}"))?;
+    if !hmac_sha256_verify(
+        passphrase.as_bytes(),
+        canonical_json(&value).as_bytes(),
+        &file.signature,
+    ) {
+        log::warn!("Rejected session file {path}: signature mismatch");
+        return Err(
+            "Signature check failed: the passphrase is wrong or the file was modified".to_string(),
+        );
+    }
+
+    let mut conversation = file.session.conversation;
+    crate::validate_filename(&conversation.id)?;
+
+    let existing = conversations::load_all(&app)?;
+    let taken: HashSet<String> = existing.iter().map(|c| c.id.clone()).collect();
+    let (version_bumped, already_present) = match existing.iter().find(|c| c.id == conversation.id)
+    {
+        // Already handed off once and unchanged since
+        Some(local) if local.updated_at == conversation.updated_at => (false, true),
+        Some(_) => {
+            conversation.id = next_version_id(&conversation.id, &taken);
+            (true, false)
+        }
+        None => (false, false),
+    };
+
+    if !already_present {
+        conversations::save(&app, &conversation)?;
+        if let Err(e) = app.emit("conversations-restored", vec![&conversation]) {
+            log::error!("Failed to emit conversations-restored event: {e}");
+        }
+    }
+
+    Ok(LiveSessionImport {
+        conversation_id: conversation.id,
+        title: conversation.title,
+        version_bumped,
+        servers: file.session.servers,
+    })
+}
@@ -0,0 +1,103 @@
+// Hashing Helpers
+// ===============
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Hex-encoded SHA-256 of a byte slice.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Hashes a JSON value with object keys sorted, so equal values always
+/// produce the same hash regardless of key order.
+pub fn canonical_json_hash(value: &Value) -> String {
+    sha256_hex(canonical_json(value).as_bytes())
+}
+
+/// Serializes a JSON value with object keys sorted.
+pub fn canonical_json(value: &Value) -> String {
+    canonicalize(value).to_string()
+}
+
+/// Hex-encoded HMAC-SHA256 of a byte slice.
+pub fn hmac_sha256_hex(key: &[u8], bytes: &[u8]) -> String {
+    // HMAC accepts keys of any length, so this cannot fail
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(bytes);
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Checks an HMAC-SHA256 signature without leaking timing information.
+pub fn hmac_sha256_verify(key: &[u8], bytes: &[u8], signature: &str) -> bool {
+    let expected = hmac_sha256_hex(key, bytes);
+    expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), canonicalize(&map[k])))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonical_json_sorts_keys_at_every_level() {
+        let value = json!({ "b": 1, "a": { "d": [3, { "f": 1, "e": 2 }], "c": null } });
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"c":null,"d":[3,{"e":2,"f":1}]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn canonical_hash_ignores_key_order_but_not_array_order() {
+        let a = json!({ "x": 1, "y": [1, 2] });
+        let b: Value = serde_json::from_str(r#"{"y":[1,2],"x":1}"#).unwrap();
+        assert_eq!(canonical_json_hash(&a), canonical_json_hash(&b));
+        assert_ne!(
+            canonical_json_hash(&a),
+            canonical_json_hash(&json!({ "x": 1, "y": [2, 1] }))
+        );
+    }
+
+    #[test]
+    fn sha256_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231_and_verifies() {
+        let key = b"Jefe";
+        let data = b"what do ya want for nothing?";
+        let signature = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        assert_eq!(hmac_sha256_hex(key, data), signature);
+        assert!(hmac_sha256_verify(key, data, signature));
+        assert!(!hmac_sha256_verify(key, data, &signature.replace('5', "6")));
+        assert!(!hmac_sha256_verify(key, data, &signature[..63]));
+        assert!(!hmac_sha256_verify(b"other", data, signature));
+    }
+}
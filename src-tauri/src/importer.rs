@@ -0,0 +1,567 @@
+// Conversation Import
+// ===================
+//
+// Brings history over from other assistants. `import_conversations` reads
+// the `conversations.json` of a ChatGPT or Claude data export, either on
+// its own or inside the export's zip archive, maps every conversation into
+// the conversation store and records where it came from in
+// `conversation_imports`. A conversation whose messages hash the same as one
+// imported before is skipped, so importing a newer export of the same
+// account only adds what is new. Imports run as operations (see
+// `operations`), reporting progress per conversation; a cancelled import
+// keeps the conversations stored so far.
+//
+// Only the visible conversation is imported: the branch the user ended on,
+// user and assistant messages, text only. Images in exports are referenced
+// by file name rather than included, so they become a placeholder.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::conversations::{ChatMessage, Conversation};
+use crate::hash::sha256_hex;
+use crate::operations::{self, Operation, OperationKind, ProgressUnit};
+
+const CONVERSATIONS_FILE: &str = "conversations.json";
+const MAX_EXPORT_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+const MAX_TITLE_CHARS: usize = 500;
+const IMAGE_PLACEHOLDER: &str = "[Image not included in the export]";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// OpenAI's ChatGPT data export
+    Chatgpt,
+    /// Anthropic's Claude data export
+    Claude,
+}
+
+impl ImportSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            ImportSource::Chatgpt => "chatgpt",
+            ImportSource::Claude => "claude",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFailure {
+    /// Id of the conversation in the export, if it had one
+    pub source_id: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    /// Ids of the new conversations in the store
+    pub imported: Vec<String>,
+    /// Conversations imported before
+    pub duplicates: usize,
+    /// Conversations without any user or assistant text
+    pub empty: usize,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// Where an imported conversation came from.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProvenance {
+    pub conversation_id: String,
+    pub source: String,
+    pub source_id: Option<String>,
+    /// The export file it was read from
+    pub source_path: String,
+    pub content_hash: String,
+    /// Milliseconds since the UNIX epoch
+    pub imported_at: i64,
+}
+
+/// Reads the list of conversations from an export, or from the zip archive
+/// the export was downloaded as.
+fn read_export(path: &Path) -> Result<Vec<Value>, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to read export: {e}"))?;
+    if metadata.len() > MAX_EXPORT_BYTES {
+        return Err("Export is too large".to_string());
+    }
+    let file = File::open(path).map_err(|e| format!("Failed to open export: {e}"))?;
+
+    let is_zip = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    let export: Value = if is_zip {
+        let mut archive = zip::ZipArchive::new(BufReader::new(file))
+            .map_err(|e| format!("Failed to open archive: {e}"))?;
+        let name = archive
+            .file_names()
+            .filter(|name| {
+                *name == CONVERSATIONS_FILE || name.ends_with(&format!("/{CONVERSATIONS_FILE}"))
+            })
+            .min_by_key(|name| name.len())
+            .map(str::to_string)
+            .ok_or_else(|| format!("Archive does not contain {CONVERSATIONS_FILE}"))?;
+        let entry = archive
+            .by_name(&name)
+            .map_err(|e| format!("Failed to read {name}: {e}"))?;
+        serde_json::from_reader(BufReader::new(entry))
+    } else {
+        serde_json::from_reader(BufReader::new(file))
+    }
+    .map_err(|e| format!("Failed to parse export: {e}"))?;
+
+    match export {
+        Value::Array(items) => Ok(items),
+        _ => Err("Export does not contain a list of conversations".to_string()),
+    }
+}
+
+fn seconds_to_ms(value: Option<&Value>) -> Option<u64> {
+    value
+        .and_then(Value::as_f64)
+        .filter(|seconds| *seconds > 0.0)
+        .map(|seconds| (seconds * 1000.0) as u64)
+}
+
+fn rfc3339_to_ms(value: Option<&Value>) -> Option<u64> {
+    value
+        .and_then(Value::as_str)
+        .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+        .map(|time| time.timestamp_millis().max(0) as u64)
+}
+
+fn title(value: Option<&Value>) -> String {
+    let title = value
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .unwrap_or("Imported conversation");
+    title.chars().take(MAX_TITLE_CHARS).collect()
+}
+
+/// A message id from the export, or a new one if it is unusable.
+fn message_id(value: Option<&Value>) -> Result<String, String> {
+    match value.and_then(Value::as_str) {
+        Some(id) if !id.is_empty() && id.len() <= 256 => Ok(id.to_string()),
+        _ => crate::storage::new_id(),
+    }
+}
+
+/// Text of a ChatGPT message, or `None` for content that is not part of
+/// the visible conversation (reasoning, hidden context).
+fn chatgpt_text(message: &Value) -> Option<String> {
+    let content = message.get("content")?;
+    let text = match content.get("content_type").and_then(Value::as_str)? {
+        "text" | "multimodal_text" => content
+            .get("parts")?
+            .as_array()?
+            .iter()
+            .map(|part| match part {
+                Value::String(text) => text.clone(),
+                Value::Object(object)
+                    if object.get("content_type").and_then(Value::as_str)
+                        == Some("image_asset_pointer") =>
+                {
+                    IMAGE_PLACEHOLDER.to_string()
+                }
+                _ => String::new(),
+            })
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        "code" => {
+            let language = content
+                .get("language")
+                .and_then(Value::as_str)
+                .filter(|language| *language != "unknown")
+                .unwrap_or_default();
+            let code = content.get("text").and_then(Value::as_str)?;
+            format!("```{language}\n{code}\n```")
+        }
+        _ => return None,
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// The nodes of a ChatGPT conversation on the branch the user ended on,
+/// from the root. Exports keep every edit and regeneration as a tree.
+fn chatgpt_branch<'a>(
+    item: &'a Value,
+    mapping: &'a serde_json::Map<String, Value>,
+) -> Vec<&'a Value> {
+    let mut branch = Vec::new();
+    match item.get("current_node").and_then(Value::as_str) {
+        Some(current) => {
+            let mut next = Some(current);
+            while let Some(node) = next.and_then(|id| mapping.get(id)) {
+                // Guard against cycles in a damaged export
+                if branch.len() > mapping.len() {
+                    break;
+                }
+                branch.push(node);
+                next = node.get("parent").and_then(Value::as_str);
+            }
+            branch.reverse();
+        }
+        None => {
+            // Without a current node, follow the latest child from the root
+            let mut next = mapping
+                .values()
+                .find(|node| node.get("parent").is_none_or(Value::is_null));
+            while let Some(node) = next {
+                if branch.len() > mapping.len() {
+                    break;
+                }
+                branch.push(node);
+                next = node
+                    .get("children")
+                    .and_then(Value::as_array)
+                    .and_then(|children| children.last())
+                    .and_then(Value::as_str)
+                    .and_then(|id| mapping.get(id));
+            }
+        }
+    }
+    branch
+}
+
+fn chatgpt_conversation(item: &Value) -> Result<Conversation, String> {
+    let mapping = item
+        .get("mapping")
+        .and_then(Value::as_object)
+        .ok_or_else(|| "Conversation has no messages".to_string())?;
+    let created_at = seconds_to_ms(item.get("create_time")).unwrap_or(0);
+
+    let mut messages = Vec::new();
+    let mut model_id = item
+        .get("default_model_slug")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    for node in chatgpt_branch(item, mapping) {
+        let Some(message) = node.get("message").filter(|m| m.is_object()) else {
+            continue;
+        };
+        let role = match message.pointer("/author/role").and_then(Value::as_str) {
+            Some(role @ ("user" | "assistant")) => role,
+            _ => continue,
+        };
+        if message
+            .pointer("/metadata/is_visually_hidden_from_conversation")
+            .and_then(Value::as_bool)
+            == Some(true)
+        {
+            continue;
+        }
+        let Some(text) = chatgpt_text(message) else {
+            continue;
+        };
+        if role == "assistant" {
+            if let Some(slug) = message
+                .pointer("/metadata/model_slug")
+                .and_then(Value::as_str)
+            {
+                model_id = Some(slug.to_string());
+            }
+        }
+
+        messages.push(ChatMessage {
+            id: message_id(message.get("id").or_else(|| node.get("id")))?,
+            role: role.to_string(),
+            content: Value::String(text),
+            timestamp: seconds_to_ms(message.get("create_time")).unwrap_or(created_at),
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            usage: None,
+        });
+    }
+
+    let updated_at = seconds_to_ms(item.get("update_time"))
+        .or_else(|| messages.last().map(|m| m.timestamp))
+        .unwrap_or(created_at);
+    Ok(Conversation {
+        id: crate::storage::new_id()?,
+        title: title(item.get("title")),
+        messages,
+        model_id: model_id.unwrap_or_else(|| "chatgpt".to_string()),
+        system_prompt: String::new(),
+        created_at,
+        updated_at,
+    })
+}
+
+/// Text of a Claude message with the extracted text of its attachments.
+fn claude_text(message: &Value) -> String {
+    let mut sections: Vec<String> = message
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|parts| {
+            parts
+                .iter()
+                .filter(|p| p.get("type").and_then(Value::as_str) == Some("text"))
+                .filter_map(|p| p.get("text").and_then(Value::as_str))
+                .filter(|text| !text.trim().is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if sections.is_empty() {
+        if let Some(text) = message.get("text").and_then(Value::as_str) {
+            sections.push(text.to_string());
+        }
+    }
+
+    for attachment in message
+        .get("attachments")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let name = attachment
+            .get("file_name")
+            .and_then(Value::as_str)
+            .unwrap_or("attachment");
+        match attachment.get("extracted_content").and_then(Value::as_str) {
+            Some(content) if !content.trim().is_empty() => {
+                sections.push(format!("[Attachment: {name}]\n\n{content}"));
+            }
+            _ => sections.push(format!("[Attachment: {name}]")),
+        }
+    }
+    for file in message
+        .get("files")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        // Uploaded files are listed by name only
+        if let Some(name) = file.get("file_name").and_then(Value::as_str) {
+            sections.push(format!("[File not included in the export: {name}]"));
+        }
+    }
+
+    sections.join("\n\n")
+}
+
+fn claude_conversation(item: &Value) -> Result<Conversation, String> {
+    let chat_messages = item
+        .get("chat_messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "Conversation has no messages".to_string())?;
+    let created_at = rfc3339_to_ms(item.get("created_at")).unwrap_or(0);
+
+    let mut messages = Vec::new();
+    for message in chat_messages {
+        let role = match message.get("sender").and_then(Value::as_str) {
+            Some("human") => "user",
+            Some("assistant") => "assistant",
+            _ => continue,
+        };
+        let text = claude_text(message);
+        if text.trim().is_empty() {
+            continue;
+        }
+        messages.push(ChatMessage {
+            id: message_id(message.get("uuid"))?,
+            role: role.to_string(),
+            content: Value::String(text),
+            timestamp: rfc3339_to_ms(message.get("created_at")).unwrap_or(created_at),
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            usage: None,
+        });
+    }
+
+    let updated_at = rfc3339_to_ms(item.get("updated_at"))
+        .or_else(|| messages.last().map(|m| m.timestamp))
+        .unwrap_or(created_at);
+    Ok(Conversation {
+        id: crate::storage::new_id()?,
+        title: title(item.get("name")),
+        messages,
+        model_id: item
+            .get("model")
+            .and_then(Value::as_str)
+            .unwrap_or("claude")
+            .to_string(),
+        system_prompt: String::new(),
+        created_at,
+        updated_at,
+    })
+}
+
+/// Hash of the roles and text of a conversation's messages, which stays the
+/// same across exports of the same conversation.
+fn content_hash(conversation: &Conversation) -> String {
+    let content: Vec<(&str, String)> = conversation
+        .messages
+        .iter()
+        .map(|m| (m.role.as_str(), m.text()))
+        .collect();
+    sha256_hex(&serde_json::to_vec(&content).unwrap_or_default())
+}
+
+/// Stores an imported conversation with its provenance. Returns `false` if
+/// a conversation with the same content was imported before.
+fn store_import(
+    conn: &mut Connection,
+    conversation: &Conversation,
+    source: ImportSource,
+    source_id: Option<&str>,
+    source_path: &str,
+) -> rusqlite::Result<bool> {
+    let hash = content_hash(conversation);
+    let tx = conn.transaction()?;
+    let duplicate: Option<i64> = tx
+        .query_row(
+            "SELECT 1 FROM conversation_imports WHERE content_hash = ?1",
+            params![hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if duplicate.is_some() {
+        return Ok(false);
+    }
+
+    crate::storage::insert(&tx, conversation)?;
+    tx.execute(
+        "INSERT INTO conversation_imports
+             (conversation_id, source, source_id, source_path, content_hash, imported_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            conversation.id,
+            source.as_str(),
+            source_id,
+            source_path,
+            hash,
+            crate::time::now_ms() as i64
+        ],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+
+fn import(
+    app: &AppHandle,
+    path: &Path,
+    source: ImportSource,
+    operation: &mut Operation,
+) -> Result<ImportReport, String> {
+    let items = read_export(path)?;
+    let total = items.len();
+    let source_path = path.to_string_lossy();
+    log::info!(
+        "Importing {total} {} conversations from {source_path}",
+        source.as_str()
+    );
+
+    let mut conn = crate::db::open(app)?;
+    let mut report = ImportReport::default();
+    for (index, item) in items.iter().enumerate() {
+        operation.check_cancelled()?;
+        let source_id = item
+            .get("uuid")
+            .or_else(|| item.get("conversation_id"))
+            .or_else(|| item.get("id"))
+            .and_then(Value::as_str);
+        let conversation = match source {
+            ImportSource::Chatgpt => chatgpt_conversation(item),
+            ImportSource::Claude => claude_conversation(item),
+        };
+
+        match conversation {
+            Ok(conversation) if conversation.messages.is_empty() => report.empty += 1,
+            Ok(conversation) => {
+                match store_import(&mut conn, &conversation, source, source_id, &source_path) {
+                    Ok(true) => report.imported.push(conversation.id),
+                    Ok(false) => report.duplicates += 1,
+                    Err(e) => {
+                        log::error!("Failed to store imported conversation: {e}");
+                        report.failed.push(ImportFailure {
+                            source_id: source_id.map(str::to_string),
+                            error: format!("Failed to store conversation: {e}"),
+                        });
+                    }
+                }
+            }
+            Err(error) => report.failed.push(ImportFailure {
+                source_id: source_id.map(str::to_string),
+                error,
+            }),
+        }
+
+        operation.progress(
+            index as u64 + 1,
+            Some(total as u64),
+            Some(format!(
+                "{} imported, {} duplicates",
+                report.imported.len(),
+                report.duplicates
+            )),
+        );
+    }
+
+    log::info!(
+        "Import finished: {} imported, {} duplicates, {} empty, {} failed",
+        report.imported.len(),
+        report.duplicates,
+        report.empty,
+        report.failed.len()
+    );
+    Ok(report)
+}
+
+/// Imports the conversations of a ChatGPT or Claude data export into the
+/// conversation store. `path` is the export's `conversations.json` or the
+/// zip archive containing it.
+#[tauri::command]
+pub async fn import_conversations(
+    app: AppHandle,
+    path: String,
+    source: ImportSource,
+) -> Result<ImportReport, String> {
+    let label = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+    let mut operation = operations::start(&app, OperationKind::Import, label, ProgressUnit::Items);
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = import(&app, Path::new(&path), source, &mut operation);
+        operation.finish(result)
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {e}"))?
+}
+
+/// Where a conversation was imported from, or `None` if it was not.
+#[tauri::command]
+pub async fn get_import_provenance(
+    app: AppHandle,
+    conversation_id: String,
+) -> Result<Option<ImportProvenance>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.query_row(
+        "SELECT conversation_id, source, source_id, source_path, content_hash, imported_at
+         FROM conversation_imports WHERE conversation_id = ?1",
+        params![conversation_id],
+        |row| {
+            Ok(ImportProvenance {
+                conversation_id: row.get(0)?,
+                source: row.get(1)?,
+                source_id: row.get(2)?,
+                source_path: row.get(3)?,
+                content_hash: row.get(4)?,
+                imported_at: row.get(5)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read import provenance: {e}"))
+}
@@ -0,0 +1,203 @@
+// Dock and Taskbar Indicators
+// ===========================
+//
+// The badge and progress bar on the app's dock icon (macOS), taskbar
+// button (Windows) or launcher entry (Linux, where the desktop supports
+// libunity), so what the app is doing shows while its window is hidden
+// or behind others.
+//
+// The badge counts what the frontend reports with `set_badge_count`, e.g.
+// unread replies, plus notifications shown while the main window was not
+// focused; focusing it clears those. Windows has no numbered badges, so
+// the taskbar button gets a red dot instead.
+//
+// The progress bar follows the running operations (see `operations`):
+// their average progress, or an indeterminate bar while none of them
+// knows its total. `set_progress` overrides it until cleared with `None`.
+
+use std::sync::Mutex;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+use crate::operations::{OperationStatus, Operations};
+
+const MAIN_WINDOW: &str = "main";
+
+#[derive(Default)]
+struct Badge {
+    /// Set by the frontend
+    count: u32,
+    /// Notifications shown while the main window was not focused
+    unseen_notifications: u32,
+}
+
+#[derive(Default)]
+pub struct Indicators {
+    badge: Mutex<Badge>,
+    /// Progress set by the frontend, overriding the operations'
+    progress: Mutex<Option<f64>>,
+    /// What the progress bar shows now, to skip redundant updates
+    shown_progress: Mutex<Option<Option<u64>>>,
+}
+
+/// A red dot for the taskbar button's overlay icon.
+#[cfg(windows)]
+fn badge_dot() -> tauri::image::Image<'static> {
+    const SIZE: u32 = 16;
+    let radius = SIZE as f32 / 2.0 - 0.5;
+    let mut rgba = vec![0u8; (SIZE * SIZE * 4) as usize];
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (dx, dy) = (
+                x as f32 + 0.5 - SIZE as f32 / 2.0,
+                y as f32 + 0.5 - SIZE as f32 / 2.0,
+            );
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * SIZE + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&[0xe5, 0x3e, 0x3e, 0xff]);
+            }
+        }
+    }
+    tauri::image::Image::new_owned(rgba, SIZE, SIZE)
+}
+
+fn refresh_badge(app: &AppHandle) {
+    let count = match app.state::<Indicators>().badge.lock() {
+        Ok(badge) => badge.count.saturating_add(badge.unseen_notifications),
+        Err(_) => return,
+    };
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    #[cfg(windows)]
+    let result = window.set_overlay_icon((count > 0).then(badge_dot));
+    #[cfg(not(windows))]
+    let result = window.set_badge_count((count > 0).then_some(count as i64));
+    if let Err(e) = result {
+        log::warn!("Failed to update badge: {e}");
+    }
+}
+
+/// The running operations' average progress in percent, `Some(None)` while
+/// none knows its total, or `None` when nothing is running.
+fn operations_progress(app: &AppHandle) -> Option<Option<u64>> {
+    let running: Vec<_> = app
+        .state::<Operations>()
+        .list()
+        .into_iter()
+        .filter(|info| info.status == OperationStatus::Running)
+        .collect();
+    if running.is_empty() {
+        return None;
+    }
+    let fractions: Vec<f64> = running
+        .iter()
+        .filter_map(|info| {
+            let total = info.progress.total.filter(|total| *total > 0)?;
+            Some((info.progress.completed as f64 / total as f64).min(1.0))
+        })
+        .collect();
+    if fractions.is_empty() {
+        return Some(None);
+    }
+    let average = fractions.iter().sum::<f64>() / fractions.len() as f64;
+    Some(Some((average * 100.0).round() as u64))
+}
+
+/// Brings the progress bar up to date. Called whenever an operation
+/// starts, progresses or finishes.
+pub fn refresh_progress(app: &AppHandle) {
+    let state = app.state::<Indicators>();
+    let explicit = state.progress.lock().ok().and_then(|progress| *progress);
+    // `None`: no bar; `Some(None)`: indeterminate
+    let progress = match explicit {
+        Some(fraction) => Some(Some((fraction * 100.0).round() as u64)),
+        None => operations_progress(app),
+    };
+    let Ok(mut shown) = state.shown_progress.lock() else {
+        return;
+    };
+    if *shown == progress {
+        return;
+    }
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    let bar = match progress {
+        None => ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        },
+        Some(None) => ProgressBarState {
+            status: Some(ProgressBarStatus::Indeterminate),
+            progress: None,
+        },
+        Some(Some(percent)) => ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some(percent),
+        },
+    };
+    match window.set_progress_bar(bar) {
+        Ok(()) => *shown = progress,
+        Err(e) => log::warn!("Failed to update progress bar: {e}"),
+    }
+}
+
+/// Counts a notification towards the badge unless the main window is
+/// focused, where the user sees what it is about.
+pub fn notification_shown(app: &AppHandle) {
+    let focused = app
+        .get_webview_window(MAIN_WINDOW)
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return;
+    }
+    if let Ok(mut badge) = app.state::<Indicators>().badge.lock() {
+        badge.unseen_notifications = badge.unseen_notifications.saturating_add(1);
+    }
+    refresh_badge(app);
+}
+
+/// Clears the notifications counted in the badge, once the main window is
+/// focused.
+pub fn main_window_focused(app: &AppHandle) {
+    let cleared = app
+        .state::<Indicators>()
+        .badge
+        .lock()
+        .map(|mut badge| std::mem::take(&mut badge.unseen_notifications) > 0)
+        .unwrap_or(false);
+    if cleared {
+        refresh_badge(app);
+    }
+}
+
+/// Sets the frontend's part of the badge; `0` removes it.
+#[tauri::command]
+pub async fn set_badge_count(app: AppHandle, count: u32) -> Result<(), String> {
+    app.state::<Indicators>()
+        .badge
+        .lock()
+        .map_err(|e| format!("Failed to lock badge: {e}"))?
+        .count = count;
+    refresh_badge(&app);
+    Ok(())
+}
+
+/// Shows `fraction` (0 to 1) on the progress bar, or with `None` hands it
+/// back to the running operations.
+#[tauri::command]
+pub async fn set_progress(app: AppHandle, fraction: Option<f64>) -> Result<(), String> {
+    if let Some(fraction) = fraction {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err("Progress must be between 0 and 1".to_string());
+        }
+    }
+    *app.state::<Indicators>()
+        .progress
+        .lock()
+        .map_err(|e| format!("Failed to lock progress: {e}"))? = fraction;
+    refresh_progress(&app);
+    Ok(())
+}
@@ -0,0 +1,666 @@
+// Operations Inspectors
+// =====================
+//
+// Opt-in built-in tools that let the model look at a Kubernetes cluster or
+// a server without a general-purpose shell. Nothing is run through a
+// shell: `kubectl` and `ssh` are started directly with an argument list
+// built here from a fixed set of read-only operations, and every value the
+// model supplies (resource names, namespaces, service units) is checked
+// against a strict character set first. Contexts, namespaces and hosts
+// must be allowlisted by the user in `inspectors.json`.
+//
+// Each run has a timeout and an output cap, and is written to the MCP tool
+// audit log under `builtin:kubectl` or `builtin:ssh`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command as StdCommand, Stdio};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::audit::{McpAuditLog, McpAuditOutcome};
+
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_OUTPUT_BYTES: usize = 256 * 1024;
+const DEFAULT_LOG_LINES: u32 = 200;
+const MAX_LOG_LINES: u32 = 2000;
+const MAX_SSH_HOSTS: usize = 50;
+/// Resource types `kubectl` may read. Secrets and config maps are left out
+/// because their contents are credentials more often than not.
+const KUBERNETES_RESOURCES: [&str; 19] = [
+    "pods",
+    "deployments",
+    "replicasets",
+    "statefulsets",
+    "daemonsets",
+    "jobs",
+    "cronjobs",
+    "services",
+    "endpoints",
+    "ingresses",
+    "nodes",
+    "namespaces",
+    "events",
+    "persistentvolumes",
+    "persistentvolumeclaims",
+    "storageclasses",
+    "horizontalpodautoscalers",
+    "poddisruptionbudgets",
+    "networkpolicies",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubernetesInspector {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `kubectl` on the login shell's PATH when not set
+    #[serde(default)]
+    pub kubectl_path: Option<String>,
+    /// Contexts that may be selected, one of which every run must name;
+    /// only the current context is used when empty
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    /// Namespaces that may be read; any when empty
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHost {
+    pub id: String,
+    pub host: String,
+    pub user: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Private key file; the agent and `~/.ssh/config` are used otherwise
+    #[serde(default)]
+    pub identity_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SshInspector {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub hosts: Vec<SshHost>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InspectorSettings {
+    #[serde(default)]
+    pub kubernetes: KubernetesInspector,
+    #[serde(default)]
+    pub ssh: SshInspector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KubectlVerb {
+    Get,
+    Describe,
+    Logs,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KubectlRequest {
+    pub verb: KubectlVerb,
+    /// Resource type; `logs` always reads a pod
+    #[serde(default)]
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Label selector such as `app=web,tier!=cache`
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Container to read logs from
+    #[serde(default)]
+    pub container: Option<String>,
+    /// Log lines from the end; 200 by default
+    #[serde(default)]
+    pub tail: Option<u32>,
+}
+
+/// Read-only commands that can be run on a host. The remote command line
+/// is built from these, never taken from the caller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum SshCommand {
+    Uptime,
+    Kernel,
+    DiskUsage,
+    Memory,
+    Processes,
+    ListeningPorts,
+    ServiceStatus {
+        unit: String,
+    },
+    ServiceLogs {
+        unit: String,
+        #[serde(default)]
+        lines: Option<u32>,
+    },
+}
+
+impl SshCommand {
+    fn command_line(&self) -> Result<String, String> {
+        Ok(match self {
+            SshCommand::Uptime => "uptime".to_string(),
+            SshCommand::Kernel => "uname -a".to_string(),
+            SshCommand::DiskUsage => "df -h".to_string(),
+            SshCommand::Memory => "free -m".to_string(),
+            SshCommand::Processes => "ps aux --sort=-%cpu | head -n 50".to_string(),
+            SshCommand::ListeningPorts => "ss -tuln".to_string(),
+            SshCommand::ServiceStatus { unit } => {
+                validate_unit(unit)?;
+                format!("systemctl status --no-pager --lines=0 -- '{unit}'")
+            }
+            SshCommand::ServiceLogs { unit, lines } => {
+                validate_unit(unit)?;
+                let lines = lines.unwrap_or(DEFAULT_LOG_LINES).clamp(1, MAX_LOG_LINES);
+                format!("journalctl --no-pager -n {lines} -u '{unit}'")
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectorResult {
+    /// The command as run, for display
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output was cut at the size limit
+    pub truncated: bool,
+    /// The command exited with a non-zero status
+    pub is_error: bool,
+}
+
+fn get_inspectors_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("inspectors.json"))
+}
+
+fn load(app: &AppHandle) -> Result<InspectorSettings, String> {
+    let path = get_inspectors_path(app)?;
+    if !path.exists() {
+        return Ok(InspectorSettings::default());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read inspector settings: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse inspector settings: {e}");
+        format!("Failed to parse inspector settings: {e}")
+    })
+}
+
+/// A Kubernetes object or namespace name (DNS subdomain).
+fn validate_name(value: &str, field: &str) -> Result<(), String> {
+    let valid = !value.is_empty()
+        && value.len() <= 253
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && value.starts_with(|c: char| c.is_ascii_alphanumeric());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid {field}: {value}"))
+    }
+}
+
+fn validate_selector(selector: &str) -> Result<(), String> {
+    let valid = !selector.is_empty()
+        && selector.len() <= 256
+        && selector.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '/' | '=' | '!' | ',')
+        })
+        && !selector.starts_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid label selector: {selector}"))
+    }
+}
+
+/// A systemd unit name, which is also safe inside single quotes.
+fn validate_unit(unit: &str) -> Result<(), String> {
+    let valid = !unit.is_empty()
+        && unit.len() <= 256
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '@' | ':'))
+        && !unit.starts_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid service unit: {unit}"))
+    }
+}
+
+fn validate_settings(settings: &InspectorSettings) -> Result<(), String> {
+    for context in &settings.kubernetes.contexts {
+        crate::validate_string_input(context, 256, "Context")?;
+        if context.starts_with('-') || context.trim().is_empty() {
+            return Err(format!("Invalid context: {context}"));
+        }
+    }
+    for namespace in &settings.kubernetes.namespaces {
+        validate_name(namespace, "namespace")?;
+    }
+
+    if settings.ssh.hosts.len() > MAX_SSH_HOSTS {
+        return Err(format!("Too many SSH hosts (max {MAX_SSH_HOSTS})"));
+    }
+    for host in &settings.ssh.hosts {
+        crate::validate_filename(&host.id)?;
+        for (value, field) in [(&host.host, "Host"), (&host.user, "User")] {
+            let valid = !value.is_empty()
+                && value.len() <= 253
+                && !value.starts_with('-')
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | ':'));
+            if !valid {
+                return Err(format!("Invalid {field}: {value}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads up to `MAX_OUTPUT_BYTES` of a pipe on its own thread, draining
+/// the rest so the process never blocks on a full pipe.
+fn read_capped<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<(Vec<u8>, bool)> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let mut truncated = false;
+        let Some(mut pipe) = pipe else {
+            return (output, truncated);
+        };
+        let mut buffer = [0u8; 8192];
+        while let Ok(read) = pipe.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let room = MAX_OUTPUT_BYTES.saturating_sub(output.len());
+            if read > room {
+                truncated = true;
+            }
+            output.extend_from_slice(&buffer[..read.min(room)]);
+        }
+        (output, truncated)
+    })
+}
+
+/// Runs a program with the login shell's PATH, killing it after
+/// `RUN_TIMEOUT`.
+fn run_process(program: &str, args: &[String]) -> Result<InspectorResult, String> {
+    let command = std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    log::info!("Running inspector command: {command}");
+
+    let shell = crate::mcp::login_shell();
+    let mut child = StdCommand::new(program)
+        .args(args)
+        .env("PATH", crate::mcp::login_shell_path(&shell))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {program}: {e}"))?;
+    let stdout = read_capped(child.stdout.take());
+    let stderr = read_capped(child.stderr.take());
+
+    let deadline = Instant::now() + RUN_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{program} did not finish within {} seconds",
+                    RUN_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for {program}: {e}")),
+        }
+    };
+
+    let (stdout, stdout_truncated) = stdout.join().unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.join().unwrap_or_default();
+    Ok(InspectorResult {
+        command,
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: stdout_truncated || stderr_truncated,
+        is_error: !status.success(),
+    })
+}
+
+/// The context a request runs in. With an allowlist, the request must name
+/// one of its contexts; without one, only the current context may be used,
+/// and `None` leaves it to the caller to look that up.
+fn requested_context(
+    settings: &KubernetesInspector,
+    request: &KubectlRequest,
+) -> Result<Option<String>, String> {
+    match &request.context {
+        Some(context) if settings.contexts.contains(context) => Ok(Some(context.clone())),
+        Some(context) => Err(format!("Context {context} is not allowed")),
+        None if !settings.contexts.is_empty() => Err(format!(
+            "A context is required: one of {}",
+            settings.contexts.join(", ")
+        )),
+        None => Ok(None),
+    }
+}
+
+/// The kubeconfig's current context, which a run is pinned to so a change
+/// of context while it starts can't point it at another cluster.
+fn current_context(program: &str) -> Result<String, String> {
+    let output = run_process(
+        program,
+        &["config".to_string(), "current-context".to_string()],
+    )?;
+    let context = output.stdout.trim();
+    if output.is_error || context.is_empty() {
+        return Err(format!(
+            "Failed to read the current kubectl context: {}",
+            output.stderr.trim()
+        ));
+    }
+    Ok(context.to_string())
+}
+
+fn kubectl_args(
+    settings: &KubernetesInspector,
+    request: &KubectlRequest,
+    context: &str,
+) -> Result<Vec<String>, String> {
+    let mut args = vec![format!("--context={context}")];
+
+    if let Some(namespace) = &request.namespace {
+        validate_name(namespace, "namespace")?;
+        if !settings.namespaces.is_empty() && !settings.namespaces.contains(namespace) {
+            return Err(format!("Namespace {namespace} is not allowed"));
+        }
+        args.push(format!("--namespace={namespace}"));
+    } else if !settings.namespaces.is_empty() {
+        return Err("A namespace is required".to_string());
+    }
+    args.push(format!("--request-timeout={}s", RUN_TIMEOUT.as_secs() - 5));
+
+    match request.verb {
+        KubectlVerb::Get | KubectlVerb::Describe => {
+            let resource = request
+                .resource
+                .as_deref()
+                .ok_or_else(|| "A resource type is required".to_string())?;
+            if !KUBERNETES_RESOURCES.contains(&resource) {
+                return Err(format!("Resource type {resource} is not allowed"));
+            }
+            let verb = if request.verb == KubectlVerb::Get {
+                "get"
+            } else {
+                "describe"
+            };
+            args.extend([verb.to_string(), resource.to_string()]);
+            if let Some(name) = &request.name {
+                validate_name(name, "name")?;
+                args.push(name.clone());
+            }
+            if let Some(selector) = &request.selector {
+                validate_selector(selector)?;
+                args.push(format!("--selector={selector}"));
+            }
+            if request.verb == KubectlVerb::Get {
+                args.push("--output=wide".to_string());
+            }
+        }
+        KubectlVerb::Logs => {
+            let name = request
+                .name
+                .as_deref()
+                .ok_or_else(|| "A pod name is required".to_string())?;
+            validate_name(name, "pod name")?;
+            let tail = request
+                .tail
+                .unwrap_or(DEFAULT_LOG_LINES)
+                .clamp(1, MAX_LOG_LINES);
+            args.extend(["logs".to_string(), name.to_string()]);
+            args.push(format!("--tail={tail}"));
+            if let Some(container) = &request.container {
+                validate_name(container, "container")?;
+                args.push(format!("--container={container}"));
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn ssh_args(host: &SshHost, command: &SshCommand) -> Result<Vec<String>, String> {
+    let mut args = vec![
+        // Never prompt: keys come from the agent or the identity file, and
+        // unknown host keys are refused rather than trusted
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+        "-T".to_string(),
+        "-p".to_string(),
+        host.port.unwrap_or(22).to_string(),
+    ];
+    if let Some(identity) = &host.identity_file {
+        args.extend(["-i".to_string(), identity.clone()]);
+    }
+    args.extend([
+        "-l".to_string(),
+        host.user.clone(),
+        host.host.clone(),
+        "--".to_string(),
+        command.command_line()?,
+    ]);
+    Ok(args)
+}
+
+/// Runs an inspector command off the async runtime and records it in the
+/// audit log.
+async fn run_audited(
+    app: &AppHandle,
+    source: &'static str,
+    tool: &str,
+    arguments: Value,
+    program: String,
+    args: Vec<String>,
+) -> Result<InspectorResult, String> {
+    let started = Instant::now();
+    let result = tauri::async_runtime::spawn_blocking(move || run_process(&program, &args))
+        .await
+        .map_err(|e| format!("Inspector task failed: {e}"))?;
+
+    let (outcome, error) = match &result {
+        Ok(output) if output.is_error => (
+            McpAuditOutcome::ToolError,
+            Some(format!("Exited with {:?}", output.exit_code)),
+        ),
+        Ok(_) => (McpAuditOutcome::Success, None),
+        Err(e) => (McpAuditOutcome::ToolError, Some(e.clone())),
+    };
+    app.state::<McpAuditLog>()
+        .record_builtin(app, source, tool, &arguments, started, outcome, error);
+
+    result.map_err(|e| {
+        log::error!("Inspector command failed: {e}");
+        e
+    })
+}
+
+#[tauri::command]
+pub async fn get_inspector_settings(app: AppHandle) -> Result<InspectorSettings, String> {
+    load(&app)
+}
+
+#[tauri::command]
+pub async fn save_inspector_settings(
+    app: AppHandle,
+    settings: InspectorSettings,
+) -> Result<(), String> {
+    validate_settings(&settings)?;
+    let json = serde_json::to_vec_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize inspector settings: {e}"))?;
+    crate::export::write_atomic(&get_inspectors_path(&app)?, &json)?;
+    log::info!(
+        "Saved inspector settings (kubernetes: {}, ssh: {})",
+        settings.kubernetes.enabled,
+        settings.ssh.enabled
+    );
+    Ok(())
+}
+
+/// Runs a read-only `kubectl get`, `describe` or `logs`.
+#[tauri::command]
+pub async fn run_kubectl(
+    app: AppHandle,
+    request: KubectlRequest,
+) -> Result<InspectorResult, String> {
+    let settings = load(&app)?.kubernetes;
+    if !settings.enabled {
+        return Err("The Kubernetes inspector is not enabled".to_string());
+    }
+    let program = settings
+        .kubectl_path
+        .clone()
+        .unwrap_or_else(|| "kubectl".to_string());
+    let context = match requested_context(&settings, &request)? {
+        Some(context) => context,
+        None => {
+            let program = program.clone();
+            tauri::async_runtime::spawn_blocking(move || current_context(&program))
+                .await
+                .map_err(|e| format!("Inspector task failed: {e}"))??
+        }
+    };
+    let args = kubectl_args(&settings, &request, &context)?;
+    let arguments = serde_json::json!({ "args": args });
+
+    run_audited(
+        &app,
+        "builtin:kubectl",
+        "kubectl_inspect",
+        arguments,
+        program,
+        args,
+    )
+    .await
+}
+
+/// Runs one of the fixed read-only commands on an allowlisted host.
+#[tauri::command]
+pub async fn run_ssh_command(
+    app: AppHandle,
+    host_id: String,
+    command: SshCommand,
+) -> Result<InspectorResult, String> {
+    let settings = load(&app)?.ssh;
+    if !settings.enabled {
+        return Err("The SSH inspector is not enabled".to_string());
+    }
+    let host = settings
+        .hosts
+        .iter()
+        .find(|h| h.id == host_id)
+        .ok_or_else(|| format!("SSH host {host_id} is not allowed"))?;
+    let args = ssh_args(host, &command)?;
+    let arguments = serde_json::json!({ "host": host_id, "command": command });
+
+    run_audited(
+        &app,
+        "builtin:ssh",
+        "ssh_inspect",
+        arguments,
+        "ssh".to_string(),
+        args,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(value: Value) -> KubectlRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn settings(contexts: &[&str]) -> KubernetesInspector {
+        KubernetesInspector {
+            enabled: true,
+            contexts: contexts.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn an_allowlist_requires_one_of_its_contexts() {
+        let settings = settings(&["staging"]);
+        let staging = request(json!({"verb": "get", "resource": "pods", "context": "staging"}));
+        assert_eq!(
+            requested_context(&settings, &staging).unwrap().as_deref(),
+            Some("staging")
+        );
+        let prod = request(json!({"verb": "get", "resource": "pods", "context": "prod"}));
+        assert!(requested_context(&settings, &prod).is_err());
+        let unnamed = request(json!({"verb": "get", "resource": "pods"}));
+        assert!(requested_context(&settings, &unnamed).is_err());
+    }
+
+    #[test]
+    fn without_an_allowlist_only_the_current_context_is_used() {
+        let settings = settings(&[]);
+        let unnamed = request(json!({"verb": "get", "resource": "pods"}));
+        assert_eq!(requested_context(&settings, &unnamed).unwrap(), None);
+        let named = request(json!({"verb": "get", "resource": "pods", "context": "prod"}));
+        assert!(requested_context(&settings, &named).is_err());
+    }
+
+    #[test]
+    fn always_passes_the_context() {
+        let unnamed = request(json!({"verb": "logs", "name": "web-1"}));
+        let args = kubectl_args(&settings(&[]), &unnamed, "current").unwrap();
+        assert_eq!(args[0], "--context=current");
+        assert!(args.contains(&"logs".to_string()));
+    }
+
+    #[test]
+    fn refuses_secrets_and_flags_in_names() {
+        let secrets = request(json!({"verb": "get", "resource": "secrets"}));
+        assert!(kubectl_args(&settings(&[]), &secrets, "current").is_err());
+        let flag = request(json!({"verb": "logs", "name": "--all-containers"}));
+        assert!(kubectl_args(&settings(&[]), &flag, "current").is_err());
+    }
+}
@@ -0,0 +1,49 @@
+// Keychain
+// ========
+//
+// Secrets kept in the operating system's credential store (macOS Keychain,
+// Windows Credential Manager, Secret Service on Linux) instead of files in
+// the app data directory. Entries are identified by an account name under
+// the app's service name, prefixed with the profile for profiles other than
+// the default one.
+
+use keyring::{Entry, Error};
+
+const SERVICE: &str = "com.navjotdhanawat.nexus";
+
+fn entry(account: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, &crate::profiles::keychain_account(account)).map_err(|e| {
+        log::error!("Failed to open keychain entry {account}: {e}");
+        format!("Failed to access keychain: {e}")
+    })
+}
+
+/// Reads a secret, or `None` if there is none.
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(Error::NoEntry) => Ok(None),
+        Err(e) => {
+            log::error!("Failed to read keychain entry {account}: {e}");
+            Err(format!("Failed to read from keychain: {e}"))
+        }
+    }
+}
+
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    entry(account)?.set_password(secret).map_err(|e| {
+        log::error!("Failed to write keychain entry {account}: {e}");
+        format!("Failed to write to keychain: {e}")
+    })
+}
+
+/// Removes a secret; removing one that doesn't exist is not an error.
+pub fn delete(account: &str) -> Result<(), String> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(Error::NoEntry) => Ok(()),
+        Err(e) => {
+            log::error!("Failed to delete keychain entry {account}: {e}");
+            Err(format!("Failed to delete from keychain: {e}"))
+        }
+    }
+}
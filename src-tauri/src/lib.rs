@@ -1,14 +1,87 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
-use std::process::{Child, ChildStdin, Command as StdCommand, Stdio};
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::menu::{MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::menu::{
+    CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+mod agent;
+mod annotations;
+mod api_keys;
+mod archive;
+mod attachments;
+mod audio;
+mod audit;
+mod backup;
+mod benchmark;
+mod budget;
+mod bundle;
+mod calendar;
+mod chat_proxy;
+mod clipboard;
+mod command_metrics;
+mod comparisons;
+mod conversations;
+mod daemon;
+mod databases;
+mod db;
+mod db_backup;
+mod deep_link;
+mod destinations;
+mod diff;
+mod embedding_runtime;
+mod embeddings;
+mod export;
+mod feedback;
+mod file_drop;
+mod graphql;
+mod handoff;
+mod hash;
+mod importer;
+mod indicators;
+mod inspectors;
+mod keychain;
+mod llm;
+mod local_llm;
+mod local_models;
+mod mcp;
+mod middleware;
+mod model_compare;
+mod network;
+mod ollama;
+mod openapi;
+mod operations;
+mod persistence;
+mod platform;
+mod power;
+mod profiles;
+mod prompt_history;
+mod prompt_templates;
+mod providers;
+mod quick_actions;
+mod rate_limit;
+mod replay;
+mod response_cache;
+mod retry;
+mod scheduler;
+mod screenshot;
+mod search;
+mod second_factor;
+mod share;
+mod speech;
+mod storage;
+mod tasks;
+mod test_inspect;
+mod time;
+mod tokens;
+mod training;
+mod unfurl;
+mod usage;
+mod watchfolder;
+mod window_state;
 
 // Validation functions
 fn validate_filename(filename: &str) -> Result<(), String> {
@@ -64,8 +137,72 @@ fn greet(name: &str) -> String {
 // Preferences data structure
 // Only contains settings that should be persisted to disk
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AppPreferences {
     pub theme: String,
+    /// Domains (including their subdomains) that link previews may be fetched from
+    #[serde(default = "default_link_preview_domains")]
+    pub link_preview_domains: Vec<String>,
+    /// Periodic Markdown export of changed conversations to an external folder
+    #[serde(default)]
+    pub scheduled_export: export::ScheduledExportSettings,
+    /// Cap on simultaneously running MCP servers
+    #[serde(default)]
+    pub mcp_concurrency: mcp::McpConcurrencySettings,
+    /// TOTP / security key check before high-risk operations. Only
+    /// `second_factor::configure_second_factor` can change this.
+    #[serde(default)]
+    pub second_factor: second_factor::SecondFactorSettings,
+    /// Ordered middleware applied to backend model requests
+    #[serde(default)]
+    pub llm_middleware: Vec<middleware::MiddlewareConfig>,
+    /// Scheduled snapshots of the local database
+    #[serde(default)]
+    pub database_backup: db_backup::DatabaseBackupSettings,
+    /// Accelerator that shows the window from anywhere, e.g. `CmdOrCtrl+Shift+Space`
+    #[serde(default)]
+    pub global_shortcut: Option<String>,
+    /// Where conversations are stored. Only `persistence::migrate_storage`
+    /// can change this.
+    #[serde(default)]
+    pub storage_backend: persistence::StorageBackend,
+    /// Record provider responses, or answer backend model requests from
+    /// the recordings
+    #[serde(default)]
+    pub llm_replay: replay::ReplayMode,
+    /// Appending to an archived conversation unarchives it instead of
+    /// failing
+    #[serde(default)]
+    pub unarchive_on_reply: bool,
+    /// Retrying model requests that fail with a transient error
+    #[serde(default)]
+    pub llm_retry: retry::RetryPolicy,
+    /// Time zone and locale timestamps are shown in, e.g. in exports
+    #[serde(default)]
+    pub time_display: time::TimeDisplaySettings,
+    /// Per-host request rate limits for model APIs
+    #[serde(default)]
+    pub llm_rate_limits: rate_limit::RateLimitSettings,
+    /// Answering repeated chat completions from a local cache
+    #[serde(default)]
+    pub llm_cache: response_cache::ResponseCacheSettings,
+    /// Proxy and extra CA certificates for backend requests
+    #[serde(default)]
+    pub network: network::NetworkSettings,
+    /// Closing the main window hides it to the tray instead of quitting
+    #[serde(default)]
+    pub close_to_tray: bool,
+    /// Keeping what is copied for pasting into chats later
+    #[serde(default)]
+    pub clipboard_history: clipboard::ClipboardHistorySettings,
+    /// The main window pinned above others or in compact mode. Only
+    /// `window_state::set_window_pinned` and `set_compact_mode` can change
+    /// this.
+    #[serde(default)]
+    pub window_mode: window_state::WindowMode,
+    /// Pausing heavy background work on battery
+    #[serde(default)]
+    pub power: power::PowerSettings,
     // Add new persistent preferences here, e.g.:
     // pub auto_save: bool,
     // pub language: String,
@@ -75,16 +212,49 @@ impl Default for AppPreferences {
     fn default() -> Self {
         Self {
             theme: "system".to_string(),
+            link_preview_domains: default_link_preview_domains(),
+            scheduled_export: export::ScheduledExportSettings::default(),
+            mcp_concurrency: mcp::McpConcurrencySettings::default(),
+            second_factor: second_factor::SecondFactorSettings::default(),
+            llm_middleware: Vec::new(),
+            database_backup: db_backup::DatabaseBackupSettings::default(),
+            global_shortcut: None,
+            storage_backend: persistence::StorageBackend::default(),
+            llm_replay: replay::ReplayMode::default(),
+            unarchive_on_reply: false,
+            llm_retry: retry::RetryPolicy::default(),
+            time_display: time::TimeDisplaySettings::default(),
+            llm_rate_limits: rate_limit::RateLimitSettings::default(),
+            llm_cache: response_cache::ResponseCacheSettings::default(),
+            network: network::NetworkSettings::default(),
+            close_to_tray: false,
+            clipboard_history: clipboard::ClipboardHistorySettings::default(),
+            window_mode: window_state::WindowMode::default(),
+            power: power::PowerSettings::default(),
             // Add defaults for new preferences here
         }
     }
 }
 
+fn default_link_preview_domains() -> Vec<String> {
+    [
+        "github.com",
+        "gitlab.com",
+        "wikipedia.org",
+        "youtube.com",
+        "stackoverflow.com",
+        "developer.mozilla.org",
+        "docs.rs",
+        "crates.io",
+        "npmjs.com",
+    ]
+    .iter()
+    .map(|d| d.to_string())
+    .collect()
+}
+
 fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    let app_data_dir = crate::profiles::data_dir(app)?;
 
     // Ensure the directory exists
     std::fs::create_dir_all(&app_data_dir)
@@ -93,10 +263,11 @@ fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("preferences.json"))
 }
 
-#[tauri::command]
-async fn load_preferences(app: AppHandle) -> Result<AppPreferences, String> {
-    log::debug!("Loading preferences from disk");
-    let prefs_path = get_preferences_path(&app)?;
+/// Reads preferences from disk, falling back to defaults when none are saved yet.
+/// Backend subsystems use this to pick up user settings without a round-trip
+/// through the frontend.
+fn read_preferences(app: &AppHandle) -> Result<AppPreferences, String> {
+    let prefs_path = get_preferences_path(app)?;
 
     if !prefs_path.exists() {
         log::info!("Preferences file not found, using defaults");
@@ -108,24 +279,65 @@ async fn load_preferences(app: AppHandle) -> Result<AppPreferences, String> {
         format!("Failed to read preferences file: {e}")
     })?;
 
-    let preferences: AppPreferences = serde_json::from_str(&contents).map_err(|e| {
+    serde_json::from_str(&contents).map_err(|e| {
         log::error!("Failed to parse preferences JSON: {e}");
         format!("Failed to parse preferences: {e}")
-    })?;
+    })
+}
+
+#[tauri::command]
+async fn load_preferences(app: AppHandle) -> Result<AppPreferences, String> {
+    log::debug!("Loading preferences from disk");
+    let preferences = read_preferences(&app)?;
 
     log::info!("Successfully loaded preferences");
     Ok(preferences)
 }
 
 #[tauri::command]
-async fn save_preferences(app: AppHandle, preferences: AppPreferences) -> Result<(), String> {
+async fn save_preferences(app: AppHandle, mut preferences: AppPreferences) -> Result<(), String> {
     // Validate theme value
     validate_theme(&preferences.theme)?;
+    export::validate_scheduled_export(&preferences.scheduled_export)?;
+    if preferences.mcp_concurrency.max_running == Some(0) {
+        return Err("Allow at least one running MCP server".to_string());
+    }
+    middleware::validate(&preferences.llm_middleware)?;
+    db_backup::validate_settings(&preferences.database_backup)?;
+    retry::validate(&preferences.llm_retry)?;
+    time::validate_settings(&preferences.time_display)?;
+    rate_limit::validate(&preferences.llm_rate_limits)?;
+    response_cache::validate(&preferences.llm_cache)?;
+    network::validate_settings(&preferences.network)?;
+    clipboard::validate(&preferences.clipboard_history)?;
+    if let Some(shortcut) = &preferences.global_shortcut {
+        platform::validate_shortcut(shortcut)?;
+    }
+
+    // The second factor settings can't be changed from here, otherwise the
+    // webview could simply switch the check off
+    let stored = read_preferences(&app)?;
+    preferences.second_factor = stored.second_factor;
+    // Switching backends without moving the data would hide every
+    // conversation
+    preferences.storage_backend = stored.storage_backend;
+    // Also changed from the menus, so the frontend's copy may be stale
+    preferences.window_mode = stored.window_mode;
 
     log::debug!("Saving preferences to disk: {preferences:?}");
-    let prefs_path = get_preferences_path(&app)?;
+    write_preferences(&app, &preferences)?;
+    time::apply_settings(&preferences.time_display);
+    network::apply_settings(&preferences.network);
+    clipboard::apply_settings(&app, &preferences.clipboard_history);
+    power::apply_settings(&app, &preferences.power);
+    platform::apply_global_shortcut(&app, preferences.global_shortcut.as_deref())
+}
+
+/// Writes preferences to disk atomically.
+fn write_preferences(app: &AppHandle, preferences: &AppPreferences) -> Result<(), String> {
+    let prefs_path = get_preferences_path(app)?;
 
-    let json_content = serde_json::to_string_pretty(&preferences).map_err(|e| {
+    let json_content = serde_json::to_string_pretty(preferences).map_err(|e| {
         log::error!("Failed to serialize preferences: {e}");
         format!("Failed to serialize preferences: {e}")
     })?;
@@ -168,6 +380,7 @@ async fn send_native_notification(
         match notification.show() {
             Ok(_) => {
                 log::info!("Native notification sent successfully");
+                indicators::notification_shown(&app);
                 Ok(())
             }
             Err(e) => {
@@ -186,10 +399,7 @@ async fn send_native_notification(
 
 // Recovery functions - simple pattern for saving JSON data to disk
 fn get_recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    let app_data_dir = crate::profiles::data_dir(app)?;
 
     let recovery_dir = app_data_dir.join("recovery");
 
@@ -200,9 +410,35 @@ fn get_recovery_dir(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(recovery_dir)
 }
 
+// Recovery data can be namespaced by workspace id or window label, so two
+// windows recovering at once never read or overwrite each other's files.
+// Namespaced files live in `recovery/namespaces/<namespace>/`; files saved
+// without a namespace stay directly in `recovery/`.
+const RECOVERY_NAMESPACES_DIR: &str = "namespaces";
+
+fn get_namespace_dir(app: &AppHandle, namespace: Option<&str>) -> Result<PathBuf, String> {
+    let recovery_dir = get_recovery_dir(app)?;
+    let Some(namespace) = namespace else {
+        return Ok(recovery_dir);
+    };
+
+    validate_filename(namespace).map_err(|e| format!("Invalid recovery namespace: {e}"))?;
+    let namespace_dir = recovery_dir.join(RECOVERY_NAMESPACES_DIR).join(namespace);
+
+    std::fs::create_dir_all(&namespace_dir)
+        .map_err(|e| format!("Failed to create recovery namespace directory: {e}"))?;
+
+    Ok(namespace_dir)
+}
+
 #[tauri::command]
-async fn save_emergency_data(app: AppHandle, filename: String, data: Value) -> Result<(), String> {
-    log::info!("Saving emergency data to file: {filename}");
+async fn save_emergency_data(
+    app: AppHandle,
+    filename: String,
+    data: Value,
+    namespace: Option<String>,
+) -> Result<(), String> {
+    log::info!("Saving emergency data to file: {filename} (namespace: {namespace:?})");
 
     // Validate filename with proper security checks
     validate_filename(&filename)?;
@@ -214,7 +450,7 @@ async fn save_emergency_data(app: AppHandle, filename: String, data: Value) -> R
         return Err("Data too large (max 10MB)".to_string());
     }
 
-    let recovery_dir = get_recovery_dir(&app)?;
+    let recovery_dir = get_namespace_dir(&app, namespace.as_deref())?;
     let file_path = recovery_dir.join(format!("{filename}.json"));
 
     let json_content = serde_json::to_string_pretty(&data).map_err(|e| {
@@ -239,14 +475,265 @@ async fn save_emergency_data(app: AppHandle, filename: String, data: Value) -> R
     Ok(())
 }
 
+const MAX_EMERGENCY_BATCH_ENTRIES: usize = 100;
+/// Extension of a batch's journal, `<batch id>.batch`
+const EMERGENCY_JOURNAL_EXTENSION: &str = "batch";
+
+/// Held while a batch is saved or interrupted batches are recovered, so
+/// recovery never mistakes a batch in progress for a crashed one.
+static EMERGENCY_BATCH_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmergencyEntry {
+    filename: String,
+    data: Value,
+}
+
+/// Written once every file of a batch is in place as a temporary file, and
+/// removed once the batch is committed.
+#[derive(Debug, Serialize, Deserialize)]
+struct EmergencyJournal {
+    filenames: Vec<String>,
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+fn is_batch_id(value: &str) -> bool {
+    value.len() == 32 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Finishes the batches a crash interrupted in `dir`. A batch with a
+/// journal had all of its files written, so it is rolled forward: the
+/// temporary files still there replace their targets and the backups are
+/// removed. The temporary and backup files of a batch without a readable
+/// journal never replaced anything, so they are removed.
+fn recover_emergency_batches(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let paths: Vec<PathBuf> = entries.filter_map(Result::ok).map(|e| e.path()).collect();
+
+    for journal_path in &paths {
+        if journal_path
+            .extension()
+            .is_none_or(|ext| ext != EMERGENCY_JOURNAL_EXTENSION)
+        {
+            continue;
+        }
+        let Some(batch_id) = journal_path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let journal = std::fs::read(journal_path)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<EmergencyJournal>(&json).ok());
+        if let Some(journal) = journal {
+            log::warn!("Completing interrupted recovery batch {batch_id}");
+            for filename in journal.filenames {
+                if validate_filename(&filename).is_err() {
+                    continue;
+                }
+                let temp_path = dir.join(format!("{filename}.{batch_id}.tmp"));
+                if temp_path.exists() {
+                    if let Err(e) =
+                        std::fs::rename(&temp_path, dir.join(format!("{filename}.json")))
+                    {
+                        log::error!("Failed to complete recovery file {filename}: {e}");
+                        // Keep the journal to try again next time
+                        return;
+                    }
+                }
+                let _ = std::fs::remove_file(dir.join(format!("{filename}.{batch_id}.bak")));
+            }
+        }
+        let _ = std::fs::remove_file(journal_path);
+    }
+
+    // What is left belongs to batches that never reached their journal
+    for path in &paths {
+        let leftover = path
+            .extension()
+            .is_some_and(|ext| ext == "tmp" || ext == "bak")
+            && path
+                .file_stem()
+                .and_then(|stem| Path::new(stem).extension())
+                .and_then(|id| id.to_str())
+                .is_some_and(is_batch_id);
+        if leftover && path.exists() {
+            log::warn!("Removing leftover recovery file {path:?}");
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Undoes the renames of a partly committed batch: each target gets its
+/// previous contents back, or is removed if it did not exist before.
+fn rollback_emergency_batch(committed: &[(PathBuf, Option<PathBuf>)]) {
+    for (file_path, backup_path) in committed.iter().rev() {
+        let result = match backup_path {
+            Some(backup_path) => std::fs::rename(backup_path, file_path),
+            None => std::fs::remove_file(file_path),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to roll back recovery file {file_path:?}: {e}");
+        }
+    }
+}
+
+/// Saves several recovery files as one unit. Every file is written to a
+/// temporary file first, then a journal listing the batch, and only then do
+/// the temporary files replace the existing ones. If a rename fails, the
+/// files already replaced are restored. If the app dies partway, the next
+/// save or load finishes the batch from its journal, or discards it if the
+/// journal was never written, so half of a session is never what is read.
+#[tauri::command]
+async fn save_emergency_batch(
+    app: AppHandle,
+    entries: Vec<EmergencyEntry>,
+    namespace: Option<String>,
+) -> Result<(), String> {
+    log::info!(
+        "Saving batch of {} emergency files (namespace: {namespace:?})",
+        entries.len()
+    );
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if entries.len() > MAX_EMERGENCY_BATCH_ENTRIES {
+        return Err(format!(
+            "Too many files in batch (max {MAX_EMERGENCY_BATCH_ENTRIES})"
+        ));
+    }
+
+    // Validate everything before touching the disk
+    let mut contents = Vec::with_capacity(entries.len());
+    let mut total_size = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        validate_filename(&entry.filename)?;
+        if entries[..index]
+            .iter()
+            .any(|other| other.filename == entry.filename)
+        {
+            return Err(format!("Duplicate file in batch: {}", entry.filename));
+        }
+        let json_content = serde_json::to_string_pretty(&entry.data).map_err(|e| {
+            log::error!("Failed to serialize emergency data: {e}");
+            format!("Failed to serialize data: {e}")
+        })?;
+        total_size += json_content.len();
+        contents.push(json_content);
+    }
+    if total_size > 10_485_760 {
+        return Err("Data too large (max 10MB)".to_string());
+    }
+
+    let recovery_dir = get_namespace_dir(&app, namespace.as_deref())?;
+    let _batch = EMERGENCY_BATCH_LOCK
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    recover_emergency_batches(&recovery_dir);
+    let batch_id = crate::storage::new_id()?;
+    let paths: Vec<(PathBuf, PathBuf)> = entries
+        .iter()
+        .map(|entry| {
+            let file_path = recovery_dir.join(format!("{}.json", entry.filename));
+            let temp_path = recovery_dir.join(format!("{}.{batch_id}.tmp", entry.filename));
+            (file_path, temp_path)
+        })
+        .collect();
+
+    // Phase one: write every temporary file, then the journal
+    let journal_path = recovery_dir.join(format!("{batch_id}.{EMERGENCY_JOURNAL_EXTENSION}"));
+    let journal = serde_json::to_vec(&EmergencyJournal {
+        filenames: entries.iter().map(|e| e.filename.clone()).collect(),
+    })
+    .map_err(|e| format!("Failed to serialize batch journal: {e}"))?;
+    let written = paths
+        .iter()
+        .zip(contents)
+        .try_for_each(|((_, temp_path), json_content)| {
+            write_synced(temp_path, json_content.as_bytes())
+        })
+        .and_then(|_| write_synced(&journal_path, &journal));
+    if let Err(e) = written {
+        log::error!("Failed to write emergency data file: {e}");
+        for (_, temp_path) in &paths {
+            let _ = std::fs::remove_file(temp_path);
+        }
+        let _ = std::fs::remove_file(&journal_path);
+        return Err(format!("Failed to write data file: {e}"));
+    }
+
+    // Phase two: move the existing files aside and the new ones in place
+    let mut committed: Vec<(PathBuf, Option<PathBuf>)> = Vec::with_capacity(paths.len());
+    for (file_path, temp_path) in &paths {
+        let backup_path = file_path.with_extension(format!("{batch_id}.bak"));
+        let result = (|| {
+            let had_previous = file_path.exists();
+            if had_previous {
+                std::fs::rename(file_path, &backup_path)?;
+            }
+            if let Err(e) = std::fs::rename(temp_path, file_path) {
+                if had_previous {
+                    let _ = std::fs::rename(&backup_path, file_path);
+                }
+                return Err(e);
+            }
+            Ok(had_previous.then_some(backup_path))
+        })();
+        match result {
+            Ok(backup_path) => committed.push((file_path.clone(), backup_path)),
+            Err(e) => {
+                log::error!("Failed to finalize emergency data file {file_path:?}: {e}");
+                rollback_emergency_batch(&committed);
+                for (_, temp_path) in &paths {
+                    let _ = std::fs::remove_file(temp_path);
+                }
+                let _ = std::fs::remove_file(&journal_path);
+                return Err(format!("Failed to finalize data file: {e}"));
+            }
+        }
+    }
+
+    for (_, backup_path) in committed {
+        if let Some(backup_path) = backup_path {
+            let _ = std::fs::remove_file(backup_path);
+        }
+    }
+    if let Err(e) = std::fs::remove_file(&journal_path) {
+        log::warn!("Failed to remove batch journal {journal_path:?}: {e}");
+    }
+
+    log::info!(
+        "Successfully saved {} emergency files to {recovery_dir:?}",
+        paths.len()
+    );
+    Ok(())
+}
+
 #[tauri::command]
-async fn load_emergency_data(app: AppHandle, filename: String) -> Result<Value, String> {
-    log::info!("Loading emergency data from file: {filename}");
+async fn load_emergency_data(
+    app: AppHandle,
+    filename: String,
+    namespace: Option<String>,
+) -> Result<Value, String> {
+    log::info!("Loading emergency data from file: {filename} (namespace: {namespace:?})");
 
     // Validate filename with proper security checks
     validate_filename(&filename)?;
 
-    let recovery_dir = get_recovery_dir(&app)?;
+    let recovery_dir = get_namespace_dir(&app, namespace.as_deref())?;
+    {
+        let _batch = EMERGENCY_BATCH_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        recover_emergency_batches(&recovery_dir);
+    }
     let file_path = recovery_dir.join(format!("{filename}.json"));
 
     if !file_path.exists() {
@@ -268,22 +755,13 @@ async fn load_emergency_data(app: AppHandle, filename: String) -> Result<Value,
     Ok(data)
 }
 
-#[tauri::command]
-async fn cleanup_old_recovery_files(app: AppHandle) -> Result<u32, String> {
-    log::info!("Cleaning up old recovery files");
-
-    let recovery_dir = get_recovery_dir(&app)?;
+/// Removes the JSON files in `dir` last modified before `cutoff` (seconds
+/// since the UNIX epoch) and returns how many were removed.
+fn remove_recovery_files_before(dir: &Path, cutoff: u64) -> Result<u32, String> {
     let mut removed_count = 0;
 
-    // Calculate cutoff time (7 days ago)
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("Failed to get current time: {e}"))?
-        .as_secs();
-    let seven_days_ago = now - (7 * 24 * 60 * 60);
-
     // Read directory and check each file
-    let entries = std::fs::read_dir(&recovery_dir).map_err(|e| {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
         log::error!("Failed to read recovery directory: {e}");
         format!("Failed to read directory: {e}")
     })?;
@@ -329,8 +807,8 @@ async fn cleanup_old_recovery_files(app: AppHandle) -> Result<u32, String> {
             }
         };
 
-        // Remove if older than 7 days
-        if modified_secs < seven_days_ago {
+        // Remove if older than the cutoff
+        if modified_secs < cutoff {
             match std::fs::remove_file(&path) {
                 Ok(_) => {
                     log::info!("Removed old recovery file: {path:?}");
@@ -343,211 +821,70 @@ async fn cleanup_old_recovery_files(app: AppHandle) -> Result<u32, String> {
         }
     }
 
-    log::info!("Cleanup complete. Removed {removed_count} old recovery files");
     Ok(removed_count)
 }
 
-// MCP Process Management
-// =======================
-
-struct McpProcess {
-    stdin: ChildStdin,
-    #[allow(dead_code)]
-    child: Child,
-}
-
-#[derive(Default)]
-pub struct McpProcesses {
-    processes: Mutex<HashMap<String, McpProcess>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct McpServerConfig {
-    pub id: String,
-    pub command: String,
-    pub args: Vec<String>,
-    pub env: Option<HashMap<String, String>>,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct McpStdoutEvent {
-    pub server_id: String,
-    pub data: String,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct McpStderrEvent {
-    pub server_id: String,
-    pub data: String,
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct McpExitEvent {
-    pub server_id: String,
-    pub code: Option<i32>,
-}
-
 #[tauri::command]
-async fn spawn_mcp_server(
-    app: AppHandle,
-    state: State<'_, McpProcesses>,
-    config: McpServerConfig,
-) -> Result<u32, String> {
-    log::info!(
-        "Spawning MCP server: {} with command: {}",
-        config.id,
-        config.command
-    );
-
-    // Build the shell command to run
-    // Use login shell to get the user's PATH
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
-
-    // Build environment exports
-    let env_exports = if let Some(ref env) = config.env {
-        env.iter()
-            .map(|(k, v)| format!("export {}=\"{}\"", k, v))
-            .collect::<Vec<_>>()
-            .join("; ")
-            + "; "
-    } else {
-        String::new()
-    };
-
-    // Build the full command
-    let full_command = format!(
-        "{}{}",
-        env_exports,
-        std::iter::once(config.command.clone())
-            .chain(config.args.iter().cloned())
-            .collect::<Vec<_>>()
-            .join(" ")
-    );
-
-    log::debug!("Shell command: {} -l -c \"{}\"", shell, full_command);
-
-    let mut child = StdCommand::new(&shell)
-        .args(["-l", "-c", &full_command])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn process: {e}"))?;
+async fn cleanup_old_recovery_files(app: AppHandle) -> Result<u32, String> {
+    log::info!("Cleaning up old recovery files");
 
-    let pid = child.id();
-    log::info!("MCP server {} spawned with PID: {}", config.id, pid);
+    let recovery_dir = get_recovery_dir(&app)?;
 
-    let stdin = child.stdin.take().ok_or("Failed to get stdin")?;
-    let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+    // Calculate cutoff time (7 days ago)
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Failed to get current time: {e}"))?
+        .as_secs();
+    let seven_days_ago = now - (7 * 24 * 60 * 60);
 
-    // Store the process
-    {
-        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
-        processes.insert(config.id.clone(), McpProcess { stdin, child });
-    }
+    let mut removed_count = remove_recovery_files_before(&recovery_dir, seven_days_ago)?;
 
-    // Spawn thread to read stdout
-    let app_stdout = app.clone();
-    let server_id_stdout = config.id.clone();
-    std::thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            match line {
-                Ok(data) => {
-                    let _ = app_stdout.emit(
-                        "mcp-stdout",
-                        McpStdoutEvent {
-                            server_id: server_id_stdout.clone(),
-                            data,
-                        },
-                    );
-                }
-                Err(e) => {
-                    log::error!("Error reading stdout: {e}");
-                    break;
-                }
+    // Namespaces are cleaned the same way; one left empty is removed
+    if let Ok(namespaces) = std::fs::read_dir(recovery_dir.join(RECOVERY_NAMESPACES_DIR)) {
+        for namespace_dir in namespaces.filter_map(Result::ok).map(|e| e.path()) {
+            if !namespace_dir.is_dir() {
+                continue;
             }
-        }
-    });
-
-    // Spawn thread to read stderr
-    let app_stderr = app.clone();
-    let server_id_stderr = config.id.clone();
-    std::thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            match line {
-                Ok(data) => {
-                    log::debug!("MCP {} stderr: {}", server_id_stderr, data);
-                    let _ = app_stderr.emit(
-                        "mcp-stderr",
-                        McpStderrEvent {
-                            server_id: server_id_stderr.clone(),
-                            data,
-                        },
-                    );
-                }
-                Err(e) => {
-                    log::error!("Error reading stderr: {e}");
-                    break;
-                }
+            match remove_recovery_files_before(&namespace_dir, seven_days_ago) {
+                Ok(count) => removed_count += count,
+                Err(e) => log::warn!("Failed to clean recovery namespace {namespace_dir:?}: {e}"),
             }
+            // Fails, as intended, while the namespace still has files
+            let _ = std::fs::remove_dir(&namespace_dir);
         }
-    });
-
-    Ok(pid)
-}
-
-#[tauri::command]
-async fn write_mcp_stdin(
-    state: State<'_, McpProcesses>,
-    server_id: String,
-    data: String,
-) -> Result<(), String> {
-    log::debug!("Writing to MCP server {}: {}", server_id, data.trim());
-
-    let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
-    let process = processes
-        .get_mut(&server_id)
-        .ok_or_else(|| format!("MCP server {} not found", server_id))?;
-
-    process
-        .stdin
-        .write_all(data.as_bytes())
-        .map_err(|e| format!("Failed to write to stdin: {e}"))?;
-
-    process
-        .stdin
-        .flush()
-        .map_err(|e| format!("Failed to flush stdin: {e}"))?;
+    }
 
-    Ok(())
+    log::info!("Cleanup complete. Removed {removed_count} old recovery files");
+    Ok(removed_count)
 }
 
+/// Removes every recovery file of a namespace, e.g. once its workspace is
+/// deleted or its window closes cleanly. Returns how many were removed.
 #[tauri::command]
-async fn kill_mcp_server(state: State<'_, McpProcesses>, server_id: String) -> Result<(), String> {
-    log::info!("Killing MCP server: {}", server_id);
-
-    let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
-    if let Some(mut process) = processes.remove(&server_id) {
-        process
-            .child
-            .kill()
-            .map_err(|e| format!("Failed to kill process: {e}"))?;
-        log::info!("MCP server {} killed", server_id);
+async fn cleanup_namespace(app: AppHandle, namespace: String) -> Result<u32, String> {
+    log::info!("Cleaning up recovery namespace: {namespace}");
+
+    validate_filename(&namespace).map_err(|e| format!("Invalid recovery namespace: {e}"))?;
+    let namespace_dir = get_recovery_dir(&app)?
+        .join(RECOVERY_NAMESPACES_DIR)
+        .join(&namespace);
+    if !namespace_dir.exists() {
+        return Ok(0);
     }
 
-    Ok(())
-}
+    let removed_count = std::fs::read_dir(&namespace_dir)
+        .map_err(|e| format!("Failed to read directory: {e}"))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .count() as u32;
 
-#[tauri::command]
-async fn is_mcp_server_running(
-    state: State<'_, McpProcesses>,
-    server_id: String,
-) -> Result<bool, String> {
-    let processes = state.processes.lock().map_err(|e| e.to_string())?;
-    Ok(processes.contains_key(&server_id))
+    std::fs::remove_dir_all(&namespace_dir).map_err(|e| {
+        log::error!("Failed to remove recovery namespace {namespace}: {e}");
+        format!("Failed to remove recovery namespace: {e}")
+    })?;
+
+    log::info!("Removed {removed_count} recovery files from namespace {namespace}");
+    Ok(removed_count)
 }
 
 // Create the native menu system
@@ -566,12 +903,27 @@ fn create_app_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
                 .build(app)?,
         )
         .separator()
+        .separator();
+
+    // Hide Others and Show All only exist on macOS, and Linux has no
+    // predefined Hide or Quit, so those are handled in `on_menu_event`
+    #[cfg(target_os = "macos")]
+    let app_submenu = app_submenu
         .item(&PredefinedMenuItem::hide(app, Some("Hide Nexus"))?)
         .item(&PredefinedMenuItem::hide_others(app, None)?)
         .item(&PredefinedMenuItem::show_all(app, None)?)
         .separator()
-        .item(&PredefinedMenuItem::quit(app, Some("Quit Nexus"))?)
-        .build()?;
+        .item(&PredefinedMenuItem::quit(app, Some("Quit Nexus"))?);
+    #[cfg(not(target_os = "macos"))]
+    let app_submenu = app_submenu
+        .item(&MenuItemBuilder::with_id("hide-window", "Hide Nexus").build(app)?)
+        .separator()
+        .item(
+            &MenuItemBuilder::with_id("quit", "Quit Nexus")
+                .accelerator("CmdOrCtrl+Q")
+                .build(app)?,
+        );
+    let app_submenu = app_submenu.build()?;
 
     // Build the Edit submenu (required for copy/paste shortcuts on macOS)
     let edit_submenu = SubmenuBuilder::new(app, "Edit")
@@ -585,7 +937,7 @@ fn create_app_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
         .build()?;
 
     // Build the View submenu
-    let view_submenu = SubmenuBuilder::new(app, "View")
+    let view_submenu = SubmenuBuilder::with_id(app, window_state::VIEW_MENU_ID, "View")
         .item(
             &MenuItemBuilder::with_id("toggle-left-sidebar", "Toggle Left Sidebar")
                 .accelerator("CmdOrCtrl+1")
@@ -596,6 +948,17 @@ fn create_app_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
                 .accelerator("CmdOrCtrl+2")
                 .build(app)?,
         )
+        .separator()
+        .item(
+            &CheckMenuItemBuilder::with_id(window_state::PIN_MENU_ID, "Pin on Top")
+                .accelerator("CmdOrCtrl+Shift+T")
+                .build(app)?,
+        )
+        .item(
+            &CheckMenuItemBuilder::with_id(window_state::COMPACT_MENU_ID, "Compact Mode")
+                .accelerator("CmdOrCtrl+Shift+M")
+                .build(app)?,
+        )
         .build()?;
 
     // Build the main menu with submenus
@@ -645,11 +1008,84 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(platform::AUTOSTART_ARGS.to_vec()),
+        ))
         // Note: tauri-plugin-shell is still in Cargo.toml but not used for MCP
         // MCP process management is handled by custom Rust commands
-        .manage(McpProcesses::default())
+        .manage(mcp::McpProcesses::default())
+        .manage(audit::McpAuditLog::default())
+        .manage(unfurl::LinkPreviewCache::default())
+        .manage(attachments::AttachmentStore::default())
+        .manage(mcp::stats::McpStatsSampler::default())
+        .manage(mcp::traffic::McpTrafficRecorder::default())
+        .manage(mcp::faults::McpFaults::default())
+        .manage(middleware::LlmGateway::default())
+        .manage(watchfolder::WatchFolders::default())
+        .manage(daemon::DaemonState::default())
+        .manage(quick_actions::PendingQuickAction::default())
+        .manage(scheduler::SchedulerStatus::default())
+        .manage(operations::Operations::default())
+        .manage(chat_proxy::ActiveStreams::default())
+        .manage(budget::Budgets::default())
+        .manage(rate_limit::LlmQueue::default())
+        .manage(local_llm::LocalLlm::default())
+        .manage(local_models::ModelDownloads::default())
+        .manage(embedding_runtime::EmbeddingRuntime::default())
+        .manage(mcp::client::McpBackendRequests::default())
+        .manage(agent::AgentRuns::default())
+        .manage(platform::TrayState::default())
+        .manage(window_state::WindowState::default())
+        .manage(deep_link::PendingDeepLink::default())
+        .manage(clipboard::ClipboardHistory::default())
+        .manage(audio::AudioRecordings::default())
+        .manage(speech::Speech::default())
+        .manage(indicators::Indicators::default())
+        .manage(bundle::PendingBundle::default())
+        .manage(power::Power::default())
         .setup(|app| {
             log::info!("🚀 Application starting up");
+
+            // A running daemon owns the backend: hand the request over to
+            // it instead of starting a second one
+            if daemon::stop_requested() || !daemon::requested() {
+                let command = if daemon::stop_requested() {
+                    daemon::DaemonCommand::Quit
+                } else if let Some(action) = quick_actions::from_args() {
+                    daemon::DaemonCommand::Run(action)
+                } else if let Some(link) = deep_link::from_args() {
+                    daemon::DaemonCommand::OpenLink(link)
+                } else if let Some(path) = bundle::from_args() {
+                    daemon::DaemonCommand::OpenBundle(path)
+                } else {
+                    daemon::DaemonCommand::Show
+                };
+                match daemon::send(app.handle(), command.clone()) {
+                    Ok(Some(_)) => {
+                        log::info!("Handed {command:?} to the running daemon, exiting");
+                        app.handle().exit(0);
+                        return Ok(());
+                    }
+                    Ok(None) if daemon::stop_requested() => {
+                        log::info!("No daemon is running");
+                        app.handle().exit(0);
+                        return Ok(());
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Failed to reach the running daemon: {e}"),
+                }
+            }
+
+            profiles::init(app.handle());
+            // Apply pending schema migrations before anything else uses
+            // the database
+            if let Err(e) = db::open(app.handle()) {
+                log::error!("Failed to prepare database: {e}");
+            }
+            // After the profile, whose preferences pick the backend
+            app.manage(persistence::ActiveStorage::new(app.handle()));
             log::debug!(
                 "App handle initialized for package: {}",
                 app.package_info().name
@@ -660,6 +1096,36 @@ pub fn run() {
                 log::error!("Failed to create app menu: {e}");
                 return Err(e);
             }
+            window_state::load_mode(app.handle());
+
+            // Start background maintenance jobs
+            power::start(app.handle());
+            scheduler::start(app.handle().clone(), scheduler::default_jobs());
+            mcp::stats::start(app.handle().clone());
+            watchfolder::start(app.handle());
+
+            if daemon::requested() {
+                daemon::start(app.handle())?;
+            } else {
+                daemon::show_main_window(app.handle())?;
+            }
+
+            quick_actions::start(app.handle());
+            deep_link::start(app.handle());
+            bundle::start(app.handle());
+            clipboard::start(app.handle());
+            if let Err(e) = platform::create_tray(app.handle()) {
+                log::error!("{e}");
+            }
+            let preferences = read_preferences(app.handle()).ok();
+            if let Some(preferences) = &preferences {
+                time::apply_settings(&preferences.time_display);
+                network::apply_settings(&preferences.network);
+            }
+            let shortcut = preferences.and_then(|p| p.global_shortcut);
+            if let Err(e) = platform::apply_global_shortcut(app.handle(), shortcut.as_deref()) {
+                log::error!("{e}");
+            }
 
             // Set up menu event handlers
             app.on_menu_event(move |app, event| {
@@ -714,6 +1180,23 @@ pub fn run() {
                             }
                         }
                     }
+                    window_state::PIN_MENU_ID => window_state::toggle_pinned(app),
+                    window_state::COMPACT_MENU_ID => window_state::toggle_compact(app),
+                    "hide-window" => {
+                        if let Some(window) = app.get_webview_window("main") {
+                            if let Err(e) = window.hide() {
+                                log::error!("Failed to hide window: {e}");
+                            }
+                        }
+                    }
+                    "quit" => app.exit(0),
+                    id if id.starts_with(quick_actions::MENU_ID_PREFIX) => {
+                        if let Some(action) = quick_actions::from_menu_id(id) {
+                            if let Err(e) = quick_actions::perform(app, action) {
+                                log::error!("Failed to run quick action: {e}");
+                            }
+                        }
+                    }
                     _ => {
                         log::debug!("Unhandled menu event: {:?}", event.id());
                     }
@@ -737,11 +1220,288 @@ pub fn run() {
             save_emergency_data,
             load_emergency_data,
             cleanup_old_recovery_files,
-            spawn_mcp_server,
-            write_mcp_stdin,
-            kill_mcp_server,
-            is_mcp_server_running
+            mcp::spawn_mcp_server,
+            mcp::write_mcp_stdin,
+            mcp::kill_mcp_server,
+            mcp::is_mcp_server_running,
+            mcp::get_mcp_stderr_tail,
+            mcp::update_and_restart_mcp_server,
+            mcp::stats::get_mcp_server_stats,
+            mcp::mock::spawn_mock_mcp_server,
+            mcp::traffic::set_mcp_traffic_recording,
+            mcp::traffic::get_mcp_traffic,
+            mcp::traffic::export_mcp_traffic,
+            mcp::bundle::export_mcp_configs,
+            mcp::runtimes::check_mcp_runtimes,
+            mcp::conformance::run_mcp_conformance,
+            mcp::registry::fetch_mcp_registry,
+            mcp::install::install_mcp_server,
+            audit::query_mcp_audit_log,
+            unfurl::unfurl_url,
+            conversations::sync_conversation,
+            conversations::remove_synced_conversation,
+            export::run_scheduled_export_now,
+            backup::merge_backup,
+            handoff::export_live_session,
+            handoff::import_live_session,
+            benchmark::benchmark_providers,
+            benchmark::get_benchmark_history,
+            prompt_history::record_system_prompt,
+            prompt_history::get_system_prompt_history,
+            prompt_history::rollback_system_prompt,
+            attachments::get_attachment_usage,
+            middleware::get_middleware_metrics,
+            second_factor::begin_totp_enrollment,
+            second_factor::enroll_security_key,
+            second_factor::configure_second_factor,
+            second_factor::wipe_app_data,
+            second_factor::export_all_data,
+            tasks::extract_tasks,
+            tasks::list_tasks,
+            tasks::update_task,
+            tasks::delete_task,
+            calendar::create_ics,
+            destinations::list_destinations,
+            destinations::save_destination,
+            destinations::remove_destination,
+            destinations::send_to_destination,
+            comparisons::create_comparison,
+            comparisons::record_comparison_turn,
+            comparisons::list_comparisons,
+            comparisons::get_linked_comparison,
+            comparisons::delete_comparison,
+            comparisons::export_comparison,
+            diff::diff_texts,
+            feedback::set_feedback,
+            feedback::get_feedback,
+            feedback::get_feedback_stats,
+            feedback::export_feedback,
+            training::export_training_data,
+            storage::create_conversation,
+            storage::append_message,
+            storage::get_conversation,
+            storage::list_conversations,
+            storage::delete_conversation,
+            watchfolder::list_watch_folders,
+            watchfolder::save_watch_folder,
+            watchfolder::remove_watch_folder,
+            watchfolder::list_watch_folder_files,
+            search::search_messages,
+            profiles::list_profiles,
+            profiles::create_profile,
+            profiles::switch_profile,
+            export::export_conversation,
+            export::export_all_conversations,
+            openapi::register_openapi_tool,
+            openapi::list_openapi_tools,
+            openapi::remove_openapi_tool,
+            openapi::call_openapi_tool,
+            graphql::register_graphql_tool,
+            graphql::list_graphql_tools,
+            graphql::remove_graphql_tool,
+            graphql::call_graphql_tool,
+            importer::import_conversations,
+            importer::get_import_provenance,
+            storage::list_folders,
+            storage::create_folder,
+            storage::rename_folder,
+            storage::move_folder,
+            storage::delete_folder,
+            storage::move_conversation,
+            storage::set_conversation_pinned,
+            storage::set_conversation_tags,
+            storage::list_tags,
+            databases::list_databases,
+            databases::save_database,
+            databases::remove_database,
+            databases::query_database,
+            databases::describe_database,
+            inspectors::get_inspector_settings,
+            inspectors::save_inspector_settings,
+            inspectors::run_kubectl,
+            inspectors::run_ssh_command,
+            storage::edit_message,
+            storage::get_message_history,
+            storage::restore_message_revision,
+            storage::delete_message,
+            storage::restore_message,
+            storage::purge_deleted,
+            db_backup::create_backup_now,
+            db_backup::list_backups,
+            db_backup::restore_backup,
+            daemon::get_daemon_status,
+            db::get_db_schema_version,
+            platform::send_actionable_notification,
+            platform::get_launch_at_login,
+            platform::set_launch_at_login,
+            attachments::store_attachment,
+            attachments::get_attachment,
+            quick_actions::take_pending_quick_action,
+            archive::archive_conversation,
+            archive::archive_conversations_before,
+            archive::unarchive_conversation,
+            archive::list_archived_conversations,
+            cleanup_namespace,
+            save_emergency_batch,
+            usage::get_usage_stats,
+            persistence::get_storage_backend,
+            persistence::migrate_storage,
+            chat_proxy::stream_chat_completion,
+            test_inspect::__test_inspect,
+            replay::count_llm_fixtures,
+            replay::clear_llm_fixtures,
+            ollama::ollama_status,
+            ollama::ollama_list_models,
+            ollama::ollama_pull_model,
+            ollama::ollama_delete_model,
+            llm::list_provider_models,
+            operations::list_operations,
+            operations::cancel_operation,
+            command_metrics::record_command_metrics,
+            command_metrics::get_command_metrics,
+            command_metrics::clear_command_metrics,
+            providers::list_providers,
+            providers::add_provider,
+            providers::update_provider,
+            providers::remove_provider,
+            providers::test_provider_connection,
+            archive::archive_conversations,
+            archive::unarchive_conversations,
+            archive::set_conversations_locked,
+            api_keys::set_api_key,
+            api_keys::has_api_key,
+            api_keys::delete_api_key,
+            annotations::get_image_annotations,
+            annotations::save_image_annotations,
+            annotations::render_annotated_image,
+            chat_proxy::cancel_llm_request,
+            share::share_file,
+            share::share_text,
+            tokens::count_tokens,
+            audit::record_mcp_network_activity,
+            audit::get_network_activity,
+            budget::set_budget,
+            budget::get_budgets,
+            rate_limit::get_llm_queue_status,
+            mcp::faults::set_mcp_faults,
+            mcp::faults::get_mcp_faults,
+            embeddings::embed_texts,
+            embeddings::benchmark_embeddings,
+            embeddings::probe_embedding_backends,
+            embedding_runtime::download_embedding_model,
+            prompt_templates::list_prompt_templates,
+            prompt_templates::create_prompt_template,
+            prompt_templates::update_prompt_template,
+            prompt_templates::delete_prompt_template,
+            prompt_templates::render_prompt,
+            response_cache::clear_llm_cache,
+            network::test_network_connectivity,
+            local_llm::local_llm_runtime,
+            local_llm::load_local_model,
+            local_llm::local_model_status,
+            local_llm::unload_local_model,
+            local_llm::local_chat_completion,
+            local_models::download_model,
+            local_models::list_local_models,
+            mcp::policy::get_tool_policy,
+            mcp::policy::set_tool_policy,
+            agent::run_agent,
+            agent::approve_agent_tool_call,
+            agent::cancel_agent_run,
+            model_compare::compare_models,
+            platform::set_tray_servers,
+            window_state::reset_window_state,
+            deep_link::take_pending_deep_link,
+            clipboard::get_clipboard_history,
+            clipboard::clear_clipboard_history,
+            clipboard::attach_clipboard_entry,
+            clipboard::paste_clipboard_as_attachment,
+            screenshot::capture_screenshot,
+            audio::start_audio_recording,
+            audio::stop_audio_recording,
+            audio::cancel_audio_recording,
+            audio::transcribe_audio,
+            speech::speak_text,
+            speech::pause_speech,
+            speech::resume_speech,
+            speech::stop_speech,
+            speech::list_voices,
+            indicators::set_badge_count,
+            indicators::set_progress,
+            window_state::set_window_pinned,
+            window_state::set_compact_mode,
+            bundle::export_bundle,
+            bundle::import_bundle,
+            bundle::take_pending_bundle,
+            power::get_power_state
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // In daemon mode closing the last window keeps the backend
+            // running; an explicit exit still goes through
+            tauri::RunEvent::ExitRequested {
+                api, code: None, ..
+            } if app.state::<daemon::DaemonState>().is_daemon() => {
+                api.prevent_exit();
+            }
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_),
+                ..
+            } if label == "main" => window_state::track(app),
+            // With `close_to_tray` the main window only hides, so its
+            // webview keeps managing MCP servers
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::CloseRequested { api, .. },
+                ..
+            } if label == "main" && platform::closes_to_tray(app) => {
+                window_state::save(app);
+                api.prevent_close();
+                if let Some(window) = app.get_webview_window(&label) {
+                    if let Err(e) = window.hide() {
+                        log::error!("Failed to hide window to the tray: {e}");
+                    }
+                }
+            }
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Destroyed,
+                ..
+            } if label == "main" => window_state::save(app),
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Focused(true),
+                ..
+            } if label == "main" => indicators::main_window_focused(app),
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }),
+                ..
+            } => file_drop::ingest(app, label, paths),
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => {
+                for url in urls {
+                    match url.to_file_path() {
+                        Ok(path) => {
+                            let app = app.clone();
+                            std::thread::spawn(move || {
+                                let _ = bundle::open(&app, &path);
+                            });
+                        }
+                        Err(()) => {
+                            let _ = deep_link::open(app, url.as_str());
+                        }
+                    }
+                }
+            }
+            tauri::RunEvent::Exit => {
+                window_state::save(app);
+                mcp::shutdown_all(app);
+                local_llm::shutdown(app);
+                daemon::shutdown(app);
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,546 @@
+// LLM Streaming Client
+// ====================
+//
+// A small streaming client for the providers the frontend talks to
+// (Google Gemini, OpenAI, Groq, Mistral, and a local Ollama), for backend
+// features that need to call a model themselves. Requests carry the API key
+// stored for the provider in the keychain (see `api_keys`). Responses are
+// read as server-sent events and passed to a callback delta by delta.
+//
+// What differs between providers lives behind the `Provider` trait: how a
+// streamed chat request is built, how its events are read, and how the
+// provider's models are listed. `ProviderId` names a provider in requests
+// and settings and hands out its adapter.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderId {
+    Google,
+    OpenAI,
+    Groq,
+    Mistral,
+    /// The local Ollama daemon; no API key
+    Ollama,
+}
+
+impl ProviderId {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderId::Google => "google",
+            ProviderId::OpenAI => "openai",
+            ProviderId::Groq => "groq",
+            ProviderId::Mistral => "mistral",
+            ProviderId::Ollama => "ollama",
+        }
+    }
+
+    pub fn adapter(self) -> &'static dyn Provider {
+        match self {
+            ProviderId::Google => &Gemini,
+            ProviderId::OpenAI => &OpenAiCompatible(ProviderId::OpenAI),
+            ProviderId::Groq => &OpenAiCompatible(ProviderId::Groq),
+            ProviderId::Mistral => &Mistral,
+            ProviderId::Ollama => &OpenAiCompatible(ProviderId::Ollama),
+        }
+    }
+}
+
+/// A model API. Implementations only build requests and read responses;
+/// sending them, errors and timing are handled once in `stream_chat` and
+/// `list_provider_models`.
+pub trait Provider: Send + Sync {
+    /// Request for a streamed chat completion.
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &LlmRequest,
+    ) -> reqwest::RequestBuilder;
+
+    /// Text delta and reported output tokens in one SSE event.
+    fn parse_event(&self, data: &Value) -> (String, Option<u64>);
+
+    /// Request for the models the key has access to.
+    fn models_request(&self, client: &reqwest::Client, api_key: &str) -> reqwest::RequestBuilder;
+
+    /// Chat models in a models response.
+    fn parse_models(&self, body: &Value) -> Vec<ModelInfo>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub provider: ProviderId,
+    pub model: String,
+    pub api_key: String,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<LlmMessage>,
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmStreamOutcome {
+    pub text: String,
+    /// Time from sending the request to the first text delta
+    pub first_token: Option<Duration>,
+    pub total: Duration,
+    /// Output tokens as reported by the provider, when it reports usage
+    pub output_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    /// The model id for requests
+    pub id: String,
+    /// Display name, when the provider has one
+    pub name: Option<String>,
+    /// Input tokens, when the provider reports it
+    pub context_window: Option<u64>,
+}
+
+/// Shared HTTP client for model requests. No overall timeout, since long
+/// generations are expected; connection setup is bounded instead.
+pub fn http_client() -> reqwest::Client {
+    crate::network::client_builder()
+        .connect_timeout(Duration::from_secs(15))
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+const GEMINI_API: &str = "https://generativelanguage.googleapis.com/v1beta";
+const MISTRAL_API: &str = "https://api.mistral.ai/v1";
+
+/// Model ids in OpenAI-style lists that are not chat models.
+const NON_CHAT_MODELS: &[&str] = &[
+    "embed",
+    "tts",
+    "whisper",
+    "transcribe",
+    "dall-e",
+    "image",
+    "moderation",
+    "realtime",
+    "audio",
+    "guard",
+    "davinci",
+    "babbage",
+];
+
+/// Google Gemini's `streamGenerateContent`.
+struct Gemini;
+
+impl Provider for Gemini {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &LlmRequest,
+    ) -> reqwest::RequestBuilder {
+        let contents: Vec<Value> = request
+            .messages
+            .iter()
+            .map(|m| {
+                let role = if m.role == "assistant" {
+                    "model"
+                } else {
+                    "user"
+                };
+                json!({ "role": role, "parts": [{ "text": m.content }] })
+            })
+            .collect();
+        let mut body = json!({ "contents": contents });
+        if let Some(system) = &request.system_prompt {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["generationConfig"] = json!({ "maxOutputTokens": max_tokens });
+        }
+
+        client
+            .post(format!(
+                "{GEMINI_API}/models/{}:streamGenerateContent?alt=sse",
+                request.model
+            ))
+            .header("x-goog-api-key", &request.api_key)
+            .json(&body)
+    }
+
+    fn parse_event(&self, data: &Value) -> (String, Option<u64>) {
+        let text = data["candidates"][0]["content"]["parts"]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p["text"].as_str())
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        (text, data["usageMetadata"]["candidatesTokenCount"].as_u64())
+    }
+
+    fn models_request(&self, client: &reqwest::Client, api_key: &str) -> reqwest::RequestBuilder {
+        client
+            .get(format!("{GEMINI_API}/models?pageSize=1000"))
+            .header("x-goog-api-key", api_key)
+    }
+
+    fn parse_models(&self, body: &Value) -> Vec<ModelInfo> {
+        let Some(models) = body["models"].as_array() else {
+            return Vec::new();
+        };
+        models
+            .iter()
+            .filter(|m| {
+                m["supportedGenerationMethods"]
+                    .as_array()
+                    .is_some_and(|methods| methods.iter().any(|v| v == "generateContent"))
+            })
+            .filter_map(|m| {
+                let name = m["name"].as_str()?;
+                Some(ModelInfo {
+                    id: name.strip_prefix("models/").unwrap_or(name).to_string(),
+                    name: m["displayName"].as_str().map(String::from),
+                    context_window: m["inputTokenLimit"].as_u64(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Messages in OpenAI's chat format, the system prompt first.
+fn openai_messages(request: &LlmRequest) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    if let Some(system) = &request.system_prompt {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+    messages.extend(
+        request
+            .messages
+            .iter()
+            .map(|m| json!({ "role": m.role, "content": m.content })),
+    );
+    messages
+}
+
+/// Text of a chat completion chunk's delta. Content is usually a string;
+/// some models send a list of typed parts instead.
+fn openai_delta_text(data: &Value) -> String {
+    let content = &data["choices"][0]["delta"]["content"];
+    match content.as_array() {
+        Some(parts) => parts
+            .iter()
+            .filter(|p| p["type"] == "text")
+            .filter_map(|p| p["text"].as_str())
+            .collect(),
+        None => content.as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// `data` of an OpenAI-style models list, minus embedding, speech, image
+/// and moderation models.
+fn openai_models(body: &Value) -> Vec<ModelInfo> {
+    let Some(models) = body["data"].as_array() else {
+        return Vec::new();
+    };
+    models
+        .iter()
+        .filter_map(|m| {
+            let id = m["id"].as_str()?;
+            if NON_CHAT_MODELS.iter().any(|word| id.contains(word)) {
+                return None;
+            }
+            Some(ModelInfo {
+                id: id.to_string(),
+                name: None,
+                context_window: m["context_window"].as_u64(),
+            })
+        })
+        .collect()
+}
+
+/// OpenAI's chat completions API, and the providers that implement it:
+/// Groq, and Ollama's compatibility endpoint.
+struct OpenAiCompatible(ProviderId);
+
+impl OpenAiCompatible {
+    fn base_url(&self) -> String {
+        match self.0 {
+            ProviderId::Groq => "https://api.groq.com/openai/v1".to_string(),
+            ProviderId::Ollama => format!("{}/v1", crate::ollama::base_url()),
+            _ => "https://api.openai.com/v1".to_string(),
+        }
+    }
+}
+
+/// Adds the key as a bearer token, unless there is none (Ollama).
+fn with_key(builder: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+    if api_key.is_empty() {
+        builder
+    } else {
+        builder.bearer_auth(api_key)
+    }
+}
+
+impl Provider for OpenAiCompatible {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &LlmRequest,
+    ) -> reqwest::RequestBuilder {
+        let mut body = json!({
+            "model": request.model,
+            "messages": openai_messages(request),
+            "stream": true,
+            "stream_options": { "include_usage": true },
+        });
+        if let Some(max_tokens) = request.max_tokens {
+            // Ollama's compatibility layer only knows the older name
+            let field = if self.0 == ProviderId::Ollama {
+                "max_tokens"
+            } else {
+                "max_completion_tokens"
+            };
+            body[field] = json!(max_tokens);
+        }
+
+        let url = format!("{}/chat/completions", self.base_url());
+        with_key(client.post(url).json(&body), &request.api_key)
+    }
+
+    fn parse_event(&self, data: &Value) -> (String, Option<u64>) {
+        let usage = data["usage"]["completion_tokens"]
+            .as_u64()
+            .or_else(|| data["x_groq"]["usage"]["completion_tokens"].as_u64());
+        (openai_delta_text(data), usage)
+    }
+
+    fn models_request(&self, client: &reqwest::Client, api_key: &str) -> reqwest::RequestBuilder {
+        with_key(client.get(format!("{}/models", self.base_url())), api_key)
+    }
+
+    fn parse_models(&self, body: &Value) -> Vec<ModelInfo> {
+        openai_models(body)
+    }
+}
+
+/// Mistral's chat completions API. OpenAI-shaped, but it rejects fields it
+/// doesn't know, such as `stream_options`, and always reports usage in the
+/// last chunk.
+struct Mistral;
+
+impl Provider for Mistral {
+    fn chat_request(
+        &self,
+        client: &reqwest::Client,
+        request: &LlmRequest,
+    ) -> reqwest::RequestBuilder {
+        let mut body = json!({
+            "model": request.model,
+            "messages": openai_messages(request),
+            "stream": true,
+        });
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+
+        client
+            .post(format!("{MISTRAL_API}/chat/completions"))
+            .bearer_auth(&request.api_key)
+            .json(&body)
+    }
+
+    fn parse_event(&self, data: &Value) -> (String, Option<u64>) {
+        (
+            openai_delta_text(data),
+            data["usage"]["completion_tokens"].as_u64(),
+        )
+    }
+
+    fn models_request(&self, client: &reqwest::Client, api_key: &str) -> reqwest::RequestBuilder {
+        client
+            .get(format!("{MISTRAL_API}/models"))
+            .bearer_auth(api_key)
+    }
+
+    fn parse_models(&self, body: &Value) -> Vec<ModelInfo> {
+        let Some(models) = body["data"].as_array() else {
+            return Vec::new();
+        };
+        models
+            .iter()
+            .filter(|m| m["capabilities"]["completion_chat"] == true)
+            .filter_map(|m| {
+                Some(ModelInfo {
+                    id: m["id"].as_str()?.to_string(),
+                    name: m["name"].as_str().map(String::from),
+                    context_window: m["max_context_length"].as_u64(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Best-effort message from a provider error body.
+pub fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| {
+            let error = if v.is_array() {
+                &v[0]["error"]
+            } else {
+                &v["error"]
+            };
+            error["message"].as_str().map(String::from)
+        })
+        .unwrap_or_else(|| body.chars().take(300).collect())
+}
+
+/// Reads a server-sent event stream to its end, calling `on_data` with the
+/// payload of every `data:` line except the `[DONE]` marker.
+pub async fn read_sse(
+    response: &mut reqwest::Response,
+    mut on_data: impl FnMut(&str),
+) -> reqwest::Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut handle_line = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
+        let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+            return;
+        };
+        if !data.is_empty() && data != "[DONE]" {
+            on_data(data);
+        }
+    };
+
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            handle_line(&line);
+        }
+    }
+    if !buffer.is_empty() {
+        handle_line(&buffer);
+    }
+    Ok(())
+}
+
+/// Streams a chat completion, calling `on_delta` for every text delta.
+/// Refused while the provider is over a budget with a hard stop (see
+/// `budget`).
+pub async fn stream_chat(
+    app: &tauri::AppHandle,
+    client: &reqwest::Client,
+    request: &LlmRequest,
+    mut on_delta: impl FnMut(&str),
+) -> Result<LlmStreamOutcome, String> {
+    let billed = request.provider != ProviderId::Ollama;
+    if billed {
+        crate::budget::check(app, request.provider.as_str())?;
+    }
+    let started = Instant::now();
+    let mut response = crate::retry::send(app, None, request.provider.as_str(), || {
+        request.provider.adapter().chat_request(client, request)
+    })
+    .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "{} returned {status}: {}",
+            request.provider.as_str(),
+            error_message(&body)
+        ));
+    }
+
+    let mut outcome = LlmStreamOutcome {
+        text: String::new(),
+        first_token: None,
+        total: Duration::ZERO,
+        output_tokens: None,
+    };
+    read_sse(&mut response, |data| {
+        let Ok(value) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+
+        let (text, tokens) = request.provider.adapter().parse_event(&value);
+        if tokens.is_some() {
+            outcome.output_tokens = tokens;
+        }
+        if !text.is_empty() {
+            outcome.first_token.get_or_insert_with(|| started.elapsed());
+            on_delta(&text);
+            outcome.text.push_str(&text);
+        }
+    })
+    .await
+    .map_err(|e| format!("Stream from {} failed: {e}", request.provider.as_str()))?;
+
+    outcome.total = started.elapsed();
+    if billed {
+        // Only output tokens are reported here, so the prompt is estimated
+        let tokenizer = crate::tokens::Tokenizer::for_model(&request.model);
+        let input_tokens = request
+            .system_prompt
+            .iter()
+            .chain(request.messages.iter().map(|m| &m.content))
+            .map(|text| crate::tokens::count_text(text, tokenizer))
+            .sum();
+        let output_tokens = outcome
+            .output_tokens
+            .unwrap_or_else(|| crate::tokens::count_text(&outcome.text, tokenizer));
+        crate::budget::record(
+            app,
+            request.provider.as_str(),
+            &request.model,
+            input_tokens,
+            output_tokens,
+        );
+    }
+    Ok(outcome)
+}
+
+/// Chat models available to the stored API key at `provider`, sorted by id,
+/// for the model picker. Ollama needs no key.
+#[tauri::command]
+pub async fn list_provider_models(provider: ProviderId) -> Result<Vec<ModelInfo>, String> {
+    let api_key = crate::api_keys::require(provider)?;
+    let adapter = provider.adapter();
+    let response = adapter
+        .models_request(&http_client(), &api_key)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {e}", provider.as_str()))?;
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        log::error!("Failed to list {} models: {status}", provider.as_str());
+        return Err(format!(
+            "{} returned {status}: {}",
+            provider.as_str(),
+            error_message(&body)
+        ));
+    }
+    let body: Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Unexpected model list from {}: {e}", provider.as_str()))?;
+
+    let mut models = adapter.parse_models(&body);
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models.dedup_by(|a, b| a.id == b.id);
+    log::debug!("{} lists {} chat models", provider.as_str(), models.len());
+    Ok(models)
+}
@@ -0,0 +1,514 @@
+// Local Inference
+// ===============
+//
+// Runs GGUF models on this machine with llama.cpp, so the playground works
+// offline and without an API key. The model is served by llama.cpp's own
+// `llama-server`, started as a child process on a free local port: a model
+// that crashes or runs out of memory takes down that process, not the app,
+// and llama.cpp builds for Metal, CUDA, Vulkan or the CPU can be swapped
+// without rebuilding Nexus. The binary is looked up on the login shell's
+// PATH, or taken from `LLAMA_SERVER`.
+//
+// Nexus does not ship `llama-server`: install llama.cpp first (`brew
+// install llama.cpp`, `winget install llama.cpp`, or a release from
+// https://github.com/ggml-org/llama.cpp/releases). `local_llm_runtime`
+// reports whether it was found, so the settings can say so before a model
+// is loaded.
+//
+// One model is loaded at a time. While it loads, `local-llm-load` events
+// report the time taken and the memory of the server process, which grows
+// towards the size of the model file as its weights are read; the same
+// event announces when it is ready or failed. Completions go through
+// `chat_proxy` against the server's OpenAI-compatible endpoint, so they
+// stream as the usual `llm-*` events and are cancelled the usual way.
+//
+// Only builds with the `local-llm` feature accept these commands.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as StdCommand, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::chat_proxy::{ProviderApi, ProviderConfig};
+
+/// Whether this build accepts the commands
+const ENABLED: bool = cfg!(feature = "local-llm");
+const DEFAULT_CONTEXT_SIZE: u32 = 4096;
+const MAX_CONTEXT_SIZE: u32 = 1 << 20;
+/// Offloads every layer to the GPU, where llama.cpp was built with one
+const ALL_GPU_LAYERS: u32 = 999;
+const LOAD_TIMEOUT: Duration = Duration::from_secs(600);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Server output kept for the error when loading fails
+const LOG_LINES: usize = 20;
+
+/// The loaded model, managed as app state.
+#[derive(Default)]
+pub struct LocalLlm {
+    server: Mutex<Option<Server>>,
+    next_load: AtomicU64,
+}
+
+struct Server {
+    /// Tells loads apart, so a load notices it was replaced
+    load: u64,
+    child: Child,
+    model_path: PathBuf,
+    port: u16,
+    context_size: u32,
+    gpu_layers: u32,
+    file_bytes: u64,
+    started: Instant,
+    /// Time it took to load, once the server answers
+    loaded_in: Option<Duration>,
+    log: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalModelState {
+    Loading,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModelStatus {
+    pub model_path: String,
+    /// File name without `.gguf`
+    pub name: String,
+    pub state: LocalModelState,
+    /// OpenAI-compatible API of the server, e.g. `http://127.0.0.1:49152/v1`
+    pub base_url: String,
+    pub context_size: u32,
+    pub gpu_layers: u32,
+    pub file_bytes: u64,
+    /// Resident memory of the server process; `None` if it can't be read
+    pub memory_bytes: Option<u64>,
+    pub elapsed_ms: u64,
+    pub loaded_in_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Whether local inference can run, for the settings.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalRuntime {
+    /// Built with the `local-llm` feature
+    pub enabled: bool,
+    /// The `llama-server` that will be started
+    pub server_path: Option<String>,
+    /// Why `llama-server` can't be used
+    pub error: Option<String>,
+}
+
+fn model_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn memory_bytes(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    system.process(pid).map(|process| process.memory())
+}
+
+impl Server {
+    fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}/v1", self.port)
+    }
+
+    fn status(&mut self, state: LocalModelState, error: Option<String>) -> LocalModelStatus {
+        LocalModelStatus {
+            model_path: self.model_path.to_string_lossy().into_owned(),
+            name: model_name(&self.model_path),
+            state,
+            base_url: self.base_url(),
+            context_size: self.context_size,
+            gpu_layers: self.gpu_layers,
+            file_bytes: self.file_bytes,
+            memory_bytes: memory_bytes(self.child.id()),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            loaded_in_ms: self.loaded_in.map(|d| d.as_millis() as u64),
+            error,
+        }
+    }
+
+    /// The last lines the server wrote, for an error message.
+    fn last_output(&self) -> String {
+        self.log
+            .lock()
+            .map(|log| log.iter().cloned().collect::<Vec<_>>().join("\n"))
+            .unwrap_or_default()
+    }
+}
+
+fn ensure_enabled() -> Result<(), String> {
+    if ENABLED {
+        Ok(())
+    } else {
+        Err("Local inference needs a build with the local-llm feature".to_string())
+    }
+}
+
+fn emit(app: &AppHandle, status: &LocalModelStatus) {
+    if let Err(e) = app.emit("local-llm-load", status) {
+        log::warn!("Failed to emit local-llm-load event: {e}");
+    }
+}
+
+/// `LLAMA_SERVER`, or `llama-server` on the login shell's PATH.
+fn find_server() -> Result<PathBuf, String> {
+    if let Ok(path) = std::env::var("LLAMA_SERVER") {
+        let path = PathBuf::from(path);
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!(
+                "LLAMA_SERVER points to {}, which does not exist",
+                path.display()
+            ))
+        };
+    }
+    let binary = if cfg!(windows) {
+        "llama-server.exe"
+    } else {
+        "llama-server"
+    };
+    let path = crate::mcp::login_shell_path(&crate::mcp::login_shell());
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            format!(
+                "{binary} was not found on the PATH. Nexus runs local models with \
+                 llama.cpp's server, which is installed separately: install \
+                 llama.cpp (https://github.com/ggml-org/llama.cpp) or set \
+                 LLAMA_SERVER to the path of {binary}"
+            )
+        })
+}
+
+fn validate_model_path(path: &str) -> Result<(PathBuf, u64), String> {
+    crate::validate_string_input(path, 4096, "Model path")?;
+    let path = PathBuf::from(path);
+    let is_gguf = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"));
+    if !is_gguf {
+        return Err("Choose a .gguf model file".to_string());
+    }
+    let metadata =
+        std::fs::metadata(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    Ok((path, metadata.len()))
+}
+
+/// A port that was free a moment ago.
+fn free_port() -> Result<u16, String> {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find a free port: {e}"))
+}
+
+/// Keeps the last `LOG_LINES` lines the server writes.
+fn capture_output(child: &mut Child) -> Arc<Mutex<VecDeque<String>>> {
+    let log = Arc::new(Mutex::new(VecDeque::with_capacity(LOG_LINES)));
+    if let Some(stderr) = child.stderr.take() {
+        let log = log.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                log::debug!("llama-server: {line}");
+                if let Ok(mut log) = log.lock() {
+                    if log.len() == LOG_LINES {
+                        log.pop_front();
+                    }
+                    log.push_back(line);
+                }
+            }
+        });
+    }
+    log
+}
+
+/// What became of a load after one more look at the server.
+enum Progress {
+    Loading(LocalModelStatus),
+    Ready(LocalModelStatus),
+    Failed(String),
+}
+
+fn check_load(app: &AppHandle, load: u64, healthy: bool) -> Progress {
+    let state = app.state::<LocalLlm>();
+    let Ok(mut server) = state.server.lock() else {
+        return Progress::Failed("Local model state is unavailable".to_string());
+    };
+    let Some(current) = server.as_mut().filter(|s| s.load == load) else {
+        return Progress::Failed("Loading was cancelled".to_string());
+    };
+    if let Ok(Some(exit)) = current.child.try_wait() {
+        let error = format!(
+            "llama-server exited while loading ({exit}):\n{}",
+            current.last_output()
+        );
+        *server = None;
+        return Progress::Failed(error);
+    }
+    if healthy {
+        current.loaded_in = Some(current.started.elapsed());
+        return Progress::Ready(current.status(LocalModelState::Ready, None));
+    }
+    if current.started.elapsed() > LOAD_TIMEOUT {
+        *server = None;
+        return Progress::Failed(format!(
+            "The model did not load within {} minutes",
+            LOAD_TIMEOUT.as_secs() / 60
+        ));
+    }
+    Progress::Loading(current.status(LocalModelState::Loading, None))
+}
+
+/// Loads a GGUF model file in a new llama.cpp server, replacing the model
+/// loaded before. `gpu_layers` defaults to all layers, `context_size` to
+/// 4096 tokens. Resolves once the model is ready for completions.
+#[tauri::command]
+pub async fn load_local_model(
+    app: AppHandle,
+    path: String,
+    context_size: Option<u32>,
+    gpu_layers: Option<u32>,
+) -> Result<LocalModelStatus, String> {
+    ensure_enabled()?;
+    let (model_path, file_bytes) = validate_model_path(&path)?;
+    let context_size = context_size.unwrap_or(DEFAULT_CONTEXT_SIZE);
+    if context_size == 0 || context_size > MAX_CONTEXT_SIZE {
+        return Err(format!(
+            "Context size must be between 1 and {MAX_CONTEXT_SIZE} tokens"
+        ));
+    }
+    let gpu_layers = gpu_layers.unwrap_or(ALL_GPU_LAYERS);
+    let binary = find_server()?;
+    let port = free_port()?;
+
+    let state = app.state::<LocalLlm>();
+    let load = state.next_load.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut server = state.server.lock().map_err(|e| e.to_string())?;
+        // Frees the memory of the previous model before loading the next
+        *server = None;
+        let mut child = StdCommand::new(&binary)
+            .arg("--model")
+            .arg(&model_path)
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .args(["--ctx-size", &context_size.to_string()])
+            .args(["--n-gpu-layers", &gpu_layers.to_string()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                format!(
+                    "Failed to start llama-server at {}: {e}. Check that it is \
+                     a llama.cpp build for this machine",
+                    binary.display()
+                )
+            })?;
+        let log = capture_output(&mut child);
+        *server = Some(Server {
+            load,
+            child,
+            model_path: model_path.clone(),
+            port,
+            context_size,
+            gpu_layers,
+            file_bytes,
+            started: Instant::now(),
+            loaded_in: None,
+            log,
+        });
+    }
+    log::info!(
+        "Loading local model {} on port {port}",
+        model_path.display()
+    );
+
+    // Answers 503 while the weights are loading
+    let health = format!("http://127.0.0.1:{port}/health");
+    let client = crate::network::client_builder()
+        .no_proxy()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    loop {
+        let healthy = client
+            .get(&health)
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success());
+        match check_load(&app, load, healthy) {
+            Progress::Loading(status) => emit(&app, &status),
+            Progress::Ready(status) => {
+                log::info!(
+                    "Loaded local model {} in {}ms",
+                    status.name,
+                    status.loaded_in_ms.unwrap_or_default()
+                );
+                emit(&app, &status);
+                return Ok(status);
+            }
+            Progress::Failed(error) => {
+                log::error!(
+                    "Failed to load local model {}: {error}",
+                    model_path.display()
+                );
+                emit(
+                    &app,
+                    &LocalModelStatus {
+                        model_path: model_path.to_string_lossy().into_owned(),
+                        name: model_name(&model_path),
+                        state: LocalModelState::Failed,
+                        base_url: format!("http://127.0.0.1:{port}/v1"),
+                        context_size,
+                        gpu_layers,
+                        file_bytes,
+                        memory_bytes: None,
+                        elapsed_ms: 0,
+                        loaded_in_ms: None,
+                        error: Some(error.clone()),
+                    },
+                );
+                return Err(error);
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Whether this build runs local models and where `llama-server` is.
+#[tauri::command]
+pub async fn local_llm_runtime() -> Result<LocalRuntime, String> {
+    if let Err(error) = ensure_enabled() {
+        return Ok(LocalRuntime {
+            enabled: false,
+            server_path: None,
+            error: Some(error),
+        });
+    }
+    let server = tauri::async_runtime::spawn_blocking(find_server)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(match server {
+        Ok(path) => LocalRuntime {
+            enabled: true,
+            server_path: Some(path.display().to_string()),
+            error: None,
+        },
+        Err(error) => LocalRuntime {
+            enabled: true,
+            server_path: None,
+            error: Some(error),
+        },
+    })
+}
+
+/// The loaded or loading model, with its current memory use.
+#[tauri::command]
+pub async fn local_model_status(app: AppHandle) -> Result<Option<LocalModelStatus>, String> {
+    let state = app.state::<LocalLlm>();
+    let mut server = state.server.lock().map_err(|e| e.to_string())?;
+    Ok(server.as_mut().map(|server| {
+        let state = if server.loaded_in.is_some() {
+            LocalModelState::Ready
+        } else {
+            LocalModelState::Loading
+        };
+        server.status(state, None)
+    }))
+}
+
+/// Stops the model server, freeing its memory. Returns false if no model
+/// was loaded.
+#[tauri::command]
+pub async fn unload_local_model(app: AppHandle) -> Result<bool, String> {
+    let state = app.state::<LocalLlm>();
+    let server = state.server.lock().map_err(|e| e.to_string())?.take();
+    if let Some(server) = &server {
+        log::info!("Unloading local model {}", server.model_path.display());
+    }
+    Ok(server.is_some())
+}
+
+/// Streams a chat completion from the loaded model, like
+/// `stream_chat_completion`. `request` is an OpenAI chat completions body;
+/// `model` may be left out.
+#[tauri::command]
+pub async fn local_chat_completion(
+    app: AppHandle,
+    request_id: String,
+    mut request: Value,
+) -> Result<(), String> {
+    ensure_enabled()?;
+    let (base_url, name) = {
+        let state = app.state::<LocalLlm>();
+        let server = state.server.lock().map_err(|e| e.to_string())?;
+        match server.as_ref() {
+            Some(server) if server.loaded_in.is_some() => {
+                (server.base_url(), model_name(&server.model_path))
+            }
+            Some(_) => return Err("The local model is still loading".to_string()),
+            None => return Err("Load a local model first".to_string()),
+        }
+    };
+    if let Some(body) = request.as_object_mut() {
+        body.entry("model").or_insert(Value::String(name));
+    }
+    let config = ProviderConfig {
+        api: ProviderApi::Openai,
+        base_url,
+        api_key: None,
+        headers: HashMap::new(),
+    };
+    crate::chat_proxy::stream_chat_completion(app, request_id, Some(config), None, request).await
+}
+
+/// Stops the model server. Called when the app exits so it doesn't outlive
+/// it.
+pub fn shutdown(app: &AppHandle) {
+    let state = app.state::<LocalLlm>();
+    let server = state
+        .server
+        .lock()
+        .ok()
+        .and_then(|mut server| server.take());
+    if let Some(server) = server {
+        log::info!(
+            "Stopping local model {} on exit",
+            server.model_path.display()
+        );
+    }
+}
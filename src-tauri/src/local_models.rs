@@ -0,0 +1,455 @@
+// Local Model Files
+// =================
+//
+// GGUF model files for local inference (see `local_llm`), kept in the
+// `models` directory at the top of the app data directory. The directory
+// is shared by all profiles, since models are large and hold no personal
+// data.
+//
+// `download_model` fetches a file from a URL or from a Hugging Face
+// repository. Data goes to `<file>.part` first and the file only takes its
+// real name once it is complete and verified, so `list_local_models` never
+// shows a half-written model. A download that is cancelled or interrupted
+// resumes from where it stopped the next time, if the server supports
+// ranges, and so does one broken off while background work is paused for
+// power (see `power`), e.g. on battery. The SHA-256 given, or the one
+// Hugging Face publishes for the file, is checked before the file is kept.
+// Downloads run as operations (see `operations`), which carry their
+// progress and cancellation. Embedding models (see `embedding_runtime`)
+// are downloaded the same way, into `models/embeddings`.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use sysinfo::Disks;
+use tauri::{AppHandle, Manager};
+
+use crate::operations::{self, Operation, OperationKind, ProgressUnit};
+
+const HUGGING_FACE: &str = "https://huggingface.co";
+const PARTIAL_SUFFIX: &str = ".part";
+/// Free space left over after a download, so it can't fill the disk
+const DISK_HEADROOM_BYTES: u64 = 512 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Paths being downloaded to, managed as app state.
+#[derive(Default)]
+pub struct ModelDownloads {
+    active: Mutex<HashSet<String>>,
+}
+
+/// Releases a download target when its download ends.
+struct Claim<'a> {
+    downloads: &'a ModelDownloads,
+    key: String,
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.downloads.active.lock() {
+            active.remove(&self.key);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModelFile {
+    pub file_name: String,
+    /// File name without `.gguf`
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Milliseconds since the UNIX epoch
+    pub modified_at: u64,
+    /// False for a download that stopped before it was done
+    pub complete: bool,
+}
+
+/// Directory model files are kept in, created if needed.
+pub fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = crate::profiles::base_dir(app)?.join("models");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create models directory: {e}"))?;
+    Ok(dir)
+}
+
+fn validate_file_name(file_name: &str) -> Result<(), String> {
+    crate::validate_string_input(file_name, 255, "File name")?;
+    let valid = !file_name.starts_with('.')
+        && !file_name.contains(['/', '\\', ':'])
+        && file_name.to_ascii_lowercase().ends_with(".gguf");
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid model file name {file_name}: use a plain name ending in .gguf"
+        ))
+    }
+}
+
+pub(crate) fn validate_hf_repo(repo: &str) -> Result<(), String> {
+    let valid = repo.split('/').count() == 2
+        && repo.split('/').all(|part| {
+            !part.is_empty()
+                && !part.starts_with('.')
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid Hugging Face repository {repo}: use owner/name"
+        ))
+    }
+}
+
+fn gigabytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / 1_000_000_000.0)
+}
+
+/// Free space on the disk holding `dir`, if it can be told.
+fn available_space(dir: &Path) -> Option<u64> {
+    let dir = dir.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// The download URL of a file in a Hugging Face repository, and the
+/// SHA-256 and size Hugging Face publishes for it, if it does.
+pub(crate) async fn resolve_hf(
+    repo: &str,
+    path: &str,
+) -> Result<(String, Option<String>, Option<u64>), String> {
+    let url = format!("{HUGGING_FACE}/{repo}/resolve/main/{path}");
+    // The checksum is on the response that redirects to the storage
+    let client = crate::network::client_builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(CONNECT_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    let response = client
+        .head(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Hugging Face: {e}"))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(format!("{path} was not found in {repo} on Hugging Face"));
+    }
+    if !status.is_success() && !status.is_redirection() {
+        return Err(format!("Hugging Face returned {status} for {path}"));
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim_matches('"').to_string())
+    };
+    // Only files stored with LFS have one, which model files are
+    let sha256 = header("x-linked-etag").filter(|etag| etag.len() == 64);
+    let size = header("x-linked-size").and_then(|size| size.parse().ok());
+    Ok((url, sha256, size))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn model_file(path: &Path, complete: bool) -> Option<LocalModelFile> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let file_name = path.file_name()?.to_string_lossy().into_owned();
+    let file_name = file_name
+        .strip_suffix(PARTIAL_SUFFIX)
+        .map(String::from)
+        .unwrap_or(file_name);
+    let name = Path::new(&file_name)
+        .file_stem()?
+        .to_string_lossy()
+        .into_owned();
+    Some(LocalModelFile {
+        name,
+        path: path.to_string_lossy().into_owned(),
+        size_bytes: metadata.len(),
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64),
+        complete,
+        file_name,
+    })
+}
+
+/// Downloads `url` into `partial`, resuming from what it already holds.
+/// Breaks off with `power::PAUSED` once background work is paused.
+async fn fetch(
+    app: &AppHandle,
+    url: &str,
+    partial: &Path,
+    expected_size: Option<u64>,
+    models_dir: &Path,
+    operation: &mut Operation,
+) -> Result<u64, String> {
+    let mut have = std::fs::metadata(partial).map_or(0, |m| m.len());
+    let client = crate::network::client_builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    let mut request = client.get(url);
+    if have > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={have}-"));
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {url}: {e}"))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && have > 0 {
+        // Everything was downloaded before
+        return Ok(have);
+    }
+    if !status.is_success() {
+        return Err(format!("Download of {url} failed with {status}"));
+    }
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if have > 0 && !resumed {
+        log::info!("Server doesn't support resuming; downloading {url} from the start");
+        have = 0;
+    }
+    let total = response
+        .content_length()
+        .map(|remaining| have + remaining)
+        .or(expected_size);
+    if let (Some(total), Some(free)) = (total, available_space(models_dir)) {
+        let needed = total.saturating_sub(have);
+        if needed + DISK_HEADROOM_BYTES > free {
+            return Err(format!(
+                "Not enough disk space: {} needed, {} free",
+                gigabytes(needed + DISK_HEADROOM_BYTES),
+                gigabytes(free)
+            ));
+        }
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(partial)
+        .map_err(|e| format!("Failed to write {}: {e}", partial.display()))?;
+    operation.progress(have, total, None);
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Download interrupted: {e}"))?
+    {
+        operation.check_cancelled()?;
+        if crate::power::paused(app).is_some() {
+            return Err(crate::power::PAUSED.to_string());
+        }
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {e}", partial.display()))?;
+        have += chunk.len() as u64;
+        operation.progress(have, total, None);
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write {}: {e}", partial.display()))?;
+    match total {
+        Some(total) if have != total => Err(format!(
+            "Download ended after {have} of {total} bytes; try again to resume"
+        )),
+        _ => Ok(have),
+    }
+}
+
+/// Checks the finished download and gives it its real name.
+async fn verify(
+    partial: PathBuf,
+    target: PathBuf,
+    size: u64,
+    sha256: Option<String>,
+    operation: &mut Operation,
+) -> Result<(), String> {
+    if let Some(expected) = sha256 {
+        operation.progress(size, Some(size), Some("Verifying".to_string()));
+        let path = partial.clone();
+        let actual = tauri::async_runtime::spawn_blocking(move || sha256_file(&path))
+            .await
+            .map_err(|e| e.to_string())??;
+        if !actual.eq_ignore_ascii_case(&expected) {
+            // Resuming would only build on the bad data
+            let _ = std::fs::remove_file(&partial);
+            return Err(format!(
+                "Checksum mismatch: expected {expected}, got {actual}; the download was discarded"
+            ));
+        }
+    } else {
+        log::warn!("No checksum to verify {} against", target.display());
+    }
+    std::fs::rename(&partial, &target)
+        .map_err(|e| format!("Failed to move the download into place: {e}"))
+}
+
+/// Downloads `url` to `target` as an operation, resuming a partial file
+/// left next to it, waiting out power pauses and checking `sha256` when
+/// given. `target` lies under `models_dir`.
+pub(crate) async fn download_file(
+    app: &AppHandle,
+    url: &str,
+    target: &Path,
+    sha256: Option<String>,
+    expected_size: Option<u64>,
+) -> Result<(), String> {
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| format!("Invalid download target {}", target.display()))?
+        .to_string_lossy()
+        .into_owned();
+    let dir = target
+        .parent()
+        .ok_or_else(|| format!("Invalid download target {}", target.display()))?;
+    let partial = dir.join(format!("{file_name}{PARTIAL_SUFFIX}"));
+
+    let downloads = app.state::<ModelDownloads>();
+    let key = target.to_string_lossy().into_owned();
+    {
+        let mut active = downloads.active.lock().map_err(|e| e.to_string())?;
+        if !active.insert(key.clone()) {
+            return Err(format!("{file_name} is already being downloaded"));
+        }
+    }
+    let _claim = Claim {
+        downloads: downloads.inner(),
+        key,
+    };
+
+    log::info!("Downloading model {file_name} from {url}");
+    let mut operation = operations::start(
+        app,
+        OperationKind::ModelDownload,
+        file_name.clone(),
+        ProgressUnit::Bytes,
+    );
+    let result = async {
+        let size = loop {
+            crate::power::wait_while_paused(app, &mut operation).await?;
+            match fetch(app, url, &partial, expected_size, dir, &mut operation).await {
+                Err(e) if e == crate::power::PAUSED => continue,
+                result => break result?,
+            }
+        };
+        verify(partial, target.to_path_buf(), size, sha256, &mut operation).await
+    }
+    .await;
+    operation.finish(result).map_err(|e| {
+        log::error!("Failed to download model {file_name}: {e}");
+        format!("Failed to download {file_name}: {e}")
+    })?;
+    log::info!("Downloaded model {file_name}");
+    Ok(())
+}
+
+/// Downloads a GGUF model from `url`, or the file `filename` from the
+/// Hugging Face repository `hf_repo` (e.g. `TheBloke/Mistral-7B-GGUF`).
+/// The file is saved under `filename`, or for Hugging Face under its last
+/// path segment. `sha256` is checked when given; for Hugging Face files it
+/// defaults to the published checksum.
+#[tauri::command]
+pub async fn download_model(
+    app: AppHandle,
+    url: Option<String>,
+    hf_repo: Option<String>,
+    filename: String,
+    sha256: Option<String>,
+) -> Result<LocalModelFile, String> {
+    crate::validate_string_input(&filename, 1000, "File name")?;
+    if let Some(sha256) = &sha256 {
+        if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("SHA-256 must be 64 hexadecimal characters".to_string());
+        }
+    }
+    let (url, file_name, sha256, expected_size) = match (url, hf_repo) {
+        (Some(url), None) => {
+            crate::validate_string_input(&url, 4096, "URL")?;
+            let parsed =
+                reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL {url}: {e}"))?;
+            if parsed.scheme() != "https" {
+                return Err("Model URLs must use https".to_string());
+            }
+            validate_file_name(&filename)?;
+            (url, filename, sha256, None)
+        }
+        (None, Some(repo)) => {
+            validate_hf_repo(&repo)?;
+            let path = filename.trim_matches('/');
+            if path.split('/').any(|part| part.is_empty() || part == "..") {
+                return Err(format!("Invalid file path {filename}"));
+            }
+            let file_name = path.rsplit('/').next().unwrap_or(path).to_string();
+            validate_file_name(&file_name)?;
+            let (url, published, size) = resolve_hf(&repo, path).await?;
+            (url, file_name, sha256.or(published), size)
+        }
+        _ => return Err("Give either a URL or a Hugging Face repository".to_string()),
+    };
+
+    let target = models_dir(&app)?.join(&file_name);
+    if target.exists() {
+        return Err(format!("{file_name} is already downloaded"));
+    }
+    download_file(&app, &url, &target, sha256, expected_size).await?;
+    model_file(&target, true).ok_or_else(|| format!("Failed to read {}", target.display()))
+}
+
+/// GGUF files in the models directory, sorted by file name, with
+/// downloads that can be resumed marked incomplete.
+#[tauri::command]
+pub async fn list_local_models(app: AppHandle) -> Result<Vec<LocalModelFile>, String> {
+    let dir = models_dir(&app)?;
+    let entries =
+        std::fs::read_dir(&dir).map_err(|e| format!("Failed to read models directory: {e}"))?;
+    let mut models: Vec<LocalModelFile> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+            if name.ends_with(".gguf") {
+                model_file(&path, true)
+            } else if name.ends_with(".gguf.part") {
+                model_file(&path, false)
+            } else {
+                None
+            }
+        })
+        .collect();
+    models.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(models)
+}
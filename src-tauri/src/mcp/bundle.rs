@@ -0,0 +1,147 @@
+// MCP Configuration Bundle
+// ========================
+//
+// Writes the configured servers to a JSON file in the `mcpServers` format
+// Claude Desktop reads (and Cursor, VS Code and most other MCP clients
+// accept), so a team can share one setup across machines and tools. Stdio
+// servers become `command`/`args`/`env` entries, docker servers are spelled
+// out as the equivalent `docker run` invocation, and HTTP servers use the
+// widely supported `url`/`headers` form. Secret values are blanked unless the
+// caller asks for them; the variable and header names are always kept.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::handoff::SessionServer;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DesktopConfig {
+    mcp_servers: BTreeMap<String, DesktopServer>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum DesktopServer {
+    Stdio {
+        command: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+    },
+    Http {
+        url: String,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+/// Values are blanked unless `include` is set.
+fn secrets(values: &HashMap<String, String>, include: bool) -> BTreeMap<String, String> {
+    values
+        .iter()
+        .map(|(k, v)| (k.clone(), if include { v.clone() } else { String::new() }))
+        .collect()
+}
+
+fn desktop_server(server: &SessionServer, include_env: bool) -> Result<DesktopServer, String> {
+    if server.transport == "http" {
+        let url = server
+            .url
+            .clone()
+            .filter(|u| !u.trim().is_empty())
+            .ok_or_else(|| format!("Server {} has no URL", server.name))?;
+        return Ok(DesktopServer::Http {
+            url,
+            headers: secrets(&server.headers, include_env),
+        });
+    }
+
+    let command = server.command.clone().unwrap_or_default();
+    let Some(docker) = &server.docker else {
+        if command.trim().is_empty() {
+            return Err(format!("Server {} has no command", server.name));
+        }
+        return Ok(DesktopServer::Stdio {
+            command,
+            args: server.args.clone(),
+            env: secrets(&server.env, include_env),
+        });
+    };
+
+    // Same invocation as `docker::build_run_command`, minus the container
+    // name, which is specific to this app
+    let mut env = secrets(&server.env, include_env);
+    env.extend(secrets(&docker.env, include_env));
+    let mut args: Vec<String> = ["run", "-i", "--rm", "--init"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    for key in env.keys() {
+        args.extend(["-e".to_string(), key.clone()]);
+    }
+    for volume in &docker.volumes {
+        args.extend(["-v".to_string(), volume.clone()]);
+    }
+    args.push(docker.image.clone());
+    if !command.is_empty() {
+        args.push(command);
+    }
+    args.extend(server.args.iter().cloned());
+
+    Ok(DesktopServer::Stdio {
+        command: "docker".to_string(),
+        args,
+        env,
+    })
+}
+
+/// Key for a server in `mcpServers`: its name, made unique with a numeric
+/// suffix when two servers share one.
+fn unique_key(name: &str, used: &mut HashSet<String>) -> String {
+    let base = match name.trim() {
+        "" => "server",
+        name => name,
+    };
+    let mut key = base.to_string();
+    let mut n = 2;
+    while !used.insert(key.clone()) {
+        key = format!("{base} ({n})");
+        n += 1;
+    }
+    key
+}
+
+/// Writes `servers` to `path` as a Claude Desktop style `mcpServers` file.
+/// Env values and HTTP headers are only written when `include_env` is set.
+/// Returns the number of servers written.
+#[tauri::command]
+pub async fn export_mcp_configs(
+    servers: Vec<SessionServer>,
+    path: String,
+    include_env: bool,
+) -> Result<usize, String> {
+    let mut used = HashSet::new();
+    let mut mcp_servers = BTreeMap::new();
+    for server in &servers {
+        let entry = desktop_server(server, include_env)?;
+        mcp_servers.insert(unique_key(&server.name, &mut used), entry);
+    }
+
+    let json = serde_json::to_vec_pretty(&DesktopConfig { mcp_servers })
+        .map_err(|e| format!("Failed to serialize MCP configs: {e}"))?;
+    crate::export::write_atomic(Path::new(&path), &json)?;
+
+    log::info!(
+        "Exported {} MCP server configs to {path} ({})",
+        servers.len(),
+        if include_env {
+            "with secrets"
+        } else {
+            "secrets redacted"
+        }
+    );
+    Ok(servers.len())
+}
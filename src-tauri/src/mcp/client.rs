@@ -0,0 +1,175 @@
+// Backend MCP Requests
+// ====================
+//
+// Lets the backend send JSON-RPC requests to a running server, e.g. to call
+// tools for the agent loop (see `agent`), over the session the frontend
+// opened and initialized. Requests are written through `write_mcp_stdin`,
+// so fault injection, the traffic inspector and the audit log see them like
+// any other. Their ids are strings starting with `nexus-backend-`, which
+// can't collide with the frontend's numeric ids; responses carrying them
+// are taken out of the server's output before it is forwarded to the
+// webview.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use super::McpProcesses;
+use crate::audit::McpAuditLog;
+
+const ID_PREFIX: &str = "nexus-backend-";
+/// Pages of `tools/list` followed before giving up
+const MAX_TOOL_PAGES: usize = 20;
+
+/// Requests waiting for their response, managed as app state.
+#[derive(Default)]
+pub struct McpBackendRequests {
+    pending: Mutex<HashMap<String, oneshot::Sender<Value>>>,
+    next_id: AtomicU64,
+}
+
+impl McpBackendRequests {
+    /// Hands a response to the backend request waiting for it. Returns
+    /// true if the line was one, so it isn't forwarded to the webview.
+    pub fn take_response(&self, data: &str) -> bool {
+        if !data.contains(ID_PREFIX) {
+            return false;
+        }
+        let Ok(message) = serde_json::from_str::<Value>(data) else {
+            return false;
+        };
+        let Some(id) = message["id"]
+            .as_str()
+            .filter(|id| id.starts_with(ID_PREFIX))
+        else {
+            return false;
+        };
+        let waiting = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(id));
+        match waiting {
+            Some(sender) => {
+                let _ = sender.send(message);
+            }
+            None => log::debug!("Dropping response to abandoned request {id}"),
+        }
+        true
+    }
+}
+
+/// Takes a request out of the pending ones when it is answered, times out
+/// or is given up.
+struct Pending<'a> {
+    requests: &'a McpBackendRequests,
+    id: String,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.requests.pending.lock() {
+            pending.remove(&self.id);
+        }
+    }
+}
+
+/// Sends a request to a running server and waits up to `timeout` for its
+/// result. JSON-RPC errors come back as `Err` with the server's message.
+pub async fn request(
+    app: &AppHandle,
+    server_id: &str,
+    method: &str,
+    params: Value,
+    timeout: Duration,
+) -> Result<Value, String> {
+    let requests = app.state::<McpBackendRequests>();
+    let requests = requests.inner();
+    let id = format!(
+        "{ID_PREFIX}{}",
+        requests.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    );
+    let (sender, receiver) = oneshot::channel();
+    requests
+        .pending
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(id.clone(), sender);
+    let _pending = Pending {
+        requests,
+        id: id.clone(),
+    };
+
+    let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    super::write_mcp_stdin(
+        app.clone(),
+        app.state::<McpProcesses>(),
+        app.state::<McpAuditLog>(),
+        server_id.to_string(),
+        format!("{message}\n"),
+    )
+    .await?;
+
+    let response = tokio::time::timeout(timeout, receiver)
+        .await
+        .map_err(|_| {
+            format!(
+                "MCP server {server_id} did not answer {method} within {} seconds",
+                timeout.as_secs()
+            )
+        })?
+        .map_err(|_| format!("MCP server {server_id} stopped before answering {method}"))?;
+    if let Some(error) = response.get("error") {
+        let message = error["message"].as_str().unwrap_or("unknown error");
+        return Err(format!("MCP server {server_id} failed {method}: {message}"));
+    }
+    Ok(response["result"].clone())
+}
+
+/// Every tool the server offers, following pagination.
+pub async fn list_tools(
+    app: &AppHandle,
+    server_id: &str,
+    timeout: Duration,
+) -> Result<Vec<Value>, String> {
+    let mut tools = Vec::new();
+    let mut cursor: Option<String> = None;
+    for _ in 0..MAX_TOOL_PAGES {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let result = request(app, server_id, "tools/list", params, timeout).await?;
+        if let Some(page) = result["tools"].as_array() {
+            tools.extend(page.iter().cloned());
+        }
+        cursor = result["nextCursor"].as_str().map(String::from);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(tools)
+}
+
+/// Calls a tool and returns its `tools/call` result, with `content` and
+/// `isError`.
+pub async fn call_tool(
+    app: &AppHandle,
+    server_id: &str,
+    name: &str,
+    arguments: Value,
+    timeout: Duration,
+) -> Result<Value, String> {
+    request(
+        app,
+        server_id,
+        "tools/call",
+        json!({ "name": name, "arguments": arguments }),
+        timeout,
+    )
+    .await
+}
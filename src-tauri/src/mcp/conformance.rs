@@ -0,0 +1,598 @@
+// MCP Conformance Suite
+// =====================
+//
+// Runs a standard set of protocol checks against an MCP server, for people
+// developing servers with Nexus as their test client. The suite starts a
+// separate, headless instance from the running server's config, so the
+// session the frontend is using is never disturbed: it initializes, lists
+// tools, calls each tool with arguments generated from its input schema,
+// and sends malformed input the server should survive. Only tools that
+// declare `readOnlyHint` or `destructiveHint: false` are called; the others
+// are skipped and reported, since the spec assumes a tool is destructive
+// unless it says otherwise.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::process::{Child, ChildStdin};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::lines::BoundedLines;
+use super::policy::is_destructive;
+use super::{
+    launch_command, login_shell, spawn_in_shell, McpProcesses, MAX_STDERR_LINE_BYTES,
+    MAX_STDOUT_LINE_BYTES,
+};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+/// How long the server gets to answer a single request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Nesting depth after which sample arguments stop descending into schemas
+const MAX_SAMPLE_DEPTH: usize = 4;
+/// Stray stdout lines kept for the report
+const MAX_STRAY_LINES: usize = 5;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConformanceStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceCheck {
+    pub name: String,
+    pub status: ConformanceStatus,
+    /// Why the check failed or was skipped
+    pub detail: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpConformanceReport {
+    pub server_id: String,
+    /// True when no check failed
+    pub passed: bool,
+    /// `serverInfo` from the initialize response
+    pub server_info: Option<Value>,
+    pub checks: Vec<ConformanceCheck>,
+    pub duration_ms: u64,
+}
+
+/// A headless server process the suite talks to directly.
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    next_id: u64,
+    /// Non-JSON lines the server wrote to stdout
+    stray_lines: Vec<String>,
+    container: Option<String>,
+}
+
+impl Session {
+    fn send(&mut self, message: &Value) -> Result<(), String> {
+        self.send_raw(&message.to_string())
+    }
+
+    fn send_raw(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{line}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("Failed to write to server: {e}"))
+    }
+
+    /// Sends a request and waits for the response with the same id. Returns
+    /// the whole response object so checks can inspect `result` or `error`.
+    fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = json!(self.next_id);
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(remaining) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!(
+                        "No response to {method} within {REQUEST_TIMEOUT:?}"
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("Server closed stdout before answering {method}"))
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                if self.stray_lines.len() < MAX_STRAY_LINES {
+                    self.stray_lines.push(line);
+                }
+                continue;
+            };
+            // Notifications, server-to-client requests and stale responses
+            if message.get("id") == Some(&id)
+                && (message.get("result").is_some() || message.get("error").is_some())
+            {
+                return Ok(message);
+            }
+        }
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        if let Some(container) = &self.container {
+            super::docker::remove_container(container);
+        }
+    }
+}
+
+struct Checks {
+    checks: Vec<ConformanceCheck>,
+}
+
+impl Checks {
+    fn run(&mut self, name: &str, check: impl FnOnce() -> Result<(), String>) -> bool {
+        let started = Instant::now();
+        let result = check();
+        let passed = result.is_ok();
+        self.checks.push(ConformanceCheck {
+            name: name.to_string(),
+            status: if passed {
+                ConformanceStatus::Passed
+            } else {
+                ConformanceStatus::Failed
+            },
+            detail: result.err(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        passed
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.checks.push(ConformanceCheck {
+            name: name.to_string(),
+            status: ConformanceStatus::Skipped,
+            detail: Some(reason.to_string()),
+            duration_ms: 0,
+        });
+    }
+}
+
+fn result_of(response: &Value) -> Result<&Value, String> {
+    if let Some(error) = response.get("error") {
+        return Err(format!("Server returned an error: {error}"));
+    }
+    response
+        .get("result")
+        .ok_or_else(|| "Response has no result".to_string())
+}
+
+/// A tool response is acceptable if it is a JSON-RPC error or a result that
+/// reports `isError`; used for input the server should reject.
+fn is_rejection(response: &Value) -> bool {
+    response.get("error").is_some()
+        || response
+            .pointer("/result/isError")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+}
+
+/// Generates a value that satisfies `schema`, preferring values the schema
+/// itself suggests (`const`, `default`, `examples`, `enum`).
+fn sample_value(schema: &Value, depth: usize) -> Value {
+    for key in ["const", "default"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+    for key in ["examples", "enum"] {
+        if let Some(value) = schema.get(key).and_then(|v| v.get(0)) {
+            return value.clone();
+        }
+    }
+    for key in ["anyOf", "oneOf", "allOf"] {
+        if let Some(first) = schema.get(key).and_then(|v| v.get(0)) {
+            return sample_value(first, depth);
+        }
+    }
+
+    let schema_type = match schema.get("type") {
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("null"),
+        Some(Value::String(t)) => t.as_str(),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "string",
+    };
+
+    match schema_type {
+        "object" => {
+            let mut object = Map::new();
+            if depth < MAX_SAMPLE_DEPTH {
+                let required: Vec<&str> = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|r| r.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        if required.contains(&name.as_str()) {
+                            object.insert(name.clone(), sample_value(property, depth + 1));
+                        }
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        "array" => {
+            let min_items = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+            let item = schema.get("items").cloned().unwrap_or(json!({}));
+            Value::Array(
+                (0..min_items.min(3))
+                    .map(|_| sample_value(&item, depth + 1))
+                    .collect(),
+            )
+        }
+        "integer" => json!(schema.get("minimum").and_then(Value::as_i64).unwrap_or(1)),
+        "number" => json!(schema.get("minimum").and_then(Value::as_f64).unwrap_or(1.0)),
+        "boolean" => json!(false),
+        "null" => Value::Null,
+        _ => {
+            let text = match schema.get("format").and_then(Value::as_str) {
+                Some("uri") | Some("url") => "https://example.com".to_string(),
+                Some("email") => "user@example.com".to_string(),
+                Some("date") => chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                Some("date-time") => chrono::Utc::now().to_rfc3339(),
+                Some("uuid") => "00000000-0000-4000-8000-000000000000".to_string(),
+                _ => "example".to_string(),
+            };
+            let min_length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+            json!(format!("{text:x<min_length$}"))
+        }
+    }
+}
+
+fn has_required_arguments(tool: &Value) -> bool {
+    tool.pointer("/inputSchema/required")
+        .and_then(Value::as_array)
+        .is_some_and(|r| !r.is_empty())
+}
+
+fn run_suite(session: &mut Session, checks: &mut Checks) -> Option<Value> {
+    let mut server_info = None;
+    let mut capabilities = Value::Null;
+    let initialized = checks.run("initialize", || {
+        let response = session.request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "nexus-conformance", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )?;
+        let result = result_of(&response)?;
+        if !result.get("protocolVersion").is_some_and(Value::is_string) {
+            return Err("Result has no protocolVersion".to_string());
+        }
+        if !result
+            .pointer("/serverInfo/name")
+            .is_some_and(Value::is_string)
+        {
+            return Err("Result has no serverInfo.name".to_string());
+        }
+        capabilities = result
+            .get("capabilities")
+            .filter(|c| c.is_object())
+            .cloned()
+            .ok_or("Result has no capabilities object")?;
+        server_info = result.get("serverInfo").cloned();
+        session.send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+    });
+    if !initialized {
+        for name in ["ping", "tools/list", "unknown_method", "malformed_json"] {
+            checks.skip(name, "Initialization failed");
+        }
+        return server_info;
+    }
+
+    checks.run("ping", || {
+        result_of(&session.request("ping", json!({}))?).map(|_| ())
+    });
+
+    let mut tools = Vec::new();
+    if capabilities.get("tools").is_some() {
+        checks.run("tools/list", || {
+            let response = session.request("tools/list", json!({}))?;
+            let listed = result_of(&response)?
+                .get("tools")
+                .and_then(Value::as_array)
+                .ok_or("Result has no tools array")?;
+            for tool in listed {
+                let name = tool
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("A tool has no name")?;
+                if tool.pointer("/inputSchema/type").and_then(Value::as_str) != Some("object") {
+                    return Err(format!("Tool {name} has no object inputSchema"));
+                }
+            }
+            tools = listed.clone();
+            Ok(())
+        });
+    } else {
+        checks.skip(
+            "tools/list",
+            "Server does not advertise the tools capability",
+        );
+    }
+
+    for tool in &tools {
+        let name = tool["name"].as_str().unwrap_or_default();
+        let check_name = format!("tools/call {name}");
+        if is_destructive(tool) {
+            checks.skip(
+                &check_name,
+                "Tool is not annotated as read-only or non-destructive",
+            );
+            continue;
+        }
+        let arguments = sample_value(&tool["inputSchema"], 0);
+        checks.run(&check_name, || {
+            let response = session.request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )?;
+            let result = result_of(&response)?;
+            if !result.get("content").is_some_and(Value::is_array) {
+                return Err("Result has no content array".to_string());
+            }
+            Ok(())
+        });
+    }
+
+    if !tools.is_empty() {
+        checks.run("tools/call unknown tool", || {
+            let response = session.request(
+                "tools/call",
+                json!({ "name": "__nexus_conformance_missing_tool__", "arguments": {} }),
+            )?;
+            if is_rejection(&response) {
+                Ok(())
+            } else {
+                Err("Calling a tool that does not exist succeeded".to_string())
+            }
+        });
+
+        match tools
+            .iter()
+            .find(|t| has_required_arguments(t) && !is_destructive(t))
+        {
+            Some(tool) => {
+                checks.run("tools/call missing arguments", || {
+                    let response = session.request(
+                        "tools/call",
+                        json!({ "name": tool["name"], "arguments": {} }),
+                    )?;
+                    if is_rejection(&response) {
+                        Ok(())
+                    } else {
+                        Err(format!(
+                            "{} accepted a call without its required arguments",
+                            tool["name"]
+                        ))
+                    }
+                });
+            }
+            None => checks.skip(
+                "tools/call missing arguments",
+                "No non-destructive tool has required arguments",
+            ),
+        }
+    }
+
+    checks.run("unknown_method", || {
+        let response = session.request("nexus/conformance-unknown-method", json!({}))?;
+        match response.pointer("/error/code").and_then(Value::as_i64) {
+            Some(METHOD_NOT_FOUND) => Ok(()),
+            Some(code) => Err(format!(
+                "Expected error code {METHOD_NOT_FOUND}, got {code}"
+            )),
+            None => Err("Unknown method did not return an error".to_string()),
+        }
+    });
+
+    checks.run("malformed_json", || {
+        session.send_raw("{\"jsonrpc\": \"2.0\", \"id\": ")?;
+        if !session.is_alive() {
+            return Err("Server exited after malformed input".to_string());
+        }
+        result_of(&session.request("ping", json!({}))?)
+            .map(|_| ())
+            .map_err(|e| format!("Server stopped responding after malformed input: {e}"))
+    });
+
+    server_info
+}
+
+/// Runs the conformance suite against a fresh instance of a running server.
+#[tauri::command]
+pub async fn run_mcp_conformance(
+    app: AppHandle,
+    server_id: String,
+) -> Result<McpConformanceReport, String> {
+    let config = {
+        let state = app.state::<McpProcesses>();
+        let processes = state.processes.lock().map_err(|e| e.to_string())?;
+        processes
+            .get(&server_id)
+            .map(|p| p.config.clone())
+            .ok_or_else(|| format!("MCP server {server_id} is not running"))?
+    };
+
+    log::info!("Running conformance suite against MCP server {server_id}");
+    let shell = login_shell();
+    let (full_command, container) = launch_command(&app, &shell, &config)
+        .await
+        .map_err(|e| e.message)?;
+    let isolate_env = config.isolate_env;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let mut child = spawn_in_shell(&shell, &full_command, isolate_env)?;
+        let stdin = child.stdin.take().ok_or("Failed to get stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+        let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            let lines = BoundedLines::new(stdout, MAX_STDOUT_LINE_BYTES, "conformance stdout");
+            for line in lines {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        // Drain stderr so a chatty server can't block on a full pipe
+        std::thread::spawn(move || {
+            let lines = BoundedLines::new(stderr, MAX_STDERR_LINE_BYTES, "conformance stderr");
+            for line in lines {
+                log::debug!("MCP conformance stderr: {line}");
+            }
+        });
+
+        let mut session = Session {
+            child,
+            stdin,
+            lines,
+            next_id: 0,
+            stray_lines: Vec::new(),
+            container,
+        };
+        let mut checks = Checks { checks: Vec::new() };
+        let server_info = run_suite(&mut session, &mut checks);
+
+        let stray_lines = std::mem::take(&mut session.stray_lines);
+        checks.run("stdout_is_json_rpc", || {
+            if stray_lines.is_empty() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Non-JSON output on stdout: {}",
+                    stray_lines.join(" | ")
+                ))
+            }
+        });
+        checks.run("process_alive", || {
+            if session.is_alive() {
+                Ok(())
+            } else {
+                Err("Server exited during the suite".to_string())
+            }
+        });
+        drop(session);
+
+        let passed = checks
+            .checks
+            .iter()
+            .all(|c| c.status != ConformanceStatus::Failed);
+        log::info!(
+            "Conformance suite for {server_id} {}",
+            if passed { "passed" } else { "failed" }
+        );
+
+        Ok(McpConformanceReport {
+            server_id,
+            passed,
+            server_info,
+            checks: checks.checks,
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    })
+    .await
+    .map_err(|e| format!("Conformance suite failed: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_value_prefers_given_values() {
+        assert_eq!(
+            sample_value(&json!({ "const": 7, "default": 1 }), 0),
+            json!(7)
+        );
+        assert_eq!(
+            sample_value(&json!({ "type": "string", "default": "x" }), 0),
+            json!("x")
+        );
+        assert_eq!(sample_value(&json!({ "enum": ["a", "b"] }), 0), json!("a"));
+        assert_eq!(
+            sample_value(
+                &json!({ "anyOf": [{ "type": "boolean" }, { "type": "string" }] }),
+                0
+            ),
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn sample_value_fills_only_required_properties() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "minLength": 10 },
+                "limit": { "type": "integer", "minimum": 5 },
+                "tags": { "type": "array", "items": { "type": "string" }, "minItems": 9 },
+                "email": { "type": ["null", "string"], "format": "email" },
+                "verbose": { "type": "boolean" }
+            },
+            "required": ["query", "limit", "tags", "email"]
+        });
+        assert_eq!(
+            sample_value(&schema, 0),
+            json!({
+                "query": "examplexxx",
+                "limit": 5,
+                "tags": ["example", "example", "example"],
+                "email": "user@example.com"
+            })
+        );
+    }
+
+    #[test]
+    fn sample_value_stops_at_max_depth() {
+        let mut schema = json!({ "type": "string" });
+        for _ in 0..MAX_SAMPLE_DEPTH + 2 {
+            schema = json!({
+                "type": "object",
+                "properties": { "inner": schema },
+                "required": ["inner"]
+            });
+        }
+        let sample = sample_value(&schema, 0);
+        let pointer = "/inner".repeat(MAX_SAMPLE_DEPTH);
+        assert_eq!(sample.pointer(&pointer), Some(&json!({})));
+    }
+}
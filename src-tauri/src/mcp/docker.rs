@@ -0,0 +1,180 @@
+// Docker Transport
+// ================
+//
+// Runs an MCP server inside a container with `docker run -i`, speaking stdio
+// through the docker CLI exactly like a local process. Containers get a
+// predictable name so they can be force-removed when the server is stopped
+// or the app quits; killing the CLI alone would leave them running.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Command as StdCommand, Stdio};
+use tauri::{AppHandle, Emitter};
+
+use super::{login_shell, run_in_login_shell, shell_quote};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpDockerConfig {
+    pub image: String,
+    /// Volume mounts in `docker run -v` syntax, e.g. `/host/path:/data:ro`
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    /// Environment variables passed into the container
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpDockerPullEvent {
+    pub server_id: String,
+    pub image: String,
+    /// A progress line from `docker pull`
+    pub status: String,
+    pub done: bool,
+}
+
+/// Container name used for a server, unique per spawn.
+pub fn container_name(server_id: &str) -> String {
+    let sanitized: String = server_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("nexus-mcp-{sanitized}-{nonce}")
+}
+
+/// Fails with a helpful message when the docker CLI or daemon is unavailable.
+pub fn ensure_docker_available(shell: &str) -> Result<(), String> {
+    let found = run_in_login_shell(shell, "command -v docker")
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !found {
+        return Err(
+            "Docker is not installed or not on your PATH. Install Docker Desktop to use container-based MCP servers."
+                .to_string(),
+        );
+    }
+
+    let daemon_running = run_in_login_shell(shell, "docker info --format '{{.ServerVersion}}'")
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !daemon_running {
+        return Err("The Docker daemon is not running. Start Docker and try again.".to_string());
+    }
+
+    Ok(())
+}
+
+/// Pulls the image if it is not available locally, emitting `mcp-docker-pull`
+/// events for each progress line.
+pub fn ensure_image(
+    app: &AppHandle,
+    shell: &str,
+    server_id: &str,
+    image: &str,
+) -> Result<(), String> {
+    let present = run_in_login_shell(
+        shell,
+        &format!("docker image inspect {}", shell_quote(image)),
+    )
+    .map(|o| o.status.success())
+    .unwrap_or(false);
+    if present {
+        return Ok(());
+    }
+
+    log::info!("Pulling docker image {image} for MCP server {server_id}");
+    let mut child = StdCommand::new(shell)
+        .args([
+            "-l",
+            "-c",
+            &format!("docker pull {} 2>&1", shell_quote(image)),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run docker pull: {e}"))?;
+
+    let emit = |status: String, done: bool| {
+        let _ = app.emit(
+            "mcp-docker-pull",
+            McpDockerPullEvent {
+                server_id: server_id.to_string(),
+                image: image.to_string(),
+                status,
+                done,
+            },
+        );
+    };
+
+    let mut last_line = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            log::debug!("docker pull {image}: {line}");
+            emit(line.clone(), false);
+            last_line = line;
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for docker pull: {e}"))?;
+    emit(last_line.clone(), true);
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to pull docker image {image}: {last_line}"))
+    }
+}
+
+/// Builds the `docker run` command line. Environment values are exported by
+/// the caller and only referenced by name here, so secrets never show up in
+/// the process list.
+pub fn build_run_command(
+    docker: &McpDockerConfig,
+    container: &str,
+    env_keys: &[&String],
+    command: &str,
+    args: &[String],
+) -> String {
+    let mut parts = vec![
+        "docker run -i --rm --init".to_string(),
+        format!("--name {}", shell_quote(container)),
+    ];
+    parts.extend(env_keys.iter().map(|k| format!("-e {}", shell_quote(k))));
+    parts.extend(
+        docker
+            .volumes
+            .iter()
+            .map(|v| format!("-v {}", shell_quote(v))),
+    );
+    parts.push(shell_quote(&docker.image));
+    if !command.is_empty() {
+        parts.push(command.to_string());
+    }
+    parts.extend(args.iter().cloned());
+    parts.join(" ")
+}
+
+/// Force-removes a container, ignoring containers that are already gone.
+pub fn remove_container(container: &str) {
+    match run_in_login_shell(
+        &login_shell(),
+        &format!("docker rm -f {} >/dev/null 2>&1", shell_quote(container)),
+    ) {
+        Ok(_) => log::info!("Removed docker container {container}"),
+        Err(e) => log::warn!("Failed to remove docker container {container}: {e}"),
+    }
+}
@@ -0,0 +1,255 @@
+// Elevated Privilege Detection
+// ============================
+//
+// A server config copied from a README can quietly run with more privilege
+// than the app: through `sudo` and friends, a setuid binary, a privileged
+// port (which needs root on Linux) or a mounted Docker socket (root on the
+// host in all but name). Such configs only start once the user has set
+// `allow_elevated` on them. Either way an `mcp-elevation-warning` event lists
+// what was found, so the UI can explain the refusal or flag the server.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::queue::{McpSpawnError, McpSpawnErrorKind};
+use super::{login_shell_path, McpServerConfig};
+
+/// Programs that run their arguments with elevated privileges
+const ELEVATION_WRAPPERS: &[&str] = &["sudo", "doas", "pkexec", "su", "run0", "gsudo", "runas"];
+/// Ports below this need root on Linux
+const PRIVILEGED_PORT_LIMIT: u16 = 1024;
+/// Flags whose value is a port, or a `host:port` address
+const PORT_FLAGS: &[&str] = &[
+    "--port",
+    "-p",
+    "--http-port",
+    "--listen-port",
+    "--listen",
+    "--bind",
+    "--addr",
+    "--address",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationKind {
+    /// Runs through sudo, doas, pkexec or similar
+    Wrapper,
+    /// The executable has the setuid or setgid bit
+    Setuid,
+    /// Listens on a port below 1024
+    PrivilegedPort,
+    /// The container gets the host's Docker socket
+    DockerSocket,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ElevationReason {
+    pub kind: ElevationKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpElevationEvent {
+    pub server_id: String,
+    pub reasons: Vec<ElevationReason>,
+    /// The config has `allow_elevated` set and the server was started anyway
+    pub allowed: bool,
+}
+
+/// Words of the command line in command position: the first word and the
+/// first after each `;`, `&&`, `||` or `|`, skipping `VAR=value` prefixes.
+fn programs(command_line: &str) -> Vec<&str> {
+    let mut programs = Vec::new();
+    let mut expect_program = true;
+    for word in command_line.split_whitespace() {
+        if matches!(word, ";" | "&&" | "||" | "|") {
+            expect_program = true;
+            continue;
+        }
+        let assignment = word.contains('=') && !word.starts_with('=');
+        if expect_program && !assignment {
+            programs.push(word.trim_matches(|c| c == '"' || c == '\''));
+            expect_program = false;
+        }
+        if word.ends_with(';') || word.ends_with("&&") || word.ends_with('|') {
+            expect_program = true;
+        }
+    }
+    programs
+}
+
+fn file_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(program)
+}
+
+/// Resolves a program name against `path` like the shell would.
+fn resolve(program: &str, path: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Some(PathBuf::from(program));
+    }
+    std::env::split_paths(path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(unix)]
+fn is_setuid(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o6000 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_setuid(_path: &Path) -> bool {
+    false
+}
+
+/// The port in a `N`, `:N` or `host:N` value.
+fn parse_port(value: &str) -> Option<u16> {
+    value
+        .rsplit(':')
+        .next()
+        .and_then(|port| port.trim_matches(|c| c == '"' || c == '\'').parse().ok())
+}
+
+/// Privileged ports named in flags (`--port 80`, `--bind=0.0.0.0:443`) or in
+/// `PORT`-like environment variables.
+fn privileged_ports(config: &McpServerConfig) -> Vec<u16> {
+    let words: Vec<&str> = config
+        .command
+        .split_whitespace()
+        .chain(config.args.iter().flat_map(|a| a.split_whitespace()))
+        .collect();
+
+    let mut values: Vec<&str> = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        match word.split_once('=') {
+            Some((flag, value)) if PORT_FLAGS.contains(&flag) => values.push(value),
+            _ if PORT_FLAGS.contains(word) => values.extend(words.get(i + 1)),
+            _ => {}
+        }
+    }
+    let env = config.env.iter().flatten();
+    values.extend(
+        env.filter(|(key, _)| {
+            let key = key.to_ascii_uppercase();
+            key == "PORT" || key.ends_with("_PORT")
+        })
+        .map(|(_, value)| value.as_str()),
+    );
+
+    values
+        .into_iter()
+        .filter_map(parse_port)
+        .filter(|port| (1..PRIVILEGED_PORT_LIMIT).contains(port))
+        .collect()
+}
+
+/// Everything about a config that suggests it runs with elevated privileges.
+fn detect(config: &McpServerConfig, shell: &str) -> Vec<ElevationReason> {
+    let mut reasons = Vec::new();
+    let command_line = std::iter::once(config.command.as_str())
+        .chain(config.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let programs = if config.docker.is_some() {
+        // The command runs inside the container
+        Vec::new()
+    } else {
+        programs(&command_line)
+    };
+    let (wrappers, programs): (Vec<&str>, Vec<&str>) = programs
+        .into_iter()
+        .partition(|program| ELEVATION_WRAPPERS.contains(&file_name(program)));
+    for wrapper in wrappers {
+        reasons.push(ElevationReason {
+            kind: ElevationKind::Wrapper,
+            detail: format!("Runs through {}", file_name(wrapper)),
+        });
+    }
+    // Wrappers are setuid themselves, so only the other programs are checked
+    if cfg!(unix) && !programs.is_empty() {
+        let path = login_shell_path(shell);
+        for program in &programs {
+            if resolve(program, &path).is_some_and(|p| is_setuid(&p)) {
+                reasons.push(ElevationReason {
+                    kind: ElevationKind::Setuid,
+                    detail: format!("{program} is a setuid or setgid executable"),
+                });
+            }
+        }
+    }
+
+    // macOS and Windows let any user bind low ports
+    if cfg!(target_os = "linux") {
+        for port in privileged_ports(config) {
+            reasons.push(ElevationReason {
+                kind: ElevationKind::PrivilegedPort,
+                detail: format!("Listens on privileged port {port}"),
+            });
+        }
+    }
+
+    if let Some(docker) = &config.docker {
+        for volume in &docker.volumes {
+            let host = volume.split(':').next().unwrap_or_default();
+            if host.ends_with("docker.sock") || host.contains("docker_engine") {
+                reasons.push(ElevationReason {
+                    kind: ElevationKind::DockerSocket,
+                    detail: format!("Mounts the Docker socket ({host})"),
+                });
+            }
+        }
+    }
+
+    reasons
+}
+
+/// Refuses configs that need elevated privileges unless `allow_elevated`
+/// is set, emitting `mcp-elevation-warning` whenever any are detected.
+pub fn check(app: &AppHandle, shell: &str, config: &McpServerConfig) -> Result<(), McpSpawnError> {
+    let reasons = detect(config, shell);
+    if reasons.is_empty() {
+        return Ok(());
+    }
+
+    let summary = reasons
+        .iter()
+        .map(|r| r.detail.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    let _ = app.emit(
+        "mcp-elevation-warning",
+        McpElevationEvent {
+            server_id: config.id.clone(),
+            reasons,
+            allowed: config.allow_elevated,
+        },
+    );
+
+    if config.allow_elevated {
+        log::warn!(
+            "Starting MCP server {} with elevated privileges: {summary}",
+            config.id
+        );
+        return Ok(());
+    }
+
+    log::warn!(
+        "Refusing to start MCP server {} without allow_elevated: {summary}",
+        config.id
+    );
+    Err(McpSpawnError {
+        kind: McpSpawnErrorKind::ElevationRequired,
+        message: format!(
+            "MCP server {} needs elevated privileges ({summary}). Allow elevated privileges in its settings to run it anyway.",
+            config.id
+        ),
+    })
+}
@@ -0,0 +1,220 @@
+// MCP Fault Injection
+// ===================
+//
+// Makes a stdio MCP server misbehave on purpose, to check that the
+// supervisor, timeouts and retries hold up before agents are trusted with
+// real work. Per server, a share of the messages in either direction can be
+// delayed, dropped or corrupted (cut off at a random point, so they are no
+// longer valid JSON). Faults apply to messages after Nexus writes them and
+// before it reads them, so the traffic inspector and the audit log see
+// what actually went through.
+//
+// Inbound delays hold up the server's later output as well, like a slow
+// server would; outbound ones hold up only the delayed write, so requests
+// may reach the server out of order. Faults are kept in memory only, and
+// only debug builds or builds with the `fault-injection` feature accept
+// them. Remote servers, whose transports run in the webview, are not
+// covered.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::State;
+
+use super::traffic::TrafficDirection;
+
+/// Whether this build accepts faults
+const ENABLED: bool = cfg!(any(debug_assertions, feature = "fault-injection"));
+const MAX_DELAY_MS: u64 = 120_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultDirection {
+    /// Messages written by Nexus to the server
+    Outbound,
+    /// Messages written by the server to Nexus
+    Inbound,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct FaultConfig {
+    pub direction: FaultDirection,
+    /// Share of messages delayed, 0 to 100
+    pub delay_percent: u8,
+    pub delay_ms: u64,
+    /// Share of messages dropped, 0 to 100
+    pub drop_percent: u8,
+    /// Share of messages cut off, 0 to 100
+    pub corrupt_percent: u8,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FaultCounts {
+    pub delayed: u64,
+    pub dropped: u64,
+    pub corrupted: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerFaults {
+    pub server_id: String,
+    pub config: FaultConfig,
+    /// Faults injected since the config was set
+    pub injected: FaultCounts,
+}
+
+#[derive(Default)]
+pub struct McpFaults {
+    servers: Mutex<HashMap<String, (FaultConfig, FaultCounts)>>,
+}
+
+/// Whether an event with a chance of `percent` in 100 happens.
+fn roll(percent: u8) -> bool {
+    if percent == 0 {
+        return false;
+    }
+    let mut byte = [0u8; 2];
+    getrandom::getrandom(&mut byte).is_ok() && u16::from_le_bytes(byte) % 100 < u16::from(percent)
+}
+
+/// `line` cut off somewhere in its first half, on a character boundary.
+fn corrupt(line: &str) -> String {
+    let mut bytes = [0u8; 4];
+    let _ = getrandom::getrandom(&mut bytes);
+    let mut cut = u32::from_le_bytes(bytes) as usize % (line.len() / 2).max(1);
+    while !line.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    line[..cut].to_string()
+}
+
+impl McpFaults {
+    /// Applies the server's faults to `data`, which may hold several
+    /// newline-delimited messages. Returns how long to wait before passing
+    /// it on and what is left of it; `None` when every message was dropped.
+    pub fn inject(
+        &self,
+        server_id: &str,
+        direction: TrafficDirection,
+        data: String,
+    ) -> (Duration, Option<String>) {
+        if !ENABLED {
+            return (Duration::ZERO, Some(data));
+        }
+        let Ok(mut servers) = self.servers.lock() else {
+            return (Duration::ZERO, Some(data));
+        };
+        let Some((config, counts)) = servers.get_mut(server_id) else {
+            return (Duration::ZERO, Some(data));
+        };
+        let applies = match config.direction {
+            FaultDirection::Both => true,
+            FaultDirection::Outbound => direction == TrafficDirection::Outbound,
+            FaultDirection::Inbound => direction == TrafficDirection::Inbound,
+        };
+        if !applies {
+            return (Duration::ZERO, Some(data));
+        }
+
+        let mut delay = Duration::ZERO;
+        let mut kept = String::with_capacity(data.len());
+        for message in data.split_inclusive('\n') {
+            let line = message.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() {
+                kept.push_str(message);
+                continue;
+            }
+            if roll(config.drop_percent) {
+                counts.dropped += 1;
+                log::debug!("Dropping message of MCP server {server_id}");
+                continue;
+            }
+            if roll(config.delay_percent) {
+                counts.delayed += 1;
+                delay += Duration::from_millis(config.delay_ms);
+            }
+            if roll(config.corrupt_percent) {
+                counts.corrupted += 1;
+                log::debug!("Corrupting message of MCP server {server_id}");
+                kept.push_str(&corrupt(line));
+                kept.push_str(&message[line.len()..]);
+            } else {
+                kept.push_str(message);
+            }
+        }
+        let delay = delay.min(Duration::from_millis(MAX_DELAY_MS));
+        (delay, (!kept.trim().is_empty()).then_some(kept))
+    }
+}
+
+fn validate(config: &FaultConfig) -> Result<(), String> {
+    for (name, percent) in [
+        ("Delay", config.delay_percent),
+        ("Drop", config.drop_percent),
+        ("Corrupt", config.corrupt_percent),
+    ] {
+        if percent > 100 {
+            return Err(format!("{name} percentage must be between 0 and 100"));
+        }
+    }
+    if config.delay_ms > MAX_DELAY_MS {
+        return Err(format!(
+            "Delay can be at most {} seconds",
+            MAX_DELAY_MS / 1000
+        ));
+    }
+    Ok(())
+}
+
+/// Sets the faults injected into a server's messages, or clears them with
+/// `config` null. Takes effect with the next message, also for a server
+/// that is already running.
+#[tauri::command]
+pub async fn set_mcp_faults(
+    faults: State<'_, McpFaults>,
+    server_id: String,
+    config: Option<FaultConfig>,
+) -> Result<(), String> {
+    if !ENABLED {
+        return Err(
+            "Fault injection needs a debug build or the fault-injection feature".to_string(),
+        );
+    }
+    crate::validate_string_input(&server_id, 200, "Server id")?;
+    let mut servers = faults.servers.lock().map_err(|e| e.to_string())?;
+    match config {
+        Some(config) => {
+            validate(&config)?;
+            log::warn!("Injecting faults into MCP server {server_id}: {config:?}");
+            servers.insert(server_id, (config, FaultCounts::default()));
+        }
+        None => {
+            if servers.remove(&server_id).is_some() {
+                log::info!("Stopped injecting faults into MCP server {server_id}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Servers with faults set, sorted by id, with the faults injected so far.
+#[tauri::command]
+pub async fn get_mcp_faults(faults: State<'_, McpFaults>) -> Result<Vec<ServerFaults>, String> {
+    let servers = faults.servers.lock().map_err(|e| e.to_string())?;
+    let mut list: Vec<ServerFaults> = servers
+        .iter()
+        .map(|(server_id, (config, injected))| ServerFaults {
+            server_id: server_id.clone(),
+            config: config.clone(),
+            injected: injected.clone(),
+        })
+        .collect();
+    list.sort_by(|a, b| a.server_id.cmp(&b.server_id));
+    Ok(list)
+}
@@ -0,0 +1,312 @@
+// MCP Server Installation
+// =======================
+//
+// Installs an MCP server package into an app-managed directory, so no global
+// installs (or sudo) are needed: npm packages get their own `--prefix`,
+// Python packages their own virtualenv created with pip or uv. Installer
+// output is streamed to the frontend as `mcp-install-progress` events, and
+// the resulting executable is checked before a server config is returned.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command as StdCommand, Stdio};
+use tauri::{AppHandle, Emitter};
+
+use super::{login_shell, shell_quote, McpServerConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpPackageRuntime {
+    Npm,
+    Pip,
+    Uv,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpInstallProgressEvent {
+    pub package: String,
+    /// A line of installer output
+    pub line: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpInstallResult {
+    /// Package name without a version specifier
+    pub name: String,
+    pub runtime: McpPackageRuntime,
+    pub binary_path: String,
+    /// Ready-to-start config; its id is derived from the package name
+    pub config: McpServerConfig,
+}
+
+/// Entries in a virtualenv's bin directory that belong to Python itself
+const VENV_BUILTINS: &[&str] = &["python", "pip", "activate", "deactivate", "Activate"];
+
+fn validate_package(package: &str) -> Result<(), String> {
+    crate::validate_string_input(package, 214, "Package")?;
+    let valid = !package.is_empty()
+        && !package.starts_with('-')
+        && package
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@/._-=<>~^!".contains(c));
+    if !valid {
+        return Err(format!("Invalid package name: {package}"));
+    }
+    Ok(())
+}
+
+/// Package name without its version specifier, e.g. `@scope/pkg@1.2` ->
+/// `@scope/pkg` and `pkg==1.0` -> `pkg`.
+fn package_name(package: &str, runtime: McpPackageRuntime) -> String {
+    match runtime {
+        McpPackageRuntime::Npm => match package.rfind('@') {
+            Some(at) if at > 0 => package[..at].to_string(),
+            _ => package.to_string(),
+        },
+        McpPackageRuntime::Pip | McpPackageRuntime::Uv => package
+            .split(|c| "=<>~!".contains(c))
+            .next()
+            .unwrap_or(package)
+            .to_string(),
+    }
+}
+
+/// Filesystem- and id-safe version of a package name.
+fn slug(name: &str) -> String {
+    name.trim_start_matches('@')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn get_packages_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let packages_dir = app_data_dir.join("mcp-packages");
+
+    // Ensure the packages directory exists
+    std::fs::create_dir_all(&packages_dir)
+        .map_err(|e| format!("Failed to create MCP packages directory: {e}"))?;
+
+    Ok(packages_dir)
+}
+
+fn venv_bin_dir(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    }
+}
+
+fn install_command(runtime: McpPackageRuntime, package: &str, dir: &Path) -> String {
+    let dir_str = dir.to_string_lossy();
+    let python = venv_bin_dir(dir).join("python");
+    match runtime {
+        McpPackageRuntime::Npm => format!(
+            "npm install --prefix {} --no-fund --no-audit {}",
+            shell_quote(&dir_str),
+            shell_quote(package)
+        ),
+        McpPackageRuntime::Pip => format!(
+            "python3 -m venv {} && {} -m pip install {}",
+            shell_quote(&dir_str),
+            shell_quote(&python.to_string_lossy()),
+            shell_quote(package)
+        ),
+        McpPackageRuntime::Uv => format!(
+            "uv venv {} && uv pip install --python {} {}",
+            shell_quote(&dir_str),
+            shell_quote(&python.to_string_lossy()),
+            shell_quote(package)
+        ),
+    }
+}
+
+/// Runs the installer, emitting each output line as a progress event.
+fn run_installer(app: &AppHandle, package: &str, command: &str) -> Result<(), String> {
+    log::info!("Installing MCP package {package}: {command}");
+    let mut child = StdCommand::new(login_shell())
+        .args(["-l", "-c", &format!("{command} 2>&1")])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start installer: {e}"))?;
+
+    let emit = |line: String, done: bool| {
+        let _ = app.emit(
+            "mcp-install-progress",
+            McpInstallProgressEvent {
+                package: package.to_string(),
+                line,
+                done,
+            },
+        );
+    };
+
+    let mut last_line = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            log::debug!("install {package}: {line}");
+            emit(line.clone(), false);
+            last_line = line;
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for installer: {e}"))?;
+    emit(last_line.clone(), true);
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to install {package}: {last_line}"))
+    }
+}
+
+/// Executable declared in an installed npm package's `bin` field.
+fn npm_binary(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let manifest =
+        std::fs::read_to_string(dir.join("node_modules").join(name).join("package.json"))
+            .map_err(|e| format!("Failed to read package.json for {name}: {e}"))?;
+    let manifest: Value = serde_json::from_str(&manifest)
+        .map_err(|e| format!("Failed to parse package.json for {name}: {e}"))?;
+
+    let bin_name = match &manifest["bin"] {
+        // A string `bin` is installed under the unscoped package name
+        Value::String(_) => name.rsplit('/').next().unwrap_or(name).to_string(),
+        Value::Object(bins) => bins
+            .keys()
+            .next()
+            .cloned()
+            .ok_or_else(|| format!("{name} does not declare an executable"))?,
+        _ => return Err(format!("{name} does not declare an executable")),
+    };
+
+    Ok(dir.join("node_modules").join(".bin").join(bin_name))
+}
+
+/// Console script installed into a virtualenv, preferring one named after
+/// the package.
+fn python_binary(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let bin_dir = venv_bin_dir(dir);
+    let scripts: Vec<String> = std::fs::read_dir(&bin_dir)
+        .map_err(|e| format!("Failed to read {bin_dir:?}: {e}"))?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|f| !VENV_BUILTINS.iter().any(|b| f.starts_with(b)))
+        .collect();
+
+    let wanted = name.to_lowercase().replace('_', "-");
+    let script = scripts
+        .iter()
+        .find(|s| s.trim_end_matches(".exe").to_lowercase().replace('_', "-") == wanted)
+        .or_else(|| (scripts.len() == 1).then(|| &scripts[0]))
+        .ok_or_else(|| {
+            format!(
+                "Could not tell which executable {name} provides (found: {})",
+                scripts.join(", ")
+            )
+        })?;
+
+    Ok(bin_dir.join(script))
+}
+
+fn verify_binary(path: &Path) -> Result<(), String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|_| format!("Installed executable missing: {path:?}"))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("Installed file is not executable: {path:?}"));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    Ok(())
+}
+
+/// Installs an MCP server package and returns a config that launches it.
+/// `binary` picks the executable when a package installs several.
+#[tauri::command]
+pub async fn install_mcp_server(
+    app: AppHandle,
+    package: String,
+    runtime: McpPackageRuntime,
+    binary: Option<String>,
+) -> Result<McpInstallResult, String> {
+    validate_package(&package)?;
+    if let Some(binary) = &binary {
+        crate::validate_filename(binary)?;
+    }
+
+    let name = package_name(&package, runtime);
+    let runtime_dir = match runtime {
+        McpPackageRuntime::Npm => "npm",
+        McpPackageRuntime::Pip | McpPackageRuntime::Uv => "python",
+    };
+    let dir = get_packages_dir(&app)?.join(runtime_dir).join(slug(&name));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Start from a clean directory so a previous failed install can't linger
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)
+                .map_err(|e| format!("Failed to clear previous install: {e}"))?;
+        }
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create install directory: {e}"))?;
+
+        run_installer(&app, &package, &install_command(runtime, &package, &dir))?;
+
+        let binary_path = match (binary, runtime) {
+            (Some(binary), McpPackageRuntime::Npm) => {
+                dir.join("node_modules").join(".bin").join(binary)
+            }
+            (Some(binary), _) => venv_bin_dir(&dir).join(binary),
+            (None, McpPackageRuntime::Npm) => npm_binary(&dir, &name)?,
+            (None, _) => python_binary(&dir, &name)?,
+        };
+        verify_binary(&binary_path)?;
+
+        let binary_path = binary_path.to_string_lossy().to_string();
+        log::info!("Installed MCP package {package} at {binary_path}");
+
+        Ok(McpInstallResult {
+            config: McpServerConfig {
+                id: slug(&name),
+                // The command runs through the login shell, and the path
+                // may contain spaces, e.g. macOS's "Application Support"
+                command: shell_quote(&binary_path),
+                args: Vec::new(),
+                env: None,
+                docker: None,
+                isolate_env: false,
+                startup_timeout_ms: None,
+                watch_paths: Vec::new(),
+                log_level: None,
+                stderr_suppress: Vec::new(),
+                allow_elevated: false,
+                mock_fixture: None,
+            },
+            name,
+            runtime,
+            binary_path,
+        })
+    })
+    .await
+    .map_err(|e| format!("Install task failed: {e}"))?
+}
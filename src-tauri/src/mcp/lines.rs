@@ -0,0 +1,84 @@
+// Bounded Line Reader
+// ===================
+//
+// Splits a server's output into lines without trusting it. `BufRead::lines`
+// fails on the first invalid UTF-8 byte (ending the reader thread and
+// dropping everything after it) and buffers arbitrarily long lines in
+// memory. This reader decodes lossily, so invalid bytes become U+FFFD, and
+// keeps at most `max_len` bytes per line; the rest of an overlong line is
+// skipped and replaced by a truncation marker.
+
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+
+pub struct BoundedLines<R> {
+    reader: BufReader<R>,
+    max_len: usize,
+    label: String,
+}
+
+impl<R: Read> BoundedLines<R> {
+    /// `label` identifies the stream in log messages.
+    pub fn new(inner: R, max_len: usize, label: impl Into<String>) -> Self {
+        Self {
+            reader: BufReader::new(inner),
+            max_len,
+            label: label.into(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for BoundedLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut line: Vec<u8> = Vec::new();
+        let mut dropped = 0usize;
+        let mut read_any = false;
+
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::error!("Error reading {}: {e}", self.label);
+                    break;
+                }
+            };
+            if buffer.is_empty() {
+                break;
+            }
+            read_any = true;
+
+            let newline = buffer.iter().position(|&b| b == b'\n');
+            let chunk = &buffer[..newline.unwrap_or(buffer.len())];
+            let room = self.max_len.saturating_sub(line.len());
+            let keep = room.min(chunk.len());
+            line.extend_from_slice(&chunk[..keep]);
+            dropped += chunk.len() - keep;
+
+            let consumed = chunk.len() + usize::from(newline.is_some());
+            self.reader.consume(consumed);
+            if newline.is_some() {
+                break;
+            }
+        }
+
+        if !read_any {
+            return None;
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        let mut text = String::from_utf8_lossy(&line).into_owned();
+        if dropped > 0 {
+            log::warn!(
+                "Truncated a {} byte line from {}",
+                line.len() + dropped,
+                self.label
+            );
+            text.push_str(&format!(" [truncated {dropped} bytes]"));
+        }
+        Some(text)
+    }
+}
@@ -0,0 +1,423 @@
+// Mock MCP Server
+// ===============
+//
+// A built-in server that answers from a fixture file instead of running a
+// process, for UI development and automated tests without real servers.
+// It plugs into the normal pipeline: requests arrive through
+// `write_mcp_stdin` and responses go out as `mcp-stdout` events, or to the
+// backend request that sent them (see `client`), so the frontend can't tell
+// it apart from a stdio server. A server config with `mock_fixture` set
+// starts one through `spawn_mcp_server`.
+//
+// Fixture format (JSON):
+//
+//   {
+//     "serverInfo": { "name": "mock", "version": "1.0.0" },
+//     "tools": [{ "name": "...", "description": "...", "inputSchema": {...},
+//                 "response": { "content": [...], "isError": false } }],
+//     "resources": [{ "uri": "...", "name": "...", "mimeType": "...", "text": "..." }],
+//     "prompts": [{ "name": "...", "description": "...", "arguments": [...],
+//                   "messages": [...] }]
+//   }
+//
+// Tools without a `response` echo their arguments back as text.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::client::McpBackendRequests;
+use super::traffic::{McpTrafficRecorder, TrafficDirection};
+use super::{McpProcesses, McpStdoutEvent};
+use crate::audit::McpAuditLog;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+/// Largest fixture file accepted
+const MAX_FIXTURE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockFixture {
+    #[serde(default)]
+    server_info: Option<Value>,
+    #[serde(default)]
+    tools: Vec<MockTool>,
+    #[serde(default)]
+    resources: Vec<MockResource>,
+    #[serde(default)]
+    prompts: Vec<MockPrompt>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockTool {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default = "empty_object_schema")]
+    input_schema: Value,
+    /// Canned `tools/call` result
+    #[serde(default)]
+    response: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockResource {
+    uri: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    mime_type: Option<String>,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MockPrompt {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    arguments: Vec<Value>,
+    #[serde(default)]
+    messages: Vec<Value>,
+}
+
+fn empty_object_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+fn load_fixture(path: &str) -> Result<MockFixture, String> {
+    let path = Path::new(path);
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("Failed to read mock fixture: {e}"))?;
+    if metadata.len() > MAX_FIXTURE_BYTES {
+        return Err("Mock fixture is too large".to_string());
+    }
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read mock fixture: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse mock fixture {path:?}: {e}");
+        format!("Failed to parse mock fixture: {e}")
+    })
+}
+
+impl MockFixture {
+    /// Result of a request, or a JSON-RPC error code and message.
+    fn handle(&self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+                "serverInfo": self
+                    .server_info
+                    .clone()
+                    .unwrap_or_else(|| json!({ "name": "nexus-mock", "version": "1.0.0" })),
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": self
+                    .tools
+                    .iter()
+                    .map(|t| json!({
+                        "name": t.name,
+                        "description": t.description,
+                        "inputSchema": t.input_schema,
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+            "tools/call" => {
+                let name = params["name"].as_str().unwrap_or_default();
+                let tool = self
+                    .tools
+                    .iter()
+                    .find(|t| t.name == name)
+                    .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool: {name}")))?;
+                Ok(tool.response.clone().unwrap_or_else(|| {
+                    json!({
+                        "content": [{
+                            "type": "text",
+                            "text": params.get("arguments").cloned().unwrap_or(json!({})).to_string(),
+                        }],
+                    })
+                }))
+            }
+            "resources/list" => Ok(json!({
+                "resources": self
+                    .resources
+                    .iter()
+                    .map(|r| json!({ "uri": r.uri, "name": r.name, "mimeType": r.mime_type }))
+                    .collect::<Vec<_>>(),
+            })),
+            "resources/read" => {
+                let uri = params["uri"].as_str().unwrap_or_default();
+                let resource = self
+                    .resources
+                    .iter()
+                    .find(|r| r.uri == uri)
+                    .ok_or_else(|| (INVALID_PARAMS, format!("Unknown resource: {uri}")))?;
+                Ok(json!({
+                    "contents": [{
+                        "uri": resource.uri,
+                        "mimeType": resource.mime_type,
+                        "text": resource.text,
+                    }],
+                }))
+            }
+            "prompts/list" => Ok(json!({
+                "prompts": self
+                    .prompts
+                    .iter()
+                    .map(|p| json!({
+                        "name": p.name,
+                        "description": p.description,
+                        "arguments": p.arguments,
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+            "prompts/get" => {
+                let name = params["name"].as_str().unwrap_or_default();
+                let prompt = self
+                    .prompts
+                    .iter()
+                    .find(|p| p.name == name)
+                    .ok_or_else(|| (INVALID_PARAMS, format!("Unknown prompt: {name}")))?;
+                Ok(json!({ "description": prompt.description, "messages": prompt.messages }))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {method}"))),
+        }
+    }
+
+    /// Responses to the newline-delimited messages in `data`. Notifications
+    /// get no response.
+    pub fn respond(&self, data: &str) -> Vec<Value> {
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                let Ok(message) = serde_json::from_str::<Value>(line) else {
+                    return Some(json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": PARSE_ERROR, "message": "Parse error" },
+                    }));
+                };
+                let id = message.get("id")?.clone();
+                let method = message["method"].as_str().unwrap_or_default();
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                Some(match self.handle(method, &params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Answers a write to a mock server by emitting its responses.
+pub fn handle_write(app: &AppHandle, state: &McpProcesses, server_id: &str, data: &str) {
+    let responses = match state.mocks.lock() {
+        Ok(mocks) => mocks
+            .get(server_id)
+            .map(|fixture| fixture.respond(data))
+            .unwrap_or_default(),
+        Err(_) => return,
+    };
+
+    for response in responses {
+        let data = response.to_string();
+        app.state::<McpTrafficRecorder>()
+            .record(server_id, TrafficDirection::Inbound, &data);
+        app.state::<McpAuditLog>()
+            .observe_response(app, server_id, &data);
+        if app.state::<McpBackendRequests>().take_response(&data) {
+            continue;
+        }
+        let _ = app.emit(
+            "mcp-stdout",
+            McpStdoutEvent {
+                server_id: server_id.to_string(),
+                data,
+            },
+        );
+    }
+}
+
+/// Registers a mock server for `server_id` that answers from `fixture`.
+/// Called by `spawn_mcp_server` for a config with `mock_fixture` set, while
+/// it holds the claim on the id.
+pub(super) fn start(
+    app: &AppHandle,
+    state: &McpProcesses,
+    server_id: &str,
+    fixture: &str,
+) -> Result<(), String> {
+    let loaded = load_fixture(fixture)?;
+    let mut mocks = state.mocks.lock().map_err(|e| e.to_string())?;
+    if mocks.contains_key(server_id) {
+        return Err(format!("MCP server {server_id} is already running"));
+    }
+    log_start(server_id, fixture, &loaded);
+    mocks.insert(server_id.to_string(), loaded);
+    drop(mocks);
+    crate::platform::server_started(app, server_id);
+    Ok(())
+}
+
+fn log_start(server_id: &str, fixture: &str, loaded: &MockFixture) {
+    log::info!(
+        "Starting mock MCP server {server_id} from {fixture} ({} tools, {} resources, {} prompts)",
+        loaded.tools.len(),
+        loaded.resources.len(),
+        loaded.prompts.len()
+    );
+}
+
+/// Starts a mock server that serves the tools, resources and prompts in a
+/// fixture file. Stop it with `kill_mcp_server` like any other server.
+/// `spawn_mcp_server` does the same for a config with `mock_fixture` set.
+#[tauri::command]
+pub async fn spawn_mock_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpProcesses>,
+    server_id: String,
+    fixture: String,
+) -> Result<(), String> {
+    crate::validate_string_input(&server_id, 256, "Server id")?;
+    let loaded = load_fixture(&fixture)?;
+
+    // Same lock order as `spawn_mcp_server`, so a real spawn of the id can't slip in
+    let spawning = state.spawning.lock().map_err(|e| e.to_string())?;
+    let running = spawning.contains(&server_id)
+        || state
+            .processes
+            .lock()
+            .map_err(|e| e.to_string())?
+            .contains_key(&server_id);
+    let mut mocks = state.mocks.lock().map_err(|e| e.to_string())?;
+    if running || mocks.contains_key(&server_id) {
+        return Err(format!("MCP server {server_id} is already running"));
+    }
+
+    log_start(&server_id, &fixture, &loaded);
+    mocks.insert(server_id.clone(), loaded);
+    drop(mocks);
+    drop(spawning);
+    crate::platform::server_started(&app, &server_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const FIXTURE: &str = r#"{
+        "serverInfo": { "name": "fixture-server", "version": "2.0.0" },
+        "tools": [
+            { "name": "weather", "description": "Forecast",
+              "response": { "content": [{ "type": "text", "text": "Sunny" }] } },
+            { "name": "echo" }
+        ],
+        "resources": [{ "uri": "file:///notes.txt", "name": "Notes",
+                        "mimeType": "text/plain", "text": "hello" }],
+        "prompts": [{ "name": "greet", "description": "Greeting",
+                      "messages": [{ "role": "user",
+                                     "content": { "type": "text", "text": "Hi" } }] }]
+    }"#;
+
+    fn fixture() -> MockFixture {
+        // Tests run in parallel, so each loads its own copy
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "nexus-mock-fixture-{}-{n}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, FIXTURE).unwrap();
+        let fixture = load_fixture(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        fixture.unwrap()
+    }
+
+    fn request(fixture: &MockFixture, id: i64, method: &str, params: Value) -> Value {
+        let line = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let mut responses = fixture.respond(&format!("{line}\n"));
+        assert_eq!(responses.len(), 1);
+        let response = responses.remove(0);
+        assert_eq!(response["id"], id);
+        response
+    }
+
+    #[test]
+    fn answers_a_session_from_the_fixture() {
+        let fixture = fixture();
+
+        let init = request(&fixture, 1, "initialize", json!({}));
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(init["result"]["serverInfo"]["name"], "fixture-server");
+
+        let tools = request(&fixture, 2, "tools/list", json!({}));
+        let names: Vec<_> = tools["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["weather", "echo"]);
+        assert_eq!(
+            tools["result"]["tools"][1]["inputSchema"],
+            empty_object_schema()
+        );
+
+        let call = request(&fixture, 3, "tools/call", json!({ "name": "weather" }));
+        assert_eq!(call["result"]["content"][0]["text"], "Sunny");
+
+        let read = request(
+            &fixture,
+            4,
+            "resources/read",
+            json!({ "uri": "file:///notes.txt" }),
+        );
+        assert_eq!(read["result"]["contents"][0]["text"], "hello");
+
+        let prompt = request(&fixture, 5, "prompts/get", json!({ "name": "greet" }));
+        assert_eq!(prompt["result"]["messages"][0]["content"]["text"], "Hi");
+    }
+
+    #[test]
+    fn echoes_arguments_of_tools_without_a_response() {
+        let call = request(
+            &fixture(),
+            1,
+            "tools/call",
+            json!({ "name": "echo", "arguments": { "city": "Oslo" } }),
+        );
+        assert_eq!(call["result"]["content"][0]["text"], r#"{"city":"Oslo"}"#);
+    }
+
+    #[test]
+    fn reports_unknown_names_and_methods() {
+        let fixture = fixture();
+        let call = request(&fixture, 1, "tools/call", json!({ "name": "missing" }));
+        assert_eq!(call["error"]["code"], INVALID_PARAMS);
+        let other = request(&fixture, 2, "sampling/createMessage", json!({}));
+        assert_eq!(other["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn skips_notifications_and_reports_parse_errors() {
+        let responses = fixture()
+            .respond("{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\nnot json\n");
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -0,0 +1,953 @@
+// MCP Process Management
+// =======================
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command as StdCommand, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit::McpAuditLog;
+use faults::McpFaults;
+use lines::BoundedLines;
+use queue::{McpSpawnError, McpSpawnErrorKind};
+use startup::StartupWatch;
+use stderr::{LogLevel, ServerLog, SharedStderrFilter, StderrFilter};
+use traffic::{McpTrafficRecorder, TrafficDirection};
+
+pub mod bundle;
+pub mod client;
+pub mod conformance;
+mod docker;
+pub mod elevation;
+pub mod faults;
+pub mod install;
+mod lines;
+pub mod mock;
+pub mod policy;
+pub mod queue;
+pub mod registry;
+pub mod runtimes;
+pub mod startup;
+pub mod stats;
+pub mod stderr;
+pub mod traffic;
+mod watch;
+
+pub use docker::McpDockerConfig;
+pub use queue::McpConcurrencySettings;
+
+/// The user's login shell, used so servers see the same PATH as a terminal
+pub(crate) fn login_shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
+}
+
+fn run_in_login_shell(shell: &str, command: &str) -> std::io::Result<std::process::Output> {
+    StdCommand::new(shell)
+        .args(["-l", "-c", command])
+        .stdin(Stdio::null())
+        .output()
+}
+
+/// Quotes a value for safe interpolation into a POSIX shell command line.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Number of stderr lines kept per server for crash reports
+const STDERR_TAIL_LINES: usize = 200;
+/// Longest stdout line passed on; a JSON-RPC message may carry large
+/// embedded content such as base64 images
+const MAX_STDOUT_LINE_BYTES: usize = 16 * 1024 * 1024;
+/// Longest stderr line passed on
+const MAX_STDERR_LINE_BYTES: usize = 64 * 1024;
+/// How often the exit monitor polls child processes
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long a server gets to exit after its stdin is closed before it is killed
+const GRACEFUL_STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+type StderrTail = Arc<Mutex<VecDeque<String>>>;
+
+/// A server's child process. Dropping it kills the process if it is still
+/// running and removes its container, so a server can't outlive its entry
+/// in the process table on any code path.
+struct ServerChild {
+    child: Child,
+    /// Container to remove on shutdown for docker-backed servers
+    container: Option<String>,
+}
+
+impl ServerChild {
+    /// Kills the process and removes its container, if any.
+    fn kill(&mut self) -> std::io::Result<()> {
+        let result = self.child.kill();
+        // Reap it, so dropping doesn't find it still running
+        let _ = self.child.wait();
+        if let Some(container) = self.container.take() {
+            docker::remove_container(&container);
+        }
+        result
+    }
+}
+
+impl Deref for ServerChild {
+    type Target = Child;
+
+    fn deref(&self) -> &Child {
+        &self.child
+    }
+}
+
+impl DerefMut for ServerChild {
+    fn deref_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+}
+
+impl Drop for ServerChild {
+    fn drop(&mut self) {
+        if matches!(self.child.try_wait(), Ok(None)) {
+            log::warn!(
+                "Killing MCP server process {} that was still running",
+                self.child.id()
+            );
+        }
+        let _ = self.kill();
+    }
+}
+
+struct McpProcess {
+    stdin: ChildStdin,
+    child: ServerChild,
+    started: Instant,
+    stderr_tail: StderrTail,
+    /// Applied by the stderr reader; replaced when the config's filter changes
+    stderr_filter: SharedStderrFilter,
+    /// Config the process was started with
+    config: McpServerConfig,
+    /// Hot reload watcher for `config.watch_paths`, dropped with the process
+    _watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl McpProcess {
+    /// Kills the process and removes its container, if any.
+    fn terminate(&mut self) -> std::io::Result<()> {
+        self.child.kill()
+    }
+
+    /// Closes stdin so the server can shut down cleanly, killing it if it
+    /// hasn't exited within `GRACEFUL_STOP_TIMEOUT`.
+    fn stop_gracefully(self) {
+        let McpProcess {
+            stdin,
+            mut child,
+            config,
+            ..
+        } = self;
+        drop(stdin);
+
+        let deadline = Instant::now() + GRACEFUL_STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) => std::thread::sleep(EXIT_POLL_INTERVAL),
+                Err(e) => {
+                    log::warn!("Failed to poll MCP server {}: {e}", config.id);
+                    break;
+                }
+            }
+        }
+        if !matches!(child.try_wait(), Ok(Some(_))) {
+            log::info!("MCP server {} did not exit in time, killing it", config.id);
+        }
+        if let Err(e) = child.kill() {
+            log::debug!("Failed to kill MCP server {}: {e}", config.id);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct McpProcesses {
+    processes: Mutex<HashMap<String, McpProcess>>,
+    /// Mock servers, answered in-process from their fixture
+    mocks: Mutex<HashMap<String, mock::MockFixture>>,
+    queue: Mutex<queue::SpawnQueue>,
+    /// Ids with a spawn in progress, see `SpawnClaim`
+    spawning: Mutex<HashSet<String>>,
+    /// Signalled whenever a server stops or a spawn gives up its slot
+    slot_freed: tokio::sync::Notify,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    pub id: String,
+    /// Executable to run, or for docker servers an optional container command
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub env: Option<HashMap<String, String>>,
+    /// Run the server in a container instead of as a local process
+    #[serde(default)]
+    pub docker: Option<McpDockerConfig>,
+    /// Start the server with a clean environment holding only PATH, HOME and
+    /// the variables in `env`, instead of inheriting the app's
+    #[serde(default)]
+    pub isolate_env: bool,
+    /// How long the server may take to answer before it is stopped;
+    /// defaults to `startup::DEFAULT_STARTUP_TIMEOUT_MS`
+    #[serde(default)]
+    pub startup_timeout_ms: Option<u64>,
+    /// Files or directories whose changes restart the server
+    #[serde(default)]
+    pub watch_paths: Vec<PathBuf>,
+    /// Least severe stderr level forwarded to the webview; everything is
+    /// forwarded when unset. The server log on disk always gets every line.
+    #[serde(default)]
+    pub log_level: Option<LogLevel>,
+    /// Regexes of stderr lines never forwarded to the webview
+    #[serde(default)]
+    pub stderr_suppress: Vec<String>,
+    /// The user acknowledged that the server runs with elevated privileges;
+    /// see `elevation`
+    #[serde(default)]
+    pub allow_elevated: bool,
+    /// Fixture file a built-in mock server answers from instead of running
+    /// `command`; see `mock`
+    #[serde(default)]
+    pub mock_fixture: Option<String>,
+}
+
+impl McpServerConfig {
+    /// Whether `other` launches a different process, i.e. the command, args,
+    /// env or container settings differ.
+    fn launch_differs(&self, other: &McpServerConfig) -> bool {
+        self.command != other.command
+            || self.args != other.args
+            || self.env.clone().unwrap_or_default() != other.env.clone().unwrap_or_default()
+            || self.docker != other.docker
+            || self.isolate_env != other.isolate_env
+            || self.allow_elevated != other.allow_elevated
+            || self.mock_fixture != other.mock_fixture
+    }
+
+    fn stderr_filter_differs(&self, other: &McpServerConfig) -> bool {
+        self.log_level != other.log_level || self.stderr_suppress != other.stderr_suppress
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpProcessSummary {
+    pub id: String,
+    /// Answered in-process from a fixture
+    pub mock: bool,
+    /// `None` for mocks
+    pub uptime_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpRestartResult {
+    /// Whether the server was actually restarted
+    pub restarted: bool,
+    /// Pid of the running process, `None` if the server wasn't running
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpStdoutEvent {
+    pub server_id: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpStderrEvent {
+    pub server_id: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpExitEvent {
+    pub server_id: String,
+    pub code: Option<i32>,
+}
+
+/// Emitted as `mcp-crash` when a server exits unsuccessfully on its own
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpCrashEvent {
+    pub server_id: String,
+    /// Exit code, or `None` if the process was terminated by a signal
+    pub code: Option<i32>,
+    pub uptime_ms: u64,
+    /// The last lines the server wrote to stderr, oldest first
+    pub stderr_tail: Vec<String>,
+}
+
+/// `export` statements for `env`, ending in a separator, in the order of
+/// their names.
+fn env_exports(env: &HashMap<String, String>) -> String {
+    let mut names: Vec<&String> = env.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("export {name}=\"{}\"; ", env[name]))
+        .collect()
+}
+
+/// The shell command line a stdio server outside Docker is launched with.
+/// The command and arguments are joined as they are, so the shell expands
+/// them as it would for the user.
+pub fn command_line(command: &str, args: &[String], env: &HashMap<String, String>) -> String {
+    let command = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{}{command}", env_exports(env))
+}
+
+/// Builds the shell command line that launches a server, along with the
+/// container it runs in for docker-backed servers.
+async fn launch_command(
+    app: &AppHandle,
+    shell: &str,
+    config: &McpServerConfig,
+) -> Result<(String, Option<String>), McpSpawnError> {
+    elevation::check(app, shell, config)?;
+
+    let mut env: HashMap<String, String> = config.env.clone().unwrap_or_default();
+    if let Some(docker) = &config.docker {
+        env.extend(docker.env.clone());
+    }
+
+    let env_exports = env_exports(&env);
+
+    // Build the full command
+    if let Some(docker) = config.docker.clone() {
+        if docker.image.trim().is_empty() {
+            return Err("Docker MCP servers require an image".into());
+        }
+
+        let check_app = app.clone();
+        let check_shell = shell.to_string();
+        let check_id = config.id.clone();
+        let check_image = docker.image.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            docker::ensure_docker_available(&check_shell)?;
+            docker::ensure_image(&check_app, &check_shell, &check_id, &check_image)
+        })
+        .await
+        .map_err(|e| format!("Docker check failed: {e}"))??;
+
+        let container = docker::container_name(&config.id);
+        let env_keys: Vec<&String> = env.keys().collect();
+        let run = docker::build_run_command(
+            &docker,
+            &container,
+            &env_keys,
+            &config.command,
+            &config.args,
+        );
+        Ok((format!("{env_exports}{run}"), Some(container)))
+    } else {
+        if config.command.trim().is_empty() {
+            return Err("MCP server command cannot be empty".into());
+        }
+
+        Ok((command_line(&config.command, &config.args, &env), None))
+    }
+}
+
+/// PATH as the user's login shell sets it up, resolved once. Isolated
+/// servers get this instead of running through the login shell, whose
+/// profile could export the very secrets isolation is meant to keep out.
+pub(crate) fn login_shell_path(shell: &str) -> String {
+    static LOGIN_PATH: OnceLock<String> = OnceLock::new();
+    LOGIN_PATH
+        .get_or_init(|| {
+            run_in_login_shell(shell, "printf '%s' \"$PATH\"")
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .filter(|path| !path.is_empty())
+                .or_else(|| std::env::var("PATH").ok())
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+/// Starts a launch command with piped stdio. With `isolate_env` the command
+/// runs under a plain `sh` with only PATH, HOME and the proxy variables
+/// (see `network`) set; the variables from the server config are exported
+/// by the command line itself.
+fn spawn_in_shell(shell: &str, full_command: &str, isolate_env: bool) -> Result<Child, String> {
+    let mut command = if isolate_env {
+        log::debug!("Isolated shell command: /bin/sh -c \"{}\"", full_command);
+        let mut command = StdCommand::new("/bin/sh");
+        command
+            .args(["-c", full_command])
+            .env_clear()
+            .env("PATH", login_shell_path(shell));
+        if let Ok(home) = std::env::var("HOME") {
+            command.env("HOME", home);
+        }
+        command
+    } else {
+        log::debug!("Shell command: {} -l -c \"{}\"", shell, full_command);
+        let mut command = StdCommand::new(shell);
+        command.args(["-l", "-c", full_command]);
+        command
+    };
+    crate::network::apply_to_command(&mut command);
+
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn process: {e}"))
+}
+
+/// Marks a server id as starting until dropped, so two concurrent spawns of
+/// the same id can't both launch a process.
+struct SpawnClaim {
+    app: AppHandle,
+    server_id: String,
+}
+
+impl Drop for SpawnClaim {
+    fn drop(&mut self) {
+        if let Ok(mut spawning) = self.app.state::<McpProcesses>().spawning.lock() {
+            spawning.remove(&self.server_id);
+        }
+    }
+}
+
+fn already_running(server_id: &str, starting: bool) -> McpSpawnError {
+    McpSpawnError {
+        kind: McpSpawnErrorKind::AlreadyRunning,
+        message: format!(
+            "MCP server {server_id} is already {}",
+            if starting { "starting" } else { "running" }
+        ),
+    }
+}
+
+/// Claims `server_id` for a spawn. A server that is already running or
+/// starting is an error, unless `restart` is set and it is running: then it
+/// is taken out of the table and returned for the caller to stop.
+fn claim_server_id(
+    app: &AppHandle,
+    state: &McpProcesses,
+    server_id: &str,
+    restart: bool,
+) -> Result<(SpawnClaim, Option<McpProcess>), McpSpawnError> {
+    let mut spawning = state.spawning.lock().map_err(|e| e.to_string())?;
+    if spawning.contains(server_id) {
+        return Err(already_running(server_id, true));
+    }
+    let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+    let mut mocks = state.mocks.lock().map_err(|e| e.to_string())?;
+    if !restart && (processes.contains_key(server_id) || mocks.contains_key(server_id)) {
+        return Err(already_running(server_id, false));
+    }
+
+    // Removing it first keeps the exit monitor from reporting the stop
+    let previous = processes.remove(server_id);
+    let previous_mock = mocks.remove(server_id);
+    spawning.insert(server_id.to_string());
+    drop(mocks);
+    drop(processes);
+    drop(spawning);
+
+    if previous_mock.is_some() {
+        log::info!("Replacing mock MCP server {server_id}");
+        app.state::<McpAuditLog>().abandon_server(app, server_id);
+    }
+    Ok((
+        SpawnClaim {
+            app: app.clone(),
+            server_id: server_id.to_string(),
+        },
+        previous,
+    ))
+}
+
+/// Starts a server, returning its PID, or 0 for a mock server (a config
+/// with `mock_fixture` set). Starting an id that is already running or
+/// starting fails with `already_running`, unless `restart` is set, in which
+/// case a running server is stopped gracefully and replaced.
+#[tauri::command]
+pub async fn spawn_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpProcesses>,
+    config: McpServerConfig,
+    restart: Option<bool>,
+) -> Result<u32, McpSpawnError> {
+    log::info!(
+        "Spawning MCP server: {} with command: {}",
+        config.id,
+        config.command
+    );
+
+    let stderr_filter: SharedStderrFilter = Arc::new(Mutex::new(StderrFilter::new(&config)?));
+    let (_claim, previous) = claim_server_id(&app, &state, &config.id, restart.unwrap_or(false))?;
+    if let Some(previous) = previous {
+        log::info!("Stopping running MCP server {} to restart it", config.id);
+        tauri::async_runtime::spawn_blocking(move || previous.stop_gracefully())
+            .await
+            .map_err(|e| format!("Failed to stop MCP server: {e}"))?;
+        app.state::<McpAuditLog>().abandon_server(&app, &config.id);
+        queue::emit_queue_status(&app);
+    }
+
+    if let Some(fixture) = &config.mock_fixture {
+        // The claim keeps the id free, and mocks take no process slot
+        mock::start(&app, &state, &config.id, fixture)?;
+        return Ok(0);
+    }
+
+    // Held until the process is registered, so concurrent spawns can't overshoot the cap
+    let _slot = queue::acquire_slot(&app, &config.id).await?;
+
+    // Use login shell to get the user's PATH
+    let shell = login_shell();
+    let (full_command, container) = launch_command(&app, &shell, &config).await?;
+    let mut child = ServerChild {
+        child: spawn_in_shell(&shell, &full_command, config.isolate_env)?,
+        container,
+    };
+
+    let pid = child.id();
+    log::info!("MCP server {} spawned with PID: {}", config.id, pid);
+
+    let stdin = child.stdin.take().ok_or("Failed to get stdin")?;
+    let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+
+    let stderr_tail: StderrTail = Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_TAIL_LINES)));
+    let startup = Arc::new(StartupWatch::new());
+
+    // Store the process
+    {
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        // The claim keeps the id free; never overwrite an entry regardless,
+        // since that would orphan its process. `child` is killed on return.
+        let Entry::Vacant(entry) = processes.entry(config.id.clone()) else {
+            return Err(already_running(&config.id, false));
+        };
+        entry.insert(McpProcess {
+            stdin,
+            child,
+            started: Instant::now(),
+            stderr_tail: stderr_tail.clone(),
+            stderr_filter: stderr_filter.clone(),
+            config: config.clone(),
+            _watcher: watch::start(&app, &config),
+        });
+    }
+
+    // Spawn thread to read stdout
+    let app_stdout = app.clone();
+    let server_id_stdout = config.id.clone();
+    let startup_stdout = startup.clone();
+    std::thread::spawn(move || {
+        let label = format!("stdout of MCP server {server_id_stdout}");
+        for data in BoundedLines::new(stdout, MAX_STDOUT_LINE_BYTES, label) {
+            startup_stdout.mark_ready();
+            let (delay, data) = app_stdout.state::<McpFaults>().inject(
+                &server_id_stdout,
+                TrafficDirection::Inbound,
+                data,
+            );
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+            let Some(data) = data else {
+                continue;
+            };
+            app_stdout.state::<McpTrafficRecorder>().record(
+                &server_id_stdout,
+                TrafficDirection::Inbound,
+                &data,
+            );
+            app_stdout.state::<McpAuditLog>().observe_response(
+                &app_stdout,
+                &server_id_stdout,
+                &data,
+            );
+            if app_stdout
+                .state::<client::McpBackendRequests>()
+                .take_response(&data)
+            {
+                continue;
+            }
+            let _ = app_stdout.emit(
+                "mcp-stdout",
+                McpStdoutEvent {
+                    server_id: server_id_stdout.clone(),
+                    data,
+                },
+            );
+        }
+    });
+
+    // Spawn thread to read stderr
+    let app_stderr = app.clone();
+    let server_id_stderr = config.id.clone();
+    let startup_stderr = startup.clone();
+    std::thread::spawn(move || {
+        let label = format!("stderr of MCP server {server_id_stderr}");
+        let mut server_log = ServerLog::open(&app_stderr, &server_id_stderr);
+        for data in BoundedLines::new(stderr, MAX_STDERR_LINE_BYTES, label) {
+            log::debug!("MCP {} stderr: {}", server_id_stderr, data);
+            startup_stderr.observe_stderr(&data);
+            if let Some(server_log) = server_log.as_mut() {
+                server_log.write(&data);
+            }
+            if let Ok(mut tail) = stderr_tail.lock() {
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(data.clone());
+            }
+            if !stderr_filter.lock().is_ok_and(|f| f.forwards(&data)) {
+                continue;
+            }
+            let _ = app_stderr.emit(
+                "mcp-stderr",
+                McpStderrEvent {
+                    server_id: server_id_stderr.clone(),
+                    data,
+                },
+            );
+        }
+    });
+
+    // Spawn thread to watch for the process exiting on its own
+    let app_monitor = app.clone();
+    let server_id_monitor = config.id.clone();
+    std::thread::spawn(move || monitor_exit(app_monitor, server_id_monitor, pid));
+
+    // Spawn thread to report startup progress until the server answers
+    let app_startup = app.clone();
+    let server_id_startup = config.id.clone();
+    let timeout_ms = config
+        .startup_timeout_ms
+        .unwrap_or(startup::DEFAULT_STARTUP_TIMEOUT_MS);
+    std::thread::spawn(move || {
+        startup::watch(app_startup, server_id_startup, pid, &startup, timeout_ms)
+    });
+
+    crate::platform::server_started(&app, &config.id);
+    Ok(pid)
+}
+
+/// Polls a server process until it exits, then removes it from the process
+/// table and emits `mcp-exit` (plus `mcp-crash` for unsuccessful exits).
+/// Servers stopped through `kill_mcp_server` are already gone from the table,
+/// so the monitor simply stops without reporting them.
+fn monitor_exit(app: AppHandle, server_id: String, pid: u32) {
+    loop {
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+
+        let state = app.state::<McpProcesses>();
+        let Ok(mut processes) = state.processes.lock() else {
+            return;
+        };
+
+        // A different pid means the server was stopped and spawned again
+        let Some(process) = processes
+            .get_mut(&server_id)
+            .filter(|p| p.child.id() == pid)
+        else {
+            return;
+        };
+
+        let status = match process.child.try_wait() {
+            Ok(Some(status)) => status,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("Failed to poll MCP server {server_id}: {e}");
+                return;
+            }
+        };
+
+        let Some(process) = processes.remove(&server_id) else {
+            return;
+        };
+        drop(processes);
+
+        let code = status.code();
+        log::info!("MCP server {server_id} exited with status {status}");
+        app.state::<McpAuditLog>().abandon_server(&app, &server_id);
+        queue::emit_queue_status(&app);
+
+        let _ = app.emit(
+            "mcp-exit",
+            McpExitEvent {
+                server_id: server_id.clone(),
+                code,
+            },
+        );
+
+        if status.success() {
+            crate::platform::refresh_tray(&app);
+        } else {
+            crate::platform::server_crashed(&app, &server_id);
+            let stderr_tail = process
+                .stderr_tail
+                .lock()
+                .map(|tail| tail.iter().cloned().collect())
+                .unwrap_or_default();
+            log::warn!("MCP server {server_id} crashed (exit code {code:?})");
+            let _ = app.emit(
+                "mcp-crash",
+                McpCrashEvent {
+                    server_id,
+                    code,
+                    uptime_ms: process.started.elapsed().as_millis() as u64,
+                    stderr_tail,
+                },
+            );
+        }
+        return;
+    }
+}
+
+#[tauri::command]
+pub async fn write_mcp_stdin(
+    app: AppHandle,
+    state: State<'_, McpProcesses>,
+    audit: State<'_, McpAuditLog>,
+    server_id: String,
+    data: String,
+) -> Result<(), String> {
+    log::debug!("Writing to MCP server {}: {}", server_id, data.trim());
+
+    let (delay, data) =
+        app.state::<McpFaults>()
+            .inject(&server_id, TrafficDirection::Outbound, data);
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+    let Some(data) = data else {
+        return Ok(());
+    };
+
+    if state
+        .mocks
+        .lock()
+        .map_err(|e| e.to_string())?
+        .contains_key(&server_id)
+    {
+        app.state::<McpTrafficRecorder>()
+            .record(&server_id, TrafficDirection::Outbound, &data);
+        audit.observe_request(&server_id, &data);
+        mock::handle_write(&app, &state, &server_id, &data);
+        return Ok(());
+    }
+
+    let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+    let process = processes
+        .get_mut(&server_id)
+        .ok_or_else(|| format!("MCP server {} not found", server_id))?;
+
+    audit.observe_request(&server_id, &data);
+    let written = process
+        .stdin
+        .write_all(data.as_bytes())
+        .map_err(|e| format!("Failed to write to stdin: {e}"))
+        .and_then(|_| {
+            process
+                .stdin
+                .flush()
+                .map_err(|e| format!("Failed to flush stdin: {e}"))
+        });
+    if let Err(e) = written {
+        audit.forget_request(&server_id, &data);
+        return Err(e);
+    }
+
+    app.state::<McpTrafficRecorder>()
+        .record(&server_id, TrafficDirection::Outbound, &data);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn kill_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpProcesses>,
+    audit: State<'_, McpAuditLog>,
+    server_id: String,
+) -> Result<(), String> {
+    log::info!("Killing MCP server: {}", server_id);
+
+    // A server still waiting for a slot has no process yet
+    if queue::cancel_queued(&app, &server_id) {
+        return Ok(());
+    }
+
+    let mock = state
+        .mocks
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&server_id);
+    if mock.is_some() {
+        log::info!("Mock MCP server {} stopped", server_id);
+        audit.abandon_server(&app, &server_id);
+        crate::platform::refresh_tray(&app);
+        return Ok(());
+    }
+
+    let process = state
+        .processes
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&server_id);
+    if let Some(mut process) = process {
+        // Reaping the process and `docker rm -f` can take seconds, so they run
+        // on a blocking thread with the process table unlocked
+        tauri::async_runtime::spawn_blocking(move || process.terminate())
+            .await
+            .map_err(|e| format!("Failed to kill process: {e}"))?
+            .map_err(|e| format!("Failed to kill process: {e}"))?;
+        log::info!("MCP server {} killed", server_id);
+    }
+
+    audit.abandon_server(&app, &server_id);
+    queue::emit_queue_status(&app);
+    crate::platform::refresh_tray(&app);
+
+    Ok(())
+}
+
+/// Applies a new config to a running server. The server is stopped
+/// gracefully and started again under the same id only if the command, args
+/// or env changed, so frontend listeners keyed on the id keep working; a
+/// changed log level or suppression list applies without a restart.
+/// Servers that aren't running are left alone.
+#[tauri::command]
+pub async fn update_and_restart_mcp_server(
+    app: AppHandle,
+    config: McpServerConfig,
+) -> Result<McpRestartResult, McpSpawnError> {
+    let state = app.state::<McpProcesses>();
+    {
+        let mut processes = state.processes.lock().map_err(|e| e.to_string())?;
+        let Some(process) = processes.get_mut(&config.id) else {
+            return Ok(McpRestartResult {
+                restarted: false,
+                pid: None,
+            });
+        };
+        if !process.config.launch_differs(&config) {
+            log::debug!("MCP server {} config unchanged, not restarting", config.id);
+            if process.config.watch_paths != config.watch_paths {
+                process._watcher = watch::start(&app, &config);
+            }
+            if process.config.stderr_filter_differs(&config) {
+                let filter = StderrFilter::new(&config)?;
+                *process.stderr_filter.lock().map_err(|e| e.to_string())? = filter;
+            }
+            process.config = config;
+            return Ok(McpRestartResult {
+                restarted: false,
+                pid: Some(process.child.id()),
+            });
+        }
+    }
+
+    log::info!("Restarting MCP server {} with a new config", config.id);
+    let pid = spawn_mcp_server(app.clone(), state, config, Some(true)).await?;
+    Ok(McpRestartResult {
+        restarted: true,
+        pid: Some(pid),
+    })
+}
+
+/// Stops every running server. Called when the app exits so no child
+/// processes or containers outlive it.
+pub fn shutdown_all(app: &AppHandle) {
+    let state = app.state::<McpProcesses>();
+    let Ok(mut processes) = state.processes.lock() else {
+        return;
+    };
+    // Terminated with the table unlocked, like in `kill_mcp_server`
+    let stopping: Vec<_> = processes.drain().collect();
+    drop(processes);
+
+    for (server_id, mut process) in stopping {
+        log::info!("Stopping MCP server {server_id} on exit");
+        if let Err(e) = process.terminate() {
+            log::warn!("Failed to stop MCP server {server_id}: {e}");
+        }
+    }
+}
+
+/// Ids of every running server, including mocks, sorted.
+pub fn running_server_ids(app: &AppHandle) -> Vec<String> {
+    let state = app.state::<McpProcesses>();
+    let mut ids: Vec<String> = Vec::new();
+    if let Ok(processes) = state.processes.lock() {
+        ids.extend(processes.keys().cloned());
+    }
+    if let Ok(mocks) = state.mocks.lock() {
+        ids.extend(mocks.keys().cloned());
+    }
+    ids.sort();
+    ids
+}
+
+/// Every running server with its uptime, mocks included, sorted by id.
+pub fn process_summaries(app: &AppHandle) -> Vec<McpProcessSummary> {
+    let state = app.state::<McpProcesses>();
+    let mut summaries: Vec<McpProcessSummary> = Vec::new();
+    if let Ok(processes) = state.processes.lock() {
+        summaries.extend(processes.iter().map(|(id, process)| McpProcessSummary {
+            id: id.clone(),
+            mock: false,
+            uptime_ms: Some(process.started.elapsed().as_millis() as u64),
+        }));
+    }
+    if let Ok(mocks) = state.mocks.lock() {
+        summaries.extend(mocks.keys().map(|id| McpProcessSummary {
+            id: id.clone(),
+            mock: true,
+            uptime_ms: None,
+        }));
+    }
+    summaries.sort_by(|a, b| a.id.cmp(&b.id));
+    summaries
+}
+
+/// Returns the buffered stderr lines of a running server, oldest first.
+#[tauri::command]
+pub async fn get_mcp_stderr_tail(
+    state: State<'_, McpProcesses>,
+    server_id: String,
+) -> Result<Vec<String>, String> {
+    let processes = state.processes.lock().map_err(|e| e.to_string())?;
+    let process = processes
+        .get(&server_id)
+        .ok_or_else(|| format!("MCP server {} not found", server_id))?;
+    let tail = process.stderr_tail.lock().map_err(|e| e.to_string())?;
+    Ok(tail.iter().cloned().collect())
+}
+
+#[tauri::command]
+pub async fn is_mcp_server_running(
+    state: State<'_, McpProcesses>,
+    server_id: String,
+) -> Result<bool, String> {
+    let processes = state.processes.lock().map_err(|e| e.to_string())?;
+    let mocks = state.mocks.lock().map_err(|e| e.to_string())?;
+    Ok(processes.contains_key(&server_id) || mocks.contains_key(&server_id))
+}
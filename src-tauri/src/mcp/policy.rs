@@ -0,0 +1,225 @@
+// Tool Permissions
+// ================
+//
+// Decides whether a tool call the backend makes on a model's behalf (see
+// `agent`) runs right away, waits for the user to approve it, or is
+// refused. Rules name a tool as `server_id/tool_name`, or every tool of a
+// server as `server_id/*`; the exact tool wins over the server. Tools
+// without a rule get the policy's default, which is to ask. A server's own
+// annotations never allow a call: only the user's rules do.
+//
+// Destructive tools are asked about even where a rule allows them, unless
+// the policy sets `allow_destructive`. Like the MCP spec, a tool counts as
+// destructive unless it declares `readOnlyHint` or `destructiveHint: false`. Saving such a policy is protected by the second
+// factor (see `second_factor`). The policy is kept in `tool-policy.json`
+// rather than in the preferences, so the webview can't loosen it without
+// that check.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::second_factor::{self, ProtectedOperation};
+
+const MAX_RULES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermission {
+    Allow,
+    #[default]
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ToolPolicy {
+    /// For tools without a rule
+    pub default: ToolPermission,
+    /// By `server_id/tool_name`, or `server_id/*` for a whole server
+    pub rules: HashMap<String, ToolPermission>,
+    /// Destructive tools run without asking where a rule or the default
+    /// allows them
+    pub allow_destructive: bool,
+}
+
+fn get_policy_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("tool-policy.json"))
+}
+
+pub fn load(app: &AppHandle) -> Result<ToolPolicy, String> {
+    let path = get_policy_path(app)?;
+    if !path.exists() {
+        return Ok(ToolPolicy::default());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read tool policy: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse tool policy: {e}");
+        format!("Failed to parse tool policy: {e}")
+    })
+}
+
+fn store(app: &AppHandle, policy: &ToolPolicy) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(policy)
+        .map_err(|e| format!("Failed to serialize tool policy: {e}"))?;
+    crate::export::write_atomic(&get_policy_path(app)?, &json)
+}
+
+/// Whether `tool`, as listed by `tools/list`, may be destructive. The spec
+/// defaults `destructiveHint` to true for tools that are not read-only.
+pub fn is_destructive(tool: &Value) -> bool {
+    let annotations = &tool["annotations"];
+    annotations["readOnlyHint"].as_bool() != Some(true)
+        && annotations["destructiveHint"].as_bool() != Some(false)
+}
+
+impl ToolPolicy {
+    /// What to do with a call of `tool` on `server_id`.
+    pub fn permission(&self, server_id: &str, tool: &Value) -> ToolPermission {
+        let name = tool["name"].as_str().unwrap_or_default();
+        let rule = self
+            .rules
+            .get(&format!("{server_id}/{name}"))
+            .or_else(|| self.rules.get(&format!("{server_id}/*")));
+        let permission = rule.copied().unwrap_or(self.default);
+        if permission == ToolPermission::Allow && is_destructive(tool) && !self.allow_destructive {
+            ToolPermission::Ask
+        } else {
+            permission
+        }
+    }
+}
+
+fn validate(policy: &ToolPolicy) -> Result<(), String> {
+    if policy.rules.len() > MAX_RULES {
+        return Err(format!("A tool policy can have at most {MAX_RULES} rules"));
+    }
+    for key in policy.rules.keys() {
+        let valid = key
+            .split_once('/')
+            .is_some_and(|(server, tool)| !server.is_empty() && !tool.is_empty());
+        if !valid || key.len() > 400 {
+            return Err(format!(
+                "Invalid tool rule {key}: use server_id/tool_name or server_id/*"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_tool_policy(app: AppHandle) -> Result<ToolPolicy, String> {
+    load(&app)
+}
+
+/// Replaces the tool policy. A policy that lets destructive tools run
+/// without asking needs the second factor.
+#[tauri::command]
+pub async fn set_tool_policy(
+    app: AppHandle,
+    policy: ToolPolicy,
+    second_factor_code: Option<String>,
+) -> Result<(), String> {
+    validate(&policy)?;
+    if policy.allow_destructive {
+        second_factor::require(
+            &app,
+            ProtectedOperation::ApproveToolPolicy,
+            second_factor_code,
+        )
+        .await?;
+    }
+    store(&app, &policy)?;
+    log::info!(
+        "Saved tool policy: default {:?}, {} rules, destructive tools {}",
+        policy.default,
+        policy.rules.len(),
+        if policy.allow_destructive {
+            "allowed"
+        } else {
+            "asked about"
+        }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(rules: &[(&str, ToolPermission)]) -> ToolPolicy {
+        ToolPolicy {
+            rules: rules
+                .iter()
+                .map(|(key, permission)| (key.to_string(), *permission))
+                .collect(),
+            ..ToolPolicy::default()
+        }
+    }
+
+    #[test]
+    fn asks_without_a_rule_whatever_the_server_declares() {
+        let read_only = json!({"name": "list", "annotations": {"readOnlyHint": true}});
+        let plain = json!({"name": "list"});
+        let policy = policy(&[]);
+        assert_eq!(policy.permission("fs", &read_only), ToolPermission::Ask);
+        assert_eq!(policy.permission("fs", &plain), ToolPermission::Ask);
+    }
+
+    #[test]
+    fn exact_rules_win_over_server_rules() {
+        let tool = json!({"name": "read", "annotations": {"readOnlyHint": true}});
+        let policy = policy(&[
+            ("fs/*", ToolPermission::Deny),
+            ("fs/read", ToolPermission::Allow),
+        ]);
+        assert_eq!(policy.permission("fs", &tool), ToolPermission::Allow);
+        assert_eq!(
+            policy.permission("fs", &json!({"name": "other"})),
+            ToolPermission::Deny
+        );
+    }
+
+    #[test]
+    fn tools_are_destructive_unless_they_say_otherwise() {
+        assert!(is_destructive(&json!({"name": "write"})));
+        assert!(is_destructive(
+            &json!({"name": "write", "annotations": {"readOnlyHint": false}})
+        ));
+        assert!(!is_destructive(
+            &json!({"name": "read", "annotations": {"readOnlyHint": true}})
+        ));
+        assert!(!is_destructive(
+            &json!({"name": "add", "annotations": {"destructiveHint": false}})
+        ));
+    }
+
+    #[test]
+    fn allowed_destructive_tools_are_asked_about() {
+        let unannotated = json!({"name": "write"});
+        let safe = json!({"name": "add", "annotations": {"destructiveHint": false}});
+        let mut policy = policy(&[("fs/*", ToolPermission::Allow)]);
+        assert_eq!(policy.permission("fs", &unannotated), ToolPermission::Ask);
+        assert_eq!(policy.permission("fs", &safe), ToolPermission::Allow);
+        policy.allow_destructive = true;
+        assert_eq!(policy.permission("fs", &unannotated), ToolPermission::Allow);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(validate(&policy(&[("fs/read", ToolPermission::Allow)])).is_ok());
+        assert!(validate(&policy(&[("fs", ToolPermission::Allow)])).is_err());
+        assert!(validate(&policy(&[("/read", ToolPermission::Allow)])).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+// MCP Spawn Queue
+// ===============
+//
+// Caps how many MCP servers run at once. Spawn requests beyond the cap
+// either wait in a first-come, first-served queue until a server stops, or
+// are rejected straight away, depending on preferences. The queue state is
+// broadcast as `mcp-queue-status` whenever it changes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::McpProcesses;
+
+/// Safety net for missed wakeups while waiting in the queue
+const QUEUE_RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpConcurrencySettings {
+    /// Maximum number of servers running at once; `None` means unlimited
+    pub max_running: Option<u32>,
+    /// Queue spawns beyond the cap instead of rejecting them
+    pub queue_when_full: bool,
+}
+
+impl Default for McpConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_running: Some(10),
+            queue_when_full: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpSpawnErrorKind {
+    /// The cap is reached and queueing is disabled
+    CapacityReached,
+    /// The queued spawn was cancelled by `kill_mcp_server`
+    Cancelled,
+    /// A server with the same id is already running or starting
+    AlreadyRunning,
+    /// The config needs elevated privileges and lacks `allow_elevated`
+    ElevationRequired,
+    /// The process could not be started
+    SpawnFailed,
+}
+
+/// Error returned by `spawn_mcp_server`, so the frontend can tell a full
+/// queue apart from a broken server config.
+#[derive(Debug, Clone, Serialize)]
+pub struct McpSpawnError {
+    pub kind: McpSpawnErrorKind,
+    pub message: String,
+}
+
+impl From<String> for McpSpawnError {
+    fn from(message: String) -> Self {
+        Self {
+            kind: McpSpawnErrorKind::SpawnFailed,
+            message,
+        }
+    }
+}
+
+impl From<&str> for McpSpawnError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpQueueStatusEvent {
+    /// Servers running or currently starting
+    pub running: usize,
+    pub max_running: Option<u32>,
+    /// Server ids waiting for a slot, in order
+    pub queued: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct SpawnQueue {
+    waiting: VecDeque<String>,
+    /// Spawns that hold a slot but have not registered their process yet
+    starting: usize,
+}
+
+/// Holds a slot until the spawn finishes, successfully or not.
+pub struct SpawnSlot {
+    app: Option<AppHandle>,
+}
+
+impl Drop for SpawnSlot {
+    fn drop(&mut self) {
+        let Some(app) = self.app.take() else {
+            return;
+        };
+        let state = app.state::<McpProcesses>();
+        if let Ok(mut queue) = state.queue.lock() {
+            queue.starting = queue.starting.saturating_sub(1);
+        }
+        state.slot_freed.notify_waiters();
+    }
+}
+
+fn running_count(state: &McpProcesses, queue: &SpawnQueue) -> usize {
+    state.processes.lock().map(|p| p.len()).unwrap_or(0) + queue.starting
+}
+
+fn status(
+    state: &McpProcesses,
+    queue: &SpawnQueue,
+    max_running: Option<u32>,
+) -> McpQueueStatusEvent {
+    McpQueueStatusEvent {
+        running: running_count(state, queue),
+        max_running,
+        queued: queue.waiting.iter().cloned().collect(),
+    }
+}
+
+fn current_settings(app: &AppHandle) -> McpConcurrencySettings {
+    crate::read_preferences(app)
+        .map(|p| p.mcp_concurrency)
+        .unwrap_or_default()
+}
+
+/// The current queue state.
+pub fn queue_status(app: &AppHandle) -> McpQueueStatusEvent {
+    let state = app.state::<McpProcesses>();
+    let max_running = current_settings(app).max_running;
+    let status = match state.queue.lock() {
+        Ok(queue) => status(&state, &queue, max_running),
+        Err(_) => McpQueueStatusEvent {
+            running: 0,
+            max_running,
+            queued: Vec::new(),
+        },
+    };
+    status
+}
+
+/// Emits the current queue state. Called whenever a server stops.
+pub fn emit_queue_status(app: &AppHandle) {
+    let state = app.state::<McpProcesses>();
+    let Ok(queue) = state.queue.lock() else {
+        return;
+    };
+    let event = status(&state, &queue, current_settings(app).max_running);
+    drop(queue);
+
+    state.slot_freed.notify_waiters();
+    let _ = app.emit("mcp-queue-status", event);
+}
+
+/// Drops a server from the queue. Returns whether it was waiting.
+pub fn cancel_queued(app: &AppHandle, server_id: &str) -> bool {
+    let state = app.state::<McpProcesses>();
+    let Ok(mut queue) = state.queue.lock() else {
+        return false;
+    };
+    let before = queue.waiting.len();
+    queue.waiting.retain(|id| id != server_id);
+    let cancelled = queue.waiting.len() != before;
+    drop(queue);
+
+    if cancelled {
+        log::info!("Cancelled queued spawn of MCP server {server_id}");
+        emit_queue_status(app);
+    }
+    cancelled
+}
+
+/// Waits for a free slot according to the concurrency preferences.
+pub async fn acquire_slot(app: &AppHandle, server_id: &str) -> Result<SpawnSlot, McpSpawnError> {
+    let settings = current_settings(app);
+    let Some(max_running) = settings.max_running else {
+        return Ok(SpawnSlot { app: None });
+    };
+
+    let state = app.state::<McpProcesses>();
+    let mut queued = false;
+
+    loop {
+        // Register for wakeups before checking, so a slot freed in between isn't missed
+        let notified = state.slot_freed.notified();
+
+        {
+            let mut queue = state
+                .queue
+                .lock()
+                .map_err(|e| McpSpawnError::from(e.to_string()))?;
+
+            if queued && !queue.waiting.contains(&server_id.to_string()) {
+                return Err(McpSpawnError {
+                    kind: McpSpawnErrorKind::Cancelled,
+                    message: format!("Start of MCP server {server_id} was cancelled"),
+                });
+            }
+
+            let running = running_count(&state, &queue);
+            let first_in_line = queue.waiting.front().is_none_or(|id| id == server_id);
+            if running < max_running as usize && first_in_line {
+                queue.waiting.retain(|id| id != server_id);
+                queue.starting += 1;
+                let event = status(&state, &queue, Some(max_running));
+                drop(queue);
+                let _ = app.emit("mcp-queue-status", event);
+                return Ok(SpawnSlot {
+                    app: Some(app.clone()),
+                });
+            }
+
+            if !settings.queue_when_full {
+                return Err(McpSpawnError {
+                    kind: McpSpawnErrorKind::CapacityReached,
+                    message: format!(
+                        "Too many MCP servers running ({running} of {max_running}). Stop a server or raise the limit in preferences."
+                    ),
+                });
+            }
+
+            if !queued {
+                log::info!("MCP server {server_id} queued ({running} of {max_running} running)");
+                queue.waiting.push_back(server_id.to_string());
+                queued = true;
+                let event = status(&state, &queue, Some(max_running));
+                drop(queue);
+                let _ = app.emit("mcp-queue-status", event);
+            }
+        }
+
+        let _ = tokio::time::timeout(QUEUE_RECHECK_INTERVAL, notified).await;
+    }
+}
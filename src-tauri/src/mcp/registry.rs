@@ -0,0 +1,422 @@
+// MCP Server Registry
+// ===================
+//
+// Downloads the official MCP server registry (and optionally npm packages
+// named `mcp-server-*`) and flattens each listing into what the install
+// dialog needs: a launch command and the environment variables to ask for.
+// Results are cached on disk for a day, and a stale cache is served when the
+// registry cannot be reached.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::McpDockerConfig;
+
+const REGISTRY_URL: &str = "https://registry.modelcontextprotocol.io/v0/servers";
+const NPM_SEARCH_URL: &str = "https://registry.npmjs.org/-/v1/search";
+
+/// How long a cached listing is used before refetching
+const CACHE_TTL_MS: u64 = 24 * 60 * 60 * 1000;
+const REGISTRY_PAGE_SIZE: usize = 100;
+/// Upper bound on registry pages, in case the cursor never ends
+const MAX_REGISTRY_PAGES: usize = 50;
+const NPM_SEARCH_SIZE: usize = 250;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRegistryEnvVar {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+    pub secret: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRegistryEntry {
+    pub name: String,
+    pub description: String,
+    pub version: Option<String>,
+    /// "registry" or "npm"
+    pub source: String,
+    /// Launch command for stdio servers, e.g. `npx`
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Endpoint for remote (HTTP) servers
+    pub url: Option<String>,
+    /// Image for servers distributed as containers
+    #[serde(default)]
+    pub docker: Option<McpDockerConfig>,
+    #[serde(default)]
+    pub env_vars: Vec<McpRegistryEnvVar>,
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRegistryListing {
+    /// UNIX timestamp (milliseconds) of the fetch
+    #[serde(deserialize_with = "crate::time::legacy_ms")]
+    pub fetched_at: u64,
+    /// The listing came from the cache after a failed refresh
+    #[serde(default)]
+    pub stale: bool,
+    pub include_npm: bool,
+    pub entries: Vec<McpRegistryEntry>,
+}
+
+// Registry API types. Field names changed between registry API revisions,
+// hence the aliases.
+
+#[derive(Debug, Deserialize)]
+struct RegistryPage {
+    #[serde(default)]
+    servers: Vec<RegistryItem>,
+    #[serde(default)]
+    metadata: Option<RegistryMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryMetadata {
+    #[serde(default, alias = "next_cursor", rename = "nextCursor")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RegistryItem {
+    Wrapped { server: RegistryServer },
+    Bare(RegistryServer),
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryServer {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    repository: Option<RegistryRepository>,
+    #[serde(default)]
+    packages: Vec<RegistryPackage>,
+    #[serde(default)]
+    remotes: Vec<RegistryRemote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryRepository {
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryPackage {
+    #[serde(
+        alias = "registry_type",
+        alias = "registry_name",
+        rename = "registryType"
+    )]
+    registry_type: String,
+    #[serde(alias = "name")]
+    identifier: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default, alias = "runtime_hint", rename = "runtimeHint")]
+    runtime_hint: Option<String>,
+    #[serde(
+        default,
+        alias = "environment_variables",
+        rename = "environmentVariables"
+    )]
+    environment_variables: Vec<RegistryEnvVar>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryEnvVar {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, alias = "is_required", rename = "isRequired")]
+    is_required: bool,
+    #[serde(default, alias = "is_secret", rename = "isSecret")]
+    is_secret: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryRemote {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmSearch {
+    objects: Vec<NpmObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmObject {
+    package: NpmPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmPackage {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    links: Option<NpmLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmLinks {
+    #[serde(default)]
+    repository: Option<String>,
+}
+
+fn get_cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get app cache directory: {e}"))?;
+
+    std::fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("Failed to create cache directory: {e}"))?;
+
+    Ok(cache_dir.join("mcp-registry.json"))
+}
+
+fn read_cache(app: &AppHandle) -> Option<McpRegistryListing> {
+    let path = get_cache_path(app).ok()?;
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents)
+        .map_err(|e| log::warn!("Ignoring unreadable MCP registry cache: {e}"))
+        .ok()
+}
+
+fn write_cache(app: &AppHandle, listing: &McpRegistryListing) -> Result<(), String> {
+    let json = serde_json::to_vec(listing)
+        .map_err(|e| format!("Failed to serialize registry cache: {e}"))?;
+    crate::export::write_atomic(&get_cache_path(app)?, &json)
+}
+
+/// Maps a registry server to an entry, preferring packages we know how to
+/// launch over remote endpoints.
+fn registry_entry(server: RegistryServer) -> McpRegistryEntry {
+    let mut entry = McpRegistryEntry {
+        name: server.name,
+        description: server.description,
+        version: server.version,
+        source: "registry".to_string(),
+        command: None,
+        args: Vec::new(),
+        url: None,
+        docker: None,
+        env_vars: Vec::new(),
+        repository: server.repository.and_then(|r| r.url),
+    };
+
+    let package = server.packages.into_iter().find(|p| {
+        matches!(
+            p.registry_type.as_str(),
+            "npm" | "pypi" | "oci" | "docker" | "nuget"
+        )
+    });
+
+    match package {
+        Some(package) => {
+            let spec = |separator: &str| match &package.version {
+                Some(version) => format!("{}{separator}{version}", package.identifier),
+                None => package.identifier.clone(),
+            };
+            match package.registry_type.as_str() {
+                // Container images run through the docker transport
+                "oci" | "docker" => {
+                    entry.docker = Some(McpDockerConfig {
+                        image: spec(":"),
+                        ..Default::default()
+                    });
+                }
+                registry_type => {
+                    let (command, args) = match registry_type {
+                        "npm" => ("npx", vec!["-y".to_string(), spec("@")]),
+                        "pypi" => ("uvx", vec![spec("==")]),
+                        _ => ("dnx", vec![spec("@"), "--yes".to_string()]),
+                    };
+                    entry.command = Some(package.runtime_hint.unwrap_or(command.to_string()));
+                    entry.args = args;
+                }
+            }
+            entry.env_vars = package
+                .environment_variables
+                .into_iter()
+                .map(|v| McpRegistryEnvVar {
+                    name: v.name,
+                    description: v.description,
+                    required: v.is_required,
+                    secret: v.is_secret,
+                })
+                .collect();
+        }
+        None => entry.url = server.remotes.into_iter().next().map(|r| r.url),
+    }
+
+    entry
+}
+
+/// Whether an npm package name follows the `mcp-server-*` convention,
+/// scoped or not.
+fn is_mcp_server_package(name: &str) -> bool {
+    name.rsplit('/')
+        .next()
+        .is_some_and(|n| n.starts_with("mcp-server-"))
+}
+
+async fn fetch_registry(client: &reqwest::Client) -> Result<Vec<McpRegistryEntry>, String> {
+    let mut entries = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..MAX_REGISTRY_PAGES {
+        let mut request = client
+            .get(REGISTRY_URL)
+            .query(&[("limit", REGISTRY_PAGE_SIZE.to_string())]);
+        if let Some(cursor) = &cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let page: RegistryPage = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch MCP registry: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse MCP registry: {e}"))?;
+
+        entries.extend(page.servers.into_iter().map(|item| match item {
+            RegistryItem::Wrapped { server } | RegistryItem::Bare(server) => registry_entry(server),
+        }));
+
+        cursor = page.metadata.and_then(|m| m.next_cursor);
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    // The registry lists every published version of a server
+    let mut seen = std::collections::HashSet::new();
+    entries.retain(|e| seen.insert(e.name.clone()));
+
+    Ok(entries)
+}
+
+async fn fetch_npm(client: &reqwest::Client) -> Result<Vec<McpRegistryEntry>, String> {
+    let search: NpmSearch = client
+        .get(NPM_SEARCH_URL)
+        .query(&[
+            ("text", "mcp-server".to_string()),
+            ("size", NPM_SEARCH_SIZE.to_string()),
+        ])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to search npm: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse npm search results: {e}"))?;
+
+    Ok(search
+        .objects
+        .into_iter()
+        .map(|o| o.package)
+        .filter(|p| is_mcp_server_package(&p.name))
+        .map(|p| McpRegistryEntry {
+            args: vec!["-y".to_string(), p.name.clone()],
+            name: p.name,
+            description: p.description.unwrap_or_default(),
+            version: p.version,
+            source: "npm".to_string(),
+            command: Some("npx".to_string()),
+            url: None,
+            docker: None,
+            // npm metadata does not declare required environment variables
+            env_vars: Vec::new(),
+            repository: p.links.and_then(|l| l.repository),
+        })
+        .collect())
+}
+
+/// Returns the MCP server registry, from the cache when it is fresh.
+#[tauri::command]
+pub async fn fetch_mcp_registry(
+    app: AppHandle,
+    include_npm: Option<bool>,
+    force_refresh: Option<bool>,
+) -> Result<McpRegistryListing, String> {
+    let include_npm = include_npm.unwrap_or(false);
+    let cached = read_cache(&app);
+
+    if let Some(cached) = &cached {
+        let fresh = crate::time::now_ms().saturating_sub(cached.fetched_at) < CACHE_TTL_MS;
+        if fresh && cached.include_npm == include_npm && !force_refresh.unwrap_or(false) {
+            log::debug!("MCP registry cache hit");
+            return Ok(cached.clone());
+        }
+    }
+
+    log::info!("Fetching MCP registry (npm: {include_npm})");
+    let client = crate::network::client_builder()
+        .timeout(Duration::from_secs(20))
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+    let fetched = async {
+        let mut entries = fetch_registry(&client).await?;
+        if include_npm {
+            // Registry listings win over npm results for the same package
+            let known: std::collections::HashSet<String> = entries
+                .iter()
+                .filter(|e| e.command.as_deref() == Some("npx"))
+                .filter_map(|e| e.args.get(1))
+                .map(|spec| spec.rsplit_once('@').map_or(spec.as_str(), |(n, _)| n))
+                .map(String::from)
+                .collect();
+            match fetch_npm(&client).await {
+                Ok(npm) => entries.extend(npm.into_iter().filter(|e| !known.contains(&e.name))),
+                Err(e) => log::warn!("Skipping npm results: {e}"),
+            }
+        }
+        Ok::<_, String>(entries)
+    }
+    .await;
+
+    match fetched {
+        Ok(entries) => {
+            let listing = McpRegistryListing {
+                fetched_at: crate::time::now_ms(),
+                stale: false,
+                include_npm,
+                entries,
+            };
+            if let Err(e) = write_cache(&app, &listing) {
+                log::warn!("Failed to cache MCP registry: {e}");
+            }
+            log::info!("Fetched {} MCP registry entries", listing.entries.len());
+            Ok(listing)
+        }
+        Err(e) => {
+            log::error!("{e}");
+            match cached {
+                Some(cached) => Ok(McpRegistryListing {
+                    stale: true,
+                    ..cached
+                }),
+                None => Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// MCP Runtime Doctor
+// ==================
+//
+// Most MCP servers are launched through a language runtime (npx, uvx,
+// docker, deno). When one is missing the server just fails with an opaque
+// "command not found" on stderr, so this probes each runtime through the
+// same login shell used to spawn servers and reports what was found.
+
+use serde::Serialize;
+
+use super::{login_shell, run_in_login_shell};
+
+struct RuntimeProbe {
+    name: &'static str,
+    /// Commands MCP configs use that are provided by this runtime
+    commands: &'static [&'static str],
+    version_command: &'static str,
+    install_hint: &'static str,
+}
+
+const PROBES: &[RuntimeProbe] = &[
+    RuntimeProbe {
+        name: "node",
+        commands: &["node", "npx", "npm"],
+        version_command: "node --version",
+        install_hint:
+            "Install Node.js from https://nodejs.org (or via nvm / Homebrew: brew install node)",
+    },
+    RuntimeProbe {
+        name: "npx",
+        commands: &["npx"],
+        version_command: "npx --version",
+        install_hint: "npx ships with npm; reinstall Node.js or run: npm install -g npm",
+    },
+    RuntimeProbe {
+        name: "python",
+        commands: &["python", "python3"],
+        version_command: "python3 --version 2>&1 || python --version 2>&1",
+        install_hint:
+            "Install Python 3 from https://www.python.org or via Homebrew: brew install python",
+    },
+    RuntimeProbe {
+        name: "uvx",
+        commands: &["uvx", "uv"],
+        version_command: "uvx --version",
+        install_hint: "Install uv: curl -LsSf https://astral.sh/uv/install.sh | sh",
+    },
+    RuntimeProbe {
+        name: "docker",
+        commands: &["docker"],
+        version_command: "docker --version",
+        install_hint: "Install Docker Desktop from https://www.docker.com/products/docker-desktop",
+    },
+    RuntimeProbe {
+        name: "deno",
+        commands: &["deno"],
+        version_command: "deno --version | head -n 1",
+        install_hint: "Install Deno: curl -fsSL https://deno.land/install.sh | sh",
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRuntimeStatus {
+    pub name: String,
+    pub found: bool,
+    /// Resolved executable path
+    pub path: Option<String>,
+    pub version: Option<String>,
+    /// Config commands that depend on this runtime
+    pub commands: Vec<String>,
+    /// Suggested fix when the runtime is missing
+    pub install_hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpRuntimeReport {
+    /// The login shell used to resolve commands
+    pub shell: String,
+    /// PATH as seen by the login shell
+    pub path: String,
+    pub runtimes: Vec<McpRuntimeStatus>,
+}
+
+fn first_line(output: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(String::from)
+}
+
+fn probe(shell: &str, probe: &RuntimeProbe) -> McpRuntimeStatus {
+    let binary = probe
+        .version_command
+        .split_whitespace()
+        .next()
+        .unwrap_or(probe.name);
+
+    let path = run_in_login_shell(shell, &format!("command -v {binary}"))
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| first_line(&o.stdout));
+
+    let version = path.as_ref().and_then(|_| {
+        run_in_login_shell(shell, probe.version_command)
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| first_line(&o.stdout))
+    });
+
+    let found = path.is_some();
+    McpRuntimeStatus {
+        name: probe.name.to_string(),
+        found,
+        path,
+        version,
+        commands: probe.commands.iter().map(|c| c.to_string()).collect(),
+        install_hint: (!found).then(|| probe.install_hint.to_string()),
+    }
+}
+
+/// Probes node/npx, python/uvx, docker and deno in parallel.
+#[tauri::command]
+pub async fn check_mcp_runtimes() -> Result<McpRuntimeReport, String> {
+    log::info!("Checking MCP runtimes");
+
+    tauri::async_runtime::spawn_blocking(|| {
+        let shell = login_shell();
+
+        let path = run_in_login_shell(&shell, "printf '%s' \"$PATH\"")
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+
+        let runtimes = std::thread::scope(|scope| {
+            let handles: Vec<_> = PROBES
+                .iter()
+                .map(|p| {
+                    let shell = &shell;
+                    scope.spawn(move || probe(shell, p))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().ok())
+                .collect::<Vec<_>>()
+        });
+
+        for runtime in &runtimes {
+            log::debug!(
+                "Runtime {}: {}",
+                runtime.name,
+                runtime.version.as_deref().unwrap_or("not found")
+            );
+        }
+
+        McpRuntimeReport {
+            shell,
+            path,
+            runtimes,
+        }
+    })
+    .await
+    .map_err(|e| format!("Runtime check failed: {e}"))
+}
@@ -0,0 +1,211 @@
+// MCP Startup Progress
+// ====================
+//
+// Servers launched through npx, uvx or docker may spend a minute installing
+// packages before they answer `initialize`. A watcher thread follows each
+// spawned server until it writes its first line to stdout and emits
+// `mcp-startup-progress` once a second, classifying the wait from stderr:
+// install output means it is still installing dependencies, silence for
+// `STALL_AFTER` means it looks hung. A server that isn't ready within its
+// `startup_timeout_ms` is stopped. While it keeps printing install output,
+// the timeout counts from its last install line rather than from spawn, so
+// a slow first download isn't mistaken for a hang.
+
+use regex::Regex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::{queue, McpProcesses};
+use crate::audit::McpAuditLog;
+
+pub const DEFAULT_STARTUP_TIMEOUT_MS: u64 = 60_000;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// Quiet time after which a server that isn't ready is reported as stalled
+const STALL_AFTER: Duration = Duration::from_secs(15);
+
+/// stderr output of package managers and image pulls
+static INSTALL_PATTERNS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(npm (warn|notice|http)|added \d+ packages?|need to install|downloading|downloaded|installing|installed \d+ packages?|resolved \d+ packages?|prepared \d+ packages?|collecting |pulling (from|fs layer)|extracting|fetching|building wheel)",
+    )
+    .expect("valid install pattern")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Running, nothing suggests it is installing
+    Starting,
+    /// stderr shows package or image downloads
+    Installing,
+    /// No output for a while
+    Stalled,
+    /// First stdout line received
+    Ready,
+    /// Not ready within the timeout; the server was stopped
+    TimedOut,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpStartupProgress {
+    pub server_id: String,
+    pub phase: StartupPhase,
+    pub elapsed_ms: u64,
+    pub timeout_ms: u64,
+    /// Most recent stderr line, if any
+    pub last_stderr: Option<String>,
+}
+
+/// Startup state shared between a server's reader threads and its watcher.
+pub struct StartupWatch {
+    started: Instant,
+    ready: AtomicBool,
+    /// When stderr last printed anything, and what
+    last_output: Mutex<Option<(Instant, String)>>,
+    /// When stderr last looked like an installer
+    last_install: Mutex<Option<Instant>>,
+}
+
+impl StartupWatch {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            ready: AtomicBool::new(false),
+            last_output: Mutex::new(None),
+            last_install: Mutex::new(None),
+        }
+    }
+
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+    }
+
+    pub fn observe_stderr(&self, line: &str) {
+        if self.ready.load(Ordering::Relaxed) {
+            return;
+        }
+        let now = Instant::now();
+        if let Ok(mut last) = self.last_output.lock() {
+            *last = Some((now, line.to_string()));
+        }
+        if INSTALL_PATTERNS.is_match(line) {
+            if let Ok(mut last) = self.last_install.lock() {
+                *last = Some(now);
+            }
+        }
+    }
+
+    /// Current phase, and whether the timeout has passed.
+    fn phase(&self, timeout: Duration) -> (StartupPhase, bool) {
+        if self.ready.load(Ordering::Relaxed) {
+            return (StartupPhase::Ready, false);
+        }
+        let last_output = self
+            .last_output
+            .lock()
+            .ok()
+            .and_then(|last| last.as_ref().map(|(at, _)| *at));
+        let last_install = self.last_install.lock().ok().and_then(|last| *last);
+
+        let quiet = last_output.unwrap_or(self.started).elapsed();
+        let installing = last_install.is_some_and(|at| at.elapsed() < STALL_AFTER);
+        let timed_out = last_install.unwrap_or(self.started).elapsed() >= timeout;
+
+        let phase = if quiet >= STALL_AFTER {
+            StartupPhase::Stalled
+        } else if installing {
+            StartupPhase::Installing
+        } else {
+            StartupPhase::Starting
+        };
+        (phase, timed_out)
+    }
+
+    fn last_stderr(&self) -> Option<String> {
+        self.last_output
+            .lock()
+            .ok()
+            .and_then(|last| last.as_ref().map(|(_, line)| line.clone()))
+    }
+}
+
+/// Stops a server that didn't become ready, unless it was already replaced.
+fn stop_server(app: &AppHandle, server_id: &str, pid: u32) {
+    let state = app.state::<McpProcesses>();
+    let Ok(mut processes) = state.processes.lock() else {
+        return;
+    };
+    if processes.get(server_id).is_none_or(|p| p.child.id() != pid) {
+        return;
+    }
+    let process = processes.remove(server_id);
+    drop(processes);
+    // Killed with the table unlocked, since removing a container takes a while
+    if let Some(mut process) = process {
+        if let Err(e) = process.terminate() {
+            log::error!("Failed to stop MCP server {server_id}: {e}");
+        }
+    }
+
+    app.state::<McpAuditLog>().abandon_server(app, server_id);
+    queue::emit_queue_status(app);
+}
+
+/// Follows a spawned server until it is ready, exits or times out.
+pub fn watch(app: AppHandle, server_id: String, pid: u32, watch: &StartupWatch, timeout_ms: u64) {
+    let timeout = Duration::from_millis(timeout_ms);
+    let mut last_phase = None;
+
+    loop {
+        std::thread::sleep(PROGRESS_INTERVAL);
+
+        // Exited or stopped; the exit monitor reports that
+        let running = app
+            .state::<McpProcesses>()
+            .processes
+            .lock()
+            .map(|processes| {
+                processes
+                    .get(&server_id)
+                    .is_some_and(|p| p.child.id() == pid)
+            })
+            .unwrap_or(false);
+        if !running {
+            return;
+        }
+
+        let (mut phase, timed_out) = watch.phase(timeout);
+        if timed_out && phase != StartupPhase::Ready {
+            phase = StartupPhase::TimedOut;
+        }
+        if last_phase != Some(phase) {
+            log::info!("MCP server {server_id} startup: {phase:?}");
+            last_phase = Some(phase);
+        }
+
+        let _ = app.emit(
+            "mcp-startup-progress",
+            McpStartupProgress {
+                server_id: server_id.clone(),
+                phase,
+                elapsed_ms: watch.started.elapsed().as_millis() as u64,
+                timeout_ms,
+                last_stderr: watch.last_stderr(),
+            },
+        );
+
+        match phase {
+            StartupPhase::Ready => return,
+            StartupPhase::TimedOut => {
+                log::warn!("MCP server {server_id} not ready after {timeout_ms}ms, stopping it");
+                stop_server(&app, &server_id, pid);
+                return;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// MCP Resource Usage
+// ==================
+//
+// Samples CPU, memory and open file descriptors of running MCP servers so
+// the UI can show per-server resource usage and spot leaks. A server is
+// started through a shell, so each sample covers the whole process tree
+// below the spawned pid. For docker-backed servers this is the docker CLI
+// only; the container itself runs under the docker daemon.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::McpProcesses;
+
+/// How often `mcp-stats` is emitted while servers are running
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Keeps the previous sample around; CPU usage is computed from the
+/// difference between two refreshes.
+pub struct McpStatsSampler {
+    system: Mutex<System>,
+}
+
+impl Default for McpStatsSampler {
+    fn default() -> Self {
+        Self {
+            system: Mutex::new(System::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStats {
+    pub server_id: String,
+    pub pid: u32,
+    /// Processes in the server's tree, including the shell
+    pub process_count: usize,
+    /// Resident memory of the whole tree
+    pub rss_bytes: u64,
+    /// CPU usage since the previous sample; 100 is one full core
+    pub cpu_percent: f32,
+    /// Open file descriptors, `None` where the platform doesn't report them
+    pub open_files: Option<u64>,
+    pub uptime_ms: u64,
+}
+
+/// Refreshes the process table and sums usage over each server's tree.
+fn sample(sampler: &McpStatsSampler, servers: &[(String, u32, Instant)]) -> Vec<McpServerStats> {
+    let Ok(mut system) = sampler.system.lock() else {
+        return Vec::new();
+    };
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in system.processes() {
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+
+    servers
+        .iter()
+        .filter_map(|(server_id, pid, started)| {
+            let root = Pid::from_u32(*pid);
+            system.process(root)?;
+
+            let mut stats = McpServerStats {
+                server_id: server_id.clone(),
+                pid: *pid,
+                process_count: 0,
+                rss_bytes: 0,
+                cpu_percent: 0.0,
+                open_files: Some(0),
+                uptime_ms: started.elapsed().as_millis() as u64,
+            };
+            let mut pending = vec![root];
+            while let Some(pid) = pending.pop() {
+                let Some(process) = system.process(pid) else {
+                    continue;
+                };
+                stats.process_count += 1;
+                stats.rss_bytes += process.memory();
+                stats.cpu_percent += process.cpu_usage();
+                stats.open_files = stats
+                    .open_files
+                    .zip(process.open_files())
+                    .map(|(total, count)| total + count as u64);
+                if let Some(descendants) = children.get(&pid) {
+                    pending.extend(descendants);
+                }
+            }
+            Some(stats)
+        })
+        .collect()
+}
+
+fn running_servers(state: &McpProcesses) -> Vec<(String, u32, Instant)> {
+    state
+        .processes
+        .lock()
+        .map(|processes| {
+            processes
+                .iter()
+                .map(|(id, p)| (id.clone(), p.child.id(), p.started))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Starts the thread that emits `mcp-stats` every `SAMPLE_INTERVAL` while
+/// any server is running.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SAMPLE_INTERVAL);
+
+        let servers = running_servers(&app.state::<McpProcesses>());
+        if servers.is_empty() {
+            continue;
+        }
+        let stats = sample(&app.state::<McpStatsSampler>(), &servers);
+        let _ = app.emit("mcp-stats", stats);
+    });
+}
+
+/// Returns current resource usage of a running server.
+#[tauri::command]
+pub async fn get_mcp_server_stats(
+    state: State<'_, McpProcesses>,
+    sampler: State<'_, McpStatsSampler>,
+    server_id: String,
+) -> Result<McpServerStats, String> {
+    let server = running_servers(&state)
+        .into_iter()
+        .find(|(id, _, _)| *id == server_id)
+        .ok_or_else(|| format!("MCP server {} not found", server_id))?;
+
+    sample(&sampler, &[server])
+        .pop()
+        .ok_or_else(|| format!("MCP server {server_id} has exited"))
+}
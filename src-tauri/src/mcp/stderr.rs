@@ -0,0 +1,216 @@
+// Server stderr Filtering and Logs
+// ================================
+//
+// Many servers write debug noise to stderr. Every line still goes to a
+// per-server log file under `mcp/` in the app log directory, but only lines
+// at or above the server's `log_level`, and not matching one of its
+// `stderr_suppress` patterns, are forwarded to the webview. The level of a
+// line is read from common log formats (`[DEBUG]`, `WARN:`, `level=info`,
+// JSON `"level": "error"` or pino's numeric levels); lines without a
+// recognizable level, such as stack trace continuations, are always
+// forwarded unless suppressed.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::{AppHandle, Manager};
+
+use super::McpServerConfig;
+
+/// A log file is rotated to `<id>.log.1` once it grows past this
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_SUPPRESS_PATTERNS: usize = 50;
+const MAX_PATTERN_CHARS: usize = 500;
+
+/// A level word near the start of the line, optionally bracketed or
+/// written as `level=...`
+static LEVEL_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)^(?:\S+\s+){0,3}?[\[(<]?(?:level[=:]\s*"?)?(trace|debug|info|notice|warn|warning|error|err|fatal|critical|panic)\b"#,
+    )
+    .expect("valid level pattern")
+});
+
+/// `"level": "debug"` or `"level": 20` anywhere in a JSON log line
+static JSON_LEVEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)"(?:level|severity)"\s*:\s*"?(\w+)"?"#).expect("valid JSON level pattern")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn parse(word: &str) -> Option<LogLevel> {
+        match word.to_ascii_lowercase().as_str() {
+            "trace" | "10" => Some(LogLevel::Trace),
+            "debug" | "20" => Some(LogLevel::Debug),
+            "info" | "notice" | "30" => Some(LogLevel::Info),
+            "warn" | "warning" | "40" => Some(LogLevel::Warn),
+            "error" | "err" | "fatal" | "critical" | "panic" | "50" | "60" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+
+    /// The level a stderr line was logged at, if it says.
+    fn of_line(line: &str) -> Option<LogLevel> {
+        let line = line.trim_start();
+        if line.starts_with('{') {
+            if let Some(level) = JSON_LEVEL
+                .captures(line)
+                .and_then(|c| LogLevel::parse(&c[1]))
+            {
+                return Some(level);
+            }
+        }
+        LEVEL_PREFIX
+            .captures(line)
+            .and_then(|c| LogLevel::parse(&c[1]))
+    }
+}
+
+/// Decides which stderr lines reach the webview.
+#[derive(Debug, Default)]
+pub struct StderrFilter {
+    level: Option<LogLevel>,
+    suppress: Vec<Regex>,
+}
+
+pub type SharedStderrFilter = Arc<Mutex<StderrFilter>>;
+
+impl StderrFilter {
+    /// Compiles the filter of a server config, rejecting invalid patterns.
+    pub fn new(config: &McpServerConfig) -> Result<Self, String> {
+        if config.stderr_suppress.len() > MAX_SUPPRESS_PATTERNS {
+            return Err(format!(
+                "Too many stderr suppression patterns (max {MAX_SUPPRESS_PATTERNS})"
+            ));
+        }
+        let suppress = config
+            .stderr_suppress
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .map(|pattern| {
+                crate::validate_string_input(pattern, MAX_PATTERN_CHARS, "Suppression pattern")?;
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid suppression pattern {pattern:?}: {e}"))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(StderrFilter {
+            level: config.log_level,
+            suppress,
+        })
+    }
+
+    pub fn forwards(&self, line: &str) -> bool {
+        if self.suppress.iter().any(|pattern| pattern.is_match(line)) {
+            return false;
+        }
+        match (self.level, LogLevel::of_line(line)) {
+            (Some(min), Some(level)) => level >= min,
+            _ => true,
+        }
+    }
+}
+
+/// Appends a server's stderr to its log file, rotating it when it grows
+/// too large.
+pub struct ServerLog {
+    path: PathBuf,
+    /// `None` after a failed rotation
+    file: Option<File>,
+    size: u64,
+}
+
+/// Where server logs go in the app log directory `app_log_dir`.
+pub fn log_dir(app_log_dir: &Path) -> PathBuf {
+    app_log_dir.join("mcp")
+}
+
+impl ServerLog {
+    /// Opens the log of a server, or `None` (logged) if it can't be written.
+    pub fn open(app: &AppHandle, server_id: &str) -> Option<ServerLog> {
+        let open = || -> Result<ServerLog, String> {
+            let dir = log_dir(
+                &app.path()
+                    .app_log_dir()
+                    .map_err(|e| format!("Failed to get log directory: {e}"))?,
+            );
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create MCP log directory: {e}"))?;
+
+            // Server ids come from the frontend; keep them from escaping the directory
+            let name: String = server_id
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let path = dir.join(format!("{name}.log"));
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("Failed to open MCP server log: {e}"))?;
+            let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+            Ok(ServerLog {
+                path,
+                file: Some(file),
+                size,
+            })
+        };
+
+        open()
+            .map_err(|e| log::warn!("MCP server {server_id} stderr won't be logged: {e}"))
+            .ok()
+    }
+
+    pub fn write(&mut self, line: &str) {
+        if self.size >= MAX_LOG_BYTES {
+            self.rotate();
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let entry = format!(
+            "{} {line}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f")
+        );
+        match file.write_all(entry.as_bytes()) {
+            Ok(()) => self.size += entry.len() as u64,
+            Err(e) => log::debug!("Failed to write {:?}: {e}", self.path),
+        }
+    }
+
+    fn rotate(&mut self) {
+        // Close the file first; Windows can't rename an open file
+        self.file = None;
+        self.size = 0;
+
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        let reopened = std::fs::rename(&self.path, &rotated).and_then(|_| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+        });
+        match reopened {
+            Ok(file) => self.file = Some(file),
+            Err(e) => log::warn!("Failed to rotate {:?}, no longer logging: {e}", self.path),
+        }
+    }
+}
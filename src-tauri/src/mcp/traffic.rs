@@ -0,0 +1,153 @@
+// MCP Traffic Inspector
+// =====================
+//
+// Opt-in, per-server capture of every JSON-RPC message exchanged with an MCP
+// server, for debugging servers that misbehave. While recording is enabled,
+// each line written to the server and each line it writes back is kept in a
+// bounded ring buffer with a timestamp, direction and sequence number. The
+// frontend polls with `get_mcp_traffic(server_id, since)` using the last
+// sequence number it saw, and a capture can be exported as JSON Lines.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::State;
+
+/// Messages kept per server; the oldest are dropped first
+const TRAFFIC_BUFFER_SIZE: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficDirection {
+    /// Written by Nexus to the server
+    Outbound,
+    /// Written by the server to Nexus
+    Inbound,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficEntry {
+    /// Increases by one per recorded message, across all servers
+    pub seq: u64,
+    /// UNIX timestamp in milliseconds
+    pub timestamp_ms: u64,
+    pub direction: TrafficDirection,
+    pub data: String,
+}
+
+#[derive(Default)]
+pub struct McpTrafficRecorder {
+    /// Servers that currently record
+    enabled: Mutex<HashSet<String>>,
+    buffers: Mutex<HashMap<String, VecDeque<TrafficEntry>>>,
+    next_seq: AtomicU64,
+}
+
+impl McpTrafficRecorder {
+    /// Records one message if recording is enabled for the server. `data`
+    /// may hold several newline-delimited messages.
+    pub fn record(&self, server_id: &str, direction: TrafficDirection, data: &str) {
+        let recording = self
+            .enabled
+            .lock()
+            .map(|enabled| enabled.contains(server_id))
+            .unwrap_or(false);
+        if !recording {
+            return;
+        }
+
+        let timestamp_ms = crate::time::now_ms();
+        let Ok(mut buffers) = self.buffers.lock() else {
+            return;
+        };
+        let buffer = buffers.entry(server_id.to_string()).or_default();
+        for line in data.lines().filter(|l| !l.trim().is_empty()) {
+            if buffer.len() == TRAFFIC_BUFFER_SIZE {
+                buffer.pop_front();
+            }
+            buffer.push_back(TrafficEntry {
+                seq: self.next_seq.fetch_add(1, Ordering::Relaxed) + 1,
+                timestamp_ms,
+                direction,
+                data: line.to_string(),
+            });
+        }
+    }
+
+    fn entries(&self, server_id: &str, since: u64) -> Result<Vec<TrafficEntry>, String> {
+        let buffers = self.buffers.lock().map_err(|e| e.to_string())?;
+        Ok(buffers
+            .get(server_id)
+            .map(|buffer| buffer.iter().filter(|e| e.seq > since).cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Turns traffic recording for a server on or off. Turning it off keeps the
+/// captured messages; `clear` drops them.
+#[tauri::command]
+pub async fn set_mcp_traffic_recording(
+    recorder: State<'_, McpTrafficRecorder>,
+    server_id: String,
+    enabled: bool,
+    clear: Option<bool>,
+) -> Result<(), String> {
+    log::info!(
+        "Traffic recording for MCP server {server_id} {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    {
+        let mut recording = recorder.enabled.lock().map_err(|e| e.to_string())?;
+        if enabled {
+            recording.insert(server_id.clone());
+        } else {
+            recording.remove(&server_id);
+        }
+    }
+    if clear.unwrap_or(false) {
+        recorder
+            .buffers
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(&server_id);
+    }
+    Ok(())
+}
+
+/// Returns captured messages of a server, oldest first, with a sequence
+/// number greater than `since`.
+#[tauri::command]
+pub async fn get_mcp_traffic(
+    recorder: State<'_, McpTrafficRecorder>,
+    server_id: String,
+    since: Option<u64>,
+) -> Result<Vec<TrafficEntry>, String> {
+    recorder.entries(&server_id, since.unwrap_or(0))
+}
+
+/// Writes a server's captured messages to `path` as JSON Lines. Returns the
+/// number of messages written.
+#[tauri::command]
+pub async fn export_mcp_traffic(
+    recorder: State<'_, McpTrafficRecorder>,
+    server_id: String,
+    path: String,
+) -> Result<usize, String> {
+    let entries = recorder.entries(&server_id, 0)?;
+    let mut contents = String::new();
+    for entry in &entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize traffic: {e}"))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+
+    crate::export::write_atomic(std::path::Path::new(&path), contents.as_bytes())?;
+    log::info!(
+        "Exported {} MCP messages of {server_id} to {path}",
+        entries.len()
+    );
+    Ok(entries.len())
+}
@@ -0,0 +1,166 @@
+// MCP Hot Reload
+// ==============
+//
+// For people developing their own MCP servers: a config can list
+// `watch_paths`, and the server is restarted whenever one of them changes.
+// Files are watched through their parent directory, because editors often
+// save by writing a new file and renaming it over the old one, which would
+// end a watch on the file itself. Changes are debounced so a burst of writes
+// (a build, a formatter) causes a single restart. `mcp-hot-reloaded` is
+// emitted afterwards so the frontend can initialize the new process.
+//
+// The watcher is owned by the server's process entry, so stopping or
+// replacing the server also ends its watch.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::{McpProcesses, McpServerConfig};
+
+/// Quiet time after the last change before the server is restarted
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpHotReloadEvent {
+    pub server_id: String,
+    /// Watched paths that changed
+    pub changed: Vec<String>,
+    /// Pid of the new process, `None` if the restart failed
+    pub pid: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Whether an event touches one of the watched paths. Events in a watched
+/// file's parent directory that concern other files are ignored.
+fn relevant(event: &Event, watched: &[PathBuf]) -> Vec<PathBuf> {
+    if matches!(event.kind, EventKind::Access(_) | EventKind::Other) {
+        return Vec::new();
+    }
+    event
+        .paths
+        .iter()
+        .filter(|path| watched.iter().any(|w| path.starts_with(w)))
+        .cloned()
+        .collect()
+}
+
+/// Starts watching the config's `watch_paths`. Returns `None` if there is
+/// nothing to watch.
+pub fn start(app: &AppHandle, config: &McpServerConfig) -> Option<RecommendedWatcher> {
+    let watched: Vec<PathBuf> = config
+        .watch_paths
+        .iter()
+        .filter(|path| {
+            let exists = path.is_absolute() && path.exists();
+            if !exists {
+                log::warn!(
+                    "Not watching {path:?} for MCP server {}: not an existing absolute path",
+                    config.id
+                );
+            }
+            exists
+        })
+        .cloned()
+        .collect();
+    if watched.is_empty() {
+        return None;
+    }
+
+    let (sender, changes) = mpsc::channel::<Vec<PathBuf>>();
+    let filter = watched.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) => {
+                let paths = relevant(&event, &filter);
+                if !paths.is_empty() {
+                    let _ = sender.send(paths);
+                }
+            }
+            Err(e) => log::warn!("File watch error: {e}"),
+        })
+        .map_err(|e| log::error!("Failed to create file watcher: {e}"))
+        .ok()?;
+
+    for path in &watched {
+        let (target, mode) = match path.parent() {
+            Some(parent) if path.is_file() => (parent, RecursiveMode::NonRecursive),
+            _ => (path.as_path(), RecursiveMode::Recursive),
+        };
+        if let Err(e) = watcher.watch(target, mode) {
+            log::error!("Failed to watch {path:?} for MCP server {}: {e}", config.id);
+        }
+    }
+    log::info!(
+        "Watching {} paths for MCP server {}",
+        watched.len(),
+        config.id
+    );
+
+    let app = app.clone();
+    let config = config.clone();
+    std::thread::spawn(move || debounce_and_restart(app, config, changes));
+    Some(watcher)
+}
+
+/// Waits for changes and restarts the server once they settle. The restart
+/// replaces the watcher, so this runs at most once per process; it also ends
+/// when the watcher is dropped because the server stopped.
+fn debounce_and_restart(
+    app: AppHandle,
+    config: McpServerConfig,
+    changes: mpsc::Receiver<Vec<PathBuf>>,
+) {
+    let Ok(first) = changes.recv() else {
+        return;
+    };
+    let mut changed: BTreeSet<PathBuf> = first.into_iter().collect();
+    loop {
+        match changes.recv_timeout(DEBOUNCE) {
+            Ok(paths) => changed.extend(paths),
+            Err(RecvTimeoutError::Timeout) => break,
+            // The server stopped while changes were settling
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+
+    log::info!(
+        "Restarting MCP server {} after changes to {changed:?}",
+        config.id
+    );
+    let result = tauri::async_runtime::block_on(super::spawn_mcp_server(
+        app.clone(),
+        app.state::<McpProcesses>(),
+        config.clone(),
+        Some(true),
+    ));
+    let (pid, error) = match result {
+        Ok(pid) => (Some(pid), None),
+        Err(e) => {
+            log::error!(
+                "Hot reload of MCP server {} failed: {}",
+                config.id,
+                e.message
+            );
+            (None, Some(e.message))
+        }
+    };
+
+    let _ = app.emit(
+        "mcp-hot-reloaded",
+        McpHotReloadEvent {
+            server_id: config.id.clone(),
+            changed: changed
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+            pid,
+            error,
+        },
+    );
+}
@@ -0,0 +1,752 @@
+// LLM Request Middleware
+// ======================
+//
+// An ordered, user-configured pipeline that every backend model request
+// passes through. Each middleware can inspect or rewrite the outgoing
+// request, short-circuit it (the cache), reject it (the budget), and
+// inspect or rewrite the final response. Built-ins cover secret redaction,
+// request budgets and an in-memory response cache; custom middleware is a
+// Rhai script defining `on_request(request)` and/or
+// `on_response(request, response)`, each returning the modified map, or
+// nothing to leave it unchanged, and throwing to reject the request.
+//
+// A middleware that fails rather than rejects (a script error, a script
+// running past its operation or time limit, a malformed return value) is
+// skipped: the failure is logged and counted in its metrics, and the
+// request goes on as if it were disabled.
+//
+// Response hooks see the finished response; streamed deltas have already
+// reached the caller by then. Requests no middleware answered go to the
+// provider, or to its recorded responses in replay mode (see `replay`).
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::hash::sha256_hex;
+use crate::llm::{self, LlmMessage, LlmRequest, LlmStreamOutcome, ProviderId};
+use crate::replay::ReplayMode;
+
+const REDACTED: &str = "[REDACTED]";
+/// Responses kept by the cache middleware
+const CACHE_MAX_ENTRIES: usize = 200;
+/// Upper bound on work a script may do per hook
+const SCRIPT_MAX_OPERATIONS: u64 = 1_000_000;
+/// Upper bound on the time a script may take per hook
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Rough characters per token, for the budget middleware
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Secrets that are always redacted by the redaction middleware
+static BUILTIN_SECRET_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        r"sk-(?:proj-|ant-)?[A-Za-z0-9_\-]{20,}",
+        r"AIza[0-9A-Za-z_\-]{35}",
+        r"gsk_[A-Za-z0-9]{20,}",
+        r"gh[pousr]_[A-Za-z0-9]{36,}",
+        r"AKIA[0-9A-Z]{16}",
+        r"xox[abprs]-[A-Za-z0-9\-]{10,}",
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+    ]
+    .iter()
+    .filter_map(|p| Regex::new(p).ok())
+    .collect()
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiddlewareConfig {
+    /// Unique name, used in errors and metrics
+    pub id: String,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub kind: MiddlewareKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum MiddlewareKind {
+    /// Replaces API keys, tokens and private keys (plus `patterns`) in the
+    /// system prompt and messages with `[REDACTED]`
+    Redaction {
+        #[serde(default)]
+        patterns: Vec<String>,
+    },
+    /// Rejects requests whose estimated input is too large and caps the
+    /// requested output
+    Budget {
+        #[serde(default)]
+        max_input_tokens: Option<u64>,
+        #[serde(default)]
+        max_output_tokens: Option<u32>,
+    },
+    /// Answers identical requests from memory for `ttl_secs`
+    Cache { ttl_secs: u64 },
+    /// Rhai script defining `on_request` and/or `on_response`
+    Script { source: String },
+}
+
+impl MiddlewareKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MiddlewareKind::Redaction { .. } => "redaction",
+            MiddlewareKind::Budget { .. } => "budget",
+            MiddlewareKind::Cache { .. } => "cache",
+            MiddlewareKind::Script { .. } => "script",
+        }
+    }
+}
+
+/// The request as middleware scripts see it. The API key is left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScriptRequest {
+    provider: ProviderId,
+    model: String,
+    system_prompt: Option<String>,
+    messages: Vec<LlmMessage>,
+    max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScriptResponse {
+    text: String,
+    output_tokens: Option<u64>,
+}
+
+impl ScriptRequest {
+    fn from_request(request: &LlmRequest) -> Self {
+        Self {
+            provider: request.provider,
+            model: request.model.clone(),
+            system_prompt: request.system_prompt.clone(),
+            messages: request.messages.clone(),
+            max_tokens: request.max_tokens,
+        }
+    }
+
+    fn apply_to(self, request: &mut LlmRequest) {
+        request.provider = self.provider;
+        request.model = self.model;
+        request.system_prompt = self.system_prompt;
+        request.messages = self.messages;
+        request.max_tokens = self.max_tokens;
+    }
+}
+
+/// Why a middleware hook did not go through.
+#[derive(Debug, PartialEq)]
+enum HookError {
+    /// The middleware refused the request: over budget, or a script threw
+    Rejected(String),
+    /// The middleware itself broke; the request goes on without it
+    Failed(String),
+}
+
+struct CachedResponse {
+    stored: Instant,
+    text: String,
+    output_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MiddlewareMetrics {
+    pub id: String,
+    pub kind: String,
+    /// Requests the middleware handled
+    pub calls: u64,
+    /// Requests it rejected or failed on
+    pub errors: u64,
+    /// Time spent in the middleware across both hooks
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+/// Shared state of the middleware pipeline.
+#[derive(Default)]
+pub struct LlmGateway {
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    metrics: Mutex<HashMap<String, MiddlewareMetrics>>,
+}
+
+impl LlmGateway {
+    /// Responses held by the cache middleware, including expired ones.
+    pub fn cached_responses(&self) -> usize {
+        self.cache.lock().map(|c| c.len()).unwrap_or(0)
+    }
+
+    fn record(&self, config: &MiddlewareConfig, elapsed: Duration, failed: bool) {
+        let Ok(mut metrics) = self.metrics.lock() else {
+            return;
+        };
+        let entry = metrics
+            .entry(config.id.clone())
+            .or_insert_with(|| MiddlewareMetrics {
+                id: config.id.clone(),
+                kind: config.kind.name().to_string(),
+                ..Default::default()
+            });
+        let micros = elapsed.as_micros() as u64;
+        entry.calls += 1;
+        entry.total_us += micros;
+        entry.max_us = entry.max_us.max(micros);
+        if failed {
+            entry.errors += 1;
+        }
+    }
+}
+
+/// Checks a middleware list before it is saved to preferences.
+pub fn validate(configs: &[MiddlewareConfig]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for config in configs {
+        crate::validate_string_input(&config.id, 100, "Middleware id")?;
+        if config.id.trim().is_empty() || !ids.insert(config.id.as_str()) {
+            return Err(format!(
+                "Middleware ids must be unique and non-empty: {:?}",
+                config.id
+            ));
+        }
+        match &config.kind {
+            MiddlewareKind::Redaction { patterns } => {
+                for pattern in patterns {
+                    Regex::new(pattern)
+                        .map_err(|e| format!("Invalid pattern in {}: {e}", config.id))?;
+                }
+            }
+            MiddlewareKind::Script { source } => {
+                crate::validate_string_input(source, 100_000, "Middleware script")?;
+                script_engine()
+                    .compile(source)
+                    .map_err(|e| format!("Script {} does not compile: {e}", config.id))?;
+            }
+            MiddlewareKind::Budget { .. } | MiddlewareKind::Cache { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+fn script_engine() -> rhai::Engine {
+    let mut engine = rhai::Engine::new();
+    engine.set_max_operations(SCRIPT_MAX_OPERATIONS);
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        (started.elapsed() > SCRIPT_TIMEOUT).then(|| rhai::Dynamic::from("timed out"))
+    });
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(10 * 1024 * 1024);
+    engine
+}
+
+/// Calls `function` in a script if it defines it. Returns `None` when the
+/// function is missing or returned nothing. Only a `throw` rejects the
+/// request; any other error is the script failing.
+fn call_script<T: serde::de::DeserializeOwned>(
+    source: &str,
+    function: &str,
+    args: impl rhai::FuncArgs,
+) -> Result<Option<T>, HookError> {
+    let engine = script_engine();
+    let ast = engine
+        .compile(source)
+        .map_err(|e| HookError::Failed(e.to_string()))?;
+    if !ast.iter_functions().any(|f| f.name == function) {
+        return Ok(None);
+    }
+    let result: rhai::Dynamic = engine
+        .call_fn(&mut rhai::Scope::new(), &ast, function, args)
+        .map_err(|e| match e.unwrap_inner() {
+            rhai::EvalAltResult::ErrorRuntime(thrown, _) => HookError::Rejected(thrown.to_string()),
+            _ => HookError::Failed(e.to_string()),
+        })?;
+    if result.is_unit() {
+        return Ok(None);
+    }
+    rhai::serde::from_dynamic(&result)
+        .map(Some)
+        .map_err(|e| HookError::Failed(format!("{function} returned an invalid value: {e}")))
+}
+
+fn cache_key(request: &LlmRequest) -> String {
+    let json = serde_json::to_string(&ScriptRequest::from_request(request)).unwrap_or_default();
+    sha256_hex(json.as_bytes())
+}
+
+pub fn redact(text: &str, extra: &[Regex]) -> String {
+    BUILTIN_SECRET_PATTERNS
+        .iter()
+        .chain(extra)
+        .fold(text.to_string(), |text, pattern| {
+            pattern.replace_all(&text, REDACTED).into_owned()
+        })
+}
+
+/// Runs a middleware's request hook. `Ok(Some(_))` answers the request
+/// without calling the provider.
+fn before(
+    gateway: &LlmGateway,
+    config: &MiddlewareConfig,
+    request: &mut LlmRequest,
+) -> Result<Option<LlmStreamOutcome>, HookError> {
+    match &config.kind {
+        MiddlewareKind::Redaction { patterns } => {
+            let extra: Vec<Regex> = patterns.iter().filter_map(|p| Regex::new(p).ok()).collect();
+            if let Some(system) = &request.system_prompt {
+                request.system_prompt = Some(redact(system, &extra));
+            }
+            for message in &mut request.messages {
+                message.content = redact(&message.content, &extra);
+            }
+            Ok(None)
+        }
+        MiddlewareKind::Budget {
+            max_input_tokens,
+            max_output_tokens,
+        } => {
+            if let Some(limit) = max_input_tokens {
+                let chars = request.system_prompt.as_deref().map_or(0, str::len)
+                    + request
+                        .messages
+                        .iter()
+                        .map(|m| m.content.len())
+                        .sum::<usize>();
+                let estimated = (chars / CHARS_PER_TOKEN) as u64;
+                if estimated > *limit {
+                    return Err(HookError::Rejected(format!(
+                        "Request is about {estimated} tokens, over the budget of {limit}"
+                    )));
+                }
+            }
+            if let Some(cap) = max_output_tokens {
+                request.max_tokens = Some(request.max_tokens.map_or(*cap, |t| t.min(*cap)));
+            }
+            Ok(None)
+        }
+        MiddlewareKind::Cache { ttl_secs } => {
+            let cache = gateway
+                .cache
+                .lock()
+                .map_err(|e| HookError::Failed(e.to_string()))?;
+            Ok(cache
+                .get(&cache_key(request))
+                .filter(|c| c.stored.elapsed() < Duration::from_secs(*ttl_secs))
+                .map(|c| LlmStreamOutcome {
+                    text: c.text.clone(),
+                    first_token: Some(Duration::ZERO),
+                    total: Duration::ZERO,
+                    output_tokens: c.output_tokens,
+                }))
+        }
+        MiddlewareKind::Script { source } => {
+            let input = rhai::serde::to_dynamic(ScriptRequest::from_request(request))
+                .map_err(|e| HookError::Failed(e.to_string()))?;
+            if let Some(modified) = call_script::<ScriptRequest>(source, "on_request", (input,))? {
+                modified.apply_to(request);
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Runs a middleware's response hook.
+fn after(
+    gateway: &LlmGateway,
+    config: &MiddlewareConfig,
+    request: &LlmRequest,
+    outcome: &mut LlmStreamOutcome,
+) -> Result<(), HookError> {
+    match &config.kind {
+        MiddlewareKind::Cache { ttl_secs } => {
+            let mut cache = gateway
+                .cache
+                .lock()
+                .map_err(|e| HookError::Failed(e.to_string()))?;
+            let ttl = Duration::from_secs(*ttl_secs);
+            cache.retain(|_, c| c.stored.elapsed() < ttl);
+            if cache.len() >= CACHE_MAX_ENTRIES {
+                if let Some(oldest) = cache
+                    .iter()
+                    .min_by_key(|(_, c)| c.stored)
+                    .map(|(k, _)| k.clone())
+                {
+                    cache.remove(&oldest);
+                }
+            }
+            cache.insert(
+                cache_key(request),
+                CachedResponse {
+                    stored: Instant::now(),
+                    text: outcome.text.clone(),
+                    output_tokens: outcome.output_tokens,
+                },
+            );
+            Ok(())
+        }
+        MiddlewareKind::Script { source } => {
+            let input = rhai::serde::to_dynamic(ScriptRequest::from_request(request))
+                .map_err(|e| HookError::Failed(e.to_string()))?;
+            let response = rhai::serde::to_dynamic(ScriptResponse {
+                text: outcome.text.clone(),
+                output_tokens: outcome.output_tokens,
+            })
+            .map_err(|e| HookError::Failed(e.to_string()))?;
+            if let Some(modified) =
+                call_script::<ScriptResponse>(source, "on_response", (input, response))?
+            {
+                outcome.text = modified.text;
+                outcome.output_tokens = modified.output_tokens;
+            }
+            Ok(())
+        }
+        MiddlewareKind::Redaction { .. } | MiddlewareKind::Budget { .. } => Ok(()),
+    }
+}
+
+/// Sends the request to the provider, recording or replaying the response
+/// as the `llm_replay` preference says.
+async fn provider_round_trip(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    request: &LlmRequest,
+    mut on_delta: impl FnMut(&str),
+) -> Result<LlmStreamOutcome, String> {
+    let mode = crate::replay::mode(app);
+    if mode == ReplayMode::Off {
+        return llm::stream_chat(app, client, request, on_delta).await;
+    }
+
+    let key = cache_key(request);
+    if mode == ReplayMode::Replay {
+        let fixture = crate::replay::load(app, &key)?.ok_or_else(|| {
+            log::warn!("No recorded response for request {key}");
+            format!("Replay mode: no recorded response for this request ({key})")
+        })?;
+        log::debug!("Replaying recorded response {key}");
+        on_delta(&fixture.text);
+        return Ok(LlmStreamOutcome {
+            text: fixture.text,
+            first_token: Some(Duration::ZERO),
+            total: Duration::ZERO,
+            output_tokens: fixture.output_tokens,
+        });
+    }
+
+    let outcome = llm::stream_chat(app, client, request, on_delta).await?;
+    let recorded = serde_json::to_value(ScriptRequest::from_request(request))
+        .map_err(|e| e.to_string())
+        .and_then(|summary| crate::replay::record(app, &key, summary, &outcome));
+    if let Err(e) = recorded {
+        log::warn!("Failed to record provider response {key}: {e}");
+    }
+    Ok(outcome)
+}
+
+/// Streams a chat completion through the enabled middleware. With
+/// `use_cache` false the cache middleware is skipped, for callers that need
+/// a real provider round trip.
+pub async fn stream_chat(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    request: &LlmRequest,
+    use_cache: bool,
+    mut on_delta: impl FnMut(&str),
+) -> Result<LlmStreamOutcome, String> {
+    let pipeline: Vec<MiddlewareConfig> = crate::read_preferences(app)
+        .map(|p| p.llm_middleware)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.enabled && (use_cache || !matches!(m.kind, MiddlewareKind::Cache { .. })))
+        .collect();
+    let gateway = app.state::<LlmGateway>();
+
+    let mut request = request.clone();
+    let answered = run_before(&gateway, &pipeline, &mut request)?;
+    // Response hooks run in reverse, starting below the one that answered
+    let (ran, mut outcome) = match answered {
+        Some((index, outcome)) => {
+            on_delta(&outcome.text);
+            (index, outcome)
+        }
+        None => (
+            pipeline.len(),
+            provider_round_trip(app, client, &request, on_delta).await?,
+        ),
+    };
+    run_after(&gateway, &pipeline[..ran], &request, &mut outcome)?;
+    Ok(outcome)
+}
+
+/// Runs the request hooks in order, skipping middleware that fail. Returns
+/// the index of the middleware that answered the request and its answer,
+/// if one did.
+fn run_before(
+    gateway: &LlmGateway,
+    pipeline: &[MiddlewareConfig],
+    request: &mut LlmRequest,
+) -> Result<Option<(usize, LlmStreamOutcome)>, String> {
+    for (index, config) in pipeline.iter().enumerate() {
+        let started = Instant::now();
+        let result = before(gateway, config, request);
+        gateway.record(config, started.elapsed(), result.is_err());
+        match result {
+            Ok(None) => {}
+            Ok(Some(outcome)) => {
+                log::debug!("Middleware {} answered the request", config.id);
+                return Ok(Some((index, outcome)));
+            }
+            Err(HookError::Failed(e)) => {
+                log::warn!(
+                    "Middleware {} failed on the request, skipped: {e}",
+                    config.id
+                );
+            }
+            Err(HookError::Rejected(e)) => {
+                log::warn!("Middleware {} rejected the request: {e}", config.id);
+                return Err(format!(
+                    "Middleware {} rejected the request: {e}",
+                    config.id
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Runs the response hooks of `pipeline` in reverse, skipping middleware
+/// that fail.
+fn run_after(
+    gateway: &LlmGateway,
+    pipeline: &[MiddlewareConfig],
+    request: &LlmRequest,
+    outcome: &mut LlmStreamOutcome,
+) -> Result<(), String> {
+    for config in pipeline.iter().rev() {
+        let started = Instant::now();
+        let result = after(gateway, config, request, outcome);
+        gateway.record(config, started.elapsed(), result.is_err());
+        match result {
+            Ok(()) => {}
+            Err(HookError::Failed(e)) => {
+                log::warn!(
+                    "Middleware {} failed on the response, skipped: {e}",
+                    config.id
+                );
+            }
+            Err(HookError::Rejected(e)) => {
+                log::warn!("Middleware {} rejected the response: {e}", config.id);
+                return Err(format!(
+                    "Middleware {} rejected the response: {e}",
+                    config.id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns call counts and timings of every middleware that has run.
+#[tauri::command]
+pub async fn get_middleware_metrics(
+    gateway: State<'_, LlmGateway>,
+) -> Result<Vec<MiddlewareMetrics>, String> {
+    let metrics = gateway.metrics.lock().map_err(|e| e.to_string())?;
+    let mut entries: Vec<MiddlewareMetrics> = metrics.values().cloned().collect();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content: &str) -> LlmRequest {
+        LlmRequest {
+            provider: ProviderId::OpenAI,
+            model: "gpt-4o".to_string(),
+            api_key: String::new(),
+            system_prompt: None,
+            messages: vec![LlmMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            max_tokens: None,
+        }
+    }
+
+    fn outcome(text: &str) -> LlmStreamOutcome {
+        LlmStreamOutcome {
+            text: text.to_string(),
+            first_token: None,
+            total: Duration::ZERO,
+            output_tokens: None,
+        }
+    }
+
+    fn middleware(id: &str, kind: MiddlewareKind) -> MiddlewareConfig {
+        MiddlewareConfig {
+            id: id.to_string(),
+            enabled: true,
+            kind,
+        }
+    }
+
+    fn script(id: &str, source: &str) -> MiddlewareConfig {
+        middleware(
+            id,
+            MiddlewareKind::Script {
+                source: source.to_string(),
+            },
+        )
+    }
+
+    fn redaction() -> MiddlewareConfig {
+        middleware("redact", MiddlewareKind::Redaction { patterns: vec![] })
+    }
+
+    fn errors(gateway: &LlmGateway, id: &str) -> u64 {
+        gateway.metrics.lock().unwrap()[id].errors
+    }
+
+    #[test]
+    fn script_errors_skip_the_middleware() {
+        let gateway = LlmGateway::default();
+        let pipeline = [
+            script(
+                "broken",
+                "fn on_request(request) { request.messages[5].content }",
+            ),
+            script("invalid", "fn on_request(request) { 42 }"),
+            redaction(),
+        ];
+        let mut request = request("key sk-abcdefghijklmnopqrstuvwx");
+        let answered = run_before(&gateway, &pipeline, &mut request).unwrap();
+        assert!(answered.is_none());
+        // The middleware after the broken ones still ran
+        assert_eq!(request.messages[0].content, "key [REDACTED]");
+        assert_eq!(errors(&gateway, "broken"), 1);
+        assert_eq!(errors(&gateway, "invalid"), 1);
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped_and_skipped() {
+        let gateway = LlmGateway::default();
+        let pipeline = [script("loop", "fn on_request(request) { loop {} }")];
+        let mut request = request("Hello");
+        assert!(run_before(&gateway, &pipeline, &mut request)
+            .unwrap()
+            .is_none());
+        assert_eq!(request.messages[0].content, "Hello");
+        assert_eq!(errors(&gateway, "loop"), 1);
+
+        let mut response = outcome("Hi");
+        let pipeline = [script(
+            "loop",
+            "fn on_response(request, response) { loop {} }",
+        )];
+        run_after(&gateway, &pipeline, &request, &mut response).unwrap();
+        assert_eq!(response.text, "Hi");
+    }
+
+    #[test]
+    fn scripts_time_out() {
+        let source = "fn on_request(request) { let n = 0; loop { n += 1; } }";
+        let mut engine = script_engine();
+        engine.set_max_operations(0);
+        let ast = engine.compile(source).unwrap();
+        let started = Instant::now();
+        let error = engine
+            .call_fn::<rhai::Dynamic>(
+                &mut rhai::Scope::new(),
+                &ast,
+                "on_request",
+                (rhai::Dynamic::UNIT,),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error.unwrap_inner(),
+            rhai::EvalAltResult::ErrorTerminated(..)
+        ));
+        assert!(started.elapsed() < SCRIPT_TIMEOUT * 5);
+    }
+
+    #[test]
+    fn throwing_rejects_the_request() {
+        let gateway = LlmGateway::default();
+        let pipeline = [
+            script(
+                "guard",
+                r#"fn on_request(request) { throw "no secrets here"; }"#,
+            ),
+            redaction(),
+        ];
+        let error = run_before(&gateway, &pipeline, &mut request("Hello")).unwrap_err();
+        assert_eq!(
+            error,
+            "Middleware guard rejected the request: no secrets here"
+        );
+    }
+
+    #[test]
+    fn scripts_rewrite_requests_and_responses() {
+        let gateway = LlmGateway::default();
+        let pipeline = [script(
+            "rewrite",
+            r#"
+                fn on_request(request) { request.max_tokens = 100; request }
+                fn on_response(request, response) { response.text += "!"; response }
+            "#,
+        )];
+        let mut request = request("Hello");
+        run_before(&gateway, &pipeline, &mut request).unwrap();
+        assert_eq!(request.max_tokens, Some(100));
+        let mut response = outcome("Hi");
+        run_after(&gateway, &pipeline, &request, &mut response).unwrap();
+        assert_eq!(response.text, "Hi!");
+    }
+
+    #[test]
+    fn budget_rejects_large_requests_and_caps_output() {
+        let gateway = LlmGateway::default();
+        let budget = |max_input_tokens| {
+            middleware(
+                "budget",
+                MiddlewareKind::Budget {
+                    max_input_tokens,
+                    max_output_tokens: Some(50),
+                },
+            )
+        };
+        let mut small = request("Hello");
+        run_before(&gateway, &[budget(Some(10))], &mut small).unwrap();
+        assert_eq!(small.max_tokens, Some(50));
+        let mut large = request(&"word ".repeat(100));
+        assert!(run_before(&gateway, &[budget(Some(10))], &mut large).is_err());
+    }
+
+    #[test]
+    fn cache_answers_repeated_requests() {
+        let gateway = LlmGateway::default();
+        let pipeline = [
+            redaction(),
+            middleware("cache", MiddlewareKind::Cache { ttl_secs: 60 }),
+        ];
+        let mut first = request("Hello");
+        assert!(run_before(&gateway, &pipeline, &mut first)
+            .unwrap()
+            .is_none());
+        run_after(&gateway, &pipeline, &first, &mut outcome("Hi")).unwrap();
+
+        let mut again = request("Hello");
+        let (index, answer) = run_before(&gateway, &pipeline, &mut again)
+            .unwrap()
+            .unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(answer.text, "Hi");
+    }
+}
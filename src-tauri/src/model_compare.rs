@@ -0,0 +1,258 @@
+// Model Comparison
+// ================
+//
+// `compare_models` sends one request to several models at once, for the
+// playground's side-by-side view. Every lane streams through `chat_proxy`
+// concurrently, so its text arrives as the usual `llm-*` events, with the
+// lane's request id `{comparison_id}:{lane_id}`. When a lane ends,
+// `compare-lane-done` reports its latency and token stats; the command
+// resolves with the stats of every lane once all of them have ended. A
+// failing or cancelled lane doesn't stop the others.
+//
+// The request is written in the OpenAI chat completions shape. Lanes on an
+// Anthropic API get it rewritten: system messages move to `system`, and
+// `max_tokens`, which Anthropic requires, defaults to 4096.
+//
+// Linking the answers into conversations is up to the caller (see
+// `comparisons`).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use crate::chat_proxy::{ChatResponse, ProviderApi, ProviderConfig};
+
+const MIN_LANES: usize = 2;
+const MAX_LANES: usize = 8;
+const MAX_COMPARISON_ID_LENGTH: usize = 50;
+const MAX_LANE_ID_LENGTH: usize = 40;
+const MAX_MODEL_CHARS: usize = 200;
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u64 = 4096;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareLane {
+    /// Tags the lane's events; unique within the comparison
+    pub lane_id: String,
+    /// The API in full, or `provider_id` of a registered provider
+    #[serde(default)]
+    pub provider_config: Option<ProviderConfig>,
+    #[serde(default)]
+    pub provider_id: Option<String>,
+    /// Replaces the request's `model`
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaneStatus {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaneStats {
+    pub comparison_id: String,
+    pub lane_id: String,
+    /// Id of the lane's `llm-*` events
+    pub request_id: String,
+    pub model: String,
+    pub status: LaneStatus,
+    pub error: Option<String>,
+    /// The whole answer
+    pub text: String,
+    pub finish_reason: Option<String>,
+    /// Milliseconds to the first streamed text or tool call
+    pub first_chunk_ms: Option<u64>,
+    /// Milliseconds until the lane ended
+    pub duration_ms: u64,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    /// Output tokens per second from the first chunk to the end
+    pub tokens_per_second: Option<f64>,
+    /// Answered from the response cache (see `response_cache`)
+    pub cached: bool,
+}
+
+fn validate_lanes(comparison_id: &str, lanes: &[CompareLane]) -> Result<(), String> {
+    crate::validate_string_input(comparison_id, MAX_COMPARISON_ID_LENGTH, "Comparison id")?;
+    if comparison_id.is_empty() {
+        return Err("Comparison id is required".to_string());
+    }
+    if lanes.len() < MIN_LANES || lanes.len() > MAX_LANES {
+        return Err(format!(
+            "A comparison needs between {MIN_LANES} and {MAX_LANES} models"
+        ));
+    }
+    let mut lane_ids = HashSet::new();
+    for lane in lanes {
+        crate::validate_string_input(&lane.lane_id, MAX_LANE_ID_LENGTH, "Lane id")?;
+        crate::validate_string_input(&lane.model, MAX_MODEL_CHARS, "Model")?;
+        if lane.lane_id.is_empty() || lane.model.trim().is_empty() {
+            return Err("Every lane needs an id and a model".to_string());
+        }
+        if !lane_ids.insert(lane.lane_id.as_str()) {
+            return Err(format!("Lane {} appears twice", lane.lane_id));
+        }
+        if lane.provider_config.is_some() == lane.provider_id.is_some() {
+            return Err(format!(
+                "Lane {}: pass either a provider configuration or a provider id",
+                lane.lane_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The request for one lane: its model, in its API's shape.
+fn lane_request(api: ProviderApi, request: &Value, model: &str) -> Value {
+    let mut request = request.clone();
+    request["model"] = json!(model);
+    if api == ProviderApi::Anthropic {
+        let messages = request["messages"].as_array().cloned().unwrap_or_default();
+        let (system, messages): (Vec<Value>, Vec<Value>) = messages
+            .into_iter()
+            .partition(|message| message["role"] == "system");
+        let system: Vec<&str> = system
+            .iter()
+            .filter_map(|message| message["content"].as_str())
+            .collect();
+        if !system.is_empty() && request.get("system").is_none() {
+            request["system"] = json!(system.join("\n\n"));
+        }
+        request["messages"] = json!(messages);
+        if request.get("max_tokens").is_none() {
+            request["max_tokens"] = json!(DEFAULT_ANTHROPIC_MAX_TOKENS);
+        }
+    }
+    request
+}
+
+async fn run_lane(
+    app: &AppHandle,
+    comparison_id: &str,
+    lane: CompareLane,
+    request: &Value,
+) -> LaneStats {
+    let request_id = format!("{comparison_id}:{}", lane.lane_id);
+    let started = Instant::now();
+    let api = match (&lane.provider_config, &lane.provider_id) {
+        (Some(config), _) => Ok(config.api),
+        (None, Some(id)) => crate::providers::config(app, id).map(|config| config.api),
+        (None, None) => Ok(ProviderApi::default()),
+    };
+    let result = match api {
+        Ok(api) => {
+            crate::chat_proxy::complete(
+                app,
+                request_id.clone(),
+                lane.provider_config,
+                lane.provider_id,
+                lane_request(api, request, &lane.model),
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let mut stats = LaneStats {
+        comparison_id: comparison_id.to_string(),
+        lane_id: lane.lane_id,
+        request_id,
+        model: lane.model,
+        status: LaneStatus::Completed,
+        error: None,
+        text: String::new(),
+        finish_reason: None,
+        first_chunk_ms: None,
+        duration_ms,
+        input_tokens: None,
+        output_tokens: None,
+        tokens_per_second: None,
+        cached: false,
+    };
+    match result {
+        Ok(Some(response)) => {
+            let ChatResponse {
+                text,
+                finish_reason,
+                usage,
+                first_chunk_ms,
+                cached,
+                ..
+            } = response;
+            stats.text = text;
+            stats.finish_reason = finish_reason;
+            stats.cached = cached;
+            stats.first_chunk_ms = first_chunk_ms;
+            if let Some(usage) = usage {
+                stats.input_tokens = Some(usage.input_tokens);
+                stats.output_tokens = Some(usage.output_tokens);
+                let streaming_ms = duration_ms.saturating_sub(first_chunk_ms.unwrap_or(0));
+                if first_chunk_ms.is_some() && streaming_ms > 0 {
+                    stats.tokens_per_second =
+                        Some(usage.output_tokens as f64 * 1000.0 / streaming_ms as f64);
+                }
+            }
+        }
+        Ok(None) => stats.status = LaneStatus::Cancelled,
+        Err(e) => {
+            stats.status = LaneStatus::Failed;
+            stats.error = Some(e);
+        }
+    }
+    log::info!(
+        "Comparison {comparison_id} lane {} ({}) {:?} in {duration_ms}ms",
+        stats.lane_id,
+        stats.model,
+        stats.status
+    );
+    if let Err(e) = app.emit("compare-lane-done", stats.clone()) {
+        log::warn!("Failed to emit compare-lane-done event: {e}");
+    }
+    stats
+}
+
+/// Streams `request`, an OpenAI chat completions body, from every model in
+/// `model_list` concurrently. Resolves with each lane's stats, in the order
+/// of `model_list`, once all lanes have ended. A lane is stopped with
+/// `cancel_llm_request` and its request id.
+#[tauri::command]
+pub async fn compare_models(
+    app: AppHandle,
+    comparison_id: String,
+    request: Value,
+    model_list: Vec<CompareLane>,
+) -> Result<Vec<LaneStats>, String> {
+    validate_lanes(&comparison_id, &model_list)?;
+    if !request.is_object() {
+        return Err("The request must be a JSON object".to_string());
+    }
+    log::info!("Comparing {} models in {comparison_id}", model_list.len());
+    let request = Arc::new(request);
+    let tasks: Vec<_> = model_list
+        .into_iter()
+        .map(|lane| {
+            let app = app.clone();
+            let comparison_id = comparison_id.clone();
+            let request = request.clone();
+            tauri::async_runtime::spawn(async move {
+                run_lane(&app, &comparison_id, lane, &request).await
+            })
+        })
+        .collect();
+    let mut stats = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        stats.push(
+            task.await
+                .map_err(|e| format!("Comparison lane failed: {e}"))?,
+        );
+    }
+    Ok(stats)
+}
@@ -0,0 +1,333 @@
+// Network Settings
+// ================
+//
+// Proxy and certificate settings for every HTTP request the backend makes:
+// model APIs, MCP registry lookups, link previews, tool endpoints and
+// destinations. All clients are built from `client_builder`, which applies
+// the `network` preference:
+//
+// - `system` (the default) uses the proxy configured for the system: the
+//   `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` variables, and
+//   on Windows and macOS the proxy from the system settings
+// - `manual` sends everything through `proxy_url`, except the hosts in
+//   `no_proxy`
+// - `none` connects directly
+//
+// A CA bundle, a PEM file like the one corporate TLS inspection needs, is
+// trusted in addition to the built-in roots. Settings are applied at
+// startup and whenever preferences are saved; clients built before then
+// keep the old ones. Stdio MCP servers get the same proxy through the
+// usual environment variables and the CA bundle through
+// `NODE_EXTRA_CA_CERTS`.
+
+use reqwest::{Certificate, NoProxy, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::process::Command as StdCommand;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::llm::ProviderId;
+
+const MAX_CA_BUNDLE_BYTES: u64 = 10 * 1024 * 1024;
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(15);
+/// Proxy variables passed on to MCP servers, in both spellings tools read
+const PROXY_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "NO_PROXY",
+    "no_proxy",
+];
+
+static APPLIED: RwLock<Option<Applied>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    #[default]
+    System,
+    Manual,
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub proxy: ProxyMode,
+    /// `http://` or `https://` URL of the proxy for `manual`, optionally
+    /// with `user:password@`
+    pub proxy_url: Option<String>,
+    /// Comma-separated hosts, domains and IP ranges that bypass the manual
+    /// proxy, e.g. `localhost,.corp.example.com,10.0.0.0/8`
+    pub no_proxy: Option<String>,
+    /// PEM file with extra root certificates to trust
+    pub ca_bundle_path: Option<String>,
+}
+
+/// The settings in the form clients are built with.
+struct Applied {
+    settings: NetworkSettings,
+    proxy: Option<Proxy>,
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityReport {
+    pub provider: String,
+    pub url: String,
+    /// Whether the server answered at all, with any status
+    pub reachable: bool,
+    pub status: Option<u16>,
+    /// How the request was routed, e.g. `system proxy` or
+    /// `proxy http://proxy.corp:8080`
+    pub route: String,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+fn manual_proxy(settings: &NetworkSettings) -> Result<Proxy, String> {
+    let url = settings
+        .proxy_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .ok_or("Enter the URL of the proxy")?;
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid proxy URL {url}: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!(
+            "Invalid proxy URL {url}: use http:// or https:// with a host"
+        ));
+    }
+    let proxy = Proxy::all(parsed).map_err(|e| format!("Invalid proxy URL {url}: {e}"))?;
+    Ok(proxy.no_proxy(settings.no_proxy.as_deref().and_then(NoProxy::from_string)))
+}
+
+fn read_ca_bundle(path: &str) -> Result<Vec<Certificate>, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read CA bundle {path}: {e}"))?
+        .len();
+    if size > MAX_CA_BUNDLE_BYTES {
+        return Err(format!(
+            "CA bundle {path} is larger than {} MB",
+            MAX_CA_BUNDLE_BYTES / 1024 / 1024
+        ));
+    }
+    let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA bundle {path}: {e}"))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("CA bundle {path} is not a valid PEM file: {e}"))?;
+    if certificates.is_empty() {
+        return Err(format!("CA bundle {path} holds no certificates"));
+    }
+    Ok(certificates)
+}
+
+fn prepare(settings: &NetworkSettings) -> Result<Applied, String> {
+    let proxy = match settings.proxy {
+        ProxyMode::Manual => Some(manual_proxy(settings)?),
+        ProxyMode::System | ProxyMode::None => None,
+    };
+    let certificates = match &settings.ca_bundle_path {
+        Some(path) if !path.trim().is_empty() => read_ca_bundle(path.trim())?,
+        _ => Vec::new(),
+    };
+    Ok(Applied {
+        settings: settings.clone(),
+        proxy,
+        certificates,
+    })
+}
+
+pub fn validate_settings(settings: &NetworkSettings) -> Result<(), String> {
+    prepare(settings).map(|_| ())
+}
+
+/// Makes `settings` the ones new clients are built with. Invalid settings,
+/// e.g. a CA bundle that was deleted since, are logged and leave the
+/// defaults in place.
+pub fn apply_settings(settings: &NetworkSettings) {
+    let applied = match prepare(settings) {
+        Ok(applied) => applied,
+        Err(e) => {
+            log::error!("Ignoring network settings: {e}");
+            return;
+        }
+    };
+    if let Ok(mut current) = APPLIED.write() {
+        *current = Some(applied);
+    }
+}
+
+/// A client builder with the proxy and CA bundle from the settings.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    let Ok(applied) = APPLIED.read() else {
+        return builder;
+    };
+    let Some(applied) = applied.as_ref() else {
+        return builder;
+    };
+    let mut builder = match (&applied.settings.proxy, &applied.proxy) {
+        (ProxyMode::Manual, Some(proxy)) => builder.proxy(proxy.clone()),
+        (ProxyMode::None, _) => builder.no_proxy(),
+        _ => builder,
+    };
+    for certificate in &applied.certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    builder
+}
+
+fn settings() -> NetworkSettings {
+    APPLIED
+        .read()
+        .ok()
+        .and_then(|applied| applied.as_ref().map(|a| a.settings.clone()))
+        .unwrap_or_default()
+}
+
+/// Passes the proxy and CA bundle on to an MCP server process.
+pub fn apply_to_command(command: &mut StdCommand) {
+    let settings = settings();
+    match settings.proxy {
+        // Isolated servers start with an empty environment
+        ProxyMode::System => {
+            for var in PROXY_VARS {
+                if let Ok(value) = std::env::var(var) {
+                    command.env(var, value);
+                }
+            }
+        }
+        ProxyMode::Manual => {
+            let url = settings.proxy_url.as_deref().unwrap_or_default().trim();
+            for var in &PROXY_VARS[..6] {
+                command.env(var, url);
+            }
+            for var in &PROXY_VARS[6..] {
+                command.env(var, settings.no_proxy.as_deref().unwrap_or_default());
+            }
+        }
+        ProxyMode::None => {
+            for var in PROXY_VARS {
+                command.env_remove(var);
+            }
+        }
+    }
+    if let Some(path) = settings.ca_bundle_path.filter(|p| !p.trim().is_empty()) {
+        command.env("NODE_EXTRA_CA_CERTS", path.trim());
+    }
+}
+
+/// The proxy URL without its credentials, for showing.
+fn redacted(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// A request error with its causes, which hold the useful part, e.g.
+/// `invalid peer certificate: UnknownIssuer`.
+fn describe(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    if message.contains("certificate") {
+        message.push_str(" (if a proxy inspects TLS traffic, set its CA bundle)");
+    }
+    message
+}
+
+/// The URL to try for `provider`: a built-in provider's API, or the base
+/// URL of a registered one.
+fn provider_url(app: &AppHandle, client: &reqwest::Client, provider: &str) -> Result<Url, String> {
+    let builtin: Option<ProviderId> =
+        serde_json::from_value(serde_json::Value::String(provider.to_string())).ok();
+    let url = match builtin {
+        Some(id) => id
+            .adapter()
+            .models_request(client, "")
+            .build()
+            .map_err(|e| e.to_string())?
+            .url()
+            .clone(),
+        None => {
+            let config = crate::providers::config(app, provider)?;
+            Url::parse(&config.base_url)
+                .map_err(|e| format!("Invalid base URL {}: {e}", config.base_url))?
+        }
+    };
+    Ok(url)
+}
+
+/// Checks whether `provider`, a built-in provider like `openai` or the id
+/// of a registered one, can be reached with the current network settings.
+/// Any answer counts, an authentication error included; failures report
+/// what went wrong, e.g. the proxy refusing the connection or an unknown
+/// certificate authority.
+#[tauri::command]
+pub async fn test_network_connectivity(
+    app: AppHandle,
+    provider: String,
+) -> Result<ConnectivityReport, String> {
+    crate::validate_string_input(&provider, 200, "Provider")?;
+    let settings = settings();
+    let route = match settings.proxy {
+        ProxyMode::System => "system proxy".to_string(),
+        ProxyMode::Manual => format!(
+            "proxy {}",
+            redacted(settings.proxy_url.as_deref().unwrap_or_default())
+        ),
+        ProxyMode::None => "direct".to_string(),
+    };
+    let client = client_builder()
+        .timeout(CONNECTIVITY_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+    let url = provider_url(&app, &client, &provider)?;
+
+    let started = Instant::now();
+    let result = client.get(url.clone()).send().await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let report = match result {
+        Ok(response) => ConnectivityReport {
+            reachable: true,
+            status: Some(response.status().as_u16()),
+            error: None,
+            provider,
+            url: url.to_string(),
+            route,
+            duration_ms,
+        },
+        Err(e) => ConnectivityReport {
+            reachable: false,
+            status: None,
+            error: Some(describe(&e)),
+            provider,
+            url: url.to_string(),
+            route,
+            duration_ms,
+        },
+    };
+    log::info!(
+        "Connectivity to {} ({}): {}",
+        report.url,
+        report.route,
+        report.error.as_deref().unwrap_or("reachable")
+    );
+    Ok(report)
+}
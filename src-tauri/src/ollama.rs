@@ -0,0 +1,264 @@
+// Ollama
+// ======
+//
+// Local models served by Ollama (https://ollama.com): whether it is
+// installed and running, the models it has, pulling and deleting models,
+// and chat through `llm::ProviderId::Ollama`, which talks to Ollama's
+// OpenAI-compatible endpoint. Everything goes to the HTTP API of the local
+// daemon, at `http://127.0.0.1:11434` or wherever `OLLAMA_HOST` points.
+//
+// Pulls run as operations (see `operations`), so their progress and
+// cancellation go through the same API as every other long task. While
+// background work is paused for power (see `power`) a pull disconnects,
+// and starts again afterwards; Ollama keeps what it already downloaded.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::operations::{self, Operation, OperationKind, ProgressUnit};
+
+const DEFAULT_HOST: &str = "http://127.0.0.1:11434";
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_MODEL_NAME_LENGTH: usize = 200;
+
+/// Base URL of the Ollama API. `OLLAMA_HOST` may be a URL or `host:port`,
+/// as for the Ollama CLI.
+pub fn base_url() -> String {
+    match std::env::var("OLLAMA_HOST") {
+        Ok(host) if host.starts_with("http://") || host.starts_with("https://") => {
+            host.trim_end_matches('/').to_string()
+        }
+        Ok(host) if !host.trim().is_empty() => format!("http://{}", host.trim()),
+        _ => DEFAULT_HOST.to_string(),
+    }
+}
+
+pub fn validate_model_name(name: &str) -> Result<(), String> {
+    crate::validate_string_input(name, MAX_MODEL_NAME_LENGTH, "Model name")?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | ':' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid model name: {name}"))
+    }
+}
+
+/// Turns a failed response into an error with Ollama's message.
+pub async fn check(response: reqwest::Response, action: &str) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| v["error"].as_str().map(String::from))
+        .unwrap_or(body);
+    log::error!("Ollama failed to {action}: {status} {message}");
+    Err(format!("Ollama failed to {action}: {message}"))
+}
+
+pub fn unreachable(e: reqwest::Error) -> String {
+    format!("Ollama is not reachable at {}: {e}", base_url())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaStatus {
+    /// The `ollama` executable is on the PATH
+    pub installed: bool,
+    /// The daemon answers on `base_url`
+    pub running: bool,
+    pub version: Option<String>,
+    pub base_url: String,
+}
+
+/// Whether the `ollama` executable is on the login shell's PATH.
+fn find_executable() -> bool {
+    let binary = if cfg!(windows) {
+        "ollama.exe"
+    } else {
+        "ollama"
+    };
+    let path = crate::mcp::login_shell_path(&crate::mcp::login_shell());
+    std::env::split_paths(&path).any(|dir| dir.join(binary).is_file())
+}
+
+#[tauri::command]
+pub async fn ollama_status() -> Result<OllamaStatus, String> {
+    let base_url = base_url();
+    let client = crate::network::client_builder()
+        .timeout(STATUS_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let version = match client.get(format!("{base_url}/api/version")).send().await {
+        Ok(response) if response.status().is_success() => response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|v| v["version"].as_str().map(String::from)),
+        _ => None,
+    };
+    let running = version.is_some();
+    let installed = running
+        || tauri::async_runtime::spawn_blocking(find_executable)
+            .await
+            .unwrap_or(false);
+
+    log::debug!("Ollama installed: {installed}, running: {running}");
+    Ok(OllamaStatus {
+        installed,
+        running,
+        version,
+        base_url,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct OllamaModel {
+    /// e.g. `llama3.2:latest`; the model id for chat requests
+    pub name: String,
+    /// Bytes on disk
+    pub size: u64,
+    pub digest: String,
+    /// RFC 3339
+    pub modified_at: String,
+    #[serde(default)]
+    pub details: Option<OllamaModelDetails>,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    models: Vec<OllamaModel>,
+}
+
+/// Models the local daemon has, sorted by name.
+#[tauri::command]
+pub async fn ollama_list_models() -> Result<Vec<OllamaModel>, String> {
+    let response = crate::llm::http_client()
+        .get(format!("{}/api/tags", base_url()))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let mut models = check(response, "list models")
+        .await?
+        .json::<TagsResponse>()
+        .await
+        .map_err(|e| format!("Unexpected model list from Ollama: {e}"))?
+        .models;
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Downloads a model as a `model_download` operation, whose progress is the
+/// bytes of the layer being pulled. Resolves once the model is ready.
+/// Cancelling the operation disconnects, which stops the daemon's pull.
+#[tauri::command]
+pub async fn ollama_pull_model(app: AppHandle, name: String) -> Result<(), String> {
+    validate_model_name(&name)?;
+    log::info!("Pulling Ollama model {name}");
+
+    let mut operation = operations::start(
+        &app,
+        OperationKind::ModelDownload,
+        name.clone(),
+        ProgressUnit::Bytes,
+    );
+    let result = loop {
+        if let Err(e) = crate::power::wait_while_paused(&app, &mut operation).await {
+            break Err(e);
+        }
+        match pull(&app, &name, &mut operation).await {
+            Err(e) if e == crate::power::PAUSED => continue,
+            result => break result,
+        }
+    };
+    operation.finish(result).map_err(|e| {
+        log::error!("Failed to pull Ollama model {name}: {e}");
+        format!("Failed to pull {name}: {e}")
+    })?;
+    log::info!("Pulled Ollama model {name}");
+    Ok(())
+}
+
+/// Breaks off with `power::PAUSED` once background work is paused.
+async fn pull(app: &AppHandle, name: &str, operation: &mut Operation) -> Result<(), String> {
+    let response = crate::llm::http_client()
+        .post(format!("{}/api/pull", base_url()))
+        .json(&json!({ "model": name, "stream": true }))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let mut response = check(response, &format!("pull {name}")).await?;
+
+    // Newline-delimited JSON, one status object per line
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Download interrupted: {e}"))?
+    {
+        operation.check_cancelled()?;
+        if crate::power::paused(app).is_some() {
+            return Err(crate::power::PAUSED.to_string());
+        }
+        buffer.extend_from_slice(&chunk);
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            pull_status(&line, operation)?;
+        }
+    }
+    if !buffer.is_empty() {
+        pull_status(&buffer, operation)?;
+    }
+    Ok(())
+}
+
+/// Records one line of pull status as progress, or returns its error.
+fn pull_status(line: &[u8], operation: &mut Operation) -> Result<(), String> {
+    let Ok(value) = serde_json::from_slice::<Value>(line) else {
+        return Ok(());
+    };
+    if let Some(error) = value["error"].as_str() {
+        return Err(error.to_string());
+    }
+    operation.progress(
+        value["completed"].as_u64().unwrap_or(0),
+        value["total"].as_u64(),
+        value["status"].as_str().map(String::from),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ollama_delete_model(name: String) -> Result<(), String> {
+    validate_model_name(&name)?;
+    let response = crate::llm::http_client()
+        .delete(format!("{}/api/delete", base_url()))
+        .json(&json!({ "model": name }))
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    check(response, &format!("delete {name}")).await?;
+    log::info!("Deleted Ollama model {name}");
+    Ok(())
+}
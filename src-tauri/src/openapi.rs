@@ -0,0 +1,932 @@
+// OpenAPI Tools
+// =============
+//
+// Tools generated from an OpenAPI 3 description, so the model can call an
+// internal HTTP API without anyone writing an MCP server for it. Registering
+// a spec (from a URL or a local JSON or YAML file) turns the selected
+// operations into tool definitions whose input schema is built from the
+// operation's parameters and JSON request body. Calling a tool builds the
+// request, adds the credential from the keychain and reads at most
+// `max_response_bytes` of the response, so a large listing cannot flood the
+// conversation.
+//
+// Registered APIs live in `openapi_tools.json` in the app data directory
+// with their generated operations, so the spec is only fetched once.
+// Credentials are kept in the keychain under `openapi:<id>`.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::keychain;
+
+const MAX_APIS: usize = 50;
+const MAX_OPERATIONS: usize = 200;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_SPEC_BYTES: usize = 10 * 1024 * 1024;
+const DEFAULT_RESPONSE_BYTES: usize = 256 * 1024;
+const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+const MAX_DESCRIPTION_CHARS: usize = 1024;
+/// Providers accept tool names of up to 64 `[a-zA-Z0-9_-]` characters
+const MAX_TOOL_NAME_CHARS: usize = 64;
+/// Characters of the API name used as tool name prefix
+const MAX_PREFIX_CHARS: usize = 20;
+/// `$ref`s followed within one schema; recursive schemas are cut off here
+const MAX_REF_DEPTH: usize = 16;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "patch", "head", "options", "trace",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpenApiAuth {
+    #[default]
+    None,
+    /// The credential is sent as a bearer token
+    Bearer,
+    /// The credential is sent in a header or query parameter
+    ApiKey {
+        name: String,
+        location: ApiKeyLocation,
+    },
+    /// The credential is the password
+    Basic { username: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    Header,
+    Query,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationParameter {
+    pub name: String,
+    pub location: ParameterLocation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiOperation {
+    /// Name of the tool, unique across all registered APIs
+    pub tool_name: String,
+    /// `operationId` from the spec, or `METHOD /path` if it has none
+    pub operation_id: String,
+    /// Upper-case HTTP method
+    pub method: String,
+    /// Path template relative to the base URL, e.g. `/users/{id}`
+    pub path: String,
+    pub description: String,
+    pub parameters: Vec<OperationParameter>,
+    /// The operation takes a JSON request body, passed as the `body`
+    /// argument
+    pub has_body: bool,
+    /// JSON Schema of the tool's arguments
+    pub input_schema: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiIntegration {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    pub name: String,
+    /// URL or path the spec was read from
+    pub source: String,
+    /// Requests go to this URL followed by the operation's path
+    pub base_url: String,
+    pub auth: OpenApiAuth,
+    /// Response bytes returned to the model; the rest is cut off
+    pub max_response_bytes: usize,
+    pub operations: Vec<OpenApiOperation>,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: i64,
+}
+
+/// A registered API as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiIntegrationInfo {
+    #[serde(flatten)]
+    pub integration: OpenApiIntegration,
+    /// A secret is stored in the keychain
+    pub has_credential: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiOptions {
+    /// Defaults to the spec's title
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `operationId`s (or `METHOD /path`) to expose; all when left out
+    #[serde(default)]
+    pub operations: Option<Vec<String>>,
+    /// Overrides the first server of the spec
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub max_response_bytes: Option<usize>,
+}
+
+/// Result of calling a tool backed by an HTTP API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpToolResult {
+    pub status: u16,
+    /// Response body as text, cut off after `max_response_bytes`
+    pub content: String,
+    pub truncated: bool,
+    /// The API answered with a status outside 2xx
+    pub is_error: bool,
+}
+
+fn credential_account(id: &str) -> String {
+    format!("openapi:{id}")
+}
+
+fn get_registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("openapi_tools.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<OpenApiIntegration>, String> {
+    let path = get_registry_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read OpenAPI tools: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse OpenAPI tools: {e}");
+        format!("Failed to parse OpenAPI tools: {e}")
+    })
+}
+
+fn store(app: &AppHandle, integrations: &[OpenApiIntegration]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(integrations)
+        .map_err(|e| format!("Failed to serialize OpenAPI tools: {e}"))?;
+    crate::export::write_atomic(&get_registry_path(app)?, &json)
+}
+
+pub fn parse_http_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Only http and https URLs are supported".to_string());
+    }
+    Ok(parsed)
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    crate::network::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("Nexus/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+/// Reads at most `limit` bytes of a response body. Returns the bytes and
+/// whether the body was longer.
+pub async fn read_limited(
+    mut response: reqwest::Response,
+    limit: usize,
+) -> Result<(Vec<u8>, bool), String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response: {e}"))?
+    {
+        let room = limit - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+/// Turns a response into a tool result with at most `limit` bytes of body.
+pub async fn tool_result(
+    response: reqwest::Response,
+    limit: usize,
+) -> Result<HttpToolResult, String> {
+    let status = response.status();
+    let (body, truncated) = read_limited(response, limit).await?;
+    let mut content = String::from_utf8_lossy(&body).into_owned();
+    if truncated {
+        content.push_str(&format!("\n\n[Response truncated after {limit} bytes]"));
+    }
+
+    Ok(HttpToolResult {
+        status: status.as_u16(),
+        content,
+        truncated,
+        is_error: !status.is_success(),
+    })
+}
+
+/// Adds an API key that is sent as a query parameter to `url`.
+pub fn authorize_url(url: &mut Url, auth: &OpenApiAuth, credential: Option<&str>) {
+    if let (
+        OpenApiAuth::ApiKey {
+            name,
+            location: ApiKeyLocation::Query,
+        },
+        Some(key),
+    ) = (auth, credential)
+    {
+        url.query_pairs_mut().append_pair(name, key);
+    }
+}
+
+/// Adds a credential that is sent in a header to `request`.
+pub fn authorize_request(
+    request: reqwest::RequestBuilder,
+    auth: &OpenApiAuth,
+    credential: Option<String>,
+) -> reqwest::RequestBuilder {
+    match (auth, credential) {
+        (OpenApiAuth::Bearer, Some(token)) => request.bearer_auth(token),
+        (
+            OpenApiAuth::ApiKey {
+                name,
+                location: ApiKeyLocation::Header,
+            },
+            Some(key),
+        ) => request.header(name, key),
+        (OpenApiAuth::Basic { username }, password) => request.basic_auth(username, password),
+        _ => request,
+    }
+}
+
+/// Checks the parts of `auth` that end up in a request.
+pub fn validate_auth(auth: &OpenApiAuth) -> Result<(), String> {
+    match auth {
+        OpenApiAuth::ApiKey { name, location } => {
+            if matches!(location, ApiKeyLocation::Header) {
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| format!("Invalid header name: {name}"))?;
+            } else if name.trim().is_empty() {
+                return Err("API key parameter name is required".to_string());
+            }
+        }
+        OpenApiAuth::Basic { username } => {
+            crate::validate_string_input(username, 256, "Username")?;
+        }
+        OpenApiAuth::None | OpenApiAuth::Bearer => {}
+    }
+    Ok(())
+}
+
+/// Names of the tools generated from OpenAPI specs.
+pub fn tool_names(app: &AppHandle) -> Vec<String> {
+    load(app)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|i| i.operations.into_iter().map(|o| o.tool_name))
+        .collect()
+}
+
+/// Reads a spec from an http(s) URL or an absolute path. Returns the text
+/// and the URL it came from, against which relative server URLs resolve.
+async fn fetch_spec(source: &str) -> Result<(String, Option<Url>), String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let url = parse_http_url(source)?;
+        let response = http_client()?
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch spec: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!("Fetching the spec returned {}", response.status()));
+        }
+        let (bytes, truncated) = read_limited(response, MAX_SPEC_BYTES).await?;
+        if truncated {
+            return Err("Spec is too large".to_string());
+        }
+        let text = String::from_utf8(bytes).map_err(|_| "Spec is not valid UTF-8".to_string())?;
+        return Ok((text, Some(url)));
+    }
+
+    let path = Path::new(source);
+    if !path.is_absolute() {
+        return Err("Spec must be an http(s) URL or an absolute path".to_string());
+    }
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to read spec: {e}"))?;
+    if metadata.len() > MAX_SPEC_BYTES as u64 {
+        return Err("Spec is too large".to_string());
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read spec: {e}"))?;
+    Ok((text, None))
+}
+
+/// Parses a JSON or YAML spec and checks that it is OpenAPI 3.
+fn parse_spec(text: &str) -> Result<Value, String> {
+    let spec: Value = serde_json::from_str(text)
+        .or_else(|_| serde_yaml::from_str(text))
+        .map_err(|e| format!("Spec is neither valid JSON nor YAML: {e}"))?;
+
+    match spec.get("openapi").and_then(Value::as_str) {
+        Some(version) if version.starts_with("3.") => Ok(spec),
+        Some(version) => Err(format!("Unsupported OpenAPI version {version}")),
+        None if spec.get("swagger").is_some() => {
+            Err("Swagger 2.0 specs are not supported; convert the spec to OpenAPI 3".to_string())
+        }
+        None => Err("Not an OpenAPI spec".to_string()),
+    }
+}
+
+/// Follows local `$ref`s (`#/components/...`) until a plain value.
+fn resolve<'a>(spec: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REF_DEPTH {
+        let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+            return value;
+        };
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| spec.pointer(pointer))
+        {
+            Some(target) => value = target,
+            None => return &Value::Null,
+        }
+    }
+    &Value::Null
+}
+
+/// Copies a schema with every local `$ref` replaced by its target, so tool
+/// schemas stand alone. References nested deeper than `MAX_REF_DEPTH`, and
+/// ones that cannot be resolved, become an unconstrained schema.
+fn inline_schema(spec: &Value, schema: &Value, depth: usize) -> Value {
+    match schema {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                let target = reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| spec.pointer(pointer));
+                return match target {
+                    Some(target) if depth < MAX_REF_DEPTH => inline_schema(spec, target, depth + 1),
+                    _ => json!({}),
+                };
+            }
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), inline_schema(spec, value, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| inline_schema(spec, item, depth))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Replaces characters providers reject in tool names.
+pub fn sanitize_tool_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// A tool name for `operation_id` that is not in `taken`.
+pub fn unique_tool_name(prefix: &str, operation_id: &str, taken: &mut HashSet<String>) -> String {
+    let base: String = sanitize_tool_name(&format!("{prefix}_{operation_id}"))
+        .chars()
+        .take(MAX_TOOL_NAME_CHARS)
+        .collect();
+    let mut name = base.clone();
+    let mut counter = 2;
+    while taken.contains(&name) {
+        let suffix = format!("_{counter}");
+        name = base
+            .chars()
+            .take(MAX_TOOL_NAME_CHARS - suffix.len())
+            .chain(suffix.chars())
+            .collect();
+        counter += 1;
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// Builds the tool for one operation, or `None` if it cannot be called as
+/// a tool (a request body that is not JSON).
+fn build_operation(
+    spec: &Value,
+    method: &str,
+    path: &str,
+    shared_parameters: &[Value],
+    operation: &Value,
+) -> Option<(Vec<OperationParameter>, bool, Value)> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut parameters: Vec<OperationParameter> = Vec::new();
+
+    // Operation parameters override path-level ones with the same name
+    let own_parameters = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for parameter in own_parameters.iter().chain(shared_parameters) {
+        let parameter = resolve(spec, parameter);
+        let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let location = match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => ParameterLocation::Path,
+            Some("query") => ParameterLocation::Query,
+            Some("header") => ParameterLocation::Header,
+            // Cookie parameters are not supported
+            _ => continue,
+        };
+        if properties.contains_key(name) {
+            continue;
+        }
+
+        let mut schema = parameter
+            .get("schema")
+            .map(|schema| inline_schema(spec, schema, 0))
+            .unwrap_or_else(|| json!({ "type": "string" }));
+        if let (Some(description), Some(object)) = (
+            parameter.get("description").and_then(Value::as_str),
+            schema.as_object_mut(),
+        ) {
+            object.insert("description".to_string(), json!(description));
+        }
+        if location == ParameterLocation::Path
+            || parameter.get("required").and_then(Value::as_bool) == Some(true)
+        {
+            required.push(json!(name));
+        }
+        properties.insert(name.to_string(), schema);
+        parameters.push(OperationParameter {
+            name: name.to_string(),
+            location,
+        });
+    }
+
+    let mut has_body = false;
+    if let Some(request_body) = operation.get("requestBody") {
+        let request_body = resolve(spec, request_body);
+        let content = request_body.get("content").and_then(Value::as_object);
+        let json_schema = content.and_then(|content| {
+            content
+                .iter()
+                .find(|(mime, _)| *mime == "application/json" || mime.ends_with("+json"))
+                .map(|(_, media)| media.get("schema").cloned().unwrap_or_else(|| json!({})))
+        });
+        match json_schema {
+            Some(schema) => {
+                let mut schema = inline_schema(spec, &schema, 0);
+                if let (Some(description), Some(object)) = (
+                    request_body.get("description").and_then(Value::as_str),
+                    schema.as_object_mut(),
+                ) {
+                    object.insert("description".to_string(), json!(description));
+                }
+                if properties.contains_key("body") {
+                    log::warn!("Skipping {method} {path}: parameter named body");
+                    return None;
+                }
+                properties.insert("body".to_string(), schema);
+                if request_body.get("required").and_then(Value::as_bool) == Some(true) {
+                    required.push(json!("body"));
+                }
+                has_body = true;
+            }
+            None if content.is_some_and(|content| !content.is_empty()) => {
+                log::warn!("Skipping {method} {path}: request body is not JSON");
+                return None;
+            }
+            None => {}
+        }
+    }
+
+    let input_schema = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    Some((parameters, has_body, input_schema))
+}
+
+/// Generates tools for the operations in `spec`, restricted to `selected`
+/// if given. Tool names are prefixed with `prefix` and kept out of `taken`.
+fn generate_operations(
+    spec: &Value,
+    prefix: &str,
+    selected: Option<&[String]>,
+    taken: &mut HashSet<String>,
+) -> Result<Vec<OpenApiOperation>, String> {
+    let paths = spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| "Spec has no paths".to_string())?;
+
+    let mut found: HashSet<&str> = HashSet::new();
+    let mut operations = Vec::new();
+    for (path, item) in paths {
+        let item = resolve(spec, item);
+        let shared_parameters = item
+            .get("parameters")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let method = method.to_ascii_uppercase();
+            let route = format!("{method} {path}");
+            let operation_id = operation
+                .get("operationId")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| route.clone());
+            if let Some(selected) = selected {
+                let Some(entry) = selected
+                    .iter()
+                    .find(|s| **s == operation_id || s.eq_ignore_ascii_case(&route))
+                else {
+                    continue;
+                };
+                found.insert(entry.as_str());
+            }
+            if operation.get("deprecated").and_then(Value::as_bool) == Some(true)
+                && selected.is_none()
+            {
+                continue;
+            }
+
+            let Some((parameters, has_body, input_schema)) =
+                build_operation(spec, &method, path, shared_parameters, operation)
+            else {
+                continue;
+            };
+            let description = [operation.get("summary"), operation.get("description")]
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let description = if description.is_empty() {
+                route.clone()
+            } else {
+                description.chars().take(MAX_DESCRIPTION_CHARS).collect()
+            };
+
+            operations.push(OpenApiOperation {
+                tool_name: unique_tool_name(prefix, &operation_id, taken),
+                operation_id,
+                method,
+                path: path.clone(),
+                description,
+                parameters,
+                has_body,
+                input_schema,
+            });
+        }
+    }
+
+    if let Some(selected) = selected {
+        let missing: Vec<&str> = selected
+            .iter()
+            .map(String::as_str)
+            .filter(|s| !found.contains(s))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Operations not found in spec: {}",
+                missing.join(", ")
+            ));
+        }
+    }
+    if operations.is_empty() {
+        return Err("Spec has no operations that can be used as tools".to_string());
+    }
+    if operations.len() > MAX_OPERATIONS {
+        return Err(format!(
+            "Spec has {} operations; select at most {MAX_OPERATIONS}",
+            operations.len()
+        ));
+    }
+    Ok(operations)
+}
+
+/// The base URL from the first server in the spec, with variables set to
+/// their defaults and relative URLs resolved against the spec's URL.
+fn server_url(spec: &Value, spec_url: Option<&Url>) -> Result<String, String> {
+    let server = spec
+        .get("servers")
+        .and_then(Value::as_array)
+        .and_then(|servers| servers.first())
+        .ok_or_else(|| "Spec has no servers; set a base URL".to_string())?;
+    let mut url = server
+        .get("url")
+        .and_then(Value::as_str)
+        .unwrap_or("/")
+        .to_string();
+    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{name}}}"), default);
+            }
+        }
+    }
+
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(url);
+    }
+    match spec_url {
+        Some(spec_url) => spec_url
+            .join(&url)
+            .map(|url| url.to_string())
+            .map_err(|e| format!("Invalid server URL {url}: {e}")),
+        None => Err(format!("Server URL {url} is relative; set a base URL")),
+    }
+}
+
+/// Text of an argument as it goes into a path, query or header.
+fn argument_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Percent-encodes a path segment (RFC 3986 unreserved characters stay).
+fn encode_path_segment(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Deletes the keychain entries of every registered API. Used when wiping
+/// app data; failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for integration in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&credential_account(&integration.id)) {
+            log::warn!("Failed to delete credential of {}: {e}", integration.id);
+        }
+    }
+}
+
+/// Reads an OpenAPI 3 spec from `source` (an http(s) URL or an absolute
+/// path to a JSON or YAML file) and registers its operations as tools. A
+/// non-empty `credential` is stored in the keychain and sent as `auth`
+/// describes.
+#[tauri::command]
+pub async fn register_openapi_tool(
+    app: AppHandle,
+    source: String,
+    auth: Option<OpenApiAuth>,
+    credential: Option<String>,
+    options: Option<OpenApiOptions>,
+) -> Result<OpenApiIntegrationInfo, String> {
+    let source = source.trim().to_string();
+    crate::validate_string_input(&source, 4096, "Spec location")?;
+    let auth = auth.unwrap_or_default();
+    let options = options.unwrap_or_default();
+    validate_auth(&auth)?;
+    let max_response_bytes = options
+        .max_response_bytes
+        .unwrap_or(DEFAULT_RESPONSE_BYTES)
+        .clamp(1024, MAX_RESPONSE_BYTES);
+
+    let mut integrations = load(&app)?;
+    if integrations.len() >= MAX_APIS {
+        return Err(format!("Too many OpenAPI integrations (max {MAX_APIS})"));
+    }
+
+    let (text, spec_url) = fetch_spec(&source).await.map_err(|e| {
+        log::error!("Failed to read OpenAPI spec {source}: {e}");
+        e
+    })?;
+    let spec = parse_spec(&text)?;
+
+    let name = options
+        .name
+        .as_deref()
+        .or_else(|| spec.pointer("/info/title").and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("api")
+        .to_string();
+    crate::validate_string_input(&name, MAX_NAME_CHARS, "Name")?;
+    let base_url = match options.base_url.as_deref().map(str::trim) {
+        Some(url) if !url.is_empty() => url.to_string(),
+        _ => server_url(&spec, spec_url.as_ref())?,
+    };
+    parse_http_url(&base_url)?;
+
+    let prefix: String = sanitize_tool_name(&name.to_ascii_lowercase())
+        .chars()
+        .take(MAX_PREFIX_CHARS)
+        .collect();
+    let mut taken: HashSet<String> = tool_names(&app)
+        .into_iter()
+        .chain(crate::graphql::tool_names(&app))
+        .collect();
+    let operations =
+        generate_operations(&spec, &prefix, options.operations.as_deref(), &mut taken)?;
+
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate id: {e}"))?;
+    let integration = OpenApiIntegration {
+        id: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        name,
+        source,
+        base_url,
+        auth,
+        max_response_bytes,
+        operations,
+        created_at: crate::time::now_ms() as i64,
+    };
+
+    let has_credential = match credential.as_deref() {
+        Some(secret) if !secret.is_empty() => {
+            keychain::set(&credential_account(&integration.id), secret)?;
+            true
+        }
+        _ => false,
+    };
+    integrations.push(integration.clone());
+    store(&app, &integrations)?;
+
+    log::info!(
+        "Registered OpenAPI integration {} with {} tools",
+        integration.id,
+        integration.operations.len()
+    );
+    Ok(OpenApiIntegrationInfo {
+        integration,
+        has_credential,
+    })
+}
+
+#[tauri::command]
+pub async fn list_openapi_tools(app: AppHandle) -> Result<Vec<OpenApiIntegrationInfo>, String> {
+    load(&app)?
+        .into_iter()
+        .map(|integration| {
+            let has_credential = keychain::get(&credential_account(&integration.id))?.is_some();
+            Ok(OpenApiIntegrationInfo {
+                integration,
+                has_credential,
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn remove_openapi_tool(app: AppHandle, id: String) -> Result<(), String> {
+    let mut integrations = load(&app)?;
+    integrations.retain(|i| i.id != id);
+    store(&app, &integrations)?;
+    keychain::delete(&credential_account(&id))?;
+    log::info!("Removed OpenAPI integration {id}");
+    Ok(())
+}
+
+/// Calls the operation behind a generated tool with the model's arguments.
+/// HTTP errors are returned as results so the model can see them; only
+/// failures to make the request at all are errors.
+#[tauri::command]
+pub async fn call_openapi_tool(
+    app: AppHandle,
+    tool_name: String,
+    arguments: Option<Value>,
+) -> Result<HttpToolResult, String> {
+    let integrations = load(&app)?;
+    let (integration, operation) = integrations
+        .iter()
+        .find_map(|integration| {
+            integration
+                .operations
+                .iter()
+                .find(|o| o.tool_name == tool_name)
+                .map(|operation| (integration, operation))
+        })
+        .ok_or_else(|| format!("OpenAPI tool {tool_name} not found"))?;
+    let arguments = match arguments {
+        Some(Value::Object(arguments)) => arguments,
+        None | Some(Value::Null) => Map::new(),
+        Some(_) => return Err("Tool arguments must be an object".to_string()),
+    };
+
+    let mut path = operation.path.clone();
+    for parameter in &operation.parameters {
+        if parameter.location != ParameterLocation::Path {
+            continue;
+        }
+        let value = arguments
+            .get(&parameter.name)
+            .filter(|value| !value.is_null())
+            .ok_or_else(|| format!("Missing path parameter {}", parameter.name))?;
+        path = path.replace(
+            &format!("{{{}}}", parameter.name),
+            &encode_path_segment(&argument_text(value)),
+        );
+    }
+    let mut url = parse_http_url(&format!(
+        "{}{path}",
+        integration.base_url.trim_end_matches('/')
+    ))?;
+
+    let credential = keychain::get(&credential_account(&integration.id))?;
+    if !matches!(integration.auth, OpenApiAuth::None) && credential.is_none() {
+        return Err(format!("No credential stored for {}", integration.name));
+    }
+
+    {
+        let mut query = url.query_pairs_mut();
+        for parameter in &operation.parameters {
+            if parameter.location != ParameterLocation::Query {
+                continue;
+            }
+            match arguments.get(&parameter.name) {
+                None | Some(Value::Null) => {}
+                Some(Value::Array(values)) => {
+                    for value in values {
+                        query.append_pair(&parameter.name, &argument_text(value));
+                    }
+                }
+                Some(value) => {
+                    query.append_pair(&parameter.name, &argument_text(value));
+                }
+            }
+        }
+    }
+    authorize_url(&mut url, &integration.auth, credential.as_deref());
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+
+    let method = reqwest::Method::from_bytes(operation.method.as_bytes())
+        .map_err(|e| format!("Invalid method {}: {e}", operation.method))?;
+    let mut request = http_client()?.request(method, url);
+    for parameter in &operation.parameters {
+        if parameter.location != ParameterLocation::Header {
+            continue;
+        }
+        if let Some(value) = arguments.get(&parameter.name).filter(|v| !v.is_null()) {
+            request = request.header(&parameter.name, argument_text(value));
+        }
+    }
+    if operation.has_body {
+        if let Some(body) = arguments.get("body").filter(|v| !v.is_null()) {
+            request = request.json(body);
+        }
+    }
+    request = authorize_request(request, &integration.auth, credential);
+
+    log::info!(
+        "Calling OpenAPI tool {tool_name} ({} {})",
+        operation.method,
+        operation.path
+    );
+    let response = request.send().await.map_err(|e| {
+        log::error!("OpenAPI tool {tool_name} failed: {e}");
+        format!("Request to {} failed: {e}", integration.name)
+    })?;
+    tool_result(response, integration.max_response_bytes).await
+}
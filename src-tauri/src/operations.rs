@@ -0,0 +1,349 @@
+// Long-Running Operations
+// =======================
+//
+// One registry for every task that takes long enough to need a progress
+// bar: conversation imports, exports, model downloads, storage migrations
+// and provider benchmarks. A task calls `start` to register itself and gets
+// an `Operation` handle to report progress on, to poll for cancellation
+// between units of work, and to finish with its result.
+//
+// The frontend sees all of them the same way: `list_operations` for the
+// current and recently finished ones, `cancel_operation` to ask one to
+// stop, and the `operation-started`, `operation-progress` and
+// `operation-finished` events, each carrying the operation's `OperationInfo`.
+// Cancellation is cooperative: the task stops at its next check and
+// finishes as cancelled, leaving whatever it completed in place. Running
+// operations also drive the dock or taskbar progress bar (`indicators`).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::time::now_ms;
+
+/// Finished operations kept for `list_operations`
+const MAX_FINISHED: usize = 50;
+/// Progress events per operation are at most this frequent
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Error a cancelled task returns from `Operation::check_cancelled`.
+pub const CANCELLED: &str = "Cancelled";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Import,
+    Export,
+    ModelDownload,
+    StorageMigration,
+    Benchmark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressUnit {
+    Items,
+    Bytes,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    pub completed: u64,
+    /// `None` while the amount of work is not known yet
+    pub total: Option<u64>,
+    pub unit: ProgressUnit,
+    /// What the task is doing right now, e.g. a download's current layer
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+    /// e.g. the model being downloaded or the file being imported
+    pub label: String,
+    pub status: OperationStatus,
+    pub progress: OperationProgress,
+    /// Set once a cancellation was asked for, while the task winds down
+    pub cancel_requested: bool,
+    /// Milliseconds since the UNIX epoch
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+struct Entry {
+    info: OperationInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+pub struct Operations {
+    entries: Mutex<HashMap<String, Entry>>,
+    next_id: AtomicU64,
+}
+
+impl Operations {
+    /// Running operations first, then finished ones, newest first.
+    pub fn list(&self) -> Vec<OperationInfo> {
+        let Ok(entries) = self.entries.lock() else {
+            return Vec::new();
+        };
+        let mut list: Vec<OperationInfo> = entries.values().map(|e| e.info.clone()).collect();
+        list.sort_by(|a, b| {
+            (b.status == OperationStatus::Running)
+                .cmp(&(a.status == OperationStatus::Running))
+                .then(b.started_at.cmp(&a.started_at))
+        });
+        list
+    }
+
+    /// Applies `change` to an operation and returns its new state.
+    fn update(&self, id: &str, change: impl FnOnce(&mut OperationInfo)) -> Option<OperationInfo> {
+        let mut entries = self.entries.lock().ok()?;
+        let entry = entries.get_mut(id)?;
+        change(&mut entry.info);
+        Some(entry.info.clone())
+    }
+
+    /// Drops the oldest finished operations beyond `MAX_FINISHED`.
+    fn prune(&self) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        let mut finished: Vec<(u64, String)> = entries
+            .values()
+            .filter_map(|e| e.info.finished_at.map(|at| (at, e.info.id.clone())))
+            .collect();
+        if finished.len() <= MAX_FINISHED {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED;
+        for (_, id) in finished.into_iter().take(excess) {
+            entries.remove(&id);
+        }
+    }
+}
+
+fn emit(app: &AppHandle, event: &str, info: &OperationInfo) {
+    if let Err(e) = app.emit(event, info) {
+        log::warn!("Failed to emit {event} event: {e}");
+    }
+}
+
+/// An operation's cancellation, for work it spreads over several tasks.
+#[derive(Clone)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A registered operation, owned by the task doing the work. Dropping it
+/// without calling `finish` marks the operation cancelled if that was asked
+/// for, and failed otherwise.
+pub struct Operation {
+    app: AppHandle,
+    id: String,
+    cancel: Arc<AtomicBool>,
+    last_progress: Option<Instant>,
+    finished: bool,
+}
+
+/// Registers a new running operation and announces it with
+/// `operation-started`.
+pub fn start(
+    app: &AppHandle,
+    kind: OperationKind,
+    label: impl Into<String>,
+    unit: ProgressUnit,
+) -> Operation {
+    let operations = app.state::<Operations>();
+    let id = format!(
+        "op-{}",
+        operations.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    );
+    let cancel = Arc::new(AtomicBool::new(false));
+    let info = OperationInfo {
+        id: id.clone(),
+        kind,
+        label: label.into(),
+        status: OperationStatus::Running,
+        progress: OperationProgress {
+            completed: 0,
+            total: None,
+            unit,
+            message: None,
+        },
+        cancel_requested: false,
+        started_at: now_ms(),
+        finished_at: None,
+        error: None,
+    };
+    log::debug!("Operation {id} started: {kind:?} {}", info.label);
+
+    if let Ok(mut entries) = operations.entries.lock() {
+        entries.insert(
+            id.clone(),
+            Entry {
+                info: info.clone(),
+                cancel: cancel.clone(),
+            },
+        );
+    }
+    operations.prune();
+    emit(app, "operation-started", &info);
+    crate::indicators::refresh_progress(app);
+
+    Operation {
+        app: app.clone(),
+        id,
+        cancel,
+        last_progress: None,
+        finished: false,
+    }
+}
+
+impl Operation {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel_flag(&self) -> CancelFlag {
+        CancelFlag(self.cancel.clone())
+    }
+
+    /// `Err(CANCELLED)` once a cancellation was asked for, to return early
+    /// with `?` between units of work.
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records progress. Events are throttled, except for the last step of
+    /// a known total.
+    pub fn progress(&mut self, completed: u64, total: Option<u64>, message: Option<String>) {
+        let Some(info) = self.app.state::<Operations>().update(&self.id, |info| {
+            info.progress.completed = completed;
+            info.progress.total = total;
+            info.progress.message = message;
+        }) else {
+            return;
+        };
+        let last = total.is_some_and(|total| completed >= total);
+        if !last
+            && self
+                .last_progress
+                .is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_progress = Some(Instant::now());
+        emit(&self.app, "operation-progress", &info);
+        crate::indicators::refresh_progress(&self.app);
+    }
+
+    /// Replaces the progress message, keeping the progress itself, e.g.
+    /// while the task waits for something.
+    pub fn set_message(&mut self, message: Option<String>) {
+        let Some(info) = self
+            .app
+            .state::<Operations>()
+            .update(&self.id, |info| info.progress.message = message)
+        else {
+            return;
+        };
+        emit(&self.app, "operation-progress", &info);
+    }
+
+    /// Marks the operation completed, failed or cancelled after `result`
+    /// and announces it with `operation-finished`. Returns `result`.
+    pub fn finish<T>(mut self, result: Result<T, String>) -> Result<T, String> {
+        let error = result.as_ref().err().cloned();
+        self.close(error);
+        result
+    }
+
+    fn close(&mut self, error: Option<String>) {
+        self.finished = true;
+        let cancelled = self.is_cancelled() && error.as_deref() == Some(CANCELLED);
+        let status = match &error {
+            None => OperationStatus::Completed,
+            Some(_) if cancelled => OperationStatus::Cancelled,
+            Some(_) => OperationStatus::Failed,
+        };
+        let operations = self.app.state::<Operations>();
+        let Some(info) = operations.update(&self.id, |info| {
+            info.status = status;
+            info.finished_at = Some(now_ms());
+            info.error = error.filter(|_| !cancelled);
+        }) else {
+            return;
+        };
+        log::debug!("Operation {} finished: {status:?}", self.id);
+        emit(&self.app, "operation-finished", &info);
+        operations.prune();
+        crate::indicators::refresh_progress(&self.app);
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if !self.finished {
+            let error = if self.is_cancelled() {
+                CANCELLED
+            } else {
+                "The operation ended unexpectedly"
+            };
+            self.close(Some(error.to_string()));
+        }
+    }
+}
+
+/// Current and recently finished operations, running ones first.
+#[tauri::command]
+pub async fn list_operations(app: AppHandle) -> Result<Vec<OperationInfo>, String> {
+    Ok(app.state::<Operations>().list())
+}
+
+/// Asks a running operation to stop. Returns `false` if it is not running.
+#[tauri::command]
+pub async fn cancel_operation(app: AppHandle, id: String) -> Result<bool, String> {
+    let info = {
+        let operations = app.state::<Operations>();
+        let mut entries = operations
+            .entries
+            .lock()
+            .map_err(|e| format!("Failed to lock operations: {e}"))?;
+        match entries.get_mut(&id) {
+            Some(entry) if entry.info.status == OperationStatus::Running => {
+                entry.cancel.store(true, Ordering::Relaxed);
+                entry.info.cancel_requested = true;
+                entry.info.clone()
+            }
+            _ => return Ok(false),
+        }
+    };
+    log::info!("Cancelling operation {id}");
+    emit(&app, "operation-progress", &info);
+    Ok(true)
+}
@@ -0,0 +1,277 @@
+// Storage Backends
+// ================
+//
+// Conversations are stored through the `Storage` trait, so commands do not
+// depend on where the data lives. Two backends exist:
+//
+// - `sqlite`, the default: the database in `storage`, which also holds
+//   folders, tags, pins, message revisions and the search index.
+// - `json`: one file per conversation in `conversations/`, the format the
+//   frontend mirror uses (see `conversations`).
+//
+// The backend is chosen by the `storage_backend` preference when the app
+// starts. Only `migrate_storage` changes it: it copies every conversation
+// to the other backend and switches over right away, so picking a backend
+// can never leave the app looking at an empty store. The data in the
+// previous backend is left in place.
+//
+// A new backend (encrypted, remote, in-memory for tests) implements
+// `Storage` and gets a `StorageBackend` variant; nothing else changes.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+
+use crate::conversations::{ChatMessage, Conversation};
+use crate::operations::{self, Operation, OperationKind, ProgressUnit};
+
+pub trait Storage: Send + Sync {
+    fn kind(&self) -> StorageBackend;
+
+    /// A conversation with all of its messages, or `None` if it does not
+    /// exist.
+    fn load(&self, app: &AppHandle, id: &str) -> Result<Option<Conversation>, String>;
+
+    /// Ids of every stored conversation.
+    fn ids(&self, app: &AppHandle) -> Result<Vec<String>, String>;
+
+    /// Stores a new conversation with its messages, keeping its timestamps.
+    /// Fails if one with the same id exists.
+    fn create(&self, app: &AppHandle, conversation: &Conversation) -> Result<(), String>;
+
+    /// Adds messages to the end of a conversation.
+    fn append(
+        &self,
+        app: &AppHandle,
+        conversation_id: &str,
+        messages: &[ChatMessage],
+    ) -> Result<(), String>;
+
+    /// Deletes a conversation. Returns whether it existed.
+    fn delete(&self, app: &AppHandle, id: &str) -> Result<bool, String>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    #[default]
+    Sqlite,
+    Json,
+}
+
+impl StorageBackend {
+    fn open(self) -> Arc<dyn Storage> {
+        match self {
+            StorageBackend::Sqlite => Arc::new(SqliteStorage),
+            StorageBackend::Json => Arc::new(JsonStorage),
+        }
+    }
+}
+
+struct SqliteStorage;
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageBackend {
+        StorageBackend::Sqlite
+    }
+
+    fn load(&self, app: &AppHandle, id: &str) -> Result<Option<Conversation>, String> {
+        crate::storage::load(app, id)
+    }
+
+    fn ids(&self, app: &AppHandle) -> Result<Vec<String>, String> {
+        crate::storage::ids(app)
+    }
+
+    fn create(&self, app: &AppHandle, conversation: &Conversation) -> Result<(), String> {
+        crate::storage::create(app, conversation)
+    }
+
+    fn append(
+        &self,
+        app: &AppHandle,
+        conversation_id: &str,
+        messages: &[ChatMessage],
+    ) -> Result<(), String> {
+        crate::storage::append(app, conversation_id, messages)
+    }
+
+    fn delete(&self, app: &AppHandle, id: &str) -> Result<bool, String> {
+        crate::storage::delete(app, id)
+    }
+}
+
+struct JsonStorage;
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageBackend {
+        StorageBackend::Json
+    }
+
+    fn load(&self, app: &AppHandle, id: &str) -> Result<Option<Conversation>, String> {
+        crate::conversations::load(app, id)
+    }
+
+    fn ids(&self, app: &AppHandle) -> Result<Vec<String>, String> {
+        crate::conversations::ids(app)
+    }
+
+    fn create(&self, app: &AppHandle, conversation: &Conversation) -> Result<(), String> {
+        if crate::conversations::load(app, &conversation.id)?.is_some() {
+            return Err(format!("Conversation {} already exists", conversation.id));
+        }
+        crate::conversations::save(app, conversation)
+    }
+
+    fn append(
+        &self,
+        app: &AppHandle,
+        conversation_id: &str,
+        messages: &[ChatMessage],
+    ) -> Result<(), String> {
+        check_writable(app, conversation_id)?;
+        let mut conversation = crate::conversations::load(app, conversation_id)?
+            .ok_or_else(|| format!("Conversation {conversation_id} not found"))?;
+        for message in messages {
+            crate::validate_string_input(&message.id, 256, "Message id")?;
+            if conversation.messages.iter().any(|m| m.id == message.id) {
+                return Err(format!(
+                    "Message {} already exists in conversation {conversation_id}",
+                    message.id
+                ));
+            }
+        }
+        conversation.messages.extend_from_slice(messages);
+        conversation.updated_at = crate::time::now_ms();
+        crate::conversations::save(app, &conversation)
+    }
+
+    fn delete(&self, app: &AppHandle, id: &str) -> Result<bool, String> {
+        check_writable(app, id)?;
+        crate::conversations::remove(app, id)
+    }
+}
+
+/// Archives and locks are kept in the database whatever the backend, so a
+/// conversation copied from it keeps them.
+fn check_writable(app: &AppHandle, id: &str) -> Result<(), String> {
+    crate::archive::check_writable(&crate::db::open(app)?, id)
+}
+
+/// The backend in use, managed as app state.
+pub struct ActiveStorage(RwLock<Arc<dyn Storage>>);
+
+impl ActiveStorage {
+    /// Opens the backend the preferences select.
+    pub fn new(app: &AppHandle) -> Self {
+        let kind = crate::read_preferences(app)
+            .map(|p| p.storage_backend)
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read storage backend preference, using the default: {e}");
+                StorageBackend::default()
+            });
+        log::info!("Using the {kind:?} storage backend");
+        Self(RwLock::new(kind.open()))
+    }
+}
+
+/// The backend in use.
+pub fn current(app: &AppHandle) -> Arc<dyn Storage> {
+    let active = app.state::<ActiveStorage>();
+    let storage = active.0.read().unwrap_or_else(|e| e.into_inner());
+    Arc::clone(&storage)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageMigration {
+    pub backend: StorageBackend,
+    pub copied: u64,
+    /// Already present in the target backend, left as they were
+    pub skipped: u64,
+}
+
+#[tauri::command]
+pub async fn get_storage_backend(app: AppHandle) -> Result<StorageBackend, String> {
+    Ok(current(&app).kind())
+}
+
+/// `migrate_storage`, checking for cancellation between conversations.
+fn migrate(
+    app: &AppHandle,
+    to: StorageBackend,
+    operation: &mut Operation,
+) -> Result<StorageMigration, String> {
+    let source = current(app);
+    if source.kind() == to {
+        return Err(format!("Already using the {to:?} storage backend"));
+    }
+    let target = to.open();
+    log::info!("Migrating storage from {:?} to {to:?}", source.kind());
+
+    let existing = target.ids(app)?;
+    let mut migration = StorageMigration {
+        backend: to,
+        copied: 0,
+        skipped: 0,
+    };
+    let ids = source.ids(app)?;
+    let total = ids.len() as u64;
+    for (index, id) in ids.into_iter().enumerate() {
+        operation.check_cancelled()?;
+        operation.progress(index as u64, Some(total), None);
+        if existing.contains(&id) {
+            migration.skipped += 1;
+            continue;
+        }
+        // Deleted between listing and loading
+        let Some(conversation) = source.load(app, &id)? else {
+            continue;
+        };
+        target.create(app, &conversation).map_err(|e| {
+            log::error!("Storage migration failed at conversation {id}: {e}");
+            format!("Storage migration failed at conversation {id}: {e}")
+        })?;
+        migration.copied += 1;
+    }
+    operation.progress(total, Some(total), None);
+
+    let mut preferences = crate::read_preferences(app)?;
+    preferences.storage_backend = to;
+    crate::write_preferences(app, &preferences)?;
+    *app.state::<ActiveStorage>()
+        .0
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = target;
+
+    log::info!(
+        "Migrated storage to {to:?}: {} copied, {} skipped",
+        migration.copied,
+        migration.skipped
+    );
+    crate::quick_actions::refresh(app);
+    Ok(migration)
+}
+
+/// Copies every conversation into the `to` backend and makes it the one in
+/// use. Conversations the target already has are not overwritten. If any
+/// copy fails, or the migration is cancelled, the app stays on the current
+/// backend.
+#[tauri::command]
+pub async fn migrate_storage(
+    app: AppHandle,
+    to: StorageBackend,
+) -> Result<StorageMigration, String> {
+    let mut operation = operations::start(
+        &app,
+        OperationKind::StorageMigration,
+        format!("{to:?} storage"),
+        ProgressUnit::Items,
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = migrate(&app, to, &mut operation);
+        operation.finish(result)
+    })
+    .await
+    .map_err(|e| format!("Storage migration task failed: {e}"))?
+}
@@ -0,0 +1,600 @@
+// Platform Integration
+// ====================
+//
+// OS integration outside the window itself: the tray icon, notifications
+// with action buttons, the global shortcut that brings the window back and
+// launching at login. Each works on macOS, Windows and Linux, with Linux
+// specific paths where the cross-platform plugins fall short:
+//
+// - The tray icon is a StatusNotifierItem (through libappindicator), which
+//   only shows a menu, so every tray action is a menu item.
+// - Notification actions go over D-Bus (org.freedesktop.Notifications);
+//   elsewhere the notification is shown without its buttons.
+// - Under Wayland, applications cannot grab keys, so the global shortcut is
+//   bound through the xdg-desktop-portal GlobalShortcuts interface. The
+//   compositor asks the user to confirm it once and owns the binding from
+//   then on; changing the preference takes effect at the next launch.
+// - Launch at login writes an XDG autostart entry on Linux, a launch agent
+//   on macOS and a registry key on Windows. It starts the app in daemon
+//   mode, without a window.
+//
+// The tray lists the configured MCP servers with a checkmark on the running
+// ones, counts them in its tooltip and gets a red dot while one has crashed.
+// With `close_to_tray`, closing the window hides it, so servers and the tray
+// stay usable while it is closed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_autostart::ManagerExt as _;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+const TRAY_ID: &str = "main";
+/// Prefix of the ids of the tray's server items
+const TRAY_SERVER_PREFIX: &str = "tray-server:";
+const MAX_TRAY_SERVERS: usize = 50;
+const MAX_NOTIFICATION_ACTIONS: usize = 4;
+const MAX_SHORTCUT_LENGTH: usize = 64;
+/// Arguments a launch at login starts the app with
+pub const AUTOSTART_ARGS: [&str; 1] = ["--daemon"];
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// The portal shortcut is bound at most once per launch
+    static PORTAL_BOUND: AtomicBool = AtomicBool::new(false);
+
+    /// Whether the session runs under Wayland, where X11 key grabs do not
+    /// work.
+    pub fn is_wayland() -> bool {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
+            || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+    }
+
+    pub fn claim_portal_binding() -> bool {
+        !PORTAL_BOUND.swap(true, Ordering::SeqCst)
+    }
+
+    /// Shows a notification over D-Bus with action buttons and calls
+    /// `on_action` with the id of the one clicked.
+    pub fn notify_with_actions(
+        title: &str,
+        body: Option<&str>,
+        actions: &[(String, String)],
+        on_action: impl FnOnce(String) + Send + 'static,
+    ) -> Result<(), String> {
+        let mut notification = notify_rust::Notification::new();
+        notification.appname("Nexus").summary(title);
+        if let Some(body) = body {
+            notification.body(body);
+        }
+        for (id, label) in actions {
+            notification.action(id, label);
+        }
+        let handle = notification
+            .show()
+            .map_err(|e| format!("Failed to send notification: {e}"))?;
+
+        // Blocks until the notification is clicked or closed
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                if action != "__closed" {
+                    on_action(action.to_string());
+                }
+            });
+        });
+        Ok(())
+    }
+
+    /// Converts an accelerator such as `CmdOrCtrl+Shift+Space` to the XDG
+    /// shortcuts format the portal expects, e.g. `CTRL+SHIFT+space`.
+    pub fn portal_trigger(accelerator: &str) -> String {
+        accelerator
+            .split('+')
+            .map(|part| match part.to_ascii_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" | "ctrl" | "control" => "CTRL".to_string(),
+                "alt" | "option" => "ALT".to_string(),
+                "shift" => "SHIFT".to_string(),
+                "super" | "meta" | "cmd" | "command" => "LOGO".to_string(),
+                key if key.chars().count() == 1 => key.to_string(),
+                "space" => "space".to_string(),
+                _ => part.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    /// Binds a shortcut through the GlobalShortcuts portal and calls
+    /// `on_activated` every time it is pressed. Only returns when the portal
+    /// fails or the session ends.
+    pub async fn run_portal_shortcut(
+        id: &str,
+        description: &str,
+        accelerator: &str,
+        on_activated: impl Fn(),
+    ) -> Result<(), String> {
+        use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+        use futures_util::StreamExt;
+
+        let fail = |e: ashpd::Error| format!("Global shortcuts portal failed: {e}");
+        let portal = GlobalShortcuts::new().await.map_err(fail)?;
+        let session = portal.create_session().await.map_err(fail)?;
+        let trigger = portal_trigger(accelerator);
+        let shortcut = NewShortcut::new(id, description).preferred_trigger(trigger.as_str());
+        portal
+            .bind_shortcuts(&session, &[shortcut], None)
+            .await
+            .and_then(|request| request.response())
+            .map_err(fail)?;
+
+        let mut activated = portal.receive_activated().await.map_err(fail)?;
+        while let Some(event) = activated.next().await {
+            if event.shortcut_id() == id {
+                on_activated();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A configured MCP server, as the tray lists it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrayServer {
+    pub id: String,
+    pub name: String,
+}
+
+/// What the tray shows besides the running servers; managed as app state.
+#[derive(Default)]
+pub struct TrayState {
+    /// Configured servers, in the order the frontend lists them
+    servers: Mutex<Vec<TrayServer>>,
+    /// Servers that crashed and haven't been started since
+    unhealthy: Mutex<HashSet<String>>,
+}
+
+/// Payload of the `tray-server-action` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayServerAction {
+    pub server_id: String,
+    /// Start the server, or stop it
+    pub start: bool,
+}
+
+/// The default icon with a red dot in its corner, for unhealthy servers.
+fn badged_icon(icon: &Image<'_>) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    let radius = width.min(height) as f32 * 0.22;
+    let (center_x, center_y) = (width as f32 - radius - 1.0, height as f32 - radius - 1.0);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * width + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&[0xe5, 0x3e, 0x3e, 0xff]);
+            }
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+fn build_tray_menu(
+    app: &AppHandle,
+    servers: &[(TrayServer, bool, bool)],
+    running: usize,
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let mode = crate::window_state::mode(app);
+    let mut menu = MenuBuilder::new(app)
+        .item(&MenuItemBuilder::with_id("tray-new-chat", "New Chat").build(app)?)
+        .item(&MenuItemBuilder::with_id("tray-toggle-window", "Show/Hide Window").build(app)?)
+        .item(
+            &CheckMenuItemBuilder::with_id("tray-toggle-pinned", "Pin on Top")
+                .checked(mode.pinned)
+                .build(app)?,
+        )
+        .item(
+            &CheckMenuItemBuilder::with_id("tray-toggle-compact", "Compact Mode")
+                .checked(mode.compact)
+                .build(app)?,
+        )
+        .separator()
+        .item(
+            &MenuItemBuilder::new(format!("MCP servers: {running} running"))
+                .enabled(false)
+                .build(app)?,
+        );
+    for (server, is_running, unhealthy) in servers {
+        let label = if *unhealthy {
+            format!("{} (crashed)", server.name)
+        } else {
+            server.name.clone()
+        };
+        menu = menu.item(
+            &CheckMenuItemBuilder::with_id(format!("{TRAY_SERVER_PREFIX}{}", server.id), label)
+                .checked(*is_running)
+                .build(app)?,
+        );
+    }
+    menu.separator()
+        .item(&MenuItemBuilder::with_id("tray-quit", "Quit Nexus").build(app)?)
+        .build()
+}
+
+/// Brings the tray up to date with the running servers: the menu, the
+/// tooltip and the badge. Called whenever a server starts, stops or
+/// crashes, and when the configured servers change.
+pub fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app.state::<TrayState>();
+    let running: HashSet<String> = crate::mcp::running_server_ids(app).into_iter().collect();
+    let unhealthy = state
+        .unhealthy
+        .lock()
+        .map(|unhealthy| unhealthy.clone())
+        .unwrap_or_default();
+    let mut servers: Vec<TrayServer> = state
+        .servers
+        .lock()
+        .map(|servers| servers.clone())
+        .unwrap_or_default();
+    // Servers started without being configured, e.g. mocks
+    for id in &running {
+        if !servers.iter().any(|server| &server.id == id) {
+            servers.push(TrayServer {
+                id: id.clone(),
+                name: id.clone(),
+            });
+        }
+    }
+    let servers: Vec<(TrayServer, bool, bool)> = servers
+        .into_iter()
+        .map(|server| {
+            let is_running = running.contains(&server.id);
+            let crashed = unhealthy.contains(&server.id);
+            (server, is_running, crashed)
+        })
+        .collect();
+
+    match build_tray_menu(app, &servers, running.len()) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                log::warn!("Failed to update tray menu: {e}");
+            }
+        }
+        Err(e) => log::warn!("Failed to build tray menu: {e}"),
+    }
+    let mut tooltip = format!("Nexus: {} MCP servers running", running.len());
+    if !unhealthy.is_empty() {
+        tooltip.push_str(&format!(", {} crashed", unhealthy.len()));
+    }
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        log::warn!("Failed to update tray tooltip: {e}");
+    }
+    if let Some(icon) = app.default_window_icon() {
+        let icon = if unhealthy.is_empty() {
+            icon.clone().to_owned()
+        } else {
+            badged_icon(icon)
+        };
+        if let Err(e) = tray.set_icon(Some(icon)) {
+            log::warn!("Failed to update tray icon: {e}");
+        }
+    }
+}
+
+/// Records that a server crashed, badging the tray until it is started
+/// again.
+pub fn server_crashed(app: &AppHandle, server_id: &str) {
+    if let Ok(mut unhealthy) = app.state::<TrayState>().unhealthy.lock() {
+        unhealthy.insert(server_id.to_string());
+    }
+    refresh_tray(app);
+}
+
+/// Records that a server started, clearing an earlier crash.
+pub fn server_started(app: &AppHandle, server_id: &str) {
+    if let Ok(mut unhealthy) = app.state::<TrayState>().unhealthy.lock() {
+        unhealthy.remove(server_id);
+    }
+    refresh_tray(app);
+}
+
+/// Shows the main window, or hides it if it is visible.
+fn toggle_main_window(app: &AppHandle) -> Result<(), String> {
+    match app.get_webview_window("main") {
+        Some(window) if window.is_visible().unwrap_or(false) => window
+            .hide()
+            .map_err(|e| format!("Failed to hide window: {e}")),
+        _ => crate::daemon::show_main_window(app),
+    }
+}
+
+/// Starts or stops a server from the tray. The frontend owns the MCP
+/// sessions, so it is asked to do it; without a window, a server is
+/// stopped here and starting one opens the window instead.
+fn toggle_server(app: &AppHandle, server_id: String) {
+    let start = !crate::mcp::running_server_ids(app).contains(&server_id);
+    log::info!(
+        "{} MCP server {server_id} from the tray",
+        if start { "Starting" } else { "Stopping" }
+    );
+    if app.get_webview_window("main").is_some() {
+        let event = TrayServerAction { server_id, start };
+        if let Err(e) = app.emit("tray-server-action", event) {
+            log::error!("Failed to emit tray-server-action event: {e}");
+        }
+        // The menu checks the item itself; undo it until the server has
+        // actually started or stopped
+        refresh_tray(app);
+        return;
+    }
+    if start {
+        if let Err(e) = crate::daemon::show_main_window(app) {
+            log::error!("Failed to show window from tray: {e}");
+        }
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = crate::mcp::kill_mcp_server(
+            app.clone(),
+            app.state::<crate::mcp::McpProcesses>(),
+            app.state::<crate::audit::McpAuditLog>(),
+            server_id,
+        )
+        .await;
+        if let Err(e) = result {
+            log::error!("Failed to stop MCP server from tray: {e}");
+        }
+    });
+}
+
+/// Creates the tray icon: new chat, showing or hiding the window, starting
+/// and stopping each MCP server, and quitting. Needed above all in daemon
+/// mode, where there may be no window at all.
+pub fn create_tray(app: &AppHandle) -> Result<(), String> {
+    let menu =
+        build_tray_menu(app, &[], 0).map_err(|e| format!("Failed to build tray menu: {e}"))?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Nexus")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "tray-new-chat" => {
+                let action = crate::quick_actions::QuickAction::NewChat;
+                if let Err(e) = crate::quick_actions::perform(app, action) {
+                    log::error!("Failed to start a new chat from tray: {e}");
+                }
+            }
+            "tray-toggle-window" => {
+                if let Err(e) = toggle_main_window(app) {
+                    log::error!("{e}");
+                }
+            }
+            "tray-toggle-pinned" => crate::window_state::toggle_pinned(app),
+            "tray-toggle-compact" => crate::window_state::toggle_compact(app),
+            "tray-quit" => app.exit(0),
+            id => {
+                if let Some(server_id) = id.strip_prefix(TRAY_SERVER_PREFIX) {
+                    toggle_server(app, server_id.to_string());
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app).map_err(|e| {
+        log::error!("Failed to create tray icon: {e}");
+        format!("Failed to create tray icon: {e}")
+    })?;
+    refresh_tray(app);
+    Ok(())
+}
+
+/// Replaces the servers the tray lists; called by the frontend whenever
+/// its configured servers change.
+#[tauri::command]
+pub async fn set_tray_servers(app: AppHandle, servers: Vec<TrayServer>) -> Result<(), String> {
+    if servers.len() > MAX_TRAY_SERVERS {
+        return Err(format!("The tray lists at most {MAX_TRAY_SERVERS} servers"));
+    }
+    for server in &servers {
+        crate::validate_string_input(&server.id, 100, "Server id")?;
+        crate::validate_string_input(&server.name, 100, "Server name")?;
+    }
+    *app.state::<TrayState>()
+        .servers
+        .lock()
+        .map_err(|e| e.to_string())? = servers;
+    refresh_tray(&app);
+    Ok(())
+}
+
+/// Whether closing the main window should hide it to the tray instead.
+pub fn closes_to_tray(app: &AppHandle) -> bool {
+    crate::read_preferences(app).is_ok_and(|p| p.close_to_tray)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationAction {
+    pub id: String,
+    pub label: String,
+}
+
+/// Payload of the `notification-action` event.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationActionEvent {
+    /// The id the notification was sent with
+    pub notification: String,
+    pub action: String,
+}
+
+/// Shows a notification with action buttons. Clicking one emits
+/// `notification-action`. Where the platform has no actions (everywhere
+/// but Linux for now) the notification is shown without them.
+#[tauri::command]
+pub async fn send_actionable_notification(
+    app: AppHandle,
+    id: String,
+    title: String,
+    body: Option<String>,
+    actions: Vec<NotificationAction>,
+) -> Result<(), String> {
+    crate::validate_string_input(&id, 100, "Notification id")?;
+    crate::validate_string_input(&title, 200, "Notification title")?;
+    if let Some(body) = &body {
+        crate::validate_string_input(body, 2000, "Notification body")?;
+    }
+    if actions.len() > MAX_NOTIFICATION_ACTIONS {
+        return Err(format!(
+            "A notification can have at most {MAX_NOTIFICATION_ACTIONS} actions"
+        ));
+    }
+    for action in &actions {
+        crate::validate_string_input(&action.id, 100, "Action id")?;
+        crate::validate_string_input(&action.label, 50, "Action label")?;
+    }
+    log::info!("Sending notification {id} with {} actions", actions.len());
+
+    #[cfg(target_os = "linux")]
+    {
+        let actions: Vec<(String, String)> = actions.into_iter().map(|a| (a.id, a.label)).collect();
+        linux::notify_with_actions(&title, body.as_deref(), &actions, move |action| {
+            log::info!("Notification {id} action: {action}");
+            let event = NotificationActionEvent {
+                notification: id,
+                action,
+            };
+            if let Err(e) = app.emit("notification-action", event) {
+                log::error!("Failed to emit notification-action event: {e}");
+            }
+        })
+        .inspect_err(|e| log::error!("{e}"))?;
+        crate::indicators::notification_shown(&app);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        use tauri_plugin_notification::NotificationExt;
+
+        let _ = actions;
+        let mut notification = app.notification().builder().title(title);
+        if let Some(body) = body {
+            notification = notification.body(body);
+        }
+        notification.show().map_err(|e| {
+            log::error!("Failed to send notification {id}: {e}");
+            format!("Failed to send notification: {e}")
+        })?;
+        crate::indicators::notification_shown(&app);
+        Ok(())
+    }
+}
+
+/// Checks that an accelerator such as `CmdOrCtrl+Shift+Space` parses.
+pub fn validate_shortcut(shortcut: &str) -> Result<(), String> {
+    crate::validate_string_input(shortcut, MAX_SHORTCUT_LENGTH, "Global shortcut")?;
+    shortcut
+        .parse::<tauri_plugin_global_shortcut::Shortcut>()
+        .map(|_| ())
+        .map_err(|e| format!("Invalid global shortcut {shortcut}: {e}"))
+}
+
+/// Shows the main window when the shortcut is pressed, unless the
+/// preference was cleared since it was bound.
+fn on_shortcut(app: &AppHandle) {
+    let enabled = crate::read_preferences(app).is_ok_and(|p| p.global_shortcut.is_some());
+    if !enabled {
+        return;
+    }
+    if let Err(e) = crate::daemon::show_main_window(app) {
+        log::error!("Failed to show window from global shortcut: {e}");
+    }
+}
+
+/// Registers the global shortcut that shows the window, replacing the
+/// previous one; `None` removes it. Called at startup and whenever
+/// preferences are saved.
+pub fn apply_global_shortcut(app: &AppHandle, shortcut: Option<&str>) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    if linux::is_wayland() {
+        let Some(shortcut) = shortcut else {
+            return Ok(());
+        };
+        if !linux::claim_portal_binding() {
+            log::info!("Global shortcut changes apply at the next launch under Wayland");
+            return Ok(());
+        }
+        let app = app.clone();
+        let accelerator = shortcut.to_string();
+        tauri::async_runtime::spawn(async move {
+            let result =
+                linux::run_portal_shortcut("show-window", "Show Nexus", &accelerator, || {
+                    on_shortcut(&app)
+                })
+                .await;
+            if let Err(e) = result {
+                log::error!("{e}");
+            }
+        });
+        return Ok(());
+    }
+
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| format!("Failed to clear global shortcut: {e}"))?;
+    let Some(shortcut) = shortcut else {
+        return Ok(());
+    };
+    shortcuts
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                on_shortcut(app);
+            }
+        })
+        .map_err(|e| {
+            log::error!("Failed to register global shortcut {shortcut}: {e}");
+            format!("Failed to register global shortcut {shortcut}: {e}")
+        })?;
+    log::info!("Registered global shortcut {shortcut}");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_launch_at_login(app: AppHandle) -> Result<bool, String> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("Failed to read launch at login: {e}"))
+}
+
+/// Starts Nexus in daemon mode when the user logs in, or stops doing so.
+#[tauri::command]
+pub async fn set_launch_at_login(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| {
+        log::error!("Failed to update launch at login: {e}");
+        format!("Failed to update launch at login: {e}")
+    })?;
+    log::info!(
+        "Launch at login {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
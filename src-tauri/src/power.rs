@@ -0,0 +1,576 @@
+// Power Awareness
+// ===============
+//
+// Whether the machine runs on battery, whether the user is idle and when
+// the system is about to sleep, so heavy background work can wait for a
+// better moment. A thread polls the battery and idle time every
+// `POLL_INTERVAL`. Sleep and wake are announced by the system itself: a
+// suspend/resume notification on Windows, NSWorkspace on macOS and
+// logind's `PrepareForSleep` on Linux, where a delay lock holds off the
+// suspend until the app has been told.
+//
+// Background work is paused on battery, unless `pause_on_battery` is
+// switched off, and from the moment the system announces sleep until it
+// wakes. Meanwhile the scheduler skips its heavy jobs, and model downloads
+// disconnect and pick up where they stopped once work can go on. Every
+// change is announced with `power-state-changed`, carrying the new
+// `PowerState`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::operations::Operation;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How often paused work checks whether it may go on
+const RESUME_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Seconds without keyboard or mouse input after which the user is idle
+const IDLE_AFTER_SECS: u64 = 5 * 60;
+
+/// Error a task returns from work it broke off because background work was
+/// paused, for its caller to `wait_while_paused` and start it again.
+pub const PAUSED: &str = "Paused for power";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct PowerSettings {
+    /// Heavy background work waits while the machine runs on battery
+    pub pause_on_battery: bool,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self {
+            pause_on_battery: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseReason {
+    OnBattery,
+    Sleep,
+}
+
+impl PauseReason {
+    fn message(self) -> &'static str {
+        match self {
+            Self::OnBattery => "Paused while on battery",
+            Self::Sleep => "Paused while the system sleeps",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerState {
+    /// `false` on AC power and on machines without a battery
+    pub on_battery: bool,
+    /// `None` without a battery or when the system doesn't say
+    pub battery_percent: Option<u8>,
+    /// No input for a while; `None` where the system doesn't say
+    pub idle: Option<bool>,
+    /// Between the system announcing sleep and waking up
+    pub sleeping: bool,
+    /// Why heavy background work waits, if it does
+    pub paused: Option<PauseReason>,
+}
+
+#[derive(Default)]
+struct Monitor {
+    state: PowerState,
+    settings: PowerSettings,
+}
+
+/// The power state, managed as app state.
+#[derive(Default)]
+pub struct Power(Mutex<Monitor>);
+
+/// What the system reports about its battery.
+#[derive(Default)]
+struct Battery {
+    on_battery: bool,
+    percent: Option<u8>,
+}
+
+/// Applies `change`, works out whether background work is paused and
+/// announces the state if anything changed.
+fn update(app: &AppHandle, change: impl FnOnce(&mut Monitor)) {
+    let power = app.state::<Power>();
+    let changed = {
+        let Ok(mut monitor) = power.0.lock() else {
+            return;
+        };
+        let before = monitor.state.clone();
+        change(&mut monitor);
+        monitor.state.paused = if monitor.state.sleeping {
+            Some(PauseReason::Sleep)
+        } else if monitor.state.on_battery && monitor.settings.pause_on_battery {
+            Some(PauseReason::OnBattery)
+        } else {
+            None
+        };
+        if before.paused != monitor.state.paused {
+            match monitor.state.paused {
+                Some(reason) => log::info!("Background work paused: {reason:?}"),
+                None => log::info!("Background work resumed"),
+            }
+        }
+        (monitor.state != before).then(|| monitor.state.clone())
+    };
+    if let Some(state) = changed {
+        if let Err(e) = app.emit("power-state-changed", &state) {
+            log::warn!("Failed to emit power-state-changed event: {e}");
+        }
+    }
+}
+
+fn poll(app: &AppHandle) {
+    let battery = native::battery();
+    let idle = native::idle_secs().map(|secs| secs >= IDLE_AFTER_SECS);
+    update(app, |monitor| {
+        monitor.state.on_battery = battery.on_battery;
+        monitor.state.battery_percent = battery.percent;
+        monitor.state.idle = idle;
+    });
+}
+
+/// Called by the platform's sleep watcher as the system goes to sleep and
+/// once it woke up.
+fn sleep_changed(app: &AppHandle, sleeping: bool) {
+    if sleeping {
+        log::info!("System going to sleep");
+    } else {
+        log::info!("System woke up");
+    }
+    update(app, |monitor| monitor.state.sleeping = sleeping);
+}
+
+/// Applies saved settings. Called at startup and whenever preferences are
+/// saved.
+pub fn apply_settings(app: &AppHandle, settings: &PowerSettings) {
+    let settings = settings.clone();
+    update(app, |monitor| monitor.settings = settings);
+}
+
+/// Reads the power state once, so the scheduler's first run already knows
+/// it, then keeps it current.
+pub fn start(app: &AppHandle) {
+    match crate::read_preferences(app) {
+        Ok(preferences) => apply_settings(app, &preferences.power),
+        Err(e) => log::warn!("Using default power settings: {e}"),
+    }
+    poll(app);
+    native::watch_sleep(app);
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        poll(&app);
+    });
+}
+
+/// Why heavy background work should wait right now, if it should.
+pub fn paused(app: &AppHandle) -> Option<PauseReason> {
+    app.state::<Power>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|monitor| monitor.state.paused)
+}
+
+/// Returns once background work may go on, showing why it waits as the
+/// operation's message meanwhile. Fails if the operation is cancelled.
+pub async fn wait_while_paused(app: &AppHandle, operation: &mut Operation) -> Result<(), String> {
+    let mut shown = None;
+    while let Some(reason) = paused(app) {
+        operation.check_cancelled()?;
+        if shown != Some(reason) {
+            operation.set_message(Some(reason.message().to_string()));
+            shown = Some(reason);
+        }
+        tokio::time::sleep(RESUME_CHECK_INTERVAL).await;
+    }
+    if shown.is_some() {
+        operation.set_message(None);
+    }
+    operation.check_cancelled()
+}
+
+#[tauri::command]
+pub async fn get_power_state(app: AppHandle) -> Result<PowerState, String> {
+    app.state::<Power>()
+        .0
+        .lock()
+        .map(|monitor| monitor.state.clone())
+        .map_err(|e| format!("Failed to lock power state: {e}"))
+}
+
+#[cfg(windows)]
+mod native {
+    use super::Battery;
+    use std::ffi::c_void;
+    use tauri::AppHandle;
+    use windows::Win32::Foundation::{ERROR_SUCCESS, HANDLE};
+    use windows::Win32::System::Power::{
+        GetSystemPowerStatus, PowerRegisterSuspendResumeNotification,
+        DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, SYSTEM_POWER_STATUS,
+    };
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND,
+    };
+
+    /// `ACLineStatus` while running on battery
+    const AC_OFFLINE: u8 = 0;
+    /// `BatteryFlag` bit of a machine without a battery
+    const NO_BATTERY: u8 = 128;
+    /// `BatteryLifePercent` when unknown
+    const UNKNOWN_PERCENT: u8 = 255;
+
+    pub fn battery() -> Battery {
+        let mut status = SYSTEM_POWER_STATUS::default();
+        if unsafe { GetSystemPowerStatus(&mut status) }.is_err()
+            || status.BatteryFlag & NO_BATTERY != 0
+        {
+            return Battery::default();
+        }
+        Battery {
+            on_battery: status.ACLineStatus == AC_OFFLINE,
+            percent: (status.BatteryLifePercent != UNKNOWN_PERCENT)
+                .then_some(status.BatteryLifePercent),
+        }
+    }
+
+    pub fn idle_secs() -> Option<u64> {
+        let mut info = LASTINPUTINFO {
+            cbSize: size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+            return None;
+        }
+        // Both tick counts wrap around after 49.7 days
+        let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+        Some(u64::from(idle_ms) / 1000)
+    }
+
+    unsafe extern "system" fn on_power_event(
+        context: *const c_void,
+        kind: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        let app = &*context.cast::<AppHandle>();
+        match kind {
+            PBT_APMSUSPEND => super::sleep_changed(app, true),
+            PBT_APMRESUMEAUTOMATIC => super::sleep_changed(app, false),
+            _ => {}
+        }
+        ERROR_SUCCESS.0
+    }
+
+    pub fn watch_sleep(app: &AppHandle) {
+        // The registration lasts as long as the app, and so must these
+        let parameters = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(on_power_event),
+            Context: Box::into_raw(Box::new(app.clone())).cast(),
+        }));
+        let mut registration = std::ptr::null_mut();
+        let result = unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK,
+                HANDLE(std::ptr::from_mut(parameters).cast()),
+                &mut registration,
+            )
+        };
+        if result != ERROR_SUCCESS {
+            log::warn!("Failed to watch for sleep: {result:?}");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod native {
+    use super::Battery;
+    use objc2::runtime::{AnyClass, AnyObject, Imp, Sel};
+    use objc2::{class, msg_send, sel};
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    /// `kCGEventSourceStateHIDSystemState`
+    const HID_SYSTEM_STATE: i32 = 1;
+    /// `kCGAnyInputEventType`
+    const ANY_INPUT_EVENT: u32 = u32::MAX;
+
+    /// The app, for the observer's notifications
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+
+    /// From `pmset -g batt`, whose first line names the power source, e.g.
+    /// `Now drawing from 'Battery Power'`, followed by a line per battery
+    /// with its charge, e.g. `-InternalBattery-0 (id=4653155)	85%; discharging`.
+    pub fn battery() -> Battery {
+        let Ok(output) = std::process::Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+        else {
+            return Battery::default();
+        };
+        let output = String::from_utf8_lossy(&output.stdout);
+        let percent = output.lines().skip(1).find_map(|line| {
+            let (before, _) = line.split_once('%')?;
+            before
+                .rsplit(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        });
+        Battery {
+            on_battery: output
+                .lines()
+                .next()
+                .is_some_and(|line| line.contains("'Battery Power'")),
+            percent,
+        }
+    }
+
+    pub fn idle_secs() -> Option<u64> {
+        let secs =
+            unsafe { CGEventSourceSecondsSinceLastEventType(HID_SYSTEM_STATE, ANY_INPUT_EVENT) };
+        (secs.is_finite() && secs >= 0.0).then_some(secs as u64)
+    }
+
+    extern "C-unwind" fn will_sleep(
+        _this: *mut AnyObject,
+        _cmd: Sel,
+        _notification: *mut AnyObject,
+    ) {
+        if let Some(app) = APP.get() {
+            super::sleep_changed(app, true);
+        }
+    }
+
+    extern "C-unwind" fn did_wake(_this: *mut AnyObject, _cmd: Sel, _notification: *mut AnyObject) {
+        if let Some(app) = APP.get() {
+            super::sleep_changed(app, false);
+        }
+    }
+
+    /// Registers an observer for the workspace's sleep and wake
+    /// notifications. Must run on the main thread.
+    unsafe fn observe() -> Result<(), String> {
+        type NotificationFn = extern "C-unwind" fn(*mut AnyObject, Sel, *mut AnyObject);
+        let superclass = class!(NSObject) as *const AnyClass;
+        let class =
+            objc2::ffi::objc_allocateClassPair(superclass, c"NexusPowerObserver".as_ptr(), 0);
+        if class.is_null() {
+            return Err("Failed to create the sleep observer".to_string());
+        }
+        let methods: [(Sel, NotificationFn); 2] =
+            [(sel!(willSleep:), will_sleep), (sel!(didWake:), did_wake)];
+        for (sel, method) in methods {
+            let imp: Imp = std::mem::transmute(method);
+            objc2::ffi::class_addMethod(class, sel, imp, c"v@:@".as_ptr());
+        }
+        objc2::ffi::objc_registerClassPair(class);
+
+        let class = &*class;
+        let observer: *mut AnyObject = msg_send![class, new];
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let center: *mut AnyObject = msg_send![workspace, notificationCenter];
+        let notifications = [
+            (sel!(willSleep:), c"NSWorkspaceWillSleepNotification"),
+            (sel!(didWake:), c"NSWorkspaceDidWakeNotification"),
+        ];
+        for (sel, name) in notifications {
+            let name: *mut AnyObject =
+                msg_send![class!(NSString), stringWithUTF8String: name.as_ptr()];
+            let _: () = msg_send![
+                center,
+                addObserver: observer,
+                selector: sel,
+                name: name,
+                object: std::ptr::null_mut::<AnyObject>()
+            ];
+        }
+        Ok(())
+    }
+
+    pub fn watch_sleep(app: &AppHandle) {
+        let _ = APP.set(app.clone());
+        let result = app.run_on_main_thread(|| {
+            if let Err(e) = unsafe { observe() } {
+                log::warn!("{e}");
+            }
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to watch for sleep: {e}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use super::Battery;
+    use ashpd::zbus;
+    use futures_util::StreamExt;
+    use std::path::Path;
+    use tauri::AppHandle;
+    use tokio::sync::OnceCell;
+
+    const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+    const LOGIND: &str = "org.freedesktop.login1";
+
+    /// logind's object for the app's session, connected once
+    static SESSION: OnceCell<Option<zbus::Proxy<'static>>> = OnceCell::const_new();
+
+    fn read(supply: &Path, name: &str) -> Option<String> {
+        std::fs::read_to_string(supply.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    }
+
+    /// From the kernel's power supplies: the machine is on battery when
+    /// none of its chargers is online or, where no charger is listed, when
+    /// its battery discharges. Batteries of mice and the like have the
+    /// `Device` scope and are left out.
+    pub fn battery() -> Battery {
+        let Ok(entries) = std::fs::read_dir(POWER_SUPPLY_DIR) else {
+            return Battery::default();
+        };
+        let mut charger_online = None;
+        let mut battery = None;
+        for entry in entries.filter_map(Result::ok) {
+            let supply = entry.path();
+            if read(&supply, "scope").as_deref() == Some("Device") {
+                continue;
+            }
+            match read(&supply, "type").as_deref() {
+                Some("Battery") if battery.is_none() => {
+                    let percent = read(&supply, "capacity").and_then(|c| c.parse().ok());
+                    battery = Some((read(&supply, "status"), percent));
+                }
+                Some("Battery") | None => {}
+                Some(_) => {
+                    let online = read(&supply, "online").as_deref() == Some("1");
+                    charger_online = Some(charger_online.unwrap_or(false) || online);
+                }
+            }
+        }
+        let Some((status, percent)) = battery else {
+            return Battery::default();
+        };
+        Battery {
+            on_battery: match charger_online {
+                Some(online) => !online,
+                None => status.as_deref() == Some("Discharging"),
+            },
+            percent,
+        }
+    }
+
+    async fn session() -> Option<&'static zbus::Proxy<'static>> {
+        SESSION
+            .get_or_init(|| async {
+                let connection = zbus::Connection::system()
+                    .await
+                    .map_err(|e| log::warn!("Failed to reach the system bus: {e}"))
+                    .ok()?;
+                zbus::Proxy::new(
+                    &connection,
+                    LOGIND,
+                    "/org/freedesktop/login1/session/auto",
+                    "org.freedesktop.login1.Session",
+                )
+                .await
+                .ok()
+            })
+            .await
+            .as_ref()
+    }
+
+    /// From the session's idle hint, which desktops set after a while
+    /// without input. `IdleSinceHint` is in microseconds since the epoch.
+    pub fn idle_secs() -> Option<u64> {
+        tauri::async_runtime::block_on(async {
+            let session = session().await?;
+            if !session.get_property::<bool>("IdleHint").await.ok()? {
+                return Some(0);
+            }
+            let since = session.get_property::<u64>("IdleSinceHint").await.ok()?;
+            Some((crate::time::now_ms() * 1000).saturating_sub(since) / 1_000_000)
+        })
+    }
+
+    /// A delay lock, which holds off suspending (up to logind's
+    /// `InhibitDelayMaxSec`) until it is closed.
+    async fn inhibit(manager: &zbus::Proxy<'_>) -> Option<zbus::zvariant::OwnedFd> {
+        manager
+            .call(
+                "Inhibit",
+                &("sleep", "Nexus", "Pausing background work", "delay"),
+            )
+            .await
+            .map_err(|e| log::warn!("Failed to take a sleep delay lock: {e}"))
+            .ok()
+    }
+
+    async fn watch(app: &AppHandle) -> Result<(), zbus::Error> {
+        let connection = zbus::Connection::system().await?;
+        let manager = zbus::Proxy::new(
+            &connection,
+            LOGIND,
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )
+        .await?;
+        let mut signals = manager.receive_signal("PrepareForSleep").await?;
+        let mut lock = inhibit(&manager).await;
+        while let Some(message) = signals.next().await {
+            let Ok(sleeping) = message.body().deserialize::<bool>() else {
+                continue;
+            };
+            super::sleep_changed(app, sleeping);
+            if sleeping {
+                // Background work knows; let the system go
+                lock = None;
+            } else if lock.is_none() {
+                lock = inhibit(&manager).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn watch_sleep(app: &AppHandle) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = watch(&app).await {
+                log::warn!("Not watching for sleep: {e}");
+            }
+        });
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod native {
+    use super::Battery;
+    use tauri::AppHandle;
+
+    pub fn battery() -> Battery {
+        Battery::default()
+    }
+
+    pub fn idle_secs() -> Option<u64> {
+        None
+    }
+
+    pub fn watch_sleep(_app: &AppHandle) {}
+}
@@ -0,0 +1,230 @@
+// Profiles
+// ========
+//
+// Separate histories for people sharing one machine. Every profile has its
+// own data directory, `profiles/<id>/` under the app data directory, and so
+// its own conversations, database and preferences; keychain entries are
+// namespaced by profile too. The default profile keeps the app data
+// directory itself, so an existing install simply becomes the default
+// profile. The registry lives in `profiles.json` at the top of the app data
+// directory.
+//
+// The active profile is read once at startup. Switching records the new
+// profile and restarts the app, so nothing loaded for the previous one (MCP
+// servers, watchers, caches, unlocked state) carries over.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_PROFILES: usize = 20;
+const MAX_NAME_CHARS: usize = 100;
+
+/// Id of the active profile; empty until `init` runs, meaning the default
+static ACTIVE: RwLock<String> = RwLock::new(String::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Milliseconds since the UNIX epoch; 0 for the default profile
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfileRegistry {
+    active: String,
+    /// Profiles other than the default one
+    profiles: Vec<Profile>,
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    /// The default profile first, then the others by creation
+    pub profiles: Vec<Profile>,
+}
+
+fn default_profile() -> Profile {
+    Profile {
+        id: DEFAULT_PROFILE.to_string(),
+        name: "Default".to_string(),
+        created_at: 0,
+    }
+}
+
+pub(crate) fn base_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))
+}
+
+fn get_registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = base_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("profiles.json"))
+}
+
+fn load(app: &AppHandle) -> Result<ProfileRegistry, String> {
+    let path = get_registry_path(app)?;
+    if !path.exists() {
+        return Ok(ProfileRegistry::default());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read profiles: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse profiles: {e}");
+        format!("Failed to parse profiles: {e}")
+    })
+}
+
+fn store(app: &AppHandle, registry: &ProfileRegistry) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(registry)
+        .map_err(|e| format!("Failed to serialize profiles: {e}"))?;
+    crate::export::write_atomic(&get_registry_path(app)?, &json)
+}
+
+/// Id of the active profile.
+pub fn active() -> String {
+    match ACTIVE.read() {
+        Ok(active) if !active.is_empty() => active.clone(),
+        _ => DEFAULT_PROFILE.to_string(),
+    }
+}
+
+/// Reads the active profile. Called once at startup, before anything
+/// touches the data directory.
+pub fn init(app: &AppHandle) {
+    let registry = load(app).unwrap_or_else(|e| {
+        log::error!("Failed to load profiles, using the default profile: {e}");
+        ProfileRegistry::default()
+    });
+    let known = registry.active == DEFAULT_PROFILE
+        || registry.profiles.iter().any(|p| p.id == registry.active);
+    let active = if known {
+        registry.active
+    } else {
+        log::warn!(
+            "Active profile {} does not exist, using the default profile",
+            registry.active
+        );
+        DEFAULT_PROFILE.to_string()
+    };
+
+    log::info!("Using profile {active}");
+    if let Ok(mut current) = ACTIVE.write() {
+        *current = active;
+    }
+}
+
+/// Data directory of the active profile, created if needed.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let active = active();
+    let dir = if active == DEFAULT_PROFILE {
+        base_dir(app)?
+    } else {
+        base_dir(app)?.join("profiles").join(active)
+    };
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(dir)
+}
+
+/// Keychain account name of `account` in the active profile.
+pub fn keychain_account(account: &str) -> String {
+    let active = active();
+    if active == DEFAULT_PROFILE {
+        account.to_string()
+    } else {
+        format!("profile:{active}:{account}")
+    }
+}
+
+/// Every profile, the default one first.
+pub fn list(app: &AppHandle) -> Result<Vec<Profile>, String> {
+    let registry = load(app)?;
+    let mut profiles = vec![default_profile()];
+    profiles.extend(registry.profiles);
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle) -> Result<ProfileList, String> {
+    Ok(ProfileList {
+        active: active(),
+        profiles: list(&app)?,
+    })
+}
+
+/// Adds a profile with an empty history. It becomes active once switched to.
+#[tauri::command]
+pub async fn create_profile(app: AppHandle, name: String) -> Result<Profile, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    crate::validate_string_input(&name, MAX_NAME_CHARS, "Profile name")?;
+
+    let mut registry = load(&app)?;
+    if registry.profiles.len() + 1 >= MAX_PROFILES {
+        return Err(format!("Too many profiles (max {MAX_PROFILES})"));
+    }
+    if registry
+        .profiles
+        .iter()
+        .map(|p| p.name.as_str())
+        .chain(["Default"])
+        .any(|existing| existing.eq_ignore_ascii_case(&name))
+    {
+        return Err(format!("A profile named {name} already exists"));
+    }
+
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate profile id: {e}"))?;
+    let profile = Profile {
+        id: bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        name,
+        created_at: crate::time::now_ms() as i64,
+    };
+    registry.profiles.push(profile.clone());
+    store(&app, &registry)?;
+
+    log::info!("Created profile {}", profile.id);
+    crate::quick_actions::refresh(&app);
+    Ok(profile)
+}
+
+/// Makes `id` the active profile and restarts the app into it. Does not
+/// return when the profile changes.
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let mut registry = load(&app)?;
+    if id != DEFAULT_PROFILE && !registry.profiles.iter().any(|p| p.id == id) {
+        return Err(format!("Profile {id} not found"));
+    }
+    if id == active() {
+        return Ok(());
+    }
+
+    registry.active = id.clone();
+    store(&app, &registry)?;
+    log::info!("Switching to profile {id}, restarting");
+    app.restart()
+}
@@ -0,0 +1,210 @@
+// System Prompt History
+// =====================
+//
+// Keeps every version of the workspace system prompt and of each
+// conversation's system prompt, so a good prompt can be recovered after it
+// has been tweaked away. Prompt texts are stored once, content-addressed by
+// their SHA-256, and each scope ("workspace" or a conversation id) has an
+// append-only index of which text was active when.
+
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use crate::conversations;
+use crate::hash::sha256_hex;
+
+/// Scope of the default prompt applied to new conversations
+pub const WORKSPACE_SCOPE: &str = "workspace";
+
+const MAX_PROMPT_LENGTH: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    /// UNIX timestamp (milliseconds)
+    #[serde(deserialize_with = "crate::time::legacy_ms")]
+    timestamp: u64,
+    hash: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemPromptVersion {
+    pub hash: String,
+    /// UNIX timestamp (milliseconds) the version became active
+    pub timestamp: u64,
+    pub prompt: String,
+    /// Unified diff against the previous version, empty for the first one
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemPromptRestoredEvent {
+    pub scope: String,
+    pub prompt: String,
+}
+
+fn get_history_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    let history_dir = app_data_dir.join("prompt-history");
+
+    // Ensure the history and blob directories exist
+    std::fs::create_dir_all(history_dir.join("blobs"))
+        .map_err(|e| format!("Failed to create prompt history directory: {e}"))?;
+
+    Ok(history_dir)
+}
+
+fn read_index(app: &AppHandle, scope: &str) -> Result<Vec<IndexEntry>, String> {
+    let path = get_history_dir(app)?.join(format!("{scope}.jsonl"));
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file =
+        std::fs::File::open(&path).map_err(|e| format!("Failed to open prompt history: {e}"))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+fn read_blob(app: &AppHandle, hash: &str) -> Result<String, String> {
+    crate::validate_filename(hash)?;
+    std::fs::read_to_string(
+        get_history_dir(app)?
+            .join("blobs")
+            .join(format!("{hash}.txt")),
+    )
+    .map_err(|e| format!("Failed to read prompt version {hash}: {e}"))
+}
+
+/// Records `prompt` as the current version for `scope` unless it already is.
+/// Returns whether a new version was written.
+pub fn record(app: &AppHandle, scope: &str, prompt: &str) -> Result<bool, String> {
+    crate::validate_filename(scope)?;
+    crate::validate_string_input(prompt, MAX_PROMPT_LENGTH, "System prompt")?;
+
+    let index = read_index(app, scope)?;
+    let hash = sha256_hex(prompt.as_bytes());
+    match index.last() {
+        Some(last) if last.hash == hash => return Ok(false),
+        // Nothing worth keeping yet
+        None if prompt.is_empty() => return Ok(false),
+        _ => {}
+    }
+
+    let history_dir = get_history_dir(app)?;
+    let blob_path = history_dir.join("blobs").join(format!("{hash}.txt"));
+    if !blob_path.exists() {
+        crate::export::write_atomic(&blob_path, prompt.as_bytes())?;
+    }
+
+    let entry = IndexEntry {
+        timestamp: crate::time::now_ms(),
+        hash,
+    };
+    let line =
+        serde_json::to_string(&entry).map_err(|e| format!("Failed to serialize version: {e}"))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_dir.join(format!("{scope}.jsonl")))
+        .map_err(|e| format!("Failed to open prompt history: {e}"))?;
+    writeln!(file, "{line}").map_err(|e| {
+        log::error!("Failed to write prompt history: {e}");
+        format!("Failed to write prompt history: {e}")
+    })?;
+
+    log::debug!("Recorded system prompt version for {scope}");
+    Ok(true)
+}
+
+/// Records the workspace system prompt when it changes.
+#[tauri::command]
+pub async fn record_system_prompt(app: AppHandle, prompt: String) -> Result<bool, String> {
+    record(&app, WORKSPACE_SCOPE, &prompt)
+}
+
+/// Every version of a scope's system prompt, newest first.
+#[tauri::command]
+pub async fn get_system_prompt_history(
+    app: AppHandle,
+    scope: String,
+) -> Result<Vec<SystemPromptVersion>, String> {
+    crate::validate_filename(&scope)?;
+
+    let mut previous = String::new();
+    let mut versions = Vec::new();
+    for entry in read_index(&app, &scope)? {
+        let prompt = match read_blob(&app, &entry.hash) {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                log::warn!("Skipping prompt version: {e}");
+                continue;
+            }
+        };
+        let diff = if versions.is_empty() {
+            String::new()
+        } else {
+            TextDiff::from_lines(&previous, &prompt)
+                .unified_diff()
+                .header("previous", "current")
+                .to_string()
+        };
+        versions.push(SystemPromptVersion {
+            hash: entry.hash,
+            timestamp: entry.timestamp,
+            prompt: prompt.clone(),
+            diff,
+        });
+        previous = prompt;
+    }
+
+    versions.reverse();
+    Ok(versions)
+}
+
+/// Makes an earlier version current again. The rollback itself is recorded
+/// as a new version, so it can be undone the same way.
+#[tauri::command]
+pub async fn rollback_system_prompt(
+    app: AppHandle,
+    scope: String,
+    hash: String,
+) -> Result<String, String> {
+    crate::validate_filename(&scope)?;
+    if !read_index(&app, &scope)?.iter().any(|e| e.hash == hash) {
+        return Err(format!("Version {hash} not found in {scope} history"));
+    }
+
+    let prompt = read_blob(&app, &hash)?;
+    log::info!("Rolling back system prompt for {scope} to {hash}");
+
+    if scope != WORKSPACE_SCOPE {
+        if let Some(mut conversation) = conversations::load_all(&app)?
+            .into_iter()
+            .find(|c| c.id == scope)
+        {
+            conversation.system_prompt = prompt.clone();
+            conversations::save(&app, &conversation)?;
+        }
+    }
+    record(&app, &scope, &prompt)?;
+
+    // Let the frontend update its store
+    if let Err(e) = app.emit(
+        "system-prompt-restored",
+        SystemPromptRestoredEvent {
+            scope,
+            prompt: prompt.clone(),
+        },
+    ) {
+        log::error!("Failed to emit system-prompt-restored event: {e}");
+    }
+
+    Ok(prompt)
+}
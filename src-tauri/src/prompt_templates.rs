@@ -0,0 +1,563 @@
+// Prompt Templates
+// ================
+//
+// A library of reusable prompts, such as a system prompt shared by several
+// conversations or a review request with blanks to fill in. A template is
+// a list of messages (system, user or assistant) whose text may use typed
+// variables, and `render_prompt` turns it and a set of values into the
+// final messages. The library lives in `prompt-templates.json` in the app
+// data directory.
+//
+// Templates use a Handlebars-style syntax:
+//
+// - `{{name}}` inserts a variable; lists are joined with ", "
+// - `{{#if name}}...{{else}}...{{/if}}` and `{{#unless name}}...{{/unless}}`
+//   test whether a variable is set, non-empty, non-zero and not false
+// - `{{#each name}}...{{this}}...{{@index}}...{{/each}}` repeats for each
+//   item of a list, with an optional `{{else}}` for an empty one
+// - `{{! comment }}` is left out, and `\{{` is a literal `{{`
+//
+// Text is inserted as is, without HTML escaping. Templates may only use
+// the variables they declare, which is checked when they are saved, and
+// values are checked against the declared types when rendering. Messages
+// that render to nothing but whitespace are left out, so a message can be
+// made optional with `{{#if}}`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::time::now_ms;
+
+const MAX_TEMPLATES: usize = 500;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_DESCRIPTION_CHARS: usize = 1000;
+const MAX_VARIABLES: usize = 50;
+const MAX_MESSAGES: usize = 50;
+const MAX_CONTENT_CHARS: usize = 100_000;
+/// Longest rendered message, so `#each` over a long list can't run away
+const MAX_RENDERED_CHARS: usize = 1_000_000;
+const ROLES: &[&str] = &["system", "user", "assistant"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableType {
+    String,
+    Number,
+    Boolean,
+    /// A list of strings, numbers or booleans
+    List,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    /// Letters, digits and underscores, not starting with a digit
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: VariableType,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Rendering fails without a value for it, unless it has a default
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateMessage {
+    /// `system`, `user` or `assistant`
+    pub role: String,
+    /// The message text, with template syntax
+    pub content: String,
+}
+
+/// A template as the frontend edits it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateSettings {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+    pub messages: Vec<TemplateMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub id: String,
+    #[serde(flatten)]
+    pub settings: PromptTemplateSettings,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    /// The inside of `{{...}}`, trimmed
+    Tag(String),
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Variable(String),
+    If {
+        name: String,
+        /// `#unless`
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        name: String,
+        body: Vec<Node>,
+        /// Rendered for an empty list
+        otherwise: Vec<Node>,
+    },
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            text.push_str(&rest[..start - 1]);
+            text.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        text.push_str(&rest[..start]);
+        let length = rest[start..].find("}}").ok_or("Unclosed {{ in template")?;
+        let tag = rest[start + 2..start + length].trim();
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        if !tag.starts_with('!') {
+            tokens.push(Token::Tag(tag.to_string()));
+        }
+        rest = &rest[start + length + 2..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Parses nodes up to the end of the input or an `{{else}}` or closing
+/// tag, which is returned with them.
+fn parse_block(
+    tokens: &mut impl Iterator<Item = Token>,
+) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) if tag == "else" || tag.starts_with('/') => {
+                return Ok((nodes, Some(tag)));
+            }
+            Token::Tag(tag) => tag,
+        };
+        let Some(block) = tag.strip_prefix('#') else {
+            nodes.push(Node::Variable(tag));
+            continue;
+        };
+        let (helper, name) = block
+            .split_once(char::is_whitespace)
+            .map(|(helper, name)| (helper, name.trim().to_string()))
+            .ok_or_else(|| format!("{{{{#{block}}}}} needs a variable"))?;
+        let (body, end) = parse_block(tokens)?;
+        let (otherwise, end) = match end.as_deref() {
+            Some("else") => parse_block(tokens)?,
+            _ => (Vec::new(), end),
+        };
+        if end.as_deref() != Some(&format!("/{helper}")) {
+            return Err(format!(
+                "{{{{#{helper} {name}}}}} is not closed with {{{{/{helper}}}}}"
+            ));
+        }
+        nodes.push(match helper {
+            "if" | "unless" => Node::If {
+                name,
+                negate: helper == "unless",
+                then: body,
+                otherwise,
+            },
+            "each" => Node::Each {
+                name,
+                body,
+                otherwise,
+            },
+            _ => return Err(format!("Unknown block helper #{helper}")),
+        });
+    }
+    Ok((nodes, None))
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, String> {
+    let mut tokens = tokenize(source)?.into_iter();
+    match parse_block(&mut tokens)? {
+        (nodes, None) => Ok(nodes),
+        (_, Some(tag)) => Err(format!("Unexpected {{{{{tag}}}}}")),
+    }
+}
+
+/// Fails on a variable that is not declared, or on `this` and `@index`
+/// outside of `#each`.
+fn check_names(nodes: &[Node], declared: &[&str], in_each: bool) -> Result<(), String> {
+    let check = |name: &str| match name {
+        "this" | "@index" if in_each => Ok(()),
+        "this" | "@index" => Err(format!("{{{{{name}}}}} can only be used inside #each")),
+        _ if declared.contains(&name) => Ok(()),
+        _ => Err(format!("Variable {name} is not declared")),
+    };
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Variable(name) => check(name)?,
+            Node::If {
+                name,
+                then,
+                otherwise,
+                ..
+            } => {
+                check(name)?;
+                check_names(then, declared, in_each)?;
+                check_names(otherwise, declared, in_each)?;
+            }
+            Node::Each {
+                name,
+                body,
+                otherwise,
+            } => {
+                check(name)?;
+                check_names(body, declared, true)?;
+                check_names(otherwise, declared, in_each)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "this"
+}
+
+/// Whether `value` fits the variable type.
+fn matches_type(value: &Value, kind: VariableType) -> bool {
+    match kind {
+        VariableType::String => value.is_string(),
+        VariableType::Number => value.is_number(),
+        VariableType::Boolean => value.is_boolean(),
+        VariableType::List => value.as_array().is_some_and(|items| {
+            items
+                .iter()
+                .all(|item| item.is_string() || item.is_number() || item.is_boolean())
+        }),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+/// The current `#each` item and its index.
+type Item<'a> = Option<(&'a Value, usize)>;
+
+fn lookup<'a>(name: &str, values: &'a Map<String, Value>, item: Item<'a>) -> Value {
+    match (name, item) {
+        ("this", Some((value, _))) => value.clone(),
+        ("@index", Some((_, index))) => Value::from(index),
+        _ => values.get(name).cloned().unwrap_or(Value::Null),
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    values: &Map<String, Value>,
+    item: Item<'_>,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        if out.len() > MAX_RENDERED_CHARS {
+            return Err(format!(
+                "Rendered message is longer than {MAX_RENDERED_CHARS} characters"
+            ));
+        }
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable(name) => out.push_str(&display(&lookup(name, values, item))),
+            Node::If {
+                name,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(&lookup(name, values, item)) != *negate {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, values, item, out)?;
+            }
+            Node::Each {
+                name,
+                body,
+                otherwise,
+            } => match lookup(name, values, item) {
+                Value::Array(items) if !items.is_empty() => {
+                    for (index, value) in items.iter().enumerate() {
+                        render_nodes(body, values, Some((value, index)), out)?;
+                    }
+                }
+                _ => render_nodes(otherwise, values, item, out)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+fn validate(settings: &PromptTemplateSettings) -> Result<(), String> {
+    if settings.name.trim().is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    crate::validate_string_input(&settings.name, MAX_NAME_CHARS, "Template name")?;
+    if let Some(description) = &settings.description {
+        crate::validate_string_input(description, MAX_DESCRIPTION_CHARS, "Description")?;
+    }
+
+    if settings.variables.len() > MAX_VARIABLES {
+        return Err(format!("At most {MAX_VARIABLES} variables are allowed"));
+    }
+    let mut names: Vec<&str> = Vec::with_capacity(settings.variables.len());
+    for variable in &settings.variables {
+        if !is_valid_name(&variable.name) {
+            return Err(format!(
+                "Invalid variable name {:?}: use letters, digits and underscores",
+                variable.name
+            ));
+        }
+        if names.contains(&variable.name.as_str()) {
+            return Err(format!("Variable {} is declared twice", variable.name));
+        }
+        if let Some(default) = &variable.default {
+            if !matches_type(default, variable.kind) {
+                return Err(format!(
+                    "Default of {} is not a {:?}",
+                    variable.name, variable.kind
+                ));
+            }
+        }
+        names.push(&variable.name);
+    }
+
+    if settings.messages.is_empty() || settings.messages.len() > MAX_MESSAGES {
+        return Err(format!(
+            "A template needs between 1 and {MAX_MESSAGES} messages"
+        ));
+    }
+    for (index, message) in settings.messages.iter().enumerate() {
+        if !ROLES.contains(&message.role.as_str()) {
+            return Err(format!("Invalid role: {}", message.role));
+        }
+        crate::validate_string_input(&message.content, MAX_CONTENT_CHARS, "Message")?;
+        parse(&message.content)
+            .and_then(|nodes| check_names(&nodes, &names, false))
+            .map_err(|e| format!("Message {}: {e}", index + 1))?;
+    }
+    Ok(())
+}
+
+/// The values to render with: those given, or else the defaults, checked
+/// against the declared variables.
+fn resolve_values(
+    variables: &[TemplateVariable],
+    mut given: Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    if let Some(name) = given
+        .keys()
+        .find(|name| !variables.iter().any(|v| &v.name == *name))
+    {
+        return Err(format!("The template has no variable {name}"));
+    }
+    let mut values = Map::new();
+    for variable in variables {
+        let value = match given.remove(&variable.name).filter(|v| !v.is_null()) {
+            Some(value) => value,
+            None => match &variable.default {
+                Some(default) => default.clone(),
+                None if variable.required => {
+                    return Err(format!("Missing value for {}", variable.name))
+                }
+                None => continue,
+            },
+        };
+        if !matches_type(&value, variable.kind) {
+            return Err(format!(
+                "Value of {} is not a {:?}",
+                variable.name, variable.kind
+            ));
+        }
+        values.insert(variable.name.clone(), value);
+    }
+    Ok(values)
+}
+
+fn get_templates_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(crate::profiles::data_dir(app)?.join("prompt-templates.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let path = get_templates_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read prompt templates: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse prompt templates: {e}");
+        format!("Failed to parse prompt templates: {e}")
+    })
+}
+
+fn store(app: &AppHandle, templates: &[PromptTemplate]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(templates)
+        .map_err(|e| format!("Failed to serialize prompt templates: {e}"))?;
+    crate::export::write_atomic(&get_templates_path(app)?, &json)
+}
+
+/// Every template, sorted by name.
+#[tauri::command]
+pub async fn list_prompt_templates(app: AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let mut templates = load(&app)?;
+    templates.sort_by_key(|t| t.settings.name.to_lowercase());
+    Ok(templates)
+}
+
+#[tauri::command]
+pub async fn create_prompt_template(
+    app: AppHandle,
+    settings: PromptTemplateSettings,
+) -> Result<PromptTemplate, String> {
+    validate(&settings)?;
+    let mut templates = load(&app)?;
+    if templates.len() >= MAX_TEMPLATES {
+        return Err(format!("Too many prompt templates (max {MAX_TEMPLATES})"));
+    }
+
+    let now = now_ms();
+    let template = PromptTemplate {
+        id: crate::storage::new_id()?,
+        settings,
+        created_at: now,
+        updated_at: now,
+    };
+    templates.push(template.clone());
+    store(&app, &templates)?;
+    log::info!(
+        "Created prompt template {} ({})",
+        template.id,
+        template.settings.name
+    );
+    Ok(template)
+}
+
+/// Replaces a template's name, variables and messages.
+#[tauri::command]
+pub async fn update_prompt_template(
+    app: AppHandle,
+    id: String,
+    settings: PromptTemplateSettings,
+) -> Result<PromptTemplate, String> {
+    validate(&settings)?;
+    let mut templates = load(&app)?;
+    let template = templates
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Prompt template {id} not found"))?;
+    template.settings = settings;
+    template.updated_at = now_ms();
+    let template = template.clone();
+
+    store(&app, &templates)?;
+    log::info!("Updated prompt template {id}");
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn delete_prompt_template(app: AppHandle, id: String) -> Result<(), String> {
+    let mut templates = load(&app)?;
+    let count = templates.len();
+    templates.retain(|t| t.id != id);
+    if templates.len() == count {
+        return Err(format!("Prompt template {id} not found"));
+    }
+    store(&app, &templates)?;
+    log::info!("Deleted prompt template {id}");
+    Ok(())
+}
+
+/// Renders a template with `vars`, a map from variable names to values,
+/// into the messages to send. Variables left out take their default.
+#[tauri::command]
+pub async fn render_prompt(
+    app: AppHandle,
+    template_id: String,
+    vars: Option<Map<String, Value>>,
+) -> Result<Vec<RenderedMessage>, String> {
+    let template = load(&app)?
+        .into_iter()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| format!("Prompt template {template_id} not found"))?;
+    let settings = template.settings;
+    let values = resolve_values(&settings.variables, vars.unwrap_or_default())?;
+
+    let mut messages = Vec::with_capacity(settings.messages.len());
+    for message in settings.messages {
+        let mut content = String::new();
+        render_nodes(&parse(&message.content)?, &values, None, &mut content)?;
+        if !content.trim().is_empty() {
+            messages.push(RenderedMessage {
+                role: message.role,
+                content,
+            });
+        }
+    }
+    Ok(messages)
+}
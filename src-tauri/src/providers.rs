@@ -0,0 +1,350 @@
+// Model Providers
+// ===============
+//
+// A registry of model APIs beyond the built-in ones, for self-hosted and
+// aggregating OpenAI-compatible endpoints (vLLM, LM Studio, OpenRouter,
+// ...) and Anthropic-style APIs. Each entry names the API's shape, its base
+// URL, how it authenticates, the model to use by default and any extra
+// headers. The registry lives in `providers.json` in the app data
+// directory; API keys never touch that file, they are kept in the keychain
+// under `provider:<id>`.
+//
+// A registered provider is used by passing its id to
+// `stream_chat_completion` instead of a full configuration, so its key
+// stays in the backend.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::chat_proxy::{self, ProviderApi, ProviderConfig};
+use crate::keychain;
+use crate::time::now_ms;
+
+const MAX_PROVIDERS: usize = 100;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_MODEL_CHARS: usize = 200;
+/// The proxy allows 20, one of which may be the key
+const MAX_HEADERS: usize = 19;
+const TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProviderAuth {
+    /// No credentials, e.g. a local LM Studio server
+    None,
+    /// `Authorization: Bearer <key>`
+    #[default]
+    Bearer,
+    /// The key as the value of a header, e.g. `x-api-key`
+    Header { name: String },
+}
+
+/// A provider as the frontend edits it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSettings {
+    pub name: String,
+    #[serde(default)]
+    pub api: ProviderApi,
+    /// e.g. `http://localhost:1234/v1` or `https://openrouter.ai/api/v1`
+    pub base_url: String,
+    #[serde(default)]
+    pub auth: ProviderAuth,
+    #[serde(default)]
+    pub default_model: Option<String>,
+    /// Extra headers, e.g. `HTTP-Referer` for OpenRouter
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderEntry {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    #[serde(flatten)]
+    pub settings: ProviderSettings,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// A provider as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    #[serde(flatten)]
+    pub provider: ProviderEntry,
+    /// An API key is stored in the keychain
+    pub has_api_key: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTest {
+    pub ok: bool,
+    /// HTTP status of the models request, if the server answered
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    /// Models the endpoint lists, if it lists them
+    pub models: Option<Vec<String>>,
+    pub error: Option<String>,
+}
+
+fn credential_account(id: &str) -> String {
+    format!("provider:{id}")
+}
+
+fn get_providers_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("providers.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<ProviderEntry>, String> {
+    let path = get_providers_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read providers: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse providers: {e}");
+        format!("Failed to parse providers: {e}")
+    })
+}
+
+fn store(app: &AppHandle, providers: &[ProviderEntry]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(providers)
+        .map_err(|e| format!("Failed to serialize providers: {e}"))?;
+    crate::export::write_atomic(&get_providers_path(app)?, &json)
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<ProviderEntry, String> {
+    load(app)?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Provider {id} not found"))
+}
+
+fn validate(settings: &ProviderSettings) -> Result<(), String> {
+    if settings.name.trim().is_empty() {
+        return Err("Provider name is required".to_string());
+    }
+    crate::validate_string_input(&settings.name, MAX_NAME_CHARS, "Provider name")?;
+    chat_proxy::parse_base_url(&settings.base_url)?;
+    if let Some(model) = &settings.default_model {
+        crate::validate_string_input(model, MAX_MODEL_CHARS, "Default model")?;
+    }
+    if let ProviderAuth::Header { name } = &settings.auth {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name: {name}"))?;
+    }
+    if settings.headers.len() > MAX_HEADERS {
+        return Err(format!("At most {MAX_HEADERS} extra headers are allowed"));
+    }
+    for (name, value) in &settings.headers {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name: {name}"))?;
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| format!("Invalid value for header {name}"))?;
+    }
+    Ok(())
+}
+
+fn info(provider: ProviderEntry) -> Result<ProviderInfo, String> {
+    let has_api_key = keychain::get(&credential_account(&provider.id))?.is_some();
+    Ok(ProviderInfo {
+        provider,
+        has_api_key,
+    })
+}
+
+/// Stores a non-empty key, removes the stored one for an empty key, and
+/// keeps it when there is none.
+fn save_api_key(id: &str, api_key: Option<&str>) -> Result<(), String> {
+    match api_key {
+        Some("") => keychain::delete(&credential_account(id)),
+        Some(key) => keychain::set(&credential_account(id), key),
+        None => Ok(()),
+    }
+}
+
+/// The proxy configuration for a registered provider, its key read from
+/// the keychain and sent the way the provider's `auth` says.
+pub fn config(app: &AppHandle, id: &str) -> Result<ProviderConfig, String> {
+    let provider = find(app, id)?;
+    let settings = provider.settings;
+    let mut headers = settings.headers;
+    if let Some(key) = keychain::get(&credential_account(id))? {
+        match settings.auth {
+            ProviderAuth::None => {}
+            ProviderAuth::Bearer => {
+                headers.insert("authorization".to_string(), format!("Bearer {key}"));
+            }
+            ProviderAuth::Header { name } => {
+                headers.insert(name, key);
+            }
+        }
+    }
+    Ok(ProviderConfig {
+        api: settings.api,
+        base_url: settings.base_url,
+        api_key: None,
+        headers,
+    })
+}
+
+/// Deletes the keychain entries of every provider. Used when wiping app
+/// data; failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for provider in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&credential_account(&provider.id)) {
+            log::warn!("Failed to delete API key of provider {}: {e}", provider.id);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_providers(app: AppHandle) -> Result<Vec<ProviderInfo>, String> {
+    load(&app)?.into_iter().map(info).collect()
+}
+
+/// Registers a provider. A non-empty `api_key` is stored in the keychain.
+#[tauri::command]
+pub async fn add_provider(
+    app: AppHandle,
+    settings: ProviderSettings,
+    api_key: Option<String>,
+) -> Result<ProviderInfo, String> {
+    validate(&settings)?;
+    let mut providers = load(&app)?;
+    if providers.len() >= MAX_PROVIDERS {
+        return Err(format!("Too many providers (max {MAX_PROVIDERS})"));
+    }
+
+    let now = now_ms();
+    let provider = ProviderEntry {
+        id: crate::storage::new_id()?,
+        settings,
+        created_at: now,
+        updated_at: now,
+    };
+    save_api_key(&provider.id, api_key.as_deref())?;
+    providers.push(provider.clone());
+    store(&app, &providers)?;
+
+    log::info!(
+        "Added provider {} ({})",
+        provider.id,
+        provider.settings.name
+    );
+    info(provider)
+}
+
+/// Replaces a provider's settings. A non-empty `api_key` replaces the
+/// stored key, an empty one removes it, and leaving it out keeps it.
+#[tauri::command]
+pub async fn update_provider(
+    app: AppHandle,
+    id: String,
+    settings: ProviderSettings,
+    api_key: Option<String>,
+) -> Result<ProviderInfo, String> {
+    validate(&settings)?;
+    let mut providers = load(&app)?;
+    let provider = providers
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Provider {id} not found"))?;
+    provider.settings = settings;
+    provider.updated_at = now_ms();
+    let provider = provider.clone();
+
+    save_api_key(&id, api_key.as_deref())?;
+    store(&app, &providers)?;
+    log::info!("Updated provider {id}");
+    info(provider)
+}
+
+#[tauri::command]
+pub async fn remove_provider(app: AppHandle, id: String) -> Result<(), String> {
+    let mut providers = load(&app)?;
+    providers.retain(|p| p.id != id);
+    store(&app, &providers)?;
+    keychain::delete(&credential_account(&id))?;
+    log::info!("Removed provider {id}");
+    Ok(())
+}
+
+/// Checks that a provider answers with its key by listing its models.
+/// Failures are reported in the result rather than as an error.
+#[tauri::command]
+pub async fn test_provider_connection(
+    app: AppHandle,
+    id: String,
+) -> Result<ConnectionTest, String> {
+    let config = config(&app, &id)?;
+    let url = chat_proxy::api_url(&config.base_url, "models")?;
+    let headers = chat_proxy::build_headers(&config)?;
+
+    let started = Instant::now();
+    let response = crate::llm::http_client()
+        .get(url)
+        .headers(headers)
+        .timeout(TEST_TIMEOUT)
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let test = match response {
+        Err(e) => ConnectionTest {
+            ok: false,
+            status: None,
+            latency_ms,
+            models: None,
+            error: Some(format!("Could not connect: {e}")),
+        },
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            if status.is_success() {
+                let models = serde_json::from_str::<Value>(&body).ok().and_then(|v| {
+                    v["data"].as_array().map(|models| {
+                        models
+                            .iter()
+                            .filter_map(|m| m["id"].as_str().map(String::from))
+                            .collect()
+                    })
+                });
+                ConnectionTest {
+                    ok: true,
+                    status: Some(status.as_u16()),
+                    latency_ms,
+                    models,
+                    error: None,
+                }
+            } else {
+                ConnectionTest {
+                    ok: false,
+                    status: Some(status.as_u16()),
+                    latency_ms,
+                    models: None,
+                    error: Some(crate::llm::error_message(&body)),
+                }
+            }
+        }
+    };
+    log::info!(
+        "Tested provider {id}: {} in {latency_ms}ms",
+        if test.ok { "ok" } else { "failed" }
+    );
+    Ok(test)
+}
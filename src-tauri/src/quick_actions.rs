@@ -0,0 +1,390 @@
+// Dock Menu and Jump List
+// =======================
+//
+// Shortcuts into the app from the macOS dock menu and the Windows taskbar
+// jump list: New Chat, the pinned conversations and switching to another
+// profile. Both are rebuilt from the backend whenever a conversation is
+// pinned, unpinned or deleted and when a profile is created, so they follow
+// the store without the frontend's help.
+//
+// A dock menu item is handled in this process like any menu item. A jump
+// list entry launches the app again with `--quick-action <action>`; that
+// launch hands the action to a running daemon like any other launch, or
+// otherwise starts up and keeps the action pending until the window asks
+// for it with `take_pending_quick_action`. An action for a window that is
+// already open is delivered as a `quick-action` event. Linux has neither,
+// so there this is a no-op.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const ACTION_FLAG: &str = "--quick-action";
+/// Prefix of the ids of dock menu items
+pub const MENU_ID_PREFIX: &str = "quick-action:";
+/// Pinned conversations listed; jump lists show about ten entries
+const MAX_PINNED: u32 = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuickAction {
+    NewChat,
+    OpenConversation { id: String },
+    SwitchProfile { id: String },
+}
+
+impl QuickAction {
+    /// The action as a command line argument or menu id suffix.
+    fn encode(&self) -> String {
+        match self {
+            QuickAction::NewChat => "new-chat".to_string(),
+            QuickAction::OpenConversation { id } => format!("conversation:{id}"),
+            QuickAction::SwitchProfile { id } => format!("profile:{id}"),
+        }
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let valid = |id: &str| crate::validate_filename(id).is_ok().then(|| id.to_string());
+        match value.split_once(':') {
+            None if value == "new-chat" => Some(QuickAction::NewChat),
+            Some(("conversation", id)) => Some(QuickAction::OpenConversation { id: valid(id)? }),
+            Some(("profile", id)) => Some(QuickAction::SwitchProfile { id: valid(id)? }),
+            _ => None,
+        }
+    }
+}
+
+/// An action a launch with `--quick-action` has not delivered yet; managed
+/// as app state.
+#[derive(Default)]
+pub struct PendingQuickAction(Mutex<Option<QuickAction>>);
+
+/// A dock menu item or jump list entry.
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+struct Entry {
+    action: QuickAction,
+    title: String,
+}
+
+struct Entries {
+    pinned: Vec<Entry>,
+    profiles: Vec<Entry>,
+}
+
+/// The action the app was launched with, if any.
+pub fn from_args() -> Option<QuickAction> {
+    let mut args = std::env::args().skip_while(|arg| arg != ACTION_FLAG);
+    args.next()?;
+    let value = args.next()?;
+    let action = QuickAction::decode(&value);
+    if action.is_none() {
+        log::warn!("Ignoring unknown quick action {value}");
+    }
+    action
+}
+
+/// The action of a dock menu item, given its id.
+pub fn from_menu_id(id: &str) -> Option<QuickAction> {
+    QuickAction::decode(id.strip_prefix(MENU_ID_PREFIX)?)
+}
+
+/// Carries out an action: switching profiles restarts the app, anything
+/// else shows the window and hands the action to it.
+pub fn perform(app: &AppHandle, action: QuickAction) -> Result<(), String> {
+    log::info!("Quick action: {}", action.encode());
+    if let QuickAction::SwitchProfile { id } = action {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::profiles::switch_profile(app, id).await {
+                log::error!("Failed to switch profile from quick action: {e}");
+            }
+        });
+        return Ok(());
+    }
+
+    let window_open = app.get_webview_window("main").is_some();
+    crate::daemon::show_main_window(app)?;
+    if window_open {
+        app.emit("quick-action", &action)
+            .map_err(|e| format!("Failed to emit quick-action event: {e}"))
+    } else {
+        // The new window picks it up once it has loaded
+        let pending = app.state::<PendingQuickAction>();
+        *pending.0.lock().map_err(|e| e.to_string())? = Some(action);
+        Ok(())
+    }
+}
+
+/// Called at startup: keeps the action this launch was started with for
+/// the window and builds the dock menu or jump list.
+pub fn start(app: &AppHandle) {
+    if let Some(action) = from_args() {
+        if let Ok(mut pending) = app.state::<PendingQuickAction>().0.lock() {
+            *pending = Some(action);
+        }
+    }
+    refresh(app);
+}
+
+fn entries(app: &AppHandle) -> Result<Entries, String> {
+    let pinned = crate::storage::pinned(app, MAX_PINNED)?
+        .into_iter()
+        .map(|(id, title)| Entry {
+            action: QuickAction::OpenConversation { id },
+            title,
+        })
+        .collect();
+    let active = crate::profiles::active();
+    let profiles = crate::profiles::list(app)?
+        .into_iter()
+        .filter(|profile| profile.id != active)
+        .map(|profile| Entry {
+            action: QuickAction::SwitchProfile { id: profile.id },
+            title: format!("Switch to {}", profile.name),
+        })
+        .collect();
+    Ok(Entries { pinned, profiles })
+}
+
+/// Rebuilds the dock menu or jump list in the background.
+pub fn refresh(app: &AppHandle) {
+    let entries = match entries(app) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Failed to read quick actions: {e}");
+            return;
+        }
+    };
+
+    #[cfg(target_os = "macos")]
+    if let Err(e) = app.run_on_main_thread(move || {
+        if let Err(e) = dock::set_dock_menu(&entries) {
+            log::error!("Failed to update dock menu: {e}");
+        }
+    }) {
+        log::error!("Failed to update dock menu: {e}");
+    }
+
+    #[cfg(windows)]
+    std::thread::spawn(move || {
+        if let Err(e) = jump_list::set_jump_list(&entries) {
+            log::error!("Failed to update jump list: {e}");
+        }
+    });
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    log::debug!(
+        "No dock menu or jump list on this platform, {} entries unused",
+        entries.pinned.len() + entries.profiles.len()
+    );
+}
+
+#[cfg(target_os = "macos")]
+mod dock {
+    use super::{Entries, Entry, MENU_ID_PREFIX};
+    use muda::{ContextMenu, Menu, MenuItem, PredefinedMenuItem};
+    use objc2::runtime::{AnyClass, AnyObject, Imp, Sel};
+    use objc2::{class, msg_send, sel};
+    use std::cell::RefCell;
+    use std::sync::Once;
+
+    thread_local! {
+        /// The menu `applicationDockMenu:` returns; only touched on the
+        /// main thread
+        static DOCK_MENU: RefCell<Option<Menu>> = const { RefCell::new(None) };
+    }
+    static INSTALL: Once = Once::new();
+
+    extern "C-unwind" fn dock_menu(
+        _this: *mut AnyObject,
+        _cmd: Sel,
+        _sender: *mut AnyObject,
+    ) -> *mut AnyObject {
+        DOCK_MENU.with(|menu| {
+            menu.borrow()
+                .as_ref()
+                .map_or(std::ptr::null_mut(), |menu| menu.ns_menu().cast())
+        })
+    }
+
+    /// Adds `applicationDockMenu:` to the application delegate, which tao
+    /// owns and does not implement it.
+    fn install() -> Result<(), String> {
+        let mut result = Ok(());
+        INSTALL.call_once(|| unsafe {
+            let ns_app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+            let delegate: *mut AnyObject = msg_send![ns_app, delegate];
+            if delegate.is_null() {
+                result = Err("The application has no delegate".to_string());
+                return;
+            }
+            let class = (*delegate).class() as *const AnyClass as *mut AnyClass;
+            let imp: Imp = std::mem::transmute(
+                dock_menu
+                    as extern "C-unwind" fn(*mut AnyObject, Sel, *mut AnyObject) -> *mut AnyObject,
+            );
+            objc2::ffi::class_addMethod(class, sel!(applicationDockMenu:), imp, c"@@:@".as_ptr());
+        });
+        result
+    }
+
+    fn append(menu: &Menu, entry: &Entry) -> Result<(), String> {
+        let id = format!("{MENU_ID_PREFIX}{}", entry.action.encode());
+        menu.append(&MenuItem::with_id(id, &entry.title, true, None))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Must run on the main thread.
+    pub fn set_dock_menu(entries: &Entries) -> Result<(), String> {
+        install()?;
+        let menu = Menu::new();
+        append(
+            &menu,
+            &Entry {
+                action: super::QuickAction::NewChat,
+                title: "New Chat".to_string(),
+            },
+        )?;
+        for group in [&entries.pinned, &entries.profiles] {
+            if group.is_empty() {
+                continue;
+            }
+            menu.append(&PredefinedMenuItem::separator())
+                .map_err(|e| e.to_string())?;
+            for entry in group {
+                append(&menu, entry)?;
+            }
+        }
+        DOCK_MENU.with(|current| *current.borrow_mut() = Some(menu));
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod jump_list {
+    use super::{Entries, Entry, QuickAction, ACTION_FLAG};
+    use std::mem::ManuallyDrop;
+    use windows::core::{Interface, HSTRING, PWSTR};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::{
+        PROPVARIANT, PROPVARIANT_0, PROPVARIANT_0_0, PROPVARIANT_0_0_0,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+        COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    fn arguments(action: &QuickAction) -> String {
+        format!("{ACTION_FLAG} {}", action.encode())
+    }
+
+    /// A shell link that launches the app with the entry's action.
+    unsafe fn link(exe: &HSTRING, entry: &Entry) -> windows::core::Result<IShellLinkW> {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(exe)?;
+        link.SetArguments(&HSTRING::from(arguments(&entry.action)))?;
+        link.SetIconLocation(exe, 0)?;
+
+        // The title is what the jump list shows; the property store copies
+        // the string, which must be a VT_LPWSTR
+        let mut title: Vec<u16> = entry.title.encode_utf16().chain([0]).collect();
+        let value = PROPVARIANT {
+            Anonymous: PROPVARIANT_0 {
+                Anonymous: ManuallyDrop::new(PROPVARIANT_0_0 {
+                    vt: VT_LPWSTR,
+                    wReserved1: 0,
+                    wReserved2: 0,
+                    wReserved3: 0,
+                    Anonymous: PROPVARIANT_0_0_0 {
+                        pwszVal: PWSTR(title.as_mut_ptr()),
+                    },
+                }),
+            },
+        };
+        let store: IPropertyStore = link.cast()?;
+        store.SetValue(&PKEY_Title, &value)?;
+        store.Commit()?;
+        Ok(link)
+    }
+
+    /// Arguments of the entries the user removed from the jump list, which
+    /// may not be added back.
+    unsafe fn removed_arguments(removed: &IObjectArray) -> Vec<String> {
+        let count = removed.GetCount().unwrap_or(0);
+        (0..count)
+            .filter_map(|i| {
+                let link: IShellLinkW = removed.GetAt(i).ok()?;
+                let mut buffer = [0u16; 1024];
+                link.GetArguments(&mut buffer).ok()?;
+                let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+                Some(String::from_utf16_lossy(&buffer[..len]))
+            })
+            .collect()
+    }
+
+    unsafe fn collection(
+        exe: &HSTRING,
+        entries: &[Entry],
+        removed: &[String],
+    ) -> windows::core::Result<IObjectArray> {
+        let collection: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for entry in entries {
+            if removed.contains(&arguments(&entry.action)) {
+                continue;
+            }
+            collection.AddObject(&link(exe, entry)?)?;
+        }
+        collection.cast()
+    }
+
+    unsafe fn build(entries: &Entries) -> windows::core::Result<()> {
+        let exe = HSTRING::from(std::env::current_exe().unwrap_or_default().as_path());
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        let mut slots = 0u32;
+        let removed: IObjectArray = list.BeginList(&mut slots)?;
+        let removed = removed_arguments(&removed);
+
+        for (category, group) in [("Pinned", &entries.pinned), ("Profiles", &entries.profiles)] {
+            let items = collection(&exe, group, &removed)?;
+            if items.GetCount()? > 0 {
+                list.AppendCategory(&HSTRING::from(category), &items)?;
+            }
+        }
+        let new_chat = Entry {
+            action: QuickAction::NewChat,
+            title: "New Chat".to_string(),
+        };
+        list.AddUserTasks(&collection(&exe, &[new_chat], &[])?)?;
+        list.CommitList()
+    }
+
+    /// Replaces the jump list. Runs COM on the calling thread, which must
+    /// not be one the app uses for anything else.
+    pub fn set_jump_list(entries: &Entries) -> Result<(), String> {
+        unsafe {
+            CoInitializeEx(None, COINIT_APARTMENTTHREADED)
+                .ok()
+                .map_err(|e| e.to_string())?;
+            let result = build(entries).map_err(|e| e.to_string());
+            CoUninitialize();
+            result
+        }
+    }
+}
+
+/// The action a launch with `--quick-action` left for the window, if any.
+/// Returns it only once.
+#[tauri::command]
+pub async fn take_pending_quick_action(app: AppHandle) -> Result<Option<QuickAction>, String> {
+    let pending = app.state::<PendingQuickAction>();
+    let action = pending.0.lock().map_err(|e| e.to_string())?.take();
+    Ok(action)
+}
@@ -0,0 +1,412 @@
+// Rate Limiting
+// =============
+//
+// Model requests wait their turn here before they are sent, so a burst of
+// parallel requests (tool-augmented chats, batch tasks, several chats at
+// once) is spread out instead of being answered with 429s. Each API host
+// has a token bucket holding up to `burst` requests and refilling at
+// `requests_per_minute`. A request that finds the bucket empty joins the
+// host's queue, and queued requests go out in the order they arrived.
+// Every attempt queues, retries (see `retry`) included.
+//
+// Hosts listed in the `llm_rate_limits` preference get their own limit,
+// other remote hosts the default one; local servers are not limited unless
+// listed. Whenever a request joins or leaves a queue, the host's queue
+// depth is announced with an `llm-queue` event.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+const MAX_REQUESTS_PER_MINUTE: u32 = 100_000;
+const MAX_BURST: u32 = 1000;
+const MAX_HOSTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub requests_per_minute: u32,
+    /// Requests that may go out at once after a quiet spell
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    /// For remote hosts not in `hosts`; `None` leaves them unlimited
+    pub default: Option<RateLimit>,
+    /// By API host, with the port for local servers, e.g.
+    /// `api.anthropic.com` or `localhost:1234`
+    pub hosts: HashMap<String, RateLimit>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            default: Some(RateLimit {
+                requests_per_minute: 50,
+                burst: 5,
+            }),
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled: Instant,
+    /// Tickets of the requests waiting, first in line first
+    queue: VecDeque<u64>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            refilled: now,
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let per_second = f64::from(self.limit.requests_per_minute) / 60.0;
+        self.tokens = (self.tokens
+            + now.saturating_duration_since(self.refilled).as_secs_f64() * per_second)
+            .min(f64::from(self.limit.burst));
+        self.refilled = now;
+    }
+
+    /// Applies a changed preference, keeping the tokens already refilled.
+    fn set_limit(&mut self, limit: RateLimit, now: Instant) {
+        self.refill(now);
+        self.limit = limit;
+        self.tokens = self.tokens.min(f64::from(limit.burst));
+    }
+
+    /// Takes a token for a request, or tells how long until there is one.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self.wait())
+        }
+    }
+
+    /// Time until the bucket holds a whole token.
+    fn wait(&self) -> Duration {
+        let per_second = f64::from(self.limit.requests_per_minute) / 60.0;
+        Duration::from_secs_f64(((1.0 - self.tokens) / per_second).max(0.0))
+    }
+}
+
+/// The hosts' buckets and queues, managed as app state.
+#[derive(Default)]
+pub struct LlmQueue {
+    buckets: Mutex<HashMap<String, Bucket>>,
+    next_ticket: AtomicU64,
+    /// Signalled whenever a request leaves a queue
+    changed: Notify,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmQueueStatus {
+    pub host: String,
+    /// Requests waiting to be sent
+    pub queued: usize,
+    /// Requests that could be sent right away
+    pub available: u32,
+    pub requests_per_minute: u32,
+    pub burst: u32,
+}
+
+fn status(host: &str, bucket: &Bucket) -> LlmQueueStatus {
+    LlmQueueStatus {
+        host: host.to_string(),
+        queued: bucket.queue.len(),
+        available: bucket.tokens.floor() as u32,
+        requests_per_minute: bucket.limit.requests_per_minute,
+        burst: bucket.limit.burst,
+    }
+}
+
+fn emit(app: &AppHandle, status: Option<LlmQueueStatus>) {
+    if let Some(status) = status {
+        if let Err(e) = app.emit("llm-queue", status) {
+            log::warn!("Failed to emit llm-queue: {e}");
+        }
+    }
+}
+
+pub fn validate(settings: &RateLimitSettings) -> Result<(), String> {
+    if settings.hosts.len() > MAX_HOSTS {
+        return Err(format!("At most {MAX_HOSTS} hosts can have a rate limit"));
+    }
+    for (host, limit) in settings
+        .hosts
+        .iter()
+        .map(|(host, limit)| (host.as_str(), limit))
+        .chain(settings.default.iter().map(|limit| ("default", limit)))
+    {
+        if host.trim().is_empty() {
+            return Err("Rate limit host must not be empty".to_string());
+        }
+        if limit.requests_per_minute == 0 || limit.requests_per_minute > MAX_REQUESTS_PER_MINUTE {
+            return Err(format!(
+                "Rate limit for {host} must be between 1 and {MAX_REQUESTS_PER_MINUTE} requests per minute"
+            ));
+        }
+        if limit.burst == 0 || limit.burst > MAX_BURST {
+            return Err(format!(
+                "Burst for {host} must be between 1 and {MAX_BURST} requests"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The bucket key of `url`: its host, with the port if it has one.
+fn host_key(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+/// The limit for `host`: its own if listed, otherwise the default for
+/// remote hosts and none for local ones.
+fn resolve_limit(settings: &RateLimitSettings, host: &str, local: bool) -> Option<RateLimit> {
+    match settings.hosts.get(host) {
+        Some(limit) => Some(*limit),
+        None if local => None,
+        None => settings.default,
+    }
+}
+
+fn limit_for(app: &AppHandle, url: &Url, host: &str) -> Option<RateLimit> {
+    let settings = crate::read_preferences(app)
+        .map(|p| p.llm_rate_limits)
+        .ok()
+        .filter(|settings| validate(settings).is_ok())
+        .unwrap_or_default();
+    resolve_limit(&settings, host, crate::chat_proxy::is_local(url))
+}
+
+/// Takes the request out of the queue when it is sent or given up, e.g.
+/// because its stream was cancelled while it waited.
+struct Leave<'a> {
+    app: &'a AppHandle,
+    queue: &'a LlmQueue,
+    host: &'a str,
+    ticket: u64,
+}
+
+impl Drop for Leave<'_> {
+    fn drop(&mut self) {
+        let status = self.queue.buckets.lock().ok().and_then(|mut buckets| {
+            let bucket = buckets.get_mut(self.host)?;
+            bucket.queue.retain(|ticket| *ticket != self.ticket);
+            Some(status(self.host, bucket))
+        });
+        self.queue.changed.notify_waiters();
+        emit(self.app, status);
+    }
+}
+
+/// Waits until a request to `url` may be sent without exceeding the
+/// host's rate limit, behind the requests queued before it.
+pub async fn acquire(app: &AppHandle, url: &Url) {
+    let host = host_key(url);
+    let Some(limit) = limit_for(app, url, &host) else {
+        return;
+    };
+    let queue = app.state::<LlmQueue>();
+    let queue = queue.inner();
+    let ticket = queue.next_ticket.fetch_add(1, Ordering::Relaxed);
+    let joined = queue.buckets.lock().ok().map(|mut buckets| {
+        let now = Instant::now();
+        let bucket = buckets
+            .entry(host.clone())
+            .or_insert_with(|| Bucket::new(limit, now));
+        // The preference may have changed since the bucket was made
+        bucket.set_limit(limit, now);
+        bucket.queue.push_back(ticket);
+        status(&host, bucket)
+    });
+    let Some(joined) = joined else {
+        return;
+    };
+    let _leave = Leave {
+        app,
+        queue,
+        host: &host,
+        ticket,
+    };
+    if joined.queued > 1 || joined.available == 0 {
+        log::debug!("Request to {host} queued behind {}", joined.queued - 1);
+    }
+    emit(app, Some(joined));
+
+    loop {
+        // Registered before looking, so a request leaving in between
+        // still wakes this one
+        let changed = queue.changed.notified();
+        tokio::pin!(changed);
+        changed.as_mut().enable();
+
+        let wait = {
+            let Ok(mut buckets) = queue.buckets.lock() else {
+                return;
+            };
+            let Some(bucket) = buckets.get_mut(&host) else {
+                return;
+            };
+            match bucket.queue.front() {
+                Some(first) if *first == ticket => match bucket.take(Instant::now()) {
+                    Ok(()) => return,
+                    Err(wait) => Some(wait),
+                },
+                _ => None,
+            }
+        };
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => changed.await,
+        }
+    }
+}
+
+/// Queue depth and spare capacity of every host with a rate limit that
+/// has had requests, sorted by host.
+#[tauri::command]
+pub async fn get_llm_queue_status(app: AppHandle) -> Result<Vec<LlmQueueStatus>, String> {
+    let queue = app.state::<LlmQueue>();
+    let mut buckets = queue
+        .buckets
+        .lock()
+        .map_err(|e| format!("Failed to read the request queue: {e}"))?;
+    let mut statuses: Vec<LlmQueueStatus> = buckets
+        .iter_mut()
+        .map(|(host, bucket)| {
+            bucket.refill(Instant::now());
+            status(host, bucket)
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.host.cmp(&b.host));
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(requests_per_minute: u32, burst: u32) -> RateLimit {
+        RateLimit {
+            requests_per_minute,
+            burst,
+        }
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_waits() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(limit(60, 3), start);
+        for _ in 0..3 {
+            assert_eq!(bucket.take(start), Ok(()));
+        }
+        // 60 per minute refills one token a second
+        assert_eq!(bucket.take(start), Err(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn bucket_refills_over_time_up_to_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(limit(120, 2), start);
+        bucket.take(start).unwrap();
+        bucket.take(start).unwrap();
+
+        let later = start + Duration::from_millis(250);
+        let wait = bucket.take(later).unwrap_err();
+        assert_eq!(wait.as_millis(), 250);
+        assert_eq!(bucket.take(start + Duration::from_millis(500)), Ok(()));
+
+        bucket.refill(start + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 2.0);
+        assert_eq!(status("h", &bucket).available, 2);
+    }
+
+    #[test]
+    fn bucket_ignores_a_clock_going_backwards() {
+        let start = Instant::now() + Duration::from_secs(10);
+        let mut bucket = Bucket::new(limit(60, 1), start);
+        bucket.take(start).unwrap();
+        bucket.refill(start - Duration::from_secs(5));
+        assert_eq!(bucket.tokens, 0.0);
+    }
+
+    #[test]
+    fn lowering_the_limit_caps_saved_tokens() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(limit(60, 10), start);
+        bucket.set_limit(limit(60, 2), start);
+        assert_eq!(bucket.tokens, 2.0);
+        bucket.take(start).unwrap();
+        bucket.take(start).unwrap();
+        assert!(bucket.take(start).is_err());
+    }
+
+    #[test]
+    fn resolves_limits_per_host() {
+        let mut settings = RateLimitSettings::default();
+        settings
+            .hosts
+            .insert("api.anthropic.com".to_string(), limit(10, 1));
+        settings
+            .hosts
+            .insert("localhost:1234".to_string(), limit(600, 20));
+
+        let own = resolve_limit(&settings, "api.anthropic.com", false);
+        assert_eq!(own, Some(limit(10, 1)));
+        let default = resolve_limit(&settings, "api.openai.com", false);
+        assert_eq!(default, settings.default);
+        let listed_local = resolve_limit(&settings, "localhost:1234", true);
+        assert_eq!(listed_local, Some(limit(600, 20)));
+        assert_eq!(resolve_limit(&settings, "localhost:11434", true), None);
+
+        settings.default = None;
+        assert_eq!(resolve_limit(&settings, "api.openai.com", false), None);
+    }
+
+    #[test]
+    fn host_key_keeps_explicit_ports() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(
+            host_key(&url("https://api.openai.com/v1/chat")),
+            "api.openai.com"
+        );
+        assert_eq!(host_key(&url("http://localhost:1234/v1")), "localhost:1234");
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_limits() {
+        assert!(validate(&RateLimitSettings::default()).is_ok());
+        let with_host = |host: &str, limit: RateLimit| RateLimitSettings {
+            default: None,
+            hosts: HashMap::from([(host.to_string(), limit)]),
+        };
+        assert!(validate(&with_host("api.openai.com", limit(0, 1))).is_err());
+        assert!(validate(&with_host("api.openai.com", limit(60, 0))).is_err());
+        assert!(validate(&with_host("api.openai.com", limit(60, MAX_BURST + 1))).is_err());
+        assert!(validate(&with_host(" ", limit(60, 1))).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+// Provider Record and Replay
+// ==========================
+//
+// Makes backend model requests reproducible. With the `llm_replay`
+// preference set to `record`, every response that reaches the gateway from
+// a provider is saved as a fixture in `llm-fixtures/` in the app data
+// directory, named after a hash of the request (provider, model, prompt,
+// messages and token limit; never the API key). Set to `replay`, the
+// gateway answers from those fixtures instead of calling the provider and
+// fails requests it has no fixture for, so demos, offline development and
+// tests of features built on the gateway give the same output every time.
+//
+// The hash is taken after the request middleware ran, and replayed
+// responses still pass through the response middleware, so a replay sees
+// the pipeline the recording saw.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::llm::LlmStreamOutcome;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMode {
+    /// Requests go to the provider and nothing is recorded
+    #[default]
+    Off,
+    /// Requests go to the provider and responses are recorded
+    Record,
+    /// Requests are answered from recorded responses only
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// The request the response answered, for reading the fixture
+    pub request: Value,
+    pub text: String,
+    pub output_tokens: Option<u64>,
+    /// Milliseconds since the UNIX epoch
+    pub recorded_at: u64,
+}
+
+fn get_fixtures_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let fixtures_dir = crate::profiles::data_dir(app)?.join("llm-fixtures");
+
+    std::fs::create_dir_all(&fixtures_dir)
+        .map_err(|e| format!("Failed to create fixtures directory: {e}"))?;
+
+    Ok(fixtures_dir)
+}
+
+pub fn mode(app: &AppHandle) -> ReplayMode {
+    crate::read_preferences(app)
+        .map(|p| p.llm_replay)
+        .unwrap_or_default()
+}
+
+/// The recorded response to the request hashed to `key`, or `None` if there
+/// is none.
+pub fn load(app: &AppHandle, key: &str) -> Result<Option<Fixture>, String> {
+    let path = get_fixtures_dir(app)?.join(format!("{key}.json"));
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read fixture: {e}"))?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse fixture {key}: {e}"))
+}
+
+/// Saves a provider response as the fixture for the request hashed to
+/// `key`, replacing an earlier recording.
+pub fn record(
+    app: &AppHandle,
+    key: &str,
+    request: Value,
+    outcome: &LlmStreamOutcome,
+) -> Result<(), String> {
+    let fixture = Fixture {
+        request,
+        text: outcome.text.clone(),
+        output_tokens: outcome.output_tokens,
+        recorded_at: crate::time::now_ms(),
+    };
+    let json = serde_json::to_vec_pretty(&fixture)
+        .map_err(|e| format!("Failed to serialize fixture: {e}"))?;
+    crate::export::write_atomic(&get_fixtures_dir(app)?.join(format!("{key}.json")), &json)?;
+    log::debug!("Recorded provider response {key}");
+    Ok(())
+}
+
+/// Number of recorded responses.
+#[tauri::command]
+pub async fn count_llm_fixtures(app: AppHandle) -> Result<usize, String> {
+    let entries = std::fs::read_dir(get_fixtures_dir(&app)?)
+        .map_err(|e| format!("Failed to read fixtures directory: {e}"))?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .count())
+}
+
+/// Deletes every recorded response. Returns how many there were.
+#[tauri::command]
+pub async fn clear_llm_fixtures(app: AppHandle) -> Result<usize, String> {
+    let entries = std::fs::read_dir(get_fixtures_dir(&app)?)
+        .map_err(|e| format!("Failed to read fixtures directory: {e}"))?;
+    let mut removed = 0;
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to remove fixture {path:?}: {e}"),
+        }
+    }
+    log::info!("Cleared {removed} recorded provider responses");
+    Ok(removed)
+}
@@ -0,0 +1,429 @@
+// Response Cache
+// ==============
+//
+// Opt-in cache of chat completion responses, so sending the same request
+// again while iterating on a prompt costs nothing and answers at once.
+// Completed streams proxied through `chat_proxy` are stored in the
+// `llm_cache` table of the local database, keyed by a hash of the provider
+// and the normalized request body: keys in a fixed order, message text
+// without surrounding whitespace, and the fields that don't change the
+// answer (`stream`, `stream_options`, `user`, `metadata`) left out. The
+// model and sampling parameters are part of the body, so changing either
+// misses the cache.
+//
+// A cached response is replayed as one `llm-chunk` followed by `llm-done`
+// with `cached` set. It is not counted against budgets or rate limits.
+// Entries expire after `ttl_secs`; past `max_entries` or `max_mb`, the
+// least recently used are evicted. Only streams that finished are cached,
+// never failed or cancelled ones.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::chat_proxy::ChatResponse;
+use crate::hash::canonical_json_hash;
+use crate::time::now_ms;
+
+const MAX_TTL_SECS: u64 = 90 * 24 * 60 * 60;
+const MAX_ENTRIES_LIMIT: u32 = 100_000;
+const MAX_MB_LIMIT: u32 = 10_000;
+/// Request fields that don't change the response
+const IGNORED_FIELDS: &[&str] = &["stream", "stream_options", "user", "metadata"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ResponseCacheSettings {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    pub max_entries: u32,
+    /// Upper bound on the stored responses, in megabytes
+    pub max_mb: u32,
+}
+
+impl Default for ResponseCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 24 * 60 * 60,
+            max_entries: 1000,
+            max_mb: 50,
+        }
+    }
+}
+
+pub fn validate(settings: &ResponseCacheSettings) -> Result<(), String> {
+    if settings.ttl_secs == 0 || settings.ttl_secs > MAX_TTL_SECS {
+        return Err(format!(
+            "Cache lifetime must be between 1 second and {} days",
+            MAX_TTL_SECS / 86_400
+        ));
+    }
+    if settings.max_entries == 0 || settings.max_entries > MAX_ENTRIES_LIMIT {
+        return Err(format!(
+            "Cache size must be between 1 and {MAX_ENTRIES_LIMIT} responses"
+        ));
+    }
+    if settings.max_mb == 0 || settings.max_mb > MAX_MB_LIMIT {
+        return Err(format!(
+            "Cache size must be between 1 and {MAX_MB_LIMIT} MB"
+        ));
+    }
+    Ok(())
+}
+
+/// The cache settings, or `None` while the cache is off.
+pub fn settings(app: &AppHandle) -> Option<ResponseCacheSettings> {
+    crate::read_preferences(app)
+        .map(|p| p.llm_cache)
+        .ok()
+        .filter(|settings| settings.enabled && validate(settings).is_ok())
+}
+
+/// Trims the text of messages, in either the string or the content part
+/// form.
+fn normalize_messages(messages: &mut Value) {
+    let Some(messages) = messages.as_array_mut() else {
+        return;
+    };
+    for message in messages {
+        match &mut message["content"] {
+            Value::String(text) => *text = text.trim().to_string(),
+            Value::Array(parts) => {
+                for part in parts {
+                    if let Some(Value::String(text)) = part.get_mut("text") {
+                        *text = text.trim().to_string();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The cache key of `request` sent to `provider`.
+pub fn key(provider: &str, request: &Value) -> String {
+    let mut body = request.clone();
+    if let Some(fields) = body.as_object_mut() {
+        for field in IGNORED_FIELDS {
+            fields.remove(*field);
+        }
+        if let Some(messages) = fields.get_mut("messages") {
+            normalize_messages(messages);
+        }
+        if let Some(Value::String(system)) = fields.get_mut("system") {
+            *system = system.trim().to_string();
+        }
+    }
+    canonical_json_hash(&serde_json::json!({ "provider": provider, "request": body }))
+}
+
+/// The unexpired response stored under `key`, if any, counting the hit.
+fn get_from(
+    conn: &Connection,
+    settings: &ResponseCacheSettings,
+    key: &str,
+    now: i64,
+) -> Result<Option<ChatResponse>, String> {
+    let oldest = now - (settings.ttl_secs * 1000) as i64;
+    let response: Option<String> = conn
+        .query_row(
+            "SELECT response FROM llm_cache WHERE key = ?1 AND created_at >= ?2",
+            params![key, oldest],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(response) = response else {
+        return Ok(None);
+    };
+    conn.execute(
+        "UPDATE llm_cache SET hits = hits + 1, last_hit_at = ?2 WHERE key = ?1",
+        params![key, now],
+    )
+    .map_err(|e| e.to_string())?;
+    serde_json::from_str(&response)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// The unexpired response stored under `key`, if any.
+pub fn get(app: &AppHandle, settings: &ResponseCacheSettings, key: &str) -> Option<ChatResponse> {
+    let result =
+        crate::db::open(app).and_then(|conn| get_from(&conn, settings, key, now_ms() as i64));
+    result.unwrap_or_else(|e| {
+        log::warn!("Failed to read the response cache: {e}");
+        None
+    })
+}
+
+/// Stores `response` under `key`, then evicts what is expired or over the
+/// size bounds. Returns how many entries were evicted.
+fn put_into(
+    conn: &Connection,
+    settings: &ResponseCacheSettings,
+    key: &str,
+    provider: &str,
+    model: &str,
+    response: &ChatResponse,
+    now: i64,
+) -> Result<usize, String> {
+    let json = serde_json::to_string(response).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO llm_cache
+             (key, provider, model, response, size, created_at, last_hit_at, hits)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 0)",
+        params![key, provider, model, json, json.len() as i64, now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let oldest = now - (settings.ttl_secs * 1000) as i64;
+    let max_bytes = i64::from(settings.max_mb) * 1024 * 1024;
+    conn.execute(
+        "DELETE FROM llm_cache WHERE created_at < ?1",
+        params![oldest],
+    )
+    .and_then(|expired| {
+        // Most recently used first; whatever is past either bound goes
+        let over = conn.execute(
+            "DELETE FROM llm_cache WHERE key IN (
+                 SELECT key FROM (
+                     SELECT key,
+                            ROW_NUMBER() OVER recent AS position,
+                            SUM(size) OVER recent AS total
+                     FROM llm_cache
+                     WINDOW recent AS (ORDER BY last_hit_at DESC, key)
+                 )
+                 WHERE position > ?1 OR total > ?2
+             )",
+            params![settings.max_entries, max_bytes],
+        )?;
+        Ok(expired + over)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Stores a finished response under `key`, then evicts what is expired or
+/// over the size bounds.
+pub fn put(
+    app: &AppHandle,
+    settings: &ResponseCacheSettings,
+    key: &str,
+    provider: &str,
+    model: &str,
+    response: &ChatResponse,
+) {
+    let result = crate::db::open(app).and_then(|conn| {
+        put_into(
+            &conn,
+            settings,
+            key,
+            provider,
+            model,
+            response,
+            now_ms() as i64,
+        )
+    });
+    match result {
+        Ok(0) => {}
+        Ok(evicted) => log::debug!("Evicted {evicted} cached responses"),
+        Err(e) => log::warn!("Failed to store the response in the cache: {e}"),
+    }
+}
+
+/// Deletes every cached response. Returns how many there were.
+#[tauri::command]
+pub async fn clear_llm_cache(app: AppHandle) -> Result<usize, String> {
+    let conn = crate::db::open(&app)?;
+    let cleared = conn
+        .execute("DELETE FROM llm_cache", [])
+        .map_err(|e| format!("Failed to clear the response cache: {e}"))?;
+    log::info!("Cleared {cleared} cached responses");
+    Ok(cleared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MINUTE_MS: i64 = 60 * 1000;
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn response(text: &str) -> ChatResponse {
+        ChatResponse {
+            text: text.to_string(),
+            tool_calls: Vec::new(),
+            finish_reason: Some("stop".to_string()),
+            usage: None,
+            first_chunk_ms: None,
+            cached: false,
+        }
+    }
+
+    fn settings() -> ResponseCacheSettings {
+        ResponseCacheSettings {
+            enabled: true,
+            ..ResponseCacheSettings::default()
+        }
+    }
+
+    fn keys(conn: &Connection) -> Vec<String> {
+        let mut statement = conn
+            .prepare("SELECT key FROM llm_cache ORDER BY key")
+            .unwrap();
+        let rows = statement.query_map([], |row| row.get(0)).unwrap();
+        rows.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn key_ignores_whitespace_field_order_and_streaming() {
+        let request = json!({
+            "model": "gpt-4o",
+            "temperature": 0.2,
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": [{"type": "text", "text": "Hi"}]},
+            ],
+        });
+        let same = json!({
+            "stream": true,
+            "stream_options": {"include_usage": true},
+            "user": "someone",
+            "messages": [
+                {"content": "  Be brief.\n", "role": "system"},
+                {"role": "user", "content": [{"type": "text", "text": "Hi  "}]},
+            ],
+            "temperature": 0.2,
+            "model": "gpt-4o",
+        });
+        assert_eq!(key("openai", &request), key("openai", &same));
+
+        let system = |text: &str| json!({"model": "claude", "system": text});
+        assert_eq!(
+            key("anthropic", &system("Be brief.")),
+            key("anthropic", &system(" Be brief. "))
+        );
+    }
+
+    #[test]
+    fn key_changes_with_provider_model_and_parameters() {
+        let request = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+        let base = key("openai", &request);
+        assert_ne!(base, key("azure", &request));
+
+        let mut other_model = request.clone();
+        other_model["model"] = json!("gpt-4o-mini");
+        assert_ne!(base, key("openai", &other_model));
+
+        let mut warmer = request.clone();
+        warmer["temperature"] = json!(1.0);
+        assert_ne!(base, key("openai", &warmer));
+
+        let mut other_text = request;
+        other_text["messages"][0]["content"] = json!("Hi there");
+        assert_ne!(base, key("openai", &other_text));
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let conn = database();
+        let settings = ResponseCacheSettings {
+            ttl_secs: 60,
+            ..settings()
+        };
+        put_into(
+            &conn,
+            &settings,
+            "k",
+            "openai",
+            "gpt-4o",
+            &response("Hello"),
+            0,
+        )
+        .unwrap();
+
+        let hit = get_from(&conn, &settings, "k", MINUTE_MS).unwrap();
+        assert_eq!(hit.map(|r| r.text).as_deref(), Some("Hello"));
+        assert!(get_from(&conn, &settings, "k", MINUTE_MS + 1)
+            .unwrap()
+            .is_none());
+        assert!(get_from(&conn, &settings, "missing", 0).unwrap().is_none());
+
+        // Storing anything later removes what has expired
+        let later = 2 * MINUTE_MS;
+        put_into(
+            &conn,
+            &settings,
+            "k2",
+            "openai",
+            "gpt-4o",
+            &response("Hi"),
+            later,
+        )
+        .unwrap();
+        assert_eq!(keys(&conn), ["k2"]);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_past_max_entries() {
+        let conn = database();
+        let settings = ResponseCacheSettings {
+            max_entries: 2,
+            ..settings()
+        };
+        put_into(&conn, &settings, "a", "openai", "m", &response("A"), 1).unwrap();
+        put_into(&conn, &settings, "b", "openai", "m", &response("B"), 2).unwrap();
+        // Reading `a` makes `b` the least recently used
+        assert!(get_from(&conn, &settings, "a", 3).unwrap().is_some());
+
+        let evicted = put_into(&conn, &settings, "c", "openai", "m", &response("C"), 4).unwrap();
+        assert_eq!(evicted, 1);
+        assert_eq!(keys(&conn), ["a", "c"]);
+    }
+
+    #[test]
+    fn evicts_past_the_size_bound() {
+        let conn = database();
+        let settings = ResponseCacheSettings {
+            max_mb: 1,
+            ..settings()
+        };
+        let large = response(&"x".repeat(600 * 1024));
+        put_into(&conn, &settings, "old", "openai", "m", &large, 1).unwrap();
+        put_into(&conn, &settings, "new", "openai", "m", &large, 2).unwrap();
+        assert_eq!(keys(&conn), ["new"]);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_settings() {
+        assert!(validate(&settings()).is_ok());
+        for invalid in [
+            ResponseCacheSettings {
+                ttl_secs: 0,
+                ..settings()
+            },
+            ResponseCacheSettings {
+                ttl_secs: MAX_TTL_SECS + 1,
+                ..settings()
+            },
+            ResponseCacheSettings {
+                max_entries: 0,
+                ..settings()
+            },
+            ResponseCacheSettings {
+                max_mb: MAX_MB_LIMIT + 1,
+                ..settings()
+            },
+        ] {
+            assert!(validate(&invalid).is_err());
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Retries
+// =======
+//
+// Model requests that fail before any output arrives (rate limits, 5xx
+// responses, refused or reset connections) are sent again after an
+// exponentially growing, jittered delay, or after the delay the provider
+// asks for in `Retry-After`. Each wait is announced with an `llm-retrying`
+// event so the UI can show "retrying in 4s…" rather than failing at once.
+// A stream that has started is never retried: its output was already
+// shown. Every attempt waits its turn under the host's rate limit (see
+// `rate_limit`).
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Upper bound on `max_attempts`, whatever the preference says
+const MAX_ATTEMPTS_LIMIT: u32 = 10;
+/// Upper bound on any single wait, including a provider's `Retry-After`
+const MAX_DELAY_LIMIT_MS: u64 = 300_000;
+/// Statuses worth another attempt; 529 is Anthropic's "overloaded"
+const RETRYABLE_STATUSES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 1 turns retries off
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each one after
+    pub initial_delay_ms: u64,
+    /// Cap on the backoff delay
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmRetryingEvent {
+    /// The proxied chat completion, or `None` for backend requests
+    pub request_id: Option<String>,
+    /// Where the request goes, e.g. `api.openai.com` or `openai`
+    pub target: String,
+    /// The attempt about to be made, counting from 1
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    /// HTTP status of the failed attempt; `None` for connection errors
+    pub status: Option<u16>,
+    pub reason: String,
+}
+
+pub fn validate(policy: &RetryPolicy) -> Result<(), String> {
+    if policy.max_attempts == 0 || policy.max_attempts > MAX_ATTEMPTS_LIMIT {
+        return Err(format!(
+            "Retry attempts must be between 1 and {MAX_ATTEMPTS_LIMIT}"
+        ));
+    }
+    if policy.max_delay_ms > MAX_DELAY_LIMIT_MS {
+        return Err(format!(
+            "Retry delay can be at most {} seconds",
+            MAX_DELAY_LIMIT_MS / 1000
+        ));
+    }
+    if policy.initial_delay_ms > policy.max_delay_ms {
+        return Err("The initial retry delay can't exceed the maximum delay".to_string());
+    }
+    Ok(())
+}
+
+pub fn policy(app: &AppHandle) -> RetryPolicy {
+    crate::read_preferences(app)
+        .map(|p| p.llm_retry)
+        .ok()
+        .filter(|policy| validate(policy).is_ok())
+        .unwrap_or_default()
+}
+
+/// Sends the request `build` makes, building it again for each retry, and
+/// returns the first response that is not worth retrying or the last one.
+/// Only a request that never got a response fails with `Err`.
+pub async fn send(
+    app: &AppHandle,
+    request_id: Option<&str>,
+    target: &str,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, String> {
+    let policy = policy(app);
+    let mut attempt = 1;
+    loop {
+        let (client, request) = build().build_split();
+        let request = request.map_err(|e| format!("Request to {target} failed: {e}"))?;
+        crate::rate_limit::acquire(app, request.url()).await;
+        let (status, retry_after, reason) = match client.execute(request).await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                if attempt >= policy.max_attempts || !RETRYABLE_STATUSES.contains(&status) {
+                    return Ok(response);
+                }
+                let retry_after = retry_after(response.headers(), chrono::Utc::now());
+                let body = response.text().await.unwrap_or_default();
+                (Some(status), retry_after, crate::llm::error_message(&body))
+            }
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                (None, None, e.to_string())
+            }
+            Err(e) => return Err(format!("Request to {target} failed: {e}")),
+        };
+
+        let delay = retry_delay(&policy, attempt, retry_after);
+        attempt += 1;
+        log::warn!(
+            "Request to {target} failed ({}), attempt {attempt} of {} in {}ms",
+            status.map_or_else(|| reason.clone(), |s| s.to_string()),
+            policy.max_attempts,
+            delay.as_millis()
+        );
+        let event = LlmRetryingEvent {
+            request_id: request_id.map(String::from),
+            target: target.to_string(),
+            attempt,
+            max_attempts: policy.max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            status,
+            reason,
+        };
+        if let Err(e) = app.emit("llm-retrying", &event) {
+            log::warn!("Failed to emit llm-retrying: {e}");
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Errors from which a fresh connection may well recover.
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request()
+}
+
+/// Exponential backoff with "equal jitter": between half and all of the
+/// exponential delay, so clients that failed together don't retry together.
+fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let exponential = policy
+        .initial_delay_ms
+        .saturating_mul(1 << (attempt - 1).min(20))
+        .min(policy.max_delay_ms);
+    let mut bytes = [0u8; 8];
+    let fraction = match getrandom::getrandom(&mut bytes) {
+        Ok(()) => u64::from_le_bytes(bytes) as f64 / u64::MAX as f64,
+        Err(_) => 1.0,
+    };
+    Duration::from_millis(exponential / 2 + (exponential as f64 / 2.0 * fraction) as u64)
+}
+
+/// The provider's `Retry-After` if it sent one, otherwise the backoff;
+/// either way no longer than the largest delay allowed.
+fn retry_delay(policy: &RetryPolicy, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| backoff(policy, attempt))
+        .min(Duration::from_millis(MAX_DELAY_LIMIT_MS))
+}
+
+/// The delay asked for in `Retry-After`, given in seconds or as an HTTP date.
+fn retry_after(
+    headers: &reqwest::header::HeaderMap,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(now);
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn within(delay: Duration, low_ms: u64, high_ms: u64) -> bool {
+        (low_ms..=high_ms).contains(&(delay.as_millis() as u64))
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        let policy = RetryPolicy::default();
+        for _ in 0..50 {
+            assert!(within(backoff(&policy, 1), 500, 1000));
+            assert!(within(backoff(&policy, 3), 2000, 4000));
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        for attempt in [6, 10, 64, u32::MAX] {
+            assert!(within(backoff(&policy, attempt), 15_000, 30_000));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_policies() {
+        assert!(validate(&RetryPolicy::default()).is_ok());
+        let policy = |max_attempts, initial_delay_ms, max_delay_ms| RetryPolicy {
+            max_attempts,
+            initial_delay_ms,
+            max_delay_ms,
+        };
+        assert!(validate(&policy(0, 1000, 30_000)).is_err());
+        assert!(validate(&policy(MAX_ATTEMPTS_LIMIT + 1, 1000, 30_000)).is_err());
+        assert!(validate(&policy(3, 1000, MAX_DELAY_LIMIT_MS + 1)).is_err());
+        assert!(validate(&policy(3, 5000, 1000)).is_err());
+    }
+
+    fn headers(retry_after: &str) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, retry_after.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn reads_retry_after_in_seconds_and_as_a_date() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            retry_after(&headers("120"), now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:30 GMT"), now),
+            Some(Duration::from_secs(30))
+        );
+        // A date already past means retry right away
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:27:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers("soon"), now), None);
+        assert_eq!(retry_after(&reqwest::header::HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn retry_after_replaces_the_backoff_within_the_cap() {
+        let policy = RetryPolicy::default();
+        let asked = Some(Duration::from_secs(3));
+        assert_eq!(retry_delay(&policy, 5, asked), Duration::from_secs(3));
+        let too_long = Some(Duration::from_secs(3600));
+        assert_eq!(
+            retry_delay(&policy, 1, too_long),
+            Duration::from_millis(MAX_DELAY_LIMIT_MS)
+        );
+        assert!(within(retry_delay(&policy, 1, None), 500, 1000));
+    }
+}
@@ -0,0 +1,141 @@
+// Background Scheduler
+// ====================
+//
+// A single background thread that periodically runs maintenance jobs. Each
+// job decides for itself whether there is work to do (e.g. the scheduled
+// export checks its own last-run timestamp), so the scheduler only has to
+// call it at a regular cadence. When each job last ran, and how, is kept in
+// `SchedulerStatus`. Heavy jobs are skipped while background work is
+// paused for power (see `power`) and run at the first tick after.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const TICK: Duration = Duration::from_secs(60);
+
+pub struct ScheduledJob {
+    pub name: &'static str,
+    /// How often the job is invoked
+    pub interval: Duration,
+    /// Waits while background work is paused, e.g. on battery
+    pub heavy: bool,
+    pub run: fn(&AppHandle) -> Result<(), String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub interval_secs: u64,
+    /// Milliseconds since the UNIX epoch; `None` until the first run
+    pub last_run: Option<u64>,
+    pub last_error: Option<String>,
+    pub runs: u64,
+}
+
+#[derive(Default)]
+pub struct SchedulerStatus {
+    jobs: Mutex<Vec<JobStatus>>,
+}
+
+impl SchedulerStatus {
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.clone())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, index: usize, result: &Result<(), String>) {
+        let Ok(mut jobs) = self.jobs.lock() else {
+            return;
+        };
+        if let Some(job) = jobs.get_mut(index) {
+            job.last_run = Some(crate::time::now_ms());
+            job.last_error = result.as_ref().err().cloned();
+            job.runs += 1;
+        }
+    }
+}
+
+pub fn default_jobs() -> Vec<ScheduledJob> {
+    vec![
+        ScheduledJob {
+            name: "scheduled-export",
+            interval: Duration::from_secs(60 * 60),
+            heavy: true,
+            run: crate::export::run_scheduled_export,
+        },
+        ScheduledJob {
+            name: "attachment-gc",
+            interval: Duration::from_secs(6 * 60 * 60),
+            heavy: true,
+            run: crate::attachments::collect_garbage,
+        },
+        ScheduledJob {
+            name: "task-reminders",
+            interval: Duration::from_secs(60),
+            heavy: false,
+            run: crate::tasks::send_due_reminders,
+        },
+        ScheduledJob {
+            name: "watch-folders",
+            interval: Duration::from_secs(5 * 60),
+            heavy: true,
+            run: crate::watchfolder::scan_all,
+        },
+        ScheduledJob {
+            name: "database-backup",
+            interval: Duration::from_secs(60 * 60),
+            heavy: true,
+            run: crate::db_backup::run_scheduled_backup,
+        },
+    ]
+}
+
+/// Starts the scheduler thread. Every job runs once shortly after startup and
+/// then every `interval`.
+pub fn start(app: AppHandle, jobs: Vec<ScheduledJob>) {
+    if let Ok(mut status) = app.state::<SchedulerStatus>().jobs.lock() {
+        *status = jobs
+            .iter()
+            .map(|job| JobStatus {
+                name: job.name,
+                interval_secs: job.interval.as_secs(),
+                last_run: None,
+                last_error: None,
+                runs: 0,
+            })
+            .collect();
+    }
+
+    std::thread::spawn(move || {
+        let mut last_run: Vec<Option<Instant>> = vec![None; jobs.len()];
+
+        loop {
+            std::thread::sleep(TICK);
+
+            for (index, (job, last)) in jobs.iter().zip(last_run.iter_mut()).enumerate() {
+                if last.is_some_and(|t| t.elapsed() < job.interval) {
+                    continue;
+                }
+                if job.heavy {
+                    if let Some(reason) = crate::power::paused(&app) {
+                        log::debug!("Deferring scheduled job {}: {reason:?}", job.name);
+                        continue;
+                    }
+                }
+                *last = Some(Instant::now());
+
+                log::debug!("Running scheduled job: {}", job.name);
+                let result = (job.run)(&app);
+                if let Err(e) = &result {
+                    log::error!("Scheduled job {} failed: {e}", job.name);
+                }
+                app.state::<SchedulerStatus>().record(index, &result);
+            }
+        }
+    });
+}
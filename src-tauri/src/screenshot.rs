@@ -0,0 +1,286 @@
+// Screenshots
+// ===========
+//
+// `capture_screenshot` takes a screenshot with the platform's own tooling
+// and stores it as a PNG attachment, so the user can ask a vision model
+// about something on screen without leaving the app. The calling window
+// hides while the screenshot is taken and comes back afterwards.
+//
+// - macOS: `screencapture`, interactive for a window or a region.
+// - Windows: the full screen is copied through PowerShell; for a window or
+//   a region the Snipping Tool opens, and the image it puts on the
+//   clipboard is taken.
+// - Linux: the XDG desktop portal, which lets the user pick a window or a
+//   region in its interactive mode. Without one, `gnome-screenshot`,
+//   `spectacle` or `scrot` is used, whichever is installed.
+//
+// Cancelling the selection is not an error: the command resolves with
+// `None`.
+
+use base64::Engine;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::attachments::StoredAttachment;
+use crate::time::now_ms;
+
+/// Lets the compositor take the hidden window off the screen
+const HIDE_DELAY: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotMode {
+    /// Every monitor
+    FullScreen,
+    /// A window the user picks
+    Window,
+    /// A rectangle the user drags
+    Region,
+}
+
+/// Runs a capture tool off the async runtime; interactive ones block until
+/// the user is done.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Screenshot task failed: {e}"))?
+}
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "nexus-screenshot-{}-{}.png",
+        std::process::id(),
+        now_ms()
+    ))
+}
+
+/// The PNG a capture tool wrote, or `None` if it wrote nothing because the
+/// user cancelled.
+fn read_png(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) if bytes.is_empty() => Ok(None),
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read screenshot: {e}")),
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn capture(_app: &AppHandle, mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    blocking(move || screencapture(mode)).await
+}
+
+#[cfg(target_os = "macos")]
+fn screencapture(mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    let path = temp_path();
+    let args: &[&str] = match mode {
+        ScreenshotMode::FullScreen => &["-x"],
+        ScreenshotMode::Window => &["-x", "-i", "-W"],
+        ScreenshotMode::Region => &["-x", "-i", "-s"],
+    };
+    let status = std::process::Command::new("screencapture")
+        .args(args)
+        .arg(&path)
+        .status()
+        .map_err(|e| format!("Failed to run screencapture: {e}"))?;
+    let png = read_png(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status.success() {
+        return Err(format!("screencapture failed with {status}"));
+    }
+    png
+}
+
+#[cfg(windows)]
+async fn capture(app: &AppHandle, mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    match mode {
+        ScreenshotMode::FullScreen => blocking(windows_full_screen).await,
+        ScreenshotMode::Window | ScreenshotMode::Region => snipping_tool(app, mode).await,
+    }
+}
+
+/// Copies every monitor into one image.
+#[cfg(windows)]
+fn windows_full_screen() -> Result<Option<Vec<u8>>, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    // Without DPI awareness only the scaled down part of the screen is
+    // copied
+    const SCRIPT: &str = r#"
+Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+Add-Type -Namespace Nexus -Name Dpi -MemberDefinition '[DllImport("user32.dll")] public static extern bool SetProcessDPIAware();'
+[Nexus.Dpi]::SetProcessDPIAware() | Out-Null
+$bounds = [System.Windows.Forms.SystemInformation]::VirtualScreen
+$bitmap = New-Object System.Drawing.Bitmap $bounds.Width, $bounds.Height
+$graphics = [System.Drawing.Graphics]::FromImage($bitmap)
+$graphics.CopyFromScreen($bounds.Left, $bounds.Top, 0, 0, $bitmap.Size)
+$bitmap.Save($env:NEXUS_SCREENSHOT_PATH, [System.Drawing.Imaging.ImageFormat]::Png)
+"#;
+
+    let path = temp_path();
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("NEXUS_SCREENSHOT_PATH", &path)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run PowerShell: {e}"))?;
+    let png = read_png(&path);
+    let _ = std::fs::remove_file(&path);
+    if !output.status.success() {
+        return Err(format!(
+            "Screen capture failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    png
+}
+
+/// Opens the Snipping Tool and waits for the image it puts on the
+/// clipboard.
+#[cfg(windows)]
+async fn snipping_tool(app: &AppHandle, mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    use crate::hash::sha256_hex;
+    /// How long the user has to make a selection
+    const SELECTION_TIMEOUT: Duration = Duration::from_secs(120);
+    const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+    let fingerprint = |app: &AppHandle| {
+        crate::clipboard::read_image(app).map(|image| (sha256_hex(image.as_raw()), image))
+    };
+    let before = fingerprint(app).map(|(hash, _)| hash);
+    let uri = match mode {
+        ScreenshotMode::Window => "ms-screenclip:?clippingMode=Window",
+        _ => "ms-screenclip:?clippingMode=Rectangle",
+    };
+    // explorer exits with 1 even when it opened the URI
+    std::process::Command::new("explorer")
+        .arg(uri)
+        .spawn()
+        .map_err(|e| format!("Failed to open the Snipping Tool: {e}"))?;
+
+    let started = std::time::Instant::now();
+    while started.elapsed() < SELECTION_TIMEOUT {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if let Some((hash, image)) = fingerprint(app) {
+            if before.as_ref() != Some(&hash) {
+                return crate::clipboard::encode_png(&image).map(Some);
+            }
+        }
+    }
+    log::info!("No screenshot was taken with the Snipping Tool");
+    Ok(None)
+}
+
+#[cfg(target_os = "linux")]
+async fn capture(_app: &AppHandle, mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    use ashpd::desktop::screenshot::Screenshot;
+
+    let portal = Screenshot::request()
+        .interactive(mode != ScreenshotMode::FullScreen)
+        .modal(false)
+        .send()
+        .await
+        .and_then(|request| request.response());
+    match portal {
+        Ok(screenshot) => {
+            let path = screenshot
+                .uri()
+                .to_file_path()
+                .map_err(|_| "The screenshot portal returned no file".to_string())?;
+            return read_png(&path);
+        }
+        Err(ashpd::Error::Response(ashpd::desktop::ResponseError::Cancelled)) => return Ok(None),
+        Err(e) => log::info!("Screenshot portal unavailable, trying capture tools: {e}"),
+    }
+    blocking(move || capture_tools(mode)).await
+}
+
+#[cfg(target_os = "linux")]
+fn capture_tools(mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    let path = temp_path();
+    let path_arg = path.to_string_lossy().into_owned();
+    let path_arg = path_arg.as_str();
+    let tools: [(&str, Vec<&str>); 3] = [
+        (
+            "gnome-screenshot",
+            match mode {
+                ScreenshotMode::FullScreen => vec!["-f", path_arg],
+                ScreenshotMode::Window => vec!["-w", "-f", path_arg],
+                ScreenshotMode::Region => vec!["-a", "-f", path_arg],
+            },
+        ),
+        (
+            "spectacle",
+            match mode {
+                ScreenshotMode::FullScreen => vec!["-b", "-n", "-f", "-o", path_arg],
+                ScreenshotMode::Window => vec!["-b", "-n", "-a", "-o", path_arg],
+                ScreenshotMode::Region => vec!["-b", "-n", "-r", "-o", path_arg],
+            },
+        ),
+        (
+            "scrot",
+            match mode {
+                ScreenshotMode::FullScreen => vec![path_arg],
+                ScreenshotMode::Window | ScreenshotMode::Region => vec!["-s", path_arg],
+            },
+        ),
+    ];
+    for (tool, args) in tools {
+        match std::process::Command::new(tool).args(&args).status() {
+            Ok(_) => {
+                // Tools disagree on the exit status of a cancelled
+                // selection; a missing file says enough
+                let png = read_png(&path);
+                let _ = std::fs::remove_file(&path);
+                return png;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to run {tool}: {e}")),
+        }
+    }
+    Err("No screenshot tool found; install gnome-screenshot, spectacle or scrot".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+async fn capture(_app: &AppHandle, _mode: ScreenshotMode) -> Result<Option<Vec<u8>>, String> {
+    Err("Screenshots are not supported on this platform".to_string())
+}
+
+/// Takes a screenshot and stores it as a PNG attachment. Resolves with
+/// `None` if the user cancelled the selection.
+#[tauri::command]
+pub async fn capture_screenshot(
+    app: AppHandle,
+    window: WebviewWindow,
+    mode: ScreenshotMode,
+) -> Result<Option<StoredAttachment>, String> {
+    let was_visible = window.is_visible().unwrap_or(false);
+    if was_visible {
+        window
+            .hide()
+            .map_err(|e| format!("Failed to hide window: {e}"))?;
+        tokio::time::sleep(HIDE_DELAY).await;
+    }
+    let result = capture(&app, mode).await;
+    if was_visible {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            log::warn!("Failed to show window after a screenshot: {e}");
+        }
+    }
+
+    let Some(png) = result? else {
+        log::info!("Screenshot ({mode:?}) cancelled");
+        return Ok(None);
+    };
+    let stored = crate::attachments::store(
+        &app,
+        base64::engine::general_purpose::STANDARD.encode(png),
+        Some("image/png".to_string()),
+    )?;
+    log::info!("Stored screenshot ({mode:?}) as attachment {}", stored.hash);
+    Ok(Some(stored))
+}
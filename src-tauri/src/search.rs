@@ -0,0 +1,213 @@
+// Message Search
+// ==============
+//
+// Full-text search over the conversation store. Message text is indexed in
+// the `message_search` FTS5 table when a message is stored, and removed by
+// a trigger when the message row goes away. Queries are plain text: words
+// must all match (the last one as a prefix, for search-as-you-type) and
+// "quoted phrases" match exactly, so user input can never be an FTS syntax
+// error. Hits come back ranked by BM25 with a snippet and the offsets of
+// the matched terms in it.
+
+use rusqlite::{params, Transaction};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+const MAX_QUERY_CHARS: usize = 1000;
+/// Tokens of context in a snippet
+const SNIPPET_TOKENS: u32 = 24;
+/// Markers around matched terms in raw snippets; control characters that
+/// do not occur in message text
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// Only these conversations
+    #[serde(default)]
+    pub conversation_ids: Option<Vec<String>>,
+    /// Only messages with one of these roles
+    #[serde(default)]
+    pub roles: Option<Vec<String>>,
+    /// Only messages sent at or after this time (milliseconds since the
+    /// UNIX epoch)
+    #[serde(default)]
+    pub since: Option<i64>,
+    /// Only messages sent before this time
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub conversation_title: String,
+    pub message_id: String,
+    pub role: String,
+    /// Milliseconds since the UNIX epoch
+    pub timestamp: i64,
+    /// Excerpt around the best match, with `…` where text was cut
+    pub snippet: String,
+    /// `[start, end)` of each matched term in `snippet`, in UTF-16 code
+    /// units so they can be used with JavaScript string methods directly
+    pub highlights: Vec<(usize, usize)>,
+    /// BM25 score; lower is a better match
+    pub rank: f64,
+}
+
+/// Indexes a message inside the transaction that stores it.
+pub fn index_message(
+    tx: &Transaction,
+    conversation_id: &str,
+    message_id: &str,
+    text: &str,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        "DELETE FROM message_search WHERE conversation_id = ?1 AND message_id = ?2",
+        params![conversation_id, message_id],
+    )?;
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+    tx.execute(
+        "INSERT INTO message_search (text, conversation_id, message_id) VALUES (?1, ?2, ?3)",
+        params![text, conversation_id, message_id],
+    )?;
+    Ok(())
+}
+
+/// Turns user input into an FTS5 query: every word and "quoted phrase" is
+/// quoted as a string, and the last bare word becomes a prefix query.
+/// Returns `None` if there is nothing to search for.
+fn fts_query(input: &str) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut last_is_word = false;
+    for (i, segment) in input.split('"').enumerate() {
+        // Odd segments were inside quotes
+        if i % 2 == 1 {
+            if !segment.trim().is_empty() {
+                terms.push(format!("\"{}\"", segment.trim()));
+                last_is_word = false;
+            }
+            continue;
+        }
+        for word in segment.split_whitespace() {
+            terms.push(format!("\"{word}\""));
+            last_is_word = true;
+        }
+    }
+    if last_is_word {
+        if let Some(last) = terms.last_mut() {
+            last.push('*');
+        }
+    }
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Strips the highlight markers from a raw snippet, returning the text and
+/// the UTF-16 ranges they enclosed.
+fn split_highlights(raw: &str) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let mut start = None;
+    for c in raw.chars() {
+        match c {
+            HIGHLIGHT_START => start = Some(offset),
+            HIGHLIGHT_END => {
+                if let Some(start) = start.take() {
+                    ranges.push((start, offset));
+                }
+            }
+            _ => {
+                text.push(c);
+                offset += c.len_utf16();
+            }
+        }
+    }
+    (text, ranges)
+}
+
+/// Searches message text in the conversation store, best matches first.
+#[tauri::command]
+pub async fn search_messages(
+    app: AppHandle,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SearchHit>, String> {
+    crate::validate_string_input(&query, MAX_QUERY_CHARS, "Search query")?;
+    let Some(fts_query) = fts_query(&query) else {
+        return Ok(Vec::new());
+    };
+    let filters = filters.unwrap_or_default();
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    // Filters on lists are passed as JSON arrays and expanded with json_each
+    let conversation_ids = filters
+        .conversation_ids
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let roles = filters
+        .roles
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let conn = crate::db::open(&app)?;
+    let sql = format!(
+        "SELECT s.conversation_id, c.title, s.message_id, m.role, m.timestamp,
+                snippet(message_search, 0, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '…', {SNIPPET_TOKENS}),
+                bm25(message_search) AS rank
+         FROM message_search s
+         JOIN messages m ON m.conversation_id = s.conversation_id AND m.id = s.message_id
+         JOIN conversations c ON c.id = s.conversation_id
+         WHERE message_search MATCH ?1
+           AND NOT EXISTS (SELECT 1 FROM message_deletions d
+                           WHERE d.conversation_id = m.conversation_id AND d.message_id = m.id)
+           AND (?2 IS NULL OR s.conversation_id IN (SELECT value FROM json_each(?2)))
+           AND (?3 IS NULL OR m.role IN (SELECT value FROM json_each(?3)))
+           AND (?4 IS NULL OR m.timestamp >= ?4)
+           AND (?5 IS NULL OR m.timestamp < ?5)
+         ORDER BY rank
+         LIMIT ?6"
+    );
+    conn.prepare(&sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(
+                params![
+                    fts_query,
+                    conversation_ids,
+                    roles,
+                    filters.since,
+                    filters.until,
+                    limit
+                ],
+                |row| {
+                    let (snippet, highlights) = split_highlights(&row.get::<_, String>(5)?);
+                    Ok(SearchHit {
+                        conversation_id: row.get(0)?,
+                        conversation_title: row.get(1)?,
+                        message_id: row.get(2)?,
+                        role: row.get(3)?,
+                        timestamp: row.get(4)?,
+                        snippet,
+                        highlights,
+                        rank: row.get(6)?,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| {
+            log::error!("Message search failed: {e}");
+            format!("Search failed: {e}")
+        })
+}
@@ -0,0 +1,684 @@
+// Second Factor
+// =============
+//
+// Optionally requires a TOTP code or a FIDO2 security key touch before
+// high-risk operations. The check runs in Rust inside the protected commands
+// themselves, so a compromised or buggy webview cannot skip it. Secrets live
+// in `second-factor.json` in the app data directory, never in preferences,
+// and the settings can only be changed through `configure_second_factor`,
+// which asks for the current factor first. FIDO2 support is behind the
+// `fido2` cargo feature because it links hidapi.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha1::Sha1;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::conversations;
+
+/// Prefix of the error returned when a protected command needs a code, so the
+/// frontend can prompt for one and retry
+pub const SECOND_FACTOR_REQUIRED: &str = "Second factor required";
+
+const TOTP_STEP_SECS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// Accepted clock drift, in steps on either side
+const TOTP_SKEW_STEPS: u64 = 1;
+const TOTP_SECRET_BYTES: usize = 20;
+const TOTP_ISSUER: &str = "Nexus";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecondFactorMethod {
+    #[default]
+    None,
+    Totp,
+    Fido2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectedOperation {
+    WipeData,
+    ExportAllData,
+    /// Approving a tool policy that allows destructive tool calls
+    ApproveToolPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondFactorSettings {
+    pub method: SecondFactorMethod,
+    pub protected_operations: Vec<ProtectedOperation>,
+}
+
+impl Default for SecondFactorSettings {
+    fn default() -> Self {
+        Self {
+            method: SecondFactorMethod::None,
+            protected_operations: vec![
+                ProtectedOperation::WipeData,
+                ProtectedOperation::ExportAllData,
+                ProtectedOperation::ApproveToolPolicy,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecondFactorSecrets {
+    /// Base32 TOTP secret in use
+    totp_secret: Option<String>,
+    /// Base32 TOTP secret waiting for its first code
+    pending_totp_secret: Option<String>,
+    /// Last accepted TOTP step, so a code can't be replayed
+    #[serde(default)]
+    last_totp_step: u64,
+    fido2: Option<Fido2Credential>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Fido2Credential {
+    /// Base64 credential id
+    credential_id: String,
+    /// Base64 DER public key
+    public_key_der: String,
+    /// `ctap_hid_fido2::public_key::PublicKeyType` as a number
+    key_type: u8,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI for QR codes
+    pub otpauth_uri: String,
+}
+
+fn get_secrets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("second-factor.json"))
+}
+
+fn read_secrets(app: &AppHandle) -> Result<SecondFactorSecrets, String> {
+    let path = get_secrets_path(app)?;
+    if !path.exists() {
+        return Ok(SecondFactorSecrets::default());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read second factor settings: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse second factor settings: {e}");
+        format!("Failed to parse second factor settings: {e}")
+    })
+}
+
+fn write_secrets(app: &AppHandle, secrets: &SecondFactorSecrets) -> Result<(), String> {
+    let path = get_secrets_path(app)?;
+    let json = serde_json::to_vec_pretty(secrets)
+        .map_err(|e| format!("Failed to serialize second factor settings: {e}"))?;
+    crate::export::write_atomic(&path, &json)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+fn current_settings(app: &AppHandle) -> SecondFactorSettings {
+    crate::read_preferences(app)
+        .map(|p| p.second_factor)
+        .unwrap_or_default()
+}
+
+// TOTP (RFC 6238)
+// ===============
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn totp_code(secret: &[u8], step: u64) -> u32 {
+    // HMAC accepts keys of any length, so this cannot fail
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Returns the matching time step if `code` is valid for `secret` now.
+fn verify_totp(secret: &str, code: &str, last_step: u64) -> Option<u64> {
+    let secret = base32_decode(secret)?;
+    let code: u32 = code.trim().replace(' ', "").parse().ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let current = now / TOTP_STEP_SECS;
+
+    (current.saturating_sub(TOTP_SKEW_STEPS)..=current + TOTP_SKEW_STEPS)
+        .filter(|step| *step > last_step)
+        .find(|step| totp_code(&secret, *step) == code)
+}
+
+// FIDO2
+// =====
+
+#[cfg(feature = "fido2")]
+const FIDO2_RPID: &str = "nexus.local";
+
+#[cfg(feature = "fido2")]
+fn fido2_register() -> Result<Fido2Credential, String> {
+    use base64::Engine;
+    use ctap_hid_fido2::fidokey::MakeCredentialArgsBuilder;
+    use ctap_hid_fido2::{verifier, FidoKeyHidFactory, LibCfg};
+
+    let device = FidoKeyHidFactory::create(&LibCfg::init())
+        .map_err(|e| format!("No security key found: {e}"))?;
+    let challenge = verifier::create_challenge();
+    let args = MakeCredentialArgsBuilder::new(FIDO2_RPID, &challenge)
+        .without_pin_and_uv()
+        .build();
+    let attestation = device
+        .make_credential_with_args(&args)
+        .map_err(|e| format!("Security key registration failed: {e}"))?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(Fido2Credential {
+        credential_id: engine.encode(&attestation.credential_descriptor.id),
+        public_key_der: engine.encode(&attestation.credential_publickey.der),
+        key_type: attestation.credential_publickey.key_type as u8,
+    })
+}
+
+#[cfg(not(feature = "fido2"))]
+fn fido2_register() -> Result<Fido2Credential, String> {
+    Err("Security key support is not included in this build".to_string())
+}
+
+#[cfg(feature = "fido2")]
+fn fido2_verify(credential: &Fido2Credential) -> Result<(), String> {
+    use base64::Engine;
+    use ctap_hid_fido2::fidokey::GetAssertionArgsBuilder;
+    use ctap_hid_fido2::public_key::{PublicKey, PublicKeyType};
+    use ctap_hid_fido2::{verifier, FidoKeyHidFactory, LibCfg};
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let credential_id = engine
+        .decode(&credential.credential_id)
+        .map_err(|e| format!("Corrupt security key credential: {e}"))?;
+    let der = engine
+        .decode(&credential.public_key_der)
+        .map_err(|e| format!("Corrupt security key credential: {e}"))?;
+    let key_type = match credential.key_type {
+        2 => PublicKeyType::Ed25519,
+        _ => PublicKeyType::Ecdsa256,
+    };
+
+    let device = FidoKeyHidFactory::create(&LibCfg::init())
+        .map_err(|e| format!("No security key found: {e}"))?;
+    let challenge = verifier::create_challenge();
+    let args = GetAssertionArgsBuilder::new(FIDO2_RPID, &challenge)
+        .add_credential_id(&credential_id)
+        .without_pin_and_uv()
+        .build();
+    let assertions = device
+        .get_assertion_with_args(&args)
+        .map_err(|e| format!("Security key check failed: {e}"))?;
+    let assertion = assertions
+        .first()
+        .ok_or("Security key returned no assertion")?;
+
+    let public_key = PublicKey::with_der(&der, key_type);
+    if verifier::verify_assertion(FIDO2_RPID, &public_key, &challenge, assertion) {
+        Ok(())
+    } else {
+        Err("Security key signature did not verify".to_string())
+    }
+}
+
+#[cfg(not(feature = "fido2"))]
+fn fido2_verify(_credential: &Fido2Credential) -> Result<(), String> {
+    Err("Security key support is not included in this build".to_string())
+}
+
+// Enforcement
+// ===========
+
+/// Checks the configured second factor for `method`. TOTP needs `code`;
+/// FIDO2 blocks until the key is touched.
+fn verify_method(
+    app: &AppHandle,
+    method: SecondFactorMethod,
+    code: Option<&str>,
+) -> Result<(), String> {
+    let mut secrets = read_secrets(app)?;
+    match method {
+        SecondFactorMethod::None => Ok(()),
+        SecondFactorMethod::Totp => {
+            let secret = secrets
+                .totp_secret
+                .clone()
+                .ok_or("No authenticator app is set up")?;
+            let code = code.ok_or_else(|| {
+                format!("{SECOND_FACTOR_REQUIRED}: enter the code from your authenticator app")
+            })?;
+            let step = verify_totp(&secret, code, secrets.last_totp_step)
+                .ok_or("Invalid or already used authenticator code")?;
+            secrets.last_totp_step = step;
+            write_secrets(app, &secrets)
+        }
+        SecondFactorMethod::Fido2 => {
+            let credential = secrets.fido2.ok_or("No security key is registered")?;
+            fido2_verify(&credential)
+        }
+    }
+}
+
+/// Fails unless the second factor for `operation` is satisfied. Protected
+/// commands call this before doing anything.
+pub async fn require(
+    app: &AppHandle,
+    operation: ProtectedOperation,
+    code: Option<String>,
+) -> Result<(), String> {
+    let settings = current_settings(app);
+    if settings.method == SecondFactorMethod::None
+        || !settings.protected_operations.contains(&operation)
+    {
+        return Ok(());
+    }
+
+    log::info!("Checking second factor for {operation:?}");
+    let app = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        verify_method(&app, settings.method, code.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Second factor check failed: {e}"))?;
+
+    if let Err(e) = &result {
+        log::warn!("Second factor check for {operation:?} failed: {e}");
+    }
+    result
+}
+
+// Commands
+// ========
+
+/// Generates a new TOTP secret. It only becomes active once
+/// `configure_second_factor` confirms a code from it.
+#[tauri::command]
+pub async fn begin_totp_enrollment(app: AppHandle) -> Result<TotpEnrollment, String> {
+    let mut bytes = [0u8; TOTP_SECRET_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate secret: {e}"))?;
+    let secret = base32_encode(&bytes);
+
+    let mut secrets = read_secrets(&app)?;
+    secrets.pending_totp_secret = Some(secret.clone());
+    write_secrets(&app, &secrets)?;
+
+    Ok(TotpEnrollment {
+        otpauth_uri: format!(
+            "otpauth://totp/{TOTP_ISSUER}?secret={secret}&issuer={TOTP_ISSUER}&digits={TOTP_DIGITS}&period={TOTP_STEP_SECS}"
+        ),
+        secret,
+    })
+}
+
+/// Registers a security key. Blocks until the key is touched.
+#[tauri::command]
+pub async fn enroll_security_key(app: AppHandle) -> Result<(), String> {
+    let credential = tauri::async_runtime::spawn_blocking(fido2_register)
+        .await
+        .map_err(|e| format!("Security key registration failed: {e}"))??;
+
+    let mut secrets = read_secrets(&app)?;
+    secrets.fido2 = Some(credential);
+    write_secrets(&app, &secrets)?;
+    log::info!("Registered a security key");
+    Ok(())
+}
+
+/// Changes the second factor settings. The currently configured factor must
+/// be satisfied first (`current_code` for TOTP); switching to TOTP also
+/// needs a code from the pending enrollment (`enrollment_code`).
+#[tauri::command]
+pub async fn configure_second_factor(
+    app: AppHandle,
+    settings: SecondFactorSettings,
+    current_code: Option<String>,
+    enrollment_code: Option<String>,
+) -> Result<(), String> {
+    let current = current_settings(&app);
+    let verify_app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        verify_method(&verify_app, current.method, current_code.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Second factor check failed: {e}"))??;
+
+    let mut secrets = read_secrets(&app)?;
+    match settings.method {
+        SecondFactorMethod::Totp => {
+            if let Some(pending) = secrets.pending_totp_secret.take() {
+                let code = enrollment_code
+                    .ok_or("Enter a code from your authenticator app to finish setup")?;
+                let step = verify_totp(&pending, &code, 0)
+                    .ok_or("The authenticator code does not match the new secret")?;
+                secrets.totp_secret = Some(pending);
+                secrets.last_totp_step = step;
+            } else if secrets.totp_secret.is_none() {
+                return Err("Set up an authenticator app first".to_string());
+            }
+        }
+        SecondFactorMethod::Fido2 if secrets.fido2.is_none() => {
+            return Err("Register a security key first".to_string());
+        }
+        _ => {}
+    }
+    write_secrets(&app, &secrets)?;
+
+    let mut preferences = crate::read_preferences(&app)?;
+    preferences.second_factor = settings;
+    crate::write_preferences(&app, &preferences)?;
+
+    log::info!(
+        "Second factor set to {:?}",
+        preferences.second_factor.method
+    );
+    Ok(())
+}
+
+/// Entries of the profile's data directory a wipe leaves alone. All but
+/// the last only exist in the default profile's directory, which is the app
+/// data directory itself, and are shared by every profile.
+const WIPE_KEEP: &[&str] = &[
+    // The other profiles and their registry
+    "profiles",
+    "profiles.json",
+    // Downloaded models hold no personal data; see `local_models`
+    "models",
+    "window-state.json",
+    // The endpoint of a running daemon
+    "daemon.json",
+    // The factor guarding the wipe stays until it is switched off
+    "second-factor.json",
+];
+
+/// Removes everything in `dir` except the `WIPE_KEEP` entries and `keep`.
+fn wipe_dir(dir: &Path, keep: &[PathBuf]) -> Result<(), String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {e}", dir.display())),
+    };
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if WIPE_KEEP.iter().any(|kept| name == *kept) || keep.contains(&path) {
+            continue;
+        }
+        // Not followed: a symlinked directory belongs to someone else
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let removed = if is_dir {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        removed.map_err(|e| {
+            log::error!("Failed to remove {}: {e}", path.display());
+            format!("Failed to remove {}: {e}", name.to_string_lossy())
+        })?;
+    }
+    Ok(())
+}
+
+/// Deletes everything the active profile stored on the backend except the
+/// `WIPE_KEEP` entries, plus the MCP server logs. The frontend clears its
+/// own storage afterwards. On Linux the app log directory lives in the app
+/// data directory; it is kept there, apart from the MCP logs.
+#[tauri::command]
+pub async fn wipe_app_data(
+    app: AppHandle,
+    second_factor_code: Option<String>,
+) -> Result<(), String> {
+    require(&app, ProtectedOperation::WipeData, second_factor_code).await?;
+    log::warn!("Wiping app data");
+
+    let app_data_dir = crate::profiles::data_dir(&app)?;
+    let log_dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get log directory: {e}"))?;
+
+    crate::destinations::delete_all_credentials(&app);
+    crate::watchfolder::delete_all_credentials(&app);
+    crate::openapi::delete_all_credentials(&app);
+    crate::graphql::delete_all_credentials(&app);
+    crate::databases::delete_all_credentials(&app);
+    crate::providers::delete_all_credentials(&app);
+    crate::api_keys::delete_all_credentials();
+
+    wipe_dir(&app_data_dir, std::slice::from_ref(&log_dir))?;
+    wipe_dir(&crate::mcp::stderr::log_dir(&log_dir), &[])
+}
+
+/// Writes every conversation plus the given MCP server list to a backup file
+/// in the format "Export Data" has always used.
+#[tauri::command]
+pub async fn export_all_data(
+    app: AppHandle,
+    path: String,
+    mcp_servers: Vec<Value>,
+    second_factor_code: Option<String>,
+) -> Result<(), String> {
+    require(&app, ProtectedOperation::ExportAllData, second_factor_code).await?;
+    log::info!("Exporting all data to {path}");
+
+    let backup = serde_json::json!({
+        "conversations": conversations::load_all(&app)?,
+        "mcpServers": mcp_servers,
+        "exportedAt": chrono::Utc::now().to_rfc3339(),
+    });
+    let json = serde_json::to_vec_pretty(&backup)
+        .map_err(|e| format!("Failed to serialize backup: {e}"))?;
+    crate::export::write_atomic(std::path::Path::new(&path), &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret for SHA-1
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    /// Directories and files the backend keeps in a profile's data directory
+    const KNOWN_STORES: &[&str] = &[
+        "conversations/",
+        "attachments/",
+        "prompt-history/",
+        "recovery/",
+        "audit/",
+        "calendar/",
+        "backups/",
+        "llm-fixtures/",
+        "mcp-packages/",
+        "preferences.json",
+        "scheduled-export.json",
+        "provider-benchmarks.jsonl",
+        "destinations.json",
+        "openapi_tools.json",
+        "graphql_tools.json",
+        "databases.json",
+        "inspectors.json",
+        "watch_folders.json",
+        "providers.json",
+        "budgets.json",
+        "prompt-templates.json",
+        "tool-policy.json",
+        crate::db::DATABASE_FILE,
+        "nexus.db-wal",
+        "nexus.db-shm",
+    ];
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nexus-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create(dir: &Path, entry: &str) {
+        match entry.strip_suffix('/') {
+            Some(name) => {
+                std::fs::create_dir_all(dir.join(name).join("nested")).unwrap();
+                std::fs::write(dir.join(name).join("nested/data"), "x").unwrap();
+            }
+            None => std::fs::write(dir.join(entry), "x").unwrap(),
+        }
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn wipe_removes_every_store_but_the_kept_ones() {
+        let dir = temp_dir("wipe");
+        for store in KNOWN_STORES {
+            create(&dir, store);
+        }
+        // Anything added later goes too, without being listed anywhere
+        create(&dir, "some-future-store/");
+        create(&dir, "some-future-store.json");
+        for kept in WIPE_KEEP {
+            create(&dir, kept);
+        }
+        create(&dir, "logs/");
+
+        wipe_dir(&dir, &[dir.join("logs")]).unwrap();
+
+        let mut expected: Vec<String> = WIPE_KEEP.iter().map(|k| k.to_string()).collect();
+        expected.push("logs".to_string());
+        expected.sort();
+        assert_eq!(entries(&dir), expected);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wipe_clears_mcp_logs() {
+        let log_dir = temp_dir("wipe-logs");
+        let mcp_logs = crate::mcp::stderr::log_dir(&log_dir);
+        std::fs::create_dir_all(&mcp_logs).unwrap();
+        std::fs::write(mcp_logs.join("server.log"), "secret").unwrap();
+        std::fs::write(mcp_logs.join("server.log.1"), "secret").unwrap();
+
+        wipe_dir(&mcp_logs, &[]).unwrap();
+        assert!(entries(&mcp_logs).is_empty());
+        // A missing directory is nothing to wipe
+        wipe_dir(&log_dir.join("missing"), &[]).unwrap();
+        std::fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn base32_matches_rfc_4648() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_decode("MZXW6YTBOI").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn base32_decode_accepts_padding_spaces_and_lowercase() {
+        assert_eq!(base32_decode("mzxw 6ytb oi======").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_none());
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238_vectors() {
+        // The RFC lists eight-digit codes; six digits are their last six
+        for (time, code) in [
+            (59, 287_082),
+            (1_111_111_109, 81_804),
+            (1_234_567_890, 5_924),
+            (2_000_000_000, 279_037),
+        ] {
+            assert_eq!(
+                totp_code(RFC_SECRET, time / TOTP_STEP_SECS),
+                code,
+                "at {time}"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_totp_accepts_current_code_once() {
+        let secret = base32_encode(RFC_SECRET);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let code = format!("{:06}", totp_code(RFC_SECRET, now / TOTP_STEP_SECS));
+
+        let step = verify_totp(&secret, &code, 0).expect("current code is valid");
+        assert!(step.abs_diff(now / TOTP_STEP_SECS) <= TOTP_SKEW_STEPS);
+        // A used step can't be replayed
+        assert_eq!(verify_totp(&secret, &code, step), None);
+        assert_eq!(verify_totp(&secret, "not a code", 0), None);
+    }
+}
@@ -0,0 +1,239 @@
+// Share Sheet
+// ===========
+//
+// Hands an exported file or a piece of text to the system's share UI, so
+// it can go straight to Mail, Messages, AirDrop or another app without
+// saving it somewhere and attaching it by hand: the `NSSharingServicePicker`
+// popover on macOS, anchored to the middle of the window, and the Share
+// dialog (`DataTransferManager`) on Windows. Linux has no share UI, so
+// there both commands fail and the frontend falls back to saving the file.
+//
+// The UI only opens; whether and where the item was shared is not reported
+// back.
+
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+const MAX_TEXT_LENGTH: usize = 1_000_000;
+
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+#[derive(Debug, Clone)]
+enum Item {
+    /// Absolute path of an existing file
+    File(String),
+    Text(String),
+}
+
+#[cfg(target_os = "macos")]
+mod picker {
+    use super::Item;
+    use objc2::encode::{Encode, Encoding};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use std::ffi::{c_void, CString};
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Size {
+        width: f64,
+        height: f64,
+    }
+
+    /// `NSRect`
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Rect {
+        origin: Point,
+        size: Size,
+    }
+
+    unsafe impl Encode for Point {
+        const ENCODING: Encoding = Encoding::Struct("CGPoint", &[f64::ENCODING, f64::ENCODING]);
+    }
+    unsafe impl Encode for Size {
+        const ENCODING: Encoding = Encoding::Struct("CGSize", &[f64::ENCODING, f64::ENCODING]);
+    }
+    unsafe impl Encode for Rect {
+        const ENCODING: Encoding = Encoding::Struct("CGRect", &[Point::ENCODING, Size::ENCODING]);
+    }
+
+    /// `NSRectEdge.minY`: the popover opens below its anchor
+    const MIN_Y_EDGE: usize = 1;
+
+    /// Shows the picker over `view`, the window's content view. Must run on
+    /// the main thread.
+    pub unsafe fn show(view: *mut c_void, item: &Item) -> Result<(), String> {
+        let (Item::File(value) | Item::Text(value)) = item;
+        let value = CString::new(value.as_str()).map_err(|_| "Can't share text containing NUL")?;
+        let string: *mut AnyObject =
+            msg_send![class!(NSString), stringWithUTF8String: value.as_ptr()];
+        let object: *mut AnyObject = match item {
+            Item::File(_) => msg_send![class!(NSURL), fileURLWithPath: string],
+            Item::Text(_) => string,
+        };
+        if object.is_null() {
+            return Err("Failed to prepare the item to share".to_string());
+        }
+        let items: *mut AnyObject = msg_send![class!(NSArray), arrayWithObject: object];
+
+        // Not released: the picker has to outlive its popover, which AppKit
+        // doesn't guarantee, and one is small
+        let picker: *mut AnyObject = msg_send![class!(NSSharingServicePicker), alloc];
+        let picker: *mut AnyObject = msg_send![picker, initWithItems: items];
+        if picker.is_null() {
+            return Err("Failed to create the share sheet".to_string());
+        }
+        let view = view.cast::<AnyObject>();
+        let bounds: Rect = msg_send![view, bounds];
+        let anchor = Rect {
+            origin: Point {
+                x: bounds.size.width / 2.0,
+                y: bounds.size.height / 2.0,
+            },
+            size: Size {
+                width: 1.0,
+                height: 1.0,
+            },
+        };
+        let () =
+            msg_send![picker, showRelativeToRect: anchor, ofView: view, preferredEdge: MIN_Y_EDGE];
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod share_ui {
+    use super::Item;
+    use std::cell::RefCell;
+    use windows::core::{factory, Interface, Ref, HSTRING};
+    use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+    use windows::Foundation::TypedEventHandler;
+    use windows::Storage::{IStorageItem, StorageFile};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+    use windows_collections::IIterable;
+
+    thread_local! {
+        /// The manager and `DataRequested` handler token of the last share,
+        /// removed before the next one; only touched on the main thread
+        static HANDLER: RefCell<Option<(DataTransferManager, i64)>> = const { RefCell::new(None) };
+    }
+
+    /// Fills in the data the Share dialog asks for once it opens.
+    fn provide(args: Ref<'_, DataRequestedEventArgs>, item: &Item) -> windows::core::Result<()> {
+        let Some(args) = args.as_ref() else {
+            return Ok(());
+        };
+        let data = args.Request()?.Data()?;
+        match item {
+            Item::File(path) => {
+                // Resolving a local path is quick; the dialog waits for its
+                // data either way
+                let file =
+                    StorageFile::GetFileFromPathAsync(&HSTRING::from(path.as_str()))?.get()?;
+                data.Properties()?.SetTitle(&file.Name()?)?;
+                let items: IIterable<IStorageItem> =
+                    vec![Some(file.cast::<IStorageItem>()?)].into();
+                data.SetStorageItemsReadOnly(&items)?;
+            }
+            Item::Text(text) => {
+                // The dialog refuses data without a title
+                data.Properties()?.SetTitle(&HSTRING::from("Nexus"))?;
+                data.SetText(&HSTRING::from(text.as_str()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the Share dialog for the window `hwnd`. Must run on the main
+    /// thread.
+    pub fn show(hwnd: HWND, item: Item) -> windows::core::Result<()> {
+        let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+        let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+        HANDLER.with(|handler| -> windows::core::Result<()> {
+            if let Some((manager, token)) = handler.borrow_mut().take() {
+                manager.RemoveDataRequested(token)?;
+            }
+            let token = manager
+                .DataRequested(&TypedEventHandler::new(move |_, args| provide(args, &item)))?;
+            *handler.borrow_mut() = Some((manager.clone(), token));
+            Ok(())
+        })?;
+        unsafe { interop.ShowShareUIForWindow(hwnd) }
+    }
+}
+
+fn show(app: &AppHandle, item: Item) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("The main window is not open")?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let view = window
+            .ns_view()
+            .map_err(|e| format!("Failed to get the window view: {e}"))?
+            as usize;
+        window
+            .run_on_main_thread(move || {
+                if let Err(e) = unsafe { picker::show(view as *mut _, &item) } {
+                    log::error!("Failed to show share sheet: {e}");
+                }
+            })
+            .map_err(|e| format!("Failed to show share sheet: {e}"))
+    }
+
+    #[cfg(windows)]
+    {
+        let hwnd = window
+            .hwnd()
+            .map_err(|e| format!("Failed to get the window handle: {e}"))?
+            .0 as usize;
+        window
+            .run_on_main_thread(move || {
+                let hwnd = windows::Win32::Foundation::HWND(hwnd as *mut _);
+                if let Err(e) = share_ui::show(hwnd, item) {
+                    log::error!("Failed to show share dialog: {e}");
+                }
+            })
+            .map_err(|e| format!("Failed to show share dialog: {e}"))
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let _ = (window, item);
+        Err("Sharing is not supported on this platform".to_string())
+    }
+}
+
+/// Opens the share UI for a file, e.g. an exported conversation.
+#[tauri::command]
+pub async fn share_file(app: AppHandle, path: String) -> Result<(), String> {
+    let file = Path::new(&path);
+    if !file.is_absolute() {
+        return Err("Path must be absolute".to_string());
+    }
+    if !file.is_file() {
+        return Err(format!("No file at {path}"));
+    }
+    log::info!("Sharing file {path}");
+    show(&app, Item::File(path))
+}
+
+/// Opens the share UI for a piece of text.
+#[tauri::command]
+pub async fn share_text(app: AppHandle, text: String) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to share".to_string());
+    }
+    crate::validate_string_input(&text, MAX_TEXT_LENGTH, "text")?;
+    log::info!("Sharing {} characters of text", text.len());
+    show(&app, Item::Text(text))
+}
@@ -0,0 +1,928 @@
+// Text to Speech
+// ==============
+//
+// Reads responses aloud with the system's own speech engine:
+// AVSpeechSynthesizer on macOS, SAPI on Windows and speech-dispatcher on
+// Linux, so the voices are the ones the user installed and configured.
+// One utterance is spoken at a time; starting another stops the current
+// one. It can be paused, resumed and stopped.
+//
+// `tts-progress` events follow an utterance: when it starts, every word
+// as it is spoken, pauses and resumes, and how it ended. Word positions
+// are offsets and lengths in UTF-16 code units, the way JavaScript indexes
+// strings, so the webview can highlight the word in the text it passed.
+//
+// `rate` is a multiple of the voice's normal speed; each engine's own
+// scale is mapped onto it.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAX_TEXT_CHARS: usize = 100_000;
+const MIN_RATE: f32 = 0.5;
+const MAX_RATE: f32 = 2.0;
+const MAX_VOICE_ID_LENGTH: usize = 500;
+
+/// What a running utterance is told to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Pause,
+    Resume,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechEvent {
+    Started,
+    /// A word is being spoken
+    Word,
+    Paused,
+    Resumed,
+    Finished,
+    /// Stopped, or replaced by another utterance
+    Cancelled,
+    Failed,
+}
+
+/// Payload of the `tts-progress` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtsProgress {
+    pub utterance_id: String,
+    pub event: SpeechEvent,
+    /// UTF-16 offset of the word in the text
+    pub offset: Option<u32>,
+    /// UTF-16 length of the word
+    pub length: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Voice {
+    /// Pass to `speak_text` as `voice`
+    pub id: String,
+    pub name: String,
+    /// BCP 47 tag, where the engine reports one
+    pub language: Option<String>,
+}
+
+struct Utterance {
+    id: String,
+    handle: native::Handle,
+}
+
+/// The utterance being spoken; managed as app state.
+#[derive(Default)]
+pub struct Speech {
+    current: Mutex<Option<Utterance>>,
+    next_id: AtomicU64,
+}
+
+fn emit(app: &AppHandle, utterance_id: &str, event: SpeechEvent, word: Option<(u32, u32)>) {
+    let progress = TtsProgress {
+        utterance_id: utterance_id.to_string(),
+        event,
+        offset: word.map(|(offset, _)| offset),
+        length: word.map(|(_, length)| length),
+    };
+    if let Err(e) = app.emit("tts-progress", progress) {
+        log::warn!("Failed to emit tts-progress event: {e}");
+    }
+}
+
+/// Called by the engines once an utterance ended, however it ended.
+fn ended(app: &AppHandle, utterance_id: &str, event: SpeechEvent) {
+    if let Ok(mut current) = app.state::<Speech>().current.lock() {
+        if current
+            .as_ref()
+            .is_some_and(|utterance| utterance.id == utterance_id)
+        {
+            *current = None;
+        }
+    }
+    log::info!("Utterance {utterance_id} ended: {event:?}");
+    emit(app, utterance_id, event, None);
+}
+
+/// The words of `text` as byte ranges with their UTF-16 offset and length.
+#[cfg(target_os = "linux")]
+fn words(text: &str) -> Vec<(std::ops::Range<usize>, u32, u32)> {
+    let mut words = Vec::new();
+    let mut start: Option<(usize, u32)> = None;
+    let mut utf16 = 0u32;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((i, utf16)),
+            (true, Some((byte, offset))) => {
+                words.push((byte..i, offset, utf16 - offset));
+                start = None;
+            }
+            _ => {}
+        }
+        utf16 += c.len_utf16() as u32;
+    }
+    if let Some((byte, offset)) = start {
+        words.push((byte..text.len(), offset, utf16 - offset));
+    }
+    words
+}
+
+fn control(app: &AppHandle, control: Control) -> Result<bool, String> {
+    let state = app.state::<Speech>();
+    let current = state.current.lock().map_err(|e| e.to_string())?;
+    let Some(utterance) = current.as_ref() else {
+        return Ok(false);
+    };
+    utterance.handle.control(control)?;
+    Ok(true)
+}
+
+/// Speaks `text` with `voice` (an id from `list_voices`, or the system
+/// default) at `rate` times the normal speed. Returns the utterance id
+/// `tts-progress` events carry.
+#[tauri::command]
+pub async fn speak_text(
+    app: AppHandle,
+    text: String,
+    voice: Option<String>,
+    rate: Option<f32>,
+) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to speak".to_string());
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        return Err(format!(
+            "Text too long to speak (max {MAX_TEXT_CHARS} characters)"
+        ));
+    }
+    if let Some(voice) = &voice {
+        crate::validate_string_input(voice, MAX_VOICE_ID_LENGTH, "Voice")?;
+    }
+    let rate = rate.unwrap_or(1.0);
+    if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+        return Err(format!(
+            "Speech rate must be between {MIN_RATE} and {MAX_RATE}"
+        ));
+    }
+
+    let state = app.state::<Speech>();
+    let utterance_id = format!("tts-{}", state.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+    // The replaced utterance reports itself cancelled
+    let previous = state.current.lock().map_err(|e| e.to_string())?.take();
+    if let Some(previous) = previous {
+        if let Err(e) = previous.handle.control(Control::Stop) {
+            log::warn!("Failed to stop utterance {}: {e}", previous.id);
+        }
+    }
+    let handle = native::speak(app.clone(), utterance_id.clone(), text, voice, rate)?;
+    *state.current.lock().map_err(|e| e.to_string())? = Some(Utterance {
+        id: utterance_id.clone(),
+        handle,
+    });
+    log::info!("Speaking utterance {utterance_id} at {rate}x");
+    Ok(utterance_id)
+}
+
+/// Pauses the current utterance. Returns whether one was speaking.
+#[tauri::command]
+pub async fn pause_speech(app: AppHandle) -> Result<bool, String> {
+    control(&app, Control::Pause)
+}
+
+#[tauri::command]
+pub async fn resume_speech(app: AppHandle) -> Result<bool, String> {
+    control(&app, Control::Resume)
+}
+
+#[tauri::command]
+pub async fn stop_speech(app: AppHandle) -> Result<bool, String> {
+    control(&app, Control::Stop)
+}
+
+/// The voices installed on this system.
+#[tauri::command]
+pub async fn list_voices() -> Result<Vec<Voice>, String> {
+    tauri::async_runtime::spawn_blocking(native::voices)
+        .await
+        .map_err(|e| format!("Voice listing failed: {e}"))?
+}
+
+#[cfg(windows)]
+mod native {
+    use super::{emit, ended, Control, SpeechEvent, Voice};
+    use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+    use std::time::Duration;
+    use tauri::AppHandle;
+    use windows::core::{HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::Media::Speech::{
+        ISpObjectToken, ISpObjectTokenCategory, ISpVoice, SpObjectToken, SpObjectTokenCategory,
+        SpVoice, SPCAT_VOICES, SPF_ASYNC, SPF_IS_NOT_XML, SPF_PURGEBEFORESPEAK, SPRS_DONE,
+        SPVOICESTATUS,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL,
+        COINIT_MULTITHREADED,
+    };
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Talks to the thread that owns the voice.
+    pub struct Handle(Sender<Control>);
+
+    impl Handle {
+        pub fn control(&self, control: Control) -> Result<(), String> {
+            self.0
+                .send(control)
+                .map_err(|_| "The utterance has already ended".to_string())
+        }
+    }
+
+    unsafe fn take_string(value: PWSTR) -> String {
+        let string = value.to_string().unwrap_or_default();
+        CoTaskMemFree(Some(value.0 as *const _));
+        string
+    }
+
+    /// SAPI rates run from -10 to 10; every 10 doubles or halves the speed.
+    fn sapi_rate(rate: f32) -> i32 {
+        ((rate.log2() * 10.0).round() as i32).clamp(-10, 10)
+    }
+
+    unsafe fn create_voice(voice_id: Option<&str>, rate: f32) -> windows::core::Result<ISpVoice> {
+        let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL)?;
+        if let Some(voice_id) = voice_id {
+            let token: ISpObjectToken = CoCreateInstance(&SpObjectToken, None, CLSCTX_ALL)?;
+            token.SetId(PCWSTR::null(), &HSTRING::from(voice_id), false)?;
+            voice.SetVoice(&token)?;
+        }
+        voice.SetRate(sapi_rate(rate))?;
+        Ok(voice)
+    }
+
+    /// Follows the utterance until it ends or is stopped.
+    unsafe fn follow(
+        app: &AppHandle,
+        utterance_id: &str,
+        voice: &ISpVoice,
+        controls: &Receiver<Control>,
+    ) -> windows::core::Result<SpeechEvent> {
+        let mut paused = false;
+        let mut last_word = None;
+        loop {
+            match controls.recv_timeout(POLL_INTERVAL) {
+                Ok(Control::Pause) if !paused => {
+                    voice.Pause()?;
+                    paused = true;
+                    emit(app, utterance_id, SpeechEvent::Paused, None);
+                }
+                Ok(Control::Resume) if paused => {
+                    voice.Resume()?;
+                    paused = false;
+                    emit(app, utterance_id, SpeechEvent::Resumed, None);
+                }
+                Ok(Control::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                    if paused {
+                        voice.Resume()?;
+                    }
+                    voice.Speak(PCWSTR::null(), SPF_PURGEBEFORESPEAK.0 as u32, None)?;
+                    return Ok(SpeechEvent::Cancelled);
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            let mut status = SPVOICESTATUS::default();
+            voice.GetStatus(&mut status, std::ptr::null_mut())?;
+            if status.dwRunningState & SPRS_DONE.0 as u32 != 0 {
+                return Ok(SpeechEvent::Finished);
+            }
+            let word = (status.ulInputWordPos, status.ulInputWordLen);
+            if word.1 > 0 && last_word != Some(word) {
+                last_word = Some(word);
+                emit(app, utterance_id, SpeechEvent::Word, Some(word));
+            }
+        }
+    }
+
+    pub fn speak(
+        app: AppHandle,
+        utterance_id: String,
+        text: String,
+        voice_id: Option<String>,
+        rate: f32,
+    ) -> Result<Handle, String> {
+        let (controls_tx, controls) = mpsc::channel();
+        let (started_tx, started) = mpsc::channel::<Result<(), String>>();
+        std::thread::spawn(move || unsafe {
+            if let Err(e) = CoInitializeEx(None, COINIT_MULTITHREADED).ok() {
+                let _ = started_tx.send(Err(e.to_string()));
+                return;
+            }
+            let voice = create_voice(voice_id.as_deref(), rate).and_then(|voice| {
+                let flags = SPF_ASYNC.0 | SPF_IS_NOT_XML.0 | SPF_PURGEBEFORESPEAK.0;
+                voice.Speak(&HSTRING::from(text.as_str()), flags as u32, None)?;
+                Ok(voice)
+            });
+            match voice {
+                Ok(voice) => {
+                    let _ = started_tx.send(Ok(()));
+                    emit(&app, &utterance_id, SpeechEvent::Started, None);
+                    let event =
+                        follow(&app, &utterance_id, &voice, &controls).unwrap_or_else(|e| {
+                            log::error!("Speech failed: {e}");
+                            SpeechEvent::Failed
+                        });
+                    ended(&app, &utterance_id, event);
+                }
+                Err(e) => {
+                    let _ = started_tx.send(Err(format!("Failed to start speech: {e}")));
+                }
+            }
+            CoUninitialize();
+        });
+        started
+            .recv()
+            .map_err(|_| "The speech thread failed".to_string())??;
+        Ok(Handle(controls_tx))
+    }
+
+    unsafe fn list() -> windows::core::Result<Vec<Voice>> {
+        let category: ISpObjectTokenCategory =
+            CoCreateInstance(&SpObjectTokenCategory, None, CLSCTX_ALL)?;
+        category.SetId(SPCAT_VOICES, false)?;
+        let tokens = category.EnumTokens(PCWSTR::null(), PCWSTR::null())?;
+        let mut voices = Vec::new();
+        loop {
+            let mut token: Option<ISpObjectToken> = None;
+            tokens.Next(1, &mut token, None)?;
+            let Some(token) = token else {
+                break;
+            };
+            let id = take_string(token.GetId()?);
+            // The token's default value is its display name
+            let name = token
+                .GetStringValue(PCWSTR::null())
+                .map(|name| take_string(name))
+                .unwrap_or_else(|_| id.clone());
+            voices.push(Voice {
+                id,
+                name,
+                language: None,
+            });
+        }
+        Ok(voices)
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED)
+                .ok()
+                .map_err(|e| e.to_string())?;
+            let result = list().map_err(|e| format!("Failed to list voices: {e}"));
+            CoUninitialize();
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod native {
+    use super::{emit, ended, Control, SpeechEvent, Voice};
+    use objc2::encode::{Encode, Encoding};
+    use objc2::runtime::{AnyClass, AnyObject, Imp, Sel};
+    use objc2::{class, msg_send, sel};
+    use std::collections::HashMap;
+    use std::ffi::{c_char, CStr, CString};
+    use std::sync::{Mutex, OnceLock};
+    use tauri::AppHandle;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {}
+
+    /// `AVSpeechUtteranceDefaultSpeechRate`; the scale runs from 0 to 1
+    const DEFAULT_RATE: f32 = 0.5;
+    /// `AVSpeechBoundary.immediate`
+    const IMMEDIATE: isize = 0;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct NSRange {
+        location: usize,
+        length: usize,
+    }
+
+    unsafe impl Encode for NSRange {
+        const ENCODING: Encoding =
+            Encoding::Struct("_NSRange", &[usize::ENCODING, usize::ENCODING]);
+    }
+
+    /// The app, for the delegate's events
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    /// The synthesizer, created once on the main thread and kept
+    static SYNTHESIZER: OnceLock<usize> = OnceLock::new();
+    /// Utterance ids by the address of their `AVSpeechUtterance`
+    static UTTERANCES: Mutex<Option<HashMap<usize, String>>> = Mutex::new(None);
+
+    fn utterance_id(utterance: *mut AnyObject, remove: bool) -> Option<String> {
+        let mut utterances = UTTERANCES.lock().ok()?;
+        let utterances = utterances.get_or_insert_with(HashMap::new);
+        if remove {
+            utterances.remove(&(utterance as usize))
+        } else {
+            utterances.get(&(utterance as usize)).cloned()
+        }
+    }
+
+    extern "C-unwind" fn will_speak_range(
+        _this: *mut AnyObject,
+        _cmd: Sel,
+        _synthesizer: *mut AnyObject,
+        range: NSRange,
+        utterance: *mut AnyObject,
+    ) {
+        if let (Some(app), Some(id)) = (APP.get(), utterance_id(utterance, false)) {
+            let word = (range.location as u32, range.length as u32);
+            emit(app, &id, SpeechEvent::Word, Some(word));
+        }
+    }
+
+    fn on_event(utterance: *mut AnyObject, event: SpeechEvent) {
+        let done = matches!(event, SpeechEvent::Finished | SpeechEvent::Cancelled);
+        let (Some(app), Some(id)) = (APP.get(), utterance_id(utterance, done)) else {
+            return;
+        };
+        if done {
+            ended(app, &id, event);
+        } else {
+            emit(app, &id, event, None);
+        }
+    }
+
+    macro_rules! delegate_method {
+        ($name:ident, $event:expr) => {
+            extern "C-unwind" fn $name(
+                _this: *mut AnyObject,
+                _cmd: Sel,
+                _synthesizer: *mut AnyObject,
+                utterance: *mut AnyObject,
+            ) {
+                on_event(utterance, $event);
+            }
+        };
+    }
+    delegate_method!(did_start, SpeechEvent::Started);
+    delegate_method!(did_finish, SpeechEvent::Finished);
+    delegate_method!(did_cancel, SpeechEvent::Cancelled);
+    delegate_method!(did_pause, SpeechEvent::Paused);
+    delegate_method!(did_continue, SpeechEvent::Resumed);
+
+    /// A delegate class reporting the synthesizer's events.
+    unsafe fn delegate_class() -> Result<*mut AnyClass, String> {
+        type EventFn = extern "C-unwind" fn(*mut AnyObject, Sel, *mut AnyObject, *mut AnyObject);
+        let superclass = class!(NSObject) as *const AnyClass;
+        let class =
+            objc2::ffi::objc_allocateClassPair(superclass, c"NexusSpeechDelegate".as_ptr(), 0);
+        if class.is_null() {
+            return Err("Failed to create the speech delegate".to_string());
+        }
+        let events: [(Sel, EventFn); 5] = [
+            (sel!(speechSynthesizer:didStartSpeechUtterance:), did_start),
+            (
+                sel!(speechSynthesizer:didFinishSpeechUtterance:),
+                did_finish,
+            ),
+            (
+                sel!(speechSynthesizer:didCancelSpeechUtterance:),
+                did_cancel,
+            ),
+            (sel!(speechSynthesizer:didPauseSpeechUtterance:), did_pause),
+            (
+                sel!(speechSynthesizer:didContinueSpeechUtterance:),
+                did_continue,
+            ),
+        ];
+        for (sel, method) in events {
+            let imp: Imp = std::mem::transmute(method);
+            objc2::ffi::class_addMethod(class, sel, imp, c"v@:@@".as_ptr());
+        }
+        let imp: Imp = std::mem::transmute(
+            will_speak_range
+                as extern "C-unwind" fn(
+                    *mut AnyObject,
+                    Sel,
+                    *mut AnyObject,
+                    NSRange,
+                    *mut AnyObject,
+                ),
+        );
+        objc2::ffi::class_addMethod(
+            class,
+            sel!(speechSynthesizer:willSpeakRangeOfSpeechString:utterance:),
+            imp,
+            c"v@:@{_NSRange=QQ}@".as_ptr(),
+        );
+        objc2::ffi::objc_registerClassPair(class);
+        Ok(class)
+    }
+
+    /// Must run on the main thread.
+    unsafe fn synthesizer() -> Result<*mut AnyObject, String> {
+        if let Some(synthesizer) = SYNTHESIZER.get() {
+            return Ok(*synthesizer as *mut AnyObject);
+        }
+        let class = &*delegate_class()?;
+        let delegate: *mut AnyObject = msg_send![class, new];
+        let synthesizer: *mut AnyObject = msg_send![class!(AVSpeechSynthesizer), new];
+        if synthesizer.is_null() {
+            return Err("Speech synthesis is unavailable".to_string());
+        }
+        let _: () = msg_send![synthesizer, setDelegate: delegate];
+        let _ = SYNTHESIZER.set(synthesizer as usize);
+        Ok(synthesizer)
+    }
+
+    unsafe fn ns_string(value: &str) -> *mut AnyObject {
+        let value = CString::new(value.replace('\0', " ")).unwrap_or_default();
+        msg_send![class!(NSString), stringWithUTF8String: value.as_ptr()]
+    }
+
+    unsafe fn rust_string(value: *mut AnyObject) -> Option<String> {
+        if value.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![value, UTF8String];
+        (!utf8.is_null()).then(|| CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+
+    /// Controls the shared synthesizer, on the main thread.
+    pub struct Handle {
+        app: AppHandle,
+    }
+
+    impl Handle {
+        pub fn control(&self, control: Control) -> Result<(), String> {
+            self.app
+                .run_on_main_thread(move || unsafe {
+                    let Ok(synthesizer) = synthesizer() else {
+                        return;
+                    };
+                    let _: bool = match control {
+                        Control::Pause => {
+                            msg_send![synthesizer, pauseSpeakingAtBoundary: IMMEDIATE]
+                        }
+                        Control::Resume => msg_send![synthesizer, continueSpeaking],
+                        Control::Stop => msg_send![synthesizer, stopSpeakingAtBoundary: IMMEDIATE],
+                    };
+                })
+                .map_err(|e| format!("Failed to control speech: {e}"))
+        }
+    }
+
+    pub fn speak(
+        app: AppHandle,
+        utterance_id: String,
+        text: String,
+        voice_id: Option<String>,
+        rate: f32,
+    ) -> Result<Handle, String> {
+        let _ = APP.set(app.clone());
+        let (started_tx, started) = std::sync::mpsc::channel::<Result<(), String>>();
+        app.run_on_main_thread(move || unsafe {
+            let result = synthesizer().map(|synthesizer| {
+                let utterance: *mut AnyObject = msg_send![
+                    class!(AVSpeechUtterance),
+                    speechUtteranceWithString: ns_string(&text)
+                ];
+                let _: () = msg_send![utterance, setRate: (DEFAULT_RATE * rate).clamp(0.0, 1.0)];
+                if let Some(voice_id) = voice_id {
+                    let voice: *mut AnyObject = msg_send![
+                        class!(AVSpeechSynthesisVoice),
+                        voiceWithIdentifier: ns_string(&voice_id)
+                    ];
+                    if !voice.is_null() {
+                        let _: () = msg_send![utterance, setVoice: voice];
+                    }
+                }
+                if let Ok(mut utterances) = UTTERANCES.lock() {
+                    utterances
+                        .get_or_insert_with(HashMap::new)
+                        .insert(utterance as usize, utterance_id);
+                }
+                let _: () = msg_send![synthesizer, speakUtterance: utterance];
+            });
+            let _ = started_tx.send(result);
+        })
+        .map_err(|e| format!("Failed to start speech: {e}"))?;
+        started
+            .recv()
+            .map_err(|_| "Speech did not start".to_string())??;
+        Ok(Handle { app })
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        unsafe {
+            let list: *mut AnyObject = msg_send![class!(AVSpeechSynthesisVoice), speechVoices];
+            if list.is_null() {
+                return Ok(Vec::new());
+            }
+            let count: usize = msg_send![list, count];
+            let mut voices = Vec::with_capacity(count);
+            for i in 0..count {
+                let voice: *mut AnyObject = msg_send![list, objectAtIndex: i];
+                let identifier: *mut AnyObject = msg_send![voice, identifier];
+                let name: *mut AnyObject = msg_send![voice, name];
+                let language: *mut AnyObject = msg_send![voice, language];
+                let Some(id) = rust_string(identifier) else {
+                    continue;
+                };
+                voices.push(Voice {
+                    name: rust_string(name).unwrap_or_else(|| id.clone()),
+                    id,
+                    language: rust_string(language),
+                });
+            }
+            Ok(voices)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use super::{emit, ended, words, Control, SpeechEvent, Voice};
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tauri::AppHandle;
+
+    /// Lets an autospawned speech-dispatcher open its socket
+    const SPAWN_DELAY: Duration = Duration::from_millis(500);
+
+    /// speech-dispatcher's socket: `SPEECHD_ADDRESS` or the per-user
+    /// default.
+    fn socket_path() -> Result<PathBuf, String> {
+        if let Ok(address) = std::env::var("SPEECHD_ADDRESS") {
+            if let Some(path) = address.strip_prefix("unix_socket:") {
+                return Ok(PathBuf::from(path));
+            }
+            return Err(format!("Unsupported SPEECHD_ADDRESS: {address}"));
+        }
+        let runtime_dir =
+            std::env::var_os("XDG_RUNTIME_DIR").ok_or("XDG_RUNTIME_DIR is not set")?;
+        Ok(PathBuf::from(runtime_dir).join("speech-dispatcher/speechd.sock"))
+    }
+
+    /// A connection to speech-dispatcher, started if it isn't running.
+    fn connect() -> Result<UnixStream, String> {
+        let path = socket_path()?;
+        if let Ok(stream) = UnixStream::connect(&path) {
+            return Ok(stream);
+        }
+        std::process::Command::new("speech-dispatcher")
+            .arg("--spawn")
+            .status()
+            .map_err(|e| format!("speech-dispatcher is not available: {e}"))?;
+        std::thread::sleep(SPAWN_DELAY);
+        UnixStream::connect(&path).map_err(|e| format!("Failed to reach speech-dispatcher: {e}"))
+    }
+
+    /// One SSIP reply: its code and lines.
+    fn reply(reader: &mut impl BufRead) -> Result<(u16, Vec<String>), String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader
+                .read_line(&mut line)
+                .map_err(|e| format!("speech-dispatcher failed: {e}"))?
+                == 0
+            {
+                return Err("speech-dispatcher closed the connection".to_string());
+            }
+            let line = line.trim_end();
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| format!("Unexpected reply from speech-dispatcher: {line}"))?;
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+            if last {
+                return Ok((code, lines));
+            }
+        }
+    }
+
+    /// Sends a command and fails unless it succeeded.
+    fn command(
+        stream: &mut UnixStream,
+        reader: &mut impl BufRead,
+        command: &str,
+    ) -> Result<Vec<String>, String> {
+        stream
+            .write_all(format!("{command}\r\n").as_bytes())
+            .map_err(|e| format!("speech-dispatcher failed: {e}"))?;
+        let (code, lines) = reply(reader)?;
+        if !(200..300).contains(&code) {
+            return Err(format!(
+                "speech-dispatcher refused {}: {}",
+                command.split(' ').next().unwrap_or_default(),
+                lines.last().map(String::as_str).unwrap_or_default()
+            ));
+        }
+        Ok(lines)
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    /// The text as SSML with a mark before every word, named by its index.
+    fn ssml(text: &str, words: &[(std::ops::Range<usize>, u32, u32)]) -> String {
+        let mut ssml = String::from("<speak>");
+        let mut end = 0;
+        for (i, (range, _, _)) in words.iter().enumerate() {
+            ssml.push_str(&escape(&text[end..range.start]));
+            ssml.push_str(&format!("<mark name=\"{i}\"/>"));
+            ssml.push_str(&escape(&text[range.clone()]));
+            end = range.end;
+        }
+        ssml.push_str(&escape(&text[end..]));
+        ssml.push_str("</speak>");
+        ssml
+    }
+
+    /// speech-dispatcher rates run from -100 to 100.
+    fn ssip_rate(rate: f32) -> i32 {
+        ((rate.log2() * 100.0).round() as i32).clamp(-100, 100)
+    }
+
+    /// The connection of one utterance, for controlling it.
+    pub struct Handle(Mutex<UnixStream>);
+
+    impl Handle {
+        pub fn control(&self, control: Control) -> Result<(), String> {
+            let command = match control {
+                Control::Pause => "PAUSE self",
+                Control::Resume => "RESUME self",
+                Control::Stop => "CANCEL self",
+            };
+            // The reply is read, and skipped, with the events
+            self.0
+                .lock()
+                .map_err(|e| e.to_string())?
+                .write_all(format!("{command}\r\n").as_bytes())
+                .map_err(|e| format!("Failed to control speech: {e}"))
+        }
+    }
+
+    pub fn speak(
+        app: AppHandle,
+        utterance_id: String,
+        text: String,
+        voice: Option<String>,
+        rate: f32,
+    ) -> Result<Handle, String> {
+        let mut stream = connect()?;
+        let mut reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("speech-dispatcher failed: {e}"))?,
+        );
+        let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+        command(
+            &mut stream,
+            &mut reader,
+            &format!("SET self CLIENT_NAME {user}:nexus:tts"),
+        )?;
+        command(&mut stream, &mut reader, "SET self SSML_MODE on")?;
+        command(&mut stream, &mut reader, "SET self NOTIFICATION all on")?;
+        command(
+            &mut stream,
+            &mut reader,
+            &format!("SET self RATE {}", ssip_rate(rate)),
+        )?;
+        if let Some(voice) = voice.filter(|voice| !voice.contains(['\r', '\n'])) {
+            command(
+                &mut stream,
+                &mut reader,
+                &format!("SET self SYNTHESIS_VOICE {voice}"),
+            )?;
+        }
+
+        let words = words(&text);
+        command(&mut stream, &mut reader, "SPEAK")?;
+        // Lines starting with a dot are escaped by doubling it
+        let body: String = ssml(&text, &words)
+            .lines()
+            .map(|line| match line.starts_with('.') {
+                true => format!(".{line}\r\n"),
+                false => format!("{line}\r\n"),
+            })
+            .collect();
+        command(&mut stream, &mut reader, &format!("{body}."))?;
+
+        let handle = Handle(Mutex::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("speech-dispatcher failed: {e}"))?,
+        ));
+        std::thread::spawn(move || {
+            let event = loop {
+                let (code, lines) = match reply(&mut reader) {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        log::warn!("Lost speech-dispatcher: {e}");
+                        break SpeechEvent::Failed;
+                    }
+                };
+                match code {
+                    // `msg_id`, `client_id`, then the mark's name
+                    700 => {
+                        let word = lines
+                            .get(2)
+                            .and_then(|mark| mark.parse::<usize>().ok())
+                            .and_then(|i| words.get(i));
+                        if let Some((_, offset, length)) = word {
+                            emit(
+                                &app,
+                                &utterance_id,
+                                SpeechEvent::Word,
+                                Some((*offset, *length)),
+                            );
+                        }
+                    }
+                    701 => emit(&app, &utterance_id, SpeechEvent::Started, None),
+                    702 => break SpeechEvent::Finished,
+                    703 => break SpeechEvent::Cancelled,
+                    704 => emit(&app, &utterance_id, SpeechEvent::Paused, None),
+                    705 => emit(&app, &utterance_id, SpeechEvent::Resumed, None),
+                    // Replies to pause, resume and cancel
+                    _ => {}
+                }
+            };
+            ended(&app, &utterance_id, event);
+            let _ = stream.write_all(b"QUIT\r\n");
+        });
+        Ok(handle)
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let mut stream = connect()?;
+        let mut reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| format!("speech-dispatcher failed: {e}"))?,
+        );
+        let lines = command(&mut stream, &mut reader, "LIST SYNTHESIS_VOICES")?;
+        let _ = stream.write_all(b"QUIT\r\n");
+        // `name\tlanguage\tvariant`, then the status line
+        Ok(lines
+            .iter()
+            .take(lines.len().saturating_sub(1))
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let name = fields.next()?.trim().to_string();
+                let language = fields
+                    .next()
+                    .map(|language| language.trim().to_string())
+                    .filter(|language| !language.is_empty() && language != "none");
+                Some(Voice {
+                    id: name.clone(),
+                    name,
+                    language,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod native {
+    use super::{Control, Voice};
+    use tauri::AppHandle;
+
+    pub struct Handle;
+
+    impl Handle {
+        pub fn control(&self, _control: Control) -> Result<(), String> {
+            Err("Text to speech is not supported on this platform".to_string())
+        }
+    }
+
+    pub fn speak(
+        _app: AppHandle,
+        _utterance_id: String,
+        _text: String,
+        _voice: Option<String>,
+        _rate: f32,
+    ) -> Result<Handle, String> {
+        Err("Text to speech is not supported on this platform".to_string())
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,1336 @@
+// Conversation Store
+// ==================
+//
+// Conversations kept in the database, one row per message, so the app can
+// page through thousands of chats and append to one without serializing
+// the whole history on every change. Attachment payloads are split out of
+// message content into a content-addressed `attachments` table and
+// restored on read; a payload shared by several messages is stored once
+// and dropped with the last message referring to it. Message text is also
+// indexed for full-text search (see `search`). This is the default backend
+// of `persistence::Storage`; commands reach conversations through that.
+//
+// Conversations are organized here too, so the organization travels with
+// the database instead of living in the frontend's localStorage: any number
+// of tags, a pinned flag that lists a conversation first, and at most one
+// folder each. Folders nest; deleting one deletes its subfolders and leaves
+// their conversations in no folder.
+//
+// Editing a message keeps the content it replaces as a numbered revision,
+// so any earlier version can be restored. Deleting a message only hides it
+// from reads, listings and search until `purge_deleted` removes it for
+// good, which makes destructive edits undoable.
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::conversations::{ChatMessage, Conversation, MessageUsage};
+use crate::hash::sha256_hex;
+use crate::time::now_ms;
+
+/// Key that replaces `data` in stored content parts
+const ATTACHMENT_REF_KEY: &str = "attachmentRef";
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+const MAX_TITLE_CHARS: usize = 500;
+const MAX_FOLDER_NAME_CHARS: usize = 200;
+const MAX_FOLDER_DEPTH: usize = 10;
+const MAX_TAGS: usize = 20;
+const MAX_TAG_CHARS: usize = 50;
+const MAX_MESSAGE_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub model_id: String,
+    pub message_count: u64,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+    /// Milliseconds since the UNIX epoch
+    pub updated_at: u64,
+    pub pinned: bool,
+    /// Read-only until unlocked
+    pub locked: bool,
+    pub folder_id: Option<String>,
+    /// Sorted alphabetically
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationFilter {
+    /// Only conversations in this folder (not its subfolders); an empty
+    /// string for conversations in no folder
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    /// Only conversations with this tag, ignoring case
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub pinned: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationPage {
+    /// Pinned first, then most recently updated first
+    pub conversations: Vec<ConversationSummary>,
+    /// Zero-based page number
+    pub page: u32,
+    pub page_size: u32,
+    /// Conversations across all pages
+    pub total: u64,
+}
+
+pub fn new_id() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Failed to generate conversation id: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn to_json(value: &impl Serialize) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(column: usize, json: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, e.into())
+    })
+}
+
+/// Moves inline attachment data in `content` to the attachments table,
+/// leaving a reference in its place. Returns the hashes stored.
+pub(crate) fn store_attachments(
+    tx: &Transaction,
+    content: &mut Value,
+) -> rusqlite::Result<Vec<String>> {
+    let mut hashes = Vec::new();
+    let Value::Array(parts) = content else {
+        return Ok(hashes);
+    };
+    for part in parts.iter_mut() {
+        if part.get("type").and_then(Value::as_str) == Some("text") {
+            continue;
+        }
+        let Some(object) = part.as_object_mut() else {
+            continue;
+        };
+        let Some(Value::String(data)) = object.remove("data") else {
+            continue;
+        };
+
+        let hash = sha256_hex(data.as_bytes());
+        tx.execute(
+            "INSERT INTO attachments (hash, mime_type, size, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (hash) DO NOTHING",
+            params![
+                hash,
+                object.get("mimeType").and_then(Value::as_str),
+                data.len() as i64,
+                data
+            ],
+        )?;
+        object.insert(ATTACHMENT_REF_KEY.to_string(), Value::String(hash.clone()));
+        hashes.push(hash);
+    }
+    Ok(hashes)
+}
+
+pub(crate) fn link_attachments(
+    tx: &Transaction,
+    conversation_id: &str,
+    message_id: &str,
+    hashes: Vec<String>,
+) -> rusqlite::Result<()> {
+    let mut link = tx.prepare(
+        "INSERT OR IGNORE INTO message_attachments (conversation_id, message_id, hash)
+         VALUES (?1, ?2, ?3)",
+    )?;
+    for hash in hashes {
+        link.execute(params![conversation_id, message_id, hash])?;
+    }
+    Ok(())
+}
+
+/// Drops attachments no message refers to any more.
+pub(crate) fn delete_orphaned_attachments(tx: &Transaction) -> rusqlite::Result<usize> {
+    tx.execute(
+        "DELETE FROM attachments WHERE NOT EXISTS
+             (SELECT 1 FROM message_attachments m WHERE m.hash = attachments.hash)",
+        [],
+    )
+}
+
+/// Inserts `message` at the end of its conversation inside `tx`, moving
+/// inline attachment data to the attachments table.
+fn insert_message(
+    tx: &Transaction,
+    conversation_id: &str,
+    message: &ChatMessage,
+) -> rusqlite::Result<()> {
+    let mut content = message.content.clone();
+    let hashes = store_attachments(tx, &mut content)?;
+    let usage = message.usage.as_ref();
+
+    tx.execute(
+        "INSERT INTO messages
+             (conversation_id, id, position, role, content, timestamp,
+              tool_calls, tool_call_id, tool_name,
+              provider, model, input_tokens, output_tokens, cost)
+         VALUES (?1, ?2,
+                 (SELECT COALESCE(MAX(position) + 1, 0) FROM messages WHERE conversation_id = ?1),
+                 ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            conversation_id,
+            message.id,
+            message.role,
+            to_json(&content)?,
+            message.timestamp as i64,
+            message.tool_calls.as_ref().map(to_json).transpose()?,
+            message.tool_call_id,
+            message.tool_name,
+            usage.and_then(|u| u.provider.as_deref()),
+            usage.and_then(|u| u.model.as_deref()),
+            usage.map(|u| u.input_tokens as i64),
+            usage.map(|u| u.output_tokens as i64),
+            usage.and_then(crate::usage::cost)
+        ],
+    )?;
+
+    crate::search::index_message(tx, conversation_id, &message.id, &message.text())?;
+    link_attachments(tx, conversation_id, &message.id, hashes)
+}
+
+/// Restores inline attachment data in stored content.
+pub(crate) fn rehydrate(conn: &Connection, content: &mut Value) -> rusqlite::Result<()> {
+    let Value::Array(parts) = content else {
+        return Ok(());
+    };
+    let mut select = conn.prepare_cached("SELECT data FROM attachments WHERE hash = ?1")?;
+    for part in parts.iter_mut() {
+        let Some(object) = part.as_object_mut() else {
+            continue;
+        };
+        let Some(Value::String(hash)) = object.remove(ATTACHMENT_REF_KEY) else {
+            continue;
+        };
+        match select
+            .query_row(params![hash], |row| row.get::<_, String>(0))
+            .optional()?
+        {
+            Some(data) => {
+                object.insert("data".to_string(), Value::String(data));
+            }
+            None => {
+                log::warn!("Missing stored attachment {hash}");
+                object.insert(ATTACHMENT_REF_KEY.to_string(), Value::String(hash));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_conversation(
+    conn: &Connection,
+    id: &str,
+) -> rusqlite::Result<Option<Conversation>> {
+    let Some(mut conversation) = conn
+        .query_row(
+            "SELECT id, title, model_id, system_prompt, created_at, updated_at
+             FROM conversations WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(Conversation {
+                    id: row.get("id")?,
+                    title: row.get("title")?,
+                    messages: Vec::new(),
+                    model_id: row.get("model_id")?,
+                    system_prompt: row.get("system_prompt")?,
+                    created_at: row.get::<_, i64>("created_at")? as u64,
+                    updated_at: row.get::<_, i64>("updated_at")? as u64,
+                })
+            },
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content, timestamp, tool_calls, tool_call_id, tool_name,
+                provider, model, input_tokens, output_tokens, cost
+         FROM messages m WHERE conversation_id = ?1 AND NOT EXISTS
+             (SELECT 1 FROM message_deletions d
+              WHERE d.conversation_id = m.conversation_id AND d.message_id = m.id)
+         ORDER BY position",
+    )?;
+    let mut rows = stmt.query(params![id])?;
+    while let Some(row) = rows.next()? {
+        let mut content: Value = from_json(2, &row.get::<_, String>(2)?)?;
+        rehydrate(conn, &mut content)?;
+        conversation.messages.push(ChatMessage {
+            id: row.get(0)?,
+            role: row.get(1)?,
+            content,
+            timestamp: row.get::<_, i64>(3)? as u64,
+            tool_calls: row
+                .get::<_, Option<String>>(4)?
+                .map(|json| from_json(4, &json))
+                .transpose()?,
+            tool_call_id: row.get(5)?,
+            tool_name: row.get(6)?,
+            usage: match (
+                row.get::<_, Option<i64>>(9)?,
+                row.get::<_, Option<i64>>(10)?,
+            ) {
+                (Some(input_tokens), Some(output_tokens)) => Some(MessageUsage {
+                    provider: row.get(7)?,
+                    model: row.get(8)?,
+                    input_tokens: input_tokens as u64,
+                    output_tokens: output_tokens as u64,
+                    cost: row.get(11)?,
+                }),
+                _ => None,
+            },
+        });
+    }
+    Ok(Some(conversation))
+}
+
+/// A stored conversation with all of its messages.
+pub fn load(app: &AppHandle, id: &str) -> Result<Option<Conversation>, String> {
+    let conn = crate::db::open(app)?;
+    read_conversation(&conn, id).map_err(|e| {
+        log::error!("Failed to read conversation {id}: {e}");
+        format!("Failed to read conversation: {e}")
+    })
+}
+
+/// Ids of every stored conversation.
+pub fn ids(app: &AppHandle) -> Result<Vec<String>, String> {
+    let conn = crate::db::open(app)?;
+    conn.prepare("SELECT id FROM conversations ORDER BY created_at")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .map_err(|e| format!("Failed to list conversations: {e}"))
+}
+
+/// Stores a new conversation with its messages, keeping its timestamps.
+pub fn create(app: &AppHandle, conversation: &Conversation) -> Result<(), String> {
+    let id = &conversation.id;
+    let mut conn = crate::db::open(app)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to create conversation: {e}"))?;
+    if exists(&tx, "conversations", id)
+        .map_err(|e| format!("Failed to create conversation: {e}"))?
+    {
+        return Err(format!("Conversation {id} already exists"));
+    }
+    insert(&tx, conversation)
+        .and_then(|_| tx.commit())
+        .map_err(|e| {
+            log::error!("Failed to create conversation {id}: {e}");
+            format!("Failed to create conversation: {e}")
+        })
+}
+
+/// Creates an empty conversation. The frontend may pass its own `id`;
+/// otherwise one is generated.
+#[tauri::command]
+pub async fn create_conversation(
+    app: AppHandle,
+    id: Option<String>,
+    title: String,
+    model_id: String,
+    system_prompt: Option<String>,
+) -> Result<ConversationSummary, String> {
+    let id = match id {
+        Some(id) => {
+            crate::validate_filename(&id)?;
+            id
+        }
+        None => new_id()?,
+    };
+    crate::validate_string_input(&title, MAX_TITLE_CHARS, "Conversation title")?;
+    crate::validate_string_input(&model_id, 256, "Model id")?;
+
+    let created_at = now_ms();
+    let conversation = Conversation {
+        id: id.clone(),
+        title: title.clone(),
+        messages: Vec::new(),
+        model_id: model_id.clone(),
+        system_prompt: system_prompt.unwrap_or_default(),
+        created_at,
+        updated_at: created_at,
+    };
+    crate::persistence::current(&app).create(&app, &conversation)?;
+
+    log::info!("Created conversation {id}");
+    Ok(ConversationSummary {
+        id,
+        title,
+        model_id,
+        message_count: 0,
+        created_at,
+        updated_at: created_at,
+        pinned: false,
+        locked: false,
+        folder_id: None,
+        tags: Vec::new(),
+    })
+}
+
+/// Inserts a whole conversation, keeping its timestamps, inside `tx`.
+pub fn insert(tx: &Transaction, conversation: &Conversation) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO conversations (id, title, model_id, system_prompt, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            conversation.id,
+            conversation.title,
+            conversation.model_id,
+            conversation.system_prompt,
+            conversation.created_at as i64,
+            conversation.updated_at as i64
+        ],
+    )?;
+    for message in &conversation.messages {
+        insert_message(tx, &conversation.id, message)?;
+    }
+    Ok(())
+}
+
+/// Adds messages to the end of a conversation in one transaction.
+pub fn append(
+    app: &AppHandle,
+    conversation_id: &str,
+    messages: &[ChatMessage],
+) -> Result<(), String> {
+    for message in messages {
+        crate::validate_string_input(&message.id, 256, "Message id")?;
+    }
+    append_to(&mut crate::db::open(app)?, conversation_id, messages)
+}
+
+fn append_to(
+    conn: &mut Connection,
+    conversation_id: &str,
+    messages: &[ChatMessage],
+) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to append message: {e}"))?;
+    crate::archive::check_writable(&tx, conversation_id)?;
+    let exists: Option<i64> = tx
+        .query_row(
+            "SELECT 1 FROM conversations WHERE id = ?1",
+            params![conversation_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to append message: {e}"))?;
+    if exists.is_none() {
+        return Err(format!("Conversation {conversation_id} not found"));
+    }
+    for message in messages {
+        let duplicate: Option<i64> = tx
+            .query_row(
+                "SELECT 1 FROM messages WHERE conversation_id = ?1 AND id = ?2",
+                params![conversation_id, message.id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to append message: {e}"))?;
+        if duplicate.is_some() {
+            return Err(format!(
+                "Message {} already exists in conversation {conversation_id}",
+                message.id
+            ));
+        }
+        insert_message(&tx, conversation_id, message).map_err(|e| {
+            log::error!("Failed to append message to {conversation_id}: {e}");
+            format!("Failed to append message: {e}")
+        })?;
+    }
+
+    tx.execute(
+        "UPDATE conversations SET updated_at = ?2 WHERE id = ?1",
+        params![conversation_id, now_ms() as i64],
+    )
+    .and_then(|_| tx.commit())
+    .map_err(|e| {
+        log::error!("Failed to append message to {conversation_id}: {e}");
+        format!("Failed to append message: {e}")
+    })
+}
+
+/// Adds a message to the end of a conversation. An archived conversation
+/// is unarchived first if the `unarchive_on_reply` preference is set, and
+/// refused otherwise.
+#[tauri::command]
+pub async fn append_message(
+    app: AppHandle,
+    conversation_id: String,
+    message: ChatMessage,
+) -> Result<(), String> {
+    let mut conn = crate::db::open(&app)?;
+    if crate::archive::is_archived(&conn, &conversation_id)?
+        && crate::read_preferences(&app)?.unarchive_on_reply
+    {
+        crate::archive::unarchive(&mut conn, &app, &conversation_id)?;
+    }
+    crate::persistence::current(&app).append(&app, &conversation_id, &[message])
+}
+
+/// A conversation with all of its messages, or `None` if it does not exist.
+#[tauri::command]
+pub async fn get_conversation(app: AppHandle, id: String) -> Result<Option<Conversation>, String> {
+    crate::persistence::current(&app).load(&app, &id)
+}
+
+/// One page of conversation summaries, pinned first and then most recently
+/// updated first, optionally filtered by folder, tag or pinned flag.
+#[tauri::command]
+pub async fn list_conversations(
+    app: AppHandle,
+    page: u32,
+    page_size: Option<u32>,
+    filter: Option<ConversationFilter>,
+) -> Result<ConversationPage, String> {
+    let page_size = page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let filter = filter.unwrap_or_default();
+    const FROM: &str = "FROM conversations c
+         LEFT JOIN conversation_folders f ON f.conversation_id = c.id
+         LEFT JOIN conversation_pins p ON p.conversation_id = c.id
+         WHERE (?1 IS NULL OR (?1 = '' AND f.folder_id IS NULL) OR f.folder_id = ?1)
+           AND (?2 IS NULL OR EXISTS (SELECT 1 FROM conversation_tags t
+                                      WHERE t.conversation_id = c.id AND t.tag = ?2))
+           AND (?3 IS NULL OR (p.conversation_id IS NOT NULL) = ?3)";
+
+    let conn = crate::db::open(&app)?;
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) {FROM}"),
+            params![filter.folder_id, filter.tag, filter.pinned],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to list conversations: {e}"))?;
+    let conversations = conn
+        .prepare(&format!(
+            "SELECT c.id, c.title, c.model_id, c.created_at, c.updated_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id
+                     AND NOT EXISTS (SELECT 1 FROM message_deletions d
+                                     WHERE d.conversation_id = m.conversation_id
+                                       AND d.message_id = m.id)) AS message_count,
+                    p.conversation_id IS NOT NULL AS pinned,
+                    EXISTS (SELECT 1 FROM conversation_locks l
+                            WHERE l.conversation_id = c.id) AS locked,
+                    f.folder_id,
+                    (SELECT json_group_array(tag) FROM
+                        (SELECT tag FROM conversation_tags t
+                         WHERE t.conversation_id = c.id ORDER BY tag)) AS tags
+             {FROM}
+             ORDER BY pinned DESC, c.updated_at DESC, c.id
+             LIMIT ?4 OFFSET ?5"
+        ))
+        .and_then(|mut stmt| {
+            stmt.query_map(
+                params![
+                    filter.folder_id,
+                    filter.tag,
+                    filter.pinned,
+                    page_size,
+                    page as i64 * page_size as i64
+                ],
+                |row| {
+                    Ok(ConversationSummary {
+                        id: row.get("id")?,
+                        title: row.get("title")?,
+                        model_id: row.get("model_id")?,
+                        message_count: row.get::<_, i64>("message_count")? as u64,
+                        created_at: row.get::<_, i64>("created_at")? as u64,
+                        updated_at: row.get::<_, i64>("updated_at")? as u64,
+                        pinned: row.get("pinned")?,
+                        locked: row.get("locked")?,
+                        folder_id: row.get("folder_id")?,
+                        tags: from_json(9, &row.get::<_, String>("tags")?)?,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to list conversations: {e}"))?;
+
+    Ok(ConversationPage {
+        conversations,
+        page,
+        page_size,
+        total: total as u64,
+    })
+}
+
+/// Ids and titles of pinned conversations, most recently pinned first.
+pub fn pinned(app: &AppHandle, limit: u32) -> Result<Vec<(String, String)>, String> {
+    let conn = crate::db::open(app)?;
+    let mut select = conn
+        .prepare(
+            "SELECT c.id, c.title FROM conversation_pins p
+             JOIN conversations c ON c.id = p.conversation_id
+             ORDER BY p.pinned_at DESC, c.id LIMIT ?1",
+        )
+        .map_err(|e| format!("Failed to list pinned conversations: {e}"))?;
+    let pinned = select
+        .query_map(params![limit], |row| Ok((row.get(0)?, row.get(1)?)))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to list pinned conversations: {e}"))?;
+    Ok(pinned)
+}
+
+/// Deletes a conversation with its messages, and any attachment no other
+/// message refers to. Returns whether the conversation existed.
+pub fn delete(app: &AppHandle, id: &str) -> Result<bool, String> {
+    delete_from(&mut crate::db::open(app)?, id)
+}
+
+fn delete_from(conn: &mut Connection, id: &str) -> Result<bool, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to delete conversation: {e}"))?;
+    crate::archive::check_writable(&tx, id)?;
+    let deleted = tx
+        .execute("DELETE FROM conversations WHERE id = ?1", params![id])
+        .and_then(|deleted| {
+            delete_orphaned_attachments(&tx)?;
+            tx.commit()?;
+            Ok(deleted)
+        })
+        .map_err(|e| {
+            log::error!("Failed to delete conversation {id}: {e}");
+            format!("Failed to delete conversation: {e}")
+        })?;
+    Ok(deleted > 0)
+}
+
+/// Deletes a conversation with its messages. Returns whether the
+/// conversation existed.
+#[tauri::command]
+pub async fn delete_conversation(app: AppHandle, id: String) -> Result<bool, String> {
+    let deleted = crate::persistence::current(&app).delete(&app, &id)?;
+    if deleted {
+        log::info!("Deleted conversation {id}");
+        crate::quick_actions::refresh(&app);
+    }
+    Ok(deleted)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    /// `None` for top-level folders
+    pub parent_id: Option<String>,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    /// Conversations with the tag
+    pub count: u64,
+}
+
+/// Whether a row with `id` exists in `table`, `conversations` or `folders`.
+fn exists(conn: &Connection, table: &str, id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        &format!("SELECT 1 FROM {table} WHERE id = ?1"),
+        params![id],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+}
+
+fn require_conversation(conn: &Connection, id: &str) -> Result<(), String> {
+    match exists(conn, "conversations", id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Conversation {id} not found")),
+        Err(e) => Err(format!("Failed to read conversation: {e}")),
+    }
+}
+
+/// Checks that `parent_id` can hold `folder_id` (or a new folder): it
+/// exists, is not the folder or inside it, and is not nested too deeply.
+fn check_parent(conn: &Connection, folder_id: Option<&str>, parent_id: &str) -> Result<(), String> {
+    let mut depth = 1;
+    let mut current = Some(parent_id.to_string());
+    while let Some(id) = current {
+        if Some(id.as_str()) == folder_id {
+            return Err("A folder cannot be moved into itself".to_string());
+        }
+        depth += 1;
+        if depth > MAX_FOLDER_DEPTH {
+            return Err(format!("Folders nest at most {MAX_FOLDER_DEPTH} deep"));
+        }
+        current = conn
+            .query_row(
+                "SELECT parent_id FROM folders WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read folders: {e}"))?
+            .ok_or_else(|| format!("Folder {id} not found"))?;
+    }
+    Ok(())
+}
+
+fn validate_folder_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Folder name is required".to_string());
+    }
+    crate::validate_string_input(name, MAX_FOLDER_NAME_CHARS, "Folder name")?;
+    Ok(name.to_string())
+}
+
+/// Every folder; the frontend builds the tree from `parent_id`.
+#[tauri::command]
+pub async fn list_folders(app: AppHandle) -> Result<Vec<Folder>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.prepare("SELECT id, name, parent_id, created_at FROM folders ORDER BY name COLLATE NOCASE")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(Folder {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    parent_id: row.get(2)?,
+                    created_at: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to list folders: {e}"))
+}
+
+#[tauri::command]
+pub async fn create_folder(
+    app: AppHandle,
+    name: String,
+    parent_id: Option<String>,
+) -> Result<Folder, String> {
+    let name = validate_folder_name(&name)?;
+    let conn = crate::db::open(&app)?;
+    if let Some(parent_id) = &parent_id {
+        check_parent(&conn, None, parent_id)?;
+    }
+
+    let folder = Folder {
+        id: new_id()?,
+        name,
+        parent_id,
+        created_at: now_ms(),
+    };
+    conn.execute(
+        "INSERT INTO folders (id, name, parent_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            folder.id,
+            folder.name,
+            folder.parent_id,
+            folder.created_at as i64
+        ],
+    )
+    .map_err(|e| {
+        log::error!("Failed to create folder: {e}");
+        format!("Failed to create folder: {e}")
+    })?;
+
+    log::info!("Created folder {}", folder.id);
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn rename_folder(app: AppHandle, id: String, name: String) -> Result<(), String> {
+    let name = validate_folder_name(&name)?;
+    let conn = crate::db::open(&app)?;
+    let updated = conn
+        .execute(
+            "UPDATE folders SET name = ?2 WHERE id = ?1",
+            params![id, name],
+        )
+        .map_err(|e| format!("Failed to rename folder: {e}"))?;
+    if updated == 0 {
+        return Err(format!("Folder {id} not found"));
+    }
+    Ok(())
+}
+
+/// Moves a folder, with its contents, into another folder or to the top
+/// level when `parent_id` is `None`.
+#[tauri::command]
+pub async fn move_folder(
+    app: AppHandle,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    if !exists(&conn, "folders", &id).map_err(|e| format!("Failed to read folders: {e}"))? {
+        return Err(format!("Folder {id} not found"));
+    }
+    if let Some(parent_id) = &parent_id {
+        check_parent(&conn, Some(&id), parent_id)?;
+    }
+    conn.execute(
+        "UPDATE folders SET parent_id = ?2 WHERE id = ?1",
+        params![id, parent_id],
+    )
+    .map_err(|e| format!("Failed to move folder: {e}"))?;
+    Ok(())
+}
+
+/// Deletes a folder and its subfolders. Their conversations are kept, in
+/// no folder. Returns whether the folder existed.
+#[tauri::command]
+pub async fn delete_folder(app: AppHandle, id: String) -> Result<bool, String> {
+    let conn = crate::db::open(&app)?;
+    let deleted = conn
+        .execute("DELETE FROM folders WHERE id = ?1", params![id])
+        .map_err(|e| {
+            log::error!("Failed to delete folder {id}: {e}");
+            format!("Failed to delete folder: {e}")
+        })?;
+    if deleted > 0 {
+        log::info!("Deleted folder {id}");
+    }
+    Ok(deleted > 0)
+}
+
+/// Puts a conversation into a folder, or into none when `folder_id` is
+/// `None`.
+#[tauri::command]
+pub async fn move_conversation(
+    app: AppHandle,
+    conversation_id: String,
+    folder_id: Option<String>,
+) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    require_conversation(&conn, &conversation_id)?;
+    let result = match &folder_id {
+        Some(folder_id) => {
+            if !exists(&conn, "folders", folder_id)
+                .map_err(|e| format!("Failed to read folders: {e}"))?
+            {
+                return Err(format!("Folder {folder_id} not found"));
+            }
+            conn.execute(
+                "INSERT INTO conversation_folders (conversation_id, folder_id) VALUES (?1, ?2)
+                 ON CONFLICT (conversation_id) DO UPDATE SET folder_id = excluded.folder_id",
+                params![conversation_id, folder_id],
+            )
+        }
+        None => conn.execute(
+            "DELETE FROM conversation_folders WHERE conversation_id = ?1",
+            params![conversation_id],
+        ),
+    };
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Failed to move conversation: {e}"))
+}
+
+#[tauri::command]
+pub async fn set_conversation_pinned(
+    app: AppHandle,
+    conversation_id: String,
+    pinned: bool,
+) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    require_conversation(&conn, &conversation_id)?;
+    let result = if pinned {
+        conn.execute(
+            "INSERT INTO conversation_pins (conversation_id, pinned_at) VALUES (?1, ?2)
+             ON CONFLICT (conversation_id) DO NOTHING",
+            params![conversation_id, now_ms() as i64],
+        )
+    } else {
+        conn.execute(
+            "DELETE FROM conversation_pins WHERE conversation_id = ?1",
+            params![conversation_id],
+        )
+    };
+    result.map_err(|e| format!("Failed to pin conversation: {e}"))?;
+    crate::quick_actions::refresh(&app);
+    Ok(())
+}
+
+/// Replaces the tags of a conversation. Tags are trimmed and deduplicated
+/// ignoring case; returns them as stored, sorted.
+#[tauri::command]
+pub async fn set_conversation_tags(
+    app: AppHandle,
+    conversation_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in &tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        crate::validate_string_input(tag, MAX_TAG_CHARS, "Tag")?;
+        if !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("Too many tags (max {MAX_TAGS})"));
+    }
+    normalized.sort_by_key(|tag| tag.to_lowercase());
+
+    let mut conn = crate::db::open(&app)?;
+    require_conversation(&conn, &conversation_id)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to tag conversation: {e}"))?;
+    tx.execute(
+        "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+        params![conversation_id],
+    )
+    .and_then(|_| {
+        let mut insert =
+            tx.prepare("INSERT INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)")?;
+        for tag in &normalized {
+            insert.execute(params![conversation_id, tag])?;
+        }
+        Ok(())
+    })
+    .and_then(|_| tx.commit())
+    .map_err(|e| {
+        log::error!("Failed to tag conversation {conversation_id}: {e}");
+        format!("Failed to tag conversation: {e}")
+    })?;
+
+    Ok(normalized)
+}
+
+/// Every tag in use, most used first.
+#[tauri::command]
+pub async fn list_tags(app: AppHandle) -> Result<Vec<TagCount>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.prepare(
+        "SELECT MIN(tag), COUNT(*) AS count FROM conversation_tags
+         GROUP BY tag ORDER BY count DESC, tag",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                count: row.get::<_, i64>(1)? as u64,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .map_err(|e| format!("Failed to list tags: {e}"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRevision {
+    /// Starts at 1 for the message's original content
+    pub revision: u32,
+    pub content: Value,
+    /// Milliseconds since the UNIX epoch at which an edit replaced it
+    pub replaced_at: u64,
+}
+
+/// Current content of a message that has not been deleted.
+fn message_content(
+    tx: &Transaction,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<String, String> {
+    tx.query_row(
+        "SELECT content FROM messages m
+         WHERE conversation_id = ?1 AND id = ?2 AND NOT EXISTS
+             (SELECT 1 FROM message_deletions d
+              WHERE d.conversation_id = m.conversation_id AND d.message_id = m.id)",
+        params![conversation_id, message_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read message: {e}"))?
+    .ok_or_else(|| format!("Message {message_id} not found in conversation {conversation_id}"))
+}
+
+/// Replaces a message's content inside `tx`, keeping the old content as
+/// the next revision. Returns that revision's number.
+fn replace_content(
+    tx: &Transaction,
+    conversation_id: &str,
+    message_id: &str,
+    content: &Value,
+) -> rusqlite::Result<u32> {
+    let revision: u32 = tx.query_row(
+        "INSERT INTO message_revisions
+             (conversation_id, message_id, revision, content, replaced_at)
+         SELECT conversation_id, id,
+                (SELECT COALESCE(MAX(revision), 0) + 1 FROM message_revisions
+                 WHERE conversation_id = ?1 AND message_id = ?2),
+                content, ?3
+         FROM messages WHERE conversation_id = ?1 AND id = ?2
+         RETURNING revision",
+        params![conversation_id, message_id, now_ms() as i64],
+        |row| row.get(0),
+    )?;
+
+    let mut stored = content.clone();
+    let hashes = store_attachments(tx, &mut stored)?;
+    tx.execute(
+        "UPDATE messages SET content = ?3 WHERE conversation_id = ?1 AND id = ?2",
+        params![conversation_id, message_id, to_json(&stored)?],
+    )?;
+    // Links to attachments the old content used are kept, since its
+    // revision still refers to them
+    link_attachments(tx, conversation_id, message_id, hashes)?;
+
+    let text = crate::conversations::content_text(content);
+    crate::search::index_message(tx, conversation_id, message_id, &text)?;
+    tx.execute(
+        "UPDATE conversations SET updated_at = ?2 WHERE id = ?1",
+        params![conversation_id, now_ms() as i64],
+    )?;
+    Ok(revision)
+}
+
+/// Replaces the content of a message. The previous content is kept as a
+/// revision; returns its number.
+#[tauri::command]
+pub async fn edit_message(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+    content: Value,
+) -> Result<u32, String> {
+    let size = serde_json::to_string(&content)
+        .map_err(|e| e.to_string())?
+        .len();
+    if size > MAX_MESSAGE_BYTES {
+        return Err(format!(
+            "Message is too large (max {} MB)",
+            MAX_MESSAGE_BYTES / 1024 / 1024
+        ));
+    }
+
+    let revision = edit_in(
+        &mut crate::db::open(&app)?,
+        &conversation_id,
+        &message_id,
+        &content,
+    )?;
+    log::info!("Edited message {message_id} in {conversation_id} (revision {revision})");
+    Ok(revision)
+}
+
+fn edit_in(
+    conn: &mut Connection,
+    conversation_id: &str,
+    message_id: &str,
+    content: &Value,
+) -> Result<u32, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to edit message: {e}"))?;
+    crate::archive::check_writable(&tx, conversation_id)?;
+    message_content(&tx, conversation_id, message_id)?;
+    replace_content(&tx, conversation_id, message_id, content)
+        .and_then(|revision| tx.commit().map(|_| revision))
+        .map_err(|e| {
+            log::error!("Failed to edit message {message_id}: {e}");
+            format!("Failed to edit message: {e}")
+        })
+}
+
+/// Earlier versions of a message, newest first.
+#[tauri::command]
+pub async fn get_message_history(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+) -> Result<Vec<MessageRevision>, String> {
+    let conn = crate::db::open(&app)?;
+    let mut revisions = conn
+        .prepare(
+            "SELECT revision, content, replaced_at FROM message_revisions
+             WHERE conversation_id = ?1 AND message_id = ?2
+             ORDER BY revision DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![conversation_id, message_id], |row| {
+                Ok(MessageRevision {
+                    revision: row.get(0)?,
+                    content: from_json(1, &row.get::<_, String>(1)?)?,
+                    replaced_at: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to read message history: {e}"))?;
+
+    for revision in &mut revisions {
+        rehydrate(&conn, &mut revision.content)
+            .map_err(|e| format!("Failed to read message history: {e}"))?;
+    }
+    Ok(revisions)
+}
+
+/// Makes an earlier revision the message's content again. This is an edit
+/// itself, so the content it replaces becomes a new revision.
+#[tauri::command]
+pub async fn restore_message_revision(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+    revision: u32,
+) -> Result<u32, String> {
+    let replaced = restore_revision_in(
+        &mut crate::db::open(&app)?,
+        &conversation_id,
+        &message_id,
+        revision,
+    )?;
+    log::info!("Restored revision {revision} of message {message_id} in {conversation_id}");
+    Ok(replaced)
+}
+
+fn restore_revision_in(
+    conn: &mut Connection,
+    conversation_id: &str,
+    message_id: &str,
+    revision: u32,
+) -> Result<u32, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to restore revision: {e}"))?;
+    crate::archive::check_writable(&tx, conversation_id)?;
+    message_content(&tx, conversation_id, message_id)?;
+    // Stored content keeps its attachment references, which stay linked
+    let content: Value = tx
+        .query_row(
+            "SELECT content FROM message_revisions
+             WHERE conversation_id = ?1 AND message_id = ?2 AND revision = ?3",
+            params![conversation_id, message_id, revision],
+            |row| from_json(0, &row.get::<_, String>(0)?),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to restore revision: {e}"))?
+        .ok_or_else(|| format!("Revision {revision} of message {message_id} not found"))?;
+
+    replace_content(&tx, conversation_id, message_id, &content)
+        .and_then(|replaced| tx.commit().map(|_| replaced))
+        .map_err(|e| {
+            log::error!("Failed to restore revision {revision} of {message_id}: {e}");
+            format!("Failed to restore revision: {e}")
+        })
+}
+
+/// Hides a message until it is restored or purged. Returns whether it was
+/// visible before.
+#[tauri::command]
+pub async fn delete_message(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+) -> Result<bool, String> {
+    let deleted = delete_message_in(&crate::db::open(&app)?, &conversation_id, &message_id)?;
+    if deleted {
+        log::info!("Deleted message {message_id} in {conversation_id}");
+    }
+    Ok(deleted)
+}
+
+fn delete_message_in(
+    conn: &Connection,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<bool, String> {
+    crate::archive::check_writable(conn, conversation_id)?;
+    let deleted = conn
+        .execute(
+            "INSERT INTO message_deletions (conversation_id, message_id, deleted_at)
+             SELECT conversation_id, id, ?3 FROM messages
+             WHERE conversation_id = ?1 AND id = ?2
+             ON CONFLICT (conversation_id, message_id) DO NOTHING",
+            params![conversation_id, message_id, now_ms() as i64],
+        )
+        .map_err(|e| {
+            log::error!("Failed to delete message {message_id}: {e}");
+            format!("Failed to delete message: {e}")
+        })?;
+    Ok(deleted > 0)
+}
+
+/// Brings back a deleted message that has not been purged yet. Returns
+/// whether it was deleted.
+#[tauri::command]
+pub async fn restore_message(
+    app: AppHandle,
+    conversation_id: String,
+    message_id: String,
+) -> Result<bool, String> {
+    let restored = restore_message_in(&crate::db::open(&app)?, &conversation_id, &message_id)?;
+    if restored {
+        log::info!("Restored message {message_id} in {conversation_id}");
+    }
+    Ok(restored)
+}
+
+fn restore_message_in(
+    conn: &Connection,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<bool, String> {
+    crate::archive::check_writable(conn, conversation_id)?;
+    let restored = conn
+        .execute(
+            "DELETE FROM message_deletions WHERE conversation_id = ?1 AND message_id = ?2",
+            params![conversation_id, message_id],
+        )
+        .map_err(|e| format!("Failed to restore message: {e}"))?;
+    Ok(restored > 0)
+}
+
+/// Permanently removes messages deleted before `older_than` (milliseconds
+/// since the UNIX epoch), with their revisions and any attachment no other
+/// message refers to. Returns the number of messages removed.
+#[tauri::command]
+pub async fn purge_deleted(app: AppHandle, older_than: u64) -> Result<u64, String> {
+    let mut conn = crate::db::open(&app)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to purge deleted messages: {e}"))?;
+    let purged = tx
+        .execute(
+            "DELETE FROM messages WHERE (conversation_id, id) IN
+                 (SELECT conversation_id, message_id FROM message_deletions
+                  WHERE deleted_at < ?1 AND conversation_id NOT IN
+                      (SELECT conversation_id FROM conversation_locks))",
+            params![older_than as i64],
+        )
+        .and_then(|purged| {
+            delete_orphaned_attachments(&tx)?;
+            tx.commit()?;
+            Ok(purged)
+        })
+        .map_err(|e| {
+            log::error!("Failed to purge deleted messages: {e}");
+            format!("Failed to purge deleted messages: {e}")
+        })?;
+
+    log::info!("Purged {purged} deleted messages");
+    Ok(purged as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::CONVERSATION_READ_ONLY;
+    use serde_json::json;
+
+    fn message(id: &str) -> ChatMessage {
+        ChatMessage {
+            id: id.to_string(),
+            role: "user".to_string(),
+            content: json!("Hello"),
+            timestamp: 1,
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            usage: None,
+        }
+    }
+
+    /// A database holding conversation `c1` with message `m1`, which has
+    /// one earlier revision and is deleted.
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+        crate::db::migrate(&mut conn).unwrap();
+        let tx = conn.transaction().unwrap();
+        insert(
+            &tx,
+            &Conversation {
+                id: "c1".to_string(),
+                title: "Test".to_string(),
+                messages: vec![message("m1")],
+                model_id: "model".to_string(),
+                system_prompt: String::new(),
+                created_at: 1,
+                updated_at: 1,
+            },
+        )
+        .unwrap();
+        tx.commit().unwrap();
+        edit_in(&mut conn, "c1", "m1", &json!("Edited")).unwrap();
+        assert!(delete_message_in(&conn, "c1", "m1").unwrap());
+        conn
+    }
+
+    fn assert_read_only<T: std::fmt::Debug>(result: Result<T, String>) {
+        match result {
+            Err(e) => assert!(e.starts_with(CONVERSATION_READ_ONLY), "{e}"),
+            Ok(value) => panic!("Change was allowed: {value:?}"),
+        }
+    }
+
+    fn assert_every_change_refused(conn: &mut Connection) {
+        assert_read_only(append_to(conn, "c1", &[message("m2")]));
+        assert_read_only(edit_in(conn, "c1", "m1", &json!("Again")));
+        assert_read_only(restore_revision_in(conn, "c1", "m1", 1));
+        assert_read_only(delete_message_in(conn, "c1", "m1"));
+        assert_read_only(restore_message_in(conn, "c1", "m1"));
+        assert_read_only(delete_from(conn, "c1"));
+    }
+
+    #[test]
+    fn archived_conversations_refuse_every_change() {
+        let mut conn = database();
+        conn.execute_batch(
+            "INSERT INTO conversation_archive (id, title, model_id, created_at, updated_at,
+                 archived_at, message_count, tags, preview, size, data)
+             VALUES ('c1', 'Test', 'model', 1, 1, 1, 1, '[]', '', 0, x'')",
+        )
+        .unwrap();
+        assert_every_change_refused(&mut conn);
+    }
+
+    #[test]
+    fn locked_conversations_refuse_every_change() {
+        let mut conn = database();
+        conn.execute_batch(
+            "INSERT INTO conversation_locks (conversation_id, locked_at) VALUES ('c1', 1)",
+        )
+        .unwrap();
+        assert_every_change_refused(&mut conn);
+    }
+
+    #[test]
+    fn writable_conversations_accept_changes() {
+        let mut conn = database();
+        assert!(restore_message_in(&conn, "c1", "m1").unwrap());
+        append_to(&mut conn, "c1", &[message("m2")]).unwrap();
+        assert_eq!(restore_revision_in(&mut conn, "c1", "m1", 1).unwrap(), 2);
+        assert!(delete_from(&mut conn, "c1").unwrap());
+    }
+}
@@ -0,0 +1,363 @@
+// Conversation Tasks
+// ==================
+//
+// Lightweight task tracking grounded in chat content. `extract_tasks` asks a
+// model for the action items in a conversation as JSON, and stores them in
+// the `tasks` table with their due dates. A scheduler job sends a native
+// notification for every open task whose due date has passed, once.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::conversations;
+use crate::llm::{self, LlmMessage, LlmRequest, ProviderId};
+use crate::middleware;
+use crate::time::now_ms;
+
+/// Transcript characters sent for extraction; older messages are dropped first
+const MAX_TRANSCRIPT_CHARS: usize = 60_000;
+const EXTRACTION_MAX_TOKENS: u32 = 2048;
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_DETAILS_CHARS: usize = 2000;
+/// Reminders are sent for date-only due dates at this local hour
+const DEFAULT_DUE_HOUR: u32 = 9;
+
+const EXTRACTION_PROMPT: &str = "You extract action items from a conversation. \
+List only concrete tasks the user committed to or was asked to do, not \
+suggestions that were dismissed or tasks already done. Reply with JSON only, \
+no prose and no code fences, in exactly this shape:
+{\"tasks\": [{\"title\": \"short imperative summary\", \"details\": \"optional context or null\", \"due\": \"ISO 8601 date or date-time, or null\"}]}
+Resolve relative dates such as \"tomorrow\" or \"next Friday\" against the \
+current date. Reply with {\"tasks\": []} if there are none.";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: i64,
+    pub conversation_id: String,
+    pub title: String,
+    pub details: Option<String>,
+    /// Milliseconds since the UNIX epoch
+    pub due_at: Option<i64>,
+    pub completed: bool,
+    /// A reminder notification was sent
+    pub reminded: bool,
+    /// Milliseconds since the UNIX epoch
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedTasks {
+    #[serde(default)]
+    tasks: Vec<ExtractedTask>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedTask {
+    title: String,
+    #[serde(default)]
+    details: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+}
+
+/// The conversation as plain text, newest messages kept when it is too long.
+fn transcript(conversation: &conversations::Conversation) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut total = 0;
+    for message in conversation.messages.iter().rev() {
+        if message.role != "user" && message.role != "assistant" {
+            continue;
+        }
+        let text = message.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+        let line = format!("{}: {}", message.role, text.trim());
+        total += line.len();
+        if total > MAX_TRANSCRIPT_CHARS && !lines.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n\n")
+}
+
+/// Parses the model's reply, tolerating code fences and surrounding prose.
+fn parse_extraction(text: &str) -> Result<Vec<ExtractedTask>, String> {
+    let start = text.find('{');
+    let end = text.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => return Err("Model did not return JSON".to_string()),
+    };
+    serde_json::from_str::<ExtractedTasks>(json)
+        .map(|extracted| extracted.tasks)
+        .map_err(|e| {
+            log::warn!("Unparseable task extraction: {json}");
+            format!("Failed to parse extracted tasks: {e}")
+        })
+}
+
+/// Milliseconds since the UNIX epoch for an ISO 8601 date or date-time.
+/// Times without an offset are local; dates alone get `DEFAULT_DUE_HOUR`.
+fn parse_due(due: &str) -> Option<i64> {
+    let due = due.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(due) {
+        return Some(at.timestamp_millis());
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(due, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(DEFAULT_DUE_HOUR, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|at| at.timestamp_millis())
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    text.trim().chars().take(max).collect()
+}
+
+fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get("id")?,
+        conversation_id: row.get("conversation_id")?,
+        title: row.get("title")?,
+        details: row.get("details")?,
+        due_at: row.get("due_at")?,
+        completed: row.get("completed")?,
+        reminded: row.get("reminded")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Stores extracted tasks, skipping titles the conversation already has.
+fn store(
+    conn: &mut Connection,
+    conversation_id: &str,
+    extracted: Vec<ExtractedTask>,
+) -> Result<Vec<Task>, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to store tasks: {e}"))?;
+    let mut stored = Vec::new();
+    {
+        let mut exists = tx
+            .prepare("SELECT 1 FROM tasks WHERE conversation_id = ?1 AND lower(title) = lower(?2)")
+            .map_err(|e| format!("Failed to store tasks: {e}"))?;
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO tasks (conversation_id, title, details, due_at, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(|e| format!("Failed to store tasks: {e}"))?;
+
+        for task in extracted {
+            let title = truncate(&task.title, MAX_TITLE_CHARS);
+            if title.is_empty() {
+                continue;
+            }
+            let duplicate = exists
+                .query_row(params![conversation_id, title], |_| Ok(()))
+                .optional()
+                .map_err(|e| format!("Failed to store tasks: {e}"))?
+                .is_some();
+            if duplicate {
+                continue;
+            }
+
+            let details = task
+                .details
+                .map(|d| truncate(&d, MAX_DETAILS_CHARS))
+                .filter(|d| !d.is_empty());
+            let due_at = task.due.as_deref().and_then(parse_due);
+            let created_at = now_ms() as i64;
+            insert
+                .execute(params![conversation_id, title, details, due_at, created_at])
+                .map_err(|e| format!("Failed to store tasks: {e}"))?;
+            stored.push(Task {
+                id: tx.last_insert_rowid(),
+                conversation_id: conversation_id.to_string(),
+                title,
+                details,
+                due_at,
+                completed: false,
+                reminded: false,
+                created_at,
+            });
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to store tasks: {e}"))?;
+    Ok(stored)
+}
+
+/// Sends a notification for every open task that is due, once. Runs as a
+/// scheduled job.
+pub fn send_due_reminders(app: &AppHandle) -> Result<(), String> {
+    let conn = crate::db::open(app)?;
+    let due: Vec<Task> = conn
+        .prepare(
+            "SELECT * FROM tasks
+             WHERE completed = 0 AND reminded = 0 AND due_at IS NOT NULL AND due_at <= ?1",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![now_ms() as i64], row_to_task)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| format!("Failed to query due tasks: {e}"))?;
+
+    for task in due {
+        log::info!("Sending reminder for task {}", task.id);
+        #[cfg(not(mobile))]
+        {
+            use tauri_plugin_notification::NotificationExt;
+
+            let mut notification = app.notification().builder().title(&task.title);
+            if let Some(details) = &task.details {
+                notification = notification.body(details);
+            }
+            if let Err(e) = notification.show() {
+                log::error!("Failed to show reminder for task {}: {e}", task.id);
+                continue;
+            }
+            crate::indicators::notification_shown(app);
+        }
+        conn.execute(
+            "UPDATE tasks SET reminded = 1 WHERE id = ?1",
+            params![task.id],
+        )
+        .map_err(|e| format!("Failed to update task: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Extracts the action items of a conversation with the given model and
+/// stores them as tasks. Returns the newly added tasks.
+#[tauri::command]
+pub async fn extract_tasks(
+    app: AppHandle,
+    conversation_id: String,
+    provider: ProviderId,
+    model: String,
+) -> Result<Vec<Task>, String> {
+    crate::validate_filename(&conversation_id)?;
+    let api_key = crate::api_keys::require(provider)?;
+    let conversation = conversations::load_all(&app)?
+        .into_iter()
+        .find(|c| c.id == conversation_id)
+        .ok_or_else(|| format!("Conversation {conversation_id} not found"))?;
+
+    let transcript = transcript(&conversation);
+    if transcript.is_empty() {
+        return Ok(Vec::new());
+    }
+    log::info!("Extracting tasks from conversation {conversation_id} with {model}");
+
+    let request = LlmRequest {
+        provider,
+        model,
+        api_key,
+        system_prompt: Some(format!(
+            "{EXTRACTION_PROMPT}\n\nThe current date and time is {}.",
+            Local::now().format("%A, %Y-%m-%d %H:%M %:z")
+        )),
+        messages: vec![LlmMessage {
+            role: "user".to_string(),
+            content: transcript,
+        }],
+        max_tokens: Some(EXTRACTION_MAX_TOKENS),
+    };
+    let outcome = middleware::stream_chat(&app, &llm::http_client(), &request, true, |_| {})
+        .await
+        .map_err(|e| {
+            log::error!("Task extraction failed: {e}");
+            e
+        })?;
+
+    let extracted = parse_extraction(&outcome.text)?;
+    let mut conn = crate::db::open(&app)?;
+    let stored = store(&mut conn, &conversation_id, extracted)?;
+    log::info!(
+        "Added {} tasks from conversation {conversation_id}",
+        stored.len()
+    );
+    Ok(stored)
+}
+
+/// Tasks, soonest due first, then newest. Completed tasks are left out
+/// unless `include_completed` is set.
+#[tauri::command]
+pub async fn list_tasks(
+    app: AppHandle,
+    conversation_id: Option<String>,
+    include_completed: Option<bool>,
+) -> Result<Vec<Task>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.prepare(
+        "SELECT * FROM tasks
+         WHERE (?1 IS NULL OR conversation_id = ?1) AND (?2 OR completed = 0)
+         ORDER BY due_at IS NULL, due_at, created_at DESC",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(
+            params![conversation_id, include_completed.unwrap_or(false)],
+            row_to_task,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .map_err(|e| format!("Failed to list tasks: {e}"))
+}
+
+/// Updates a task. Setting a due date re-arms its reminder; `clear_due`
+/// removes it.
+#[tauri::command]
+pub async fn update_task(
+    app: AppHandle,
+    id: i64,
+    completed: Option<bool>,
+    due_at: Option<i64>,
+    clear_due: Option<bool>,
+) -> Result<Task, String> {
+    let conn = crate::db::open(&app)?;
+    if let Some(completed) = completed {
+        conn.execute(
+            "UPDATE tasks SET completed = ?2 WHERE id = ?1",
+            params![id, completed],
+        )
+        .map_err(|e| format!("Failed to update task: {e}"))?;
+    }
+    if due_at.is_some() || clear_due.unwrap_or(false) {
+        conn.execute(
+            "UPDATE tasks SET due_at = ?2, reminded = 0 WHERE id = ?1",
+            params![id, due_at],
+        )
+        .map_err(|e| format!("Failed to update task: {e}"))?;
+    }
+
+    conn.query_row(
+        "SELECT * FROM tasks WHERE id = ?1",
+        params![id],
+        row_to_task,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read task: {e}"))?
+    .ok_or_else(|| format!("Task {id} not found"))
+}
+
+#[tauri::command]
+pub async fn delete_task(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = crate::db::open(&app)?;
+    conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete task: {e}"))?;
+    Ok(())
+}
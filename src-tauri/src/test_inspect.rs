@@ -0,0 +1,71 @@
+// Test Inspection
+// ===============
+//
+// `__test_inspect` lets integration and WebDriver end-to-end tests assert on
+// backend state instead of only on what the UI shows: running MCP servers
+// and the spawn queue, the scheduler's jobs, cache sizes, daemon mode and
+// the storage backend. It only answers in debug builds or with the
+// `test-inspect` feature; release builds refuse it.
+//
+// The state is a JSON snapshot taken per call. `state_path` picks part of
+// it with dot-separated keys, with numbers indexing arrays, e.g.
+// `mcp.queue.queued` or `scheduler.jobs.0.runs`; an empty path returns the
+// whole snapshot.
+
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+/// Whether this build answers `__test_inspect`
+const ENABLED: bool = cfg!(any(debug_assertions, feature = "test-inspect"));
+
+fn snapshot(app: &AppHandle) -> Value {
+    let daemon = app.state::<crate::daemon::DaemonState>();
+    json!({
+        "mcp": {
+            "processes": crate::mcp::process_summaries(app),
+            "queue": crate::mcp::queue::queue_status(app),
+        },
+        "scheduler": {
+            "jobs": app.state::<crate::scheduler::SchedulerStatus>().jobs(),
+        },
+        "caches": {
+            "linkPreviews": app.state::<crate::unfurl::LinkPreviewCache>().entry_count(),
+            "llmResponses": app.state::<crate::middleware::LlmGateway>().cached_responses(),
+        },
+        "daemon": {
+            "daemon": daemon.is_daemon(),
+            "startedAt": daemon.started_at(),
+        },
+        "storage": {
+            "backend": crate::persistence::current(app).kind(),
+        },
+    })
+}
+
+fn select(mut value: Value, path: &str) -> Result<Value, String> {
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        let next = match &mut value {
+            Value::Object(map) => map.remove(key),
+            Value::Array(items) => key
+                .parse::<usize>()
+                .ok()
+                .filter(|&index| index < items.len())
+                .map(|index| items.swap_remove(index)),
+            _ => None,
+        };
+        value = next.ok_or_else(|| format!("No state at {path} (missing {key})"))?;
+    }
+    Ok(value)
+}
+
+/// Part of the backend state for tests to assert on; see the module
+/// comment for `state_path`.
+#[tauri::command]
+pub async fn __test_inspect(app: AppHandle, state_path: String) -> Result<Value, String> {
+    if !ENABLED {
+        return Err("State inspection is not available in this build".to_string());
+    }
+    crate::validate_string_input(&state_path, 200, "State path")?;
+    log::debug!("Inspecting state at {state_path:?}");
+    select(snapshot(&app), &state_path)
+}
@@ -0,0 +1,191 @@
+// Timestamps
+// ==========
+//
+// Every timestamp the app persists is UTC, in milliseconds since the UNIX
+// epoch, taken from `now_ms`. Time zones and locales only come in when a
+// timestamp is shown to a person, as in Markdown and HTML exports, through
+// `format_datetime`.
+//
+// Some files used to store seconds. Fields that did are read through
+// `legacy_ms`, which scales values too small to be milliseconds, so old
+// entries load with the right time; they are written back as milliseconds.
+//
+// The time zone for display is the system's, UTC or a fixed offset, and the
+// locale decides the order of day, month and year and the 12 or 24 hour
+// clock. Both come from the `time_display` preference, applied at startup
+// and whenever preferences are saved.
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::RwLock;
+
+/// Timestamps below this are taken to be seconds: as milliseconds it is
+/// March 1973, as seconds the year 5138
+const LEGACY_SECONDS_LIMIT: u64 = 100_000_000_000;
+
+static SETTINGS: RwLock<Option<TimeDisplaySettings>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct TimeDisplaySettings {
+    /// `UTC` or an offset like `+05:30`; `None` uses the system time zone
+    pub time_zone: Option<String>,
+    /// BCP 47 tag like `en-US` or `de`; `None` uses the system locale
+    pub locale: Option<String>,
+}
+
+/// How a locale writes dates and times, as chrono format strings.
+struct LocaleFormat {
+    date: &'static str,
+    time: &'static str,
+}
+
+const MONTH_FIRST: LocaleFormat = LocaleFormat {
+    date: "%m/%d/%Y",
+    time: "%-I:%M %p",
+};
+const DAY_FIRST: LocaleFormat = LocaleFormat {
+    date: "%d/%m/%Y",
+    time: "%H:%M",
+};
+const DAY_FIRST_DOTTED: LocaleFormat = LocaleFormat {
+    date: "%d.%m.%Y",
+    time: "%H:%M",
+};
+const YEAR_FIRST: LocaleFormat = LocaleFormat {
+    date: "%Y-%m-%d",
+    time: "%H:%M",
+};
+const YEAR_FIRST_SLASHED: LocaleFormat = LocaleFormat {
+    date: "%Y/%m/%d",
+    time: "%H:%M",
+};
+
+/// Current time in milliseconds since the UNIX epoch.
+pub fn now_ms() -> u64 {
+    Utc::now().timestamp_millis().max(0) as u64
+}
+
+/// `value` in milliseconds, scaling it up if it was stored in seconds.
+pub fn from_legacy(value: u64) -> u64 {
+    if value != 0 && value < LEGACY_SECONDS_LIMIT {
+        value * 1000
+    } else {
+        value
+    }
+}
+
+/// Deserializes a timestamp that older files stored in seconds.
+pub fn legacy_ms<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    u64::deserialize(deserializer).map(from_legacy)
+}
+
+/// `legacy_ms` for optional fields; needs `#[serde(default)]` as well.
+pub fn legacy_ms_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<u64>::deserialize(deserializer).map(|value| value.map(from_legacy))
+}
+
+/// RFC 3339 in UTC with milliseconds, e.g. `2024-05-01T09:30:00.000Z`.
+pub fn iso(millis: u64) -> String {
+    DateTime::from_timestamp_millis(millis as i64)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+pub fn validate_settings(settings: &TimeDisplaySettings) -> Result<(), String> {
+    if let Some(zone) = &settings.time_zone {
+        parse_offset(zone)
+            .ok_or_else(|| format!("Invalid time zone {zone}: use UTC or an offset like +05:30"))?;
+    }
+    if let Some(locale) = &settings.locale {
+        let valid = !locale.is_empty()
+            && locale.len() <= 35
+            && locale
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid locale: {locale}"));
+        }
+    }
+    Ok(())
+}
+
+/// Makes `settings` the ones timestamps are formatted with.
+pub fn apply_settings(settings: &TimeDisplaySettings) {
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = Some(settings.clone());
+    }
+}
+
+fn settings() -> TimeDisplaySettings {
+    SETTINGS
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// `UTC`, `Z` or `±HH:MM` as an offset east of UTC.
+fn parse_offset(zone: &str) -> Option<FixedOffset> {
+    let zone = zone.trim();
+    if zone.eq_ignore_ascii_case("utc") || zone == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match zone.as_bytes().first()? {
+        b'+' => (1, &zone[1..]),
+        b'-' => (-1, &zone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The system locale from the POSIX environment, if set.
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+fn locale_format(locale: &str) -> &'static LocaleFormat {
+    let locale = locale.split('.').next().unwrap_or(locale).replace('_', "-");
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+    match (language.as_str(), region.as_str()) {
+        ("en", "US" | "PH" | "") | ("fil", _) => &MONTH_FIRST,
+        ("en", "CA") | ("sv" | "lt" | "hu" | "fr", "CA") => &YEAR_FIRST,
+        ("sv" | "lt" | "hu" | "mn", _) => &YEAR_FIRST,
+        ("zh" | "ja" | "ko", _) => &YEAR_FIRST_SLASHED,
+        ("de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "no" | "da" | "tr" | "uk" | "ro", _) => {
+            &DAY_FIRST_DOTTED
+        }
+        _ => &DAY_FIRST,
+    }
+}
+
+/// Date and time for people to read, in the display time zone and locale.
+pub fn format_datetime(millis: u64) -> String {
+    let settings = settings();
+    let locale = settings
+        .locale
+        .or_else(system_locale)
+        .unwrap_or_else(|| "en-US".to_string());
+    let format = locale_format(&locale);
+    let pattern = format!("{} {}", format.date, format.time);
+
+    let Some(utc) = DateTime::from_timestamp_millis(millis as i64) else {
+        return String::new();
+    };
+    match settings.time_zone.as_deref().and_then(parse_offset) {
+        Some(offset) => utc.with_timezone(&offset).format(&pattern).to_string(),
+        None => utc.with_timezone(&Local).format(&pattern).to_string(),
+    }
+}
@@ -0,0 +1,313 @@
+// Fine-tuning Dataset Export
+// ==========================
+//
+// Turns curated conversations into JSON Lines fine-tuning files. Each
+// example is a conversation (or, with `positive_only`, the conversation up
+// to each thumbs-up answer) in the OpenAI chat format or the Anthropic
+// `system` + `messages` format. Consecutive messages from the same role are
+// merged and tool traffic is dropped, since both formats expect strictly
+// alternating user and assistant turns ending with an answer. Secrets and
+// common PII are scrubbed, and examples are split into training and
+// validation sets by a hash of their conversation id, so a conversation
+// never lands in both and re-exports put it in the same set.
+
+use regex::Regex;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tauri::AppHandle;
+
+use crate::conversations::{self, ChatMessage, Conversation};
+
+/// Largest share of examples that may go to the validation set
+const MAX_VALIDATION_FRACTION: f64 = 0.5;
+
+/// Personal data replaced when scrubbing, with its placeholder. Secrets are
+/// handled by the redaction middleware's patterns.
+static PII_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (
+            r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}",
+            "[EMAIL]",
+        ),
+        (r"\b(?:\d[ \-]?){13,19}\b", "[CARD]"),
+        (r"\b\d{3}-\d{2}-\d{4}\b", "[SSN]"),
+        (
+            r"(?:\+\d{1,3}[ .\-]?)?\(?\b\d{3}\)?[ .\-]?\d{3}[ .\-]?\d{4}\b",
+            "[PHONE]",
+        ),
+        (r"\b(?:\d{1,3}\.){3}\d{1,3}\b", "[IP]"),
+    ]
+    .into_iter()
+    .filter_map(|(pattern, placeholder)| Some((Regex::new(pattern).ok()?, placeholder)))
+    .collect()
+});
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingFilter {
+    /// Only these conversations; all when unset
+    #[serde(default)]
+    pub conversation_ids: Option<Vec<String>>,
+    /// Only conversations with one of these models; all when unset
+    #[serde(default)]
+    pub model_ids: Option<Vec<String>>,
+    /// One example per thumbs-up answer instead of one per conversation
+    #[serde(default)]
+    pub positive_only: bool,
+    /// Only conversations updated at or after this time (milliseconds since
+    /// the UNIX epoch)
+    #[serde(default)]
+    pub updated_since: Option<u64>,
+    /// Replace emails, phone numbers, card numbers, IPs and secrets
+    #[serde(default = "default_true")]
+    pub scrub_pii: bool,
+    /// Share of examples written to the validation file, 0 to 0.5
+    #[serde(default)]
+    pub validation_fraction: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrainingFormat {
+    /// `{"messages": [{"role": "system" | "user" | "assistant", "content"}]}`
+    OpenAi,
+    /// `{"system": "...", "messages": [{"role": "user" | "assistant", "content"}]}`
+    Anthropic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingExportReport {
+    pub train_path: String,
+    /// `None` when no validation split was requested
+    pub validation_path: Option<String>,
+    pub train_examples: usize,
+    pub validation_examples: usize,
+    /// Conversations matched by the filter that produced no usable example
+    pub skipped_conversations: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Turn {
+    role: &'static str,
+    content: String,
+}
+
+fn scrub(text: &str) -> String {
+    let text = crate::middleware::redact(text, &[]);
+    PII_PATTERNS
+        .iter()
+        .fold(text, |text, (pattern, placeholder)| {
+            pattern.replace_all(&text, *placeholder).into_owned()
+        })
+}
+
+/// User and assistant turns of `messages`, alternating, starting with the
+/// user and ending with the assistant. Returns `None` if nothing is left.
+fn turns(messages: &[ChatMessage], scrub_pii: bool) -> Option<Vec<Turn>> {
+    let mut turns: Vec<Turn> = Vec::new();
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => "user",
+            "assistant" => "assistant",
+            _ => continue,
+        };
+        let text = message.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+        let content = if scrub_pii { scrub(&text) } else { text };
+        match turns.last_mut() {
+            Some(last) if last.role == role => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&content);
+            }
+            None if role == "assistant" => {}
+            _ => turns.push(Turn { role, content }),
+        }
+    }
+    while turns.last().is_some_and(|t| t.role != "assistant") {
+        turns.pop();
+    }
+    (!turns.is_empty()).then_some(turns)
+}
+
+fn example(format: TrainingFormat, system_prompt: &str, turns: Vec<Turn>) -> serde_json::Value {
+    let system = system_prompt.trim();
+    match format {
+        TrainingFormat::OpenAi => {
+            let mut messages = Vec::with_capacity(turns.len() + 1);
+            if !system.is_empty() {
+                messages.push(Turn {
+                    role: "system",
+                    content: system.to_string(),
+                });
+            }
+            messages.extend(turns);
+            serde_json::json!({ "messages": messages })
+        }
+        TrainingFormat::Anthropic if system.is_empty() => {
+            serde_json::json!({ "messages": turns })
+        }
+        TrainingFormat::Anthropic => serde_json::json!({ "system": system, "messages": turns }),
+    }
+}
+
+/// Whether a conversation goes to the validation set.
+fn is_validation(conversation_id: &str, fraction: f64) -> bool {
+    if fraction <= 0.0 {
+        return false;
+    }
+    let hash = crate::hash::sha256_hex(conversation_id.as_bytes());
+    let bucket = u32::from_str_radix(&hash[..8], 16).unwrap_or(0);
+    (bucket as f64 / u32::MAX as f64) < fraction
+}
+
+/// Ids of thumbs-up messages, per conversation.
+fn positive_messages(app: &AppHandle) -> Result<HashMap<String, HashSet<String>>, String> {
+    let conn = crate::db::open(app)?;
+    let rows = conn
+        .prepare("SELECT conversation_id, message_id FROM feedback WHERE rating = 1")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()
+        })
+        .map_err(|e| format!("Failed to read feedback: {e}"))?;
+
+    let mut positive: HashMap<String, HashSet<String>> = HashMap::new();
+    for (conversation_id, message_id) in rows {
+        positive
+            .entry(conversation_id)
+            .or_default()
+            .insert(message_id);
+    }
+    Ok(positive)
+}
+
+fn matches(filter: &TrainingFilter, conversation: &Conversation) -> bool {
+    filter
+        .conversation_ids
+        .as_ref()
+        .is_none_or(|ids| ids.contains(&conversation.id))
+        && filter
+            .model_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&conversation.model_id))
+        && filter
+            .updated_since
+            .is_none_or(|since| conversation.updated_at >= since)
+}
+
+/// The file validation examples go to: `<stem>.validation.jsonl` next to
+/// the training file.
+fn validation_path(train_path: &Path) -> PathBuf {
+    let stem = train_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "training".to_string());
+    train_path.with_file_name(format!("{stem}.validation.jsonl"))
+}
+
+/// Writes conversations matching `filter` to `path` as a JSON Lines
+/// fine-tuning file in `format`, with validation examples in a sibling
+/// `.validation.jsonl` file when `filter.validation_fraction` is set.
+#[tauri::command]
+pub async fn export_training_data(
+    app: AppHandle,
+    filter: TrainingFilter,
+    format: TrainingFormat,
+    path: String,
+) -> Result<TrainingExportReport, String> {
+    if !(0.0..=MAX_VALIDATION_FRACTION).contains(&filter.validation_fraction) {
+        return Err(format!(
+            "Validation fraction must be between 0 and {MAX_VALIDATION_FRACTION}"
+        ));
+    }
+
+    let positive = if filter.positive_only {
+        positive_messages(&app)?
+    } else {
+        HashMap::new()
+    };
+
+    let mut conversations: Vec<Conversation> = conversations::load_all(&app)?
+        .into_iter()
+        .filter(|c| matches(&filter, c))
+        .collect();
+    conversations.sort_by_key(|c| c.created_at);
+
+    let (mut train, mut validation) = (String::new(), String::new());
+    let (mut train_examples, mut validation_examples, mut skipped) = (0, 0, 0);
+    for conversation in &conversations {
+        // Each example ends at a thumbs-up answer, or is the whole conversation
+        let ends: Vec<usize> = if filter.positive_only {
+            let ids = positive.get(&conversation.id);
+            conversation
+                .messages
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| {
+                    m.role == "assistant" && ids.is_some_and(|ids| ids.contains(&m.id))
+                })
+                .map(|(i, _)| i + 1)
+                .collect()
+        } else {
+            vec![conversation.messages.len()]
+        };
+
+        let system_prompt = if filter.scrub_pii {
+            scrub(&conversation.system_prompt)
+        } else {
+            conversation.system_prompt.clone()
+        };
+        let to_validation = is_validation(&conversation.id, filter.validation_fraction);
+        let mut produced = false;
+        for end in ends {
+            let Some(turns) = turns(&conversation.messages[..end], filter.scrub_pii) else {
+                continue;
+            };
+            let line = serde_json::to_string(&example(format, &system_prompt, turns))
+                .map_err(|e| format!("Failed to serialize training example: {e}"))?;
+            let (out, count) = if to_validation {
+                (&mut validation, &mut validation_examples)
+            } else {
+                (&mut train, &mut train_examples)
+            };
+            out.push_str(&line);
+            out.push('\n');
+            *count += 1;
+            produced = true;
+        }
+        if !produced {
+            skipped += 1;
+        }
+    }
+
+    let train_path = PathBuf::from(&path);
+    crate::export::write_atomic(&train_path, train.as_bytes())?;
+    let validation_path = if filter.validation_fraction > 0.0 {
+        let validation_path = validation_path(&train_path);
+        crate::export::write_atomic(&validation_path, validation.as_bytes())?;
+        Some(validation_path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    log::info!(
+        "Exported {train_examples} training and {validation_examples} validation examples to {path} ({skipped} conversations skipped)"
+    );
+    Ok(TrainingExportReport {
+        train_path: path,
+        validation_path,
+        train_examples,
+        validation_examples,
+        skipped_conversations: skipped,
+    })
+}
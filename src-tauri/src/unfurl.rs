@@ -0,0 +1,361 @@
+// Link Previews
+// =============
+//
+// Fetches OpenGraph / Twitter card metadata for links pasted into a
+// conversation. Everything is fetched from Rust and images are returned as
+// data URLs, so the webview never makes cross-origin requests itself. Only
+// domains listed in `AppPreferences::link_preview_domains` are contacted.
+
+use base64::Engine;
+use regex::Regex;
+use reqwest::{StatusCode, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_CACHE_ENTRIES: usize = 256;
+const MAX_REDIRECTS: usize = 5;
+const MAX_HTML_BYTES: usize = 512 * 1024;
+const MAX_IMAGE_BYTES: usize = 1024 * 1024;
+const MAX_FAVICON_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkPreview {
+    pub url: String,
+    /// URL after following redirects
+    pub final_url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    /// Preview image as a `data:` URL
+    pub image: Option<String>,
+    /// Site favicon as a `data:` URL
+    pub favicon: Option<String>,
+}
+
+#[derive(Default)]
+pub struct LinkPreviewCache {
+    entries: Mutex<HashMap<String, (Instant, LinkPreview)>>,
+}
+
+/// Built per preview, so it follows the current network settings.
+fn http_client() -> Result<reqwest::Client, String> {
+    crate::network::client_builder()
+        // Redirects are followed manually so every hop is checked against the allowlist
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!(
+            "Nexus/",
+            env!("CARGO_PKG_VERSION"),
+            " (link preview)"
+        ))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))
+}
+
+impl LinkPreviewCache {
+    /// Previews held, including expired ones not evicted yet.
+    pub fn entry_count(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    fn get(&self, url: &str) -> Option<LinkPreview> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(url)
+            .filter(|(fetched, _)| fetched.elapsed() < CACHE_TTL)
+            .map(|(_, preview)| preview.clone())
+    }
+
+    fn insert(&self, url: String, preview: LinkPreview) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        entries.retain(|_, (fetched, _)| fetched.elapsed() < CACHE_TTL);
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            // Evict the oldest entry to keep the cache bounded
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (fetched, _))| *fetched)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(url, (Instant::now(), preview));
+    }
+
+    /// GETs a URL, following redirects only while they stay on allowed domains.
+    async fn fetch(&self, url: Url, allowed: &[String]) -> Result<reqwest::Response, String> {
+        let client = http_client()?;
+        let mut current = url;
+
+        for _ in 0..=MAX_REDIRECTS {
+            ensure_allowed(&current, allowed)?;
+
+            let response = client
+                .get(current.clone())
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch {current}: {e}"))?;
+
+            if !response.status().is_redirection() {
+                return Ok(response);
+            }
+
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| format!("Redirect from {current} has no location"))?;
+            current = current
+                .join(location)
+                .map_err(|e| format!("Invalid redirect location: {e}"))?;
+        }
+
+        Err("Too many redirects".to_string())
+    }
+
+    /// Fetches an image and encodes it as a data URL, or `None` if it is
+    /// missing, not an image, or larger than `max_bytes`.
+    async fn fetch_image(&self, url: Url, allowed: &[String], max_bytes: usize) -> Option<String> {
+        let response = self.fetch(url, allowed).await.ok()?;
+        if response.status() != StatusCode::OK {
+            return None;
+        }
+
+        let mime = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(';').next().unwrap_or(v).trim().to_string())?;
+        if !mime.starts_with("image/") {
+            return None;
+        }
+
+        let bytes = read_limited(response, max_bytes).await.ok()??;
+        Some(format!(
+            "data:{mime};base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    }
+}
+
+fn ensure_allowed(url: &Url, allowed: &[String]) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Only http and https links can be previewed".to_string());
+    }
+
+    let host = url
+        .host_str()
+        .ok_or("URL has no host")?
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+
+    let permitted = allowed.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches("*.").to_ascii_lowercase();
+        !domain.is_empty() && (host == domain || host.ends_with(&format!(".{domain}")))
+    });
+
+    if permitted {
+        Ok(())
+    } else {
+        Err(format!("Link previews are not enabled for {host}"))
+    }
+}
+
+/// Reads a response body, returning `None` if it exceeds `max_bytes`.
+async fn read_limited(
+    mut response: reqwest::Response,
+    max_bytes: usize,
+) -> Result<Option<Vec<u8>>, String> {
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > max_bytes)
+    {
+        return Ok(None);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response: {e}"))?
+    {
+        if body.len() + chunk.len() > max_bytes {
+            return Ok(None);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(body))
+}
+
+#[derive(Default)]
+struct PageMetadata {
+    meta: HashMap<String, String>,
+    title: Option<String>,
+    icon: Option<String>,
+}
+
+fn parse_metadata(html: &str) -> PageMetadata {
+    let mut metadata = PageMetadata::default();
+
+    // Only the <head> matters; avoid scanning huge bodies
+    let head = match html.to_ascii_lowercase().find("</head>") {
+        Some(end) => &html[..end],
+        None => html,
+    };
+
+    let (Ok(tag_re), Ok(attr_re), Ok(title_re)) = (
+        Regex::new(r"(?is)<(meta|link)\s([^>]*)>"),
+        Regex::new(r#"(?is)([a-z:_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#),
+        Regex::new(r"(?is)<title[^>]*>(.*?)</title>"),
+    ) else {
+        return metadata;
+    };
+
+    for tag in tag_re.captures_iter(head) {
+        let attrs: HashMap<String, String> = attr_re
+            .captures_iter(&tag[2])
+            .map(|a| {
+                let value = a.get(2).or_else(|| a.get(3)).map_or("", |m| m.as_str());
+                (a[1].to_ascii_lowercase(), decode_entities(value))
+            })
+            .collect();
+
+        if tag[1].eq_ignore_ascii_case("meta") {
+            let key = attrs.get("property").or_else(|| attrs.get("name"));
+            if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+                metadata
+                    .meta
+                    .entry(key.to_ascii_lowercase())
+                    .or_insert_with(|| content.trim().to_string());
+            }
+        } else if let (Some(rel), Some(href)) = (attrs.get("rel"), attrs.get("href")) {
+            let is_icon = rel
+                .split_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("icon"));
+            if is_icon && metadata.icon.is_none() {
+                metadata.icon = Some(href.clone());
+            }
+        }
+    }
+
+    metadata.title = title_re
+        .captures(head)
+        .map(|c| decode_entities(c[1].trim()))
+        .filter(|t| !t.is_empty());
+
+    metadata
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[tauri::command]
+pub async fn unfurl_url(
+    app: AppHandle,
+    cache: State<'_, LinkPreviewCache>,
+    url: String,
+) -> Result<LinkPreview, String> {
+    crate::validate_string_input(&url, 2048, "URL")?;
+
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+
+    // Checked before the cache, so removing a domain stops its cached previews too
+    let allowed = crate::read_preferences(&app)?.link_preview_domains;
+    ensure_allowed(&parsed, &allowed)?;
+
+    if let Some(preview) = cache.get(parsed.as_str()) {
+        let redirect_allowed = Url::parse(&preview.final_url)
+            .is_ok_and(|final_url| ensure_allowed(&final_url, &allowed).is_ok());
+        if redirect_allowed {
+            log::debug!("Link preview cache hit: {parsed}");
+            return Ok(preview);
+        }
+    }
+
+    log::info!("Fetching link preview: {parsed}");
+
+    let response = cache.fetch(parsed.clone(), &allowed).await?;
+    if !response.status().is_success() {
+        return Err(format!("Link returned HTTP {}", response.status()));
+    }
+    let final_url = response.url().clone();
+
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+
+    let metadata = if is_html {
+        // Truncated pages still usually contain the whole <head>
+        let mut body = Vec::new();
+        let mut response = response;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read response: {e}"))?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() >= MAX_HTML_BYTES {
+                break;
+            }
+        }
+        parse_metadata(&String::from_utf8_lossy(&body))
+    } else {
+        PageMetadata::default()
+    };
+
+    let meta = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| metadata.meta.get(*k))
+            .filter(|v| !v.is_empty())
+            .cloned()
+    };
+
+    let image = match meta(&["og:image", "og:image:url", "twitter:image"])
+        .and_then(|src| final_url.join(&src).ok())
+    {
+        Some(src) => cache.fetch_image(src, &allowed, MAX_IMAGE_BYTES).await,
+        None => None,
+    };
+
+    let favicon_url = metadata
+        .icon
+        .as_deref()
+        .and_then(|href| final_url.join(href).ok())
+        .or_else(|| final_url.join("/favicon.ico").ok());
+    let favicon = match favicon_url {
+        Some(src) => cache.fetch_image(src, &allowed, MAX_FAVICON_BYTES).await,
+        None => None,
+    };
+
+    let preview = LinkPreview {
+        url: parsed.to_string(),
+        final_url: final_url.to_string(),
+        title: meta(&["og:title", "twitter:title"]).or(metadata.title.clone()),
+        description: meta(&["og:description", "twitter:description", "description"]),
+        site_name: meta(&["og:site_name"]).or_else(|| final_url.host_str().map(String::from)),
+        image,
+        favicon,
+    };
+
+    cache.insert(parsed.to_string(), preview.clone());
+    log::info!("Link preview ready for {parsed}");
+    Ok(preview)
+}
@@ -0,0 +1,207 @@
+// Usage Statistics
+// ================
+//
+// Token counts recorded with each assistant message (see `MessageUsage`),
+// aggregated in SQL for the usage dashboard so the webview never loads
+// every message to draw a chart. A message's cost is estimated when it is
+// stored, from the price the provider reported or else from `PRICES`, and
+// kept with the message; later price changes do not rewrite history.
+//
+// Stats cover every stored message, including deleted ones that have not
+// been purged yet: their tokens were spent all the same. Days are UTC.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::conversations::MessageUsage;
+
+/// US dollars per million input and output tokens. Looked up by longest
+/// matching prefix of the model id, without any `provider/` prefix, so
+/// dated variants (`gpt-4o-2024-08-06`) share the price of their family.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4-5", 5.0, 25.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-haiku-4-5", 1.0, 5.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gemini-3.0-pro", 2.0, 12.0),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash-lite", 0.1, 0.4),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-5-nano", 0.05, 0.4),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5", 1.25, 10.0),
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("o3-mini", 1.1, 4.4),
+    ("o3", 2.0, 8.0),
+    ("o4-mini", 1.1, 4.4),
+    ("llama-3.3-70b", 0.59, 0.79),
+    ("llama-3.1-8b", 0.05, 0.08),
+    ("llama-3.2-11b", 0.18, 0.18),
+    ("mixtral-8x7b", 0.24, 0.24),
+    ("mistral-large", 2.0, 6.0),
+    ("mistral-medium", 0.4, 2.0),
+    ("mistral-small", 0.1, 0.3),
+    ("codestral", 0.3, 0.9),
+    ("ministral-8b", 0.1, 0.1),
+    ("ministral-3b", 0.04, 0.04),
+];
+
+/// The provider's cost if it reported one, or an estimate from `PRICES`;
+/// `None` for models without a known price.
+pub fn cost(usage: &MessageUsage) -> Option<f64> {
+    if usage.cost.is_some() {
+        return usage.cost;
+    }
+    request_cost(
+        usage.model.as_deref()?,
+        usage.input_tokens,
+        usage.output_tokens,
+    )
+}
+
+/// The estimated cost of a request to `model` from its token usage; `None`
+/// for models without a known price.
+pub fn request_cost(model: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+    let (input, output) = price(model)?;
+    Some((input_tokens as f64 * input + output_tokens as f64 * output) / 1_000_000.0)
+}
+
+/// The estimated cost of `tokens` input tokens to `model`; `None` for
+/// models without a known price.
+pub fn input_cost(model: &str, tokens: u64) -> Option<f64> {
+    let (input, _) = price(model)?;
+    Some(tokens as f64 * input / 1_000_000.0)
+}
+
+fn price(model: &str) -> Option<(f64, f64)> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    PRICES
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, input, output)| (*input, *output))
+}
+
+/// Time span the stats cover, in milliseconds since the UNIX epoch;
+/// either end may be open.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRange {
+    pub from: Option<u64>,
+    /// Exclusive
+    pub to: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroupBy {
+    /// UTC date, `YYYY-MM-DD`
+    Day,
+    Model,
+    Provider,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+    /// The day, model or provider; `unknown` for messages without one
+    pub key: String,
+    pub messages: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated, in US dollars
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    /// Ordered by day, or by tokens for the other groupings
+    pub buckets: Vec<UsageBucket>,
+    pub total: UsageBucket,
+}
+
+/// Message counts, tokens and estimated cost over `range`, grouped by day,
+/// model or provider. User messages count towards the conversation's model.
+#[tauri::command]
+pub async fn get_usage_stats(
+    app: AppHandle,
+    range: Option<UsageRange>,
+    group_by: UsageGroupBy,
+) -> Result<UsageStats, String> {
+    let range = range.unwrap_or_default();
+    if let (Some(from), Some(to)) = (range.from, range.to) {
+        if from > to {
+            return Err("Usage range starts after it ends".to_string());
+        }
+    }
+    let (key, order) = match group_by {
+        UsageGroupBy::Day => ("date(m.timestamp / 1000, 'unixepoch')", "key"),
+        UsageGroupBy::Model => ("COALESCE(m.model, c.model_id)", "tokens DESC, key"),
+        UsageGroupBy::Provider => ("m.provider", "tokens DESC, key"),
+    };
+
+    let conn = crate::db::open(&app)?;
+    let sql = format!(
+        "SELECT COALESCE({key}, 'unknown') AS key, COUNT(*),
+                COALESCE(SUM(m.input_tokens), 0), COALESCE(SUM(m.output_tokens), 0),
+                COALESCE(SUM(m.cost), 0.0),
+                COALESCE(SUM(m.input_tokens), 0) + COALESCE(SUM(m.output_tokens), 0) AS tokens
+         FROM messages m JOIN conversations c ON c.id = m.conversation_id
+         WHERE (?1 IS NULL OR m.timestamp >= ?1) AND (?2 IS NULL OR m.timestamp < ?2)
+         GROUP BY 1 ORDER BY {order}"
+    );
+    let buckets = conn
+        .prepare(&sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(
+                params![range.from.map(|t| t as i64), range.to.map(|t| t as i64)],
+                |row| {
+                    Ok(UsageBucket {
+                        key: row.get(0)?,
+                        messages: row.get::<_, i64>(1)? as u64,
+                        input_tokens: row.get::<_, i64>(2)? as u64,
+                        output_tokens: row.get::<_, i64>(3)? as u64,
+                        cost: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map_err(|e| {
+            log::error!("Failed to compute usage stats: {e}");
+            format!("Failed to compute usage stats: {e}")
+        })?;
+
+    let total = buckets.iter().fold(
+        UsageBucket {
+            key: "total".to_string(),
+            messages: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cost: 0.0,
+        },
+        |mut total, bucket| {
+            total.messages += bucket.messages;
+            total.input_tokens += bucket.input_tokens;
+            total.output_tokens += bucket.output_tokens;
+            total.cost += bucket.cost;
+            total
+        },
+    );
+    Ok(UsageStats { buckets, total })
+}
@@ -0,0 +1,651 @@
+// Watch Folders
+// =============
+//
+// Hands-free document processing: files dropped into a watched "inbox"
+// folder are read, run through the folder's prompt template with its model,
+// and the result is either appended to a designated conversation in the
+// store or written next to the source file as `<name>.summary.md`.
+//
+// Folders are kept in `watch_folders.json` in the app data directory, with
+// their API keys in the keychain under `watchfolder:<id>`; a folder without
+// its own key uses the one stored for its provider. Each enabled
+// folder has a file watcher that triggers a scan once writes settle, and a
+// scheduler job rescans every folder to pick up anything the watcher missed
+// (files dropped while the app was closed, dropped events). The
+// `watch_folder_files` table remembers what was processed, so a file is
+// handled again only if it changes. Files already in a folder when it is
+// added are left alone.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::conversations::ChatMessage;
+use crate::keychain;
+use crate::llm::{self, LlmMessage, LlmRequest, ProviderId};
+use crate::middleware;
+use crate::time::now_ms;
+
+const MAX_WATCH_FOLDERS: usize = 20;
+const MAX_NAME_CHARS: usize = 100;
+const MAX_PROMPT_CHARS: usize = 20_000;
+/// Larger files are skipped rather than truncated mid-document
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+const SUMMARY_MAX_TOKENS: u32 = 4096;
+/// Suffix of the files written next to processed sources
+const SUMMARY_SUFFIX: &str = ".summary.md";
+/// Quiet time after the last change before a folder is scanned, so files
+/// still being copied are not read half-written
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// Placeholders replaced in prompt templates
+const CONTENT_PLACEHOLDER: &str = "{{content}}";
+const FILENAME_PLACEHOLDER: &str = "{{filename}}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFolder {
+    /// Unique id, also used for the keychain entry
+    pub id: String,
+    pub name: String,
+    /// Absolute path of the folder; subfolders are not watched
+    pub path: String,
+    /// Prompt template. `{{filename}}` and `{{content}}` are replaced with
+    /// the file's name and text; without `{{content}}` the text is appended.
+    pub prompt: String,
+    pub provider: ProviderId,
+    pub model: String,
+    /// Lowercase extensions to process, without the dot; all files when empty
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub output: WatchFolderOutput,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum WatchFolderOutput {
+    /// The file and result are appended to a conversation in the store
+    Conversation { conversation_id: String },
+    /// The result is written to `<file name>.summary.md` next to the file
+    Sidecar,
+}
+
+/// A watch folder as listed to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderInfo {
+    #[serde(flatten)]
+    pub folder: WatchFolder,
+    /// An API key is stored in the keychain
+    pub has_api_key: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessedFile {
+    pub path: String,
+    /// Milliseconds since the UNIX epoch
+    pub processed_at: i64,
+    /// `None` for files that were in the folder when it was added
+    pub succeeded: Option<bool>,
+    /// Sidecar path or conversation id the result went to
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderEvent {
+    pub folder_id: String,
+    pub path: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// File watchers of the enabled folders, and the folders being scanned.
+#[derive(Default)]
+pub struct WatchFolders {
+    watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+    scanning: Mutex<HashSet<String>>,
+}
+
+fn api_key_account(id: &str) -> String {
+    format!("watchfolder:{id}")
+}
+
+fn get_watch_folders_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = crate::profiles::data_dir(app)?;
+
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(app_data_dir.join("watch_folders.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<WatchFolder>, String> {
+    let path = get_watch_folders_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read watch folders: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| {
+        log::error!("Failed to parse watch folders: {e}");
+        format!("Failed to parse watch folders: {e}")
+    })
+}
+
+fn store(app: &AppHandle, folders: &[WatchFolder]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(folders)
+        .map_err(|e| format!("Failed to serialize watch folders: {e}"))?;
+    crate::export::write_atomic(&get_watch_folders_path(app)?, &json)
+}
+
+fn validate(folder: &WatchFolder) -> Result<(), String> {
+    crate::validate_filename(&folder.id)?;
+    if folder.name.trim().is_empty() {
+        return Err("Watch folder name is required".to_string());
+    }
+    crate::validate_string_input(&folder.name, MAX_NAME_CHARS, "Watch folder name")?;
+    if folder.prompt.trim().is_empty() {
+        return Err("Prompt template is required".to_string());
+    }
+    crate::validate_string_input(&folder.prompt, MAX_PROMPT_CHARS, "Prompt template")?;
+    crate::validate_string_input(&folder.model, 256, "Model")?;
+    let path = Path::new(&folder.path);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(format!("{} is not an existing folder", folder.path));
+    }
+    if let WatchFolderOutput::Conversation { conversation_id } = &folder.output {
+        crate::validate_filename(conversation_id)?;
+    }
+    Ok(())
+}
+
+/// Modification time (milliseconds) and size, which decide whether a file
+/// changed since it was processed.
+fn fingerprint(path: &Path) -> Option<(i64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some((modified, metadata.len() as i64))
+}
+
+/// Files in the folder this folder's settings say to process.
+fn candidates(folder: &WatchFolder) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(&folder.path)
+        .map_err(|e| format!("Failed to read watch folder {}: {e}", folder.path))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                return false;
+            };
+            // Our own output, hidden files and partial downloads
+            if name.ends_with(SUMMARY_SUFFIX)
+                || name.starts_with('.')
+                || name.ends_with(".tmp")
+                || name.ends_with(".part")
+                || name.ends_with(".crdownload")
+            {
+                return false;
+            }
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            path.is_file()
+                && (folder.extensions.is_empty() || folder.extensions.contains(&extension))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Records the files already in a folder so only new ones are processed.
+fn record_existing(app: &AppHandle, folder: &WatchFolder) -> Result<(), String> {
+    let conn = crate::db::open(app)?;
+    let now = now_ms() as i64;
+    for path in candidates(folder)? {
+        let Some((modified, size)) = fingerprint(&path) else {
+            continue;
+        };
+        conn.execute(
+            "INSERT OR IGNORE INTO watch_folder_files
+                 (folder_id, path, modified_at, size, processed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![folder.id, path.to_string_lossy(), modified, size, now],
+        )
+        .map_err(|e| format!("Failed to record watch folder files: {e}"))?;
+    }
+    Ok(())
+}
+
+/// The prompt sent for a file.
+fn render_prompt(template: &str, file_name: &str, content: &str) -> String {
+    let prompt = template.replace(FILENAME_PLACEHOLDER, file_name);
+    if prompt.contains(CONTENT_PLACEHOLDER) {
+        prompt.replace(CONTENT_PLACEHOLDER, content)
+    } else {
+        format!("{prompt}\n\n{content}")
+    }
+}
+
+fn message_id() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate message id: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Runs one file through the folder's prompt and delivers the result.
+/// Returns where the result went.
+async fn process_file(
+    app: &AppHandle,
+    folder: &WatchFolder,
+    api_key: &str,
+    path: &Path,
+) -> Result<String, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file: {e}"))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!(
+            "File is too large ({size} bytes, max {MAX_FILE_BYTES})"
+        ));
+    }
+    let content = std::fs::read(path)
+        .map_err(|e| format!("Failed to read file: {e}"))
+        .and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|_| "File is not UTF-8 text".to_string())
+        })?;
+    if content.trim().is_empty() {
+        return Err("File is empty".to_string());
+    }
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let request = LlmRequest {
+        provider: folder.provider,
+        model: folder.model.clone(),
+        api_key: api_key.to_string(),
+        system_prompt: None,
+        messages: vec![LlmMessage {
+            role: "user".to_string(),
+            content: render_prompt(&folder.prompt, &file_name, &content),
+        }],
+        max_tokens: Some(SUMMARY_MAX_TOKENS),
+    };
+    let outcome = middleware::stream_chat(app, &llm::http_client(), &request, true, |_| {}).await?;
+    if outcome.text.trim().is_empty() {
+        return Err("The model returned an empty response".to_string());
+    }
+
+    match &folder.output {
+        WatchFolderOutput::Sidecar => {
+            let sidecar = path.with_file_name(format!("{file_name}{SUMMARY_SUFFIX}"));
+            crate::export::write_atomic(&sidecar, outcome.text.as_bytes())?;
+            Ok(sidecar.to_string_lossy().to_string())
+        }
+        WatchFolderOutput::Conversation { conversation_id } => {
+            let timestamp = now_ms();
+            let messages = [
+                ChatMessage {
+                    id: message_id()?,
+                    role: "user".to_string(),
+                    content: serde_json::Value::String(format!(
+                        "{} ({file_name} from {})",
+                        folder.name, folder.path
+                    )),
+                    timestamp,
+                    tool_calls: None,
+                    tool_call_id: None,
+                    tool_name: None,
+                    usage: None,
+                },
+                ChatMessage {
+                    id: message_id()?,
+                    role: "assistant".to_string(),
+                    content: serde_json::Value::String(outcome.text),
+                    timestamp,
+                    tool_calls: None,
+                    tool_call_id: None,
+                    tool_name: None,
+                    usage: None,
+                },
+            ];
+            crate::persistence::current(app).append(app, conversation_id, &messages)?;
+            Ok(conversation_id.clone())
+        }
+    }
+}
+
+/// Processes every new or changed file in a folder. Folders already being
+/// scanned are skipped.
+async fn scan(app: &AppHandle, folder: &WatchFolder) -> Result<(), String> {
+    let state = app.state::<WatchFolders>();
+    if !state
+        .scanning
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(folder.id.clone())
+    {
+        return Ok(());
+    }
+    let result = scan_unlocked(app, folder).await;
+    if let Ok(mut scanning) = state.scanning.lock() {
+        scanning.remove(&folder.id);
+    }
+    result
+}
+
+async fn scan_unlocked(app: &AppHandle, folder: &WatchFolder) -> Result<(), String> {
+    let mut pending = Vec::new();
+    {
+        let conn = crate::db::open(app)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT modified_at, size FROM watch_folder_files
+                 WHERE folder_id = ?1 AND path = ?2",
+            )
+            .map_err(|e| format!("Failed to read watch folder files: {e}"))?;
+        for path in candidates(folder)? {
+            let Some(current) = fingerprint(&path) else {
+                continue;
+            };
+            let seen: Option<(i64, i64)> = stmt
+                .query_row(params![folder.id, path.to_string_lossy()], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .ok();
+            if seen != Some(current) {
+                pending.push((path, current));
+            }
+        }
+    }
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let api_key = match keychain::get(&api_key_account(&folder.id))? {
+        Some(key) => key,
+        None => crate::api_keys::require(folder.provider)
+            .map_err(|e| format!("Watch folder {}: {e}", folder.name))?,
+    };
+    for (path, (modified, size)) in pending {
+        log::info!("Watch folder {} processing {path:?}", folder.id);
+        let result = process_file(app, folder, &api_key, &path).await;
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(e) => {
+                log::warn!("Watch folder {} failed on {path:?}: {e}", folder.id);
+                (None, Some(e))
+            }
+        };
+
+        let conn = crate::db::open(app)?;
+        conn.execute(
+            "INSERT INTO watch_folder_files
+                 (folder_id, path, modified_at, size, processed_at, succeeded, output, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (folder_id, path) DO UPDATE SET
+                 modified_at = excluded.modified_at,
+                 size = excluded.size,
+                 processed_at = excluded.processed_at,
+                 succeeded = excluded.succeeded,
+                 output = excluded.output,
+                 error = excluded.error",
+            params![
+                folder.id,
+                path.to_string_lossy(),
+                modified,
+                size,
+                now_ms() as i64,
+                error.is_none(),
+                output,
+                error
+            ],
+        )
+        .map_err(|e| format!("Failed to record processed file: {e}"))?;
+
+        let _ = app.emit(
+            "watch-folder-processed",
+            WatchFolderEvent {
+                folder_id: folder.id.clone(),
+                path: path.to_string_lossy().to_string(),
+                output,
+                error,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Scheduler job: rescans every enabled folder.
+pub fn scan_all(app: &AppHandle) -> Result<(), String> {
+    for folder in load(app)?.into_iter().filter(|f| f.enabled) {
+        if let Err(e) = tauri::async_runtime::block_on(scan(app, &folder)) {
+            log::error!("Scan of watch folder {} failed: {e}", folder.id);
+        }
+    }
+    Ok(())
+}
+
+/// Starts watching a folder, replacing any previous watcher for it.
+fn watch(app: &AppHandle, folder: &WatchFolder) -> Result<(), String> {
+    let (sender, changes) = mpsc::channel::<()>();
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                let _ = sender.send(());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Watch folder error: {e}"),
+        })
+        .map_err(|e| format!("Failed to create file watcher: {e}"))?;
+    watcher
+        .watch(Path::new(&folder.path), RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {e}", folder.path))?;
+
+    let app_handle = app.clone();
+    let folder_id = folder.id.clone();
+    std::thread::spawn(move || debounce_and_scan(app_handle, folder_id, changes));
+
+    app.state::<WatchFolders>()
+        .watchers
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(folder.id.clone(), watcher);
+    log::info!("Watching folder {} ({})", folder.id, folder.path);
+    Ok(())
+}
+
+fn unwatch(app: &AppHandle, id: &str) {
+    if let Ok(mut watchers) = app.state::<WatchFolders>().watchers.lock() {
+        watchers.remove(id);
+    }
+}
+
+/// Scans the folder whenever changes settle. Ends when the watcher is
+/// dropped.
+fn debounce_and_scan(app: AppHandle, folder_id: String, changes: mpsc::Receiver<()>) {
+    while changes.recv().is_ok() {
+        loop {
+            match changes.recv_timeout(DEBOUNCE) {
+                Ok(()) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        // Read the folder fresh, it may have been edited in the meantime
+        let folder = load(&app)
+            .ok()
+            .and_then(|folders| folders.into_iter().find(|f| f.id == folder_id));
+        let Some(folder) = folder.filter(|f| f.enabled) else {
+            return;
+        };
+        if let Err(e) = tauri::async_runtime::block_on(scan(&app, &folder)) {
+            log::error!("Scan of watch folder {folder_id} failed: {e}");
+        }
+    }
+}
+
+/// Starts the watchers of all enabled folders. Called once at startup.
+pub fn start(app: &AppHandle) {
+    for folder in load(app).unwrap_or_default() {
+        if folder.enabled {
+            if let Err(e) = watch(app, &folder) {
+                log::error!("Failed to start watch folder {}: {e}", folder.id);
+            }
+        }
+    }
+}
+
+/// Deletes the API keys of every watch folder. Used when wiping app data;
+/// failures are logged and skipped.
+pub fn delete_all_credentials(app: &AppHandle) {
+    for folder in load(app).unwrap_or_default() {
+        if let Err(e) = keychain::delete(&api_key_account(&folder.id)) {
+            log::warn!(
+                "Failed to delete API key of watch folder {}: {e}",
+                folder.id
+            );
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_watch_folders(app: AppHandle) -> Result<Vec<WatchFolderInfo>, String> {
+    load(&app)?
+        .into_iter()
+        .map(|folder| {
+            let has_api_key = keychain::get(&api_key_account(&folder.id))?.is_some();
+            Ok(WatchFolderInfo {
+                folder,
+                has_api_key,
+            })
+        })
+        .collect()
+}
+
+/// Adds or replaces a watch folder. A non-empty `api_key` is stored in the
+/// keychain, an empty one removes the stored key, and leaving it out keeps
+/// it. Files already in a newly added folder, or a folder whose path
+/// changed, are not processed.
+#[tauri::command]
+pub async fn save_watch_folder(
+    app: AppHandle,
+    mut folder: WatchFolder,
+    api_key: Option<String>,
+) -> Result<(), String> {
+    folder.extensions = folder
+        .extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    validate(&folder)?;
+
+    let mut folders = load(&app)?;
+    let previous = folders.iter().position(|f| f.id == folder.id);
+    let moved = previous.is_none_or(|i| folders[i].path != folder.path);
+    match previous {
+        Some(index) => folders[index] = folder.clone(),
+        None if folders.len() >= MAX_WATCH_FOLDERS => {
+            return Err(format!("Too many watch folders (max {MAX_WATCH_FOLDERS})"));
+        }
+        None => folders.push(folder.clone()),
+    }
+
+    let account = api_key_account(&folder.id);
+    match api_key.as_deref() {
+        Some("") => keychain::delete(&account)?,
+        Some(key) => keychain::set(&account, key)?,
+        None => {}
+    }
+
+    if moved {
+        let conn = crate::db::open(&app)?;
+        conn.execute(
+            "DELETE FROM watch_folder_files WHERE folder_id = ?1",
+            params![folder.id],
+        )
+        .map_err(|e| format!("Failed to reset watch folder files: {e}"))?;
+        drop(conn);
+        record_existing(&app, &folder)?;
+    }
+    store(&app, &folders)?;
+
+    unwatch(&app, &folder.id);
+    if folder.enabled {
+        watch(&app, &folder)?;
+    }
+    log::info!("Saved watch folder {}", folder.id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_watch_folder(app: AppHandle, id: String) -> Result<(), String> {
+    let mut folders = load(&app)?;
+    folders.retain(|f| f.id != id);
+    store(&app, &folders)?;
+    unwatch(&app, &id);
+    keychain::delete(&api_key_account(&id))?;
+
+    let conn = crate::db::open(&app)?;
+    conn.execute(
+        "DELETE FROM watch_folder_files WHERE folder_id = ?1",
+        params![id],
+    )
+    .map_err(|e| format!("Failed to remove watch folder files: {e}"))?;
+    log::info!("Removed watch folder {id}");
+    Ok(())
+}
+
+/// Files a folder has seen, most recently processed first.
+#[tauri::command]
+pub async fn list_watch_folder_files(
+    app: AppHandle,
+    folder_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<ProcessedFile>, String> {
+    let conn = crate::db::open(&app)?;
+    conn.prepare(
+        "SELECT path, processed_at, succeeded, output, error FROM watch_folder_files
+         WHERE folder_id = ?1 ORDER BY processed_at DESC LIMIT ?2",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![folder_id, limit.unwrap_or(100)], |row| {
+            Ok(ProcessedFile {
+                path: row.get("path")?,
+                processed_at: row.get("processed_at")?,
+                succeeded: row.get("succeeded")?,
+                output: row.get("output")?,
+                error: row.get("error")?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .map_err(|e| format!("Failed to list watch folder files: {e}"))
+}
@@ -0,0 +1,421 @@
+// Window State
+// ============
+//
+// The main window reopens where it was left: same size, position and
+// maximized state. Its bounds are tracked in memory as it moves and
+// resizes, and written to `window-state.json` in the app data directory
+// (shared by every profile, since it describes this machine's screens)
+// when the window closes and when the app exits.
+//
+// Bounds are kept in physical pixels along with the monitor's scale
+// factor. On restore they are checked against the monitors connected now:
+// a window that would not show enough of itself on any of them opens
+// centered at its default size instead, and one larger than its monitor is
+// shrunk to fit. Moving to a monitor with another scale factor keeps the
+// window's logical size. While maximized, the bounds from before are kept,
+// so unmaximizing after a restart returns to them.
+//
+// The window can also be pinned above other windows and shrunk to a
+// compact size, for keeping a small chat next to other work. Both are
+// toggled from the View menu, the tray or `set_window_pinned` and
+// `set_compact_mode`, and kept in preferences. In compact mode only the
+// window's position is tracked, so leaving it returns to the full size.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::utils::config::WindowConfig;
+use tauri::{
+    AppHandle, Emitter, LogicalSize, Manager, Monitor, PhysicalPosition, PhysicalSize,
+    WebviewWindow,
+};
+
+const MAIN_WINDOW: &str = "main";
+/// Pixels of the window that must lie on a monitor for it to be reachable
+const MIN_VISIBLE_WIDTH: i64 = 100;
+const MIN_VISIBLE_HEIGHT: i64 = 40;
+/// Logical size of the window in compact mode, and how small it can go
+const COMPACT_SIZE: (f64, f64) = (420.0, 640.0);
+const COMPACT_MIN_SIZE: (f64, f64) = (360.0, 480.0);
+/// Ids of the View menu and its items, also used by the tray
+pub const VIEW_MENU_ID: &str = "view";
+pub const PIN_MENU_ID: &str = "toggle-pinned";
+pub const COMPACT_MENU_ID: &str = "toggle-compact";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGeometry {
+    /// Physical pixels of the outer top left corner
+    pub x: i32,
+    pub y: i32,
+    /// Physical pixels of the content area
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    /// Of the monitor the window was on
+    pub scale_factor: f64,
+}
+
+/// Pinning and compact mode of the main window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowMode {
+    /// Stays above other windows
+    pub pinned: bool,
+    /// Shrunk to a small chat window
+    pub compact: bool,
+}
+
+/// The main window's latest geometry and its mode; managed as app state.
+#[derive(Default)]
+pub struct WindowState {
+    geometry: Mutex<Option<WindowGeometry>>,
+    mode: Mutex<WindowMode>,
+}
+
+fn get_state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base_dir = crate::profiles::base_dir(app)?;
+
+    std::fs::create_dir_all(&base_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+    Ok(base_dir.join("window-state.json"))
+}
+
+fn load(app: &AppHandle) -> Option<WindowGeometry> {
+    let path = get_state_path(app).ok()?;
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents)
+        .inspect_err(|e| log::warn!("Ignoring unreadable window state: {e}"))
+        .ok()
+}
+
+/// Records the main window's geometry after it moved, resized or was
+/// maximized. Minimized windows keep what was recorded before.
+pub fn track(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    if window.is_minimized().unwrap_or(true) || !window.is_visible().unwrap_or(false) {
+        return;
+    }
+    let state = app.state::<WindowState>();
+    let compact = state.mode.lock().is_ok_and(|mode| mode.compact);
+    let Ok(mut geometry) = state.geometry.lock() else {
+        return;
+    };
+    if compact {
+        if let (Some(geometry), Ok(position)) = (geometry.as_mut(), window.outer_position()) {
+            geometry.x = position.x;
+            geometry.y = position.y;
+        }
+        return;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    if maximized {
+        if let Some(geometry) = geometry.as_mut() {
+            geometry.maximized = true;
+            return;
+        }
+    }
+    let (Ok(position), Ok(size), Ok(scale_factor)) = (
+        window.outer_position(),
+        window.inner_size(),
+        window.scale_factor(),
+    ) else {
+        return;
+    };
+    *geometry = Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+        scale_factor,
+    });
+}
+
+/// Writes the recorded geometry to disk. Called when the main window
+/// closes and when the app exits.
+pub fn save(app: &AppHandle) {
+    let geometry = app
+        .state::<WindowState>()
+        .geometry
+        .lock()
+        .ok()
+        .and_then(|geometry| *geometry);
+    let Some(geometry) = geometry else {
+        return;
+    };
+    let result = serde_json::to_vec_pretty(&geometry)
+        .map_err(|e| format!("Failed to serialize window state: {e}"))
+        .and_then(|json| crate::export::write_atomic(&get_state_path(app)?, &json));
+    if let Err(e) = result {
+        log::warn!("Failed to save window state: {e}");
+    }
+}
+
+/// Pixels of the rectangle that overlap the monitor's work area.
+fn visible_area(monitor: &Monitor, x: i32, y: i32, width: u32, height: u32) -> (i64, i64) {
+    let area = monitor.work_area();
+    let overlap = |start: i32, length: u32, area_start: i32, area_length: u32| {
+        let end = (start as i64 + length as i64).min(area_start as i64 + area_length as i64);
+        (end - (start as i64).max(area_start as i64)).max(0)
+    };
+    (
+        overlap(x, width, area.position.x, area.size.width),
+        overlap(y, height, area.position.y, area.size.height),
+    )
+}
+
+/// The geometry adjusted to the monitors connected now, or `None` if the
+/// window would be out of reach.
+fn fit_to_monitors(geometry: WindowGeometry, monitors: &[Monitor]) -> Option<WindowGeometry> {
+    // The title bar area has to be on screen to move the window
+    let monitor = monitors
+        .iter()
+        .map(|monitor| {
+            let strip = MIN_VISIBLE_HEIGHT as u32;
+            let (width, height) =
+                visible_area(monitor, geometry.x, geometry.y, geometry.width, strip);
+            (monitor, width, height)
+        })
+        .filter(|(_, width, height)| *width >= MIN_VISIBLE_WIDTH && *height >= MIN_VISIBLE_HEIGHT)
+        .max_by_key(|(_, width, height)| width * height)
+        .map(|(monitor, _, _)| monitor)?;
+
+    let mut fitted = geometry;
+    let scale = monitor.scale_factor() / geometry.scale_factor;
+    if (scale - 1.0).abs() > f64::EPSILON {
+        fitted.width = (geometry.width as f64 * scale).round() as u32;
+        fitted.height = (geometry.height as f64 * scale).round() as u32;
+        fitted.scale_factor = monitor.scale_factor();
+    }
+    let area = monitor.work_area();
+    fitted.width = fitted.width.min(area.size.width);
+    fitted.height = fitted.height.min(area.size.height);
+    // Pull the window back in so none of it hangs off the monitor
+    let max_x = area.position.x as i64 + area.size.width as i64 - fitted.width as i64;
+    let max_y = area.position.y as i64 + area.size.height as i64 - fitted.height as i64;
+    fitted.x = (fitted.x as i64).clamp(area.position.x as i64, max_x) as i32;
+    fitted.y = (fitted.y as i64).clamp(area.position.y as i64, max_y) as i32;
+    Some(fitted)
+}
+
+/// Puts a newly created, still hidden main window where it was left, then
+/// shows it.
+pub fn restore(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let monitors = app.available_monitors().unwrap_or_default();
+    let geometry = load(app).and_then(|saved| {
+        let fitted = fit_to_monitors(saved, &monitors);
+        if fitted.is_none() {
+            log::info!("Saved window position is off screen, opening at the default");
+        }
+        fitted
+    });
+    if let Some(geometry) = geometry {
+        let result = window
+            .set_size(PhysicalSize::new(geometry.width, geometry.height))
+            .and_then(|_| window.set_position(PhysicalPosition::new(geometry.x, geometry.y)))
+            .and_then(|_| {
+                if geometry.maximized {
+                    window.maximize()
+                } else {
+                    Ok(())
+                }
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to restore window state: {e}");
+        }
+        if let Ok(mut state) = app.state::<WindowState>().geometry.lock() {
+            *state = Some(geometry);
+        }
+    }
+    if let Err(e) = apply_mode(app, window, WindowMode::default(), mode(app)) {
+        log::warn!("Failed to restore window mode: {e}");
+    }
+    window
+        .show()
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show window: {e}"))
+}
+
+/// Forgets the saved geometry and puts the main window back at its default
+/// size, centered.
+#[tauri::command]
+pub async fn reset_window_state(app: AppHandle) -> Result<(), String> {
+    if let Ok(mut state) = app.state::<WindowState>().geometry.lock() {
+        *state = None;
+    }
+    let path = get_state_path(&app)?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to reset window state: {e}"))?;
+    }
+    log::info!("Reset window state");
+
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return Ok(());
+    };
+    let config = main_config(&app)?;
+    window
+        .unmaximize()
+        .and_then(|_| window.set_size(LogicalSize::new(config.width, config.height)))
+        .and_then(|_| window.center())
+        .map_err(|e| format!("Failed to reset window: {e}"))
+}
+
+fn main_config(app: &AppHandle) -> Result<WindowConfig, String> {
+    app.config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == MAIN_WINDOW)
+        .cloned()
+        .ok_or_else(|| "The main window is not configured".to_string())
+}
+
+/// Applies what changed between two modes to the main window.
+fn apply_mode(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    from: WindowMode,
+    to: WindowMode,
+) -> Result<(), String> {
+    if from.pinned != to.pinned {
+        window
+            .set_always_on_top(to.pinned)
+            .map_err(|e| format!("Failed to pin window: {e}"))?;
+    }
+    if from.compact == to.compact {
+        return Ok(());
+    }
+    let result = if to.compact {
+        let (width, height) = COMPACT_SIZE;
+        let (min_width, min_height) = COMPACT_MIN_SIZE;
+        window
+            .unmaximize()
+            .and_then(|_| window.set_min_size(Some(LogicalSize::new(min_width, min_height))))
+            .and_then(|_| window.set_size(LogicalSize::new(width, height)))
+    } else {
+        let config = main_config(app)?;
+        let geometry = app
+            .state::<WindowState>()
+            .geometry
+            .lock()
+            .ok()
+            .and_then(|geometry| *geometry);
+        let min_size = config
+            .min_width
+            .zip(config.min_height)
+            .map(|(width, height)| LogicalSize::new(width, height));
+        window.set_min_size(min_size).and_then(|_| match geometry {
+            Some(geometry) => window
+                .set_size(PhysicalSize::new(geometry.width, geometry.height))
+                .and_then(|_| {
+                    if geometry.maximized {
+                        window.maximize()
+                    } else {
+                        Ok(())
+                    }
+                }),
+            None => window.set_size(LogicalSize::new(config.width, config.height)),
+        })
+    };
+    result.map_err(|e| format!("Failed to resize window: {e}"))
+}
+
+/// Checks the View menu's items to match the mode.
+fn sync_menus(app: &AppHandle, mode: WindowMode) {
+    let view = app
+        .menu()
+        .and_then(|menu| menu.get(VIEW_MENU_ID))
+        .and_then(|item| item.as_submenu().cloned());
+    if let Some(view) = view {
+        for (id, checked) in [(PIN_MENU_ID, mode.pinned), (COMPACT_MENU_ID, mode.compact)] {
+            if let Some(item) = view
+                .get(id)
+                .and_then(|item| item.as_check_menuitem().cloned())
+            {
+                if let Err(e) = item.set_checked(checked) {
+                    log::warn!("Failed to update {id} menu item: {e}");
+                }
+            }
+        }
+    }
+    crate::platform::refresh_tray(app);
+}
+
+/// Picks up the saved mode, before the main window is first shown.
+pub fn load_mode(app: &AppHandle) {
+    let mode = crate::read_preferences(app)
+        .map(|preferences| preferences.window_mode)
+        .unwrap_or_default();
+    if let Ok(mut state) = app.state::<WindowState>().mode.lock() {
+        *state = mode;
+    }
+    sync_menus(app, mode);
+}
+
+/// The mode the main window is in.
+pub fn mode(app: &AppHandle) -> WindowMode {
+    app.state::<WindowState>()
+        .mode
+        .lock()
+        .map(|mode| *mode)
+        .unwrap_or_default()
+}
+
+/// Changes the mode, saves it in preferences and applies it to the main
+/// window. Announces the new mode with `window-mode-changed`.
+fn update_mode(
+    app: &AppHandle,
+    change: impl FnOnce(&mut WindowMode),
+) -> Result<WindowMode, String> {
+    let mut preferences = crate::read_preferences(app)?;
+    let from = preferences.window_mode;
+    let mut to = from;
+    change(&mut to);
+    preferences.window_mode = to;
+    crate::write_preferences(app, &preferences)?;
+    if let Ok(mut mode) = app.state::<WindowState>().mode.lock() {
+        *mode = to;
+    }
+    log::info!("Window mode: pinned {}, compact {}", to.pinned, to.compact);
+
+    let result = match app.get_webview_window(MAIN_WINDOW) {
+        Some(window) => apply_mode(app, &window, from, to),
+        None => Ok(()),
+    };
+    sync_menus(app, to);
+    if let Err(e) = app.emit("window-mode-changed", to) {
+        log::warn!("Failed to emit window-mode-changed event: {e}");
+    }
+    result.map(|_| to)
+}
+
+/// Flips pinning, for the View menu and the tray.
+pub fn toggle_pinned(app: &AppHandle) {
+    if let Err(e) = update_mode(app, |mode| mode.pinned = !mode.pinned) {
+        log::error!("{e}");
+    }
+}
+
+/// Flips compact mode, for the View menu and the tray.
+pub fn toggle_compact(app: &AppHandle) {
+    if let Err(e) = update_mode(app, |mode| mode.compact = !mode.compact) {
+        log::error!("{e}");
+    }
+}
+
+/// Keeps the main window above other windows, or stops doing so.
+#[tauri::command]
+pub async fn set_window_pinned(app: AppHandle, pinned: bool) -> Result<WindowMode, String> {
+    update_mode(&app, |mode| mode.pinned = pinned)
+}
+
+/// Shrinks the main window to a compact size, or returns it to the size it
+/// had before.
+#[tauri::command]
+pub async fn set_compact_mode(app: AppHandle, compact: bool) -> Result<WindowMode, String> {
+    update_mode(&app, |mode| mode.compact = compact)
+}
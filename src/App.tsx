@@ -4,6 +4,9 @@ import { relaunch } from '@tauri-apps/plugin-process'
 import { initializeCommandSystem } from './lib/commands'
 import { logger } from './lib/logger'
 import { cleanupOldFiles } from './lib/recovery'
+import { startConversationSync } from './lib/conversation-sync'
+import { syncActiveProfile } from './lib/profiles'
+import { startTraySync } from './lib/tray'
 import './App.css'
 import MainWindow from './components/layout/MainWindow'
 import { ThemeProvider } from './components/ThemeProvider'
@@ -21,6 +24,18 @@ function App() {
       logger.warn('Failed to cleanup old recovery files', { error })
     })
 
+    // Reload if the stores were restored for a different profile than the
+    // backend's active one
+    syncActiveProfile().catch(error => {
+      logger.warn('Failed to check the active profile', { error })
+    })
+
+    // Mirror conversations to the backend for scheduled exports
+    const stopConversationSync = startConversationSync()
+
+    // List the MCP servers in the tray and act on its menu
+    const stopTraySync = startTraySync()
+
     // Example of logging with context
     logger.info('App environment', {
       isDev: import.meta.env.DEV,
@@ -80,7 +95,11 @@ function App() {
 
     // Check for updates 5 seconds after app loads
     const updateTimer = setTimeout(checkForUpdates, 5000)
-    return () => clearTimeout(updateTimer)
+    return () => {
+      clearTimeout(updateTimer)
+      stopConversationSync()
+      stopTraySync()
+    }
   }, [])
 
   return (
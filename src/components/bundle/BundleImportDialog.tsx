@@ -0,0 +1,109 @@
+/**
+ * Bundle Import Dialog
+ * Asks before importing a `.nexus` bundle that was opened from outside
+ * the app, showing what it holds, and reports what the import did.
+ */
+
+import { useEffect, useState, useCallback } from 'react'
+import { toast } from 'sonner'
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from '@/components/ui/alert-dialog'
+import {
+  importBundle,
+  onBundleOpened,
+  takePendingBundle,
+  type OpenedBundle,
+} from '@/lib/bundle'
+import { logger } from '@/lib/logger'
+
+/** Titles listed in the dialog before the rest are summarized */
+const MAX_LISTED = 10
+
+export function BundleImportDialog() {
+  const [opened, setOpened] = useState<OpenedBundle | null>(null)
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+    let cancelled = false
+    takePendingBundle()
+      .then(bundle => bundle && setOpened(bundle))
+      .catch(error =>
+        logger.error('Failed to take pending bundle', {
+          error: String(error),
+        })
+      )
+    onBundleOpened(setOpened).then(fn => {
+      if (cancelled) fn()
+      else unlisten = fn
+    })
+    return () => {
+      cancelled = true
+      unlisten?.()
+    }
+  }, [])
+
+  const importOpened = useCallback(async () => {
+    if (!opened) return
+    setOpened(null)
+    try {
+      const report = await importBundle(opened.path)
+      const imported = report.imported.length
+      const skipped = report.skipped.length
+      toast.success(
+        `Imported ${imported} conversations from ${report.bundle}` +
+          (skipped > 0 ? `; ${skipped} were already here` : '')
+      )
+    } catch (error) {
+      logger.error('Failed to import bundle', { error: String(error) })
+      toast.error(`Failed to import ${opened.bundle}: ${String(error)}`)
+    }
+  }, [opened])
+
+  const conversations = opened?.manifest.conversations ?? []
+
+  return (
+    <AlertDialog
+      open={opened !== null}
+      onOpenChange={open => !open && setOpened(null)}
+    >
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle>Import “{opened?.bundle}”?</AlertDialogTitle>
+          <AlertDialogDescription>
+            The bundle holds {conversations.length} conversations. Only
+            import it if you trust where it came from.
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        <ul className="max-h-48 overflow-auto text-sm">
+          {conversations.slice(0, MAX_LISTED).map(entry => (
+            <li key={entry.file} className="truncate">
+              {entry.title}{' '}
+              <span className="text-muted-foreground">
+                ({entry.messageCount} messages)
+              </span>
+            </li>
+          ))}
+          {conversations.length > MAX_LISTED && (
+            <li className="text-muted-foreground">
+              and {conversations.length - MAX_LISTED} more
+            </li>
+          )}
+        </ul>
+        <AlertDialogFooter>
+          <AlertDialogCancel>Cancel</AlertDialogCancel>
+          <AlertDialogAction onClick={importOpened}>Import</AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  )
+}
+
+export default BundleImportDialog
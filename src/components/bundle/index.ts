@@ -0,0 +1,4 @@
+export {
+  BundleImportDialog,
+  default as BundleImportDialogDefault,
+} from './BundleImportDialog'
@@ -8,6 +8,7 @@ import { AudioRecorder } from './AudioRecorder'
 import type { ContentPart, AudioContentPart, ImageContentPart } from '@/types/multimodal'
 import { modelSupportsVision, modelSupportsAudio, getDataUri } from '@/types/multimodal'
 import { createMessageContent } from '@/store/chat-store'
+import { useUIStore } from '@/store/ui-store'
 
 interface MessageInputProps {
   onSend: (content: string | ContentPart[]) => void
@@ -124,6 +125,15 @@ export const MessageInput = memo(function MessageInput({
     textareaRef.current?.focus()
   }, [])
 
+  // Take over a prompt handed in from elsewhere, e.g. a deep link
+  const draftPrompt = useUIStore(state => state.draftPrompt)
+  useEffect(() => {
+    if (draftPrompt === null) return
+    setMessage(draftPrompt)
+    useUIStore.getState().setDraftPrompt(null)
+    textareaRef.current?.focus()
+  }, [draftPrompt])
+
   const handleSubmit = useCallback(() => {
     const trimmed = message.trim()
     if ((!trimmed && attachments.length === 0) || isGenerating || disabled) return
@@ -0,0 +1,138 @@
+/**
+ * Deep Link Dialog
+ * Acts on `nexus://` links. Conversations and new chats open directly; an
+ * MCP server comes from outside the app, so it is only added, disabled,
+ * once the user has seen exactly what it would run and confirmed it.
+ */
+
+import { useEffect, useState, useCallback } from 'react'
+import { toast } from 'sonner'
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from '@/components/ui/alert-dialog'
+import { useChatStore } from '@/store/chat-store'
+import { useMCPStore } from '@/store/mcp-store'
+import { useUIStore } from '@/store/ui-store'
+import {
+  onDeepLink,
+  takePendingDeepLink,
+  type DeepLink,
+} from '@/lib/deep-link'
+import { logger } from '@/lib/logger'
+
+type McpServerLink = Extract<DeepLink, { type: 'add_mcp_server' }>
+
+export function DeepLinkDialog() {
+  const [server, setServer] = useState<McpServerLink | null>(null)
+
+  const handleLink = useCallback((link: DeepLink) => {
+    switch (link.type) {
+      case 'open_conversation': {
+        const { conversations, setActiveConversation } =
+          useChatStore.getState()
+        if (conversations.some(c => c.id === link.id)) {
+          setActiveConversation(link.id)
+        } else {
+          toast.error('That conversation does not exist')
+        }
+        break
+      }
+      case 'new_chat': {
+        useChatStore.getState().createConversation()
+        if (link.prompt) {
+          useUIStore.getState().setDraftPrompt(link.prompt)
+        }
+        break
+      }
+      case 'add_mcp_server':
+        setServer(link)
+        break
+    }
+  }, [])
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined
+    let cancelled = false
+    takePendingDeepLink()
+      .then(link => link && handleLink(link))
+      .catch(error =>
+        logger.error('Failed to take pending deep link', {
+          error: String(error),
+        })
+      )
+    onDeepLink(handleLink).then(fn => {
+      if (cancelled) fn()
+      else unlisten = fn
+    })
+    return () => {
+      cancelled = true
+      unlisten?.()
+    }
+  }, [handleLink])
+
+  const addServer = useCallback(() => {
+    if (!server) return
+    const { addServer } = useMCPStore.getState()
+    if (server.url) {
+      addServer({
+        id: '',
+        name: server.name,
+        enabled: false,
+        transport: 'http',
+        url: server.url,
+      })
+    } else {
+      addServer({
+        id: '',
+        name: server.name,
+        enabled: false,
+        transport: 'stdio',
+        command: server.command ?? '',
+        args: server.args,
+        env: server.env,
+      })
+    }
+    toast.success(`Added ${server.name}; enable it to start it`)
+    setServer(null)
+  }, [server])
+
+  return (
+    <AlertDialog
+      open={server !== null}
+      onOpenChange={open => !open && setServer(null)}
+    >
+      <AlertDialogContent>
+        <AlertDialogHeader>
+          <AlertDialogTitle>
+            Add MCP server “{server?.name}”?
+          </AlertDialogTitle>
+          <AlertDialogDescription>
+            A link asks to add this server. Only add it if you trust where
+            the link came from.{' '}
+            {server?.url
+              ? 'It connects to:'
+              : 'Once enabled, it runs this command:'}
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        <pre className="max-h-48 overflow-auto whitespace-pre-wrap break-all rounded-md bg-muted p-3 font-mono text-xs">
+          {server?.url ?? server?.commandLine}
+        </pre>
+        <AlertDialogFooter>
+          <AlertDialogCancel>Cancel</AlertDialogCancel>
+          <AlertDialogAction onClick={addServer}>
+            Add Server
+          </AlertDialogAction>
+        </AlertDialogFooter>
+      </AlertDialogContent>
+    </AlertDialog>
+  )
+}
+
+export default DeepLinkDialog
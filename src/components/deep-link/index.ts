@@ -0,0 +1,4 @@
+export {
+  DeepLinkDialog,
+  default as DeepLinkDialogDefault,
+} from './DeepLinkDialog'
@@ -9,6 +9,8 @@ import { RightSideBar } from './RightSideBar'
 import { MainWindowContent } from './MainWindowContent'
 import { CommandPalette } from '@/components/command-palette/CommandPalette'
 import { PreferencesDialog } from '@/components/preferences/PreferencesDialog'
+import { DeepLinkDialog } from '@/components/deep-link/DeepLinkDialog'
+import { BundleImportDialog } from '@/components/bundle/BundleImportDialog'
 import { Toaster } from 'sonner'
 import { useTheme } from '@/hooks/use-theme'
 import { useUIStore } from '@/store/ui-store'
@@ -64,6 +66,8 @@ export function MainWindow() {
       {/* Global UI Components (hidden until triggered) */}
       <CommandPalette />
       <PreferencesDialog />
+      <DeepLinkDialog />
+      <BundleImportDialog />
       <Toaster
         position="bottom-right"
         theme={
@@ -1,9 +1,9 @@
 /**
  * Advanced Settings Pane
- * Data management and about information
+ * Data management, local inference and about information
  */
 
-import React, { useCallback, useState } from 'react'
+import React, { useCallback, useEffect, useState } from 'react'
 import { Label } from '@/components/ui/label'
 import { Separator } from '@/components/ui/separator'
 import { Button } from '@/components/ui/button'
@@ -18,10 +18,19 @@ import {
   AlertDialogHeader,
   AlertDialogTitle,
 } from '@/components/ui/alert-dialog'
-import { Trash2, Database, Github, Heart, RefreshCw } from 'lucide-react'
+import {
+  Trash2,
+  Database,
+  Github,
+  Heart,
+  RefreshCw,
+  Cpu,
+} from 'lucide-react'
+import { save } from '@tauri-apps/plugin-dialog'
 import { useChatStore } from '@/store/chat-store'
-import { useApiKeysStore } from '@/store/api-keys-store'
 import { useMCPStore } from '@/store/mcp-store'
+import { exportAllData, wipeAppData } from '@/lib/second-factor'
+import { getLocalRuntime, type LocalRuntime } from '@/lib/local-llm'
 import { toast } from 'sonner'
 
 // ============================================
@@ -61,6 +70,67 @@ const SettingsField: React.FC<{
   </div>
 )
 
+/** Whether llama.cpp's `llama-server` was found for local models */
+const LocalInferenceStatus: React.FC = () => {
+  const [runtime, setRuntime] = useState<LocalRuntime | null>(null)
+  const [checking, setChecking] = useState(false)
+
+  const check = useCallback(async () => {
+    setChecking(true)
+    try {
+      setRuntime(await getLocalRuntime())
+    } catch (error) {
+      setRuntime({ enabled: true, serverPath: null, error: String(error) })
+    } finally {
+      setChecking(false)
+    }
+  }, [])
+
+  useEffect(() => {
+    void check()
+  }, [check])
+
+  const ready = runtime?.enabled && runtime.serverPath !== null
+
+  return (
+    <div className="rounded-lg border border-border/50 bg-muted/30 p-4">
+      <div className="flex items-center gap-3">
+        <div className="flex h-10 w-10 items-center justify-center rounded-lg bg-primary/10 text-primary">
+          <Cpu className="h-5 w-5" />
+        </div>
+        <div className="min-w-0 flex-1">
+          <p className="font-medium">llama.cpp server</p>
+          <p className="truncate text-xs text-muted-foreground">
+            {runtime === null
+              ? 'Checking…'
+              : ready
+                ? runtime.serverPath
+                : runtime.enabled
+                  ? 'Not installed'
+                  : 'Not available in this build'}
+          </p>
+        </div>
+        {runtime?.enabled && (
+          <Badge variant={ready ? 'secondary' : 'destructive'}>
+            {ready ? 'Found' : 'Missing'}
+          </Badge>
+        )}
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={check}
+          disabled={checking}
+        >
+          Check again
+        </Button>
+      </div>
+      {runtime?.error && (
+        <p className="mt-3 text-xs text-muted-foreground">{runtime.error}</p>
+      )}
+    </div>
+  )
+}
+
 // ============================================
 // Main Component
 // ============================================
@@ -86,36 +156,34 @@ export const AdvancedPane: React.FC = () => {
     toast.success('All conversations cleared')
   }, [])
 
-  const handleResetAll = useCallback(() => {
-    // Clear everything
-    localStorage.clear()
+  const handleResetAll = useCallback(async () => {
     setShowResetAllDialog(false)
+    try {
+      // The backend copy goes first, it may require a second factor
+      if (!(await wipeAppData())) return
+    } catch (error) {
+      toast.error(`Failed to reset: ${error}`)
+      return
+    }
+    localStorage.clear()
     toast.success('All data cleared. Reloading...')
     setTimeout(() => window.location.reload(), 1000)
   }, [])
 
-  const handleExportData = useCallback(() => {
-    const data = {
-      conversations: useChatStore.getState().conversations,
-      apiKeys: {
-        hasGoogle: !!useApiKeysStore.getState().googleApiKey,
-        hasOpenai: !!useApiKeysStore.getState().openaiApiKey,
-        hasGroq: !!useApiKeysStore.getState().groqApiKey,
-      },
-      mcpServers: useMCPStore.getState().servers,
-      exportedAt: new Date().toISOString(),
-    }
-
-    const blob = new Blob([JSON.stringify(data, null, 2)], {
-      type: 'application/json',
+  const handleExportData = useCallback(async () => {
+    const path = await save({
+      defaultPath: `nexus-backup-${new Date().toISOString().split('T')[0]}.json`,
+      filters: [{ name: 'Nexus backup', extensions: ['json'] }],
     })
-    const url = URL.createObjectURL(blob)
-    const a = document.createElement('a')
-    a.href = url
-    a.download = `nexus-backup-${new Date().toISOString().split('T')[0]}.json`
-    a.click()
-    URL.revokeObjectURL(url)
-    toast.success('Data exported successfully')
+    if (!path) return
+
+    try {
+      if (await exportAllData(path, useMCPStore.getState().servers)) {
+        toast.success('Data exported successfully')
+      }
+    } catch (error) {
+      toast.error(`Failed to export data: ${error}`)
+    }
   }, [])
 
   return (
@@ -179,6 +247,14 @@ export const AdvancedPane: React.FC = () => {
         </SettingsField>
       </SettingsSection>
 
+      {/* Local inference */}
+      <SettingsSection
+        title="Local Inference"
+        description="Offline GGUF models run in llama.cpp's llama-server, which is installed separately."
+      >
+        <LocalInferenceStatus />
+      </SettingsSection>
+
       {/* About */}
       <SettingsSection title="About">
         <div className="rounded-lg border border-border/50 bg-muted/30 p-4 space-y-4">
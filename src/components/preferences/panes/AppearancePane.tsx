@@ -9,7 +9,8 @@ import {
   SelectValue,
 } from '@/components/ui/select'
 import { useTheme } from '@/hooks/use-theme'
-import { useSavePreferences } from '@/services/preferences'
+import { usePreferences, useSavePreferences } from '@/services/preferences'
+import { defaultPreferences } from '@/types/preferences'
 
 const SettingsField: React.FC<{
   label: string
@@ -40,6 +41,7 @@ const SettingsSection: React.FC<{
 
 export const AppearancePane: React.FC = () => {
   const { theme, setTheme } = useTheme()
+  const { data: preferences } = usePreferences()
   const savePreferences = useSavePreferences()
 
   const handleThemeChange = useCallback(
@@ -47,10 +49,13 @@ export const AppearancePane: React.FC = () => {
       // Update the theme provider immediately for instant UI feedback
       setTheme(value)
 
-      // Persist the theme preference to disk
-      savePreferences.mutate({ theme: value })
+      // Persist the theme preference to disk, keeping the other preferences intact
+      savePreferences.mutate({
+        ...(preferences ?? defaultPreferences),
+        theme: value,
+      })
     },
-    [setTheme, savePreferences]
+    [setTheme, savePreferences, preferences]
   )
 
   return (
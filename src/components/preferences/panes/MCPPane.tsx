@@ -40,10 +40,16 @@ import {
   AlertCircle,
 } from 'lucide-react'
 import { useMCPStore } from '@/store/mcp-store'
-import { startMCPServer, stopMCPServer, isServerRunning } from '@/services/mcp'
+import {
+  applyMCPServerConfig,
+  startMCPServer,
+  stopMCPServer,
+  isServerRunning,
+} from '@/services/mcp'
 import {
   isStdioConfig,
   isHttpConfig,
+  type MCPLogLevel,
   type MCPServerConfig,
   type MCPServerConfigStdio,
   type MCPServerConfigHttp,
@@ -260,9 +266,18 @@ interface FormErrors {
   url?: string
   args?: string
   env?: string
+  startupTimeout?: string
   headers?: string
 }
 
+function parseLines(value: string): string[] | undefined {
+  const paths = value
+    .split('\n')
+    .map((line) => line.trim())
+    .filter(Boolean)
+  return paths.length > 0 ? paths : undefined
+}
+
 function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
   const [transport, setTransport] = useState<MCPTransportType>(
     server?.transport || 'stdio'
@@ -283,6 +298,30 @@ function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
     }
     return ''
   })
+  const [isolateEnv, setIsolateEnv] = useState(
+    server && isStdioConfig(server) ? !!server.isolateEnv : false
+  )
+  const [allowElevated, setAllowElevated] = useState(
+    server && isStdioConfig(server) ? !!server.allowElevated : false
+  )
+  const [watchPaths, setWatchPaths] = useState(
+    server && isStdioConfig(server) && server.watchPaths
+      ? server.watchPaths.join('\n')
+      : ''
+  )
+  const [logLevel, setLogLevel] = useState<MCPLogLevel | 'all'>(
+    (server && isStdioConfig(server) && server.logLevel) || 'all'
+  )
+  const [stderrSuppress, setStderrSuppress] = useState(
+    server && isStdioConfig(server) && server.stderrSuppress
+      ? server.stderrSuppress.join('\n')
+      : ''
+  )
+  const [startupTimeout, setStartupTimeout] = useState(
+    server && isStdioConfig(server) && server.startupTimeoutMs
+      ? String(server.startupTimeoutMs / 1000)
+      : ''
+  )
 
   // HTTP fields
   const [url, setUrl] = useState(
@@ -306,6 +345,7 @@ function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
         command,
         args,
         env,
+        startupTimeout,
       })
 
       if (!result.success) {
@@ -348,6 +388,14 @@ function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
         command,
         args: parseArgsString(args),
         env: Object.keys(envObject).length > 0 ? envObject : undefined,
+        isolateEnv: isolateEnv || undefined,
+        allowElevated: allowElevated || undefined,
+        watchPaths: parseLines(watchPaths),
+        logLevel: logLevel === 'all' ? undefined : logLevel,
+        stderrSuppress: parseLines(stderrSuppress),
+        startupTimeoutMs: Number(startupTimeout) > 0
+          ? Number(startupTimeout) * 1000
+          : undefined,
         enabled,
       }
       onSave(stdioConfig)
@@ -363,7 +411,23 @@ function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
       }
       onSave(httpConfig)
     }
-  }, [transport, name, command, args, env, url, headers, server, onSave])
+  }, [
+    transport,
+    name,
+    command,
+    args,
+    env,
+    isolateEnv,
+    allowElevated,
+    startupTimeout,
+    watchPaths,
+    logLevel,
+    stderrSuppress,
+    url,
+    headers,
+    server,
+    onSave,
+  ])
 
   const isValid = useMemo(() => {
     return name.trim() && (transport === 'stdio' ? command.trim() : url.trim())
@@ -461,6 +525,79 @@ function ServerEditor({ server, onSave, onCancel }: ServerEditorProps) {
                   className={`min-h-[80px] font-mono text-xs ${errors.env ? 'border-destructive' : ''}`}
                 />
               </SettingsField>
+
+              <SettingsField
+                label="Isolate Environment"
+                description="Start with only PATH, HOME and the variables above, so API keys from your shell aren't passed to the server"
+              >
+                <Switch checked={isolateEnv} onCheckedChange={setIsolateEnv} />
+              </SettingsField>
+
+              <SettingsField
+                label="Allow Elevated Privileges"
+                description="Servers that run through sudo, use a setuid binary, listen on a privileged port or mount the Docker socket only start with this on"
+              >
+                <Switch checked={allowElevated} onCheckedChange={setAllowElevated} />
+              </SettingsField>
+
+              <SettingsField
+                label="Watch Paths"
+                description="Absolute paths of files or folders, one per line. The server restarts when any of them changes, for developing your own servers."
+              >
+                <Textarea
+                  value={watchPaths}
+                  onChange={(e) => setWatchPaths(e.target.value)}
+                  placeholder="/Users/me/code/my-server/src"
+                  className="min-h-[60px] font-mono text-xs"
+                />
+              </SettingsField>
+
+              <SettingsField
+                label="Startup Timeout"
+                description="Seconds the server may take to start before it is stopped (default 60). Time spent visibly installing packages doesn't count."
+                error={errors.startupTimeout}
+              >
+                <Input
+                  value={startupTimeout}
+                  onChange={(e) => setStartupTimeout(e.target.value)}
+                  placeholder="60"
+                  inputMode="numeric"
+                  className={errors.startupTimeout ? 'border-destructive' : ''}
+                />
+              </SettingsField>
+
+              <SettingsField
+                label="Log Level"
+                description="Least severe stderr output shown in the app. The server log file always keeps everything."
+              >
+                <Select
+                  value={logLevel}
+                  onValueChange={(v) => setLogLevel(v as MCPLogLevel | 'all')}
+                >
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value="all">Everything</SelectItem>
+                    <SelectItem value="debug">Debug</SelectItem>
+                    <SelectItem value="info">Info</SelectItem>
+                    <SelectItem value="warn">Warnings</SelectItem>
+                    <SelectItem value="error">Errors only</SelectItem>
+                  </SelectContent>
+                </Select>
+              </SettingsField>
+
+              <SettingsField
+                label="Suppress stderr"
+                description="Regular expressions, one per line. Matching stderr lines are hidden in the app."
+              >
+                <Textarea
+                  value={stderrSuppress}
+                  onChange={(e) => setStderrSuppress(e.target.value)}
+                  placeholder="^Heartbeat"
+                  className="min-h-[60px] font-mono text-xs"
+                />
+              </SettingsField>
             </>
           ) : (
             <>
@@ -530,6 +667,7 @@ export const MCPPane: React.FC = () => {
     (serverConfig: MCPServerConfig) => {
       if (editingServer?.id) {
         updateServer(editingServer.id, serverConfig)
+        void applyMCPServerConfig({ ...serverConfig, id: editingServer.id })
       } else {
         addServer(serverConfig)
       }
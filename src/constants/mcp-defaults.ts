@@ -33,6 +33,8 @@ export const MCP_CLIENT_INFO = {
 
 export const MCP_TIMEOUTS = {
   REQUEST_TIMEOUT: 30000,
+  /** Default time a stdio server gets to answer `initialize` */
+  STARTUP_TIMEOUT: 60000,
   SSE_CONNECTION_TIMEOUT: 10000,
   DATA_COLLECT_INTERVAL: 100,
 } as const
@@ -8,10 +8,26 @@ import { useChatStore, selectActiveMessages, type ToolCallData } from '@/store/c
 import { useMCPStore } from '@/store/mcp-store'
 import { sendChatRequestWithTools, generateImage } from '@/services/llm'
 import { callTool, findToolServer } from '@/services/mcp'
+import {
+  executeOpenApiTool,
+  getOpenApiToolDefinitions,
+} from '@/lib/openapi-tools'
+import {
+  executeGraphQlTool,
+  getGraphQlToolDefinitions,
+} from '@/lib/graphql-tools'
+import {
+  executeDatabaseTool,
+  getDatabaseToolDefinitions,
+} from '@/lib/databases'
+import {
+  executeInspectorTool,
+  getInspectorToolDefinitions,
+} from '@/lib/inspectors'
 import { logger } from '@/lib/logger'
 import { handleError, isAbortError } from '@/lib/errors'
 import { MULTIMODAL_MODELS } from '@/constants/models'
-import type { MCPTool, ToolCall } from '@/types/mcp'
+import type { MCPTool, MCPToolCallResult, ToolCall } from '@/types/mcp'
 import type { ContentPart, ImageContentPart } from '@/types/multimodal'
 
 // ============================================
@@ -35,6 +51,60 @@ export interface UseChatActionsReturn {
   abortControllerRef: React.MutableRefObject<AbortController | null>
 }
 
+// ============================================
+// API Tools
+// ============================================
+
+interface ApiTool {
+  tool: MCPTool
+  execute: (
+    name: string,
+    args: Record<string, unknown>
+  ) => Promise<MCPToolCallResult>
+}
+
+/**
+ * Tools generated from OpenAPI specs and GraphQL endpoints, and the SQL
+ * and inspector tools, by name
+ */
+async function loadApiTools(): Promise<Map<string, ApiTool>> {
+  const sources = [
+    {
+      kind: 'OpenAPI',
+      load: getOpenApiToolDefinitions,
+      execute: executeOpenApiTool,
+    },
+    {
+      kind: 'GraphQL',
+      load: getGraphQlToolDefinitions,
+      execute: executeGraphQlTool,
+    },
+    {
+      kind: 'SQL',
+      load: getDatabaseToolDefinitions,
+      execute: executeDatabaseTool,
+    },
+    {
+      kind: 'inspector',
+      load: getInspectorToolDefinitions,
+      execute: executeInspectorTool,
+    },
+  ]
+  const tools = new Map<string, ApiTool>()
+
+  for (const { kind, load, execute } of sources) {
+    try {
+      for (const tool of await load()) {
+        tools.set(tool.name, { tool, execute })
+      }
+    } catch (error) {
+      logger.warn(`Failed to load ${kind} tools`, { error })
+    }
+  }
+
+  return tools
+}
+
 // ============================================
 // Hook Implementation
 // ============================================
@@ -47,8 +117,8 @@ export function useChatActions(
   // Store selectors
   const isGenerating = useChatStore(state => state.isGenerating)
 
-  // Get available tools from MCP servers
-  const getAvailableTools = useCallback((): MCPTool[] => {
+  // Get available tools from MCP servers and registered HTTP APIs
+  const getAvailableTools = useCallback(async (): Promise<MCPTool[]> => {
     const { servers, serverStates } = useMCPStore.getState()
     const tools: MCPTool[] = []
     
@@ -59,6 +129,10 @@ export function useChatActions(
         tools.push(...state.tools)
       }
     }
+
+    for (const { tool } of (await loadApiTools()).values()) {
+      tools.push(tool)
+    }
     
     return tools
   }, [])
@@ -71,12 +145,20 @@ export function useChatActions(
     ): Promise<{ id: string; name: string; result: string }[]> => {
       const results: { id: string; name: string; result: string }[] = []
       const { updateToolCallStatus } = useChatStore.getState()
+      let apiTools: Map<string, ApiTool> | undefined
 
       for (const tc of toolCalls) {
         updateToolCallStatus(messageId, tc.id, 'executing')
 
         const serverId = findToolServer(tc.name)
         if (!serverId) {
+          apiTools ??= await loadApiTools()
+        }
+        const execute = serverId
+          ? (name: string, args: Record<string, unknown>) =>
+              callTool(serverId, name, args)
+          : apiTools?.get(tc.name)?.execute
+        if (!execute) {
           const errorResult = `Error: Tool "${tc.name}" not found in any connected MCP server`
           updateToolCallStatus(messageId, tc.id, 'error', errorResult)
           results.push({ id: tc.id, name: tc.name, result: errorResult })
@@ -84,7 +166,7 @@ export function useChatActions(
         }
 
         try {
-          const result = await callTool(serverId, tc.name, tc.arguments)
+          const result = await execute(tc.name, tc.arguments)
           const resultText = result.content
             .map(c => c.text || JSON.stringify(c))
             .join('\n')
@@ -195,7 +277,7 @@ export function useChatActions(
         setMessageStreaming,
       } = useChatStore.getState()
 
-      const tools = getAvailableTools()
+      const tools = await getAvailableTools()
 
       // Build system prompt with tool awareness
       let enhancedSystemPrompt = systemPrompt
@@ -0,0 +1,163 @@
+/**
+ * Agent Runs
+ * The backend runs a tool-using chat to its end: it streams each step,
+ * calls the MCP tools the model asks for and feeds their results back.
+ * The trace arrives as events; each step's text streams as the usual
+ * `llm-*` events under the step's request id. Tool calls go through the
+ * tool policy, and the ones it asks about wait for
+ * `approveAgentToolCall`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { ProviderConfig, RegisteredProvider } from './chat-proxy'
+import { withSecondFactor } from './second-factor'
+
+export type AgentRunStatus =
+  | 'completed'
+  | 'max_steps'
+  | 'cancelled'
+  | 'failed'
+
+export type ToolCallStatus =
+  | 'awaiting_approval'
+  | 'running'
+  | 'succeeded'
+  | 'failed'
+  | 'denied'
+
+export interface AgentStepEvent {
+  runId: string
+  step: number
+  /** Id of the step's `llm-*` events */
+  requestId: string
+}
+
+export interface AgentToolCallEvent {
+  runId: string
+  step: number
+  callId: string
+  name: string
+  /** Null if no running server offers the tool */
+  serverId: string | null
+  arguments: unknown
+  destructive: boolean
+  status: ToolCallStatus
+  result: string | null
+  durationMs: number | null
+}
+
+export interface AgentDoneEvent {
+  runId: string
+  status: AgentRunStatus
+  steps: number
+  /** Text of the last step */
+  text: string
+  error: string | null
+  /** The conversation with every step appended */
+  messages: unknown[]
+}
+
+export interface AgentRunHandlers {
+  onStep?: (step: AgentStepEvent) => void
+  /** Called each time a call changes status */
+  onToolCall?: (call: AgentToolCallEvent) => void
+  onDone?: (done: AgentDoneEvent) => void
+  /** Aborting it cancels the run */
+  signal?: AbortSignal
+}
+
+export interface AgentRunOptions {
+  /** Servers whose tools are offered; defaults to every running one */
+  serverIds?: string[]
+  /** Model calls before giving up; defaults to 10, at most 50 */
+  maxSteps?: number
+}
+
+/**
+ * Run a chat with MCP tools until the model answers without calling
+ * one. `request` is the provider's chat completions or messages body.
+ */
+export async function runAgent(
+  provider: ProviderConfig | RegisteredProvider,
+  request: Record<string, unknown>,
+  handlers: AgentRunHandlers = {},
+  options: AgentRunOptions = {}
+): Promise<AgentDoneEvent> {
+  const runId = crypto.randomUUID()
+  const unlisteners: UnlistenFn[] = await Promise.all([
+    listen<AgentStepEvent>('agent-step', event => {
+      if (event.payload.runId === runId) handlers.onStep?.(event.payload)
+    }),
+    listen<AgentToolCallEvent>('agent-tool-call', event => {
+      if (event.payload.runId === runId) {
+        handlers.onToolCall?.(event.payload)
+      }
+    }),
+  ])
+  const cancel = () => {
+    void cancelAgentRun(runId)
+  }
+  handlers.signal?.addEventListener('abort', cancel, { once: true })
+
+  try {
+    const done = await invoke<AgentDoneEvent>('run_agent', {
+      runId,
+      ...('providerId' in provider
+        ? { providerId: provider.providerId }
+        : { providerConfig: provider }),
+      request,
+      serverIds: options.serverIds ?? null,
+      maxSteps: options.maxSteps ?? null,
+    })
+    handlers.onDone?.(done)
+    return done
+  } finally {
+    handlers.signal?.removeEventListener('abort', cancel)
+    unlisteners.forEach(unlisten => unlisten())
+  }
+}
+
+/** Resolves to false if the call is not waiting for approval */
+export function approveAgentToolCall(
+  runId: string,
+  callId: string,
+  approved: boolean
+): Promise<boolean> {
+  return invoke<boolean>('approve_agent_tool_call', {
+    runId,
+    callId,
+    approved,
+  })
+}
+
+/** Resolves to false if the run is not in progress */
+export function cancelAgentRun(runId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_agent_run', { runId })
+}
+
+export type ToolPermission = 'allow' | 'ask' | 'deny'
+
+export interface ToolPolicy {
+  /** For tools without a rule */
+  default: ToolPermission
+  /** By `serverId/toolName`, or `serverId/*` for a whole server */
+  rules: Record<string, ToolPermission>
+  /** Run destructive tools without asking where they are allowed */
+  allowDestructive: boolean
+}
+
+export function getToolPolicy(): Promise<ToolPolicy> {
+  return invoke<ToolPolicy>('get_tool_policy')
+}
+
+/**
+ * Replace the tool policy; allowing destructive tools asks for the second
+ * factor. Resolves to false if the user cancels the prompt.
+ */
+export async function setToolPolicy(policy: ToolPolicy): Promise<boolean> {
+  const result = await withSecondFactor(secondFactorCode =>
+    invoke('set_tool_policy', { policy, secondFactorCode }).then(() => true)
+  )
+  return result === true
+}
@@ -0,0 +1,66 @@
+/**
+ * Image Annotations
+ * Crops, arrows, ellipses, boxes and blurred regions drawn on an image
+ * attachment, saved in the backend and baked into a new PNG attachment
+ * before the image is sent to a vision model. Positions and sizes are
+ * fractions (0 to 1) of the original image's width and height.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { StoredAttachment } from './attachments'
+
+export interface Point {
+  x: number
+  y: number
+}
+
+export interface Rect {
+  x: number
+  y: number
+  width: number
+  height: number
+}
+
+export interface AnnotationStyle {
+  /** `#rrggbb` or `#rrggbbaa`; red by default */
+  color?: string
+  /** Fraction of the image's shorter side */
+  stroke?: number
+}
+
+/**
+ * Blurs are applied first and the crop last; other marks are drawn in
+ * order. An image can be cropped once.
+ */
+export type Annotation =
+  | { type: 'crop'; rect: Rect }
+  | ({ type: 'arrow'; from: Point; to: Point } & AnnotationStyle)
+  | ({ type: 'ellipse'; rect: Rect } & AnnotationStyle)
+  | ({ type: 'rectangle'; rect: Rect } & AnnotationStyle)
+  | { type: 'blur'; rect: Rect }
+
+export function getImageAnnotations(attachment: string): Promise<Annotation[]> {
+  return invoke<Annotation[]>('get_image_annotations', { attachment })
+}
+
+/** Replace an attachment's saved annotations; an empty list removes them */
+export function saveImageAnnotations(
+  attachment: string,
+  annotations: Annotation[]
+): Promise<void> {
+  return invoke('save_image_annotations', { attachment, annotations })
+}
+
+/**
+ * Render an image attachment with annotations (the saved ones if none are
+ * given) into a new PNG attachment; the original is left as it was
+ */
+export function renderAnnotatedImage(
+  attachment: string,
+  annotations?: Annotation[]
+): Promise<StoredAttachment> {
+  return invoke<StoredAttachment>('render_annotated_image', {
+    attachment,
+    annotations,
+  })
+}
@@ -0,0 +1,25 @@
+/**
+ * API Keys
+ * Keys for the built-in providers, stored in the OS keychain by the Rust
+ * backend. They can be set, checked and removed but not read back: backend
+ * features that call a model look the key up themselves.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { BackendProvider } from './provider-models'
+
+export function setApiKey(
+  provider: BackendProvider,
+  key: string
+): Promise<void> {
+  return invoke('set_api_key', { provider, key })
+}
+
+export function hasApiKey(provider: BackendProvider): Promise<boolean> {
+  return invoke<boolean>('has_api_key', { provider })
+}
+
+/** Removing a key that isn't stored is not an error */
+export function deleteApiKey(provider: BackendProvider): Promise<void> {
+  return invoke('delete_api_key', { provider })
+}
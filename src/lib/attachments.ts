@@ -0,0 +1,52 @@
+/**
+ * Attachment Store
+ * Files the user attaches are stored once by content hash. A message
+ * refers to a stored file with a `blobRef` content part instead of
+ * inline `data`; files no message refers to are garbage collected.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type AttachmentSource =
+  | { kind: 'bytes'; data: string; mimeType?: string }
+  | { kind: 'path'; path: string; mimeType?: string }
+
+export interface StoredAttachment {
+  /** Put in a content part as `blobRef` */
+  hash: string
+  mimeType: string | null
+  /** Bytes of the stored base64 payload */
+  size: number
+}
+
+export interface AttachmentData {
+  hash: string
+  mimeType: string | null
+  /** Base64 encoded contents */
+  data: string
+}
+
+export interface AttachmentUsage {
+  blobCount: number
+  totalBytes: number
+  referencedBytes: number
+  unreferencedBytes: number
+  /** Freed by the next garbage collection pass */
+  reclaimableBytes: number
+  gracePeriodSecs: number
+}
+
+/** Stores base64 `data` or the file at `path`, up to 25 MB */
+export function storeAttachment(
+  source: AttachmentSource
+): Promise<StoredAttachment> {
+  return invoke<StoredAttachment>('store_attachment', { source })
+}
+
+export function getAttachment(hash: string): Promise<AttachmentData> {
+  return invoke<AttachmentData>('get_attachment', { hash })
+}
+
+export function getAttachmentUsage(): Promise<AttachmentUsage> {
+  return invoke<AttachmentUsage>('get_attachment_usage')
+}
@@ -0,0 +1,114 @@
+/**
+ * Audio Recording and Transcription
+ * Voice input: the backend records the default microphone and stores the
+ * recording as a WAV attachment. `transcribeAudio` turns an audio
+ * attachment into text through an OpenAI-compatible API or, in builds
+ * with local-whisper, whisper.cpp.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { StoredAttachment } from './attachments'
+import type { ProviderConfig } from './chat-proxy'
+
+export interface RecordedAudio {
+  attachment: StoredAttachment
+  durationMs: number
+}
+
+export interface AudioRecording {
+  /** Ends the recording and stores it as an attachment */
+  stop: () => Promise<RecordedAudio>
+  /** Ends the recording and discards it */
+  cancel: () => Promise<void>
+}
+
+/**
+ * Starts recording from the microphone in mono. `onLimit` is called when
+ * the recording reached its maximum length; stop it then.
+ */
+export async function startRecording(
+  onLimit?: () => void
+): Promise<AudioRecording> {
+  const recordingId = crypto.randomUUID()
+  const unlisten = await listen<{ recordingId: string }>(
+    'audio-recording-limit',
+    event => {
+      if (event.payload.recordingId === recordingId) onLimit?.()
+    }
+  )
+  try {
+    await invoke('start_audio_recording', { recordingId })
+  } catch (error) {
+    unlisten()
+    throw error
+  }
+  return {
+    stop: async () => {
+      unlisten()
+      return invoke<RecordedAudio>('stop_audio_recording', { recordingId })
+    },
+    cancel: async () => {
+      unlisten()
+      await invoke('cancel_audio_recording', { recordingId })
+    },
+  }
+}
+
+export type TranscriptionEngine =
+  | ({
+      type: 'openai'
+      /** `gpt-4o-mini-transcribe` by default */
+      model?: string
+      /** ISO 639-1 code; detected when left out */
+      language?: string
+    } & (
+      | { providerConfig: ProviderConfig }
+      | { providerId: string }
+    ))
+  | {
+      type: 'whisper_cpp'
+      /** A ggml model file, e.g. `ggml-base.en.bin` */
+      modelPath: string
+      language?: string
+    }
+
+export interface TranscriptionPartial {
+  transcriptionId: string
+  /** What was added since the last event */
+  delta: string
+  /** The transcript so far */
+  text: string
+}
+
+export interface Transcription {
+  transcriptionId: string
+  text: string
+  durationMs: number
+}
+
+/** Transcribes an audio attachment, reporting the transcript as it grows */
+export async function transcribeAudio(
+  attachment: string,
+  engine: TranscriptionEngine,
+  onPartial?: (partial: TranscriptionPartial) => void
+): Promise<Transcription> {
+  const transcriptionId = crypto.randomUUID()
+  const unlisten: UnlistenFn = await listen<TranscriptionPartial>(
+    'transcription-partial',
+    event => {
+      if (event.payload.transcriptionId === transcriptionId) {
+        onPartial?.(event.payload)
+      }
+    }
+  )
+  try {
+    return await invoke<Transcription>('transcribe_audio', {
+      transcriptionId,
+      attachment,
+      engine,
+    })
+  } finally {
+    unlisten()
+  }
+}
@@ -0,0 +1,56 @@
+/**
+ * Budgets
+ * Monthly spending limits per provider, checked in the Rust backend
+ * against the estimated cost of every model request. Providers are the
+ * built-in ids, registered provider ids, or the API host of an ad-hoc
+ * configuration. Months are calendar months in UTC.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface BudgetStatus {
+  provider: string
+  /** US dollars per month; null without a limit */
+  usdLimit: number | null
+  /** Requests are refused once the limit is used up */
+  hardStop: boolean
+  /** `YYYY-MM` */
+  month: string
+  /** Estimated, in US dollars */
+  spentUsd: number
+  requests: number
+}
+
+export interface LlmBudgetWarningEvent {
+  provider: string
+  month: string
+  spentUsd: number
+  usdLimit: number
+  /** The share of the limit crossed: 80 or 100 */
+  percent: number
+  /** Requests to the provider are refused from now on */
+  hardStop: boolean
+}
+
+/** Set a provider's monthly limit, or remove it with `usdLimit` null */
+export function setBudget(
+  provider: string,
+  usdLimit: number | null,
+  hardStop = false
+): Promise<BudgetStatus> {
+  return invoke<BudgetStatus>('set_budget', { provider, usdLimit, hardStop })
+}
+
+/** Providers with a budget or with spend this month */
+export function getBudgets(): Promise<BudgetStatus[]> {
+  return invoke<BudgetStatus[]>('get_budgets')
+}
+
+export function onBudgetWarning(
+  callback: (warning: LlmBudgetWarningEvent) => void
+): Promise<UnlistenFn> {
+  return listen<LlmBudgetWarningEvent>('llm-budget-warning', event =>
+    callback(event.payload)
+  )
+}
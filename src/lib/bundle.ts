@@ -0,0 +1,76 @@
+/**
+ * Conversation Bundles
+ * `.nexus` files carry conversations between machines. Opening one only
+ * offers it: a bundle that launched the app is left pending for the new
+ * window; a window that is already open receives it as an event. Nothing
+ * is imported until `BundleImportDialog` confirms and calls `importBundle`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface BundleEntry {
+  id: string
+  title: string
+  messageCount: number
+  file: string
+}
+
+export interface BundleManifest {
+  format: string
+  version: number
+  exportedAt: number
+  appVersion: string
+  conversations: BundleEntry[]
+}
+
+export interface BundleImportReport {
+  /** Name of the bundle file */
+  bundle: string
+  /** Conversations added, renamed ones under their new id */
+  imported: string[]
+  /** Identical to a local conversation */
+  skipped: string[]
+  /** Imported next to a different local conversation with the same id */
+  renamed: { originalId: string; newId: string }[]
+}
+
+/** A bundle that was opened, waiting for the user to confirm its import */
+export interface OpenedBundle {
+  /** To pass to `importBundle` */
+  path: string
+  /** Name of the bundle file */
+  bundle: string
+  manifest: BundleManifest
+}
+
+/** Writes the conversations to a `.nexus` bundle at `path` */
+export function exportBundle(
+  conversationIds: string[],
+  path: string
+): Promise<BundleManifest> {
+  return invoke<BundleManifest>('export_bundle', { conversationIds, path })
+}
+
+export function importBundle(path: string): Promise<BundleImportReport> {
+  return invoke<BundleImportReport>('import_bundle', { path })
+}
+
+/** The bundle the app was opened with, once */
+export function takePendingBundle(): Promise<OpenedBundle | null> {
+  return invoke<OpenedBundle | null>('take_pending_bundle')
+}
+
+export function onBundleOpened(
+  callback: (opened: OpenedBundle) => void
+): Promise<UnlistenFn> {
+  return listen<OpenedBundle>('bundle-opened', event => callback(event.payload))
+}
+
+export function onBundleImported(
+  callback: (report: BundleImportReport) => void
+): Promise<UnlistenFn> {
+  return listen<BundleImportReport>('bundle-imported', event =>
+    callback(event.payload)
+  )
+}
@@ -0,0 +1,34 @@
+/**
+ * Calendar Events
+ * Writes model-produced event details to an .ics file and opens it in the
+ * default calendar app.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface EventDetails {
+  title: string
+  description?: string
+  location?: string
+  /** ISO 8601 date or date-time; times without an offset are local */
+  start: string
+  /** ISO 8601 date or date-time; defaults to start + durationMinutes */
+  end?: string
+  /** Used when `end` is missing; defaults to 60 */
+  durationMinutes?: number
+  /** Whole-day event; times in start and end are ignored */
+  allDay?: boolean
+  /** Email addresses */
+  attendees?: string[]
+}
+
+/**
+ * Validate the event, write it as an .ics file and open it with the default
+ * calendar app unless `open` is false. Returns the file path.
+ */
+export function createIcs(
+  eventDetails: EventDetails,
+  open = true
+): Promise<string> {
+  return invoke<string>('create_ics', { eventDetails, open })
+}
@@ -3,6 +3,7 @@
  * Functions for exporting conversations to different formats
  */
 
+import { invoke } from '@tauri-apps/api/core'
 import { save } from '@tauri-apps/plugin-dialog'
 import { writeTextFile } from '@tauri-apps/plugin-fs'
 import { logger } from '@/lib/logger'
@@ -16,6 +17,15 @@ import type { ContentPart } from '@/types/multimodal'
 
 export type ExportFormat = 'json' | 'markdown'
 
+/** Formats the backend renders, including standalone HTML */
+export type BackendExportFormat = ExportFormat | 'html'
+
+const EXTENSIONS: Record<BackendExportFormat, string> = {
+  markdown: 'md',
+  html: 'html',
+  json: 'json',
+}
+
 export interface ExportOptions {
   format: ExportFormat
   includeSystemPrompt?: boolean
@@ -240,7 +250,10 @@ export function exportToMarkdown(
 /**
  * Get default filename for export
  */
-function getDefaultFilename(title: string, format: ExportFormat): string {
+function getDefaultFilename(
+  title: string,
+  format: BackendExportFormat
+): string {
   // Sanitize title for filename
   const sanitized = title
     .replace(/[^a-zA-Z0-9\s-]/g, '')
@@ -248,9 +261,7 @@ function getDefaultFilename(title: string, format: ExportFormat): string {
     .substring(0, 50)
 
   const date = new Date().toISOString().split('T')[0]
-  const extension = format === 'json' ? 'json' : 'md'
-
-  return `${sanitized}_${date}.${extension}`
+  return `${sanitized}_${date}.${EXTENSIONS[format]}`
 }
 
 /**
@@ -389,3 +400,71 @@ export async function exportMultipleConversations(
   }
 }
 
+
+// ============================================
+// Backend Export
+// ============================================
+
+/**
+ * Render a conversation in the backend, with code blocks, tool calls and
+ * attachments, and save it where the user chooses. HTML exports embed
+ * attachments so the page stands on its own.
+ */
+export async function exportConversationToFile(
+  conversation: Conversation,
+  format: BackendExportFormat
+): Promise<ExportResult> {
+  try {
+    const filePath = await save({
+      defaultPath: getDefaultFilename(conversation.title, format),
+      filters: [{ name: format.toUpperCase(), extensions: [EXTENSIONS[format]] }],
+    })
+    if (!filePath) {
+      return { success: false, error: 'Export cancelled' }
+    }
+
+    await invoke('export_conversation', {
+      id: conversation.id,
+      format,
+      path: filePath,
+    })
+    logger.info(`Exported conversation to ${filePath}`, {
+      format,
+      conversationId: conversation.id,
+    })
+    return { success: true, filePath }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error)
+    logger.error(`Failed to export conversation: ${errorMessage}`)
+    return { success: false, error: errorMessage }
+  }
+}
+
+/**
+ * Export every conversation the backend knows, one file each, into a zip
+ * archive where the user chooses.
+ */
+export async function exportAllConversations(
+  format: BackendExportFormat
+): Promise<ExportResult> {
+  try {
+    const filePath = await save({
+      defaultPath: `conversations_export_${new Date().toISOString().split('T')[0]}.zip`,
+      filters: [{ name: 'Zip archive', extensions: ['zip'] }],
+    })
+    if (!filePath) {
+      return { success: false, error: 'Export cancelled' }
+    }
+
+    const count = await invoke<number>('export_all_conversations', {
+      format,
+      path: filePath,
+    })
+    logger.info(`Exported ${count} conversations to ${filePath}`, { format })
+    return { success: true, filePath }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error)
+    logger.error(`Failed to export conversations: ${errorMessage}`)
+    return { success: false, error: errorMessage }
+  }
+}
@@ -0,0 +1,182 @@
+/**
+ * Chat Completion Proxy
+ * Streams chat completions from OpenAI-compatible APIs and Anthropic's
+ * Messages API through the backend, which makes the HTTPS request, so CORS
+ * doesn't apply and API keys stay out of webview requests. Deltas arrive as
+ * `llm-chunk` events, followed by `llm-done`, `llm-error` or
+ * `llm-cancelled`, all tagged with the request id. Anthropic streams are
+ * normalized to the same shape. Transient failures before the stream
+ * starts are retried by the backend, announced with `llm-retrying`. With
+ * the response cache on, a repeated request is answered from the cache as
+ * one chunk and a `llm-done` with `cached` set.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+/** `openai` (the default) for OpenAI-compatible chat completions */
+export type ProviderApi = 'openai' | 'anthropic'
+
+export interface ProviderConfig {
+  api?: ProviderApi
+  /** e.g. `https://api.openai.com/v1` or `https://api.anthropic.com/v1` */
+  baseUrl: string
+  apiKey?: string
+  /** Extra headers, e.g. `HTTP-Referer` for OpenRouter */
+  headers?: Record<string, string>
+}
+
+/** Part of a tool call; arguments arrive in pieces to concatenate */
+export interface ToolCallDelta {
+  index: number
+  id?: string
+  name?: string
+  arguments?: string
+}
+
+export interface LlmChunkEvent {
+  requestId: string
+  delta?: string
+  toolCalls?: ToolCallDelta[]
+}
+
+export interface LlmDoneEvent {
+  requestId: string
+  /** OpenAI finish reasons: `stop`, `length`, `tool_calls`, ... */
+  finishReason: string | null
+  usage: { inputTokens: number; outputTokens: number } | null
+  /** Answered from the response cache */
+  cached: boolean
+}
+
+export interface LlmErrorEvent {
+  requestId: string
+  message: string
+  /** HTTP status, when the provider answered with an error */
+  status: number | null
+}
+
+export interface LlmCancelledEvent {
+  requestId: string
+  /** Tokens used up to the cancellation */
+  usage: { inputTokens: number; outputTokens: number }
+  /** Some of `usage` was estimated rather than reported by the provider */
+  usageEstimated: boolean
+}
+
+/**
+ * A transient failure, after which the request is sent again in `delayMs`.
+ * `requestId` is null for the backend's own model requests.
+ */
+export interface LlmRetryingEvent {
+  requestId: string | null
+  /** Host or provider the request goes to */
+  target: string
+  /** The attempt about to be made, counting from 1 */
+  attempt: number
+  maxAttempts: number
+  delayMs: number
+  /** HTTP status of the failed attempt; null for connection errors */
+  status: number | null
+  reason: string
+}
+
+/** A provider registered with `addProvider`, whose key the backend holds */
+export interface RegisteredProvider {
+  providerId: string
+}
+
+export interface ChatStreamHandlers {
+  onChunk?: (chunk: LlmChunkEvent) => void
+  onDone?: (done: LlmDoneEvent) => void
+  onError?: (error: LlmErrorEvent) => void
+  onCancelled?: (cancelled: LlmCancelledEvent) => void
+  /** e.g. to show "retrying in 4s…" */
+  onRetrying?: (retrying: LlmRetryingEvent) => void
+  /** Aborting it stops the stream, which then ends with `onCancelled` */
+  signal?: AbortSignal
+}
+
+/**
+ * Stream a chat completion from a provider given in full or registered
+ * by id. `request` is the provider's chat completions or messages body;
+ * streaming is switched on by the backend. Resolves with the id the events
+ * were tagged with once the stream has ended, and rejects with the message
+ * `onError` also receives.
+ */
+export async function streamChatCompletion(
+  provider: ProviderConfig | RegisteredProvider,
+  request: Record<string, unknown>,
+  handlers: ChatStreamHandlers
+): Promise<string> {
+  const requestId = crypto.randomUUID()
+  // Events may be delivered after the command resolves
+  let markDone = () => {}
+  const done = new Promise<void>(resolve => {
+    markDone = resolve
+  })
+  const unlisteners: UnlistenFn[] = await Promise.all([
+    listen<LlmChunkEvent>('llm-chunk', event => {
+      if (event.payload.requestId === requestId) {
+        handlers.onChunk?.(event.payload)
+      }
+    }),
+    listen<LlmDoneEvent>('llm-done', event => {
+      if (event.payload.requestId === requestId) {
+        handlers.onDone?.(event.payload)
+        markDone()
+      }
+    }),
+    listen<LlmErrorEvent>('llm-error', event => {
+      if (event.payload.requestId === requestId) {
+        handlers.onError?.(event.payload)
+      }
+    }),
+    listen<LlmCancelledEvent>('llm-cancelled', event => {
+      if (event.payload.requestId === requestId) {
+        handlers.onCancelled?.(event.payload)
+        markDone()
+      }
+    }),
+    listen<LlmRetryingEvent>('llm-retrying', event => {
+      if (event.payload.requestId === requestId) {
+        handlers.onRetrying?.(event.payload)
+      }
+    }),
+  ])
+  const cancel = () => {
+    void cancelLlmRequest(requestId)
+  }
+  handlers.signal?.addEventListener('abort', cancel, { once: true })
+
+  try {
+    await invoke('stream_chat_completion', {
+      requestId,
+      ...('providerId' in provider
+        ? { providerId: provider.providerId }
+        : { providerConfig: provider }),
+      request,
+    })
+    await done
+    return requestId
+  } finally {
+    handlers.signal?.removeEventListener('abort', cancel)
+    unlisteners.forEach(unlisten => unlisten())
+  }
+}
+
+/**
+ * Stop a streaming chat completion; resolves to false if it is not
+ * streaming
+ */
+export function cancelLlmRequest(requestId: string): Promise<boolean> {
+  return invoke<boolean>('cancel_llm_request', { requestId })
+}
+
+/**
+ * Delete every cached chat completion response; resolves to how many
+ * there were
+ */
+export function clearLlmCache(): Promise<number> {
+  return invoke<number>('clear_llm_cache')
+}
@@ -0,0 +1,50 @@
+/**
+ * Clipboard History
+ * With `clipboardHistory` switched on in the preferences, what the user
+ * copies is kept in memory, newest first, for pasting into a chat later.
+ * Copies made while an excluded app is in front are skipped. Images can
+ * be stored as attachments, from the history or straight from the
+ * clipboard.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { StoredAttachment } from './attachments'
+
+export type ClipboardEntry = {
+  id: number
+  /** UNIX timestamp (milliseconds) */
+  capturedAt: number
+  /** The app in front when it was copied, where known */
+  sourceApp: string | null
+} & (
+  | { kind: 'text'; text: string; truncated: boolean }
+  /** `thumbnail` is a base64 PNG */
+  | { kind: 'image'; width: number; height: number; thumbnail: string }
+)
+
+export function getClipboardHistory(): Promise<ClipboardEntry[]> {
+  return invoke<ClipboardEntry[]>('get_clipboard_history')
+}
+
+export function clearClipboardHistory(): Promise<void> {
+  return invoke('clear_clipboard_history')
+}
+
+/** Stores an image entry of the history as a PNG attachment */
+export function attachClipboardEntry(id: number): Promise<StoredAttachment> {
+  return invoke<StoredAttachment>('attach_clipboard_entry', { id })
+}
+
+/** Stores the image on the clipboard now as a PNG attachment */
+export function pasteClipboardAsAttachment(): Promise<StoredAttachment> {
+  return invoke<StoredAttachment>('paste_clipboard_as_attachment')
+}
+
+export function onClipboardCaptured(
+  callback: (entry: ClipboardEntry) => void
+): Promise<UnlistenFn> {
+  return listen<ClipboardEntry>('clipboard-captured', event =>
+    callback(event.payload)
+  )
+}
@@ -0,0 +1,127 @@
+/**
+ * Command Metrics
+ * Times every IPC call, including plugin commands, from request to response
+ * and reports the samples to the backend in batches. The backend keeps a
+ * week of them, logs calls over their latency budget, and sums them up per
+ * command with `getCommandMetrics`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface CommandMetrics {
+  command: string
+  calls: number
+  errors: number
+  /** 0.0 to 1.0 */
+  errorRate: number
+  avgMs: number
+  p95Ms: number
+  maxMs: number
+  totalMs: number
+  /** Calls over the latency budget */
+  slowCalls: number
+  /** Null for commands that are long-running by design */
+  budgetMs: number | null
+  avgRequestBytes: number
+  avgResponseBytes: number
+}
+
+interface CommandSample {
+  command: string
+  durationMs: number
+  requestBytes: number
+  responseBytes: number
+  error: boolean
+  at: number
+}
+
+type InvokeFn = (
+  cmd: string,
+  args?: unknown,
+  options?: unknown
+) => Promise<unknown>
+
+const RECORD_COMMAND = 'record_command_metrics'
+const FLUSH_INTERVAL_MS = 10_000
+const MAX_PENDING = 200
+
+let pending: CommandSample[] = []
+let send: InvokeFn | null = null
+
+/** Approximate serialized size of an argument or response */
+function sizeOf(value: unknown): number {
+  if (value === undefined || value === null) return 0
+  if (typeof value === 'string') return value.length
+  if (value instanceof ArrayBuffer) return value.byteLength
+  if (ArrayBuffer.isView(value)) return value.byteLength
+  try {
+    return JSON.stringify(value)?.length ?? 0
+  } catch {
+    return 0
+  }
+}
+
+function flush() {
+  if (!send || pending.length === 0) return
+  const samples = pending
+  pending = []
+  send(RECORD_COMMAND, { samples }).catch(() => {
+    // Metrics are best-effort; never let them surface as errors
+  })
+}
+
+/**
+ * Wrap the invoke layer so every call is measured. Call once at startup;
+ * does nothing outside Tauri or when already installed.
+ */
+export function installCommandMetrics() {
+  const internals = (
+    window as unknown as { __TAURI_INTERNALS__?: { invoke: InvokeFn } }
+  ).__TAURI_INTERNALS__
+  if (!internals || send) return
+
+  const original = internals.invoke.bind(internals)
+  send = original
+  internals.invoke = async (cmd, args, options) => {
+    if (cmd === RECORD_COMMAND) return original(cmd, args, options)
+
+    const started = performance.now()
+    const record = (response: unknown, error: boolean) => {
+      pending.push({
+        command: cmd,
+        durationMs: performance.now() - started,
+        requestBytes: sizeOf(args),
+        responseBytes: sizeOf(response),
+        error,
+        at: Date.now(),
+      })
+      if (pending.length >= MAX_PENDING) flush()
+    }
+
+    try {
+      const response = await original(cmd, args, options)
+      record(response, false)
+      return response
+    } catch (error) {
+      record(error, true)
+      throw error
+    }
+  }
+
+  window.setInterval(flush, FLUSH_INTERVAL_MS)
+  window.addEventListener('beforeunload', flush)
+}
+
+/**
+ * Per-command metrics over the last `sinceMs` milliseconds, or the whole
+ * week kept, slowest in total first.
+ */
+export function getCommandMetrics(sinceMs?: number): Promise<CommandMetrics[]> {
+  flush()
+  return invoke<CommandMetrics[]>('get_command_metrics', { since: sinceMs })
+}
+
+export function clearCommandMetrics(): Promise<void> {
+  pending = []
+  return invoke('clear_command_metrics')
+}
@@ -0,0 +1,88 @@
+/**
+ * Linked Comparison Sessions
+ * A prompt fanned out to several models, each answering in its own
+ * conversation. The backend keeps the threads linked so follow-ups can go to
+ * every model and exports group the answers per prompt.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface ComparisonThread {
+  conversationId: string
+  modelId: string
+}
+
+export interface ComparisonTurn {
+  /** Zero-based position of the turn */
+  turn: number
+  prompt: string
+  /** Milliseconds since the UNIX epoch */
+  sentAt: number
+  /** Conversation id to the id of the user message carrying the prompt, for synchronized scrolling */
+  anchors: Record<string, string>
+}
+
+export interface Comparison {
+  id: string
+  title: string
+  /** Threads in pane order */
+  threads: ComparisonThread[]
+  turns: ComparisonTurn[]
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  /** Milliseconds since the UNIX epoch */
+  updatedAt: number
+}
+
+/**
+ * Link conversations that were just sent the same prompt. `anchors` maps
+ * each conversation to the user message holding the prompt.
+ */
+export function createComparison(
+  title: string,
+  prompt: string,
+  threads: ComparisonThread[],
+  anchors?: Record<string, string>
+): Promise<Comparison> {
+  return invoke<Comparison>('create_comparison', {
+    title,
+    prompt,
+    threads,
+    anchors,
+  })
+}
+
+/** Record a follow-up prompt sent to every linked model */
+export function recordComparisonTurn(
+  comparisonId: string,
+  prompt: string,
+  anchors?: Record<string, string>
+): Promise<ComparisonTurn> {
+  return invoke<ComparisonTurn>('record_comparison_turn', {
+    comparisonId,
+    prompt,
+    anchors,
+  })
+}
+
+/** Comparisons, most recently active first */
+export function listComparisons(): Promise<Comparison[]> {
+  return invoke<Comparison[]>('list_comparisons')
+}
+
+/** The comparison a conversation belongs to, or null */
+export function getLinkedComparison(
+  conversationId: string
+): Promise<Comparison | null> {
+  return invoke<Comparison | null>('get_linked_comparison', { conversationId })
+}
+
+/** Unlink a comparison; its conversations are kept */
+export function deleteComparison(id: string): Promise<void> {
+  return invoke('delete_comparison', { id })
+}
+
+/** Markdown with each prompt followed by every model's answer */
+export function exportComparison(id: string): Promise<string> {
+  return invoke<string>('export_comparison', { id })
+}
@@ -0,0 +1,51 @@
+/**
+ * Conversation Import
+ * Imports history from a ChatGPT or Claude data export into the
+ * conversation store. Conversations imported before are skipped, so a newer
+ * export of the same account only adds what is new.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type ImportSource = 'chatgpt' | 'claude'
+
+export interface ImportReport {
+  /** Ids of the new conversations in the store */
+  imported: string[]
+  /** Conversations imported before */
+  duplicates: number
+  /** Conversations without any user or assistant text */
+  empty: number
+  failed: { sourceId: string | null; error: string }[]
+}
+
+export interface ImportProvenance {
+  conversationId: string
+  source: ImportSource
+  sourceId: string | null
+  /** The export file it was read from */
+  sourcePath: string
+  contentHash: string
+  /** Milliseconds since the UNIX epoch */
+  importedAt: number
+}
+
+/**
+ * Import an export's `conversations.json`, or the zip archive containing
+ * it. Runs as an `import` operation; see `operations.ts` for progress.
+ */
+export function importConversations(
+  path: string,
+  source: ImportSource
+): Promise<ImportReport> {
+  return invoke<ImportReport>('import_conversations', { path, source })
+}
+
+/** Where a conversation was imported from, or null if it was not */
+export function getImportProvenance(
+  conversationId: string
+): Promise<ImportProvenance | null> {
+  return invoke<ImportProvenance | null>('get_import_provenance', {
+    conversationId,
+  })
+}
@@ -0,0 +1,137 @@
+/**
+ * Conversation Sync
+ * Mirrors chat-store conversations to the Rust backend so background
+ * features (scheduled exports, backups) can read them while the UI is idle,
+ * and picks up conversations the backend restores from a backup. Also feeds
+ * the backend's system prompt history.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { logger } from '@/lib/logger'
+import { useChatStore, type Conversation } from '@/store/chat-store'
+
+/** Delay before flushing changes, so streaming responses sync once */
+const SYNC_DEBOUNCE_MS = 2000
+
+/**
+ * Start mirroring conversations to the backend
+ *
+ * @returns A function that stops syncing
+ */
+export function startConversationSync(): () => void {
+  // Last version of each conversation the backend has seen
+  const synced = new Map<string, number>()
+  let syncedWorkspacePrompt: string | undefined
+  let timer: ReturnType<typeof setTimeout> | undefined
+
+  const flush = async () => {
+    const { conversations, isGenerating, systemPrompt } =
+      useChatStore.getState()
+
+    // Wait for the response to finish before syncing a half-written message
+    if (isGenerating) {
+      timer = setTimeout(flush, SYNC_DEBOUNCE_MS)
+      return
+    }
+
+    const current = new Set<string>()
+    for (const conversation of conversations) {
+      current.add(conversation.id)
+      if (synced.get(conversation.id) === conversation.updatedAt) continue
+
+      try {
+        await invoke('sync_conversation', { conversation })
+        synced.set(conversation.id, conversation.updatedAt)
+      } catch (error) {
+        logger.warn('Failed to sync conversation', {
+          id: conversation.id,
+          error,
+        })
+      }
+    }
+
+    if (systemPrompt !== syncedWorkspacePrompt) {
+      try {
+        await invoke('record_system_prompt', { prompt: systemPrompt })
+        syncedWorkspacePrompt = systemPrompt
+      } catch (error) {
+        logger.warn('Failed to record system prompt', { error })
+      }
+    }
+
+    for (const id of synced.keys()) {
+      if (current.has(id)) continue
+      try {
+        await invoke('remove_synced_conversation', { id })
+        synced.delete(id)
+      } catch (error) {
+        logger.warn('Failed to remove synced conversation', { id, error })
+      }
+    }
+  }
+
+  const schedule = () => {
+    clearTimeout(timer)
+    timer = setTimeout(flush, SYNC_DEBOUNCE_MS)
+  }
+
+  const unsubscribe = useChatStore.subscribe((state, previous) => {
+    if (
+      state.conversations !== previous.conversations ||
+      state.systemPrompt !== previous.systemPrompt
+    ) {
+      schedule()
+    }
+  })
+
+  // Conversations merged from a backup by the backend
+  const unlistenRestored = listen<Conversation[]>(
+    'conversations-restored',
+    event => {
+      const restored = event.payload
+      for (const conversation of restored) {
+        synced.set(conversation.id, conversation.updatedAt)
+      }
+      useChatStore.setState(state => ({
+        conversations: [...restored, ...state.conversations],
+      }))
+      logger.info('Restored conversations from backup', {
+        count: restored.length,
+      })
+    }
+  )
+
+  // A system prompt version restored from history
+  const unlistenPromptRestored = listen<{ scope: string; prompt: string }>(
+    'system-prompt-restored',
+    event => {
+      const { scope, prompt } = event.payload
+      useChatStore.setState(state => {
+        if (scope === 'workspace') {
+          return { systemPrompt: prompt }
+        }
+        return {
+          conversations: state.conversations.map(c =>
+            c.id === scope
+              ? { ...c, systemPrompt: prompt, updatedAt: Date.now() }
+              : c
+          ),
+          ...(state.activeConversationId === scope
+            ? { systemPrompt: prompt }
+            : {}),
+        }
+      })
+    }
+  )
+
+  // Initial sync of everything restored from localStorage
+  schedule()
+
+  return () => {
+    unsubscribe()
+    unlistenRestored.then(unlisten => unlisten())
+    unlistenPromptRestored.then(unlisten => unlisten())
+    clearTimeout(timer)
+  }
+}
@@ -0,0 +1,32 @@
+/**
+ * Daemon Mode
+ * With `--daemon` the backend runs without a window and keeps MCP servers
+ * and schedules alive. A window opened later attaches to that backend, so
+ * it resyncs from `getDaemonStatus` instead of assuming a fresh start.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface DaemonStatus {
+  /** The backend runs as a daemon and outlives its window */
+  daemon: boolean
+  pid: number
+  /** Milliseconds since the UNIX epoch */
+  startedAt: number
+  /** MCP servers the backend is running, to reconnect to */
+  runningMcpServers: string[]
+}
+
+export function getDaemonStatus(): Promise<DaemonStatus> {
+  return invoke<DaemonStatus>('get_daemon_status')
+}
+
+/** Called when a new launch of the app reopens the daemon's window */
+export function onUiAttached(
+  callback: (status: DaemonStatus) => void
+): Promise<UnlistenFn> {
+  return listen<DaemonStatus>('daemon-ui-attached', event =>
+    callback(event.payload)
+  )
+}
@@ -0,0 +1,48 @@
+/**
+ * Database Backups
+ * Snapshots of the local database taken by the backend on a schedule (see
+ * the `databaseBackup` preference) or on demand, and restoring one of them.
+ * Also reports the database's schema version, which restores migrate.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type BackupKind = 'scheduled' | 'manual' | 'pre_restore'
+
+export interface DatabaseBackup {
+  id: string
+  kind: BackupKind
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  /** Bytes */
+  size: number
+}
+
+export interface SchemaVersion {
+  /** Newest migration applied to the database */
+  version: number
+  /** Newest migration this version of the app knows */
+  latest: number
+  migrations: { version: number; name: string; appliedAt: number }[]
+}
+
+export function createBackupNow(): Promise<DatabaseBackup> {
+  return invoke<DatabaseBackup>('create_backup_now')
+}
+
+/** Every backup, newest first */
+export function listBackups(): Promise<DatabaseBackup[]> {
+  return invoke<DatabaseBackup[]>('list_backups')
+}
+
+/**
+ * Replace the local database with a backup. Resolves to the backup of the
+ * contents it replaced; restoring that one undoes the restore.
+ */
+export function restoreBackup(id: string): Promise<DatabaseBackup> {
+  return invoke<DatabaseBackup>('restore_backup', { id })
+}
+
+export function getDbSchemaVersion(): Promise<SchemaVersion> {
+  return invoke<SchemaVersion>('get_db_schema_version')
+}
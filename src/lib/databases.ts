@@ -0,0 +1,145 @@
+/**
+ * Databases
+ * SQLite, PostgreSQL and MySQL connections the model can query through the
+ * built-in SQL tools. Read-only connections are enforced by the backend;
+ * passwords are stored in the OS keychain and never returned.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { MCPTool, MCPToolCallResult } from '@/types/mcp'
+
+interface ServerDatabase {
+  host: string
+  /** Defaults to 5432 for PostgreSQL and 3306 for MySQL */
+  port?: number
+  database: string
+  username: string
+  tls?: boolean
+}
+
+export type DatabaseKind =
+  | { type: 'sqlite'; path: string }
+  | ({ type: 'postgres' } & ServerDatabase)
+  | ({ type: 'mysql' } & ServerDatabase)
+
+export type DatabaseConnection = DatabaseKind & {
+  id: string
+  name: string
+  /** Only queries are allowed; defaults to true */
+  readOnly?: boolean
+  /** Rows returned by one query; defaults to 200 */
+  maxRows?: number
+  /** Bytes of values returned by one query; defaults to 256 KiB */
+  maxBytes?: number
+}
+
+export type DatabaseInfo = DatabaseConnection & {
+  /** A password is stored in the keychain */
+  hasCredential: boolean
+}
+
+export interface QueryResult {
+  columns: string[]
+  rows: unknown[][]
+  /** More rows were available than returned */
+  truncated: boolean
+  /** Rows changed by a statement that returns none */
+  rowsAffected: number | null
+}
+
+export function listDatabases(): Promise<DatabaseInfo[]> {
+  return invoke<DatabaseInfo[]>('list_databases')
+}
+
+/**
+ * Add or replace a connection. A non-empty `credential` is stored as its
+ * password, an empty one removes it and leaving it out keeps it.
+ */
+export function saveDatabase(
+  connection: DatabaseConnection,
+  credential?: string
+): Promise<void> {
+  return invoke('save_database', { connection, credential })
+}
+
+export function removeDatabase(id: string): Promise<void> {
+  return invoke('remove_database', { id })
+}
+
+export function queryDatabase(id: string, sql: string): Promise<QueryResult> {
+  return invoke<QueryResult>('query_database', { id, sql })
+}
+
+/** Tables and columns of a database */
+export function describeDatabase(id: string): Promise<QueryResult> {
+  return invoke<QueryResult>('describe_database', { id })
+}
+
+const SQL_QUERY_TOOL = 'sql_query'
+const SQL_SCHEMA_TOOL = 'sql_schema'
+
+/** The SQL tools, offered when at least one database is configured */
+export async function getDatabaseToolDefinitions(): Promise<MCPTool[]> {
+  const databases = await listDatabases()
+  if (databases.length === 0) {
+    return []
+  }
+  const database = {
+    type: 'string',
+    description: 'Id of the database',
+    enum: databases.map(db => db.id),
+  }
+  const list = databases
+    .map(db => {
+      const mode = db.readOnly === false ? 'read-write' : 'read-only'
+      return `${db.id} (${db.name}, ${db.type}, ${mode})`
+    })
+    .join('; ')
+
+  return [
+    {
+      name: SQL_QUERY_TOOL,
+      description: `Run one SQL statement. Databases: ${list}`,
+      inputSchema: {
+        type: 'object',
+        properties: {
+          database,
+          sql: { type: 'string', description: 'A single SQL statement' },
+        },
+        required: ['database', 'sql'],
+      },
+    },
+    {
+      name: SQL_SCHEMA_TOOL,
+      description: `List the tables and columns of a database: ${list}`,
+      inputSchema: {
+        type: 'object',
+        properties: { database },
+        required: ['database'],
+      },
+    },
+  ]
+}
+
+/** Run a SQL tool, returning the result as JSON */
+export async function executeDatabaseTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<MCPToolCallResult> {
+  const id = String(args.database ?? '')
+  try {
+    const result =
+      toolName === SQL_SCHEMA_TOOL
+        ? await describeDatabase(id)
+        : await queryDatabase(id, String(args.sql ?? ''))
+    return {
+      content: [{ type: 'text', text: JSON.stringify(result) }],
+      isError: false,
+    }
+  } catch (error) {
+    return {
+      content: [{ type: 'text', text: String(error) }],
+      isError: true,
+    }
+  }
+}
@@ -0,0 +1,36 @@
+/**
+ * Deep Links
+ * `nexus://` links opened from a browser or another app. A link that
+ * launched the app is left pending for the new window; a window that is
+ * already open receives it as an event. An `add_mcp_server` link comes
+ * from outside the app, so `DeepLinkDialog` shows the user what it would
+ * run and lets them confirm before adding the server.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type DeepLink =
+  | { type: 'open_conversation'; id: string }
+  | { type: 'new_chat'; prompt: string | null }
+  | {
+      type: 'add_mcp_server'
+      name: string
+      command: string | null
+      args: string[]
+      env: Record<string, string>
+      url: string | null
+      /** What the shell would run for a stdio server; show it as is */
+      commandLine: string | null
+    }
+
+/** Call once the window has loaded; returns the link only once */
+export function takePendingDeepLink(): Promise<DeepLink | null> {
+  return invoke<DeepLink | null>('take_pending_deep_link')
+}
+
+export function onDeepLink(
+  callback: (link: DeepLink) => void
+): Promise<UnlistenFn> {
+  return listen<DeepLink>('deep-link', event => callback(event.payload))
+}
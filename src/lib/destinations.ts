@@ -0,0 +1,52 @@
+/**
+ * Share Destinations
+ * Named places exports can be sent to, managed by the backend. Secrets
+ * (webhook tokens, WebDAV passwords) are stored in the OS keychain and
+ * never returned to the frontend.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type DestinationKind =
+  | { type: 'folder'; path: string }
+  | { type: 'webhook'; url: string; headers?: Record<string, string> }
+  | { type: 'email'; to: string[]; subjectPrefix?: string }
+  | { type: 'web_dav'; url: string; username: string }
+
+export type Destination = {
+  id: string
+  name: string
+} & DestinationKind
+
+export type DestinationInfo = Destination & {
+  /** A secret is stored in the keychain */
+  hasCredential: boolean
+}
+
+export function listDestinations(): Promise<DestinationInfo[]> {
+  return invoke<DestinationInfo[]>('list_destinations')
+}
+
+/**
+ * Add or replace a destination. A non-empty `credential` is stored in the
+ * keychain, an empty string removes the stored one, and `undefined` keeps it.
+ */
+export function saveDestination(
+  destination: Destination,
+  credential?: string
+): Promise<void> {
+  return invoke('save_destination', { destination, credential })
+}
+
+export function removeDestination(id: string): Promise<void> {
+  return invoke('remove_destination', { id })
+}
+
+/** Send text content to a destination as a file named `fileName` */
+export function sendToDestination(
+  destinationId: string,
+  fileName: string,
+  content: string
+): Promise<void> {
+  return invoke('send_to_destination', { destinationId, fileName, content })
+}
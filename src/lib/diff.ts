@@ -0,0 +1,35 @@
+/**
+ * Text Diff
+ * Compares two model responses or artifact versions in the backend, which
+ * keeps long diffs off the renderer thread.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type DiffGranularity = 'word' | 'sentence' | 'line'
+
+export interface DiffHunk {
+  kind: 'equal' | 'insert' | 'delete' | 'replace'
+  /** Text from the first input; empty for insertions */
+  oldText: string
+  /** Text from the second input; empty for deletions */
+  newText: string
+}
+
+export interface TextDiff {
+  /** Hunks in order; concatenating the `newText` of each rebuilds `b` */
+  hunks: DiffHunk[]
+  /** Share of tokens unchanged, 0 to 1 */
+  similarity: number
+  insertions: number
+  deletions: number
+}
+
+/** Diff `a` against `b`; short unchanged runs between edits are merged */
+export function diffTexts(
+  a: string,
+  b: string,
+  granularity: DiffGranularity = 'word'
+): Promise<TextDiff> {
+  return invoke<TextDiff>('diff_texts', { a, b, granularity })
+}
@@ -0,0 +1,111 @@
+/**
+ * Local Embeddings
+ * Embedding vectors computed on this machine, for indexing knowledge bases
+ * without sending documents to a provider: by ONNX models in the app on
+ * CoreML, DirectML or the CPU, or by the local Ollama daemon, which runs
+ * the model on the GPU (Metal, CUDA, ROCm) when it can. Unless a backend
+ * is given, the backend is picked from a probe of the system.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/** `onnx` runs in the app; `ollama` in the local daemon */
+export type EmbeddingBackend = 'onnx' | 'ollama'
+
+/** What ONNX Runtime ran a model on */
+export type ExecutionProvider = 'coreml' | 'directml' | 'cpu'
+
+/**
+ * Where the model ran: from its execution provider in the app, or from
+ * how much of it sat in GPU memory in Ollama
+ */
+export type EmbeddingDevice = 'gpu' | 'mixed' | 'cpu' | 'unknown'
+
+export interface Embeddings {
+  model: string
+  backend: EmbeddingBackend
+  /** Set for the `onnx` backend */
+  provider: ExecutionProvider | null
+  /** One vector per text, in the order of the texts */
+  embeddings: number[][]
+  dimensions: number
+  device: EmbeddingDevice
+  durationMs: number
+}
+
+export interface EmbeddingBenchmark {
+  model: string
+  backend: EmbeddingBackend
+  /** Set for the `onnx` backend */
+  provider: ExecutionProvider | null
+  /** null if the model failed; see `error` */
+  dimensions: number | null
+  device: EmbeddingDevice
+  texts: number
+  durationMs: number
+  textsPerSecond: number
+  tokensPerSecond: number | null
+  error: string | null
+}
+
+export interface OnnxRuntimeProbe {
+  /** The ONNX Runtime library in use; null if it couldn't be loaded */
+  library: string | null
+  /** Why the runtime is unavailable */
+  error: string | null
+  /** Execution providers models can run on, fastest first */
+  providers: ExecutionProvider[]
+  /** Installed ONNX models */
+  models: string[]
+}
+
+export interface EmbeddingProbe {
+  onnx: OnnxRuntimeProbe
+  /** Whether the Ollama daemon answers */
+  ollama: boolean
+  /** What `embedTexts` uses without a backend or model */
+  defaultBackend: EmbeddingBackend
+  defaultModel: string
+}
+
+/**
+ * Embed texts with a downloaded ONNX model or a pulled Ollama model; by
+ * default `all-MiniLM-L6-v2` in the app if installed, else
+ * `nomic-embed-text` in Ollama
+ */
+export function embedTexts(
+  texts: string[],
+  model?: string,
+  backend?: EmbeddingBackend
+): Promise<Embeddings> {
+  return invoke<Embeddings>('embed_texts', { model, backend, texts })
+}
+
+/** What each backend can do on this system */
+export function probeEmbeddingBackends(): Promise<EmbeddingProbe> {
+  return invoke<EmbeddingProbe>('probe_embedding_backends')
+}
+
+/**
+ * Download the ONNX export of a sentence-transformers model from Hugging
+ * Face, e.g. `sentence-transformers/all-MiniLM-L6-v2`; resolves to the
+ * model's name
+ */
+export function downloadEmbeddingModel(hfRepo: string): Promise<string> {
+  return invoke<string>('download_embedding_model', { hfRepo })
+}
+
+/**
+ * Compare the throughput of embedding models on `texts`, or on built-in
+ * sample texts; ONNX models are run on each execution provider, and
+ * failing runs are reported with their error
+ */
+export function benchmarkEmbeddings(
+  models: string[],
+  texts?: string[]
+): Promise<EmbeddingBenchmark[]> {
+  return invoke<EmbeddingBenchmark[]>('benchmark_embeddings', {
+    models,
+    texts,
+  })
+}
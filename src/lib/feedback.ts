@@ -0,0 +1,78 @@
+/**
+ * Response Feedback
+ * Thumbs up/down ratings and notes on assistant messages, stored locally
+ * and exportable as JSON Lines for fine-tuning datasets or eval seeds.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type FeedbackRating = 1 | -1
+
+export interface Feedback {
+  conversationId: string
+  messageId: string
+  modelId: string
+  rating: FeedbackRating | null
+  note: string | null
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  /** Milliseconds since the UNIX epoch */
+  updatedAt: number
+}
+
+export interface ModelFeedbackStats {
+  modelId: string
+  up: number
+  down: number
+  /** Messages with a note */
+  annotated: number
+}
+
+export interface FeedbackStats {
+  up: number
+  down: number
+  annotated: number
+  /** Per model, most rated first */
+  models: ModelFeedbackStats[]
+}
+
+/**
+ * Rate or annotate a message. Passing neither a rating nor a note removes
+ * its feedback.
+ */
+export function setFeedback(
+  conversationId: string,
+  messageId: string,
+  modelId: string,
+  feedback: { rating?: FeedbackRating | null; note?: string | null }
+): Promise<Feedback | null> {
+  return invoke<Feedback | null>('set_feedback', {
+    conversationId,
+    messageId,
+    modelId,
+    rating: feedback.rating ?? undefined,
+    note: feedback.note ?? undefined,
+  })
+}
+
+/** Feedback on the messages of a conversation */
+export function getFeedback(conversationId: string): Promise<Feedback[]> {
+  return invoke<Feedback[]>('get_feedback', { conversationId })
+}
+
+export function getFeedbackStats(): Promise<FeedbackStats> {
+  return invoke<FeedbackStats>('get_feedback_stats')
+}
+
+/**
+ * Write feedback to `path` as JSON Lines, each record with the conversation
+ * up to the rated message. Optionally only thumbs up or thumbs down.
+ *
+ * @returns The number of records written
+ */
+export function exportFeedback(
+  path: string,
+  rating?: FeedbackRating
+): Promise<number> {
+  return invoke<number>('export_feedback', { path, rating })
+}
@@ -0,0 +1,45 @@
+/**
+ * File Drop
+ * Files dropped on the window are stored in the attachment store by the
+ * backend, which then describes them in one event: text files with their
+ * text, PNG and JPEG images with a thumbnail. Files that can't be attached
+ * are listed with the reason.
+ */
+
+import type { UnlistenFn } from '@tauri-apps/api/event'
+import { getCurrentWindow } from '@tauri-apps/api/window'
+
+export interface IngestedFile {
+  name: string
+  /** Put in a content part as `blobRef` */
+  hash: string
+  mimeType: string
+  /** Bytes of the file itself */
+  size: number
+  text: string | null
+  textTruncated: boolean
+  /** Base64 PNG */
+  thumbnail: string | null
+  width: number | null
+  height: number | null
+}
+
+export interface RejectedFile {
+  name: string
+  reason: string
+}
+
+export interface FilesIngested {
+  window: string
+  files: IngestedFile[]
+  rejected: RejectedFile[]
+}
+
+/** Files dropped on this window, once they are stored */
+export function onFilesIngested(
+  callback: (ingested: FilesIngested) => void
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<FilesIngested>('files-ingested', event =>
+    callback(event.payload)
+  )
+}
@@ -0,0 +1,109 @@
+/**
+ * GraphQL Tools
+ * Tools generated by the backend from an introspected GraphQL endpoint.
+ * Each tool runs one root field with a selection set bounded by the
+ * endpoint's depth and field limits. Credentials are stored in the OS
+ * keychain and never returned to the frontend.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { MCPTool, MCPToolCallResult } from '@/types/mcp'
+import type { HttpToolResult, OpenApiAuth } from '@/lib/openapi-tools'
+
+export interface GraphQlOperation {
+  /** Name of the tool, unique across all registered APIs */
+  toolName: string
+  kind: 'query' | 'mutation'
+  /** Root field the operation selects */
+  field: string
+  description: string
+  /** Generated document; the tool's arguments are its variables */
+  document: string
+  depth: number
+  fieldCount: number
+  inputSchema: MCPTool['inputSchema']
+}
+
+export interface GraphQlIntegration {
+  id: string
+  name: string
+  endpoint: string
+  auth: OpenApiAuth
+  maxDepth: number
+  maxFields: number
+  maxResponseBytes: number
+  operations: GraphQlOperation[]
+  createdAt: number
+  /** A secret is stored in the keychain */
+  hasCredential: boolean
+}
+
+export interface GraphQlOptions {
+  /** Defaults to the endpoint's host */
+  name?: string
+  /** Root fields to expose; every query when left out. Mutations are only
+   * exposed when listed here. */
+  operations?: string[]
+  /** Nesting of generated selection sets, 1 to 5 (default 2) */
+  maxDepth?: number
+  /** Fields selected by one generated document (default 50) */
+  maxFields?: number
+  maxResponseBytes?: number
+}
+
+/** Introspect `endpoint` and register its root fields as tools */
+export function registerGraphQlTool(
+  endpoint: string,
+  auth?: OpenApiAuth,
+  credential?: string,
+  options?: GraphQlOptions
+): Promise<GraphQlIntegration> {
+  return invoke<GraphQlIntegration>('register_graphql_tool', {
+    endpoint,
+    auth,
+    credential,
+    options,
+  })
+}
+
+export function listGraphQlTools(): Promise<GraphQlIntegration[]> {
+  return invoke<GraphQlIntegration[]>('list_graphql_tools')
+}
+
+export function removeGraphQlTool(id: string): Promise<void> {
+  return invoke('remove_graphql_tool', { id })
+}
+
+export function callGraphQlTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<HttpToolResult> {
+  return invoke<HttpToolResult>('call_graphql_tool', {
+    toolName,
+    arguments: args,
+  })
+}
+
+/** Every registered root field as a tool definition for the model */
+export async function getGraphQlToolDefinitions(): Promise<MCPTool[]> {
+  const integrations = await listGraphQlTools()
+  return integrations.flatMap(integration =>
+    integration.operations.map(operation => ({
+      name: operation.toolName,
+      description: operation.description,
+      inputSchema: operation.inputSchema,
+    }))
+  )
+}
+
+/** Call a GraphQL tool; the response carries `data` and any `errors` */
+export async function executeGraphQlTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<MCPToolCallResult> {
+  const result = await callGraphQlTool(toolName, args)
+  return {
+    content: [{ type: 'text', text: result.content }],
+    isError: result.isError,
+  }
+}
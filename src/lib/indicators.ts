@@ -0,0 +1,22 @@
+/**
+ * Dock and Taskbar Indicators
+ * The badge and progress bar on the app's dock icon or taskbar button.
+ * The backend adds notifications shown while the window was unfocused to
+ * the badge, and shows running operations on the progress bar unless
+ * `setProgress` overrides it.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/** Sets the app's part of the badge, e.g. unread replies; 0 removes it */
+export function setBadgeCount(count: number): Promise<void> {
+  return invoke('set_badge_count', { count })
+}
+
+/**
+ * Shows `fraction` (0 to 1) on the progress bar; `null` hands it back to
+ * the running operations.
+ */
+export function setProgress(fraction: number | null): Promise<void> {
+  return invoke('set_progress', { fraction })
+}
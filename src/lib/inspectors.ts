@@ -0,0 +1,192 @@
+/**
+ * Operations Inspectors
+ * Opt-in tools for read-only `kubectl get/describe/logs` and a fixed set of
+ * read-only SSH commands. The backend builds every command line itself,
+ * enforces the allowlists and records each run in the tool audit log.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { MCPTool, MCPToolCallResult } from '@/types/mcp'
+
+export interface SshHost {
+  id: string
+  host: string
+  user: string
+  port?: number
+  /** Private key file; the agent and ~/.ssh/config are used otherwise */
+  identityFile?: string
+}
+
+export interface InspectorSettings {
+  kubernetes: {
+    enabled: boolean
+    /** `kubectl` on the PATH when not set */
+    kubectlPath?: string
+    /** Contexts a run must pick one of; only the current one when empty */
+    contexts: string[]
+    /** Namespaces that may be read; any when empty */
+    namespaces: string[]
+  }
+  ssh: {
+    enabled: boolean
+    hosts: SshHost[]
+  }
+}
+
+export interface KubectlRequest {
+  verb: 'get' | 'describe' | 'logs'
+  resource?: string
+  name?: string
+  namespace?: string
+  context?: string
+  selector?: string
+  container?: string
+  tail?: number
+}
+
+export type SshCommand =
+  | {
+      command:
+        | 'uptime'
+        | 'kernel'
+        | 'disk_usage'
+        | 'memory'
+        | 'processes'
+        | 'listening_ports'
+    }
+  | { command: 'service_status'; unit: string }
+  | { command: 'service_logs'; unit: string; lines?: number }
+
+export interface InspectorResult {
+  /** The command as run */
+  command: string
+  exitCode: number | null
+  stdout: string
+  stderr: string
+  truncated: boolean
+  isError: boolean
+}
+
+export function getInspectorSettings(): Promise<InspectorSettings> {
+  return invoke<InspectorSettings>('get_inspector_settings')
+}
+
+export function saveInspectorSettings(
+  settings: InspectorSettings
+): Promise<void> {
+  return invoke('save_inspector_settings', { settings })
+}
+
+export function runKubectl(request: KubectlRequest): Promise<InspectorResult> {
+  return invoke<InspectorResult>('run_kubectl', { request })
+}
+
+export function runSshCommand(
+  hostId: string,
+  command: SshCommand
+): Promise<InspectorResult> {
+  return invoke<InspectorResult>('run_ssh_command', { hostId, command })
+}
+
+const KUBECTL_TOOL = 'kubectl_inspect'
+const SSH_TOOL = 'ssh_inspect'
+
+/** Tool definitions for the inspectors the user has enabled */
+export async function getInspectorToolDefinitions(): Promise<MCPTool[]> {
+  const settings = await getInspectorSettings()
+  const tools: MCPTool[] = []
+
+  if (settings.kubernetes.enabled) {
+    const { contexts, namespaces } = settings.kubernetes
+    tools.push({
+      name: KUBECTL_TOOL,
+      description:
+        'Read Kubernetes cluster state with kubectl get, describe or ' +
+        'logs. Secrets and config maps cannot be read.',
+      inputSchema: {
+        type: 'object',
+        properties: {
+          verb: { type: 'string', enum: ['get', 'describe', 'logs'] },
+          resource: {
+            type: 'string',
+            description: 'Plural resource type, e.g. pods; not for logs',
+          },
+          name: { type: 'string', description: 'Object or pod name' },
+          namespace: namespaces.length
+            ? { type: 'string', enum: namespaces }
+            : { type: 'string' },
+          ...(contexts.length > 0
+            ? { context: { type: 'string', enum: contexts } }
+            : {}),
+          selector: { type: 'string', description: 'Label selector' },
+          container: { type: 'string', description: 'Container for logs' },
+          tail: { type: 'number', description: 'Log lines, default 200' },
+        },
+        required: contexts.length > 0 ? ['verb', 'context'] : ['verb'],
+      },
+    })
+  }
+
+  if (settings.ssh.enabled && settings.ssh.hosts.length) {
+    tools.push({
+      name: SSH_TOOL,
+      description:
+        'Run a read-only diagnostic command on a server over SSH. ' +
+        'service_status and service_logs need a systemd unit.',
+      inputSchema: {
+        type: 'object',
+        properties: {
+          host: {
+            type: 'string',
+            enum: settings.ssh.hosts.map(host => host.id),
+          },
+          command: {
+            type: 'string',
+            enum: [
+              'uptime',
+              'kernel',
+              'disk_usage',
+              'memory',
+              'processes',
+              'listening_ports',
+              'service_status',
+              'service_logs',
+            ],
+          },
+          unit: { type: 'string', description: 'systemd unit' },
+          lines: { type: 'number', description: 'Log lines, default 200' },
+        },
+        required: ['host', 'command'],
+      },
+    })
+  }
+
+  return tools
+}
+
+/** Run an inspector tool, returning its output */
+export async function executeInspectorTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<MCPToolCallResult> {
+  const { host, ...command } = args
+  try {
+    const result =
+      toolName === SSH_TOOL
+        ? await runSshCommand(String(host ?? ''), command as SshCommand)
+        : await runKubectl(args as unknown as KubectlRequest)
+    const output = [result.stdout, result.stderr].filter(Boolean).join('\n')
+    const note = result.truncated ? '\n\n[output truncated]' : ''
+    return {
+      content: [
+        { type: 'text', text: `$ ${result.command}\n\n${output}${note}` },
+      ],
+      isError: result.isError,
+    }
+  } catch (error) {
+    return {
+      content: [{ type: 'text', text: String(error) }],
+      isError: true,
+    }
+  }
+}
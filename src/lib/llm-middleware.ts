@@ -0,0 +1,36 @@
+/**
+ * LLM Middleware
+ * Metrics for the middleware pipeline that backend model requests pass
+ * through. The pipeline itself is configured in preferences
+ * (`llmMiddleware`). Also manages the provider responses recorded when
+ * the `llmReplay` preference is `record`, and replayed when it's `replay`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface MiddlewareMetrics {
+  id: string
+  kind: 'redaction' | 'budget' | 'cache' | 'script'
+  /** Requests the middleware handled */
+  calls: number
+  /** Requests it rejected or failed on */
+  errors: number
+  /** Time spent in the middleware across both hooks */
+  totalUs: number
+  maxUs: number
+}
+
+/** Call counts and timings of every middleware that has run */
+export function getMiddlewareMetrics(): Promise<MiddlewareMetrics[]> {
+  return invoke<MiddlewareMetrics[]>('get_middleware_metrics')
+}
+
+/** Number of recorded provider responses */
+export function countLlmFixtures(): Promise<number> {
+  return invoke<number>('count_llm_fixtures')
+}
+
+/** Delete every recorded provider response; resolves to how many */
+export function clearLlmFixtures(): Promise<number> {
+  return invoke<number>('clear_llm_fixtures')
+}
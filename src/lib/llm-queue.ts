@@ -0,0 +1,32 @@
+/**
+ * LLM Request Queue
+ * Model requests wait under a per-host token-bucket rate limit in the
+ * Rust backend, queued in the order they were made. The limits are the
+ * `llmRateLimits` preference; local servers are unlimited unless listed.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface LlmQueueStatus {
+  /** API host, with the port if the URL has one */
+  host: string
+  /** Requests waiting to be sent */
+  queued: number
+  /** Requests that could be sent right away */
+  available: number
+  requestsPerMinute: number
+  burst: number
+}
+
+/** Hosts with a rate limit that have had requests, sorted by host */
+export function getLlmQueueStatus(): Promise<LlmQueueStatus[]> {
+  return invoke<LlmQueueStatus[]>('get_llm_queue_status')
+}
+
+/** Called whenever a request joins or leaves a host's queue */
+export function onLlmQueue(
+  callback: (status: LlmQueueStatus) => void
+): Promise<UnlistenFn> {
+  return listen<LlmQueueStatus>('llm-queue', event => callback(event.payload))
+}
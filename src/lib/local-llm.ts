@@ -0,0 +1,104 @@
+/**
+ * Local Inference
+ * GGUF models run offline in llama.cpp's `llama-server`, which the
+ * backend starts and stops. One model is loaded at a time; while it
+ * loads, `local-llm-load` events report its progress and memory use.
+ * Needs a build with the `local-llm` feature and llama.cpp installed
+ * separately: `llama-server` on the PATH, or its path in `LLAMA_SERVER`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import { streamChatCompletion, type ChatStreamHandlers } from './chat-proxy'
+
+export type LocalModelState = 'loading' | 'ready' | 'failed'
+
+export interface LocalModelStatus {
+  modelPath: string
+  /** File name without `.gguf` */
+  name: string
+  state: LocalModelState
+  /** OpenAI-compatible API of the server */
+  baseUrl: string
+  contextSize: number
+  gpuLayers: number
+  fileBytes: number
+  /** Grows towards `fileBytes` while the weights are read */
+  memoryBytes: number | null
+  elapsedMs: number
+  loadedInMs: number | null
+  error: string | null
+}
+
+export interface LocalRuntime {
+  /** Built with the `local-llm` feature */
+  enabled: boolean
+  /** The `llama-server` that will be started */
+  serverPath: string | null
+  /** Why `llama-server` can't be used, with how to install it */
+  error: string | null
+}
+
+/** Whether local models can run; check before offering to load one */
+export function getLocalRuntime(): Promise<LocalRuntime> {
+  return invoke<LocalRuntime>('local_llm_runtime')
+}
+
+export interface LoadLocalModelOptions {
+  /** Tokens; defaults to 4096 */
+  contextSize?: number
+  /** Layers offloaded to the GPU; defaults to all of them */
+  gpuLayers?: number
+}
+
+/** Load a GGUF file, replacing the loaded model; resolves when ready */
+export function loadLocalModel(
+  path: string,
+  options: LoadLocalModelOptions = {}
+): Promise<LocalModelStatus> {
+  return invoke<LocalModelStatus>('load_local_model', {
+    path,
+    contextSize: options.contextSize ?? null,
+    gpuLayers: options.gpuLayers ?? null,
+  })
+}
+
+export function getLocalModelStatus(): Promise<LocalModelStatus | null> {
+  return invoke<LocalModelStatus | null>('local_model_status')
+}
+
+/** Resolves to false if no model was loaded */
+export function unloadLocalModel(): Promise<boolean> {
+  return invoke<boolean>('unload_local_model')
+}
+
+/** Called while a model loads, and once it is ready or failed */
+export function onLocalModelLoad(
+  callback: (status: LocalModelStatus) => void
+): Promise<UnlistenFn> {
+  return listen<LocalModelStatus>('local-llm-load', event =>
+    callback(event.payload)
+  )
+}
+
+/**
+ * Stream a chat completion from the loaded model; `request` is an OpenAI
+ * chat completions body, `model` may be left out
+ */
+export async function streamLocalChatCompletion(
+  request: Record<string, unknown>,
+  handlers: ChatStreamHandlers
+): Promise<string> {
+  const status = await getLocalModelStatus()
+  if (!status) {
+    throw new Error('Load a local model first')
+  }
+  if (status.state !== 'ready') {
+    throw new Error('The local model is still loading')
+  }
+  return streamChatCompletion(
+    { baseUrl: status.baseUrl },
+    { model: status.name, ...request },
+    handlers
+  )
+}
@@ -0,0 +1,48 @@
+/**
+ * Local Model Files
+ * GGUF files for local inference, kept in a models directory shared by all
+ * profiles. Downloads run as operations (see `operations`), so progress
+ * and cancellation work like any other long task; a cancelled download
+ * resumes where it stopped when started again.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface LocalModelFile {
+  fileName: string
+  /** File name without `.gguf` */
+  name: string
+  /** Pass to `loadLocalModel` */
+  path: string
+  sizeBytes: number
+  /** Milliseconds since the UNIX epoch */
+  modifiedAt: number
+  /** False for a download that stopped before it was done */
+  complete: boolean
+}
+
+export type ModelSource =
+  | { url: string; filename: string }
+  /** `filename` is the path in the repository */
+  | { hfRepo: string; filename: string }
+
+/**
+ * Download a GGUF model, verifying it against `sha256` or, for Hugging
+ * Face files, the published checksum
+ */
+export function downloadModel(
+  source: ModelSource,
+  sha256?: string
+): Promise<LocalModelFile> {
+  return invoke<LocalModelFile>('download_model', {
+    url: 'url' in source ? source.url : null,
+    hfRepo: 'hfRepo' in source ? source.hfRepo : null,
+    filename: source.filename,
+    sha256: sha256 ?? null,
+  })
+}
+
+/** Models in the models directory, sorted by file name */
+export function listLocalModels(): Promise<LocalModelFile[]> {
+  return invoke<LocalModelFile[]>('list_local_models')
+}
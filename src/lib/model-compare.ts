@@ -0,0 +1,110 @@
+/**
+ * Model Comparison
+ * One request streamed from several models at once, for side-by-side
+ * views. Each lane's chunks are handed over with its lane id, and each
+ * lane reports its latency and token stats when it ends. Link the answers
+ * into conversations with `createComparison`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import {
+  cancelLlmRequest,
+  type LlmChunkEvent,
+  type ProviderConfig,
+  type RegisteredProvider,
+} from './chat-proxy'
+
+export interface CompareLane {
+  /** Unique within the comparison */
+  laneId: string
+  provider: ProviderConfig | RegisteredProvider
+  /** Replaces the request's `model` */
+  model: string
+}
+
+export type LaneStatus = 'completed' | 'failed' | 'cancelled'
+
+export interface LaneStats {
+  comparisonId: string
+  laneId: string
+  /** Id of the lane's `llm-*` events */
+  requestId: string
+  model: string
+  status: LaneStatus
+  error: string | null
+  text: string
+  finishReason: string | null
+  /** Milliseconds to the first streamed text or tool call */
+  firstChunkMs: number | null
+  durationMs: number
+  inputTokens: number | null
+  outputTokens: number | null
+  tokensPerSecond: number | null
+  cached: boolean
+}
+
+export interface CompareHandlers {
+  onChunk?: (laneId: string, chunk: LlmChunkEvent) => void
+  onLaneDone?: (stats: LaneStats) => void
+  /** Aborting it stops every lane still streaming */
+  signal?: AbortSignal
+}
+
+/**
+ * Stream `request`, an OpenAI chat completions body, from every lane
+ * concurrently. Resolves with the stats of every lane, in order, once all
+ * of them have ended; a failed lane doesn't stop the others.
+ */
+export async function compareModels(
+  request: Record<string, unknown>,
+  lanes: CompareLane[],
+  handlers: CompareHandlers = {}
+): Promise<LaneStats[]> {
+  const comparisonId = crypto.randomUUID()
+  const requestIds = new Map(
+    lanes.map(lane => [`${comparisonId}:${lane.laneId}`, lane.laneId])
+  )
+  // Events may be delivered after the command resolves
+  let pendingLanes = lanes.length
+  let markDone = () => {}
+  const done = new Promise<void>(resolve => {
+    markDone = resolve
+  })
+  const unlisteners: UnlistenFn[] = await Promise.all([
+    listen<LlmChunkEvent>('llm-chunk', event => {
+      const laneId = requestIds.get(event.payload.requestId)
+      if (laneId !== undefined) handlers.onChunk?.(laneId, event.payload)
+    }),
+    listen<LaneStats>('compare-lane-done', event => {
+      if (event.payload.comparisonId === comparisonId) {
+        handlers.onLaneDone?.(event.payload)
+        pendingLanes -= 1
+        if (pendingLanes === 0) markDone()
+      }
+    }),
+  ])
+  const cancel = () => {
+    requestIds.forEach((_, requestId) => void cancelLlmRequest(requestId))
+  }
+  handlers.signal?.addEventListener('abort', cancel, { once: true })
+
+  try {
+    const stats = await invoke<LaneStats[]>('compare_models', {
+      comparisonId,
+      request,
+      modelList: lanes.map(lane => ({
+        laneId: lane.laneId,
+        model: lane.model,
+        ...('providerId' in lane.provider
+          ? { providerId: lane.provider.providerId }
+          : { providerConfig: lane.provider }),
+      })),
+    })
+    await done
+    return stats
+  } finally {
+    handlers.signal?.removeEventListener('abort', cancel)
+    unlisteners.forEach(unlisten => unlisten())
+  }
+}
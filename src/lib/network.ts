@@ -0,0 +1,33 @@
+/**
+ * Network
+ * Backend requests go through the proxy in the `network` preference: the
+ * system's (the default), a manual one, or none. A CA bundle, e.g. for a
+ * proxy that inspects TLS traffic, is trusted on top of the built-in
+ * roots. Stdio MCP servers get the same proxy and CA bundle.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface ConnectivityReport {
+  provider: string
+  url: string
+  /** Whether the server answered at all, with any status */
+  reachable: boolean
+  status: number | null
+  /** e.g. `system proxy`, `direct` or `proxy http://proxy.corp:8080/` */
+  route: string
+  durationMs: number
+  /** What went wrong, e.g. the proxy refusing or an unknown CA */
+  error: string | null
+}
+
+/**
+ * Check whether a provider can be reached with the current network
+ * settings; `provider` is a built-in one like `openai` or the id of a
+ * registered provider
+ */
+export function testNetworkConnectivity(
+  provider: string
+): Promise<ConnectivityReport> {
+  return invoke<ConnectivityReport>('test_network_connectivity', { provider })
+}
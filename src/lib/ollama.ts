@@ -0,0 +1,54 @@
+/**
+ * Ollama
+ * Local models served by an Ollama daemon: whether it is installed and
+ * running, the models it has, and pulling or deleting models. Chat goes
+ * through the backend's `ollama` provider, which needs no API key.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface OllamaStatus {
+  /** The `ollama` executable is on the PATH */
+  installed: boolean
+  /** The daemon answers on `baseUrl` */
+  running: boolean
+  version: string | null
+  baseUrl: string
+}
+
+export interface OllamaModel {
+  /** e.g. `llama3.2:latest`; the model id for chat requests */
+  name: string
+  /** Bytes on disk */
+  size: number
+  digest: string
+  /** RFC 3339 */
+  modifiedAt: string
+  details: {
+    family: string | null
+    parameterSize: string | null
+    quantizationLevel: string | null
+  } | null
+}
+
+export function getOllamaStatus(): Promise<OllamaStatus> {
+  return invoke<OllamaStatus>('ollama_status')
+}
+
+/** Models the local daemon has, sorted by name */
+export function listOllamaModels(): Promise<OllamaModel[]> {
+  return invoke<OllamaModel[]>('ollama_list_models')
+}
+
+/**
+ * Download a model, e.g. `llama3.2` or `qwen2.5:7b`. Resolves once the
+ * model is ready. Runs as a `model_download` operation; see
+ * `operations.ts` for progress and cancellation.
+ */
+export function pullOllamaModel(name: string): Promise<void> {
+  return invoke('ollama_pull_model', { name })
+}
+
+export function deleteOllamaModel(name: string): Promise<void> {
+  return invoke('ollama_delete_model', { name })
+}
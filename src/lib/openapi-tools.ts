@@ -0,0 +1,121 @@
+/**
+ * OpenAPI Tools
+ * Tools generated from an OpenAPI 3 spec by the backend, so the model can
+ * call an HTTP API without an MCP server. Credentials are stored in the OS
+ * keychain and never returned to the frontend.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { MCPTool, MCPToolCallResult } from '@/types/mcp'
+
+export type OpenApiAuth =
+  | { type: 'none' }
+  | { type: 'bearer' }
+  | { type: 'api_key'; name: string; location: 'header' | 'query' }
+  | { type: 'basic'; username: string }
+
+export interface OpenApiOperation {
+  /** Name of the tool, unique across all registered APIs */
+  toolName: string
+  operationId: string
+  method: string
+  path: string
+  description: string
+  parameters: { name: string; location: 'path' | 'query' | 'header' }[]
+  hasBody: boolean
+  inputSchema: MCPTool['inputSchema']
+}
+
+export interface OpenApiIntegration {
+  id: string
+  name: string
+  /** URL or path the spec was read from */
+  source: string
+  baseUrl: string
+  auth: OpenApiAuth
+  maxResponseBytes: number
+  operations: OpenApiOperation[]
+  createdAt: number
+  /** A secret is stored in the keychain */
+  hasCredential: boolean
+}
+
+export interface OpenApiOptions {
+  /** Defaults to the spec's title */
+  name?: string
+  /** operationIds (or `METHOD /path`) to expose; all when left out */
+  operations?: string[]
+  /** Overrides the first server of the spec */
+  baseUrl?: string
+  maxResponseBytes?: number
+}
+
+export interface HttpToolResult {
+  status: number
+  content: string
+  truncated: boolean
+  /** The API answered with a status outside 2xx */
+  isError: boolean
+}
+
+/**
+ * Register the operations of an OpenAPI 3 spec as tools. `source` is an
+ * http(s) URL or an absolute path to a JSON or YAML file.
+ */
+export function registerOpenApiTool(
+  source: string,
+  auth?: OpenApiAuth,
+  credential?: string,
+  options?: OpenApiOptions
+): Promise<OpenApiIntegration> {
+  return invoke<OpenApiIntegration>('register_openapi_tool', {
+    source,
+    auth,
+    credential,
+    options,
+  })
+}
+
+export function listOpenApiTools(): Promise<OpenApiIntegration[]> {
+  return invoke<OpenApiIntegration[]>('list_openapi_tools')
+}
+
+export function removeOpenApiTool(id: string): Promise<void> {
+  return invoke('remove_openapi_tool', { id })
+}
+
+export function callOpenApiTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<HttpToolResult> {
+  return invoke<HttpToolResult>('call_openapi_tool', {
+    toolName,
+    arguments: args,
+  })
+}
+
+/** Every registered operation as a tool definition for the model */
+export async function getOpenApiToolDefinitions(): Promise<MCPTool[]> {
+  const integrations = await listOpenApiTools()
+  return integrations.flatMap(integration =>
+    integration.operations.map(operation => ({
+      name: operation.toolName,
+      description: operation.description,
+      inputSchema: operation.inputSchema,
+    }))
+  )
+}
+
+/** Call an OpenAPI tool, reporting the HTTP status with the response */
+export async function executeOpenApiTool(
+  toolName: string,
+  args: Record<string, unknown>
+): Promise<MCPToolCallResult> {
+  const result = await callOpenApiTool(toolName, args)
+  return {
+    content: [
+      { type: 'text', text: `HTTP ${result.status}\n\n${result.content}` },
+    ],
+    isError: result.isError,
+  }
+}
@@ -0,0 +1,71 @@
+/**
+ * Operations
+ * One surface for every long-running backend task: imports, exports, model
+ * downloads, storage migrations and benchmarks. Each is registered with an
+ * id, reports typed progress, and can be cancelled; the commands that start
+ * them still resolve with their own result.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type OperationKind =
+  | 'import'
+  | 'export'
+  | 'model_download'
+  | 'storage_migration'
+  | 'benchmark'
+
+export type OperationStatus = 'running' | 'completed' | 'failed' | 'cancelled'
+
+export interface OperationProgress {
+  completed: number
+  /** Null while the amount of work is not known yet */
+  total: number | null
+  unit: 'items' | 'bytes'
+  /** What the task is doing right now, e.g. a download's current layer */
+  message: string | null
+}
+
+export interface OperationInfo {
+  id: string
+  kind: OperationKind
+  /** e.g. the model being downloaded or the file being imported */
+  label: string
+  status: OperationStatus
+  progress: OperationProgress
+  /** Set once a cancellation was asked for, while the task winds down */
+  cancelRequested: boolean
+  /** Milliseconds since the UNIX epoch */
+  startedAt: number
+  finishedAt: number | null
+  error: string | null
+}
+
+/** Current and recently finished operations, running ones first */
+export function listOperations(): Promise<OperationInfo[]> {
+  return invoke<OperationInfo[]>('list_operations')
+}
+
+/**
+ * Ask a running operation to stop. Resolves with false if it is not
+ * running. The task stops at its next check and finishes as cancelled.
+ */
+export function cancelOperation(id: string): Promise<boolean> {
+  return invoke<boolean>('cancel_operation', { id })
+}
+
+/**
+ * Follow every operation: called with the operation's state when it
+ * starts, progresses or finishes.
+ */
+export async function onOperationUpdate(
+  callback: (operation: OperationInfo) => void
+): Promise<UnlistenFn> {
+  const unlisteners = await Promise.all(
+    ['operation-started', 'operation-progress', 'operation-finished'].map(
+      event => listen<OperationInfo>(event, e => callback(e.payload))
+    )
+  )
+  return () => unlisteners.forEach(unlisten => unlisten())
+}
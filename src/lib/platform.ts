@@ -0,0 +1,76 @@
+/**
+ * Platform Integration
+ * Notifications with action buttons, launch at login, the global
+ * shortcut preference (`globalShortcut`), the main window's saved
+ * geometry and its pinned and compact modes. Notification actions are
+ * delivered on Linux; elsewhere the notification shows without buttons.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { WindowMode } from '@/types/preferences'
+
+export interface NotificationAction {
+  id: string
+  label: string
+}
+
+export interface NotificationActionEvent {
+  /** The id the notification was sent with */
+  notification: string
+  action: string
+}
+
+/** Up to four actions; clicking one fires `onNotificationAction` */
+export function sendActionableNotification(
+  id: string,
+  title: string,
+  body: string | undefined,
+  actions: NotificationAction[]
+): Promise<void> {
+  return invoke('send_actionable_notification', { id, title, body, actions })
+}
+
+export function onNotificationAction(
+  callback: (event: NotificationActionEvent) => void
+): Promise<UnlistenFn> {
+  return listen<NotificationActionEvent>('notification-action', event =>
+    callback(event.payload)
+  )
+}
+
+export function getLaunchAtLogin(): Promise<boolean> {
+  return invoke<boolean>('get_launch_at_login')
+}
+
+/** Starts the app in daemon mode, without a window, at login */
+export function setLaunchAtLogin(enabled: boolean): Promise<void> {
+  return invoke('set_launch_at_login', { enabled })
+}
+
+/**
+ * Forget where the main window was left and put it back at its default
+ * size, centered
+ */
+export function resetWindowState(): Promise<void> {
+  return invoke('reset_window_state')
+}
+
+/** Keeps the main window above other windows */
+export function setWindowPinned(pinned: boolean): Promise<WindowMode> {
+  return invoke<WindowMode>('set_window_pinned', { pinned })
+}
+
+/** Shrinks the main window to a small chat, or back to its full size */
+export function setCompactMode(compact: boolean): Promise<WindowMode> {
+  return invoke<WindowMode>('set_compact_mode', { compact })
+}
+
+/** Also fired when the mode is toggled from the View menu or the tray */
+export function onWindowModeChanged(
+  callback: (mode: WindowMode) => void
+): Promise<UnlistenFn> {
+  return listen<WindowMode>('window-mode-changed', event =>
+    callback(event.payload)
+  )
+}
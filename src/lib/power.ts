@@ -0,0 +1,37 @@
+/**
+ * Power Awareness
+ * Battery, idle and sleep state as the backend sees it. Heavy background
+ * work (scheduled exports, backups, watch-folder scans and model
+ * downloads) waits while `paused` is set: on battery, unless
+ * `power.pauseOnBattery` is switched off in the preferences, and while
+ * the system sleeps.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type PauseReason = 'on_battery' | 'sleep'
+
+export interface PowerState {
+  /** `false` on AC power and on machines without a battery */
+  onBattery: boolean
+  /** `null` without a battery or when the system doesn't say */
+  batteryPercent: number | null
+  /** No input for a while; `null` where the system doesn't say */
+  idle: boolean | null
+  sleeping: boolean
+  /** Why heavy background work waits, if it does */
+  paused: PauseReason | null
+}
+
+export function getPowerState(): Promise<PowerState> {
+  return invoke<PowerState>('get_power_state')
+}
+
+export function onPowerStateChanged(
+  callback: (state: PowerState) => void
+): Promise<UnlistenFn> {
+  return listen<PowerState>('power-state-changed', event =>
+    callback(event.payload)
+  )
+}
@@ -0,0 +1,74 @@
+/**
+ * Profiles
+ * Separate histories for people sharing one machine. The backend keeps a
+ * data directory and keychain namespace per profile; persisted frontend
+ * stores are namespaced with `profileStorageKey`. Switching restarts the app.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export const DEFAULT_PROFILE = 'default'
+/** localStorage key remembering the active profile across restarts */
+const ACTIVE_PROFILE_KEY = 'nexus-active-profile'
+
+export interface Profile {
+  id: string
+  name: string
+  /** Milliseconds since the UNIX epoch; 0 for the default profile */
+  createdAt: number
+}
+
+export interface ProfileList {
+  active: string
+  /** The default profile first */
+  profiles: Profile[]
+}
+
+function storedProfile(): string {
+  return localStorage.getItem(ACTIVE_PROFILE_KEY) ?? DEFAULT_PROFILE
+}
+
+/**
+ * localStorage key of a persisted store in the active profile. The default
+ * profile keeps the plain name so existing data stays where it is.
+ */
+export function profileStorageKey(name: string): string {
+  const profile = storedProfile()
+  return profile === DEFAULT_PROFILE ? name : `profile:${profile}:${name}`
+}
+
+export function listProfiles(): Promise<ProfileList> {
+  return invoke<ProfileList>('list_profiles')
+}
+
+export function createProfile(name: string): Promise<Profile> {
+  return invoke<Profile>('create_profile', { name })
+}
+
+/**
+ * Switch to another profile. The app restarts into it, so on success the
+ * promise never resolves.
+ */
+export async function switchProfile(id: string): Promise<void> {
+  const previous = storedProfile()
+  localStorage.setItem(ACTIVE_PROFILE_KEY, id)
+  try {
+    await invoke('switch_profile', { id })
+  } catch (error) {
+    localStorage.setItem(ACTIVE_PROFILE_KEY, previous)
+    throw error
+  }
+}
+
+/**
+ * Make sure the stores were loaded for the backend's active profile, e.g.
+ * after `profiles.json` was changed while the app was closed. Reloads the
+ * window if they were not.
+ */
+export async function syncActiveProfile(): Promise<void> {
+  const { active } = await listProfiles()
+  if (active !== storedProfile()) {
+    localStorage.setItem(ACTIVE_PROFILE_KEY, active)
+    window.location.reload()
+  }
+}
@@ -0,0 +1,74 @@
+/**
+ * Prompt Templates
+ * Reusable message lists with typed variables, stored and rendered in the
+ * Rust backend. Content uses Handlebars-style syntax: `{{name}}`,
+ * `{{#if name}}…{{else}}…{{/if}}`, `{{#unless name}}…{{/unless}}` and
+ * `{{#each name}}{{this}}{{/each}}`. Values are inserted without escaping.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type VariableType = 'string' | 'number' | 'boolean' | 'list'
+
+export type TemplateValue = string | number | boolean | (string | number)[]
+
+export interface TemplateVariable {
+  /** Letters, digits and underscores, not starting with a digit */
+  name: string
+  type: VariableType
+  description?: string | null
+  /** Rendering fails without a value, unless there is a default */
+  required?: boolean
+  default?: TemplateValue | null
+}
+
+export interface TemplateMessage {
+  role: 'system' | 'user' | 'assistant'
+  content: string
+}
+
+export interface PromptTemplateSettings {
+  name: string
+  description?: string | null
+  variables?: TemplateVariable[]
+  messages: TemplateMessage[]
+}
+
+export interface PromptTemplate extends Required<PromptTemplateSettings> {
+  id: string
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  updatedAt: number
+}
+
+export function listPromptTemplates(): Promise<PromptTemplate[]> {
+  return invoke<PromptTemplate[]>('list_prompt_templates')
+}
+
+export function createPromptTemplate(
+  settings: PromptTemplateSettings
+): Promise<PromptTemplate> {
+  return invoke<PromptTemplate>('create_prompt_template', { settings })
+}
+
+export function updatePromptTemplate(
+  id: string,
+  settings: PromptTemplateSettings
+): Promise<PromptTemplate> {
+  return invoke<PromptTemplate>('update_prompt_template', { id, settings })
+}
+
+export function deletePromptTemplate(id: string): Promise<void> {
+  return invoke('delete_prompt_template', { id })
+}
+
+/**
+ * Render a template into the messages to send; variables left out take
+ * their default. Messages that render empty are left out.
+ */
+export function renderPrompt(
+  templateId: string,
+  vars: Record<string, TemplateValue> = {}
+): Promise<TemplateMessage[]> {
+  return invoke<TemplateMessage[]>('render_prompt', { templateId, vars })
+}
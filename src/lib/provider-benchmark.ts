@@ -0,0 +1,77 @@
+/**
+ * Provider Benchmark
+ * Runs latency benchmarks from the Rust backend with the API keys stored in
+ * the keychain
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { getModelById } from '@/constants/models'
+import type { Provider } from '@/types/multimodal'
+
+// ============================================
+// Types
+// ============================================
+
+export interface BenchmarkRun {
+  ttftMs: number | null
+  totalMs: number
+  outputTokens: number
+  tokensEstimated: boolean
+  error: string | null
+}
+
+export interface BenchmarkResult {
+  provider: Provider
+  model: string
+  runs: BenchmarkRun[]
+  medianTtftMs: number | null
+  tokensPerSec: number | null
+  /** 0.0 to 1.0 */
+  errorRate: number
+}
+
+export interface BenchmarkReport {
+  /** Milliseconds since the UNIX epoch */
+  timestamp: number
+  prompt: string
+  iterations: number
+  results: BenchmarkResult[]
+}
+
+/** Providers the backend can call */
+export const BENCHMARK_PROVIDERS: Provider[] = ['google', 'openai', 'groq']
+
+// ============================================
+// Commands
+// ============================================
+
+/**
+ * Benchmark models concurrently. Models whose provider has no API key
+ * stored are skipped.
+ */
+export async function benchmarkProviders(
+  modelIds: string[],
+  prompt: string,
+  iterations = 3
+): Promise<BenchmarkReport> {
+  const models = modelIds.flatMap(id => {
+    const model = getModelById(id)
+    if (!model || !BENCHMARK_PROVIDERS.includes(model.provider)) return []
+    return [{ provider: model.provider, model: id }]
+  })
+
+  return invoke<BenchmarkReport>('benchmark_providers', {
+    models,
+    prompt,
+    iterations,
+  })
+}
+
+/**
+ * Past benchmark reports, newest first
+ */
+export async function getBenchmarkHistory(
+  limit?: number
+): Promise<BenchmarkReport[]> {
+  return invoke<BenchmarkReport[]>('get_benchmark_history', { limit })
+}
@@ -0,0 +1,34 @@
+/**
+ * Provider Models
+ * Lists the chat models a provider offers to the stored API key, from the Rust
+ * backend, so the model picker can show what an account can actually use.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/** Providers the backend has adapters for */
+export type BackendProvider =
+  | 'google'
+  | 'openai'
+  | 'groq'
+  | 'mistral'
+  | 'ollama'
+
+export interface ProviderModel {
+  /** The model id for requests */
+  id: string
+  /** Display name, when the provider has one */
+  name: string | null
+  /** Input tokens, when the provider reports it */
+  contextWindow: number | null
+}
+
+/**
+ * Chat models available at `provider`, sorted by id. Ollama lists the
+ * models pulled into the local daemon and needs no key.
+ */
+export function listProviderModels(
+  provider: BackendProvider
+): Promise<ProviderModel[]> {
+  return invoke<ProviderModel[]>('list_provider_models', { provider })
+}
@@ -0,0 +1,78 @@
+/**
+ * Model Providers
+ * A backend registry of OpenAI-compatible and Anthropic-style endpoints
+ * (vLLM, LM Studio, OpenRouter, ...). API keys are kept in the system
+ * keychain; pass `{ providerId }` to `streamChatCompletion` to use one.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { ProviderApi } from './chat-proxy'
+
+export type ProviderAuth =
+  | { type: 'none' }
+  /** `Authorization: Bearer <key>` */
+  | { type: 'bearer' }
+  /** The key as the value of a header, e.g. `x-api-key` */
+  | { type: 'header'; name: string }
+
+export interface ProviderSettings {
+  name: string
+  api?: ProviderApi
+  /** e.g. `http://localhost:1234/v1` or `https://openrouter.ai/api/v1` */
+  baseUrl: string
+  auth?: ProviderAuth
+  defaultModel?: string | null
+  /** Extra headers, e.g. `HTTP-Referer` for OpenRouter */
+  headers?: Record<string, string>
+}
+
+export interface ProviderInfo extends Required<ProviderSettings> {
+  id: string
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  updatedAt: number
+  /** An API key is stored in the keychain */
+  hasApiKey: boolean
+}
+
+export interface ConnectionTest {
+  ok: boolean
+  /** HTTP status of the models request, if the server answered */
+  status: number | null
+  latencyMs: number
+  /** Models the endpoint lists, if it lists them */
+  models: string[] | null
+  error: string | null
+}
+
+export function listProviders(): Promise<ProviderInfo[]> {
+  return invoke<ProviderInfo[]>('list_providers')
+}
+
+export function addProvider(
+  settings: ProviderSettings,
+  apiKey?: string
+): Promise<ProviderInfo> {
+  return invoke<ProviderInfo>('add_provider', { settings, apiKey })
+}
+
+/**
+ * Replace a provider's settings. A non-empty `apiKey` replaces the stored
+ * key, an empty one removes it, and leaving it out keeps it.
+ */
+export function updateProvider(
+  id: string,
+  settings: ProviderSettings,
+  apiKey?: string
+): Promise<ProviderInfo> {
+  return invoke<ProviderInfo>('update_provider', { id, settings, apiKey })
+}
+
+export function removeProvider(id: string): Promise<void> {
+  return invoke('remove_provider', { id })
+}
+
+/** List the provider's models with its key, to check it is reachable */
+export function testProviderConnection(id: string): Promise<ConnectionTest> {
+  return invoke<ConnectionTest>('test_provider_connection', { id })
+}
@@ -0,0 +1,25 @@
+/**
+ * Quick Actions
+ * Actions picked from the macOS dock menu or the Windows jump list. A
+ * launch from the jump list leaves its action pending for the new window;
+ * a window that is already open receives it as an event.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type QuickAction =
+  | { type: 'new_chat' }
+  | { type: 'open_conversation'; id: string }
+  | { type: 'switch_profile'; id: string }
+
+/** Call once the window has loaded; returns the action only once */
+export function takePendingQuickAction(): Promise<QuickAction | null> {
+  return invoke<QuickAction | null>('take_pending_quick_action')
+}
+
+export function onQuickAction(
+  callback: (action: QuickAction) => void
+): Promise<UnlistenFn> {
+  return listen<QuickAction>('quick-action', event => callback(event.payload))
+}
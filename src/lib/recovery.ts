@@ -11,6 +11,11 @@ import { logger } from '@/lib/logger'
 export interface RecoveryOptions {
   /** Suppress error notifications (useful for background saves) */
   silent?: boolean
+  /**
+   * Workspace id or window label the data belongs to. Files in different
+   * namespaces never overwrite each other; omit it for app-wide data.
+   */
+  namespace?: string
 }
 
 /**
@@ -40,6 +45,7 @@ export async function saveEmergencyData(
     await invoke('save_emergency_data', {
       filename,
       data,
+      namespace: options.namespace,
     })
 
     if (!options.silent) {
@@ -51,10 +57,54 @@ export async function saveEmergencyData(
   }
 }
 
+export interface EmergencyEntry {
+  /** Base filename (without extension) */
+  filename: string
+  data: unknown
+}
+
+/**
+ * Save several recovery files as one unit: either all of them are written
+ * or none are, so an interrupted save never leaves a partial session behind
+ *
+ * @param entries Files to save, at most 100
+ * @param options Recovery options
+ *
+ * @example
+ * ```typescript
+ * await saveEmergencyBatch([
+ *   { filename: 'session-tabs', data: tabs },
+ *   { filename: 'session-drafts', data: drafts },
+ * ])
+ * ```
+ */
+export async function saveEmergencyBatch(
+  entries: EmergencyEntry[],
+  options: RecoveryOptions = {}
+): Promise<void> {
+  const filenames = entries.map(entry => entry.filename)
+  try {
+    logger.debug('Saving emergency data batch', { filenames })
+
+    await invoke('save_emergency_batch', {
+      entries,
+      namespace: options.namespace,
+    })
+
+    if (!options.silent) {
+      logger.info('Emergency data batch saved successfully', { filenames })
+    }
+  } catch (error) {
+    logger.error('Failed to save emergency data batch', { filenames, error })
+    throw error
+  }
+}
+
 /**
  * Load data from a recovery file
  *
  * @param filename Base filename (without extension)
+ * @param namespace Namespace the file was saved in, if any
  * @returns The recovered data or null if file doesn't exist
  *
  * @example
@@ -67,13 +117,15 @@ export async function saveEmergencyData(
  * ```
  */
 export async function loadEmergencyData<T = unknown>(
-  filename: string
+  filename: string,
+  namespace?: string
 ): Promise<T | null> {
   try {
     logger.debug('Loading emergency data', { filename })
 
     const data = await invoke<T>('load_emergency_data', {
       filename,
+      namespace,
     })
 
     logger.info('Emergency data loaded successfully', { filename })
@@ -124,6 +176,26 @@ export async function cleanupOldFiles(): Promise<number> {
   }
 }
 
+/**
+ * Remove every recovery file of a namespace, e.g. when its workspace is
+ * deleted or its window closes cleanly
+ *
+ * @param namespace Workspace id or window label
+ * @returns Number of files removed
+ */
+export async function cleanupNamespace(namespace: string): Promise<number> {
+  try {
+    const removedCount = await invoke<number>('cleanup_namespace', {
+      namespace,
+    })
+    logger.debug('Cleaned up recovery namespace', { namespace, removedCount })
+    return removedCount
+  } catch (error) {
+    logger.error('Failed to clean up recovery namespace', { namespace, error })
+    throw error
+  }
+}
+
 /**
  * Save app state with timestamp for crash recovery
  * This is typically called by the error boundary
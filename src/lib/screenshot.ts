@@ -0,0 +1,19 @@
+/**
+ * Screenshots
+ * Takes a screenshot with the platform's own tooling, with this window
+ * hidden meanwhile, and stores it as a PNG attachment ready to send to a
+ * vision model.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { StoredAttachment } from './attachments'
+
+/** `window` and `region` let the user pick what to capture */
+export type ScreenshotMode = 'full_screen' | 'window' | 'region'
+
+/** Resolves with null if the user cancelled the selection */
+export function captureScreenshot(
+  mode: ScreenshotMode
+): Promise<StoredAttachment | null> {
+  return invoke<StoredAttachment | null>('capture_screenshot', { mode })
+}
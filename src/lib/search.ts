@@ -0,0 +1,40 @@
+/**
+ * Message Search
+ * Full-text search over the conversation store. Words must all match (the
+ * last one as a prefix) and "quoted phrases" match exactly.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export interface SearchFilters {
+  conversationIds?: string[]
+  roles?: string[]
+  /** Messages sent at or after this time (milliseconds since the UNIX epoch) */
+  since?: number
+  /** Messages sent before this time */
+  until?: number
+  limit?: number
+}
+
+export interface SearchHit {
+  conversationId: string
+  conversationTitle: string
+  messageId: string
+  role: string
+  /** Milliseconds since the UNIX epoch */
+  timestamp: number
+  /** Excerpt around the best match, with `…` where text was cut */
+  snippet: string
+  /** `[start, end)` of each matched term in `snippet`, usable with `slice` */
+  highlights: [number, number][]
+  /** BM25 score; lower is a better match */
+  rank: number
+}
+
+/** Search stored messages, best matches first */
+export function searchMessages(
+  query: string,
+  filters?: SearchFilters
+): Promise<SearchHit[]> {
+  return invoke<SearchHit[]>('search_messages', { query, filters })
+}
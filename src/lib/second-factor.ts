@@ -0,0 +1,84 @@
+/**
+ * Second Factor
+ * Client side of the TOTP / security key check the backend enforces before
+ * high-risk operations. The backend rejects a protected call with a
+ * "Second factor required" error when it needs a code; these helpers ask
+ * for one and retry.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { SecondFactorSettings } from '@/types/preferences'
+
+/** Prefix of the backend error asking for a code */
+const SECOND_FACTOR_REQUIRED = 'Second factor required'
+
+export interface TotpEnrollment {
+  /** Base32 secret for manual entry */
+  secret: string
+  /** otpauth:// URI for QR codes */
+  otpauthUri: string
+}
+
+export function isSecondFactorRequired(error: unknown): boolean {
+  return String(error).startsWith(SECOND_FACTOR_REQUIRED)
+}
+
+/**
+ * Runs a protected backend call, prompting for an authenticator code and
+ * retrying once if the backend asks for one. Resolves to null when the user
+ * cancels the prompt.
+ */
+export async function withSecondFactor<T>(
+  run: (code: string | null) => Promise<T>
+): Promise<T | null> {
+  try {
+    return await run(null)
+  } catch (error) {
+    if (!isSecondFactorRequired(error)) throw error
+    const code = window.prompt('Enter the code from your authenticator app')
+    if (!code) return null
+    return run(code.trim())
+  }
+}
+
+export async function wipeAppData(): Promise<boolean> {
+  const result = await withSecondFactor(secondFactorCode =>
+    invoke('wipe_app_data', { secondFactorCode })
+  )
+  return result !== null
+}
+
+export async function exportAllData(
+  path: string,
+  mcpServers: unknown[]
+): Promise<boolean> {
+  const result = await withSecondFactor(secondFactorCode =>
+    invoke('export_all_data', { path, mcpServers, secondFactorCode })
+  )
+  return result !== null
+}
+
+export function beginTotpEnrollment(): Promise<TotpEnrollment> {
+  return invoke<TotpEnrollment>('begin_totp_enrollment')
+}
+
+/** Registers a security key; resolves once the key has been touched */
+export function enrollSecurityKey(): Promise<void> {
+  return invoke('enroll_security_key')
+}
+
+/**
+ * Changes the second factor settings. `currentCode` satisfies the factor in
+ * use, `enrollmentCode` confirms a pending authenticator app setup.
+ */
+export function configureSecondFactor(
+  settings: SecondFactorSettings,
+  currentCode?: string,
+  enrollmentCode?: string
+): Promise<void> {
+  return invoke('configure_second_factor', {
+    settings,
+    currentCode: currentCode ?? null,
+    enrollmentCode: enrollmentCode ?? null,
+  })
+}
@@ -0,0 +1,161 @@
+/**
+ * Session Handoff
+ * Moves a live conversation to another machine via a signed session file
+ * that also carries the MCP servers the conversation relies on.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { ask, open, save } from '@tauri-apps/plugin-dialog'
+import { logger } from '@/lib/logger'
+import { findToolServer } from '@/services/mcp'
+import type { Conversation } from '@/store/chat-store'
+import { useMCPStore } from '@/store/mcp-store'
+import type {
+  MCPDockerConfig,
+  MCPServerConfig,
+  MCPTransportType,
+} from '@/types/mcp'
+
+// ============================================
+// Types
+// ============================================
+
+/** Server entry in a session file; secret values are blank */
+export interface SessionServer {
+  id: string
+  name: string
+  transport: MCPTransportType
+  command?: string
+  args?: string[]
+  url?: string
+  env?: Record<string, string>
+  headers?: Record<string, string>
+  docker?: MCPDockerConfig
+  isolateEnv?: boolean
+  startupTimeoutMs?: number
+}
+
+interface LiveSessionImport {
+  conversationId: string
+  title: string
+  versionBumped: boolean
+  servers: SessionServer[]
+}
+
+export interface HandoffImportResult {
+  conversationId: string
+  title: string
+  /** Servers added to the MCP store; their secrets still need to be filled in */
+  installedServers: string[]
+}
+
+// ============================================
+// Export
+// ============================================
+
+/**
+ * Servers whose tools appear in the conversation, falling back to every
+ * enabled server when no tool has been called yet
+ */
+function serversForConversation(conversation: Conversation): MCPServerConfig[] {
+  const { servers } = useMCPStore.getState()
+
+  const used = new Set<string>()
+  for (const message of conversation.messages) {
+    for (const toolCall of message.toolCalls ?? []) {
+      const serverId = findToolServer(toolCall.name)
+      if (serverId) used.add(serverId)
+    }
+  }
+
+  return used.size > 0
+    ? servers.filter(s => used.has(s.id))
+    : servers.filter(s => s.enabled)
+}
+
+/**
+ * Save a signed session file for a conversation
+ *
+ * @returns The file path, or undefined if the user cancelled
+ */
+export async function exportLiveSession(
+  conversation: Conversation,
+  passphrase: string
+): Promise<string | undefined> {
+  const path = await save({
+    defaultPath: `${conversation.title.replace(/[^a-z0-9]/gi, '_')}.nexus-session`,
+    filters: [{ name: 'Nexus Session', extensions: ['nexus-session'] }],
+  })
+  if (!path) return undefined
+
+  // Make sure the backend has the latest version before it reads it
+  await invoke('sync_conversation', { conversation })
+
+  await invoke('export_live_session', {
+    id: conversation.id,
+    servers: serversForConversation(conversation),
+    passphrase,
+    path,
+  })
+  logger.info('Exported live session', { id: conversation.id, path })
+  return path
+}
+
+// ============================================
+// Import
+// ============================================
+
+/**
+ * Import a session file, offering to install servers that are not
+ * configured on this machine
+ *
+ * @returns The import result, or undefined if the user cancelled
+ */
+export async function importLiveSession(
+  passphrase: string
+): Promise<HandoffImportResult | undefined> {
+  const path = await open({
+    multiple: false,
+    filters: [{ name: 'Nexus Session', extensions: ['nexus-session'] }],
+  })
+  if (!path) return undefined
+
+  const result = await invoke<LiveSessionImport>('import_live_session', {
+    path,
+    passphrase,
+  })
+
+  const { servers, addServer } = useMCPStore.getState()
+  const known = new Set(servers.map(s => s.id))
+  const missing = result.servers.filter(s => !known.has(s.id))
+
+  const installedServers: string[] = []
+  if (missing.length > 0) {
+    const install = await ask(
+      `This session uses MCP servers that are not set up here:\n\n${missing
+        .map(s => `• ${s.name}`)
+        .join('\n')}\n\nAdd them now? Secrets such as API keys are not included in the file and must be entered before enabling them.`,
+      { title: 'Missing MCP servers', kind: 'info' }
+    )
+
+    if (install) {
+      for (const server of missing) {
+        // Disabled until the user fills in the blanked secrets
+        addServer({ ...server, enabled: false } as MCPServerConfig)
+        installedServers.push(server.id)
+      }
+    }
+  }
+
+  logger.info('Imported live session', {
+    id: result.conversationId,
+    versionBumped: result.versionBumped,
+    installedServers,
+  })
+
+  return {
+    conversationId: result.conversationId,
+    title: result.title,
+    installedServers,
+  }
+}
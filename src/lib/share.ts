@@ -0,0 +1,17 @@
+/**
+ * Share Sheet
+ * Opens the system share UI (the macOS share sheet, the Windows Share
+ * dialog) for an exported file or a piece of text. Linux has none: both
+ * calls reject there, and callers should offer saving the file instead.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/** `path` must be an absolute path to an existing file */
+export function shareFile(path: string): Promise<void> {
+  return invoke('share_file', { path })
+}
+
+export function shareText(text: string): Promise<void> {
+  return invoke('share_text', { text })
+}
@@ -0,0 +1,75 @@
+/**
+ * Text to Speech
+ * Reads text aloud with the system's own voices. One utterance is spoken
+ * at a time; speaking another stops the current one. `onTtsProgress`
+ * follows it word by word, with offsets into the string that was passed.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface Voice {
+  /** Pass to `speakText` as `voice` */
+  id: string
+  name: string
+  language: string | null
+}
+
+export type SpeechEvent =
+  | 'started'
+  | 'word'
+  | 'paused'
+  | 'resumed'
+  | 'finished'
+  | 'cancelled'
+  | 'failed'
+
+export interface TtsProgress {
+  utteranceId: string
+  event: SpeechEvent
+  /** Index of the word being spoken in the text, for `word` events */
+  offset: number | null
+  length: number | null
+}
+
+export interface SpeakOptions {
+  /** A voice id from `listVoices`; the system default otherwise */
+  voice?: string
+  /** Multiple of the normal speed, 0.5 to 2 */
+  rate?: number
+}
+
+/** Starts speaking `text`; resolves with the utterance id */
+export function speakText(
+  text: string,
+  options: SpeakOptions = {}
+): Promise<string> {
+  return invoke<string>('speak_text', {
+    text,
+    voice: options.voice ?? null,
+    rate: options.rate ?? null,
+  })
+}
+
+/** Each resolves with whether an utterance was being spoken */
+export function pauseSpeech(): Promise<boolean> {
+  return invoke<boolean>('pause_speech')
+}
+
+export function resumeSpeech(): Promise<boolean> {
+  return invoke<boolean>('resume_speech')
+}
+
+export function stopSpeech(): Promise<boolean> {
+  return invoke<boolean>('stop_speech')
+}
+
+export function listVoices(): Promise<Voice[]> {
+  return invoke<Voice[]>('list_voices')
+}
+
+export function onTtsProgress(
+  callback: (progress: TtsProgress) => void
+): Promise<UnlistenFn> {
+  return listen<TtsProgress>('tts-progress', event => callback(event.payload))
+}
@@ -0,0 +1,328 @@
+/**
+ * Conversation Store
+ * Conversations kept in the backend database, paged and appended to one
+ * message at a time instead of saved as one JSON blob. Tags, pins and
+ * folders are stored with them, as are earlier versions of edited messages
+ * and deleted messages until they are purged. Old conversations can be
+ * archived out of the way, compressed, and unarchived when needed.
+ * Archived and locked conversations are read-only: the backend rejects
+ * changes to their messages with a "Conversation is read-only" error.
+ * Conversations can also be moved to another storage backend.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import type { ChatMessage, Conversation } from '@/store/chat-store'
+import type { StorageBackend } from '@/types/preferences'
+
+/** Prefix of the backend error for changes to a read-only conversation */
+const CONVERSATION_READ_ONLY = 'Conversation is read-only'
+
+export function isConversationReadOnly(error: unknown): boolean {
+  return String(error).startsWith(CONVERSATION_READ_ONLY)
+}
+
+export interface ConversationSummary {
+  id: string
+  title: string
+  modelId: string
+  messageCount: number
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  /** Milliseconds since the UNIX epoch */
+  updatedAt: number
+  pinned: boolean
+  /** Read-only until unlocked */
+  locked: boolean
+  folderId: string | null
+  /** Sorted alphabetically */
+  tags: string[]
+}
+
+export interface ConversationFilter {
+  /** Only conversations in this folder; `''` for those in no folder */
+  folderId?: string
+  /** Only conversations with this tag, ignoring case */
+  tag?: string
+  pinned?: boolean
+}
+
+export interface Folder {
+  id: string
+  name: string
+  /** null for top-level folders */
+  parentId: string | null
+  createdAt: number
+}
+
+export interface TagCount {
+  tag: string
+  count: number
+}
+
+export interface MessageRevision {
+  /** 1 for the message's original content */
+  revision: number
+  content: ChatMessage['content']
+  /** When an edit replaced it, in milliseconds since the UNIX epoch */
+  replacedAt: number
+}
+
+export interface ConversationPage {
+  /** Pinned first, then most recently updated first */
+  conversations: ConversationSummary[]
+  /** Zero-based page number */
+  page: number
+  pageSize: number
+  /** Conversations across all pages */
+  total: number
+}
+
+/**
+ * Create an empty conversation. Without an `id` the backend generates one.
+ */
+export function createConversation(conversation: {
+  id?: string
+  title: string
+  modelId: string
+  systemPrompt?: string
+}): Promise<ConversationSummary> {
+  return invoke<ConversationSummary>('create_conversation', conversation)
+}
+
+/**
+ * Add a message to the end of a conversation. Fails for an archived one
+ * unless the `unarchiveOnReply` preference is set.
+ */
+export function appendMessage(
+  conversationId: string,
+  message: ChatMessage
+): Promise<void> {
+  return invoke('append_message', { conversationId, message })
+}
+
+export function getConversation(id: string): Promise<Conversation | null> {
+  return invoke<Conversation | null>('get_conversation', { id })
+}
+
+export function listConversations(
+  page: number,
+  pageSize?: number,
+  filter?: ConversationFilter
+): Promise<ConversationPage> {
+  return invoke<ConversationPage>('list_conversations', {
+    page,
+    pageSize,
+    filter,
+  })
+}
+
+/** @returns Whether the conversation existed */
+export function deleteConversation(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_conversation', { id })
+}
+
+/** Every folder; build the tree from `parentId` */
+export function listFolders(): Promise<Folder[]> {
+  return invoke<Folder[]>('list_folders')
+}
+
+export function createFolder(name: string, parentId?: string): Promise<Folder> {
+  return invoke<Folder>('create_folder', { name, parentId })
+}
+
+export function renameFolder(id: string, name: string): Promise<void> {
+  return invoke('rename_folder', { id, name })
+}
+
+/** Move a folder into another one, or to the top level with `null` */
+export function moveFolder(id: string, parentId: string | null): Promise<void> {
+  return invoke('move_folder', { id, parentId })
+}
+
+/**
+ * Delete a folder and its subfolders; their conversations are kept.
+ * @returns Whether the folder existed
+ */
+export function deleteFolder(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_folder', { id })
+}
+
+/** Put a conversation into a folder, or into none with `null` */
+export function moveConversation(
+  conversationId: string,
+  folderId: string | null
+): Promise<void> {
+  return invoke('move_conversation', { conversationId, folderId })
+}
+
+export function setConversationPinned(
+  conversationId: string,
+  pinned: boolean
+): Promise<void> {
+  return invoke('set_conversation_pinned', { conversationId, pinned })
+}
+
+/** Replace a conversation's tags; resolves to the tags as stored */
+export function setConversationTags(
+  conversationId: string,
+  tags: string[]
+): Promise<string[]> {
+  return invoke<string[]>('set_conversation_tags', { conversationId, tags })
+}
+
+/** Every tag in use, most used first */
+export function listTags(): Promise<TagCount[]> {
+  return invoke<TagCount[]>('list_tags')
+}
+
+/** Replace a message's content; resolves to the revision that keeps the old */
+export function editMessage(
+  conversationId: string,
+  messageId: string,
+  content: ChatMessage['content']
+): Promise<number> {
+  return invoke<number>('edit_message', { conversationId, messageId, content })
+}
+
+/** Earlier versions of a message, newest first */
+export function getMessageHistory(
+  conversationId: string,
+  messageId: string
+): Promise<MessageRevision[]> {
+  return invoke<MessageRevision[]>('get_message_history', {
+    conversationId,
+    messageId,
+  })
+}
+
+/** Make an earlier revision current again; this is recorded as an edit */
+export function restoreMessageRevision(
+  conversationId: string,
+  messageId: string,
+  revision: number
+): Promise<number> {
+  return invoke<number>('restore_message_revision', {
+    conversationId,
+    messageId,
+    revision,
+  })
+}
+
+/** Hide a message until it is restored or purged */
+export function deleteMessage(
+  conversationId: string,
+  messageId: string
+): Promise<boolean> {
+  return invoke<boolean>('delete_message', { conversationId, messageId })
+}
+
+export function restoreMessage(
+  conversationId: string,
+  messageId: string
+): Promise<boolean> {
+  return invoke<boolean>('restore_message', { conversationId, messageId })
+}
+
+/**
+ * Permanently remove messages deleted before `olderThan` (milliseconds since
+ * the UNIX epoch); resolves to how many were removed
+ */
+export function purgeDeleted(olderThan: number): Promise<number> {
+  return invoke<number>('purge_deleted', { olderThan })
+}
+
+export interface ArchivedConversationSummary {
+  id: string
+  title: string
+  modelId: string
+  messageCount: number
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+  updatedAt: number
+  archivedAt: number
+  tags: string[]
+  /** The start of the first user message */
+  preview: string
+  /** Bytes of the uncompressed conversation */
+  size: number
+  /** Bytes stored */
+  compressedSize: number
+}
+
+export interface ArchivedConversationPage {
+  /** Most recently updated first */
+  conversations: ArchivedConversationSummary[]
+  page: number
+  pageSize: number
+  total: number
+}
+
+/** Compress a conversation out of listings and search until unarchived */
+export function archiveConversation(id: string): Promise<boolean> {
+  return invoke<boolean>('archive_conversation', { id })
+}
+
+/**
+ * Archive every unpinned conversation last updated before `before`
+ * (milliseconds since the UNIX epoch); resolves to how many were archived
+ */
+export function archiveConversationsBefore(before: number): Promise<number> {
+  return invoke<number>('archive_conversations_before', { before })
+}
+
+export function unarchiveConversation(id: string): Promise<boolean> {
+  return invoke<boolean>('unarchive_conversation', { id })
+}
+
+/** Archive several conversations; resolves to how many were archived */
+export function archiveConversations(ids: string[]): Promise<number> {
+  return invoke<number>('archive_conversations', { ids })
+}
+
+/** Unarchive several conversations; resolves to how many were archived */
+export function unarchiveConversations(ids: string[]): Promise<number> {
+  return invoke<number>('unarchive_conversations', { ids })
+}
+
+/**
+ * Make conversations read-only, or writable again; resolves to how many
+ * changed
+ */
+export function setConversationsLocked(
+  ids: string[],
+  locked: boolean
+): Promise<number> {
+  return invoke<number>('set_conversations_locked', { ids, locked })
+}
+
+/** `query` matches title, tags or first message, ignoring case */
+export function listArchivedConversations(
+  query?: string,
+  page?: number,
+  pageSize?: number
+): Promise<ArchivedConversationPage> {
+  return invoke<ArchivedConversationPage>('list_archived_conversations', {
+    query,
+    page,
+    pageSize,
+  })
+}
+
+export interface StorageMigration {
+  backend: StorageBackend
+  copied: number
+  /** Already present in the target backend, left as they were */
+  skipped: number
+}
+
+export function getStorageBackend(): Promise<StorageBackend> {
+  return invoke<StorageBackend>('get_storage_backend')
+}
+
+/**
+ * Copy every conversation to another backend and switch to it. The data in
+ * the previous backend is kept.
+ */
+export function migrateStorage(to: StorageBackend): Promise<StorageMigration> {
+  return invoke<StorageMigration>('migrate_storage', { to })
+}
@@ -0,0 +1,70 @@
+/**
+ * Conversation Tasks
+ * Action items extracted from conversations by a model, with native
+ * reminders sent by the backend when a task falls due.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { getModelById } from '@/constants/models'
+import { BENCHMARK_PROVIDERS } from '@/lib/provider-benchmark'
+
+export interface Task {
+  id: number
+  conversationId: string
+  title: string
+  details: string | null
+  /** Milliseconds since the UNIX epoch */
+  dueAt: number | null
+  completed: boolean
+  /** A reminder notification was sent */
+  reminded: boolean
+  /** Milliseconds since the UNIX epoch */
+  createdAt: number
+}
+
+/**
+ * Extract action items from a conversation with the given model. Returns
+ * only tasks that were not already stored for the conversation.
+ */
+export async function extractTasks(
+  conversationId: string,
+  modelId: string
+): Promise<Task[]> {
+  const model = getModelById(modelId)
+  if (!model || !BENCHMARK_PROVIDERS.includes(model.provider)) {
+    throw new Error(`Task extraction is not supported for ${modelId}`)
+  }
+  return invoke<Task[]>('extract_tasks', {
+    conversationId,
+    provider: model.provider,
+    model: modelId,
+  })
+}
+
+/** Open tasks, soonest due first; optionally for one conversation */
+export function listTasks(
+  conversationId?: string,
+  includeCompleted = false
+): Promise<Task[]> {
+  return invoke<Task[]>('list_tasks', { conversationId, includeCompleted })
+}
+
+/**
+ * Mark a task done or change its due date. Pass `dueAt: null` to clear the
+ * due date; a new due date re-arms the reminder.
+ */
+export function updateTask(
+  id: number,
+  changes: { completed?: boolean; dueAt?: number | null }
+): Promise<Task> {
+  return invoke<Task>('update_task', {
+    id,
+    completed: changes.completed,
+    dueAt: changes.dueAt ?? undefined,
+    clearDue: changes.dueAt === null,
+  })
+}
+
+export function deleteTask(id: number): Promise<void> {
+  return invoke('delete_task', { id })
+}
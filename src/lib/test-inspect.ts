@@ -0,0 +1,16 @@
+/**
+ * Test Inspection
+ * Backend state for integration and end-to-end tests to assert on. Only
+ * answered by debug builds or builds with the `test-inspect` feature.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/**
+ * Part of the backend state: dot-separated keys, numbers indexing arrays,
+ * e.g. `mcp.queue.queued` or `scheduler.jobs.0.runs`; `''` for all of it.
+ * Top-level keys are `mcp`, `scheduler`, `caches`, `daemon` and `storage`.
+ */
+export function testInspect<T = unknown>(statePath = ''): Promise<T> {
+  return invoke<T>('__test_inspect', { statePath })
+}
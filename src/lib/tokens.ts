@@ -0,0 +1,44 @@
+/**
+ * Token Counting
+ * Counts prompt sizes locally in the Rust backend, from the tokenizer
+ * family of the model, so no tokenizer ships to the webview and nothing is
+ * sent to a provider. OpenAI models are counted exactly; other families
+ * are estimates, typically within about 10%, and flagged as such.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type Tokenizer =
+  | 'o200k'
+  | 'cl100k'
+  | 'claude'
+  | 'llama3'
+  | 'gemini'
+  | 'sentence_piece'
+
+export interface TokenCount {
+  tokens: number
+  tokenizer: Tokenizer
+  /** The count is an estimate rather than an exact tokenizer count */
+  estimated: boolean
+  /** US dollars as input tokens; null for models without a known price */
+  inputCost: number | null
+}
+
+export function countTextTokens(
+  model: string,
+  text: string
+): Promise<TokenCount> {
+  return invoke<TokenCount>('count_tokens', { model, text })
+}
+
+/**
+ * Count the prompt tokens of chat messages in the OpenAI or Anthropic
+ * shape, including per-message overhead; image parts count a flat 765
+ */
+export function countMessageTokens(
+  model: string,
+  messages: Record<string, unknown>[]
+): Promise<TokenCount> {
+  return invoke<TokenCount>('count_tokens', { model, messages })
+}
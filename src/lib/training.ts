@@ -0,0 +1,49 @@
+/**
+ * Fine-tuning Dataset Export
+ * Converts conversations into OpenAI or Anthropic JSON Lines fine-tuning
+ * files, with PII scrubbing and a train/validation split done in Rust.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+export type TrainingFormat = 'openai' | 'anthropic'
+
+export interface TrainingFilter {
+  /** Only these conversations; all when unset */
+  conversationIds?: string[]
+  /** Only conversations with one of these models; all when unset */
+  modelIds?: string[]
+  /** One example per thumbs-up answer instead of one per conversation */
+  positiveOnly?: boolean
+  /** Only conversations updated since (milliseconds since the UNIX epoch) */
+  updatedSince?: number
+  /** Replace emails, phone numbers, card numbers, IPs and secrets (default true) */
+  scrubPii?: boolean
+  /** Share of examples written to the validation file, 0 to 0.5 */
+  validationFraction?: number
+}
+
+export interface TrainingExportReport {
+  trainPath: string
+  /** Sibling `<name>.validation.jsonl`, null without a validation split */
+  validationPath: string | null
+  trainExamples: number
+  validationExamples: number
+  /** Conversations matched by the filter that produced no usable example */
+  skippedConversations: number
+}
+
+/**
+ * Write matching conversations to `path` as a fine-tuning dataset.
+ */
+export function exportTrainingData(
+  filter: TrainingFilter,
+  format: TrainingFormat,
+  path: string
+): Promise<TrainingExportReport> {
+  return invoke<TrainingExportReport>('export_training_data', {
+    filter,
+    format,
+    path,
+  })
+}
@@ -0,0 +1,62 @@
+/**
+ * Tray
+ * The tray lists the configured stdio MCP servers and starts or stops them
+ * from its menu. MCP sessions live here in the webview, so the backend
+ * asks for that with `tray-server-action` events instead of doing it
+ * itself. Call `startTraySync` once at startup.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { startMCPServer, stopMCPServer } from '@/services/mcp'
+import { useMCPStore } from '@/store/mcp-store'
+import type { MCPServerConfig } from '@/types/mcp'
+import { logger } from './logger'
+
+export interface TrayServer {
+  id: string
+  name: string
+}
+
+interface TrayServerAction {
+  serverId: string
+  start: boolean
+}
+
+export function setTrayServers(servers: TrayServer[]): Promise<void> {
+  return invoke('set_tray_servers', { servers })
+}
+
+function syncServers(servers: MCPServerConfig[]) {
+  // HTTP servers have no backend process to show
+  const listed = servers
+    .filter(server => server.transport === 'stdio')
+    .map(server => ({ id: server.id, name: server.name }))
+  setTrayServers(listed).catch(error => {
+    logger.warn('Failed to update the tray', { error })
+  })
+}
+
+/** Keeps the tray's server list current; returns a function to stop */
+export function startTraySync(): () => void {
+  syncServers(useMCPStore.getState().servers)
+  const unsubscribe = useMCPStore.subscribe((state, previous) => {
+    if (state.servers !== previous.servers) syncServers(state.servers)
+  })
+  const unlisten = listen<TrayServerAction>('tray-server-action', event => {
+    const { serverId, start } = event.payload
+    const server = useMCPStore.getState().getServerById(serverId)
+    const action = start
+      ? server
+        ? startMCPServer(server)
+        : Promise.reject(new Error(`Unknown MCP server ${serverId}`))
+      : stopMCPServer(serverId)
+    action.catch(error => {
+      logger.error('Tray server action failed', { serverId, start, error })
+    })
+  })
+  return () => {
+    unsubscribe()
+    void unlisten.then(stop => stop())
+  }
+}
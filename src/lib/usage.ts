@@ -0,0 +1,39 @@
+/**
+ * Usage Statistics
+ * Messages, tokens and estimated cost aggregated by the backend from the
+ * usage recorded on each stored message, for the usage dashboard.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+
+/** Milliseconds since the UNIX epoch; `to` is exclusive */
+export interface UsageRange {
+  from?: number
+  to?: number
+}
+
+/** Days are UTC dates, `YYYY-MM-DD` */
+export type UsageGroupBy = 'day' | 'model' | 'provider'
+
+export interface UsageBucket {
+  /** The day, model or provider, `unknown` when a message has none */
+  key: string
+  messages: number
+  inputTokens: number
+  outputTokens: number
+  /** Estimated, in US dollars */
+  cost: number
+}
+
+export interface UsageStats {
+  /** Ordered by day, or by tokens for the other groupings */
+  buckets: UsageBucket[]
+  total: UsageBucket
+}
+
+export function getUsageStats(
+  groupBy: UsageGroupBy,
+  range?: UsageRange
+): Promise<UsageStats> {
+  return invoke<UsageStats>('get_usage_stats', { range, groupBy })
+}
@@ -30,6 +30,10 @@ export const stdioServerSchema = baseServerSchema.extend({
     .max(500, 'Command must be less than 500 characters'),
   args: z.string().max(2000, 'Arguments must be less than 2000 characters'),
   env: z.string().max(5000, 'Environment variables must be less than 5000 characters'),
+  startupTimeout: z
+    .string()
+    .regex(/^\d{0,4}$/, 'Startup timeout must be a number of seconds')
+    .optional(),
 })
 
 /**
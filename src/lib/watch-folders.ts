@@ -0,0 +1,91 @@
+/**
+ * Watch Folders
+ * Files dropped into a watched folder are run through a prompt template by
+ * the backend, and the result is appended to a stored conversation or
+ * written next to the file as `<name>.summary.md`.
+ */
+
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type WatchFolderOutput =
+  | { type: 'conversation'; conversationId: string }
+  | { type: 'sidecar' }
+
+export interface WatchFolder {
+  id: string
+  name: string
+  /** Absolute path; subfolders are not watched */
+  path: string
+  /**
+   * Prompt template. `{{filename}}` and `{{content}}` are replaced with the
+   * file's name and text; without `{{content}}` the text is appended.
+   */
+  prompt: string
+  provider: 'google' | 'openai' | 'groq' | 'mistral' | 'ollama'
+  model: string
+  /** Extensions to process, without the dot; all files when empty */
+  extensions: string[]
+  output: WatchFolderOutput
+  enabled: boolean
+}
+
+export interface WatchFolderInfo extends WatchFolder {
+  /** An API key is stored in the keychain */
+  hasApiKey: boolean
+}
+
+export interface ProcessedFile {
+  path: string
+  /** Milliseconds since the UNIX epoch */
+  processedAt: number
+  /** null for files that were in the folder when it was added */
+  succeeded: boolean | null
+  /** Sidecar path or conversation id the result went to */
+  output: string | null
+  error: string | null
+}
+
+export interface WatchFolderEvent {
+  folderId: string
+  path: string
+  output: string | null
+  error: string | null
+}
+
+export function listWatchFolders(): Promise<WatchFolderInfo[]> {
+  return invoke<WatchFolderInfo[]>('list_watch_folders')
+}
+
+/**
+ * Add or replace a watch folder. Files already in the folder are not
+ * processed. A non-empty `apiKey` is stored in the keychain, an empty one
+ * removes it, and leaving it out keeps the stored key.
+ */
+export function saveWatchFolder(
+  folder: WatchFolder,
+  apiKey?: string
+): Promise<void> {
+  return invoke('save_watch_folder', { folder, apiKey })
+}
+
+export function removeWatchFolder(id: string): Promise<void> {
+  return invoke('remove_watch_folder', { id })
+}
+
+/** Files a folder has seen, most recently processed first */
+export function listWatchFolderFiles(
+  folderId: string,
+  limit?: number
+): Promise<ProcessedFile[]> {
+  return invoke<ProcessedFile[]>('list_watch_folder_files', { folderId, limit })
+}
+
+/** Called after each file a watch folder processes */
+export function onWatchFolderProcessed(
+  callback: (event: WatchFolderEvent) => void
+): Promise<UnlistenFn> {
+  return listen<WatchFolderEvent>('watch-folder-processed', event =>
+    callback(event.payload)
+  )
+}
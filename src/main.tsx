@@ -3,6 +3,9 @@ import { QueryClientProvider } from '@tanstack/react-query'
 import { ReactQueryDevtools } from '@tanstack/react-query-devtools'
 import App from './App'
 import { queryClient } from './lib/query-client'
+import { installCommandMetrics } from './lib/command-metrics'
+
+installCommandMetrics()
 
 ReactDOM.createRoot(document.getElementById('root') as HTMLElement).render(
   <QueryClientProvider client={queryClient}>
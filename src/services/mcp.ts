@@ -22,12 +22,43 @@ import {
   type MCPToolCallResult,
   type JsonRpcRequest,
   type JsonRpcResponse,
+  type MCPRegistryEntry,
+  type MCPRegistryListing,
+  type MCPConformanceReport,
+  type MCPServerStats,
+  type MCPTrafficEntry,
+  type MCPFaultConfig,
+  type MCPServerFaults,
+  type MCPStartupProgress,
+  type MCPElevationWarning,
+  type MCPHotReloadEvent,
+  type MCPNetworkActivity,
 } from '@/types/mcp'
 
 // =============================================================================
 // Shared Types and State
 // =============================================================================
 
+/** Error returned by the spawn_mcp_server command */
+export interface MCPSpawnError {
+  kind:
+    | 'capacity_reached'
+    | 'cancelled'
+    | 'already_running'
+    | 'elevation_required'
+    | 'spawn_failed'
+  message: string
+}
+
+export function isSpawnError(error: unknown): error is MCPSpawnError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    'kind' in error &&
+    'message' in error
+  )
+}
+
 interface MCPServerStateInternal {
   transport: 'stdio' | 'http'
   requestId: number
@@ -45,6 +76,8 @@ interface StdioServerState extends MCPServerStateInternal {
   buffer: string
   unlistenStdout?: UnlistenFn
   unlistenStderr?: UnlistenFn
+  unlistenStartup?: UnlistenFn
+  unlistenHotReload?: UnlistenFn
 }
 
 interface HttpServerState extends MCPServerStateInternal {
@@ -106,10 +139,90 @@ export async function stopMCPServer(serverId: string): Promise<void> {
   }
 }
 
+/**
+ * Apply an edited config to a running server. Stdio servers are restarted
+ * in place by the backend, keeping their id, and only when the command,
+ * args or env changed; HTTP servers reconnect. Failures end up in the
+ * server's state rather than being thrown.
+ */
+export async function applyMCPServerConfig(
+  config: MCPServerConfig
+): Promise<void> {
+  const server = activeServers.get(config.id)
+  if (!server) return
+
+  const { setServerState } = useMCPStore.getState()
+  try {
+    if (server.transport === 'http' || !isStdioConfig(config)) {
+      await stopMCPServer(config.id)
+      await startMCPServer(config)
+      return
+    }
+
+    const result = await invoke<{ restarted: boolean; pid: number | null }>(
+      'update_and_restart_mcp_server',
+      {
+        config: {
+          id: config.id,
+          command: config.command,
+          args: config.args,
+          env: config.env,
+          docker: config.docker,
+          isolateEnv: config.isolateEnv,
+          startupTimeoutMs: config.startupTimeoutMs,
+          watchPaths: config.watchPaths,
+          logLevel: config.logLevel,
+          stderrSuppress: config.stderrSuppress,
+          allowElevated: config.allowElevated,
+          mockFixture: config.mockFixture,
+        },
+      }
+    )
+    if (!result.restarted) return
+
+    logger.info(`MCP server ${config.id} restarted with PID: ${result.pid}`)
+    await reconnectStdioServer(config, server)
+  } catch (error) {
+    const errorMessage =
+      error instanceof Error
+        ? error.message
+        : isSpawnError(error)
+          ? error.message
+          : String(error)
+    logger.error(
+      `Failed to apply config to MCP server ${config.id}: ${errorMessage}`
+    )
+    setServerState(config.id, { status: 'error', error: errorMessage })
+  }
+}
+
 // =============================================================================
 // Stdio Transport Implementation
 // =============================================================================
 
+/**
+ * Initializes a stdio server again after the backend replaced its process
+ * under the same id, keeping the existing event listeners.
+ */
+async function reconnectStdioServer(
+  config: MCPServerConfigStdio,
+  server: StdioServerState
+): Promise<void> {
+  const { setServerState } = useMCPStore.getState()
+  setServerState(config.id, { status: 'connecting', error: undefined })
+
+  // Requests to the old process will never be answered
+  for (const pending of server.pendingRequests.values()) {
+    pending.reject(new Error(`MCP server ${config.id} was restarted`))
+  }
+  server.pendingRequests.clear()
+  server.buffer = ''
+
+  await initializeStdioServer(config.id, startupTimeout(config))
+  const tools = await listToolsStdio(config.id)
+  setServerState(config.id, { status: 'connected', tools, startup: undefined })
+}
+
 async function startStdioServer(config: MCPServerConfigStdio): Promise<void> {
   const { setServerState } = useMCPStore.getState()
 
@@ -166,28 +279,95 @@ async function startStdioServer(config: MCPServerConfigStdio): Promise<void> {
       }
     )
 
+    // Startup progress, until the server answers or is stopped for timing out
+    serverState.unlistenStartup = await listen<MCPStartupProgress>(
+      'mcp-startup-progress',
+      (event) => {
+        if (event.payload.serverId !== config.id) return
+        const progress = event.payload
+        setServerState(config.id, { startup: progress })
+
+        if (progress.phase === 'timed_out') {
+          const reason = progress.lastStderr
+            ? `last output: ${progress.lastStderr}`
+            : 'no output'
+          const error = new Error(
+            `MCP server ${config.id} did not start within ${progress.timeoutMs / 1000}s (${reason})`
+          )
+          for (const pending of serverState.pendingRequests.values()) {
+            pending.reject(error)
+          }
+          serverState.pendingRequests.clear()
+        }
+      }
+    )
+
+    // The backend restarts servers with watchPaths when those files change
+    serverState.unlistenHotReload = await listen<MCPHotReloadEvent>(
+      'mcp-hot-reloaded',
+      (event) => {
+        if (event.payload.serverId !== config.id) return
+        const { pid, error, changed } = event.payload
+        if (error) {
+          logger.error(`Hot reload of MCP server ${config.id} failed: ${error}`)
+          setServerState(config.id, { status: 'error', error })
+          return
+        }
+        logger.info(
+          `MCP server ${config.id} reloaded with PID ${pid} after changes to ${changed.join(', ')}`
+        )
+        reconnectStdioServer(config, serverState).catch((e) => {
+          const message = e instanceof Error ? e.message : String(e)
+          setServerState(config.id, { status: 'error', error: message })
+        })
+      }
+    )
+
     activeServers.set(config.id, serverState)
 
-    // Spawn via Rust
+    // Spawn via Rust; with mockFixture set a built-in mock answers from
+    // the fixture instead, for UI development and tests
     const pid = await invoke<number>('spawn_mcp_server', {
       config: {
         id: config.id,
         command: config.command,
         args: config.args,
         env: config.env,
+        docker: config.docker,
+        isolateEnv: config.isolateEnv,
+        startupTimeoutMs: config.startupTimeoutMs,
+        watchPaths: config.watchPaths,
+        logLevel: config.logLevel,
+        stderrSuppress: config.stderrSuppress,
+        allowElevated: config.allowElevated,
+        mockFixture: config.mockFixture,
       },
     })
 
-    logger.info(`MCP server ${config.id} spawned with PID: ${pid}`)
+    logger.info(
+      config.mockFixture
+        ? `Mock MCP server ${config.id} started`
+        : `MCP server ${config.id} spawned with PID: ${pid}`
+    )
 
     // Initialize
-    await initializeStdioServer(config.id)
+    await initializeStdioServer(config.id, startupTimeout(config))
     const tools = await listToolsStdio(config.id)
-    setServerState(config.id, { status: 'connected', tools })
+    setServerState(config.id, {
+      status: 'connected',
+      tools,
+      startup: undefined,
+    })
 
     logger.info(`MCP server ${config.id} connected with ${tools.length} tools`)
   } catch (error) {
-    const errorMessage = error instanceof Error ? error.message : String(error)
+    // spawn_mcp_server rejects with a typed { kind, message } error
+    const errorMessage =
+      error instanceof Error
+        ? error.message
+        : isSpawnError(error)
+          ? error.message
+          : String(error)
     logger.error(`Failed to start MCP server ${config.id}: ${errorMessage}`)
     setServerState(config.id, { status: 'error', error: errorMessage })
 
@@ -195,6 +375,8 @@ async function startStdioServer(config: MCPServerConfigStdio): Promise<void> {
     if (server) {
       server.unlistenStdout?.()
       server.unlistenStderr?.()
+      server.unlistenStartup?.()
+      server.unlistenHotReload?.()
       activeServers.delete(config.id)
     }
 
@@ -213,6 +395,8 @@ async function stopStdioServer(
   try {
     server.unlistenStdout?.()
     server.unlistenStderr?.()
+    server.unlistenStartup?.()
+    server.unlistenHotReload?.()
     await invoke('kill_mcp_server', { serverId })
     activeServers.delete(serverId)
     clearServerState(serverId)
@@ -227,7 +411,8 @@ async function stopStdioServer(
 async function sendStdioRequest(
   serverId: string,
   method: string,
-  params?: Record<string, unknown>
+  params?: Record<string, unknown>,
+  timeoutMs: number = MCP_TIMEOUTS.REQUEST_TIMEOUT
 ): Promise<unknown> {
   const server = activeServers.get(serverId) as StdioServerState | undefined
   if (!server || server.transport !== 'stdio') {
@@ -250,7 +435,7 @@ async function sendStdioRequest(
     const timeout = setTimeout(() => {
       server.pendingRequests.delete(id)
       reject(new Error(`Request timeout for ${method}`))
-    }, MCP_TIMEOUTS.REQUEST_TIMEOUT)
+    }, timeoutMs)
 
     const originalResolve = resolve
     server.pendingRequests.set(id, {
@@ -272,12 +457,29 @@ async function sendStdioRequest(
   })
 }
 
-async function initializeStdioServer(serverId: string): Promise<void> {
-  await sendStdioRequest(serverId, 'initialize', {
-    protocolVersion: MCP_PROTOCOL_VERSION,
-    capabilities: { tools: {} },
-    clientInfo: MCP_CLIENT_INFO,
-  })
+/**
+ * Time a server gets to answer `initialize`. The backend stops it when this
+ * runs out; the extra margin lets its `timed_out` progress event, which says
+ * why, arrive before the request times out here.
+ */
+function startupTimeout(config: MCPServerConfigStdio): number {
+  return (config.startupTimeoutMs ?? MCP_TIMEOUTS.STARTUP_TIMEOUT) + 5000
+}
+
+async function initializeStdioServer(
+  serverId: string,
+  timeoutMs?: number
+): Promise<void> {
+  await sendStdioRequest(
+    serverId,
+    'initialize',
+    {
+      protocolVersion: MCP_PROTOCOL_VERSION,
+      capabilities: { tools: {} },
+      clientInfo: MCP_CLIENT_INFO,
+    },
+    timeoutMs
+  )
 
   const notification =
     JSON.stringify({
@@ -299,6 +501,70 @@ async function listToolsStdio(serverId: string): Promise<MCPTool[]> {
 // HTTP/SSE Transport Implementation
 // =============================================================================
 
+type NetworkExchangeKind = 'request' | 'sse_connect' | 'sse_event'
+
+const byteLength = (text: string) => new TextEncoder().encode(text).length
+
+/** Report an exchange with a remote server to the backend audit log */
+function recordNetworkActivity(
+  serverId: string,
+  url: string,
+  kind: NetworkExchangeKind,
+  bytesSent: number,
+  bytesReceived: number,
+  status: number | null
+): void {
+  invoke('record_mcp_network_activity', {
+    serverId,
+    url,
+    kind,
+    bytesSent,
+    bytesReceived,
+    status,
+  }).catch(error =>
+    logger.warn(`Failed to record network activity for ${serverId}`, {
+      error,
+    })
+  )
+}
+
+/** POST a JSON-RPC message, recording the exchange */
+async function postToServer(
+  serverId: string,
+  server: HttpServerState,
+  message: unknown
+): Promise<{ response: Response; text: string }> {
+  const body = JSON.stringify(message)
+  let response: Response
+  try {
+    response = await fetch(server.url, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json', ...server.headers },
+      body,
+    })
+  } catch (error) {
+    recordNetworkActivity(
+      serverId,
+      server.url,
+      'request',
+      byteLength(body),
+      0,
+      null
+    )
+    throw error
+  }
+  const text = await response.text().catch(() => '')
+  recordNetworkActivity(
+    serverId,
+    server.url,
+    'request',
+    byteLength(body),
+    byteLength(text),
+    response.status
+  )
+  return { response, text }
+}
+
 async function startHttpServer(config: MCPServerConfigHttp): Promise<void> {
   const { setServerState } = useMCPStore.getState()
 
@@ -368,6 +634,7 @@ async function connectHttpSSE(
 
     eventSource.onopen = () => {
       logger.info(`SSE connected for ${serverId}`)
+      recordNetworkActivity(serverId, server.url, 'sse_connect', 0, 0, null)
       resolve()
     }
 
@@ -377,6 +644,14 @@ async function connectHttpSSE(
     }
 
     eventSource.onmessage = (event) => {
+      recordNetworkActivity(
+        serverId,
+        server.url,
+        'sse_event',
+        0,
+        byteLength(event.data),
+        null
+      )
       try {
         const data = JSON.parse(event.data)
 
@@ -464,23 +739,18 @@ async function sendHttpRequest(
     })
 
     // Send request via HTTP POST
-    const headers: Record<string, string> = {
-      'Content-Type': 'application/json',
-      ...server.headers,
-    }
-
-    fetch(server.url, {
-      method: 'POST',
-      headers,
-      body: JSON.stringify(request),
-    })
-      .then((response) => {
+    postToServer(serverId, server, request)
+      .then(({ response, text }) => {
         if (!response.ok) {
           throw new Error(`HTTP ${response.status}: ${response.statusText}`)
         }
         // Response comes via SSE, not from this POST
         // Some servers return the response directly
-        return response.json().catch(() => null)
+        try {
+          return JSON.parse(text)
+        } catch {
+          return null
+        }
       })
       .then((data) => {
         // If we got a direct response, handle it
@@ -505,18 +775,9 @@ async function initializeHttpServer(serverId: string): Promise<void> {
   // Send initialized notification
   const server = activeServers.get(serverId) as HttpServerState
   if (server) {
-    const headers: Record<string, string> = {
-      'Content-Type': 'application/json',
-      ...server.headers,
-    }
-
-    await fetch(server.url, {
-      method: 'POST',
-      headers,
-      body: JSON.stringify({
-        jsonrpc: '2.0',
-        method: 'notifications/initialized',
-      }),
+    await postToServer(serverId, server, {
+      jsonrpc: '2.0',
+      method: 'notifications/initialized',
     })
   }
 }
@@ -647,3 +908,188 @@ export function findToolServer(toolName: string): string | undefined {
 
   return undefined
 }
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/**
+ * Fetch the MCP server registry (cached by the backend for a day)
+ */
+export async function fetchMCPRegistry(
+  options: { includeNpm?: boolean; forceRefresh?: boolean } = {}
+): Promise<MCPRegistryListing> {
+  return invoke<MCPRegistryListing>('fetch_mcp_registry', {
+    includeNpm: options.includeNpm ?? false,
+    forceRefresh: options.forceRefresh ?? false,
+  })
+}
+
+/**
+ * Build a server config from a registry entry. Required environment
+ * variables are added with empty values for the user to fill in.
+ */
+export function registryEntryToConfig(
+  entry: MCPRegistryEntry
+): MCPServerConfig {
+  const id = entry.name.replace(/[^a-zA-Z0-9_-]/g, '-').toLowerCase()
+  const name = entry.name.split('/').pop() ?? entry.name
+
+  if (entry.url && !entry.command && !entry.docker) {
+    return { id, name, enabled: false, transport: 'http', url: entry.url }
+  }
+
+  const env = Object.fromEntries(
+    entry.envVars.filter(v => v.required).map(v => [v.name, ''])
+  )
+  return {
+    id,
+    name,
+    enabled: false,
+    transport: 'stdio',
+    command: entry.command ?? '',
+    args: entry.args,
+    env,
+    docker: entry.docker ?? undefined,
+  }
+}
+
+/**
+ * Install an MCP server package into the app's package directory and add
+ * it to the server list. Progress is emitted as `mcp-install-progress`.
+ */
+export async function installMCPServer(
+  pkg: string,
+  runtime: 'npm' | 'pip' | 'uv',
+  binary?: string
+): Promise<MCPServerConfigStdio> {
+  const result = await invoke<{
+    name: string
+    binaryPath: string
+    config: { id: string; command: string; args: string[] }
+  }>('install_mcp_server', { package: pkg, runtime, binary })
+
+  const server: MCPServerConfigStdio = {
+    id: result.config.id,
+    name: result.name.split('/').pop() ?? result.name,
+    enabled: false,
+    transport: 'stdio',
+    command: result.config.command,
+    args: result.config.args,
+  }
+  useMCPStore.getState().addServer(server)
+  logger.info(`Installed MCP server ${result.name}`, {
+    binary: result.binaryPath,
+  })
+  return server
+}
+
+/**
+ * Run the protocol conformance suite against a fresh, headless instance of
+ * a running stdio server. Only tools annotated as read-only or
+ * non-destructive are called.
+ */
+export async function runMCPConformance(
+  serverId: string
+): Promise<MCPConformanceReport> {
+  const report = await invoke<MCPConformanceReport>('run_mcp_conformance', {
+    serverId,
+  })
+  logger.info(
+    `Conformance suite for ${serverId} ${report.passed ? 'passed' : 'failed'}`,
+    { checks: report.checks.length }
+  )
+  return report
+}
+
+/** Current CPU, memory and file descriptor usage of a running server */
+export async function getMCPServerStats(
+  serverId: string
+): Promise<MCPServerStats> {
+  return invoke<MCPServerStats>('get_mcp_server_stats', { serverId })
+}
+
+/** Subscribe to the periodic `mcp-stats` samples of all running servers */
+export function onMCPStats(
+  callback: (stats: MCPServerStats[]) => void
+): Promise<UnlistenFn> {
+  return listen<MCPServerStats[]>('mcp-stats', event => callback(event.payload))
+}
+
+/** Servers found to need elevated privileges, whether or not they were allowed to start */
+export function onMCPElevationWarning(
+  callback: (warning: MCPElevationWarning) => void
+): Promise<UnlistenFn> {
+  return listen<MCPElevationWarning>('mcp-elevation-warning', event =>
+    callback(event.payload)
+  )
+}
+
+/** Turn JSON-RPC traffic recording for a server on or off */
+export async function setMCPTrafficRecording(
+  serverId: string,
+  enabled: boolean,
+  clear = false
+): Promise<void> {
+  await invoke('set_mcp_traffic_recording', { serverId, enabled, clear })
+}
+
+/** Captured messages newer than the `since` sequence number, oldest first */
+export async function getMCPTraffic(
+  serverId: string,
+  since?: number
+): Promise<MCPTrafficEntry[]> {
+  return invoke<MCPTrafficEntry[]>('get_mcp_traffic', { serverId, since })
+}
+
+/** Save a server's captured messages as JSON Lines */
+export async function exportMCPTraffic(
+  serverId: string,
+  path: string
+): Promise<number> {
+  return invoke<number>('export_mcp_traffic', { serverId, path })
+}
+
+/**
+ * Delay, drop or corrupt a share of a stdio server's messages, or stop with
+ * `config` null. Only debug builds and builds with the `fault-injection`
+ * feature accept faults.
+ */
+export async function setMCPFaults(
+  serverId: string,
+  config: MCPFaultConfig | null
+): Promise<void> {
+  await invoke('set_mcp_faults', { serverId, config })
+}
+
+/** Servers with faults set, with the faults injected so far */
+export async function getMCPFaults(): Promise<MCPServerFaults[]> {
+  return invoke<MCPServerFaults[]>('get_mcp_faults')
+}
+
+/**
+ * Hosts a remote server's traffic went to, with request counts and bytes,
+ * from the audit log
+ */
+export async function getNetworkActivity(
+  serverId: string
+): Promise<MCPNetworkActivity> {
+  return invoke<MCPNetworkActivity>('get_network_activity', { serverId })
+}
+
+/**
+ * Save every configured server as a Claude Desktop style `mcpServers` file.
+ * Env values and HTTP headers are blanked unless `includeEnv` is set; mock
+ * servers are left out since their fixtures don't travel.
+ *
+ * @returns The number of servers written
+ */
+export async function exportMCPConfigs(
+  path: string,
+  includeEnv: boolean
+): Promise<number> {
+  const servers = useMCPStore
+    .getState()
+    .servers.filter(s => !(s.transport === 'stdio' && s.mockFixture))
+  return invoke<number>('export_mcp_configs', { servers, path, includeEnv })
+}
@@ -2,7 +2,7 @@ import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
 import { invoke } from '@tauri-apps/api/core'
 import { toast } from 'sonner'
 import { logger } from '@/lib/logger'
-import type { AppPreferences } from '@/types/preferences'
+import { defaultPreferences, type AppPreferences } from '@/types/preferences'
 
 // Query keys for preferences
 export const preferencesQueryKeys = {
@@ -23,7 +23,7 @@ export function usePreferences() {
       } catch (error) {
         // Return defaults if preferences file doesn't exist yet
         logger.warn('Failed to load preferences, using defaults', { error })
-        return { theme: 'system' }
+        return defaultPreferences
       }
     },
     staleTime: 1000 * 60 * 5, // 5 minutes
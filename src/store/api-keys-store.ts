@@ -2,15 +2,29 @@
  * API Keys Store
  * Manages API keys for LLM providers
  *
- * NOTE: For production, consider using Tauri's secure storage plugin:
- * @tauri-apps/plugin-store for encrypted key storage
+ * Every key set here is also stored in the OS keychain by the backend,
+ * which is where backend features (benchmarks, task extraction, model
+ * listing) read it from. Keys stored before that are copied over when the
+ * store loads.
  *
- * Current implementation stores keys in localStorage which is acceptable
- * for development but should be encrypted for production use.
+ * Chat requests made from the webview still read keys from this store,
+ * which persists them in localStorage.
  */
 
 import { create } from 'zustand'
 import { devtools, persist } from 'zustand/middleware'
+import { deleteApiKey, setApiKey } from '@/lib/api-keys'
+import { logger } from '@/lib/logger'
+import { profileStorageKey } from '@/lib/profiles'
+import type { BackendProvider } from '@/lib/provider-models'
+
+/** Copy a key to the keychain, or remove it there when it is empty */
+function syncKeychain(provider: BackendProvider, key: string) {
+  const update = key ? setApiKey(provider, key) : deleteApiKey(provider)
+  update.catch((error: unknown) => {
+    logger.error(`Failed to store ${provider} API key: ${String(error)}`)
+  })
+}
 
 // ============================================
 // Types
@@ -44,14 +58,17 @@ export const useApiKeysStore = create<ApiKeysState>()(
 
         setGoogleApiKey: key => {
           set({ googleApiKey: key }, undefined, 'setGoogleApiKey')
+          syncKeychain('google', key)
         },
 
         setGroqApiKey: key => {
           set({ groqApiKey: key }, undefined, 'setGroqApiKey')
+          syncKeychain('groq', key)
         },
 
         setOpenaiApiKey: key => {
           set({ openaiApiKey: key }, undefined, 'setOpenaiApiKey')
+          syncKeychain('openai', key)
         },
 
         clearAllKeys: () => {
@@ -60,6 +77,9 @@ export const useApiKeysStore = create<ApiKeysState>()(
             undefined,
             'clearAllKeys'
           )
+          syncKeychain('google', '')
+          syncKeychain('groq', '')
+          syncKeychain('openai', '')
         },
 
         hasAnyKey: () => {
@@ -72,9 +92,12 @@ export const useApiKeysStore = create<ApiKeysState>()(
         },
       }),
       {
-        name: 'api-keys-store',
-        // TODO: For production, implement custom storage with encryption
-        // storage: createSecureStorage(),
+        name: profileStorageKey('api-keys-store'),
+        onRehydrateStorage: () => state => {
+          if (state?.googleApiKey) syncKeychain('google', state.googleApiKey)
+          if (state?.groqApiKey) syncKeychain('groq', state.groqApiKey)
+          if (state?.openaiApiKey) syncKeychain('openai', state.openaiApiKey)
+        },
       }
     ),
     { name: 'api-keys-store' }
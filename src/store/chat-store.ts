@@ -16,6 +16,7 @@ import {
   createTextContent,
   DEFAULT_MODEL_ID,
 } from '@/types/multimodal'
+import { profileStorageKey } from '@/lib/profiles'
 
 // Re-export Provider type for backwards compatibility
 export type { Provider } from '@/types/multimodal'
@@ -66,6 +67,18 @@ export interface ChatMessage {
   toolCallId?: string
   /** For tool role messages, the name of the tool that was called */
   toolName?: string
+  /** Tokens the response took, on assistant messages */
+  usage?: MessageUsage
+}
+
+export interface MessageUsage {
+  provider?: string
+  /** The model that answered, which may differ from the conversation's */
+  model?: string
+  inputTokens: number
+  outputTokens: number
+  /** In US dollars, when the provider reports it */
+  cost?: number
 }
 
 export interface Conversation {
@@ -480,7 +493,7 @@ export const useChatStore = create<ChatState>()(
         },
       }),
       {
-        name: 'chat-store',
+        name: profileStorageKey('chat-store'),
         partialize: state => ({
           conversations: state.conversations,
           selectedModelId: state.selectedModelId,
@@ -12,6 +12,7 @@ import type {
   MCPTool,
 } from '@/types/mcp'
 import { DEFAULT_MCP_SERVERS } from '@/constants/mcp-defaults'
+import { profileStorageKey } from '@/lib/profiles'
 
 // ============================================
 // Store Types
@@ -201,7 +202,7 @@ export const useMCPStore = create<MCPState>()(
         },
       }),
       {
-        name: 'mcp-store',
+        name: profileStorageKey('mcp-store'),
         partialize: (state) => ({
           servers: state.servers,
         }),
@@ -6,6 +6,8 @@ interface UIState {
   rightSidebarVisible: boolean
   commandPaletteOpen: boolean
   preferencesOpen: boolean
+  /** Text for the message input to take over, e.g. from a deep link */
+  draftPrompt: string | null
 
   toggleLeftSidebar: () => void
   setLeftSidebarVisible: (visible: boolean) => void
@@ -15,6 +17,7 @@ interface UIState {
   setCommandPaletteOpen: (open: boolean) => void
   togglePreferences: () => void
   setPreferencesOpen: (open: boolean) => void
+  setDraftPrompt: (prompt: string | null) => void
 }
 
 export const useUIStore = create<UIState>()(
@@ -24,6 +27,7 @@ export const useUIStore = create<UIState>()(
       rightSidebarVisible: false,
       commandPaletteOpen: false,
       preferencesOpen: false,
+      draftPrompt: null,
 
       toggleLeftSidebar: () =>
         set(
@@ -72,6 +76,9 @@ export const useUIStore = create<UIState>()(
 
       setPreferencesOpen: open =>
         set({ preferencesOpen: open }, undefined, 'setPreferencesOpen'),
+
+      setDraftPrompt: prompt =>
+        set({ draftPrompt: prompt }, undefined, 'setDraftPrompt'),
     }),
     {
       name: 'ui-store',
@@ -78,12 +78,58 @@ export interface MCPServerConfigBase {
   transport: MCPTransportType
 }
 
+/** Container settings for servers run through `docker run -i` */
+export interface MCPDockerConfig {
+  image: string
+  /** Volume mounts in `docker run -v` syntax */
+  volumes?: string[]
+  env?: Record<string, string>
+}
+
+/** Severity levels read from server stderr output */
+export type MCPLogLevel = 'trace' | 'debug' | 'info' | 'warn' | 'error'
+
 /** Stdio transport configuration */
 export interface MCPServerConfigStdio extends MCPServerConfigBase {
   transport: 'stdio'
   command: string
   args: string[]
   env?: Record<string, string>
+  /** Run the server in a container instead of as a local process */
+  docker?: MCPDockerConfig
+  /**
+   * Start with a clean environment (PATH, HOME and `env` only) instead of
+   * inheriting the app's, so host API keys don't leak into the server
+   */
+  isolateEnv?: boolean
+  /**
+   * Path of a fixture file; when set, a built-in mock server answers from it
+   * instead of running `command`
+   */
+  mockFixture?: string
+  /**
+   * How long the server may take to answer `initialize` before it is
+   * stopped; defaults to 60 seconds. While it is visibly installing
+   * dependencies the time counts from its last install output.
+   */
+  startupTimeoutMs?: number
+  /**
+   * Absolute paths of files or directories; the server is restarted when
+   * any of them changes, for developing servers locally
+   */
+  watchPaths?: string[]
+  /**
+   * Least severe stderr level forwarded to the app; everything when unset.
+   * The server's log file always gets every line.
+   */
+  logLevel?: MCPLogLevel
+  /** Regular expressions of stderr lines never forwarded to the app */
+  stderrSuppress?: string[]
+  /**
+   * Run the server even though it needs elevated privileges (sudo, a
+   * setuid binary, a privileged port or the Docker socket)
+   */
+  allowElevated?: boolean
 }
 
 /** HTTP/SSE transport configuration */
@@ -96,6 +142,40 @@ export interface MCPServerConfigHttp extends MCPServerConfigBase {
 /** Union type for all server configs */
 export type MCPServerConfig = MCPServerConfigStdio | MCPServerConfigHttp
 
+// ============================================
+// MCP Registry Types
+// ============================================
+
+export interface MCPRegistryEnvVar {
+  name: string
+  description: string | null
+  required: boolean
+  secret: boolean
+}
+
+/** A server listed in the MCP registry or found on npm */
+export interface MCPRegistryEntry {
+  name: string
+  description: string
+  version: string | null
+  source: 'registry' | 'npm'
+  command: string | null
+  args: string[]
+  url: string | null
+  docker: MCPDockerConfig | null
+  envVars: MCPRegistryEnvVar[]
+  repository: string | null
+}
+
+export interface MCPRegistryListing {
+  /** Milliseconds since the UNIX epoch */
+  fetchedAt: number
+  /** Served from cache because the registry could not be reached */
+  stale: boolean
+  includeNpm: boolean
+  entries: MCPRegistryEntry[]
+}
+
 // ============================================
 // Type Guards
 // ============================================
@@ -121,6 +201,142 @@ export interface MCPServerState {
   status: 'disconnected' | 'connecting' | 'connected' | 'error'
   tools: MCPTool[]
   error?: string
+  /** Latest startup progress while connecting */
+  startup?: MCPStartupProgress
+}
+
+/** Emitted as `mcp-hot-reloaded` after a watched path changed */
+export interface MCPHotReloadEvent {
+  serverId: string
+  /** Watched paths that changed */
+  changed: string[]
+  /** Pid of the new process, null if the restart failed */
+  pid: number | null
+  error: string | null
+}
+
+/** Emitted as `mcp-elevation-warning` when a server needs elevated privileges */
+export interface MCPElevationWarning {
+  serverId: string
+  reasons: {
+    kind: 'wrapper' | 'setuid' | 'privileged_port' | 'docker_socket'
+    detail: string
+  }[]
+  /** The config has `allowElevated` set and the server was started anyway */
+  allowed: boolean
+}
+
+/** Startup progress of a spawned server, from `mcp-startup-progress` */
+export interface MCPStartupProgress {
+  serverId: string
+  /**
+   * `installing`: stderr shows package or image downloads.
+   * `stalled`: no output for a while, the server may be hung.
+   * `timed_out`: not ready in time; the server was stopped.
+   */
+  phase: 'starting' | 'installing' | 'stalled' | 'ready' | 'timed_out'
+  elapsedMs: number
+  timeoutMs: number
+  lastStderr: string | null
+}
+
+// ============================================
+// Resource Usage Types
+// ============================================
+
+/** Resource usage of a server's process tree, from `mcp-stats` */
+export interface MCPServerStats {
+  serverId: string
+  pid: number
+  processCount: number
+  rssBytes: number
+  /** Usage since the previous sample; 100 is one full core */
+  cpuPercent: number
+  /** null where the platform doesn't report descriptors */
+  openFiles: number | null
+  uptimeMs: number
+}
+
+// ============================================
+// Traffic Inspector Types
+// ============================================
+
+/** A JSON-RPC message captured while traffic recording is on */
+export interface MCPTrafficEntry {
+  /** Increases by one per message; pass the last one seen as `since` */
+  seq: number
+  timestampMs: number
+  direction: 'outbound' | 'inbound'
+  data: string
+}
+
+// ============================================
+// Fault Injection Types
+// ============================================
+
+/** Faults injected into a stdio server's messages, for resilience tests */
+export interface MCPFaultConfig {
+  direction: 'outbound' | 'inbound' | 'both'
+  /** Shares of messages, 0 to 100 */
+  delayPercent: number
+  delayMs: number
+  dropPercent: number
+  /** Corrupted messages are cut off, so they are no longer valid JSON */
+  corruptPercent: number
+}
+
+export interface MCPServerFaults {
+  serverId: string
+  config: MCPFaultConfig
+  /** Faults injected since the config was set */
+  injected: { delayed: number; dropped: number; corrupted: number }
+}
+
+// ============================================
+// Network Activity Types
+// ============================================
+
+/** Recorded traffic of a remote server to one host */
+export interface MCPHostActivity {
+  /** Host and, if not the default, port */
+  host: string
+  /** Requests and SSE connections */
+  requests: number
+  bytesSent: number
+  bytesReceived: number
+  firstSeenMs: number
+  lastSeenMs: number
+}
+
+/** Result of get_network_activity */
+export interface MCPNetworkActivity {
+  serverId: string
+  /** Most recently contacted first */
+  hosts: MCPHostActivity[]
+  bytesSent: number
+  bytesReceived: number
+}
+
+// ============================================
+// Conformance Types
+// ============================================
+
+export interface MCPConformanceCheck {
+  name: string
+  status: 'passed' | 'failed' | 'skipped'
+  /** Why the check failed or was skipped */
+  detail: string | null
+  durationMs: number
+}
+
+/** Result of run_mcp_conformance */
+export interface MCPConformanceReport {
+  serverId: string
+  /** True when no check failed */
+  passed: boolean
+  serverInfo: { name: string; version?: string } | null
+  checks: MCPConformanceCheck[]
+  durationMs: number
 }
 
 // ============================================
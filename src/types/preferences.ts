@@ -1,7 +1,177 @@
+export interface ScheduledExportSettings {
+  enabled: boolean
+  /** Absolute path of the folder exports are written to */
+  folder: string | null
+}
+
+export interface MCPConcurrencySettings {
+  /** Maximum number of MCP servers running at once; null means unlimited */
+  maxRunning: number | null
+  /** Queue spawns beyond the cap instead of rejecting them */
+  queueWhenFull: boolean
+}
+
+export interface DatabaseBackupSettings {
+  enabled: boolean
+  /** Hours between scheduled backups, 1 to 720 */
+  intervalHours: number
+  /** Scheduled backups kept; older ones are deleted */
+  keep: number
+}
+
+/**
+ * `record` saves provider responses to backend model requests; `replay`
+ * answers those requests from the recordings only
+ */
+export type ReplayMode = 'off' | 'record' | 'replay'
+
+/** Retrying model requests that fail with a transient error */
+export interface RetryPolicy {
+  /** Attempts in total including the first, 1 to 10; 1 disables retries */
+  maxAttempts: number
+  /** Delay before the first retry, doubled for each one after */
+  initialDelayMs: number
+  /** Cap on the backoff delay, at most 300000 */
+  maxDelayMs: number
+}
+
+export interface RateLimit {
+  requestsPerMinute: number
+  /** Requests that may go out at once after a quiet spell */
+  burst: number
+}
+
+export interface RateLimitSettings {
+  /** For remote hosts not in `hosts`; null leaves them unlimited */
+  default: RateLimit | null
+  /** By API host, e.g. `api.anthropic.com` or `localhost:1234` */
+  hosts: Record<string, RateLimit>
+}
+
+export interface ResponseCacheSettings {
+  enabled: boolean
+  ttlSecs: number
+  maxEntries: number
+  /** Upper bound on the stored responses, in megabytes */
+  maxMb: number
+}
+
+export interface ClipboardHistorySettings {
+  enabled: boolean
+  maxEntries: number
+  /** Names of apps whose copies are never captured, e.g. `1Password` */
+  excludedApps: string[]
+}
+
+export interface WindowMode {
+  /** Stays above other windows */
+  pinned: boolean
+  /** Shrunk to a small chat window */
+  compact: boolean
+}
+
+export interface PowerSettings {
+  /** Heavy background work waits while the machine runs on battery */
+  pauseOnBattery: boolean
+}
+
+export type ProxyMode = 'system' | 'manual' | 'none'
+
+export interface NetworkSettings {
+  proxy: ProxyMode
+  /** Proxy URL for `manual`, optionally with `user:password@` */
+  proxyUrl: string | null
+  /** Comma-separated hosts that bypass the manual proxy */
+  noProxy: string | null
+  /** PEM file with extra root certificates to trust */
+  caBundlePath: string | null
+}
+
+/** How exports show timestamps; stored ones are always UTC */
+export interface TimeDisplaySettings {
+  /** `UTC` or an offset like `+05:30`; null uses the system time zone */
+  timeZone: string | null
+  /** BCP 47 tag like `en-US`; null uses the system locale */
+  locale: string | null
+}
+
+/** `sqlite` is the default; `json` keeps one file per conversation */
+export type StorageBackend = 'sqlite' | 'json'
+
+export type SecondFactorMethod = 'none' | 'totp' | 'fido2'
+
+export type ProtectedOperation =
+  | 'wipe_data'
+  | 'export_all_data'
+  | 'approve_tool_policy'
+
+export interface SecondFactorSettings {
+  method: SecondFactorMethod
+  /** Operations that need the second factor */
+  protectedOperations: ProtectedOperation[]
+}
+
+/** Middleware applied to backend model requests, in order */
+export type LLMMiddlewareConfig = {
+  /** Unique name, used in errors and metrics */
+  id: string
+  enabled: boolean
+} & (
+  | { type: 'redaction'; patterns?: string[] }
+  | { type: 'budget'; maxInputTokens?: number; maxOutputTokens?: number }
+  | { type: 'cache'; ttlSecs: number }
+  /** Rhai script defining on_request(request) and/or on_response(request, response) */
+  | { type: 'script'; source: string }
+)
+
 // Types that match the Rust AppPreferences struct
 // Only contains settings that should be persisted to disk
 export interface AppPreferences {
   theme: string
+  /** Domains (including subdomains) that link previews may be fetched from */
+  linkPreviewDomains: string[]
+  /** Periodic Markdown export of changed conversations to an external folder */
+  scheduledExport: ScheduledExportSettings
+  /** Cap on simultaneously running MCP servers */
+  mcpConcurrency: MCPConcurrencySettings
+  /**
+   * Second factor for high-risk operations. Read-only here: save_preferences
+   * ignores it, change it with configureSecondFactor
+   */
+  secondFactor: SecondFactorSettings
+  /** Ordered middleware applied to backend model requests */
+  llmMiddleware: LLMMiddlewareConfig[]
+  /** Scheduled snapshots of the local database */
+  databaseBackup: DatabaseBackupSettings
+  /** Shows the window from anywhere, e.g. `CmdOrCtrl+Shift+Space` */
+  globalShortcut: string | null
+  /** Where conversations are stored; change it with `migrateStorage` */
+  storageBackend: StorageBackend
+  /** Record or replay provider responses to backend model requests */
+  llmReplay: ReplayMode
+  /** Replying to an archived conversation unarchives it */
+  unarchiveOnReply: boolean
+  /** Backoff for model requests failing with 429, 5xx or connection errors */
+  llmRetry: RetryPolicy
+  /** Time zone and locale timestamps are shown in, e.g. in exports */
+  timeDisplay: TimeDisplaySettings
+  /** Per-host request rate limits for model APIs */
+  llmRateLimits: RateLimitSettings
+  /** Answering repeated chat completions from a local cache */
+  llmCache: ResponseCacheSettings
+  /** Proxy and extra CA certificates for backend requests */
+  network: NetworkSettings
+  /** Closing the main window hides it to the tray instead of quitting */
+  closeToTray: boolean
+  /** Keeping what is copied for pasting into chats later */
+  clipboardHistory: ClipboardHistorySettings
+  /**
+   * Pinned on top or compact; read only here, set it with
+   * `setWindowPinned` and `setCompactMode`
+   */
+  windowMode: WindowMode
+  /** Pausing heavy background work on battery */
+  power: PowerSettings
   // Add new persistent preferences here, e.g.:
   // auto_save: boolean
   // language: string
@@ -9,5 +179,49 @@ export interface AppPreferences {
 
 export const defaultPreferences: AppPreferences = {
   theme: 'system',
+  linkPreviewDomains: [
+    'github.com',
+    'gitlab.com',
+    'wikipedia.org',
+    'youtube.com',
+    'stackoverflow.com',
+    'developer.mozilla.org',
+    'docs.rs',
+    'crates.io',
+    'npmjs.com',
+  ],
+  scheduledExport: { enabled: false, folder: null },
+  mcpConcurrency: { maxRunning: 10, queueWhenFull: true },
+  secondFactor: {
+    method: 'none',
+    protectedOperations: [
+      'wipe_data',
+      'export_all_data',
+      'approve_tool_policy',
+    ],
+  },
+  llmMiddleware: [],
+  databaseBackup: { enabled: true, intervalHours: 24, keep: 7 },
+  globalShortcut: null,
+  storageBackend: 'sqlite',
+  llmReplay: 'off',
+  unarchiveOnReply: false,
+  llmRetry: { maxAttempts: 4, initialDelayMs: 1000, maxDelayMs: 30000 },
+  timeDisplay: { timeZone: null, locale: null },
+  llmRateLimits: {
+    default: { requestsPerMinute: 50, burst: 5 },
+    hosts: {},
+  },
+  llmCache: { enabled: false, ttlSecs: 86400, maxEntries: 1000, maxMb: 50 },
+  network: {
+    proxy: 'system',
+    proxyUrl: null,
+    noProxy: null,
+    caBundlePath: null,
+  },
+  closeToTray: false,
+  clipboardHistory: { enabled: false, maxEntries: 50, excludedApps: [] },
+  windowMode: { pinned: false, compact: false },
+  power: { pauseOnBattery: true },
   // Add defaults for new preferences here
 }